@@ -4,21 +4,23 @@ use std::env;
 fn main() -> Result<(), Error> {
     let context = rclrs::Context::new(env::args())?;
 
-    let node = context.create_node("minimal_publisher")?;
-
-    let publisher =
-        node.create_publisher::<std_msgs::msg::String>("topic", rclrs::QOS_PROFILE_DEFAULT)?;
-
-    let mut message = std_msgs::msg::String::default();
+    let mut node = context.create_node("minimal_publisher")?;
 
     let mut publish_count: u32 = 1;
 
-    while context.ok() {
-        message.data = format!("Hello, world! {}", publish_count);
-        println!("Publishing: [{}]", message.data);
-        publisher.publish(&message)?;
-        publish_count += 1;
-        std::thread::sleep(std::time::Duration::from_millis(500));
-    }
-    Ok(())
+    let _timer = node.create_publisher_timer(
+        "topic",
+        rclrs::QOS_PROFILE_DEFAULT,
+        std::time::Duration::from_millis(500),
+        move || {
+            let message = std_msgs::msg::String {
+                data: format!("Hello, world! {}", publish_count),
+            };
+            println!("Publishing: [{}]", message.data);
+            publish_count += 1;
+            Some(message)
+        },
+    )?;
+
+    rclrs::spin(&node).map_err(|err| err.into())
 }
@@ -0,0 +1,25 @@
+use anyhow::{Error, Result};
+use std::env;
+
+/// Publishes a static transform on `/tf_static` until it is stopped, like the
+/// `static_transform_publisher` of `tf2_ros`, e.g.
+///
+/// ```text
+/// ros2 run examples_rclrs_tf static_transform_publisher --x 0.1 --yaw 1.57 \
+///     --frame-id base_link --child-frame-id laser
+/// ```
+fn main() -> Result<(), Error> {
+    let context = rclrs::Context::new(env::args())?;
+    // The first argument is the program name.
+    let transform = rclrs::StaticTransform::from_args(context.non_ros_arguments().iter().skip(1))?;
+
+    let mut node = context.create_node("static_transform_publisher")?;
+    let publisher = rclrs::StaticTransformPublisher::<tf2_msgs::msg::TFMessage>::new(&mut node)?;
+    publisher.send(&[transform.clone()])?;
+    println!(
+        "Publishing the static transform from '{}' to '{}': {:?}",
+        transform.parent_frame, transform.child_frame, transform.transform
+    );
+
+    rclrs::spin(&node).map_err(|err| err.into())
+}
@@ -1,9 +1,24 @@
 use std::env;
 use std::fs::read_dir;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
 const AMENT_PREFIX_PATH: &str = "AMENT_PREFIX_PATH";
 const ROS_DISTRO: &str = "ROS_DISTRO";
+const CARGO_FEATURE_STD: &str = "CARGO_FEATURE_STD";
+const CARGO_FEATURE_TRACETOOLS: &str = "CARGO_FEATURE_TRACETOOLS";
+const CARGO_FEATURE_DYN_MSG: &str = "CARGO_FEATURE_DYN_MSG";
+
+// The features that need interface packages besides rcl_interfaces, whose messages are defined by
+// hand in rclrs, and the packages whose C type support they link to.
+const INTERFACE_FEATURES: &[(&str, &str)] = &[
+    ("CARGO_FEATURE_STATISTICS_MSGS", "statistics_msgs"),
+    ("CARGO_FEATURE_STD_MSGS", "std_msgs"),
+    ("CARGO_FEATURE_STD_SRVS", "std_srvs"),
+];
+
+// The distros whose rcl API differences are handled in src/distro.rs and elsewhere.
+// Unknown distros are assumed to be newer, and get the code paths of the newest one.
+const SUPPORTED_ROS_DISTROS: &[&str] = &["foxy", "galactic", "humble", "iron", "jazzy", "rolling"];
 
 fn get_env_var_or_abort(env_var: &'static str) -> String {
     if let Ok(value) = env::var(env_var) {
@@ -18,7 +33,18 @@ fn get_env_var_or_abort(env_var: &'static str) -> String {
 
 fn main() {
     let ros_distro = get_env_var_or_abort(ROS_DISTRO);
+    if !SUPPORTED_ROS_DISTROS.contains(&ros_distro.as_str()) {
+        println!(
+            "cargo:warning=ROS distro '{ros_distro}' is not known to rclrs, assuming that its rcl API \
+             is the same as in Rolling"
+        );
+    }
     println!("cargo:rustc-cfg=ros_distro=\"{ros_distro}\"");
+    println!("cargo:rustc-env=RCLRS_ROS_DISTRO={ros_distro}");
+    // The bindings must be regenerated when a different distro is sourced.
+    println!("cargo:rerun-if-env-changed={ROS_DISTRO}");
+    println!("cargo:rerun-if-env-changed={AMENT_PREFIX_PATH}");
+    println!("cargo:rerun-if-changed=src/rcl_wrapper.h");
 
     let mut builder = bindgen::Builder::default()
         .header("src/rcl_wrapper.h")
@@ -33,11 +59,42 @@ fn main() {
         .allowlist_var("rcl_.*")
         .allowlist_var("rmw_.*")
         .allowlist_var("rcutils_.*")
+        .allowlist_var("RMW_GID_STORAGE_SIZE")
         .layout_tests(false)
         .size_t_is_usize(true)
         .default_enum_style(bindgen::EnumVariation::Rust {
             non_exhaustive: false,
-        });
+        })
+        // QoS profiles of other participants may contain policies of newer RMW versions, which
+        // would be undefined behavior in a Rust enum.
+        .newtype_enum("rmw_qos_(history|reliability|durability|liveliness)_policy_[et]");
+
+    // Without the std feature, the bindings must not refer to std::os::raw
+    if env::var_os(CARGO_FEATURE_STD).is_none() {
+        builder = builder.use_core().ctypes_prefix("libc");
+    }
+
+    let tracetools = env::var_os(CARGO_FEATURE_TRACETOOLS).is_some();
+    if tracetools {
+        builder = builder
+            .clang_arg("-DRCLRS_TRACETOOLS")
+            .allowlist_function("ros_trace_.*");
+    }
+
+    // The ~/get_type_description service of nodes is implemented by rcl from Jazzy on.
+    let type_description = !matches!(ros_distro.as_str(), "foxy" | "galactic" | "humble" | "iron");
+    if type_description {
+        builder = builder
+            .clang_arg("-DRCLRS_TYPE_DESCRIPTION")
+            .allowlist_function("type_description_interfaces__srv__GetTypeDescription_.*");
+    }
+
+    if env::var_os(CARGO_FEATURE_DYN_MSG).is_some() {
+        builder = builder
+            .clang_arg("-DRCLRS_DYN_MSG")
+            .allowlist_type("rosidl_typesupport_introspection_c.*")
+            .allowlist_var("rosidl_typesupport_introspection_c.*");
+    }
 
     // #############
     // # ALGORITHM #
@@ -54,8 +111,9 @@ fn main() {
     //
     // See REP 122 for more details: https://www.ros.org/reps/rep-0122.html#filesystem-layout
 
+    // The paths are separated by ':' on Unix and by ';' on Windows, where ':' is part of the paths.
     let ament_prefix_paths = get_env_var_or_abort(AMENT_PREFIX_PATH);
-    for ament_prefix_path in ament_prefix_paths.split(':').map(Path::new) {
+    for ament_prefix_path in env::split_paths(&ament_prefix_paths) {
         // Locate the ament index
         let ament_index = ament_prefix_path.join("share/ament_index/resource_index/packages");
         if !ament_index.is_dir() {
@@ -92,9 +150,24 @@ fn main() {
     }
 
     println!("cargo:rustc-link-lib=dylib=rcl");
+    println!("cargo:rustc-link-lib=dylib=rcl_yaml_param_parser");
+    println!("cargo:rustc-link-lib=dylib=rcl_interfaces__rosidl_generator_c");
+    println!("cargo:rustc-link-lib=dylib=rcl_interfaces__rosidl_typesupport_c");
+    for (feature, package) in INTERFACE_FEATURES {
+        if env::var_os(feature).is_some() {
+            println!("cargo:rustc-link-lib=dylib={package}__rosidl_generator_c");
+            println!("cargo:rustc-link-lib=dylib={package}__rosidl_typesupport_c");
+        }
+    }
     println!("cargo:rustc-link-lib=dylib=rcutils");
     println!("cargo:rustc-link-lib=dylib=rmw");
     println!("cargo:rustc-link-lib=dylib=rmw_implementation");
+    if type_description {
+        println!("cargo:rustc-link-lib=dylib=type_description_interfaces__rosidl_generator_c");
+    }
+    if tracetools {
+        println!("cargo:rustc-link-lib=dylib=tracetools");
+    }
 
     let bindings = builder.generate().expect("Unable to generate bindings");
 
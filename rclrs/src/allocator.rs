@@ -0,0 +1,129 @@
+use crate::rcl_bindings::*;
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::ffi::c_void;
+
+/// The alignment that `malloc()` guarantees on common platforms.
+const MIN_ALIGN: usize = 2 * core::mem::size_of::<usize>();
+
+/// A memory allocator for the `rcl` layer and below.
+///
+/// By default, `rcl` uses the C standard library allocator. A custom allocator, such as a
+/// real-time TLSF allocator, can be passed to [`Context::new_with_allocator`][1], and will then be
+/// used by the context and all nodes, publishers, subscriptions and wait sets created from it.
+///
+/// The allocator is also usable as the Rust global allocator through [`RclGlobalAlloc`], so that
+/// the allocations made by `rclrs` itself go through it as well.
+///
+/// # Safety
+/// The functions must behave like `malloc()`, `free()`, `realloc()` and `calloc()` respectively.
+/// In particular, returned pointers must be aligned suitably for any fundamental type, and
+/// failure must be signaled by a null pointer. The functions may be called from any thread.
+///
+/// [1]: crate::Context::new_with_allocator
+pub unsafe trait RclAllocator: Sync + 'static {
+    /// Allocates `size` bytes, like `malloc()`.
+    fn allocate(&self, size: usize) -> *mut c_void;
+    /// Deallocates memory returned by one of the other functions, like `free()`.
+    fn deallocate(&self, pointer: *mut c_void);
+    /// Resizes an allocation, like `realloc()`.
+    fn reallocate(&self, pointer: *mut c_void, size: usize) -> *mut c_void;
+    /// Allocates zero-initialized memory for an array, like `calloc()`.
+    fn zero_allocate(&self, number_of_elements: usize, size_of_element: usize) -> *mut c_void;
+}
+
+// SAFETY: The default allocator is the thread-safe malloc() and friends, and a custom allocator
+// may be called from any thread by the RclAllocator contract. The state is never mutated.
+unsafe impl Send for rcutils_allocator_t {}
+unsafe impl Sync for rcutils_allocator_t {}
+
+// The state passed to these functions is always created by to_rcutils_allocator().
+unsafe extern "C" fn allocate<A: RclAllocator>(size: usize, state: *mut c_void) -> *mut c_void {
+    // SAFETY: The state is a valid &'static A.
+    (*(state as *const A)).allocate(size)
+}
+
+unsafe extern "C" fn deallocate<A: RclAllocator>(pointer: *mut c_void, state: *mut c_void) {
+    // SAFETY: The state is a valid &'static A.
+    (*(state as *const A)).deallocate(pointer)
+}
+
+unsafe extern "C" fn reallocate<A: RclAllocator>(
+    pointer: *mut c_void,
+    size: usize,
+    state: *mut c_void,
+) -> *mut c_void {
+    // SAFETY: The state is a valid &'static A.
+    (*(state as *const A)).reallocate(pointer, size)
+}
+
+unsafe extern "C" fn zero_allocate<A: RclAllocator>(
+    number_of_elements: usize,
+    size_of_element: usize,
+    state: *mut c_void,
+) -> *mut c_void {
+    // SAFETY: The state is a valid &'static A.
+    (*(state as *const A)).zero_allocate(number_of_elements, size_of_element)
+}
+
+/// Creates an `rcutils_allocator_t` that forwards to the given allocator.
+pub(crate) fn to_rcutils_allocator<A: RclAllocator>(allocator: &'static A) -> rcutils_allocator_t {
+    rcutils_allocator_t {
+        allocate: Some(allocate::<A>),
+        deallocate: Some(deallocate::<A>),
+        reallocate: Some(reallocate::<A>),
+        zero_allocate: Some(zero_allocate::<A>),
+        state: allocator as *const A as *mut c_void,
+    }
+}
+
+/// Copies an `rcutils_allocator_t`, which is a plain struct of function pointers and a state.
+pub(crate) fn copy_rcutils_allocator(allocator: &rcutils_allocator_t) -> rcutils_allocator_t {
+    rcutils_allocator_t {
+        allocate: allocator.allocate,
+        deallocate: allocator.deallocate,
+        reallocate: allocator.reallocate,
+        zero_allocate: allocator.zero_allocate,
+        state: allocator.state,
+    }
+}
+
+/// Adapter for using an [`RclAllocator`] as the Rust global allocator.
+///
+/// Allocations with an alignment greater than what `malloc()` guarantees will fail.
+///
+/// # Example
+/// ```ignore
+/// #[global_allocator]
+/// static GLOBAL: rclrs::RclGlobalAlloc<MyTlsfAllocator> = rclrs::RclGlobalAlloc(MyTlsfAllocator::new());
+/// ```
+pub struct RclGlobalAlloc<A>(pub A);
+
+// SAFETY: The RclAllocator contract is the contract of malloc() and friends, which fulfill the
+// GlobalAlloc contract for alignments up to MIN_ALIGN.
+unsafe impl<A: RclAllocator> GlobalAlloc for RclGlobalAlloc<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.align() > MIN_ALIGN {
+            return core::ptr::null_mut();
+        }
+        self.0.allocate(layout.size()) as *mut u8
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, _layout: Layout) {
+        self.0.deallocate(ptr as *mut c_void)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        if layout.align() > MIN_ALIGN {
+            return core::ptr::null_mut();
+        }
+        self.0.zero_allocate(1, layout.size()) as *mut u8
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if layout.align() > MIN_ALIGN {
+            return core::ptr::null_mut();
+        }
+        self.0.reallocate(ptr as *mut c_void, new_size) as *mut u8
+    }
+}
@@ -0,0 +1,469 @@
+use crate::allocator::copy_rcutils_allocator;
+use crate::{
+    Context, GuardCondition, Node, RclReturnCode, RclrsError, ReusableWaitSet, WaitSetCapacities,
+};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+
+/// The scheduling policy of a thread, see [`ThreadOptions`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ThreadScheduling {
+    /// Keep the policy and priority of the thread that spawns the thread.
+    #[default]
+    Inherit,
+    /// The real-time `SCHED_FIFO` policy with the given priority, from 1 (lowest) to 99.
+    ///
+    /// The thread runs until it blocks or a thread with a higher priority becomes ready. This
+    /// usually requires the `CAP_SYS_NICE` capability or an `rtprio` limit, e.g. in
+    /// `/etc/security/limits.conf`.
+    Fifo {
+        /// The real-time priority.
+        priority: i32,
+    },
+    /// The real-time `SCHED_RR` policy with the given priority, from 1 (lowest) to 99.
+    ///
+    /// Like [`ThreadScheduling::Fifo`], except that threads with the same priority take turns.
+    RoundRobin {
+        /// The real-time priority.
+        priority: i32,
+    },
+}
+
+/// Options for a thread that spins nodes, see [`spin_in_background_with_options`] and
+/// [`PerNodeThreadExecutor`].
+///
+/// This is mainly for real-time controllers, which pin their callbacks to isolated cores with a
+/// real-time priority. The scheduling policy and the CPU affinity are only supported on Linux.
+///
+/// New options may be added in the future, so it is best to create this with the
+/// `..Default::default()` syntax.
+///
+/// # Example
+/// ```
+/// # use rclrs::{ThreadOptions, ThreadScheduling};
+/// let options = ThreadOptions {
+///     name: Some("controller".into()),
+///     scheduling: ThreadScheduling::Fifo { priority: 80 },
+///     cpu_affinity: Some(vec![3]),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ThreadOptions {
+    /// The name of the thread, e.g. for debuggers and `top -H`. Linux truncates it to 15 bytes.
+    pub name: Option<String>,
+    /// The stack size of the thread in bytes, or the default of the standard library if `None`.
+    pub stack_size: Option<usize>,
+    /// The scheduling policy and priority, see [`ThreadScheduling`].
+    pub scheduling: ThreadScheduling,
+    /// The CPUs that the thread may run on, by index, or all CPUs if `None`.
+    pub cpu_affinity: Option<Vec<usize>>,
+}
+
+impl ThreadOptions {
+    /// Applies the scheduling policy and the CPU affinity to the current thread, e.g. to the main
+    /// thread that calls [`spin`][1].
+    ///
+    /// The name and the stack size can only be set when spawning a thread, so they are ignored.
+    ///
+    /// Returns an [`InvalidArgument`][2] error for a CPU index that is out of range, an
+    /// [`Unsupported`][3] error for a scheduling policy or CPU affinity on platforms other than
+    /// Linux, and an [`Error`][4] if the operating system refuses the setting, e.g. because of
+    /// missing permissions for real-time priorities.
+    ///
+    /// [1]: crate::spin
+    /// [2]: crate::RclReturnCode::InvalidArgument
+    /// [3]: crate::RclReturnCode::Unsupported
+    /// [4]: crate::RclReturnCode::Error
+    pub fn apply_to_current_thread(&self) -> Result<(), RclrsError> {
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(cpus) = &self.cpu_affinity {
+                // SAFETY: A zeroed CPU set is empty.
+                let mut cpu_set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+                for &cpu in cpus {
+                    if cpu >= libc::CPU_SETSIZE as usize {
+                        return Err(RclrsError::with_message(
+                            RclReturnCode::InvalidArgument,
+                            format!("The CPU index {cpu} is out of range"),
+                        ));
+                    }
+                    // SAFETY: The index has been checked to be within the set.
+                    unsafe { libc::CPU_SET(cpu, &mut cpu_set) };
+                }
+                // SAFETY: The CPU set is valid, and a PID of zero means the current thread.
+                let ret = unsafe {
+                    libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &cpu_set)
+                };
+                if ret != 0 {
+                    return Err(RclrsError::with_message(
+                        RclReturnCode::Error,
+                        format!(
+                            "Failed to set the CPU affinity {cpus:?}: {}",
+                            std::io::Error::last_os_error()
+                        ),
+                    ));
+                }
+            }
+            let (policy, priority) = match self.scheduling {
+                ThreadScheduling::Inherit => return Ok(()),
+                ThreadScheduling::Fifo { priority } => (libc::SCHED_FIFO, priority),
+                ThreadScheduling::RoundRobin { priority } => (libc::SCHED_RR, priority),
+            };
+            let param = libc::sched_param {
+                sched_priority: priority,
+            };
+            // SAFETY: The parameter is valid for the duration of the call.
+            let ret = unsafe { libc::pthread_setschedparam(libc::pthread_self(), policy, &param) };
+            if ret != 0 {
+                return Err(RclrsError::with_message(
+                    RclReturnCode::Error,
+                    format!(
+                        "Failed to set the scheduling policy {:?}: {}",
+                        self.scheduling,
+                        std::io::Error::from_raw_os_error(ret)
+                    ),
+                ));
+            }
+            Ok(())
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            if self.scheduling != ThreadScheduling::Inherit || self.cpu_affinity.is_some() {
+                return Err(RclrsError::with_message(
+                    RclReturnCode::Unsupported,
+                    "Thread scheduling policies and CPU affinities are only supported on Linux",
+                ));
+            }
+            Ok(())
+        }
+    }
+
+    // Spawns a thread with these options, and waits until they have been applied to it.
+    fn spawn<F>(&self, f: F) -> Result<JoinHandle<Result<(), RclrsError>>, RclrsError>
+    where
+        F: FnOnce() -> Result<(), RclrsError> + Send + 'static,
+    {
+        let mut builder = std::thread::Builder::new();
+        if let Some(name) = &self.name {
+            builder = builder.name(name.clone());
+        }
+        if let Some(stack_size) = self.stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+        let options = self.clone();
+        let (sender, receiver) = mpsc::sync_channel(1);
+        let join_handle = builder
+            .spawn(move || {
+                if let Err(error) = options.apply_to_current_thread() {
+                    // The error is returned by spawn(), so the result of the thread is ignored.
+                    let _ = sender.send(Err(error));
+                    return Ok(());
+                }
+                let _ = sender.send(Ok(()));
+                f()
+            })
+            .map_err(|error| {
+                RclrsError::with_message(
+                    RclReturnCode::Error,
+                    format!("Failed to spawn a thread: {error}"),
+                )
+            })?;
+        match receiver.recv() {
+            Ok(Ok(())) => Ok(join_handle),
+            Ok(Err(error)) => {
+                let _ = join_handle.join();
+                Err(error)
+            }
+            // The thread panicked before applying the options.
+            Err(_) => match join_handle.join() {
+                Ok(_) => unreachable!("The thread exited without applying its options"),
+                Err(panic) => std::panic::resume_unwind(panic),
+            },
+        }
+    }
+}
+
+/// A handle for stopping a spin loop running in the background, see [`spin_in_background`].
+///
+/// It can be cloned and sent to other threads, e.g. to stop spinning from a signal handler
+/// thread or a GUI event.
+#[derive(Clone)]
+pub struct ShutdownToken {
+    requested: Arc<AtomicBool>,
+    guard_condition: Arc<GuardCondition>,
+}
+
+impl ShutdownToken {
+    /// Requests the spin loop to stop.
+    ///
+    /// The spin loop is woken up, finishes the callbacks that are currently ready, and then stops.
+    /// This does not shut down the context, so other nodes of the context keep working.
+    pub fn shutdown(&self) -> Result<(), RclrsError> {
+        self.requested.store(true, Ordering::Release);
+        self.guard_condition.trigger()
+    }
+
+    /// Returns true if [`ShutdownToken::shutdown`] has been called.
+    pub fn is_shutdown(&self) -> bool {
+        self.requested.load(Ordering::Acquire)
+    }
+}
+
+/// A spin loop running on a dedicated thread, returned by [`spin_in_background`].
+///
+/// Dropping this requests the spin loop to stop and waits for the thread to finish, so that the
+/// node is always torn down before the context that spawned it. Use
+/// [`BackgroundSpin::shutdown`] or [`BackgroundSpin::join`] to get the result of the spin loop.
+pub struct BackgroundSpin {
+    join_handle: Option<JoinHandle<Result<(), RclrsError>>>,
+    shutdown_token: ShutdownToken,
+}
+
+impl Drop for BackgroundSpin {
+    fn drop(&mut self) {
+        if let Some(join_handle) = self.join_handle.take() {
+            // The result, and a panic of the spin thread, can't be propagated from drop().
+            let _ = self.shutdown_token.shutdown();
+            let _ = join_handle.join();
+        }
+    }
+}
+
+impl BackgroundSpin {
+    /// Returns a token that can stop the spin loop from another thread.
+    pub fn shutdown_token(&self) -> ShutdownToken {
+        self.shutdown_token.clone()
+    }
+
+    /// Stops the spin loop and waits for the thread to finish.
+    ///
+    /// See [`BackgroundSpin::join`] for the result.
+    pub fn shutdown(self) -> Result<(), RclrsError> {
+        self.shutdown_token.shutdown()?;
+        self.join()
+    }
+
+    /// Waits for the spin loop to stop, without requesting it to stop.
+    ///
+    /// The spin loop stops when its [`ShutdownToken`] is used, when the context is shut down, or
+    /// when creating the node or executing a callback returns an error, which is returned here.
+    ///
+    /// # Panics
+    /// When the spin thread panicked, e.g. in a callback, the panic is resumed here.
+    pub fn join(mut self) -> Result<(), RclrsError> {
+        let join_handle = self.join_handle.take().unwrap();
+        match join_handle.join() {
+            Ok(result) => result,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+}
+
+/// Creates a node and spins it on a new thread.
+///
+/// This lets the main thread do other work, such as running a GUI or a compute loop, while the
+/// callbacks of the node run in the background.
+///
+/// Since a [`Node`] can not be sent between threads, it is created on the spin thread by the
+/// `create_node` function. Its subscriptions, timers etc. must be returned together with the
+/// node, as the second element of the tuple, since the node itself does not keep them alive.
+/// Data can be passed from the callbacks to other threads with channels.
+///
+/// The spin loop runs until it is stopped through the returned [`BackgroundSpin`], or until the
+/// context is shut down. Unlike with [`spin`][1], the wait set is woken up by a guard condition
+/// when stopping, so it does not need to wait for the next message.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::{Context, RclrsError, QOS_PROFILE_DEFAULT};
+/// let context = Context::new(std::env::args())?;
+/// let (sender, receiver) = std::sync::mpsc::channel();
+/// let background = rclrs::spin_in_background(&context, move |context| {
+///     let mut node = context.create_node("listener")?;
+///     let subscription = node.create_subscription(
+///         "topic",
+///         QOS_PROFILE_DEFAULT,
+///         move |msg: std_msgs::msg::String| {
+///             let _ = sender.send(msg.data);
+///         },
+///     )?;
+///     Ok((node, subscription))
+/// })?;
+/// // The main thread is free to do other work.
+/// for data in receiver.iter().take(10) {
+///     println!("I heard: '{}'", data);
+/// }
+/// background.shutdown()?;
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::spin
+pub fn spin_in_background<F, E>(
+    context: &Context,
+    create_node: F,
+) -> Result<BackgroundSpin, RclrsError>
+where
+    F: FnOnce(&Context) -> Result<(Node, E), RclrsError> + Send + 'static,
+    E: 'static,
+{
+    spin_in_background_with_options(context, &ThreadOptions::default(), create_node)
+}
+
+/// Creates a node and spins it on a new thread with the given name, scheduling policy, CPU
+/// affinity etc.
+///
+/// See [`spin_in_background`] and [`ThreadOptions`]. Returns an error without starting the spin
+/// loop if the options can not be applied to the thread, see
+/// [`ThreadOptions::apply_to_current_thread`].
+pub fn spin_in_background_with_options<F, E>(
+    context: &Context,
+    options: &ThreadOptions,
+    create_node: F,
+) -> Result<BackgroundSpin, RclrsError>
+where
+    F: FnOnce(&Context) -> Result<(Node, E), RclrsError> + Send + 'static,
+    E: 'static,
+{
+    let shutdown_token = ShutdownToken {
+        requested: Arc::new(AtomicBool::new(false)),
+        guard_condition: Arc::new(GuardCondition::new(context)?),
+    };
+    let context = Context {
+        handle: context.handle.clone(),
+        allocator: copy_rcutils_allocator(&context.allocator),
+    };
+    let thread_shutdown_token = shutdown_token.clone();
+    let join_handle = options.spawn(move || {
+        let (node, _entities) = create_node(&context)?;
+        let mut wait_set = ReusableWaitSet::new(
+            WaitSetCapacities::for_node(&node),
+            [thread_shutdown_token.guard_condition.clone()]
+                .into_iter()
+                .chain(node.guard_conditions())
+                .collect(),
+            &context,
+        )?;
+        while !thread_shutdown_token.is_shutdown() && context.ok() {
+            if let Err(error) = wait_set.spin_once(&node, None) {
+                match error.code {
+                    RclReturnCode::Timeout => continue,
+                    _ => return Err(error),
+                }
+            }
+        }
+        Ok(())
+    })?;
+    Ok(BackgroundSpin {
+        join_handle: Some(join_handle),
+        shutdown_token,
+    })
+}
+
+/// Spins several nodes in parallel, each on a worker thread of its own.
+///
+/// Since nodes and their entities can not be sent between threads, each worker creates its node
+/// on its thread, like [`spin_in_background`]. Callbacks that must not block each other, e.g. a
+/// real-time control loop and the diagnostics of a robot, are put into separate nodes on separate
+/// workers. Each worker has its own [`ThreadOptions`], so that e.g. the control loop can run with
+/// a real-time priority on an isolated core.
+///
+/// Dropping the executor stops all workers and waits for them to finish.
+///
+/// This is not a multi-threaded executor like `rclcpp::executors::MultiThreadedExecutor`, which
+/// runs the callbacks of the same node on a pool of threads: there is one thread per node, so the
+/// number of threads is the number of workers, and the callbacks of one node still run one after
+/// another. A thread pool would require the callbacks and entities to be `Send`, which they are
+/// not yet.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let mut executor = PerNodeThreadExecutor::new(&context);
+/// let control_options = ThreadOptions {
+///     name: Some("control".into()),
+///     scheduling: ThreadScheduling::Fifo { priority: 80 },
+///     cpu_affinity: Some(vec![3]),
+///     ..Default::default()
+/// };
+/// executor.add_worker(&control_options, |context| {
+///     let mut node = context.create_node("controller")?;
+///     let timer = node.create_timer(Duration::from_millis(1), move || control_step())?;
+///     Ok((node, timer))
+/// })?;
+/// executor.add_worker(&ThreadOptions::default(), |context| {
+///     let node = context.create_node("diagnostics")?;
+///     Ok((node, ()))
+/// })?;
+/// executor.join()?;
+/// ```
+pub struct PerNodeThreadExecutor {
+    context: Context,
+    workers: Vec<BackgroundSpin>,
+}
+
+impl PerNodeThreadExecutor {
+    /// Creates an executor without workers.
+    pub fn new(context: &Context) -> Self {
+        Self {
+            context: Context {
+                handle: context.handle.clone(),
+                allocator: copy_rcutils_allocator(&context.allocator),
+            },
+            workers: Vec::new(),
+        }
+    }
+
+    /// Starts a worker thread with the given options, which creates a node and spins it.
+    ///
+    /// See [`spin_in_background_with_options`] for the arguments and the errors. The returned
+    /// token stops only this worker.
+    pub fn add_worker<F, E>(
+        &mut self,
+        options: &ThreadOptions,
+        create_node: F,
+    ) -> Result<ShutdownToken, RclrsError>
+    where
+        F: FnOnce(&Context) -> Result<(Node, E), RclrsError> + Send + 'static,
+        E: 'static,
+    {
+        let worker = spin_in_background_with_options(&self.context, options, create_node)?;
+        let shutdown_token = worker.shutdown_token();
+        self.workers.push(worker);
+        Ok(shutdown_token)
+    }
+
+    /// Returns the number of worker threads.
+    pub fn num_threads(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Stops all workers and waits for them to finish.
+    ///
+    /// See [`PerNodeThreadExecutor::join`] for the result.
+    pub fn shutdown(self) -> Result<(), RclrsError> {
+        for worker in &self.workers {
+            worker.shutdown_token().shutdown()?;
+        }
+        self.join()
+    }
+
+    /// Waits for all workers to stop, without requesting them to stop.
+    ///
+    /// Returns the first error of a worker, after all workers have stopped.
+    ///
+    /// # Panics
+    /// When a worker thread panicked, the panic is resumed here.
+    pub fn join(self) -> Result<(), RclrsError> {
+        let mut result = Ok(());
+        for worker in self.workers {
+            let worker_result = worker.join();
+            if result.is_ok() {
+                result = worker_result;
+            }
+        }
+        result
+    }
+}
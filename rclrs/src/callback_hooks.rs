@@ -0,0 +1,116 @@
+use crate::rcl_bindings::*;
+use crate::RclrsError;
+
+use alloc::boxed::Box;
+use core::time::Duration;
+
+/// The kind of entity whose callback is executed, see [`CallbackId`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CallbackKind {
+    /// A [`Subscription`][1] received a message.
+    ///
+    /// [1]: crate::Subscription
+    Subscription,
+    /// A [`Timer`][1] is ready.
+    ///
+    /// [1]: crate::Timer
+    Timer,
+    /// A [`Client`][1] received a response.
+    ///
+    /// [1]: crate::Client
+    Client,
+    /// A [`Service`][1] received a request.
+    ///
+    /// [1]: crate::Service
+    Service,
+    /// A [`QoSEvent`][1] occurred.
+    ///
+    /// [1]: crate::QoSEvent
+    QoSEvent,
+    /// The ROS graph changed, and e.g. a [`ServiceWatchdog`][1] checks it.
+    ///
+    /// [1]: crate::ServiceWatchdog
+    GraphEvent,
+}
+
+/// Identifies the entity whose callback is executed, for [`CallbackHooks`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct CallbackId {
+    /// The kind of the entity.
+    pub kind: CallbackKind,
+    /// The address of the entity, which is unique while it is alive.
+    ///
+    /// For an entity in an `Arc`, such as a subscription, this is `Arc::as_ptr(&subscription)`,
+    /// so hooks can map it to e.g. the topic name of the subscription.
+    pub address: usize,
+}
+
+impl CallbackId {
+    pub(crate) fn new<T: ?Sized>(kind: CallbackKind, entity: &T) -> Self {
+        Self {
+            kind,
+            address: entity as *const T as *const () as usize,
+        }
+    }
+}
+
+/// Functions that run around each callback of a node or an [`Executor`][1], see
+/// [`Node::set_callback_hooks`][2] and [`Executor::set_callback_hooks`][3].
+///
+/// This allows custom profilers, watchdogs and flamegraph annotations without patching `rclrs`.
+/// The hooks run on the thread that spins, so they should return quickly.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// node.set_callback_hooks(CallbackHooks {
+///     on_callback_end: Some(Box::new(|id, duration| {
+///         if duration > std::time::Duration::from_millis(10) {
+///             println!("Slow {:?} callback: {:?}", id.kind, duration);
+///         }
+///     })),
+///     ..Default::default()
+/// });
+/// ```
+///
+/// [1]: crate::Executor
+/// [2]: crate::Node::set_callback_hooks
+/// [3]: crate::Executor::set_callback_hooks
+#[allow(clippy::type_complexity)]
+#[derive(Default)]
+pub struct CallbackHooks {
+    /// Runs before each callback.
+    pub on_callback_begin: Option<Box<dyn Fn(CallbackId) + 'static>>,
+    /// Runs after each callback, with how long the callback ran, measured with the steady clock.
+    ///
+    /// This also runs when the callback returned an error.
+    pub on_callback_end: Option<Box<dyn Fn(CallbackId, Duration) + 'static>>,
+}
+
+impl CallbackHooks {
+    pub(crate) fn run(
+        &self,
+        id: CallbackId,
+        execute: impl FnOnce() -> Result<(), RclrsError>,
+    ) -> Result<(), RclrsError> {
+        if let Some(on_callback_begin) = &self.on_callback_begin {
+            on_callback_begin(id);
+        }
+        let Some(on_callback_end) = &self.on_callback_end else {
+            return execute();
+        };
+        let start = steady_time_now();
+        let result = execute();
+        let nanoseconds = steady_time_now().saturating_sub(start).max(0);
+        on_callback_end(id, Duration::from_nanos(nanoseconds as u64));
+        result
+    }
+}
+
+// The steady clock of rcutils is used, since it is also available without the `std` feature.
+fn steady_time_now() -> rcutils_time_point_value_t {
+    let mut now = 0;
+    // SAFETY: No preconditions for this function. It only fails for a null pointer.
+    unsafe { rcutils_steady_time_now(&mut now) };
+    now
+}
@@ -0,0 +1,165 @@
+use crate::logging::log;
+use crate::{CallbackHooks, CallbackId, LogSeverity};
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+type StuckCallback = Box<dyn FnMut(CallbackId, Duration) + Send + 'static>;
+
+// The callback that is currently running on one spinning thread.
+struct RunningCallback {
+    id: CallbackId,
+    start: Instant,
+    reported: bool,
+}
+
+// The state that is shared between the hooks and the watchdog thread.
+struct WatchdogState {
+    // One slot per call of `CallbackWatchdog::hooks()`, i.e. per spinning thread.
+    slots: Mutex<Vec<Arc<Mutex<Option<RunningCallback>>>>>,
+    on_stuck: Mutex<Option<StuckCallback>>,
+    shutdown: Mutex<bool>,
+    // Notified when the watchdog is dropped.
+    shutdown_condvar: Condvar,
+}
+
+/// Detects callbacks that run longer than a deadline, e.g. because of a deadlock.
+///
+/// The watchdog monitors the callbacks on a thread of its own. It is connected to a node or an
+/// [`Executor`][1] through the [`CallbackHooks`] from [`CallbackWatchdog::hooks`]. When a callback
+/// exceeds the deadline, it is reported once as an error with the `rclrs` logger and to the
+/// [`on_stuck`][2] callback, which runs on the watchdog thread.
+///
+/// Callbacks can't be interrupted from another thread, so the watchdog does not stop the
+/// offending callback. To recover from a deadlock in production, the `on_stuck` callback can
+/// e.g. abort the process, so that it is restarted by its supervisor.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let watchdog = CallbackWatchdog::new(std::time::Duration::from_millis(500));
+/// watchdog.on_stuck(|id, elapsed| {
+///     eprintln!("{:?} callback stuck for {:?}, aborting", id.kind, elapsed);
+///     std::process::abort();
+/// });
+/// node.set_callback_hooks(watchdog.hooks());
+/// rclrs::spin(&node)?;
+/// ```
+///
+/// [1]: crate::Executor
+/// [2]: CallbackWatchdog::on_stuck
+pub struct CallbackWatchdog {
+    state: Arc<WatchdogState>,
+    join_handle: Option<JoinHandle<()>>,
+}
+
+impl Drop for CallbackWatchdog {
+    fn drop(&mut self) {
+        *self.state.shutdown.lock().unwrap() = true;
+        self.state.shutdown_condvar.notify_all();
+        if let Some(join_handle) = self.join_handle.take() {
+            // A panic of the on_stuck callback can't be propagated from drop().
+            let _ = join_handle.join();
+        }
+    }
+}
+
+impl CallbackWatchdog {
+    /// Starts a watchdog thread that reports callbacks running longer than `deadline`.
+    ///
+    /// The callbacks are checked with a period of a quarter of the deadline, so a stuck callback
+    /// is reported at most 25 % after the deadline.
+    pub fn new(deadline: Duration) -> Self {
+        let state = Arc::new(WatchdogState {
+            slots: Mutex::new(Vec::new()),
+            on_stuck: Mutex::new(None),
+            shutdown: Mutex::new(false),
+            shutdown_condvar: Condvar::new(),
+        });
+        let thread_state = Arc::clone(&state);
+        let join_handle = std::thread::spawn(move || thread_state.run(deadline));
+        Self {
+            state,
+            join_handle: Some(join_handle),
+        }
+    }
+
+    /// Sets the callback that runs on the watchdog thread when a callback exceeds the deadline.
+    ///
+    /// It receives the stuck callback and how long it has been running.
+    pub fn on_stuck(&self, callback: impl FnMut(CallbackId, Duration) + Send + 'static) {
+        *self.state.on_stuck.lock().unwrap() = Some(Box::new(callback));
+    }
+
+    /// Creates hooks that report the callbacks of one spinning thread to the watchdog.
+    ///
+    /// Pass them to [`Node::set_callback_hooks`][1] or [`Executor::set_callback_hooks`][2]. For
+    /// monitoring several spinning threads, call this once for each of them.
+    ///
+    /// [1]: crate::Node::set_callback_hooks
+    /// [2]: crate::Executor::set_callback_hooks
+    pub fn hooks(&self) -> CallbackHooks {
+        let slot = Arc::new(Mutex::new(None));
+        self.state.slots.lock().unwrap().push(Arc::clone(&slot));
+        let begin_slot = Arc::clone(&slot);
+        CallbackHooks {
+            on_callback_begin: Some(Box::new(move |id| {
+                *begin_slot.lock().unwrap() = Some(RunningCallback {
+                    id,
+                    start: Instant::now(),
+                    reported: false,
+                });
+            })),
+            on_callback_end: Some(Box::new(move |_, _| {
+                *slot.lock().unwrap() = None;
+            })),
+        }
+    }
+}
+
+impl WatchdogState {
+    fn run(&self, deadline: Duration) {
+        let period = deadline / 4;
+        let mut shutdown = self.shutdown.lock().unwrap();
+        while !*shutdown {
+            shutdown = self
+                .shutdown_condvar
+                .wait_timeout(shutdown, period)
+                .unwrap()
+                .0;
+            for (id, elapsed) in self.stuck_callbacks(deadline) {
+                let message = format!(
+                    "Callback watchdog: {:?} callback at {:#x} has been running for {:?}, \
+                     exceeding the deadline of {:?}",
+                    id.kind, id.address, elapsed, deadline
+                );
+                log("rclrs", LogSeverity::Error, &message);
+                if let Some(on_stuck) = &mut *self.on_stuck.lock().unwrap() {
+                    on_stuck(id, elapsed);
+                }
+            }
+        }
+    }
+
+    // Returns the callbacks that exceeded the deadline and have not been reported yet, and marks
+    // them as reported.
+    fn stuck_callbacks(&self, deadline: Duration) -> Vec<(CallbackId, Duration)> {
+        let mut slots = self.slots.lock().unwrap();
+        // Slots whose hooks have been dropped are only referenced here.
+        slots.retain(|slot| Arc::strong_count(slot) > 1);
+        slots
+            .iter()
+            .filter_map(|slot| {
+                let mut running = slot.lock().unwrap();
+                let running = running.as_mut()?;
+                let elapsed = running.start.elapsed();
+                if running.reported || elapsed < deadline {
+                    return None;
+                }
+                running.reported = true;
+                Some((running.id, elapsed))
+            })
+            .collect()
+    }
+}
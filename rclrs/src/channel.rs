@@ -0,0 +1,568 @@
+use crate::context::ShutdownObserver;
+use crate::sync::Mutex;
+use crate::Context;
+
+use parking_lot::Condvar;
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{self, Poll, Waker};
+use std::time::{Duration, Instant};
+
+/// Why an operation on a [`channel`] failed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChannelError {
+    /// The channel was empty, for [`Receiver::try_recv`].
+    Empty,
+    /// The channel was full, for [`Sender::try_send`].
+    Full,
+    /// Nothing was received within the timeout, for [`Receiver::recv_timeout`].
+    Timeout,
+    /// All senders, or the receiver, have been dropped.
+    Disconnected,
+    /// The context of the channel has been shut down.
+    Shutdown,
+}
+
+impl fmt::Display for ChannelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "The channel is empty"),
+            Self::Full => write!(f, "The channel is full"),
+            Self::Timeout => write!(f, "Timed out waiting on the channel"),
+            Self::Disconnected => write!(f, "The other side of the channel has been dropped"),
+            Self::Shutdown => write!(f, "The context of the channel has been shut down"),
+        }
+    }
+}
+
+impl std::error::Error for ChannelError {}
+
+/// A value that could not be sent, together with the reason, see [`Sender::send`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SendError<T> {
+    /// The value that was not sent.
+    pub value: T,
+    /// Why it was not sent.
+    pub error: ChannelError,
+}
+
+/// What a [`Sender`] does with a value when the channel is full, see
+/// [`Subscription::into_channel`][1].
+///
+/// [1]: crate::Subscription::into_channel
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum BackpressurePolicy {
+    /// Block until the receiver makes room. In a callback, this stalls the executor, and with it
+    /// all other callbacks of the node, so that no messages are lost on the way to the receiver.
+    Block,
+    /// Drop the oldest value in the channel to make room, so that the receiver always gets the
+    /// newest values. This suits sensor data, where only recent values are of interest.
+    #[default]
+    DropOldest,
+    /// Drop the new value, so that the receiver works through the values in the channel first.
+    DropNewest,
+}
+
+struct ChannelState<T> {
+    queue: VecDeque<T>,
+    senders: usize,
+    receiver_alive: bool,
+    shutdown: bool,
+    // The waker of a pending `RecvFuture`.
+    waker: Option<Waker>,
+}
+
+struct Shared<T> {
+    state: Mutex<ChannelState<T>>,
+    capacity: usize,
+    // Notified when a value is sent, and when the channel is closed.
+    not_empty: Condvar,
+    // Notified when a value is received, and when the channel is closed.
+    not_full: Condvar,
+}
+
+impl<T> Shared<T> {
+    // Wakes up everything that waits on the channel, after it has been closed.
+    fn wake_all(&self, state: &mut ChannelState<T>) {
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<T: Send> ShutdownObserver for Shared<T> {
+    fn on_shutdown(&self) {
+        let state = &mut *self.state.lock();
+        state.shutdown = true;
+        self.wake_all(state);
+    }
+}
+
+/// Creates a bounded channel for passing values from callbacks to worker threads, or back, that
+/// is closed when the context is shut down.
+///
+/// This is like [`std::sync::mpsc::sync_channel`], except that threads blocked in
+/// [`Receiver::recv`] or [`Sender::send`] wake up with [`ChannelError::Shutdown`] when the
+/// context is shut down, e.g. with [`Context::shutdown`], or dropped. This keeps worker threads
+/// from hanging at shutdown when the callbacks that feed them stop running. Values that are still
+/// in the channel at that point are dropped with it.
+///
+/// The receiver can also be awaited with [`Receiver::recv_future`], e.g. in
+/// [`spin_until_future_complete`][1], which wakes up the spinning thread when a value arrives
+/// from another thread.
+///
+/// The capacity must be at least 1.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let (sender, receiver) = rclrs::channel(&context, 16);
+/// let worker = std::thread::spawn(move || {
+///     while let Ok(msg) = receiver.recv() {
+///         process(msg);
+///     }
+/// });
+/// let _subscription = node.create_subscription(
+///     "topic",
+///     QOS_PROFILE_DEFAULT,
+///     move |msg: std_msgs::msg::String| {
+///         // Drop messages instead of blocking the executor when the worker falls behind.
+///         let _ = sender.try_send(msg);
+///     },
+/// )?;
+/// rclrs::spin(&node)?;
+/// context.shutdown()?;
+/// worker.join().unwrap();
+/// ```
+///
+/// # Panics
+/// When the capacity is 0.
+///
+/// [1]: crate::spin_until_future_complete
+pub fn channel<T: Send + 'static>(context: &Context, capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let (sender, receiver) = detached_channel(capacity);
+    sender.shared.state.lock().shutdown = !context.ok();
+    let observer: Arc<dyn ShutdownObserver> = sender.shared.clone();
+    context
+        .handle
+        .add_shutdown_observer(Arc::downgrade(&observer));
+    (sender, receiver)
+}
+
+// Creates a channel that is not closed by a context, but only when one side is dropped.
+pub(crate) fn detached_channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "The capacity of a channel must be at least 1");
+    let shared = Arc::new(Shared {
+        state: Mutex::new(ChannelState {
+            queue: VecDeque::with_capacity(capacity),
+            senders: 1,
+            receiver_alive: true,
+            shutdown: false,
+            waker: None,
+        }),
+        capacity,
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+    });
+    (
+        Sender {
+            shared: Arc::clone(&shared),
+        },
+        Receiver { shared },
+    )
+}
+
+/// The sending side of a [`channel`], which can be cloned.
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.state.lock().senders += 1;
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let state = &mut *self.shared.state.lock();
+        state.senders -= 1;
+        if state.senders == 0 {
+            self.shared.wake_all(state);
+        }
+    }
+}
+
+impl<T> Sender<T> {
+    /// Sends a value, and blocks while the channel is full.
+    ///
+    /// Fails with [`ChannelError::Disconnected`] when the receiver has been dropped, and with
+    /// [`ChannelError::Shutdown`] when the context has been shut down.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        self.send_impl(value, None)
+    }
+
+    /// Sends a value, and blocks while the channel is full, but at most for the timeout.
+    ///
+    /// See [`Sender::send`] for the errors. Fails with [`ChannelError::Timeout`] when the channel
+    /// is still full after the timeout.
+    pub fn send_timeout(&self, value: T, timeout: Duration) -> Result<(), SendError<T>> {
+        self.send_impl(value, Some(Instant::now() + timeout))
+    }
+
+    /// Sends a value if the channel is not full, without blocking.
+    ///
+    /// See [`Sender::send`] for the errors. Fails with [`ChannelError::Full`] when the channel is
+    /// full, which is what callbacks should usually do instead of blocking the executor.
+    pub fn try_send(&self, value: T) -> Result<(), SendError<T>> {
+        let mut state = self.shared.state.lock();
+        self.try_send_locked(&mut state, value)
+    }
+
+    /// Sends a value, and applies the policy when the channel is full.
+    ///
+    /// See [`Sender::send`] for the errors. A value that is dropped because of the policy, i.e.
+    /// the new value for [`BackpressurePolicy::DropNewest`], or the oldest value in the channel
+    /// for [`BackpressurePolicy::DropOldest`], does not count as an error.
+    pub fn send_with_policy(
+        &self,
+        value: T,
+        policy: BackpressurePolicy,
+    ) -> Result<(), SendError<T>> {
+        if policy == BackpressurePolicy::Block {
+            return self.send(value);
+        }
+        let mut state = self.shared.state.lock();
+        match self.try_send_locked(&mut state, value) {
+            Err(SendError {
+                value,
+                error: ChannelError::Full,
+            }) => {
+                if policy == BackpressurePolicy::DropOldest {
+                    state.queue.pop_front();
+                    self.try_send_locked(&mut state, value)?;
+                }
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    fn send_impl(&self, mut value: T, deadline: Option<Instant>) -> Result<(), SendError<T>> {
+        let shared = &*self.shared;
+        let mut state = shared.state.lock();
+        loop {
+            match self.try_send_locked(&mut state, value) {
+                Err(SendError {
+                    value: rejected,
+                    error: ChannelError::Full,
+                }) => value = rejected,
+                result => return result,
+            }
+            match deadline {
+                None => shared.not_full.wait(&mut state),
+                Some(deadline) => {
+                    if shared.not_full.wait_until(&mut state, deadline).timed_out() {
+                        return self.try_send_locked(&mut state, value).map_err(
+                            |SendError { value, error }| SendError {
+                                value,
+                                error: match error {
+                                    ChannelError::Full => ChannelError::Timeout,
+                                    error => error,
+                                },
+                            },
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn try_send_locked(&self, state: &mut ChannelState<T>, value: T) -> Result<(), SendError<T>> {
+        let error = if state.shutdown {
+            ChannelError::Shutdown
+        } else if !state.receiver_alive {
+            ChannelError::Disconnected
+        } else if state.queue.len() >= self.shared.capacity {
+            ChannelError::Full
+        } else {
+            state.queue.push_back(value);
+            self.shared.not_empty.notify_one();
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+            return Ok(());
+        };
+        Err(SendError { value, error })
+    }
+}
+
+/// The receiving side of a [`channel`].
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let state = &mut *self.shared.state.lock();
+        state.receiver_alive = false;
+        self.shared.wake_all(state);
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Receives a value, and blocks while the channel is empty.
+    ///
+    /// Fails with [`ChannelError::Disconnected`] when the channel is empty and all senders have
+    /// been dropped, and with [`ChannelError::Shutdown`] when the context has been shut down.
+    pub fn recv(&self) -> Result<T, ChannelError> {
+        self.recv_impl(None)
+    }
+
+    /// Receives a value, and blocks while the channel is empty, but at most for the timeout.
+    ///
+    /// See [`Receiver::recv`] for the errors. Fails with [`ChannelError::Timeout`] when the
+    /// channel is still empty after the timeout.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, ChannelError> {
+        self.recv_impl(Some(Instant::now() + timeout))
+    }
+
+    /// Receives a value if the channel is not empty, without blocking.
+    ///
+    /// See [`Receiver::recv`] for the errors. Fails with [`ChannelError::Empty`] when the channel
+    /// is empty.
+    pub fn try_recv(&self) -> Result<T, ChannelError> {
+        let state = &mut *self.shared.state.lock();
+        self.try_recv_locked(state)
+    }
+
+    /// Returns a future that resolves to the next value, see [`Receiver::recv`] for the errors.
+    ///
+    /// The future can be passed to [`spin_until_future_complete`][1], to spin a node until a
+    /// worker thread has sent a result.
+    ///
+    /// [1]: crate::spin_until_future_complete
+    pub fn recv_future(&self) -> RecvFuture<'_, T> {
+        RecvFuture { receiver: self }
+    }
+
+    fn recv_impl(&self, deadline: Option<Instant>) -> Result<T, ChannelError> {
+        let shared = &*self.shared;
+        let mut state = shared.state.lock();
+        loop {
+            match self.try_recv_locked(&mut state) {
+                Err(ChannelError::Empty) => {}
+                result => return result,
+            }
+            match deadline {
+                None => shared.not_empty.wait(&mut state),
+                Some(deadline) => {
+                    if shared
+                        .not_empty
+                        .wait_until(&mut state, deadline)
+                        .timed_out()
+                    {
+                        return match self.try_recv_locked(&mut state) {
+                            Err(ChannelError::Empty) => Err(ChannelError::Timeout),
+                            result => result,
+                        };
+                    }
+                }
+            }
+        }
+    }
+
+    fn try_recv_locked(&self, state: &mut ChannelState<T>) -> Result<T, ChannelError> {
+        if state.shutdown {
+            return Err(ChannelError::Shutdown);
+        }
+        match state.queue.pop_front() {
+            Some(value) => {
+                self.shared.not_full.notify_one();
+                Ok(value)
+            }
+            None if state.senders == 0 => Err(ChannelError::Disconnected),
+            None => Err(ChannelError::Empty),
+        }
+    }
+}
+
+/// A future for the next value of a [`Receiver`], see [`Receiver::recv_future`].
+pub struct RecvFuture<'a, T> {
+    receiver: &'a Receiver<T>,
+}
+
+impl<T> Future for RecvFuture<'_, T> {
+    type Output = Result<T, ChannelError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let state = &mut *self.receiver.shared.state.lock();
+        match self.receiver.try_recv_locked(state) {
+            Err(ChannelError::Empty) => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+            result => Poll::Ready(result),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::task::Wake;
+
+    struct Flag(AtomicBool);
+
+    impl Wake for Flag {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_send_and_receive_in_order() {
+        let (sender, receiver) = detached_channel(2);
+        assert_eq!(receiver.try_recv(), Err(ChannelError::Empty));
+        sender.send(1).unwrap();
+        sender.try_send(2).unwrap();
+        assert_eq!(
+            sender.try_send(3),
+            Err(SendError {
+                value: 3,
+                error: ChannelError::Full
+            })
+        );
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.try_recv(), Ok(2));
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_millis(10)),
+            Err(ChannelError::Timeout)
+        );
+    }
+
+    #[test]
+    fn test_backpressure_policies() {
+        let (sender, receiver) = detached_channel(2);
+        for value in 1..=3 {
+            sender
+                .send_with_policy(value, BackpressurePolicy::DropOldest)
+                .unwrap();
+        }
+        assert_eq!(receiver.try_recv(), Ok(2));
+        assert_eq!(receiver.try_recv(), Ok(3));
+        for value in 1..=3 {
+            sender
+                .send_with_policy(value, BackpressurePolicy::DropNewest)
+                .unwrap();
+        }
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert_eq!(receiver.try_recv(), Ok(2));
+        assert_eq!(receiver.try_recv(), Err(ChannelError::Empty));
+    }
+
+    #[test]
+    fn test_send_timeout_when_full() {
+        let (sender, receiver) = detached_channel(1);
+        sender.send(1).unwrap();
+        let error = sender
+            .send_timeout(2, Duration::from_millis(10))
+            .unwrap_err();
+        assert_eq!(error.error, ChannelError::Timeout);
+        assert_eq!(error.value, 2);
+        assert_eq!(receiver.try_recv(), Ok(1));
+    }
+
+    #[test]
+    fn test_blocked_sender_wakes_up_when_receiving() {
+        let (sender, receiver) = detached_channel(1);
+        sender.send(1).unwrap();
+        let thread = std::thread::spawn(move || sender.send(2));
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.recv(), Ok(2));
+        assert!(thread.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_disconnect() {
+        let (sender, receiver) = detached_channel(2);
+        let second_sender = sender.clone();
+        sender.send(1).unwrap();
+        drop(sender);
+        // The channel stays connected while a sender is alive, and is drained after that.
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert_eq!(receiver.try_recv(), Err(ChannelError::Empty));
+        second_sender.send(2).unwrap();
+        drop(second_sender);
+        assert_eq!(receiver.recv(), Ok(2));
+        assert_eq!(receiver.recv(), Err(ChannelError::Disconnected));
+
+        let (sender, receiver) = detached_channel(1);
+        drop(receiver);
+        assert_eq!(
+            sender.send(1).unwrap_err().error,
+            ChannelError::Disconnected
+        );
+    }
+
+    #[test]
+    fn test_blocked_receiver_wakes_up_when_disconnected() {
+        let (sender, receiver) = detached_channel::<i32>(1);
+        let thread = std::thread::spawn(move || receiver.recv());
+        std::thread::sleep(Duration::from_millis(10));
+        drop(sender);
+        assert_eq!(thread.join().unwrap(), Err(ChannelError::Disconnected));
+    }
+
+    #[test]
+    fn test_shutdown() {
+        let (sender, receiver) = detached_channel(2);
+        sender.send(1).unwrap();
+        let shared = Arc::clone(&sender.shared);
+        let thread = std::thread::spawn(move || {
+            // Fill up the channel, so that the sender blocks until the shutdown.
+            sender.send(2).unwrap();
+            sender.send(3)
+        });
+        std::thread::sleep(Duration::from_millis(10));
+        shared.on_shutdown();
+        assert_eq!(
+            thread.join().unwrap().unwrap_err().error,
+            ChannelError::Shutdown
+        );
+        // Values that are still in the channel are not received anymore.
+        assert_eq!(receiver.try_recv(), Err(ChannelError::Shutdown));
+        assert_eq!(receiver.recv(), Err(ChannelError::Shutdown));
+    }
+
+    #[test]
+    fn test_recv_future() {
+        let (sender, receiver) = detached_channel(1);
+        let flag = Arc::new(Flag(AtomicBool::new(false)));
+        let waker = Waker::from(Arc::clone(&flag));
+        let mut cx = task::Context::from_waker(&waker);
+        let mut future = receiver.recv_future();
+        assert!(Pin::new(&mut future).poll(&mut cx).is_pending());
+        sender.send(1).unwrap();
+        assert!(flag.0.load(Ordering::SeqCst));
+        assert_eq!(Pin::new(&mut future).poll(&mut cx), Poll::Ready(Ok(1)));
+
+        flag.0.store(false, Ordering::SeqCst);
+        assert!(Pin::new(&mut future).poll(&mut cx).is_pending());
+        drop(sender);
+        assert!(flag.0.load(Ordering::SeqCst));
+        assert_eq!(
+            Pin::new(&mut future).poll(&mut cx),
+            Poll::Ready(Err(ChannelError::Disconnected))
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use crate::error::{RclrsError, ToResult};
+use crate::rcl_bindings::*;
+use crate::sync::{Mutex, MutexGuard};
+use crate::Time;
+#[cfg(feature = "std")]
+use crate::{Context, RclReturnCode};
+
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use core::time::Duration;
+
+// How often a sleeping thread checks the clock and the context. The ROS time may be simulated
+// and advance at any rate, so its remaining time can not be slept in one go.
+#[cfg(feature = "std")]
+const SLEEP_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// SAFETY: The clock functions are thread-safe when called on different clocks, and the handle is
+// only accessed through a mutex.
+unsafe impl Send for rcl_clock_t {}
+
+/// The time source of a [`Clock`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ClockType {
+    /// The simulated time published on `/clock` when ROS time is active, and the system time
+    /// otherwise.
+    RosTime,
+    /// The wall-clock time of the system, which may jump, e.g. when it is synchronized.
+    SystemTime,
+    /// A monotonic time that never jumps, but is unrelated to the wall-clock time.
+    SteadyTime,
+}
+
+impl From<ClockType> for rcl_clock_type_t {
+    fn from(clock_type: ClockType) -> Self {
+        match clock_type {
+            ClockType::RosTime => rcl_clock_type_t::RCL_ROS_TIME,
+            ClockType::SystemTime => rcl_clock_type_t::RCL_SYSTEM_TIME,
+            ClockType::SteadyTime => rcl_clock_type_t::RCL_STEADY_TIME,
+        }
+    }
+}
+
+struct ClockHandle {
+    handle: Mutex<rcl_clock_t>,
+}
+
+impl Drop for ClockHandle {
+    fn drop(&mut self) {
+        // SAFETY: No preconditions for this function (besides passing in a valid handle).
+        unsafe {
+            rcl_clock_fini(self.handle.get_mut());
+        }
+    }
+}
+
+/// A source of the current time.
+///
+/// Cloning a clock is cheap, and the clones share the same underlying `rcl` clock.
+#[derive(Clone)]
+pub struct Clock {
+    handle: Arc<ClockHandle>,
+    clock_type: ClockType,
+}
+
+impl Clock {
+    /// Creates a new clock of the given type.
+    pub fn new(clock_type: ClockType) -> Result<Self, RclrsError> {
+        // The handle is initialized in place, so that the clock is never moved after rcl_clock_init.
+        let handle = Arc::new(ClockHandle {
+            // SAFETY: A zeroed clock is the uninitialized clock that rcl_clock_init expects.
+            handle: Mutex::new(unsafe { core::mem::zeroed() }),
+        });
+        unsafe {
+            // SAFETY: No preconditions for this function.
+            let mut allocator = rcutils_get_default_allocator();
+            // SAFETY: The clock is zero-initialized as expected by this function.
+            // The allocator is copied by this function.
+            rcl_clock_init(
+                clock_type.into(),
+                &mut *handle.handle.lock(),
+                &mut allocator,
+            )
+            .ok()?;
+        }
+        Ok(Self { handle, clock_type })
+    }
+
+    /// Returns the type of the clock.
+    pub fn clock_type(&self) -> ClockType {
+        self.clock_type
+    }
+
+    // Locks the underlying clock, e.g. for creating a timer on it. The clock is never moved, so
+    // its address stays valid as long as a clone of this clock is alive.
+    pub(crate) fn lock(&self) -> MutexGuard<rcl_clock_t> {
+        self.handle.handle.lock()
+    }
+
+    // Makes a ROS-time clock report the given override time instead of the system time, or
+    // returns it to the system time with `None`. Timers on the clock are woken up by the jump.
+    #[cfg(feature = "testing")]
+    pub(crate) fn set_ros_time_override(&self, nsec: Option<i64>) -> Result<(), RclrsError> {
+        let clock = &mut *self.lock();
+        // SAFETY: No preconditions for these functions (besides passing in a valid clock).
+        unsafe {
+            match nsec {
+                Some(nsec) => {
+                    rcl_set_ros_time_override(clock, nsec).ok()?;
+                    rcl_enable_ros_time_override(clock).ok()
+                }
+                None => rcl_disable_ros_time_override(clock).ok(),
+            }
+        }
+    }
+
+    /// Returns the current time of the clock.
+    pub fn now(&self) -> Result<Time, RclrsError> {
+        let mut nsec = 0;
+        // SAFETY: No preconditions for this function (besides passing in valid pointers).
+        unsafe { rcl_clock_get_now(&mut *self.handle.handle.lock(), &mut nsec) }.ok()?;
+        Ok(Time {
+            nsec,
+            clock_type: self.clock_type,
+        })
+    }
+
+    /// Blocks the current thread until the clock has reached the given time.
+    ///
+    /// Unlike [`std::thread::sleep`], this follows the clock, so with [ROS time][1] it sleeps
+    /// until the simulated time has been reached. The clock and the context are checked every few
+    /// milliseconds, so that the thread wakes up early when the context is shut down.
+    ///
+    /// Returns `true` if the time has been reached, and `false` if the context was shut down.
+    /// Returns an [`InvalidArgument`][2] error if the time is not of the type of this clock.
+    ///
+    /// Since a [`Node`][3] can not be sent between threads, worker threads can use a clone of the
+    /// node's [clock][4] together with its context.
+    ///
+    /// [1]: crate::ClockType::RosTime
+    /// [2]: crate::RclReturnCode::InvalidArgument
+    /// [3]: crate::Node
+    /// [4]: crate::Node::get_clock
+    #[cfg(feature = "std")]
+    pub fn sleep_until(&self, until: Time, context: &Context) -> Result<bool, RclrsError> {
+        if until.clock_type != self.clock_type {
+            return Err(RclrsError::with_message(
+                RclReturnCode::InvalidArgument,
+                "The time to sleep until is not of the type of the clock",
+            ));
+        }
+        loop {
+            if !context.ok() {
+                return Ok(false);
+            }
+            let remaining = until.saturating_duration_since(self.now()?);
+            if remaining.is_zero() {
+                return Ok(true);
+            }
+            std::thread::sleep(remaining.min(SLEEP_POLL_INTERVAL));
+        }
+    }
+
+    /// Blocks the current thread until the given duration has passed on the clock.
+    ///
+    /// See [`Clock::sleep_until`].
+    #[cfg(feature = "std")]
+    pub fn sleep_for(&self, duration: Duration, context: &Context) -> Result<bool, RclrsError> {
+        let now = self.now()?;
+        match now.checked_add(duration) {
+            Some(until) => self.sleep_until(until, context),
+            // Sleep forever, or until the context is shut down.
+            None => self.sleep_until(
+                Time {
+                    nsec: i64::MAX,
+                    clock_type: self.clock_type,
+                },
+                context,
+            ),
+        }
+    }
+}
@@ -1,29 +1,94 @@
+use crate::allocator::{copy_rcutils_allocator, to_rcutils_allocator};
+use crate::distro::{context_is_valid, init_options_set_domain_id};
 use crate::rcl_bindings::*;
-use crate::{Node, RclrsError, ToResult};
+use crate::sync::{Mutex, MutexGuard};
+#[cfg(feature = "testing")]
+use crate::testing::Loopback;
+use crate::{EntityDefaults, Node, NodeOptions, RclAllocator, RclReturnCode, RclrsError, ToResult};
 
-use std::ffi::CString;
-use std::os::raw::c_char;
-use std::string::String;
-use std::sync::Arc;
-use std::vec::Vec;
+use alloc::boxed::Box;
+use alloc::ffi::CString;
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::ffi::{c_char, CStr};
 
-use parking_lot::Mutex;
+/// Internal struct that owns the `rcl` context, unless it was created with
+/// [`Context::from_raw`].
+pub(crate) struct ContextHandle {
+    rcl_context: Mutex<rcl_context_t>,
+    // Contexts from `Context::from_raw` are shut down and finalized by their owner.
+    owned: bool,
+    // The arguments and the domain ID that the context was initialized with, for reinit().
+    arguments: Vec<String>,
+    domain_id: Option<usize>,
+    non_ros_arguments: Vec<String>,
+    // The defaults for the entities of nodes that are created from now on.
+    pub(crate) entity_defaults: Mutex<EntityDefaults>,
+    shutdown_observers: Mutex<Vec<Weak<dyn ShutdownObserver>>>,
+    // Moved to the new handle when the context is reinitialized.
+    reinit_callbacks: Mutex<Vec<ReinitCallback>>,
+    // The in-process transport that new entities use instead of the middleware, see
+    // `testing::Loopback`.
+    #[cfg(feature = "testing")]
+    pub(crate) loopback: Mutex<Option<Loopback>>,
+}
+
+type ReinitCallback = Box<dyn FnMut(&Context) -> Result<(), RclrsError> + Send + 'static>;
+
+/// Something that is notified when its context is shut down, e.g. a [`channel`][1].
+///
+/// [1]: crate::channel
+pub(crate) trait ShutdownObserver: Send + Sync {
+    fn on_shutdown(&self);
+}
+
+impl ContextHandle {
+    pub(crate) fn lock(&self) -> MutexGuard<rcl_context_t> {
+        self.rcl_context.lock()
+    }
+
+    /// Registers an observer, which is notified once when the context is shut down or dropped.
+    #[cfg(feature = "std")]
+    pub(crate) fn add_shutdown_observer(&self, observer: Weak<dyn ShutdownObserver>) {
+        let mut observers = self.shutdown_observers.lock();
+        // Forget the observers that have been dropped in the meantime.
+        observers.retain(|observer| observer.strong_count() > 0);
+        observers.push(observer);
+    }
+
+    fn notify_shutdown(&self) {
+        let observers = core::mem::take(&mut *self.shutdown_observers.lock());
+        for observer in observers.iter().filter_map(Weak::upgrade) {
+            observer.on_shutdown();
+        }
+    }
+}
 
-impl Drop for rcl_context_t {
+impl Drop for ContextHandle {
     fn drop(&mut self) {
+        self.notify_shutdown();
+        if !self.owned {
+            return;
+        }
+        let rcl_context = self.rcl_context.get_mut();
         unsafe {
             // The context may be invalid when rcl_init failed, e.g. because of invalid command
             // line arguments.
             // SAFETY: No preconditions for this function.
-            if rcl_context_is_valid(self) {
+            if context_is_valid(rcl_context) {
                 // SAFETY: These functions have no preconditions besides a valid handle
-                rcl_shutdown(self);
-                rcl_context_fini(self);
+                rcl_shutdown(rcl_context);
+                rcl_context_fini(rcl_context);
             }
         }
     }
 }
 
+// SAFETY: The context is only accessed through a mutex, and rcl does not require it to be used
+// from the thread that created it.
+unsafe impl Send for rcl_context_t {}
+
 /// Shared state between nodes and similar entities.
 ///
 /// It is possible, but not usually necessary, to have several contexts in an application.
@@ -34,10 +99,14 @@ impl Drop for rcl_context_t {
 /// A context stores, among other things
 /// - command line arguments (used for e.g. name remapping)
 /// - middleware-specific data, e.g. the domain participant in DDS
-/// - the allocator used (left as the default by `rclrs`)
+/// - the allocator used (the default allocator, unless created with
+///   [`Context::new_with_allocator`])
+///
+/// A `Context` can be sent to and shared between threads.
 ///
 pub struct Context {
-    pub(crate) handle: Arc<Mutex<rcl_context_t>>,
+    pub(crate) handle: Arc<ContextHandle>,
+    pub(crate) allocator: rcutils_allocator_t,
 }
 
 impl Context {
@@ -59,46 +128,169 @@ impl Context {
     /// # Panics
     /// When there is an interior null byte in any of the args.
     pub fn new(args: impl IntoIterator<Item = String>) -> Result<Self, RclrsError> {
+        // SAFETY: No preconditions for this function.
+        let allocator = unsafe { rcutils_get_default_allocator() };
+        Self::new_impl(args.into_iter().collect(), None, allocator)
+    }
+
+    /// Creates a new context that uses a custom allocator.
+    ///
+    /// The allocator is used by `rcl` for this context, and for all nodes, publishers,
+    /// subscriptions and wait sets created from it.
+    ///
+    /// See [`Context::new`] for the other arguments.
+    pub fn new_with_allocator<A: RclAllocator>(
+        args: impl IntoIterator<Item = String>,
+        allocator: &'static A,
+    ) -> Result<Self, RclrsError> {
+        Self::new_impl(
+            args.into_iter().collect(),
+            None,
+            to_rcutils_allocator(allocator),
+        )
+    }
+
+    fn new_impl(
+        arguments: Vec<String>,
+        domain_id: Option<usize>,
+        allocator: rcutils_allocator_t,
+    ) -> Result<Self, RclrsError> {
         // SAFETY: Getting a zero-initialized value is always safe
         let mut rcl_context = unsafe { rcl_get_zero_initialized_context() };
-        let cstring_args: Vec<CString> = args
-            .into_iter()
-            .map(|arg| CString::new(arg).unwrap())
+        let cstring_args: Vec<CString> = arguments
+            .iter()
+            .map(|arg| CString::new(arg.as_str()).unwrap())
             .collect();
         // Vector of pointers into cstring_args
         let c_args: Vec<*const c_char> = cstring_args.iter().map(|arg| arg.as_ptr()).collect();
         unsafe {
-            // SAFETY: No preconditions for this function.
-            let allocator = rcutils_get_default_allocator();
             // SAFETY: Getting a zero-initialized value is always safe.
             let mut init_options = rcl_get_zero_initialized_init_options();
             // SAFETY: Passing in a zero-initialized value is expected.
             // In the case where this returns not ok, there's nothing to clean up.
-            rcl_init_options_init(&mut init_options, allocator).ok()?;
+            rcl_init_options_init(&mut init_options, copy_rcutils_allocator(&allocator)).ok()?;
+            // SAFETY: The init options are initialized.
+            let ret = match domain_id {
+                Some(domain_id) => init_options_set_domain_id(&mut init_options, domain_id).ok(),
+                None => Ok(()),
+            };
             // SAFETY: This function does not store the ephemeral init_options and c_args
             // pointers. Passing in a zero-initialized handle is expected.
-            let ret = rcl_init(
-                c_args.len() as i32,
-                if c_args.is_empty() {
-                    std::ptr::null()
-                } else {
-                    c_args.as_ptr()
-                },
-                &init_options,
-                &mut rcl_context,
-            )
-            .ok();
+            let ret = ret.and_then(|()| {
+                rcl_init(
+                    c_args.len() as i32,
+                    if c_args.is_empty() {
+                        core::ptr::null()
+                    } else {
+                        c_args.as_ptr()
+                    },
+                    &init_options,
+                    &mut rcl_context,
+                )
+                .ok()
+            });
             // SAFETY: It's safe to pass in an initialized object.
             // Early return will not leak memory, because this is the last fini function.
             rcl_init_options_fini(&mut init_options).ok()?;
             // Move the check after the last fini()
             ret?;
         }
+        // The handle is created first, so that the context is finalized in the error case.
+        let mut handle = ContextHandle {
+            rcl_context: Mutex::new(rcl_context),
+            owned: true,
+            arguments: Vec::new(),
+            domain_id,
+            non_ros_arguments: Vec::new(),
+            entity_defaults: Mutex::new(EntityDefaults::default()),
+            shutdown_observers: Mutex::new(Vec::new()),
+            reinit_callbacks: Mutex::new(Vec::new()),
+            #[cfg(feature = "testing")]
+            loopback: Mutex::new(None),
+        };
+        if !c_args.is_empty() {
+            handle.non_ros_arguments =
+                remove_ros_arguments(&c_args, handle.rcl_context.get_mut(), &allocator)?;
+        }
+        drop(c_args);
+        handle.arguments = arguments;
         Ok(Self {
-            handle: Arc::new(Mutex::new(rcl_context)),
+            handle: Arc::new(handle),
+            allocator,
         })
     }
 
+    /// Creates a context from an `rcl` context that was initialized outside of `rclrs`, e.g. by
+    /// `rclcpp` in the process that `rclrs` is embedded into.
+    ///
+    /// This allows sharing the context, and thus the middleware participant, with C and C++
+    /// code, such as bridges and plugins. Nodes can then be created from the context as usual,
+    /// or from existing nodes with [`Node::from_raw`][1].
+    ///
+    /// The context uses the default allocator.
+    ///
+    /// # Safety
+    /// The context must be initialized, and it is not shut down or finalized by `rclrs`: Its
+    /// owner remains responsible for that, and must only do it after the returned `Context` and
+    /// everything created from it has been dropped.
+    ///
+    /// The `rcl_context_t` struct is copied, so the pointer does not need to stay valid, but the
+    /// state that it refers to does.
+    ///
+    /// [1]: crate::Node::from_raw
+    pub unsafe fn from_raw(rcl_context: *const rcl_context_t) -> Self {
+        Self {
+            handle: Arc::new(ContextHandle {
+                rcl_context: Mutex::new(core::ptr::read(rcl_context)),
+                owned: false,
+                arguments: Vec::new(),
+                domain_id: None,
+                non_ros_arguments: Vec::new(),
+                entity_defaults: Mutex::new(EntityDefaults::default()),
+                shutdown_observers: Mutex::new(Vec::new()),
+                reinit_callbacks: Mutex::new(Vec::new()),
+                #[cfg(feature = "testing")]
+                loopback: Mutex::new(None),
+            }),
+            // SAFETY: No preconditions for this function.
+            allocator: rcutils_get_default_allocator(),
+        }
+    }
+
+    /// Returns the command line arguments that are not ROS arguments, i.e. those outside of
+    /// `--ros-args ... [--]`, including the program name.
+    ///
+    /// This allows parsing the application's own arguments, e.g. with `clap`, from the same
+    /// command line as the ROS arguments. Arguments that only apply to a single node can be passed
+    /// with [`NodeOptions::arguments`].
+    ///
+    /// For a context created with [`Context::from_raw`], this is empty.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::Context;
+    /// let args = ["my_program", "--verbose", "--ros-args", "-r", "__ns:=/ns", "--", "input.txt"];
+    /// let context = Context::new(args.map(String::from))?;
+    /// assert_eq!(context.non_ros_arguments(), ["my_program", "--verbose", "input.txt"]);
+    /// # Ok::<(), rclrs::RclrsError>(())
+    /// ```
+    pub fn non_ros_arguments(&self) -> &[String] {
+        &self.handle.non_ros_arguments
+    }
+
+    /// Returns the defaults for the entities of the nodes of this context.
+    pub fn entity_defaults(&self) -> EntityDefaults {
+        self.handle.entity_defaults.lock().clone()
+    }
+
+    /// Sets the defaults for the entities of the nodes of this context, see [`EntityDefaults`].
+    ///
+    /// This applies to the nodes that are created afterwards, unless they are created with
+    /// [`NodeOptions::entity_defaults`]. Existing nodes keep their defaults.
+    pub fn set_entity_defaults(&self, defaults: EntityDefaults) {
+        *self.handle.entity_defaults.lock() = defaults;
+    }
+
     /// Creates a new node in the empty namespace.
     ///
     /// Convenience function equivalent to [`Node::new`][1].
@@ -141,6 +333,21 @@ impl Context {
         Node::new_with_namespace(node_namespace, node_name, self)
     }
 
+    /// Creates a new node in a namespace, with additional options.
+    ///
+    /// Convenience function equivalent to [`Node::new_with_options`][1].
+    /// Please see that function's documentation.
+    ///
+    /// [1]: crate::Node::new_with_options
+    pub fn create_node_with_options(
+        &self,
+        node_namespace: &str,
+        node_name: &str,
+        options: NodeOptions,
+    ) -> Result<Node, RclrsError> {
+        Node::new_with_options(node_namespace, node_name, self, options)
+    }
+
     /// Checks if the context is still valid.
     ///
     /// This will return `false` when a signal has caused the context to shut down (currently
@@ -150,6 +357,163 @@ impl Context {
         // handler could call `rcl_shutdown()`, hence making the context invalid.
         let handle = &mut *self.handle.lock();
         // SAFETY: No preconditions for this function.
-        unsafe { rcl_context_is_valid(handle) }
+        unsafe { context_is_valid(handle) }
+    }
+
+    /// Shuts down the context, after which [`Context::ok`] returns `false`.
+    ///
+    /// Spin loops of the nodes of the context stop, and the [`channel`][1]s created for the
+    /// context are closed, so that threads blocked on them wake up. Nodes and other entities
+    /// that are still alive can no longer communicate, but can be dropped as usual.
+    ///
+    /// A context from [`Context::from_raw`] is not shut down in `rcl`, since its owner is
+    /// responsible for that, but its channels are closed. Shutting down a context again does
+    /// nothing.
+    ///
+    /// [1]: crate::channel
+    pub fn shutdown(&self) -> Result<(), RclrsError> {
+        if self.handle.owned {
+            let handle = &mut *self.handle.lock();
+            // SAFETY: No preconditions for these functions (besides passing in a valid handle).
+            unsafe {
+                if context_is_valid(handle) {
+                    rcl_shutdown(handle).ok()?;
+                }
+            }
+        }
+        self.handle.notify_shutdown();
+        Ok(())
+    }
+
+    /// Shuts down the context and initializes it again, with the same arguments and allocator.
+    ///
+    /// This lets long-running applications recover from middleware faults without restarting
+    /// the process. See [`Context::reinit_with_domain_id`] for the details.
+    pub fn reinit(&mut self) -> Result<(), RclrsError> {
+        self.reinit_impl(self.handle.domain_id)
+    }
+
+    /// Shuts down the context and initializes it again in another ROS domain, with the same
+    /// arguments and allocator.
+    ///
+    /// The context is shut down like with [`Context::shutdown`], so the nodes of the old context
+    /// stop communicating. They can not be moved to the new context, but are re-created by the
+    /// callbacks that were registered with [`Context::on_reinit`], which run after the new
+    /// context has been initialized. The `rcl` resources of the old context are released once
+    /// the old nodes, and everything else that was created from them, have been dropped. The
+    /// [`EntityDefaults`] of the context are kept.
+    ///
+    /// The callbacks all run, even if some of them fail, and the first error is returned. The
+    /// domain ID is also used by later calls to [`Context::reinit`].
+    ///
+    /// Returns an [`Unsupported`][1] error for a context from [`Context::from_raw`], whose owner
+    /// is responsible for initializing it, and for a domain ID on Foxy, which only supports the
+    /// `ROS_DOMAIN_ID` environment variable. If initializing the new context fails, the old one
+    /// stays shut down, and reinitializing can be tried again.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, RclrsError};
+    /// # use std::sync::{Arc, Mutex};
+    /// let mut context = Context::new([])?;
+    /// let node = Arc::new(Mutex::new(context.create_node("my_node")?));
+    /// let reinit_node = Arc::clone(&node);
+    /// context.on_reinit(move |context| {
+    ///     *reinit_node.lock().unwrap() = context.create_node("my_node")?;
+    ///     Ok(())
+    /// });
+    /// context.reinit_with_domain_id(7)?;
+    /// assert_eq!(node.lock().unwrap().domain_id(), 7);
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    ///
+    /// [1]: crate::RclReturnCode::Unsupported
+    pub fn reinit_with_domain_id(&mut self, domain_id: usize) -> Result<(), RclrsError> {
+        self.reinit_impl(Some(domain_id))
+    }
+
+    /// Registers a callback that runs after the context has been reinitialized with
+    /// [`Context::reinit`] or [`Context::reinit_with_domain_id`], with the new context.
+    ///
+    /// The callback typically re-creates the nodes of the application, and their publishers,
+    /// subscriptions etc. The callbacks run in the order they were registered.
+    pub fn on_reinit(
+        &self,
+        callback: impl FnMut(&Context) -> Result<(), RclrsError> + Send + 'static,
+    ) {
+        self.handle.reinit_callbacks.lock().push(Box::new(callback));
+    }
+
+    fn reinit_impl(&mut self, domain_id: Option<usize>) -> Result<(), RclrsError> {
+        if !self.handle.owned {
+            return Err(RclrsError::with_message(
+                RclReturnCode::Unsupported,
+                "A context created with Context::from_raw can not be reinitialized",
+            ));
+        }
+        self.shutdown()?;
+        let context = Self::new_impl(
+            self.handle.arguments.clone(),
+            domain_id,
+            copy_rcutils_allocator(&self.allocator),
+        )?;
+        *context.handle.entity_defaults.lock() = self.entity_defaults();
+        #[cfg(feature = "testing")]
+        {
+            *context.handle.loopback.lock() = self.handle.loopback.lock().clone();
+        }
+        let mut callbacks = core::mem::take(&mut *self.handle.reinit_callbacks.lock());
+        *self = context;
+        let mut result = Ok(());
+        for callback in &mut callbacks {
+            let reinitialized = callback(self);
+            if result.is_ok() {
+                result = reinitialized;
+            }
+        }
+        // The callbacks may have registered further callbacks, which run after them next time.
+        let mut reinit_callbacks = self.handle.reinit_callbacks.lock();
+        callbacks.append(&mut reinit_callbacks);
+        *reinit_callbacks = callbacks;
+        result
+    }
+}
+
+// Returns the arguments that are not ROS arguments, as parsed into the context.
+fn remove_ros_arguments(
+    c_args: &[*const c_char],
+    rcl_context: &rcl_context_t,
+    allocator: &rcutils_allocator_t,
+) -> Result<Vec<String>, RclrsError> {
+    let mut nonros_argc = 0;
+    let mut nonros_argv = core::ptr::null_mut();
+    unsafe {
+        // SAFETY: The arguments are the ones that the context was initialized with, and the
+        // output array is allocated with the given allocator.
+        rcl_remove_ros_arguments(
+            c_args.as_ptr(),
+            &rcl_context.global_arguments,
+            copy_rcutils_allocator(allocator),
+            &mut nonros_argc,
+            &mut nonros_argv,
+        )
+        .ok()?;
+    }
+    if nonros_argv.is_null() {
+        return Ok(Vec::new());
+    }
+    let non_ros_arguments = (0..nonros_argc as usize)
+        .map(|i| {
+            // SAFETY: The output array contains pointers into the input arguments, which are
+            // valid null-terminated strings.
+            unsafe { CStr::from_ptr(*nonros_argv.add(i)) }
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+    if let Some(deallocate) = allocator.deallocate {
+        // SAFETY: The array was allocated with this allocator, and is not used anymore.
+        unsafe { deallocate(nonros_argv as *mut _, allocator.state) };
     }
+    Ok(non_ros_arguments)
 }
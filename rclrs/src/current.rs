@@ -0,0 +1,136 @@
+use crate::{Executor, Node, TraceContext};
+
+use std::cell::Cell;
+use std::ptr;
+
+thread_local! {
+    // The node and the executor whose callbacks are executed on this thread, or null. They are
+    // only set while spinning, which borrows them for longer than the callbacks run.
+    static CURRENT_NODE: Cell<*const Node> = const { Cell::new(ptr::null()) };
+    static CURRENT_EXECUTOR: Cell<*const Executor> = const { Cell::new(ptr::null()) };
+    // The trace context of the request whose service callback is executed on this thread, or
+    // null.
+    static CURRENT_TRACE_CONTEXT: Cell<*mut TraceContext> = const { Cell::new(ptr::null_mut()) };
+}
+
+/// Restores the previous node or executor when a nested spin returns.
+pub(crate) struct CurrentGuard {
+    previous_node: *const Node,
+    previous_executor: *const Executor,
+}
+
+impl CurrentGuard {
+    /// Makes the node the current node of this thread, while its callbacks are executed.
+    pub(crate) fn enter_node(node: &Node) -> Self {
+        Self {
+            previous_node: CURRENT_NODE.with(|current| current.replace(node)),
+            previous_executor: CURRENT_EXECUTOR.with(|current| current.replace(ptr::null())),
+        }
+    }
+
+    /// Makes the executor the current executor of this thread, while its callbacks are executed.
+    pub(crate) fn enter_executor(executor: &Executor) -> Self {
+        Self {
+            previous_node: CURRENT_NODE.with(|current| current.replace(ptr::null())),
+            previous_executor: CURRENT_EXECUTOR.with(|current| current.replace(executor)),
+        }
+    }
+}
+
+impl Drop for CurrentGuard {
+    fn drop(&mut self) {
+        CURRENT_NODE.with(|current| current.set(self.previous_node));
+        CURRENT_EXECUTOR.with(|current| current.set(self.previous_executor));
+    }
+}
+
+/// Runs a function with the node whose callback is executing on this thread, or `None` outside
+/// of callbacks.
+///
+/// This allows library code that is called from callbacks to e.g. name its log output after the
+/// node, or to create publishers, without passing the node through every layer. The node is set
+/// while it is spun with [`spin`][1], [`spin_once`][2] and the other spin functions, also for
+/// graph event handlers such as the [`ServiceWatchdog`][3]. Callbacks that are executed by an
+/// [`Executor`] belong to no single node, see [`with_current_executor`] instead.
+///
+/// When a callback spins another node, that node is current until the nested spin returns.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// fn report(value: f64) {
+///     rclrs::with_current_node(|node| match node {
+///         Some(node) => println!("[{}] value: {value}", node.name()),
+///         None => println!("value: {value}"),
+///     });
+/// }
+///
+/// let _subscription = node.create_subscription(
+///     "value",
+///     QOS_PROFILE_DEFAULT,
+///     |msg: std_msgs::msg::Float64| report(msg.data),
+/// )?;
+/// rclrs::spin(&node)?;
+/// ```
+///
+/// [1]: crate::spin
+/// [2]: crate::spin_once
+/// [3]: crate::ServiceWatchdog
+pub fn with_current_node<R>(f: impl FnOnce(Option<&Node>) -> R) -> R {
+    let node = CURRENT_NODE.with(Cell::get);
+    // SAFETY: The node is only set while it is borrowed by a spin function on this thread, and
+    // the reference can not escape the function.
+    f(unsafe { node.as_ref() })
+}
+
+/// Runs a function with the [`Executor`] whose callback is executing on this thread, or `None`
+/// outside of its callbacks.
+///
+/// This allows library code that is called from callbacks to schedule follow-up work, e.g. to
+/// add a timer or a subscription to the executor. See [`with_current_node`] for nodes that are
+/// spun on their own.
+pub fn with_current_executor<R>(f: impl FnOnce(Option<&Executor>) -> R) -> R {
+    let executor = CURRENT_EXECUTOR.with(Cell::get);
+    // SAFETY: The executor is only set while it is borrowed by Executor::spin_once() on this
+    // thread, and the reference can not escape the function.
+    f(unsafe { executor.as_ref() })
+}
+
+/// Restores the previous trace context when a service callback returns, also when it panics.
+struct TraceContextGuard(*mut TraceContext);
+
+impl Drop for TraceContextGuard {
+    fn drop(&mut self) {
+        CURRENT_TRACE_CONTEXT.with(|current| current.set(self.0));
+    }
+}
+
+/// Makes the trace context current while the service callback `f` runs.
+pub(crate) fn with_trace_context<R>(context: &mut TraceContext, f: impl FnOnce() -> R) -> R {
+    let _guard = TraceContextGuard(CURRENT_TRACE_CONTEXT.with(|current| current.replace(context)));
+    f()
+}
+
+/// Runs a function with the [`TraceContext`] of the request whose service callback is executing
+/// on this thread, or `None` outside of service callbacks.
+///
+/// This gives the callback of a [`Service`][1] access to the correlation ID of the request, and
+/// to the metadata that the [`TraceHooks::on_request_received`][2] hook has added, without
+/// changing the signature of the callback. Changes to the metadata are visible to the
+/// [`TraceHooks::on_response_sent`][3] hook. The callback of a [`DeferredService`][4] gets the
+/// context from its [`ServiceResponder`][5] instead.
+///
+/// Within the function, the context is not current, so that it can not be borrowed twice.
+///
+/// [1]: crate::Service
+/// [2]: crate::TraceHooks::on_request_received
+/// [3]: crate::TraceHooks::on_response_sent
+/// [4]: crate::DeferredService
+/// [5]: crate::ServiceResponder::trace_context
+pub fn with_current_trace_context<R>(f: impl FnOnce(Option<&mut TraceContext>) -> R) -> R {
+    let context = CURRENT_TRACE_CONTEXT.with(|current| current.replace(ptr::null_mut()));
+    let _guard = TraceContextGuard(context);
+    // SAFETY: The context is only set while it is mutably borrowed by with_trace_context() on
+    // this thread, and it is unset while the reference exists, so that it is not aliased.
+    f(unsafe { context.as_mut() })
+}
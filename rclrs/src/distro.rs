@@ -0,0 +1,101 @@
+//! Shims for the differences in the `rcl` API between the supported ROS distros.
+//!
+//! The bindings are generated from the headers of the distro that `rclrs` is built against, and
+//! the build script sets the `ros_distro` cfg accordingly. Code that calls a function whose
+//! signature changed between distros should call the shim here instead, so that the `cfg`s are
+//! kept in one place.
+
+use crate::rcl_bindings::*;
+
+/// The ROS distro that `rclrs` has been built against, e.g. `"humble"`.
+///
+/// This is taken from the `ROS_DISTRO` environment variable at build time.
+pub const ROS_DISTRO: &str = env!("RCLRS_ROS_DISTRO");
+
+/// Shim for `rcl_context_is_valid()`, which takes a mutable pointer in Foxy.
+///
+/// # Safety
+/// The context must be valid or zero-initialized.
+pub(crate) unsafe fn context_is_valid(context: &mut rcl_context_t) -> bool {
+    #[cfg(ros_distro = "foxy")]
+    return rcl_context_is_valid(context);
+    #[cfg(not(ros_distro = "foxy"))]
+    return rcl_context_is_valid(&*context);
+}
+
+/// Shim for `rcl_init_options_set_domain_id()`, which does not exist in Foxy, where the domain
+/// ID can only be set with the `ROS_DOMAIN_ID` environment variable. Returns
+/// `RCL_RET_UNSUPPORTED` there.
+///
+/// # Safety
+/// The init options must be initialized.
+pub(crate) unsafe fn init_options_set_domain_id(
+    init_options: *mut rcl_init_options_t,
+    domain_id: usize,
+) -> rcl_ret_t {
+    #[cfg(ros_distro = "foxy")]
+    let _ = (init_options, domain_id);
+    #[cfg(ros_distro = "foxy")]
+    return 3;
+    #[cfg(not(ros_distro = "foxy"))]
+    return rcl_init_options_set_domain_id(init_options, domain_id);
+}
+
+/// Shim for `rcl_timer_init()`, which has been replaced by `rcl_timer_init2()` with an
+/// additional `autostart` parameter in Jazzy. The timer is always started.
+///
+/// # Safety
+/// The same as for `rcl_timer_init()`.
+pub(crate) unsafe fn timer_init(
+    timer: *mut rcl_timer_t,
+    clock: *mut rcl_clock_t,
+    context: *mut rcl_context_t,
+    period: i64,
+    allocator: rcl_allocator_t,
+) -> rcl_ret_t {
+    #[cfg(any(
+        ros_distro = "foxy",
+        ros_distro = "galactic",
+        ros_distro = "humble",
+        ros_distro = "iron"
+    ))]
+    return rcl_timer_init(timer, clock, context, period, None, allocator);
+    #[cfg(not(any(
+        ros_distro = "foxy",
+        ros_distro = "galactic",
+        ros_distro = "humble",
+        ros_distro = "iron"
+    )))]
+    return rcl_timer_init2(timer, clock, context, period, None, allocator, true);
+}
+
+/// Shim for `rmw_get_gid_for_client()`, which does not exist before Iron. Returns the GUID of
+/// the writer of the client's requests, which services see as the writer GUID of their
+/// [`RequestId`][1]s, or `None` before Iron or when it can not be determined.
+///
+/// # Safety
+/// The client must be initialized.
+///
+/// [1]: crate::RequestId
+pub(crate) unsafe fn client_writer_guid(client: &rcl_client_t) -> Option<[u8; 16]> {
+    #[cfg(any(ros_distro = "foxy", ros_distro = "galactic", ros_distro = "humble"))]
+    {
+        let _ = client;
+        None
+    }
+    #[cfg(not(any(ros_distro = "foxy", ros_distro = "galactic", ros_distro = "humble")))]
+    {
+        let rmw_client = rcl_client_get_rmw_handle(client);
+        if rmw_client.is_null() {
+            return None;
+        }
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut gid = core::mem::zeroed::<rmw_gid_t>();
+        if rmw_get_gid_for_client(rmw_client, &mut gid) != 0 {
+            return None;
+        }
+        let mut guid = [0; 16];
+        guid.copy_from_slice(&gid.data[..16]);
+        Some(guid)
+    }
+}
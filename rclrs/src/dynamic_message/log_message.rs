@@ -0,0 +1,108 @@
+use super::{DynamicMessage, FlowValue};
+use crate::logging::log_with_location;
+use crate::{LogLocation, LogSeverity};
+
+use rosidl_runtime_rs::Message;
+use std::fmt::Write;
+
+/// Logs selected fields of a message through `rcutils`, like `rclcpp`'s logging macros.
+///
+/// This is meant for debugging high-rate topics, where logging whole messages would flood the
+/// log. The fields are selected by their paths, e.g. `"header.stamp"` or `"pose.position.x"`, and
+/// elements of arrays by their index, e.g. `"points.0"`. They are printed on one line, in the
+/// format of `ros2 topic echo --flow-style`, e.g.
+/// `header.stamp: {sec: 12, nanosec: 500}, pose.position: {x: 1.0, y: 2.0, z: 0.0}`. Paths that
+/// do not exist in the message are printed as `<missing>`.
+///
+/// The severity is optional, and defaults to [`LogSeverity::Debug`][1]. The message is borrowed,
+/// like by [`format!`], and only copied when the logger is enabled for the severity, so that
+/// disabled calls are cheap. The fields are read through the introspection type support of the
+/// message type, which is loaded on the first call, see [`DynamicMessage::from_message`][2].
+/// This requires the `dyn_msg` feature.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let _subscription = node.create_subscription(
+///     "odom",
+///     QOS_PROFILE_SENSOR_DATA,
+///     |msg: nav_msgs::msg::Odometry| {
+///         rclrs::log_message!("odom_listener", msg, fields = ["header.stamp", "pose.pose.position"]);
+///         rclrs::log_message!(
+///             "odom_listener",
+///             LogSeverity::Info,
+///             msg,
+///             fields = ["twist.twist.linear.x"],
+///         );
+///     },
+/// )?;
+/// ```
+///
+/// [1]: crate::LogSeverity::Debug
+/// [2]: crate::DynamicMessage::from_message
+#[macro_export]
+macro_rules! log_message {
+    ($logger:expr, $msg:expr, fields = [$($field:expr),* $(,)?] $(,)?) => {
+        $crate::log_message!(
+            $logger,
+            $crate::LogSeverity::Debug,
+            $msg,
+            fields = [$($field),*]
+        )
+    };
+    ($logger:expr, $severity:expr, $msg:expr, fields = [$($field:expr),* $(,)?] $(,)?) => {
+        $crate::__log_message(
+            $logger,
+            $severity,
+            &$msg,
+            &[$($field),*],
+            module_path!(),
+            file!(),
+            line!(),
+        )
+    };
+}
+
+// Formats the selected fields of a message, or the reason why it could not be read.
+fn format_fields<T: Message>(message: &T, fields: &[&str]) -> String {
+    let message = match DynamicMessage::from_message(message) {
+        Ok(message) => message,
+        Err(e) => {
+            return match e.msg {
+                Some(msg) => format!("Could not read the message: {msg}"),
+                None => format!("Could not read the message: {}", e.code),
+            };
+        }
+    };
+    let mut text = String::new();
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            text.push_str(", ");
+        }
+        let _ = match message.get_path(field) {
+            Some(value) => write!(text, "{field}: {}", FlowValue(value)),
+            None => write!(text, "{field}: <missing>"),
+        };
+    }
+    text
+}
+
+#[doc(hidden)]
+pub fn __log_message<T: Message>(
+    logger_name: impl AsRef<str>,
+    severity: LogSeverity,
+    message: &T,
+    fields: &[&str],
+    function_name: &'static str,
+    file_name: &'static str,
+    line_number: u32,
+) {
+    let location = LogLocation {
+        function_name,
+        file_name,
+        line_number: line_number as usize,
+    };
+    log_with_location(logger_name.as_ref(), severity, location, || {
+        format_fields(message, fields)
+    });
+}
@@ -0,0 +1,430 @@
+use super::{DynamicMessage, Value};
+use crate::rcl_bindings::*;
+use crate::sync::Mutex;
+use crate::{RclReturnCode, RclrsError};
+
+use libloading::Library;
+use rosidl_runtime_rs::MessageTypeSupport;
+use std::alloc::{self, Layout};
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::{c_void, CStr};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+type MessageMembers = rosidl_typesupport_introspection_c__MessageMembers;
+type MessageMember = rosidl_typesupport_introspection_c__MessageMember;
+type FieldTypes = rosidl_typesupport_introspection_c_field_types;
+
+// The field type IDs are taken from the bindings instead of being hardcoded, since their values
+// are not the same in all distros.
+const ROS_TYPE_FLOAT: u8 = FieldTypes::rosidl_typesupport_introspection_c__ROS_TYPE_FLOAT as u8;
+const ROS_TYPE_DOUBLE: u8 = FieldTypes::rosidl_typesupport_introspection_c__ROS_TYPE_DOUBLE as u8;
+const ROS_TYPE_CHAR: u8 = FieldTypes::rosidl_typesupport_introspection_c__ROS_TYPE_CHAR as u8;
+const ROS_TYPE_WCHAR: u8 = FieldTypes::rosidl_typesupport_introspection_c__ROS_TYPE_WCHAR as u8;
+const ROS_TYPE_BOOLEAN: u8 = FieldTypes::rosidl_typesupport_introspection_c__ROS_TYPE_BOOLEAN as u8;
+const ROS_TYPE_OCTET: u8 = FieldTypes::rosidl_typesupport_introspection_c__ROS_TYPE_OCTET as u8;
+const ROS_TYPE_UINT8: u8 = FieldTypes::rosidl_typesupport_introspection_c__ROS_TYPE_UINT8 as u8;
+const ROS_TYPE_INT8: u8 = FieldTypes::rosidl_typesupport_introspection_c__ROS_TYPE_INT8 as u8;
+const ROS_TYPE_UINT16: u8 = FieldTypes::rosidl_typesupport_introspection_c__ROS_TYPE_UINT16 as u8;
+const ROS_TYPE_INT16: u8 = FieldTypes::rosidl_typesupport_introspection_c__ROS_TYPE_INT16 as u8;
+const ROS_TYPE_UINT32: u8 = FieldTypes::rosidl_typesupport_introspection_c__ROS_TYPE_UINT32 as u8;
+const ROS_TYPE_INT32: u8 = FieldTypes::rosidl_typesupport_introspection_c__ROS_TYPE_INT32 as u8;
+const ROS_TYPE_UINT64: u8 = FieldTypes::rosidl_typesupport_introspection_c__ROS_TYPE_UINT64 as u8;
+const ROS_TYPE_INT64: u8 = FieldTypes::rosidl_typesupport_introspection_c__ROS_TYPE_INT64 as u8;
+const ROS_TYPE_STRING: u8 = FieldTypes::rosidl_typesupport_introspection_c__ROS_TYPE_STRING as u8;
+const ROS_TYPE_WSTRING: u8 = FieldTypes::rosidl_typesupport_introspection_c__ROS_TYPE_WSTRING as u8;
+const ROS_TYPE_MESSAGE: u8 = FieldTypes::rosidl_typesupport_introspection_c__ROS_TYPE_MESSAGE as u8;
+
+// The message types that have been registered with register_message_types(), by name.
+static TYPE_REGISTRY: Mutex<BTreeMap<&'static str, &'static MessageTypeSupport>> =
+    Mutex::new(BTreeMap::new());
+
+/// Registers message types, so that [`DynamicMessageMetadata::new`] finds them without loading
+/// their type support libraries.
+///
+/// The message crates generated by `rosidl_generator_rs` provide the type supports of all their
+/// messages with their `type_registry` feature. This makes [`GenericPublisher`][1]s,
+/// [`GenericSubscription`][2]s and [`DynamicSubscription`][3]s work in statically linked
+/// binaries, and in deployments without the ROS installations of the message packages. Types that
+/// have not been registered are still loaded from the `AMENT_PREFIX_PATH`.
+///
+/// Registering a type again replaces the previous registration.
+///
+/// # Example
+/// ```ignore
+/// // With the type_registry feature of both crates enabled in Cargo.toml
+/// rclrs::register_message_types(std_msgs::MESSAGE_TYPE_SUPPORTS);
+/// rclrs::register_message_types(sensor_msgs::MESSAGE_TYPE_SUPPORTS);
+/// let publisher = node.create_generic_publisher("chatter", "std_msgs/msg/String", QOS_PROFILE_DEFAULT)?;
+/// ```
+///
+/// [1]: crate::GenericPublisher
+/// [2]: crate::GenericSubscription
+/// [3]: crate::DynamicSubscription
+pub fn register_message_types(types: &'static [MessageTypeSupport]) {
+    let mut registry = TYPE_REGISTRY.lock();
+    for type_support in types {
+        registry.insert(type_support.type_name, type_support);
+    }
+}
+
+// The metadata of the message types that have been converted with DynamicMessage::from_message(),
+// so that their type support libraries are only loaded once.
+static METADATA_CACHE: Mutex<BTreeMap<&'static str, DynamicMessageMetadata>> =
+    Mutex::new(BTreeMap::new());
+
+// The layout that all rosidl_runtime_c sequence types share.
+#[repr(C)]
+struct RawSequence {
+    data: *mut c_void,
+    size: usize,
+    capacity: usize,
+}
+
+/// The type support of a message type that has been loaded at runtime.
+///
+/// This is needed for creating a [`DynamicSubscription`][1]. Cloning it is cheap, and the type
+/// support libraries stay loaded as long as any clone is alive.
+///
+/// [1]: crate::DynamicSubscription
+#[derive(Clone)]
+pub struct DynamicMessageMetadata {
+    type_name: String,
+    type_support: *const rosidl_message_type_support_t,
+    members: *const MessageMembers,
+    // The type support libraries, which are not loaded for registered types.
+    _libraries: Option<Arc<[Library; 2]>>,
+}
+
+// SAFETY: The pointers point to immutable static data in the type support libraries, which are
+// kept loaded by the metadata, or are linked into the binary.
+unsafe impl Send for DynamicMessageMetadata {}
+unsafe impl Sync for DynamicMessageMetadata {}
+
+impl DynamicMessageMetadata {
+    /// Loads the type support of the message type with the given name.
+    ///
+    /// The name has the form `package/msg/Type` or `package/Type`. Types that have been
+    /// registered with [`register_message_types`] are used directly. Otherwise, the type support
+    /// libraries of the package are searched for in the ROS installations of the
+    /// `AMENT_PREFIX_PATH`, and then in the search path of the system's dynamic loader.
+    ///
+    /// Returns an [`InvalidArgument`][1] error if the name is malformed, or if the type support
+    /// can not be found.
+    ///
+    /// [1]: crate::RclReturnCode::InvalidArgument
+    pub fn new(type_name: &str) -> Result<Self, RclrsError> {
+        let (package, type_) = match type_name.split('/').collect::<Vec<_>>()[..] {
+            [package, "msg", type_] | [package, type_]
+                if !package.is_empty() && !type_.is_empty() =>
+            {
+                (package, type_)
+            }
+            _ => {
+                return Err(RclrsError::with_message(
+                    RclReturnCode::InvalidArgument,
+                    format!("Invalid message type name '{type_name}'"),
+                ))
+            }
+        };
+        let registered = TYPE_REGISTRY
+            .lock()
+            .get(format!("{package}/msg/{type_}").as_str())
+            .copied();
+        if let Some(registered) = registered {
+            let type_support = (registered.type_support)() as *const rosidl_message_type_support_t;
+            let introspection =
+                (registered.introspection_type_support)() as *const rosidl_message_type_support_t;
+            return Ok(Self {
+                type_name: registered.type_name.to_string(),
+                type_support,
+                // SAFETY: The data of an introspection type support are the message members.
+                members: unsafe { (*introspection).data } as *const MessageMembers,
+                _libraries: None,
+            });
+        }
+        let type_support_library = load_library(&format!("{package}__rosidl_typesupport_c"))?;
+        let introspection_library =
+            load_library(&format!("{package}__rosidl_typesupport_introspection_c"))?;
+        let type_support = get_type_support(
+            &type_support_library,
+            &format!(
+                "rosidl_typesupport_c__get_message_type_support_handle__{package}__msg__{type_}"
+            ),
+        )?;
+        let introspection = get_type_support(
+            &introspection_library,
+            &format!(
+                "rosidl_typesupport_introspection_c__get_message_type_support_handle__{package}__msg__{type_}"
+            ),
+        )?;
+        Ok(Self {
+            type_name: format!("{package}/msg/{type_}"),
+            type_support,
+            // SAFETY: The data of an introspection type support are the message members.
+            members: unsafe { (*introspection).data } as *const MessageMembers,
+            _libraries: Some(Arc::new([type_support_library, introspection_library])),
+        })
+    }
+
+    /// Returns the name of the message type, in the form `package/msg/Type`.
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// Returns the names of the fields of the message type, in the order of the definition.
+    pub fn field_names(&self) -> Vec<String> {
+        // SAFETY: The members are valid, see DynamicMessageMetadata::new().
+        unsafe { member_slice(&*self.members) }
+            .iter()
+            // SAFETY: The member names are valid strings.
+            .map(|member| {
+                unsafe { CStr::from_ptr(member.name_) }
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect()
+    }
+
+    /// Returns the metadata of a message type, which is loaded on the first call for each type.
+    pub(crate) fn cached(type_name: &'static str) -> Result<Self, RclrsError> {
+        if let Some(metadata) = METADATA_CACHE.lock().get(type_name) {
+            return Ok(metadata.clone());
+        }
+        let metadata = Self::new(type_name)?;
+        METADATA_CACHE.lock().insert(type_name, metadata.clone());
+        Ok(metadata)
+    }
+
+    /// Copies a message in its C representation into a [`DynamicMessage`].
+    ///
+    /// # Safety
+    /// The data must point to an initialized message of this type.
+    pub(crate) unsafe fn read_raw_message(
+        &self,
+        data: *const c_void,
+    ) -> Result<DynamicMessage, RclrsError> {
+        read_message(&*self.members, data as *const u8)
+    }
+
+    pub(crate) fn type_support(&self) -> *const rosidl_message_type_support_t {
+        self.type_support
+    }
+
+    /// Allocates and initializes a message of this type in its C representation.
+    pub(crate) fn new_raw_message(&self) -> RawMessage<'_> {
+        // SAFETY: The members are valid, see DynamicMessageMetadata::new().
+        let members = unsafe { &*self.members };
+        // rosidl_generator_c structs need at most the alignment of long double.
+        let layout = Layout::from_size_align(members.size_of_.max(1), 16).unwrap();
+        // SAFETY: The layout has a non-zero size.
+        let data = unsafe { alloc::alloc(layout) };
+        if data.is_null() {
+            alloc::handle_alloc_error(layout);
+        }
+        if let Some(init_function) = members.init_function {
+            // SAFETY: The memory has the size and alignment of the message.
+            unsafe {
+                init_function(
+                    data as *mut c_void,
+                    rosidl_runtime_c__message_initialization::ROSIDL_RUNTIME_C_MSG_INIT_ALL,
+                )
+            };
+        }
+        RawMessage {
+            metadata: self,
+            data,
+            layout,
+        }
+    }
+}
+
+/// A message in its C representation, which is finalized and freed when dropped.
+pub(crate) struct RawMessage<'a> {
+    metadata: &'a DynamicMessageMetadata,
+    data: *mut u8,
+    layout: Layout,
+}
+
+impl Drop for RawMessage<'_> {
+    fn drop(&mut self) {
+        // SAFETY: The members are valid, see DynamicMessageMetadata::new().
+        let members = unsafe { &*self.metadata.members };
+        // SAFETY: The message has been initialized, and was allocated with this layout.
+        unsafe {
+            if let Some(fini_function) = members.fini_function {
+                fini_function(self.data as *mut c_void);
+            }
+            alloc::dealloc(self.data, self.layout);
+        }
+    }
+}
+
+impl RawMessage<'_> {
+    pub(crate) fn as_mut_ptr(&mut self) -> *mut c_void {
+        self.data as *mut c_void
+    }
+
+    /// Copies the message into a [`DynamicMessage`].
+    ///
+    /// Returns an [`Unsupported`][1] error for messages with `long double` fields.
+    ///
+    /// [1]: crate::RclReturnCode::Unsupported
+    pub(crate) fn to_dynamic_message(&self) -> Result<DynamicMessage, RclrsError> {
+        // SAFETY: The message is initialized, and is described by the members.
+        unsafe { read_message(&*self.metadata.members, self.data) }
+    }
+}
+
+fn load_library(name: &str) -> Result<Library, RclrsError> {
+    let file_name = libloading::library_filename(name);
+    let library_dir = if cfg!(windows) { "bin" } else { "lib" };
+    let path = env::var_os("AMENT_PREFIX_PATH")
+        .and_then(|prefixes| {
+            env::split_paths(&prefixes)
+                .map(|prefix| prefix.join(library_dir).join(&file_name))
+                .find(|path| path.is_file())
+        })
+        .unwrap_or_else(|| PathBuf::from(&file_name));
+    // SAFETY: Type support libraries have no initialization routines with preconditions.
+    unsafe { Library::new(&path) }.map_err(|error| {
+        RclrsError::with_message(
+            RclReturnCode::InvalidArgument,
+            format!("Could not load '{}': {error}", path.display()),
+        )
+    })
+}
+
+fn get_type_support(
+    library: &Library,
+    symbol: &str,
+) -> Result<*const rosidl_message_type_support_t, RclrsError> {
+    // SAFETY: Type support getters take no arguments and return a pointer to static data.
+    unsafe {
+        let getter = library
+            .get::<unsafe extern "C" fn() -> *const rosidl_message_type_support_t>(
+                symbol.as_bytes(),
+            )
+            .map_err(|error| {
+                RclrsError::with_message(
+                    RclReturnCode::InvalidArgument,
+                    format!("Could not find the type support '{symbol}': {error}"),
+                )
+            })?;
+        Ok(getter())
+    }
+}
+
+/// # Safety
+/// The members must be valid.
+unsafe fn member_slice(members: &MessageMembers) -> &[MessageMember] {
+    std::slice::from_raw_parts(members.members_, members.member_count_ as usize)
+}
+
+/// # Safety
+/// The member must be of type `ROS_TYPE_MESSAGE`.
+unsafe fn nested_members(member: &MessageMember) -> &MessageMembers {
+    &*((*member.members_).data as *const MessageMembers)
+}
+
+/// # Safety
+/// The data must point to an initialized message described by the members.
+unsafe fn read_message(
+    members: &MessageMembers,
+    data: *const u8,
+) -> Result<DynamicMessage, RclrsError> {
+    let fields = member_slice(members)
+        .iter()
+        .map(|member| {
+            let name = CStr::from_ptr(member.name_).to_string_lossy().into_owned();
+            let value = read_member(member, data.add(member.offset_ as usize))?;
+            Ok((name, value))
+        })
+        .collect::<Result<_, RclrsError>>()?;
+    // The namespace has the form "package__msg".
+    let namespace = CStr::from_ptr(members.message_namespace_).to_string_lossy();
+    let name = CStr::from_ptr(members.message_name_).to_string_lossy();
+    Ok(DynamicMessage {
+        type_name: format!("{}/{}", namespace.replace("__", "/"), name),
+        fields,
+    })
+}
+
+/// # Safety
+/// The field must point to an initialized field described by the member.
+unsafe fn read_member(member: &MessageMember, field: *const u8) -> Result<Value, RclrsError> {
+    if !member.is_array_ {
+        return read_element(member, field);
+    }
+    let (elements, len) = if member.array_size_ > 0 && !member.is_upper_bound_ {
+        // A fixed-size array is stored inline.
+        (field, member.array_size_)
+    } else {
+        let sequence = &*(field as *const RawSequence);
+        (sequence.data as *const u8, sequence.size)
+    };
+    let stride = element_size(member)?;
+    (0..len)
+        .map(|i| read_element(member, elements.add(i * stride)))
+        .collect::<Result<_, _>>()
+        .map(Value::Array)
+}
+
+/// # Safety
+/// The member must be valid.
+unsafe fn element_size(member: &MessageMember) -> Result<usize, RclrsError> {
+    Ok(match member.type_id_ {
+        ROS_TYPE_BOOLEAN | ROS_TYPE_OCTET | ROS_TYPE_CHAR | ROS_TYPE_UINT8 | ROS_TYPE_INT8 => 1,
+        ROS_TYPE_WCHAR | ROS_TYPE_UINT16 | ROS_TYPE_INT16 => 2,
+        ROS_TYPE_FLOAT | ROS_TYPE_UINT32 | ROS_TYPE_INT32 => 4,
+        ROS_TYPE_DOUBLE | ROS_TYPE_UINT64 | ROS_TYPE_INT64 => 8,
+        ROS_TYPE_STRING => std::mem::size_of::<rosidl_runtime_rs::String>(),
+        ROS_TYPE_WSTRING => std::mem::size_of::<rosidl_runtime_rs::WString>(),
+        ROS_TYPE_MESSAGE => nested_members(member).size_of_,
+        _ => return Err(unsupported_field(member)),
+    })
+}
+
+/// # Safety
+/// The element must point to an initialized value of the type of the member.
+unsafe fn read_element(member: &MessageMember, element: *const u8) -> Result<Value, RclrsError> {
+    Ok(match member.type_id_ {
+        // Any non-zero byte is read as true, instead of causing undefined behavior.
+        ROS_TYPE_BOOLEAN => Value::Bool(*element != 0),
+        ROS_TYPE_OCTET => Value::Octet(*element),
+        ROS_TYPE_CHAR => Value::Char(*element),
+        ROS_TYPE_UINT8 => Value::Uint8(*element),
+        ROS_TYPE_INT8 => Value::Int8(*(element as *const i8)),
+        ROS_TYPE_WCHAR => Value::WChar(*(element as *const u16)),
+        ROS_TYPE_UINT16 => Value::Uint16(*(element as *const u16)),
+        ROS_TYPE_INT16 => Value::Int16(*(element as *const i16)),
+        ROS_TYPE_UINT32 => Value::Uint32(*(element as *const u32)),
+        ROS_TYPE_INT32 => Value::Int32(*(element as *const i32)),
+        ROS_TYPE_UINT64 => Value::Uint64(*(element as *const u64)),
+        ROS_TYPE_INT64 => Value::Int64(*(element as *const i64)),
+        ROS_TYPE_FLOAT => Value::Float32(*(element as *const f32)),
+        ROS_TYPE_DOUBLE => Value::Float64(*(element as *const f64)),
+        ROS_TYPE_STRING => {
+            Value::String((*(element as *const rosidl_runtime_rs::String)).to_string())
+        }
+        ROS_TYPE_WSTRING => {
+            Value::WString((*(element as *const rosidl_runtime_rs::WString)).to_string())
+        }
+        ROS_TYPE_MESSAGE => Value::Message(read_message(nested_members(member), element)?),
+        _ => return Err(unsupported_field(member)),
+    })
+}
+
+/// # Safety
+/// The member must be valid.
+unsafe fn unsupported_field(member: &MessageMember) -> RclrsError {
+    RclrsError::with_message(
+        RclReturnCode::Unsupported,
+        format!(
+            "The field '{}' has an unsupported type (ID {})",
+            CStr::from_ptr(member.name_).to_string_lossy(),
+            member.type_id_
+        ),
+    )
+}
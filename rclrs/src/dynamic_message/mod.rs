@@ -0,0 +1,215 @@
+//! Messages whose type is only known at runtime.
+//!
+//! This requires the `dyn_msg` feature. The type support libraries of a message type are loaded
+//! by name, from the ROS installations in the `AMENT_PREFIX_PATH`, so that e.g. a tool can
+//! subscribe to any topic it discovers in the ROS graph, without depending on the message crates.
+
+mod log_message;
+mod metadata;
+
+pub use log_message::*;
+pub use metadata::*;
+
+use crate::RclrsError;
+
+use rosidl_runtime_rs::{Message, RmwMessage};
+use std::borrow::Cow;
+use std::fmt::{self, Display};
+
+/// A message whose type is only known at runtime.
+///
+/// The fields are in the order of the message definition. The `Display` implementation formats
+/// the message like `ros2 topic echo` does.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DynamicMessage {
+    /// The type of the message, e.g. `std_msgs/msg/String`.
+    pub type_name: String,
+    /// The names and values of the fields.
+    pub fields: Vec<(String, Value)>,
+}
+
+impl DynamicMessage {
+    /// Copies a message of a generated message type into a dynamic message, e.g. for accessing
+    /// its fields by name.
+    ///
+    /// The type support of the message type is loaded like with [`DynamicMessageMetadata::new`],
+    /// once per type.
+    ///
+    /// Returns an [`InvalidArgument`][1] error if the type support can not be found, and an
+    /// [`Unsupported`][2] error for messages with `long double` fields.
+    ///
+    /// [1]: crate::RclReturnCode::InvalidArgument
+    /// [2]: crate::RclReturnCode::Unsupported
+    pub fn from_message<T: Message>(message: &T) -> Result<Self, RclrsError> {
+        let metadata = DynamicMessageMetadata::cached(<T as Message>::RmwMsg::TYPE_NAME)?;
+        let rmw_message = T::into_rmw_message(Cow::Borrowed(message));
+        // SAFETY: The RMW-native message has the C representation that the type support
+        // describes.
+        unsafe {
+            metadata
+                .read_raw_message(rmw_message.as_ref() as *const <T as Message>::RmwMsg as *const _)
+        }
+    }
+
+    /// Returns the value of the field with the given name, if it exists.
+    pub fn get(&self, field_name: &str) -> Option<&Value> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name == field_name)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the value of a field of a nested message, e.g. `pose.position.x`, if it exists.
+    ///
+    /// Elements of arrays and sequences are selected by their index, e.g. `points.0.x`.
+    pub fn get_path(&self, path: &str) -> Option<&Value> {
+        let mut segments = path.split('.');
+        let mut value = self.get(segments.next()?)?;
+        for segment in segments {
+            value = match value {
+                Value::Message(message) => message.get(segment)?,
+                Value::Array(elements) => elements.get(segment.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+}
+
+/// The value of a field of a [`DynamicMessage`].
+///
+/// Arrays, bounded sequences and unbounded sequences are all represented as [`Value::Array`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// A `bool` field.
+    Bool(bool),
+    /// A `byte` (`octet`) field.
+    Octet(u8),
+    /// A `char` field.
+    Char(u8),
+    /// A `wchar` field.
+    WChar(u16),
+    /// A `uint8` field.
+    Uint8(u8),
+    /// An `int8` field.
+    Int8(i8),
+    /// A `uint16` field.
+    Uint16(u16),
+    /// An `int16` field.
+    Int16(i16),
+    /// A `uint32` field.
+    Uint32(u32),
+    /// An `int32` field.
+    Int32(i32),
+    /// A `uint64` field.
+    Uint64(u64),
+    /// An `int64` field.
+    Int64(i64),
+    /// A `float32` field.
+    Float32(f32),
+    /// A `float64` field.
+    Float64(f64),
+    /// A `string` field.
+    String(String),
+    /// A `wstring` field.
+    WString(String),
+    /// A nested message.
+    Message(DynamicMessage),
+    /// An array or sequence field.
+    Array(Vec<Value>),
+}
+
+impl Display for DynamicMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_fields(f, &self.fields, 0)
+    }
+}
+
+// Writes the fields as a YAML block mapping, with each line indented by the given amount.
+fn write_fields(
+    f: &mut fmt::Formatter<'_>,
+    fields: &[(String, Value)],
+    indent: usize,
+) -> fmt::Result {
+    for (name, value) in fields {
+        write!(f, "{:indent$}{}:", "", name)?;
+        match value {
+            Value::Message(message) => {
+                writeln!(f)?;
+                write_fields(f, &message.fields, indent + 2)?;
+            }
+            Value::Array(elements)
+                if elements
+                    .iter()
+                    .any(|element| matches!(element, Value::Message(_))) =>
+            {
+                writeln!(f)?;
+                for element in elements {
+                    match element {
+                        Value::Message(message) => {
+                            // The first field goes on the line of the list item marker.
+                            write!(f, "{:indent$}- ", "")?;
+                            let lines = Indented(&message.fields, indent + 2).to_string();
+                            f.write_str(lines.trim_start())?;
+                        }
+                        other => writeln!(f, "{:indent$}- {}", "", FlowValue(other))?,
+                    }
+                }
+            }
+            other => writeln!(f, " {}", FlowValue(other))?,
+        }
+    }
+    Ok(())
+}
+
+struct Indented<'a>(&'a [(String, Value)], usize);
+
+impl Display for Indented<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_fields(f, self.0, self.1)
+    }
+}
+
+// Formats a value on a single line, in the YAML flow style.
+struct FlowValue<'a>(&'a Value);
+
+impl Display for FlowValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Value::Bool(value) => write!(f, "{value}"),
+            Value::Octet(value) | Value::Char(value) | Value::Uint8(value) => write!(f, "{value}"),
+            Value::WChar(value) | Value::Uint16(value) => write!(f, "{value}"),
+            Value::Int8(value) => write!(f, "{value}"),
+            Value::Int16(value) => write!(f, "{value}"),
+            Value::Uint32(value) => write!(f, "{value}"),
+            Value::Int32(value) => write!(f, "{value}"),
+            Value::Uint64(value) => write!(f, "{value}"),
+            Value::Int64(value) => write!(f, "{value}"),
+            Value::Float32(value) => write!(f, "{value:?}"),
+            Value::Float64(value) => write!(f, "{value:?}"),
+            Value::String(value) | Value::WString(value) => {
+                write!(f, "'{}'", value.replace('\'', "''"))
+            }
+            Value::Message(message) => {
+                f.write_str("{")?;
+                for (i, (name, value)) in message.fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{name}: {}", FlowValue(value))?;
+                }
+                f.write_str("}")
+            }
+            Value::Array(elements) => {
+                f.write_str("[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", FlowValue(element))?;
+                }
+                f.write_str("]")
+            }
+        }
+    }
+}
@@ -1,7 +1,9 @@
 use crate::rcl_bindings::*;
+use alloc::string::String;
+use core::ffi::CStr;
+use core::fmt::{self, Display};
+#[cfg(feature = "std")]
 use std::error::Error;
-use std::ffi::CStr;
-use std::fmt::{self, Display};
 
 /// The main error type.
 #[derive(Debug, PartialEq)]
@@ -12,6 +14,22 @@ pub struct RclrsError {
     pub(crate) msg: Option<RclErrorMsg>,
 }
 
+impl RclrsError {
+    /// Creates an error that was detected by `rclrs` itself, with a message explaining it.
+    pub(crate) fn with_message(code: RclReturnCode, msg: impl Into<String>) -> Self {
+        Self {
+            code,
+            msg: Some(RclErrorMsg(msg.into())),
+        }
+    }
+
+    /// Returns whether the operation may succeed when it is tried again, see
+    /// [`RclReturnCode::is_transient`].
+    pub fn is_transient(&self) -> bool {
+        self.code.is_transient()
+    }
+}
+
 impl Display for RclrsError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.code)
@@ -37,8 +55,10 @@ impl Display for RclErrorMsg {
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for RclErrorMsg {}
 
+#[cfg(feature = "std")]
 impl Error for RclrsError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         self.msg.as_ref().map(|e| e as &dyn Error)
@@ -48,7 +68,7 @@ impl Error for RclrsError {
 /// RCL specific error codes.
 ///
 /// These are the error codes that start at 100.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum RclErrorCode {
     /// `rcl_init()` already called
     AlreadyInit = 100,
@@ -102,10 +122,11 @@ impl Display for RclErrorCode {
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for RclErrorCode {}
 
 /// Error indicating problems in the RCL node (2XX).
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum NodeErrorCode {
     /// Invalid `rcl_node_t` given
     NodeInvalid = 200,
@@ -141,10 +162,11 @@ impl Display for NodeErrorCode {
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for NodeErrorCode {}
 
 /// Error indicating problems in the RCL subscription (4XX).
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum SubscriberErrorCode {
     /// Invalid `rcl_subscription_t` given
     SubscriptionInvalid = 400,
@@ -177,10 +199,11 @@ impl Display for SubscriberErrorCode {
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for SubscriberErrorCode {}
 
 /// Error indicating problems in the RCL client (5XX).
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ClientErrorCode {
     /// Invalid `rcl_client_t` given
     ClientInvalid = 500,
@@ -209,10 +232,11 @@ impl Display for ClientErrorCode {
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for ClientErrorCode {}
 
 /// Error indicating problems in the RCL service (6XX).
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ServiceErrorCode {
     /// Invalid `rcl_service_t` given
     ServiceInvalid = 600,
@@ -243,13 +267,14 @@ impl Display for ServiceErrorCode {
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for ServiceErrorCode {}
 
 // Error codes indicating problems in RCL guard conditions are in 7XX...
 // But as of the writing of this code, they are not implemented in `rcl/types.h`!
 
 /// Error indicating problems in the RCL timer (8XX).
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum TimerErrorCode {
     /// Invalid `rcl_timer_t` given
     TimerInvalid = 800,
@@ -277,10 +302,11 @@ impl Display for TimerErrorCode {
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for TimerErrorCode {}
 
 /// Error indicating problems with RCL wait and wait set (9XX).
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum WaitSetErrorCode {
     /// Invalid `rcl_wait_set_t` given
     WaitSetInvalid = 900,
@@ -312,10 +338,11 @@ impl Display for WaitSetErrorCode {
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for WaitSetErrorCode {}
 
 /// Error indicating problems with RCL argument parsing (1XXX).
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ParsingErrorCode {
     /// Argument is not a valid remap rule
     InvalidRemapRule = 1001,
@@ -366,10 +393,11 @@ impl Display for ParsingErrorCode {
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for ParsingErrorCode {}
 
 /// Error indicating problems with RCL events (20XX)
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum EventErrorCode {
     /// Invalid `rcl_event_t` given
     EventInvalid = 2000,
@@ -400,10 +428,11 @@ impl Display for EventErrorCode {
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for EventErrorCode {}
 
 /// Error indicating problems with RCL lifecycle state registration (30XX).
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum LifecycleErrorCode {
     /// `rcl_lifecycle` state registered
     LifecycleStateRegistered = 3000,
@@ -437,10 +466,11 @@ impl Display for LifecycleErrorCode {
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for LifecycleErrorCode {}
 
 /// Return codes of RCL functions.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum RclReturnCode {
     /// Success
     Ok,
@@ -480,6 +510,18 @@ pub enum RclReturnCode {
     UnknownError(i32),
 }
 
+impl RclReturnCode {
+    /// Returns whether the operation may succeed when it is tried again.
+    ///
+    /// This is the case for timeouts, failed allocations, e.g. of loaned messages, and
+    /// unspecified errors, which the RMW implementation returns when the transport fails, e.g.
+    /// because a send buffer is full. Other errors, such as an invalid publisher or a context
+    /// that has been shut down, are permanent.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Error | Self::Timeout | Self::BadAlloc)
+    }
+}
+
 impl From<i32> for RclReturnCode {
     fn from(value: i32) -> Self {
         match value {
@@ -630,6 +672,7 @@ impl Display for RclReturnCode {
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for RclReturnCode {}
 
 pub(crate) fn to_rcl_result(code: i32) -> Result<(), RclrsError> {
@@ -708,6 +751,16 @@ mod tests {
         assert_eq!(RclReturnCode::from(11), RclReturnCode::InvalidArgument);
     }
 
+    #[test]
+    fn test_is_transient() {
+        assert!(RclReturnCode::from(1).is_transient());
+        assert!(RclReturnCode::from(2).is_transient());
+        assert!(RclReturnCode::from(10).is_transient());
+        assert!(!RclReturnCode::from(11).is_transient());
+        assert!(!RclReturnCode::from(106).is_transient());
+        assert!(!RclReturnCode::from(300).is_transient());
+    }
+
     /////////////////////
     // RclError checks //
     /////////////////////
@@ -0,0 +1,677 @@
+use crate::allocator::copy_rcutils_allocator;
+#[cfg(feature = "std")]
+use crate::current::CurrentGuard;
+use crate::distro::context_is_valid;
+#[cfg(feature = "testing")]
+use crate::rcl_bindings::*;
+use crate::sync::Mutex;
+#[cfg(feature = "testing")]
+use crate::testing::{Loopback, LoopbackTargets};
+use crate::{
+    CallbackHooks, ClientBase, Context, GuardCondition, RclReturnCode, RclrsError, ReadyEntities,
+    ServiceBase, SubscriptionBase, TimerBase, WaitSet, WaitSetCapacities,
+};
+#[cfg(feature = "testing")]
+use crate::{TimerErrorCode, ToResult};
+
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+#[cfg(all(feature = "std", unix))]
+use std::os::unix::io::RawFd;
+
+#[cfg(all(feature = "std", unix))]
+use crate::{FdInterest, FdReadiness, FdWatcher};
+
+#[cfg(all(feature = "std", unix))]
+type FdCallback = Arc<Mutex<dyn FnMut(FdReadiness) + 'static>>;
+
+// Handles the queued commands of an `ExecutorHandle`.
+type CommandHandler = Box<dyn FnMut(&Executor) -> Result<(), RclrsError> + 'static>;
+
+// The entities of an executor, which are copied into its wait set.
+#[derive(Clone, Default)]
+struct ExecutorEntities {
+    subscriptions: Vec<Arc<dyn SubscriptionBase>>,
+    guard_conditions: Vec<Arc<GuardCondition>>,
+    timers: Vec<Arc<dyn TimerBase>>,
+    clients: Vec<Arc<dyn ClientBase>>,
+    services: Vec<Arc<dyn ServiceBase>>,
+    // The registration order of the entities, by address, for deterministic executors.
+    registrations: BTreeMap<usize, u64>,
+    next_registration: u64,
+}
+
+impl ExecutorEntities {
+    fn register<T: ?Sized>(&mut self, entity: &Arc<T>) {
+        let address = Arc::as_ptr(entity) as *const () as usize;
+        self.registrations.insert(address, self.next_registration);
+        self.next_registration += 1;
+    }
+}
+
+// The wait set of an executor, which is rebuilt when its entities change.
+struct ExecutorWaitSet {
+    wait_set: WaitSet,
+    entities: ExecutorEntities,
+    ready_entities: ReadyEntities,
+}
+
+/// Spins a set of entities that can be changed while spinning.
+///
+/// Unlike with [`spin`][1], the entities are not taken from a node, but added and removed
+/// individually, e.g. with [`Executor::add_subscription`] and
+/// [`Executor::remove_subscription`]. These functions only take `&self`, so they can be called
+/// from the callbacks of the executor, e.g. for creating subscriptions on demand. The executor is
+/// woken up by a guard condition when its entities change, and rebuilds its wait set before
+/// waiting again.
+///
+/// An entity that is removed may still be executed once if it was already ready. Entities
+/// should not be spun by an executor and a node at the same time, see
+/// [`WaitSet::add_subscription`].
+///
+/// The entities of `rclrs` are not `Send`, so the executor can not be shared with other threads.
+/// Instead, other threads can send commands to it through an [`ExecutorHandle`], see
+/// [`Executor::create_handle`].
+///
+/// On Unix, the executor can also wait for file descriptors, e.g. of sockets and serial ports,
+/// see [`Executor::add_fd`].
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let context = Context::new(std::env::args())?;
+/// let node = std::rc::Rc::new(std::cell::RefCell::new(context.create_node("dynamic")?));
+/// let executor = std::rc::Rc::new(Executor::new(&context)?);
+/// let timer = {
+///     let (node, executor) = (node.clone(), executor.clone());
+///     node.borrow_mut().create_timer(Duration::from_secs(1), move || {
+///         let subscription = node
+///             .borrow_mut()
+///             .create_subscription("topic", QOS_PROFILE_DEFAULT, |msg: std_msgs::msg::String| {
+///                 println!("{}", msg.data)
+///             })
+///             .unwrap();
+///         executor.add_subscription(subscription).unwrap();
+///     })?
+/// };
+/// executor.add_timer(timer)?;
+/// executor.spin()?;
+/// ```
+///
+/// [1]: crate::spin
+pub struct Executor {
+    context: Context,
+    entities: Mutex<ExecutorEntities>,
+    changed: AtomicBool,
+    // Wakes up the wait set when the entities change.
+    interrupt: Arc<GuardCondition>,
+    wait_set: Mutex<Option<ExecutorWaitSet>>,
+    callback_hooks: Option<CallbackHooks>,
+    deterministic: bool,
+    // The handlers of the commands that are sent through `ExecutorHandle`s.
+    command_handlers: Mutex<Vec<CommandHandler>>,
+    // Created when the first file descriptor is added.
+    #[cfg(all(feature = "std", unix))]
+    fd_watcher: Mutex<Option<FdWatcher>>,
+    #[cfg(all(feature = "std", unix))]
+    fd_callbacks: Mutex<BTreeMap<RawFd, FdCallback>>,
+}
+
+impl Executor {
+    /// Creates an executor without entities.
+    pub fn new(context: &Context) -> Result<Self, RclrsError> {
+        Ok(Self {
+            context: Context {
+                handle: context.handle.clone(),
+                allocator: copy_rcutils_allocator(&context.allocator),
+            },
+            entities: Mutex::new(ExecutorEntities::default()),
+            changed: AtomicBool::new(true),
+            interrupt: Arc::new(GuardCondition::new(context)?),
+            wait_set: Mutex::new(None),
+            callback_hooks: None,
+            deterministic: false,
+            command_handlers: Mutex::new(Vec::new()),
+            #[cfg(all(feature = "std", unix))]
+            fd_watcher: Mutex::new(None),
+            #[cfg(all(feature = "std", unix))]
+            fd_callbacks: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    /// Creates an executor without entities that executes them in a deterministic order, e.g. for
+    /// tests.
+    ///
+    /// The entities that are ready after a wakeup are executed in the order in which they were
+    /// added to the executor, regardless of their kind and [priority][1]. Together with a
+    /// [`ManualClock`][2], this makes tests with several entities reproducible, see
+    /// [`DeterministicExecutor`][3].
+    ///
+    /// [1]: crate::Subscription::set_priority
+    /// [2]: crate::testing::ManualClock
+    /// [3]: crate::testing::DeterministicExecutor
+    pub fn new_deterministic(context: &Context) -> Result<Self, RclrsError> {
+        Ok(Self {
+            deterministic: true,
+            ..Self::new(context)?
+        })
+    }
+
+    /// Sets the hooks that run around each callback of the executor.
+    ///
+    /// This replaces previously set hooks. See [`CallbackHooks`].
+    pub fn set_callback_hooks(&mut self, hooks: CallbackHooks) {
+        self.callback_hooks = Some(hooks);
+    }
+
+    /// Adds a subscription to the executor.
+    pub fn add_subscription(
+        &self,
+        subscription: Arc<dyn SubscriptionBase>,
+    ) -> Result<(), RclrsError> {
+        self.change(|entities| {
+            entities.register(&subscription);
+            entities.subscriptions.push(subscription);
+        })
+    }
+
+    /// Removes a subscription from the executor.
+    ///
+    /// Returns `false` if the subscription has not been added to the executor.
+    pub fn remove_subscription(
+        &self,
+        subscription: &dyn SubscriptionBase,
+    ) -> Result<bool, RclrsError> {
+        self.change(|entities| {
+            remove_entity(
+                &mut entities.subscriptions,
+                &mut entities.registrations,
+                subscription,
+            )
+        })
+    }
+
+    /// Adds a guard condition to the executor, which wakes it up when triggered.
+    pub fn add_guard_condition(
+        &self,
+        guard_condition: Arc<GuardCondition>,
+    ) -> Result<(), RclrsError> {
+        self.change(|entities| {
+            entities.register(&guard_condition);
+            entities.guard_conditions.push(guard_condition);
+        })
+    }
+
+    /// Removes a guard condition from the executor.
+    ///
+    /// Returns `false` if the guard condition has not been added to the executor.
+    pub fn remove_guard_condition(
+        &self,
+        guard_condition: &GuardCondition,
+    ) -> Result<bool, RclrsError> {
+        self.change(|entities| {
+            remove_entity(
+                &mut entities.guard_conditions,
+                &mut entities.registrations,
+                guard_condition,
+            )
+        })
+    }
+
+    /// Adds a timer to the executor.
+    pub fn add_timer(&self, timer: Arc<dyn TimerBase>) -> Result<(), RclrsError> {
+        self.change(|entities| {
+            entities.register(&timer);
+            entities.timers.push(timer);
+        })
+    }
+
+    /// Removes a timer from the executor.
+    ///
+    /// Returns `false` if the timer has not been added to the executor.
+    pub fn remove_timer(&self, timer: &dyn TimerBase) -> Result<bool, RclrsError> {
+        self.change(|entities| {
+            remove_entity(&mut entities.timers, &mut entities.registrations, timer)
+        })
+    }
+
+    /// Adds a client to the executor.
+    pub fn add_client(&self, client: Arc<dyn ClientBase>) -> Result<(), RclrsError> {
+        self.change(|entities| {
+            entities.register(&client);
+            entities.clients.push(client);
+        })
+    }
+
+    /// Removes a client from the executor.
+    ///
+    /// Returns `false` if the client has not been added to the executor.
+    pub fn remove_client(&self, client: &dyn ClientBase) -> Result<bool, RclrsError> {
+        self.change(|entities| {
+            remove_entity(&mut entities.clients, &mut entities.registrations, client)
+        })
+    }
+
+    /// Adds a service to the executor.
+    pub fn add_service(&self, service: Arc<dyn ServiceBase>) -> Result<(), RclrsError> {
+        self.change(|entities| {
+            entities.register(&service);
+            entities.services.push(service);
+        })
+    }
+
+    /// Removes a service from the executor.
+    ///
+    /// Returns `false` if the service has not been added to the executor.
+    pub fn remove_service(&self, service: &dyn ServiceBase) -> Result<bool, RclrsError> {
+        self.change(|entities| {
+            remove_entity(&mut entities.services, &mut entities.registrations, service)
+        })
+    }
+
+    /// Creates a handle with which other threads can send commands to the executor, e.g. for
+    /// adding and removing entities.
+    ///
+    /// The handler runs on the thread that spins the executor, once for each command, at the
+    /// beginning of the next [`Executor::spin_once`]. Sending a command wakes up the executor, so
+    /// the command is handled without waiting for another entity to become ready. Errors of the
+    /// handler are returned from [`Executor::spin_once`].
+    ///
+    /// Since the handler does not need to be `Send`, it can create entities with a node that it
+    /// shares with the rest of the thread, and only the commands need to be sent between threads.
+    ///
+    /// # Example
+    /// ```ignore
+    /// # use rclrs::*;
+    /// # use std::collections::BTreeMap;
+    /// enum Command {
+    ///     Subscribe(String),
+    ///     Unsubscribe(String),
+    /// }
+    ///
+    /// let context = Context::new(std::env::args())?;
+    /// let mut node = context.create_node("dynamic")?;
+    /// let executor = Executor::new(&context)?;
+    /// let mut subscriptions = BTreeMap::new();
+    /// let handle = executor.create_handle(move |executor: &Executor, command| {
+    ///     match command {
+    ///         Command::Subscribe(topic) => {
+    ///             let subscription = node.create_subscription(
+    ///                 &topic,
+    ///                 QOS_PROFILE_DEFAULT,
+    ///                 |msg: std_msgs::msg::String| println!("{}", msg.data),
+    ///             )?;
+    ///             executor.add_subscription(subscription.clone())?;
+    ///             subscriptions.insert(topic, subscription);
+    ///         }
+    ///         Command::Unsubscribe(topic) => {
+    ///             if let Some(subscription) = subscriptions.remove(&topic) {
+    ///                 executor.remove_subscription(&*subscription)?;
+    ///             }
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// });
+    /// std::thread::spawn(move || {
+    ///     handle.send(Command::Subscribe("chatter".into())).unwrap();
+    /// });
+    /// executor.spin()?;
+    /// ```
+    pub fn create_handle<C, F>(&self, mut handler: F) -> ExecutorHandle<C>
+    where
+        C: Send + 'static,
+        F: FnMut(&Executor, C) -> Result<(), RclrsError> + 'static,
+    {
+        let commands = Arc::new(Mutex::new(VecDeque::new()));
+        let queue = Arc::clone(&commands);
+        self.command_handlers
+            .lock()
+            .push(Box::new(move |executor: &Executor| loop {
+                // The queue is not locked while the command is handled, so that the handler can
+                // send commands too.
+                let command = queue.lock().pop_front();
+                match command {
+                    Some(command) => handler(executor, command)?,
+                    None => return Ok(()),
+                }
+            }));
+        ExecutorHandle {
+            commands,
+            interrupt: Arc::clone(&self.interrupt),
+        }
+    }
+
+    /// Adds a file descriptor to the executor, whose callback runs when the file descriptor is
+    /// ready, e.g. when data can be read from a socket or a serial port.
+    ///
+    /// This lets a single thread serve hardware I/O and ROS callbacks alike. The callback runs on
+    /// the thread that spins the executor, with the readiness of the file descriptor. Readiness
+    /// is level-triggered: The file descriptor is polled again after the callback has returned,
+    /// so the callback should read or write until it would block, or at least once.
+    ///
+    /// `rcl` wait sets can not wait for file descriptors, so they are polled by a background
+    /// thread of the executor, which wakes it up through a guard condition. The thread is named
+    /// `rclrs_fd_watcher`, is started by the first call of this function, and is stopped when the
+    /// executor is dropped. It only polls the file descriptors, so the callbacks do not need to
+    /// be `Send`. If polling fails, the error is logged with the `rclrs` logger and the thread
+    /// stops. The file descriptor must stay open until it is removed with
+    /// [`Executor::remove_fd`], since its number may be reused otherwise. Adding a file descriptor
+    /// again replaces its interest and callback.
+    ///
+    /// Only available on Unix, and with the `std` feature.
+    ///
+    /// # Example
+    /// ```ignore
+    /// # use rclrs::*;
+    /// # use std::io::Read;
+    /// # use std::os::unix::io::AsRawFd;
+    /// let mut serial = std::fs::File::open("/dev/ttyUSB0")?;
+    /// let fd = serial.as_raw_fd();
+    /// let publisher = node.create_publisher::<std_msgs::msg::UInt8MultiArray>(
+    ///     "serial_rx",
+    ///     QOS_PROFILE_DEFAULT,
+    /// )?;
+    /// executor.add_fd(fd, FdInterest::Readable, move |readiness| {
+    ///     let mut buffer = [0; 256];
+    ///     if let Ok(n) = serial.read(&mut buffer) {
+    ///         let data = buffer[..n].to_vec();
+    ///         let _ = publisher.publish(std_msgs::msg::UInt8MultiArray { data, ..Default::default() });
+    ///     }
+    /// })?;
+    /// executor.spin()?;
+    /// ```
+    #[cfg(all(feature = "std", unix))]
+    pub fn add_fd<F>(&self, fd: RawFd, interest: FdInterest, callback: F) -> Result<(), RclrsError>
+    where
+        F: FnMut(FdReadiness) + 'static,
+    {
+        let mut fd_watcher = self.fd_watcher.lock();
+        let fd_watcher = match &mut *fd_watcher {
+            Some(fd_watcher) => fd_watcher,
+            None => fd_watcher.insert(FdWatcher::new(Arc::clone(&self.interrupt))?),
+        };
+        self.fd_callbacks
+            .lock()
+            .insert(fd, Arc::new(Mutex::new(callback)));
+        fd_watcher.add(fd, interest);
+        Ok(())
+    }
+
+    /// Removes a file descriptor from the executor, see [`Executor::add_fd`].
+    ///
+    /// Returns `false` if the file descriptor has not been added to the executor. This may be
+    /// called from the callback of the file descriptor.
+    #[cfg(all(feature = "std", unix))]
+    pub fn remove_fd(&self, fd: RawFd) -> bool {
+        if let Some(fd_watcher) = &*self.fd_watcher.lock() {
+            fd_watcher.remove(fd);
+        }
+        self.fd_callbacks.lock().remove(&fd).is_some()
+    }
+
+    // Runs the callbacks of the file descriptors that have become ready.
+    #[cfg(all(feature = "std", unix))]
+    fn execute_fds(&self) {
+        let ready = match &*self.fd_watcher.lock() {
+            Some(fd_watcher) => fd_watcher.take_ready(),
+            None => return,
+        };
+        for (fd, readiness) in ready {
+            // The callbacks are not locked while running, so that they can add and remove file
+            // descriptors.
+            let Some(callback) = self.fd_callbacks.lock().get(&fd).cloned() else {
+                continue;
+            };
+            (*callback.lock())(readiness);
+            let still_added = self
+                .fd_callbacks
+                .lock()
+                .get(&fd)
+                .is_some_and(|current| Arc::ptr_eq(current, &callback));
+            if still_added {
+                if let Some(fd_watcher) = &*self.fd_watcher.lock() {
+                    fd_watcher.rearm(fd);
+                }
+            }
+        }
+    }
+
+    /// Waits for the entities of the executor to become ready, and executes their callbacks.
+    ///
+    /// See [`WaitSet::wait`] for the meaning of the `timeout` parameter. If the entities have
+    /// changed since the last call, the wait set is rebuilt first.
+    pub fn spin_once(&self, timeout: Option<Duration>) -> Result<(), RclrsError> {
+        self.handle_commands()?;
+        let mut ready_entities = {
+            let mut wait_set = self.wait_set.lock();
+            if self.changed.swap(false, Ordering::AcqRel) {
+                *wait_set = None;
+            }
+            let wait_set = match &mut *wait_set {
+                Some(wait_set) => wait_set,
+                None => wait_set.insert(self.create_wait_set()?),
+            };
+            let result = wait_set.wait(timeout);
+            // Clear the storage also in the error case, so that the wait set does not keep
+            // removed entities alive until the next call.
+            wait_set.wait_set.clear();
+            result?;
+            core::mem::replace(&mut wait_set.ready_entities, ReadyEntities::new())
+        };
+        #[cfg(feature = "std")]
+        let _current = CurrentGuard::enter_executor(self);
+        // The wait set is not locked while executing, so that callbacks can change the entities.
+        let result = if self.deterministic {
+            let registrations = self.entities.lock().registrations.clone();
+            // Entities that have been removed in the meantime are executed last.
+            ready_entities.execute_sorted_by_key(self.callback_hooks.as_ref(), |id| {
+                registrations.get(&id.address).copied().unwrap_or(u64::MAX)
+            })
+        } else {
+            ready_entities.execute(None, self.callback_hooks.as_ref())
+        };
+        #[cfg(all(feature = "std", unix))]
+        self.execute_fds();
+        ready_entities.clear();
+        if let Some(wait_set) = &mut *self.wait_set.lock() {
+            // Reuse the storage of the list in the next call.
+            wait_set.ready_entities = ready_entities;
+        }
+        result
+    }
+
+    /// Calls [`Executor::spin_once`] in a loop, as long as the context is valid.
+    pub fn spin(&self) -> Result<(), RclrsError> {
+        // SAFETY: The context is valid, since it is co-owned by the executor.
+        while unsafe { context_is_valid(&mut self.context.handle.lock()) } {
+            if let Err(error) = self.spin_once(None) {
+                match error.code {
+                    RclReturnCode::Timeout => continue,
+                    _ => return Err(error),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Delivers the queued items of the loopback to the entities of the executor, and executes
+    // the timers that are due, until neither is left. Entities that are ready at the same time
+    // are executed in the order in which they were added.
+    #[cfg(feature = "testing")]
+    pub(crate) fn spin_loopback_until_idle(&self, loopback: &Loopback) -> Result<(), RclrsError> {
+        #[cfg(feature = "std")]
+        let _current = CurrentGuard::enter_executor(self);
+        let targets = || {
+            let entities = self.entities.lock();
+            LoopbackTargets {
+                subscriptions: entities.subscriptions.clone(),
+                timers: entities.timers.clone(),
+                clients: entities.clients.clone(),
+                services: entities.services.clone(),
+            }
+        };
+        loopback.run_until_idle(targets, |ready| {
+            let registrations = self.entities.lock().registrations.clone();
+            ready.execute_sorted_by_key(self.callback_hooks.as_ref(), |id| {
+                registrations.get(&id.address).copied().unwrap_or(u64::MAX)
+            })
+        })
+    }
+
+    // Returns the time until the next call of the earliest timer that is not canceled.
+    #[cfg(feature = "testing")]
+    pub(crate) fn time_until_next_timer(&self) -> Result<Option<Duration>, RclrsError> {
+        let timers = self.entities.lock().timers.clone();
+        let mut earliest = None;
+        for timer in timers {
+            let mut time_ns = 0;
+            // SAFETY: No preconditions for this function (besides passing in a valid handle).
+            match unsafe {
+                rcl_timer_get_time_until_next_call(&*timer.handle().lock(), &mut time_ns)
+            }
+            .ok()
+            {
+                Ok(()) => {}
+                Err(RclrsError {
+                    code: RclReturnCode::TimerError(TimerErrorCode::TimerCanceled),
+                    ..
+                }) => continue,
+                Err(error) => return Err(error),
+            }
+            let time = Duration::from_nanos(time_ns.max(0) as u64);
+            earliest = Some(earliest.map_or(time, |earliest: Duration| earliest.min(time)));
+        }
+        Ok(earliest)
+    }
+
+    // Handles the commands that were sent through the handles of the executor.
+    fn handle_commands(&self) -> Result<(), RclrsError> {
+        // The handlers are taken out while they run, so that they can create new handles.
+        let mut handlers = core::mem::take(&mut *self.command_handlers.lock());
+        let result = handlers.iter_mut().try_for_each(|handler| handler(self));
+        let mut command_handlers = self.command_handlers.lock();
+        handlers.append(&mut command_handlers);
+        *command_handlers = handlers;
+        result
+    }
+
+    // Changes the entities and wakes up the wait set, so that it is rebuilt.
+    fn change<R>(&self, f: impl FnOnce(&mut ExecutorEntities) -> R) -> Result<R, RclrsError> {
+        let result = f(&mut self.entities.lock());
+        self.changed.store(true, Ordering::Release);
+        self.interrupt.trigger()?;
+        Ok(result)
+    }
+
+    fn create_wait_set(&self) -> Result<ExecutorWaitSet, RclrsError> {
+        let entities = self.entities.lock().clone();
+        let mut guard_conditions = entities.guard_conditions.clone();
+        guard_conditions.push(self.interrupt.clone());
+        Ok(ExecutorWaitSet {
+            wait_set: WaitSet::new(
+                WaitSetCapacities {
+                    subscriptions: entities.subscriptions.len(),
+                    guard_conditions: guard_conditions.len(),
+                    timers: entities.timers.len(),
+                    clients: entities.clients.len(),
+                    services: entities.services.len(),
+                    qos_events: 0,
+                },
+                &self.context,
+            )?,
+            entities: ExecutorEntities {
+                guard_conditions,
+                ..entities
+            },
+            ready_entities: ReadyEntities::new(),
+        })
+    }
+}
+
+/// A handle for sending commands to an [`Executor`] from other threads.
+///
+/// It is created with [`Executor::create_handle`], which also takes the handler that runs the
+/// commands on the thread of the executor. The handle can be cloned, and sent to and shared
+/// between threads, as long as the commands can.
+pub struct ExecutorHandle<C> {
+    commands: Arc<Mutex<VecDeque<C>>>,
+    // The guard condition that wakes up the executor.
+    interrupt: Arc<GuardCondition>,
+}
+
+impl<C> Clone for ExecutorHandle<C> {
+    fn clone(&self) -> Self {
+        Self {
+            commands: Arc::clone(&self.commands),
+            interrupt: Arc::clone(&self.interrupt),
+        }
+    }
+}
+
+impl<C> ExecutorHandle<C> {
+    /// Queues a command for the executor, and wakes it up.
+    ///
+    /// The command is handled even if the executor is only spun later. It is dropped without
+    /// being handled if the executor is dropped first.
+    pub fn send(&self, command: C) -> Result<(), RclrsError> {
+        self.commands.lock().push_back(command);
+        self.interrupt.trigger()
+    }
+}
+
+impl ExecutorWaitSet {
+    fn wait(&mut self, timeout: Option<Duration>) -> Result<(), RclrsError> {
+        let entities = &self.entities;
+        for subscription in &entities.subscriptions {
+            self.wait_set.add_subscription(subscription.clone())?;
+        }
+        for guard_condition in &entities.guard_conditions {
+            self.wait_set.add_guard_condition(guard_condition.clone())?;
+        }
+        for timer in &entities.timers {
+            self.wait_set.add_timer(timer.clone())?;
+        }
+        for client in &entities.clients {
+            self.wait_set.add_client(client.clone())?;
+        }
+        for service in &entities.services {
+            self.wait_set.add_service(service.clone())?;
+        }
+        self.wait_set.wait_into(timeout, &mut self.ready_entities)
+    }
+}
+
+// Removes an entity, identified by its address, from a list of entities.
+fn remove_entity<T: ?Sized, U: ?Sized>(
+    entities: &mut Vec<Arc<T>>,
+    registrations: &mut BTreeMap<usize, u64>,
+    entity: &U,
+) -> bool {
+    let address = entity as *const U as *const ();
+    registrations.remove(&(address as usize));
+    match entities
+        .iter()
+        .position(|e| Arc::as_ptr(e) as *const () == address)
+    {
+        Some(index) => {
+            entities.remove(index);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_executor_handle_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ExecutorHandle<alloc::string::String>>();
+    }
+}
@@ -0,0 +1,270 @@
+use crate::error::{RclReturnCode, RclrsError};
+use crate::logging::log;
+use crate::sync::Mutex;
+use crate::{GuardCondition, LogSeverity};
+
+use std::os::unix::io::RawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// What to wait for on a file descriptor that is added with [`Executor::add_fd`][1].
+///
+/// [1]: crate::Executor::add_fd
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FdInterest {
+    /// Wait until data can be read, e.g. from a socket or a serial port.
+    Readable,
+    /// Wait until data can be written without blocking.
+    Writable,
+    /// Wait until data can be read or written.
+    ReadableOrWritable,
+}
+
+impl FdInterest {
+    fn poll_events(self) -> libc::c_short {
+        match self {
+            Self::Readable => libc::POLLIN,
+            Self::Writable => libc::POLLOUT,
+            Self::ReadableOrWritable => libc::POLLIN | libc::POLLOUT,
+        }
+    }
+}
+
+/// The readiness of a file descriptor, which is passed to its callback, see
+/// [`Executor::add_fd`][1].
+///
+/// [1]: crate::Executor::add_fd
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FdReadiness {
+    /// Data can be read.
+    pub readable: bool,
+    /// Data can be written without blocking.
+    pub writable: bool,
+    /// The file descriptor has an error, was closed by the other side, or is not open.
+    ///
+    /// The file descriptor stays ready in this state, so the callback should usually remove it
+    /// with [`Executor::remove_fd`][1].
+    ///
+    /// [1]: crate::Executor::remove_fd
+    pub error: bool,
+}
+
+impl FdReadiness {
+    fn from_poll_events(revents: libc::c_short) -> Self {
+        Self {
+            readable: revents & libc::POLLIN != 0,
+            writable: revents & libc::POLLOUT != 0,
+            error: revents & (libc::POLLERR | libc::POLLHUP | libc::POLLNVAL) != 0,
+        }
+    }
+}
+
+struct WatchedFd {
+    fd: RawFd,
+    interest: FdInterest,
+    // Whether the file descriptor is polled. It is disarmed while its readiness has not been
+    // handled by the executor yet, since it would stay ready until then.
+    armed: bool,
+}
+
+// The state that is shared between an executor and its watcher thread.
+struct WatcherState {
+    fds: Mutex<Vec<WatchedFd>>,
+    ready: Mutex<Vec<(RawFd, FdReadiness)>>,
+    stop: AtomicBool,
+    // The read end of a pipe that wakes up the watcher thread when the file descriptors change.
+    wake_read: RawFd,
+    wake_write: RawFd,
+}
+
+impl WatcherState {
+    fn wake(&self) {
+        // A full pipe already wakes up the thread, so the result can be ignored.
+        // SAFETY: The pipe is open until the watcher is dropped.
+        unsafe { libc::write(self.wake_write, [0u8].as_ptr() as *const _, 1) };
+    }
+}
+
+/// Polls the external file descriptors of an [`Executor`][1] on a background thread, and wakes
+/// up the executor through a guard condition when one of them is ready.
+///
+/// `rcl` wait sets can only wait for ROS entities, so the file descriptors can not be added to
+/// them directly. The callbacks still run on the thread that spins the executor.
+///
+/// The thread is named `rclrs_fd_watcher`, and is started when the first file descriptor is added
+/// to the executor. It blocks in `poll()` on the armed file descriptors and on the read end of a
+/// pipe, through which the executor wakes it up when the file descriptors change or when it is
+/// dropped. A ready file descriptor is disarmed and handed to the executor, and is only polled
+/// again once its callback has run, so the thread does not spin on level-triggered readiness.
+/// Dropping the watcher stops the thread and waits for it.
+///
+/// If `poll()` fails for another reason than a signal, e.g. when the kernel runs out of memory,
+/// the error is logged with the `rclrs` logger, and the thread stops polling. The callbacks of the
+/// file descriptors are not called anymore after that.
+///
+/// [1]: crate::Executor
+pub(crate) struct FdWatcher {
+    state: Arc<WatcherState>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FdWatcher {
+    pub(crate) fn new(guard_condition: Arc<GuardCondition>) -> Result<Self, RclrsError> {
+        let mut pipe_fds = [0; 2];
+        // SAFETY: The array has room for the two file descriptors.
+        if unsafe { libc::pipe(pipe_fds.as_mut_ptr()) } != 0 {
+            return Err(RclrsError::with_message(
+                RclReturnCode::Error,
+                format!(
+                    "Failed to create the pipe for watching file descriptors: {}",
+                    std::io::Error::last_os_error()
+                ),
+            ));
+        }
+        for fd in pipe_fds {
+            // SAFETY: The file descriptor is open. Non-blocking writes keep a full pipe from
+            // blocking the executor, and non-blocking reads let the thread drain it.
+            unsafe {
+                let flags = libc::fcntl(fd, libc::F_GETFL);
+                libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
+            }
+        }
+        let state = Arc::new(WatcherState {
+            fds: Mutex::new(Vec::new()),
+            ready: Mutex::new(Vec::new()),
+            stop: AtomicBool::new(false),
+            wake_read: pipe_fds[0],
+            wake_write: pipe_fds[1],
+        });
+        let thread_state = Arc::clone(&state);
+        let thread = std::thread::Builder::new()
+            .name("rclrs_fd_watcher".into())
+            .spawn(move || watch(&thread_state, &guard_condition))
+            .map_err(|e| {
+                RclrsError::with_message(
+                    RclReturnCode::Error,
+                    format!("Failed to spawn the thread for watching file descriptors: {e}"),
+                )
+            })?;
+        Ok(Self {
+            state,
+            thread: Some(thread),
+        })
+    }
+
+    pub(crate) fn add(&self, fd: RawFd, interest: FdInterest) {
+        let mut fds = self.state.fds.lock();
+        fds.retain(|watched| watched.fd != fd);
+        fds.push(WatchedFd {
+            fd,
+            interest,
+            armed: true,
+        });
+        drop(fds);
+        self.state.wake();
+    }
+
+    pub(crate) fn remove(&self, fd: RawFd) {
+        self.state.fds.lock().retain(|watched| watched.fd != fd);
+        self.state
+            .ready
+            .lock()
+            .retain(|(ready_fd, _)| *ready_fd != fd);
+        self.state.wake();
+    }
+
+    // Returns the file descriptors that have become ready. They are not polled again until they
+    // are re-armed.
+    pub(crate) fn take_ready(&self) -> Vec<(RawFd, FdReadiness)> {
+        core::mem::take(&mut *self.state.ready.lock())
+    }
+
+    // Polls a file descriptor again, after its readiness has been handled.
+    pub(crate) fn rearm(&self, fd: RawFd) {
+        let mut fds = self.state.fds.lock();
+        if let Some(watched) = fds.iter_mut().find(|watched| watched.fd == fd) {
+            watched.armed = true;
+            drop(fds);
+            self.state.wake();
+        }
+    }
+}
+
+impl Drop for FdWatcher {
+    fn drop(&mut self) {
+        self.state.stop.store(true, Ordering::Release);
+        self.state.wake();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        // SAFETY: The thread has exited, so nothing uses the pipe anymore.
+        unsafe {
+            libc::close(self.state.wake_read);
+            libc::close(self.state.wake_write);
+        }
+    }
+}
+
+// The loop of the watcher thread.
+fn watch(state: &WatcherState, guard_condition: &GuardCondition) {
+    let mut poll_fds = Vec::new();
+    while !state.stop.load(Ordering::Acquire) {
+        poll_fds.clear();
+        poll_fds.push(libc::pollfd {
+            fd: state.wake_read,
+            events: libc::POLLIN,
+            revents: 0,
+        });
+        poll_fds.extend(
+            state
+                .fds
+                .lock()
+                .iter()
+                .filter(|watched| watched.armed)
+                .map(|watched| libc::pollfd {
+                    fd: watched.fd,
+                    events: watched.interest.poll_events(),
+                    revents: 0,
+                }),
+        );
+        // SAFETY: The array is valid for its length.
+        let ret = unsafe { libc::poll(poll_fds.as_mut_ptr(), poll_fds.len() as libc::nfds_t, -1) };
+        if ret < 0 {
+            if std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            log(
+                "rclrs",
+                LogSeverity::Error,
+                &format!(
+                    "Polling the external file descriptors of an executor failed, so they are not \
+                     watched anymore: {}",
+                    std::io::Error::last_os_error()
+                ),
+            );
+            return;
+        }
+        if poll_fds[0].revents != 0 {
+            let mut buffer = [0u8; 64];
+            // SAFETY: The buffer is valid for its length. The pipe is non-blocking.
+            while unsafe { libc::read(state.wake_read, buffer.as_mut_ptr() as *mut _, 64) } > 0 {}
+        }
+        let mut woke_executor = false;
+        let mut fds = state.fds.lock();
+        let mut ready = state.ready.lock();
+        for poll_fd in poll_fds[1..].iter().filter(|poll_fd| poll_fd.revents != 0) {
+            // The file descriptor may have been removed while polling.
+            if let Some(watched) = fds.iter_mut().find(|watched| watched.fd == poll_fd.fd) {
+                watched.armed = false;
+                ready.push((poll_fd.fd, FdReadiness::from_poll_events(poll_fd.revents)));
+                woke_executor = true;
+            }
+        }
+        drop(ready);
+        drop(fds);
+        if woke_executor {
+            let _ = guard_condition.trigger();
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! Injection of failures into publishers, subscriptions and clients, for testing how an
+//! application handles errors.
+//!
+//! Errors such as a failed publish or an unavailable service are hard to provoke through the
+//! middleware. With the `fault_injection` feature, a test can instead register a [`Fault`] with
+//! [`inject`], which makes `rclrs` fail the matching operations itself:
+//! - [`FaultKind::Publish`] fails [`Publisher::publish`][1] and the other publish functions.
+//! - [`FaultKind::Take`] fails [`Subscription::take`][2], also when the subscription is executed
+//!   by a spin function.
+//! - [`FaultKind::SendRequest`] fails [`Client::async_send_request_with_callback`][3] and
+//!   [`Client::call_async`][4].
+//! - [`FaultKind::ServiceUnavailable`] makes [`Client::service_is_ready`][5] return `false`.
+//!
+//! Faults are global, so they also affect other tests that run in the same process at the same
+//! time. Tests that inject faults should therefore use names that no other test uses.
+//!
+//! # Example
+//! ```ignore
+//! # use rclrs::{Context, RclReturnCode, RclrsError, QOS_PROFILE_DEFAULT};
+//! use rclrs::fault_injection::{inject, Fault};
+//! let context = Context::new([])?;
+//! let mut node = context.create_node("test_node")?;
+//! let publisher = node.create_publisher::<std_msgs::msg::String>("chatter", QOS_PROFILE_DEFAULT)?;
+//! let fault = inject(Fault::publish("/chatter").times(2));
+//! let message = std_msgs::msg::String::default();
+//! assert_eq!(publisher.publish(&message).unwrap_err().code, RclReturnCode::Error);
+//! assert!(publisher.publish(&message).is_err());
+//! assert!(publisher.publish(&message).is_ok());
+//! assert_eq!(fault.triggered(), 2);
+//! # Ok::<(), RclrsError>(())
+//! ```
+//!
+//! [1]: crate::Publisher::publish
+//! [2]: crate::Subscription::take
+//! [3]: crate::Client::async_send_request_with_callback
+//! [4]: crate::Client::call_async
+//! [5]: crate::Client::service_is_ready
+
+use crate::sync::Mutex;
+use crate::{RclReturnCode, RclrsError};
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ffi::{c_char, CStr};
+
+/// The operation that a [`Fault`] makes fail.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FaultKind {
+    /// Publishing a message returns the error of the fault.
+    Publish,
+    /// Taking a message returns the error of the fault.
+    Take,
+    /// Sending a request returns the error of the fault.
+    SendRequest,
+    /// The service server is reported as not available.
+    ServiceUnavailable,
+}
+
+/// A failure that is injected into the operations on a topic or service, see [`inject`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fault {
+    /// The operation that fails.
+    pub kind: FaultKind,
+    /// The fully qualified name of the topic or service, i.e. after remapping, e.g. `/chatter`.
+    /// With `None`, the operation fails on all topics or services.
+    pub name: Option<String>,
+    /// The error code that is returned, which is ignored for
+    /// [`FaultKind::ServiceUnavailable`].
+    pub code: RclReturnCode,
+    /// How many times the operation fails, or `None` to fail until the fault is removed.
+    pub count: Option<usize>,
+}
+
+impl Fault {
+    /// Creates a fault that fails the given operation on the topic or service until it is
+    /// removed, with an unspecified [`RclReturnCode::Error`].
+    ///
+    /// Like the errors that the RMW implementation returns when the transport fails, this error
+    /// is [transient][1].
+    ///
+    /// [1]: crate::RclReturnCode::is_transient
+    pub fn new(kind: FaultKind, name: impl Into<String>) -> Self {
+        Self {
+            kind,
+            name: Some(name.into()),
+            code: RclReturnCode::Error,
+            count: None,
+        }
+    }
+
+    /// Creates a fault that fails publishing on the topic.
+    pub fn publish(topic: impl Into<String>) -> Self {
+        Self::new(FaultKind::Publish, topic)
+    }
+
+    /// Creates a fault that fails taking messages from the topic.
+    pub fn take(topic: impl Into<String>) -> Self {
+        Self::new(FaultKind::Take, topic)
+    }
+
+    /// Creates a fault that fails sending requests to the service.
+    pub fn send_request(service: impl Into<String>) -> Self {
+        Self::new(FaultKind::SendRequest, service)
+    }
+
+    /// Creates a fault that makes the service appear unavailable to its clients.
+    pub fn service_unavailable(service: impl Into<String>) -> Self {
+        Self::new(FaultKind::ServiceUnavailable, service)
+    }
+
+    /// Makes the fault apply to all topics or services.
+    pub fn on_all_names(mut self) -> Self {
+        self.name = None;
+        self
+    }
+
+    /// Sets the error code that is returned.
+    pub fn with_code(mut self, code: RclReturnCode) -> Self {
+        self.code = code;
+        self
+    }
+
+    /// Makes the operation fail only the given number of times, and succeed afterwards.
+    pub fn times(mut self, count: usize) -> Self {
+        self.count = Some(count);
+        self
+    }
+}
+
+struct InjectedFault {
+    id: u64,
+    fault: Fault,
+    triggered: usize,
+}
+
+struct Registry {
+    next_id: u64,
+    faults: Vec<InjectedFault>,
+}
+
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    next_id: 0,
+    faults: Vec::new(),
+});
+
+/// Registers a fault, which is active until the returned guard is dropped.
+///
+/// When several faults match an operation, the one that was injected first is used.
+pub fn inject(fault: Fault) -> FaultGuard {
+    let registry = &mut *REGISTRY.lock();
+    let id = registry.next_id;
+    registry.next_id += 1;
+    registry.faults.push(InjectedFault {
+        id,
+        fault,
+        triggered: 0,
+    });
+    FaultGuard { id }
+}
+
+/// Removes all faults, including those whose guards still exist.
+pub fn clear_faults() {
+    REGISTRY.lock().faults.clear();
+}
+
+/// Keeps a fault active, and removes it when dropped.
+#[must_use = "the fault is removed when the guard is dropped"]
+pub struct FaultGuard {
+    id: u64,
+}
+
+impl FaultGuard {
+    /// Returns how many operations the fault has made fail so far.
+    pub fn triggered(&self) -> usize {
+        REGISTRY
+            .lock()
+            .faults
+            .iter()
+            .find(|injected| injected.id == self.id)
+            .map_or(0, |injected| injected.triggered)
+    }
+}
+
+impl Drop for FaultGuard {
+    fn drop(&mut self) {
+        REGISTRY
+            .lock()
+            .faults
+            .retain(|injected| injected.id != self.id);
+    }
+}
+
+/// Returns the error of the first active fault of this kind for the name, and counts it.
+///
+/// The name is only looked up when there is a fault of this kind, so that the operations are not
+/// slowed down otherwise.
+pub(crate) fn check(kind: FaultKind, name: impl FnOnce() -> String) -> Result<(), RclrsError> {
+    let registry = &mut *REGISTRY.lock();
+    if !registry
+        .faults
+        .iter()
+        .any(|injected| injected.fault.kind == kind)
+    {
+        return Ok(());
+    }
+    let name = name();
+    let injected = registry.faults.iter_mut().find(|injected| {
+        injected.fault.kind == kind
+            && injected.fault.name.as_ref().is_none_or(|n| *n == name)
+            && injected
+                .fault
+                .count
+                .is_none_or(|count| injected.triggered < count)
+    });
+    let Some(injected) = injected else {
+        return Ok(());
+    };
+    injected.triggered += 1;
+    Err(RclrsError::with_message(
+        injected.fault.code,
+        format!("Injected {kind:?} fault on '{name}'"),
+    ))
+}
+
+/// Converts the name returned by an `rcl` getter, which is null for invalid entities.
+///
+/// # Safety
+/// The pointer must be null or point to a valid string.
+pub(crate) unsafe fn name_from_ptr(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    CStr::from_ptr(ptr).to_string_lossy().into_owned()
+}
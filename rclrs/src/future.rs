@@ -0,0 +1,471 @@
+use crate::sync::Mutex;
+use crate::{Clock, GuardCondition, RclReturnCode, RclrsError, Time};
+
+use alloc::sync::Arc;
+use alloc::task::Wake;
+use alloc::vec::Vec;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::cmp::Ordering;
+#[cfg(feature = "std")]
+use std::collections::BinaryHeap;
+#[cfg(feature = "std")]
+use std::sync::{Condvar, OnceLock, Weak};
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+/// A value that will be available later, e.g. the response to a service request.
+///
+/// This implements the standard [`Future`] trait, so it can be `.await`ed on an async runtime,
+/// e.g. together with [`spin_async`][1]. Without an async runtime, use
+/// [`spin_until_future_complete`][2].
+///
+/// [1]: crate::spin_async
+/// [2]: crate::spin_until_future_complete
+pub struct RclFuture<T> {
+    state: Arc<Mutex<FutureState<T>>>,
+}
+
+/// The sending half of an [`RclFuture`].
+pub(crate) struct Promise<T> {
+    state: Arc<Mutex<FutureState<T>>>,
+}
+
+struct FutureState<T> {
+    value: Option<T>,
+    waker: Option<Waker>,
+}
+
+/// Creates a future together with the promise that completes it.
+pub(crate) fn promise<T>() -> (Promise<T>, RclFuture<T>) {
+    let state = Arc::new(Mutex::new(FutureState {
+        value: None,
+        waker: None,
+    }));
+    (
+        Promise {
+            state: state.clone(),
+        },
+        RclFuture { state },
+    )
+}
+
+impl<T> Promise<T> {
+    /// Completes the future, and wakes up the task waiting on it, if any.
+    pub(crate) fn set(self, value: T) {
+        let waker = {
+            let mut state = self.state.lock();
+            state.value = Some(value);
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> RclFuture<T> {
+    /// Returns true if the value is available.
+    pub fn is_ready(&self) -> bool {
+        self.state.lock().value.is_some()
+    }
+}
+
+impl<T> Future for RclFuture<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock();
+        match state.value.take() {
+            Some(value) => Poll::Ready(value),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Combinators for futures, which make it possible to compose several futures and wait for them
+/// with a single [`spin_until_future_complete`][1], without an async runtime.
+///
+/// This is implemented for all futures that are [`Unpin`], such as [`RclFuture`].
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::{Clock, ClockType, RclFutureExt, RclrsError};
+/// # use std::time::Duration;
+/// let clock = Clock::new(ClockType::SteadyTime)?;
+/// let futures = (0..10)
+///     .map(|a| client.call_async(&AddTwoInts_Request { a, b: 1 }))
+///     .collect::<Result<Vec<_>, _>>()?;
+/// let mut future = rclrs::join_all(futures)
+///     .map(|responses| responses.iter().map(|response| response.sum).sum::<i64>())
+///     .with_timeout(&clock, Duration::from_secs(1))?;
+/// let total = rclrs::spin_until_future_complete(&node, &mut future, None)??;
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::spin_until_future_complete
+pub trait RclFutureExt: Future + Unpin + Sized {
+    /// Transforms the output of the future with a function.
+    fn map<F, U>(self, f: F) -> Map<Self, F>
+    where
+        F: FnOnce(Self::Output) -> U,
+    {
+        Map {
+            future: self,
+            f: Some(f),
+        }
+    }
+
+    /// Makes the future fail with a [`Timeout`][1] error if it is not complete after `timeout`
+    /// has elapsed on the given clock.
+    ///
+    /// The timeout starts now, not when the future is first polled.
+    ///
+    /// With the `std` feature, the task waiting for the future is woken up when the timeout has
+    /// elapsed, by a thread that is shared by all these futures. The remaining time is measured
+    /// on the steady clock of the system, so for a clock with simulated time, the task is woken
+    /// up after the corresponding real time, at which point the timeout is checked again. Without
+    /// `std`, the future must be polled regularly for the timeout to be noticed, e.g. by passing
+    /// a timeout to [`spin_until_future_complete`][2].
+    ///
+    /// Returns an error if the current time of the clock can not be read, or if the deadline can
+    /// not be represented.
+    ///
+    /// [1]: crate::RclReturnCode::Timeout
+    /// [2]: crate::spin_until_future_complete
+    fn with_timeout(
+        self,
+        clock: &Clock,
+        timeout: Duration,
+    ) -> Result<WithTimeout<Self>, RclrsError> {
+        let deadline = clock.now()?.checked_add(timeout).ok_or(RclrsError {
+            code: RclReturnCode::InvalidArgument,
+            msg: None,
+        })?;
+        Ok(WithTimeout {
+            future: self,
+            clock: clock.clone(),
+            deadline,
+            #[cfg(feature = "std")]
+            alarm: None,
+        })
+    }
+}
+
+impl<T: Future + Unpin> RclFutureExt for T {}
+
+/// Future returned by [`RclFutureExt::map`].
+pub struct Map<Fut, F> {
+    future: Fut,
+    f: Option<F>,
+}
+
+// The function is never pinned, only the future is.
+impl<Fut: Unpin, F> Unpin for Map<Fut, F> {}
+
+impl<Fut, F, U> Future for Map<Fut, F>
+where
+    Fut: Future + Unpin,
+    F: FnOnce(Fut::Output) -> U,
+{
+    type Output = U;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        match Pin::new(&mut this.future).poll(cx) {
+            Poll::Ready(output) => {
+                let f = this.f.take().expect("Map polled after completion");
+                Poll::Ready(f(output))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Future returned by [`RclFutureExt::with_timeout`].
+pub struct WithTimeout<Fut> {
+    pub(crate) future: Fut,
+    clock: Clock,
+    deadline: Time,
+    // The waker of the task, shared with the alarm thread that wakes it up at the deadline.
+    #[cfg(feature = "std")]
+    alarm: Option<Arc<Mutex<AlarmState>>>,
+}
+
+impl<Fut> WithTimeout<Fut> {
+    #[cfg(feature = "std")]
+    fn wake_at_deadline(&mut self, now: Time, waker: &Waker) {
+        if let Some(alarm) = &self.alarm {
+            let alarm = &mut *alarm.lock();
+            if !alarm.fired {
+                alarm.waker.clone_from(waker);
+                return;
+            }
+        }
+        // The alarm has fired before the deadline was reached on the clock, e.g. because
+        // simulated time runs slower than real time, so the remaining time needs another alarm.
+        let alarm = Arc::new(Mutex::new(AlarmState {
+            waker: waker.clone(),
+            fired: false,
+        }));
+        let remaining = self.deadline.saturating_duration_since(now);
+        AlarmThread::get().add(Alarm {
+            deadline: Instant::now() + remaining,
+            state: Arc::downgrade(&alarm),
+        });
+        self.alarm = Some(alarm);
+    }
+}
+
+// The thread that wakes up the tasks of all `WithTimeout` futures at their deadlines, so that
+// waiting for a timeout does not need a thread per future.
+#[cfg(feature = "std")]
+struct AlarmThread {
+    // The pending alarms, with the earliest deadline on top.
+    alarms: std::sync::Mutex<BinaryHeap<Alarm>>,
+    // Notified when an alarm has been added, since it may be earlier than the others.
+    added: Condvar,
+}
+
+#[cfg(feature = "std")]
+struct Alarm {
+    deadline: Instant,
+    // Alarms of futures that have been dropped are skipped.
+    state: Weak<Mutex<AlarmState>>,
+}
+
+// The part of an alarm that is shared between its future and the alarm thread.
+#[cfg(feature = "std")]
+struct AlarmState {
+    waker: Waker,
+    // Set when the alarm thread has woken up the task, after which the alarm is used up.
+    fired: bool,
+}
+
+#[cfg(feature = "std")]
+impl AlarmThread {
+    // Returns the alarm thread, and starts it on the first call.
+    fn get() -> &'static Self {
+        static ALARM_THREAD: OnceLock<AlarmThread> = OnceLock::new();
+        ALARM_THREAD.get_or_init(|| {
+            std::thread::Builder::new()
+                .name("rclrs_alarms".into())
+                .spawn(|| Self::get().run())
+                .expect("Could not start the alarm thread");
+            Self {
+                alarms: std::sync::Mutex::new(BinaryHeap::new()),
+                added: Condvar::new(),
+            }
+        })
+    }
+
+    fn add(&self, alarm: Alarm) {
+        self.alarms.lock().unwrap().push(alarm);
+        self.added.notify_one();
+    }
+
+    fn run(&self) {
+        let mut due = Vec::new();
+        let mut alarms = self.alarms.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            while alarms.peek().is_some_and(|alarm| alarm.deadline <= now) {
+                due.extend(alarms.pop().and_then(|alarm| alarm.state.upgrade()));
+            }
+            // The tasks are woken up without holding the lock, since waking up may run code of
+            // the async runtime.
+            drop(alarms);
+            for state in due.drain(..) {
+                let waker = {
+                    let state = &mut *state.lock();
+                    state.fired = true;
+                    state.waker.clone()
+                };
+                waker.wake();
+            }
+            alarms = self.alarms.lock().unwrap();
+            alarms = match alarms.peek() {
+                Some(alarm) => {
+                    let timeout = alarm.deadline.saturating_duration_since(Instant::now());
+                    self.added.wait_timeout(alarms, timeout).unwrap().0
+                }
+                None => self.added.wait(alarms).unwrap(),
+            };
+        }
+    }
+}
+
+// Alarms are ordered by their deadlines, in reverse, so that the heap returns the earliest one.
+#[cfg(feature = "std")]
+impl Ord for Alarm {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.deadline.cmp(&self.deadline)
+    }
+}
+
+#[cfg(feature = "std")]
+impl PartialOrd for Alarm {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(feature = "std")]
+impl PartialEq for Alarm {
+    fn eq(&self, other: &Self) -> bool {
+        self.deadline == other.deadline
+    }
+}
+
+#[cfg(feature = "std")]
+impl Eq for Alarm {}
+
+impl<Fut> Future for WithTimeout<Fut>
+where
+    Fut: Future + Unpin,
+{
+    type Output = Result<Fut::Output, RclrsError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if let Poll::Ready(output) = Pin::new(&mut this.future).poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+        let now = match this.clock.now() {
+            Ok(now) => now,
+            Err(error) => return Poll::Ready(Err(error)),
+        };
+        if now.nsec >= this.deadline.nsec {
+            return Poll::Ready(Err(RclrsError {
+                code: RclReturnCode::Timeout,
+                msg: None,
+            }));
+        }
+        #[cfg(feature = "std")]
+        this.wake_at_deadline(now, cx.waker());
+        Poll::Pending
+    }
+}
+
+/// Creates a future that completes when all of the given futures are complete.
+///
+/// The outputs are returned in the order of the futures.
+pub fn join_all<I>(futures: I) -> JoinAll<I::Item>
+where
+    I: IntoIterator,
+    I::Item: Future + Unpin,
+{
+    JoinAll {
+        futures: futures.into_iter().map(MaybeDone::Pending).collect(),
+    }
+}
+
+/// Future returned by [`join_all`].
+pub struct JoinAll<Fut: Future> {
+    futures: Vec<MaybeDone<Fut>>,
+}
+
+// The outputs are never pinned, only the futures are.
+impl<Fut: Future + Unpin> Unpin for JoinAll<Fut> {}
+
+enum MaybeDone<Fut: Future> {
+    Pending(Fut),
+    Done(Fut::Output),
+    Taken,
+}
+
+impl<Fut> Future for JoinAll<Fut>
+where
+    Fut: Future + Unpin,
+{
+    type Output = Vec<Fut::Output>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let mut all_done = true;
+        for maybe_done in &mut this.futures {
+            if let MaybeDone::Pending(future) = maybe_done {
+                match Pin::new(future).poll(cx) {
+                    Poll::Ready(output) => *maybe_done = MaybeDone::Done(output),
+                    Poll::Pending => all_done = false,
+                }
+            }
+        }
+        if !all_done {
+            return Poll::Pending;
+        }
+        let outputs = this
+            .futures
+            .iter_mut()
+            .map(
+                |maybe_done| match core::mem::replace(maybe_done, MaybeDone::Taken) {
+                    MaybeDone::Done(output) => output,
+                    _ => panic!("JoinAll polled after completion"),
+                },
+            )
+            .collect();
+        Poll::Ready(outputs)
+    }
+}
+
+/// A waker that triggers a guard condition, to wake up the wait set it has been added to.
+pub(crate) struct GuardConditionWaker(pub(crate) Arc<GuardCondition>);
+
+impl Wake for GuardConditionWaker {
+    fn wake(self: Arc<Self>) {
+        // A failure can't be reported from here, and at worst delays the task until the next
+        // wakeup of the wait set.
+        let _ = self.0.trigger();
+    }
+}
+
+#[cfg(all(test, feature = "std", feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::testing::ManualClock;
+    use crate::Context as RclContext;
+    use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+    // Counts how often the task has been woken up.
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, AtomicOrdering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_timeout_is_checked_again_when_simulated_time_is_slower() {
+        let context = RclContext::new([]).unwrap();
+        let node = context.create_node("test_with_timeout").unwrap();
+        let clock = ManualClock::new(node.get_clock()).unwrap();
+        let (_promise, future) = promise::<()>();
+        let mut future = future
+            .with_timeout(&clock.clock(), Duration::from_millis(20))
+            .unwrap();
+        let wakes = Arc::new(CountingWaker::default());
+        let waker = Waker::from(Arc::clone(&wakes));
+        let mut cx = Context::from_waker(&waker);
+
+        // The simulated time stands still, so each alarm fires before the deadline is reached.
+        for expected_wakes in 1..=2 {
+            assert!(Pin::new(&mut future).poll(&mut cx).is_pending());
+            std::thread::sleep(Duration::from_millis(100));
+            assert_eq!(wakes.0.load(AtomicOrdering::SeqCst), expected_wakes);
+        }
+
+        clock.advance(Duration::from_millis(20)).unwrap();
+        let Poll::Ready(Err(error)) = Pin::new(&mut future).poll(&mut cx) else {
+            panic!("The future did not time out");
+        };
+        assert_eq!(error.code, RclReturnCode::Timeout);
+    }
+}
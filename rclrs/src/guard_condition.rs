@@ -0,0 +1,95 @@
+use crate::allocator::copy_rcutils_allocator;
+use crate::error::{NodeErrorCode, RclReturnCode, RclrsError, ToResult};
+use crate::rcl_bindings::*;
+use crate::sync::{Mutex, MutexGuard};
+use crate::{Context, ContextHandle, NodeHandle};
+
+use alloc::sync::Arc;
+
+// SAFETY: The functions accessing this type, including drop(), are thread-safe when called on
+// different guard conditions, and the handle is only accessed through a mutex.
+unsafe impl Send for rcl_guard_condition_t {}
+
+/// A waitable entity that is triggered manually, e.g. to wake up a thread blocked in
+/// [`WaitSet::wait`][1] from another thread.
+///
+/// A guard condition is ready once it has been triggered, and stays ready until the wait set it
+/// was added to has been waited on.
+///
+/// [1]: crate::WaitSet::wait
+pub struct GuardCondition {
+    handle: Mutex<rcl_guard_condition_t>,
+    // Used to ensure the context is alive while the guard condition is alive.
+    _context_handle: Arc<ContextHandle>,
+    // False for the graph guard condition of a node, which is finalized together with the node.
+    owned: bool,
+}
+
+impl Drop for GuardCondition {
+    fn drop(&mut self) {
+        if !self.owned {
+            return;
+        }
+        // SAFETY: No preconditions for this function (besides passing in a valid handle).
+        unsafe {
+            rcl_guard_condition_fini(self.handle.get_mut());
+        }
+    }
+}
+
+impl GuardCondition {
+    /// Creates a new guard condition.
+    pub fn new(context: &Context) -> Result<Self, RclrsError> {
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut handle = unsafe { rcl_get_zero_initialized_guard_condition() };
+        unsafe {
+            // SAFETY: No preconditions for this function.
+            let mut options = rcl_guard_condition_get_default_options();
+            options.allocator = copy_rcutils_allocator(&context.allocator);
+            // SAFETY: The guard condition is zero-initialized as expected by this function.
+            // The context is kept alive because it is co-owned by the guard condition.
+            rcl_guard_condition_init(&mut handle, &mut *context.handle.lock(), options).ok()?;
+        }
+        Ok(Self {
+            handle: Mutex::new(handle),
+            _context_handle: context.handle.clone(),
+            owned: true,
+        })
+    }
+
+    /// Returns the guard condition of a node that is triggered when the ROS graph changes, e.g.
+    /// when a service server appears.
+    ///
+    /// It must only be used while the node is alive, i.e. by the node itself and by wait sets
+    /// that the node is spun with.
+    pub(crate) fn graph(
+        node_handle: &NodeHandle,
+        context_handle: &Arc<ContextHandle>,
+    ) -> Result<Self, RclrsError> {
+        // SAFETY: The guard condition is owned by the node, which outlives the returned guard
+        // condition. Its struct is copied, and the state it refers to stays valid.
+        let handle = unsafe { rcl_node_get_graph_guard_condition(&*node_handle.lock()).as_ref() }
+            .map(|handle| unsafe { core::ptr::read(handle) })
+            .ok_or(RclrsError {
+                code: RclReturnCode::NodeError(NodeErrorCode::NodeInvalid),
+                msg: None,
+            })?;
+        Ok(Self {
+            handle: Mutex::new(handle),
+            _context_handle: context_handle.clone(),
+            owned: false,
+        })
+    }
+
+    /// Triggers the guard condition, which wakes up the wait sets it has been added to.
+    ///
+    /// This may be called from any thread.
+    pub fn trigger(&self) -> Result<(), RclrsError> {
+        // SAFETY: No preconditions for this function (besides passing in a valid handle).
+        unsafe { rcl_trigger_guard_condition(&mut *self.handle.lock()) }.ok()
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<rcl_guard_condition_t> {
+        self.handle.lock()
+    }
+}
@@ -1,28 +1,154 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs)]
 //! Rust client library for ROS 2.
 //!
 //! For getting started, see the [README][1].
 //!
+//! # Features
+//! - `std` (enabled by default): Disabling this feature makes `rclrs` a `no_std` crate that only
+//!   requires `alloc`, for targets such as micro-ROS-class platforms.
+//! - `tracetools`: Emits the [ros2_tracing][2] tracepoints that `rclcpp` emits, e.g. for
+//!   publishing and for callbacks, so that Rust nodes can be analyzed with `tracetools_analysis`.
+//! - `chrono`: Adds conversions between [`Time`] and `chrono::DateTime`.
+//! - `dyn_msg`: Adds [`DynamicMessage`]s, whose type is only known at runtime, together with
+//!   [`DynamicSubscription`], [`TopicEcho`] and [`log_message!`]. Requires `std`.
+//! - `yaml`: Adds parsing of [`QoSProfile`]s from YAML, in the format of rosbag2's QoS override
+//!   files, and snapshots of parameters with [`Node::export_parameters_to_yaml`]. Requires `std`.
+//! - `rosbag`: Adds the [`Recorder`] and the [`Player`] for rosbag2 bags. Requires `dyn_msg`
+//!   and `yaml`.
+//! - `fault_injection`: Adds the [`fault_injection`] module, which makes publishers,
+//!   subscriptions and clients fail on demand, for testing the error handling of applications.
+//! - `testing`: Adds the [`testing`] module, with utilities for testing callbacks
+//!   deterministically, such as the in-process [`Loopback`][3] transport. Without this feature,
+//!   publishers, subscriptions, clients and services always use the middleware.
+//!
+//! These features use messages of other interface packages, which must be installed, since
+//! `rclrs` links to their C type support:
+//! - `statistics_msgs`: Adds [`NodeOptions::statistics`], which publishes statistics about the
+//!   callbacks of a node. Requires `std`.
+//! - `std_msgs`: Adds the [`ChunkedPublisher`] and [`ChunkedSubscription`] for large messages,
+//!   and [`TopicService`]s, which use `std_msgs/msg/UInt8MultiArray`.
+//! - `std_srvs`: Adds the [`TopicGate`] and [`Player::create_services`], which use
+//!   `std_srvs/srv/Trigger`. Together with `statistics_msgs`, this also adds the
+//!   [`ClockSyncMonitor`].
+//!
 //! [1]: https://github.com/ros2-rust/ros2_rust/blob/master/README.md
+//! [2]: https://github.com/ros2/ros2_tracing
+//! [3]: testing::Loopback
 
+extern crate alloc;
+
+mod allocator;
+#[cfg(feature = "std")]
+mod background;
+mod callback_hooks;
+#[cfg(feature = "std")]
+mod callback_watchdog;
+#[cfg(feature = "std")]
+mod channel;
+mod clock;
 mod context;
+#[cfg(feature = "std")]
+mod current;
+mod distro;
+#[cfg(feature = "dyn_msg")]
+mod dynamic_message;
 mod error;
+mod executor;
+#[cfg(all(feature = "std", unix))]
+mod external_fd;
+#[cfg(feature = "fault_injection")]
+pub mod fault_injection;
+mod future;
+mod guard_condition;
+mod logging;
 mod node;
+mod parameter;
 mod qos;
+mod qos_overriding;
+#[cfg(feature = "yaml")]
+mod qos_yaml;
+#[cfg(feature = "rosbag")]
+mod rosbag;
+mod rosidl_macros;
+mod sensor_buffers;
+#[cfg(feature = "std")]
+mod spin_async;
+mod sync;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod time;
+mod time_cache;
+#[cfg(feature = "dyn_msg")]
+mod topic_echo;
+#[cfg(feature = "std_srvs")]
+mod topic_gate;
+mod tracetools;
+#[cfg(feature = "std")]
+mod transforms;
 mod wait;
 
-mod rcl_bindings;
+/// The raw `rcl` and `rmw` bindings, for use together with the `raw_handle()` functions, e.g.
+/// [`Node::raw_handle`], and the `from_raw()` functions for embedding `rclrs` into C and C++
+/// processes, e.g. [`Node::from_raw`].
+///
+/// These are generated from the C headers of the ROS distribution that `rclrs` is built against,
+/// so they are not covered by the semver guarantees of `rclrs`.
+pub mod rcl_bindings;
+pub mod rcl_interfaces;
 
+pub use allocator::*;
+#[cfg(feature = "std")]
+pub use background::*;
+pub use callback_hooks::*;
+#[cfg(feature = "std")]
+pub use callback_watchdog::*;
+#[cfg(feature = "std")]
+pub use channel::*;
+pub use clock::*;
 pub use context::*;
+#[cfg(feature = "std")]
+pub use current::{with_current_executor, with_current_node, with_current_trace_context};
+pub use distro::ROS_DISTRO;
+#[cfg(feature = "dyn_msg")]
+pub use dynamic_message::*;
 pub use error::*;
+pub use executor::*;
+#[cfg(all(feature = "std", unix))]
+pub use external_fd::*;
+pub use future::*;
+pub use guard_condition::*;
+pub use logging::*;
 pub use node::*;
+pub use parameter::*;
 pub use qos::*;
+pub use qos_overriding::*;
+#[cfg(feature = "rosbag")]
+pub use rosbag::*;
+pub use sensor_buffers::*;
+#[cfg(feature = "std")]
+pub use spin_async::*;
+pub use time::*;
+pub use time_cache::*;
+#[cfg(feature = "dyn_msg")]
+pub use topic_echo::*;
+#[cfg(feature = "std_srvs")]
+pub use topic_gate::*;
+#[cfg(feature = "std")]
+pub use transforms::*;
 pub use wait::*;
 
-use rcl_bindings::rcl_context_is_valid;
-use std::time::Duration;
+pub use rosidl_runtime_rs::{Stamped, TfMessage, TransformMessage};
 
-/// Polls the node for new messages and executes the corresponding callbacks.
+use alloc::sync::Arc;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{self, Poll, RawWaker, RawWakerVTable, Waker};
+use core::time::Duration;
+use distro::context_is_valid;
+use rosidl_runtime_rs::Message;
+
+/// Polls the node for new messages and ready timers, and executes the corresponding callbacks.
 ///
 /// See [`WaitSet::wait`] for the meaning of the `timeout` parameter.
 ///
@@ -32,38 +158,95 @@ use std::time::Duration;
 ///
 /// [1]: crate::SubscriberErrorCode
 pub fn spin_once(node: &Node, timeout: Option<Duration>) -> Result<(), RclrsError> {
+    if let Some(static_memory) = &node.static_memory {
+        return static_memory.lock().wait_set.spin_once(node, timeout);
+    }
+
     let live_subscriptions = node.live_subscriptions();
-    let ctx = Context {
-        handle: node.context.clone(),
+    let live_timers = node.live_timers();
+    let live_clients = node.live_clients();
+    let live_services = node.live_services();
+    let live_qos_events = node.live_qos_events();
+    let guard_conditions = node.guard_conditions();
+    let capacities = WaitSetCapacities {
+        subscriptions: live_subscriptions.len(),
+        guard_conditions: guard_conditions.len(),
+        timers: live_timers.len(),
+        clients: live_clients.len(),
+        services: live_services.len(),
+        qos_events: live_qos_events.len(),
     };
-    let mut wait_set = WaitSet::new(live_subscriptions.len(), &ctx)?;
+    let mut wait_set = WaitSet::new(capacities, &node.get_context())?;
 
     for live_subscription in &live_subscriptions {
         wait_set.add_subscription(live_subscription.clone())?;
     }
 
-    let ready_entities = wait_set.wait(timeout)?;
-    for ready_subscription in ready_entities.subscriptions {
-        ready_subscription.execute()?;
+    for guard_condition in guard_conditions {
+        wait_set.add_guard_condition(guard_condition)?;
     }
 
-    Ok(())
+    for live_timer in &live_timers {
+        wait_set.add_timer(live_timer.clone())?;
+    }
+
+    for live_client in &live_clients {
+        wait_set.add_client(live_client.clone())?;
+    }
+
+    for live_service in &live_services {
+        wait_set.add_service(live_service.clone())?;
+    }
+
+    for live_qos_event in &live_qos_events {
+        wait_set.add_qos_event(live_qos_event.clone())?;
+    }
+
+    let ready_entities = wait_set.wait(timeout)?;
+    ready_entities.execute(Some(node), node.callback_hooks.as_ref())
 }
 
 /// Convenience function for calling [`spin_once`] in a loop.
 ///
 /// This function additionally checks that the context is still valid.
+///
+/// Unlike repeated calls to [`spin_once`], this creates the wait set only once, so that no
+/// allocations happen between wakeups. On the path from a wakeup to a subscription callback, each
+/// ready subscription then takes its handle mutex twice (once when it is added to the wait set,
+/// once when taking the message) and its callback mutex once. None of these are contended unless
+/// the same subscription is used concurrently from another thread, e.g. with
+/// [`Subscription::take`]. The mutexes for throttling and for replacing the callback are only
+/// taken when those features are in use.
+///
+/// The `control_loop` benchmark in `rclrs_benchmarks` measures the latency distribution for a
+/// 1 kHz subscriber with a reused wait set, through [`spin_once`] in [static memory mode][1], and
+/// compares it with a new wait set for every wakeup. Spinning a node still takes the locks listed
+/// above; there is no lock-free dispatch path.
+///
+/// Since the set of entities of the node can not change while it is borrowed here, the wait set
+/// never needs to grow.
+///
+/// [1]: crate::Node::enable_static_memory
 pub fn spin(node: &Node) -> Result<(), RclrsError> {
-    // The context_is_valid functions exists only to abstract away ROS distro differences
-    #[cfg(ros_distro = "foxy")]
-    // SAFETY: No preconditions for this function.
-    let context_is_valid = || unsafe { rcl_context_is_valid(&mut *node.context.lock()) };
-    #[cfg(not(ros_distro = "foxy"))]
-    // SAFETY: No preconditions for this function.
-    let context_is_valid = || unsafe { rcl_context_is_valid(&*node.context.lock()) };
-
-    while context_is_valid() {
-        if let Some(error) = spin_once(node, None).err() {
+    // SAFETY: The context is valid, since it is co-owned by the node.
+    let context_ok = || unsafe { context_is_valid(&mut node.context.lock()) };
+
+    // In static memory mode, spin_once() already reuses a preallocated wait set.
+    let mut wait_set = match node.static_memory {
+        Some(_) => None,
+        None => Some(ReusableWaitSet::new(
+            WaitSetCapacities::for_node(node),
+            node.guard_conditions(),
+            &node.get_context(),
+        )?),
+    };
+
+    while context_ok() {
+        let result = match &mut wait_set {
+            Some(wait_set) => wait_set.spin_once(node, None),
+            None => spin_once(node, None),
+        };
+        if let Some(error) = result.err() {
             match error.code {
                 RclReturnCode::Timeout => continue,
                 _ => return Err(error),
@@ -73,3 +256,149 @@ pub fn spin(node: &Node) -> Result<(), RclrsError> {
 
     Ok(())
 }
+
+/// Spins the node until the future is complete, and returns its output.
+///
+/// This is how to wait for e.g. the response of a [`Client::call_async`] request without an async
+/// runtime. The future is polled after every wakeup of the node, and whenever it wakes up its task,
+/// so it can be composed from several futures with the combinators of [`RclFutureExt`] and with
+/// [`join_all`].
+///
+/// With [static memory][2] enabled, no wait set is allocated here, so the future is only polled
+/// after wakeups of the node. A timeout should then be passed to notice e.g. an elapsed
+/// [`RclFutureExt::with_timeout`].
+///
+/// See [`WaitSet::wait`] for the meaning of the `timeout` parameter, which applies to each wait
+/// individually. If the node is not woken up in time, a [`Timeout`][1] error is returned.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::{Context, RclrsError, QOS_PROFILE_SERVICES_DEFAULT};
+/// let context = Context::new([])?;
+/// let mut node = context.create_node("client_node")?;
+/// let client = node.create_client::<example_interfaces::srv::AddTwoInts>(
+///     "add_two_ints",
+///     QOS_PROFILE_SERVICES_DEFAULT,
+/// )?;
+/// let request = example_interfaces::srv::AddTwoInts_Request { a: 40, b: 2 };
+/// let mut future = client.call_async(&request)?;
+/// let response = rclrs::spin_until_future_complete(&node, &mut future, None)?;
+/// assert_eq!(response.sum, 42);
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::RclReturnCode::Timeout
+/// [2]: crate::Node::enable_static_memory
+pub fn spin_until_future_complete<F>(
+    node: &Node,
+    future: &mut F,
+    timeout: Option<Duration>,
+) -> Result<F::Output, RclrsError>
+where
+    F: Future + Unpin,
+{
+    if node.static_memory.is_some() {
+        let waker = noop_waker();
+        let mut cx = task::Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = Pin::new(&mut *future).poll(&mut cx) {
+                return Ok(output);
+            }
+            spin_once(node, timeout)?;
+        }
+    }
+
+    let context = node.get_context();
+    let guard_condition = Arc::new(GuardCondition::new(&context)?);
+    let mut wait_set = ReusableWaitSet::new(
+        WaitSetCapacities::for_node(node),
+        [guard_condition.clone()]
+            .into_iter()
+            .chain(node.guard_conditions())
+            .collect(),
+        &context,
+    )?;
+    let waker = Waker::from(Arc::new(GuardConditionWaker(guard_condition)));
+    let mut cx = task::Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = Pin::new(&mut *future).poll(&mut cx) {
+            return Ok(output);
+        }
+        wait_set.spin_once(node, timeout)?;
+    }
+}
+
+// With static memory, the futures are polled after every wakeup of the node, since waking them
+// would require a guard condition in the wait set.
+fn noop_waker() -> Waker {
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(core::ptr::null(), &VTABLE)
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+    // SAFETY: The vtable functions do nothing, so they trivially uphold the RawWaker contract.
+    unsafe { Waker::from_raw(clone(core::ptr::null())) }
+}
+
+/// Waits for a single message on a topic and returns it.
+///
+/// This creates a temporary subscription, which is not registered with the node, so the node's
+/// other subscriptions are not executed while waiting. Note that the subscription may take some
+/// time to be matched with publishers, so messages published right after this function is called
+/// can be missed, unless the QoS profile has transient local durability.
+///
+/// See [`WaitSet::wait`] for the meaning of the `timeout` parameter. If no message arrives in time,
+/// a [`Timeout`][1] error is returned.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::{Context, RclrsError, QOS_PROFILE_DEFAULT};
+/// # use std::time::Duration;
+/// let context = Context::new([])?;
+/// let node = context.create_node("my_node")?;
+/// let msg = rclrs::wait_for_message::<std_msgs::msg::String>(
+///     &node,
+///     "topic",
+///     QOS_PROFILE_DEFAULT,
+///     Some(Duration::from_secs(1)),
+/// )?;
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::RclReturnCode::Timeout
+pub fn wait_for_message<T: Message>(
+    node: &Node,
+    topic: &str,
+    qos: QoSProfile,
+    timeout: Option<Duration>,
+) -> Result<T, RclrsError> {
+    let subscription = Arc::new(Subscription::<T>::new(node, topic, qos, |_: T| {})?);
+    let capacities = WaitSetCapacities {
+        subscriptions: 1,
+        ..Default::default()
+    };
+    let mut wait_set = WaitSet::new(capacities, &node.get_context())?;
+    wait_set.add_subscription(subscription.clone())?;
+    #[cfg(feature = "std")]
+    let deadline = timeout.map(|timeout| std::time::Instant::now() + timeout);
+    #[cfg_attr(not(feature = "std"), allow(unused_mut))]
+    let mut remaining = timeout;
+    loop {
+        wait_set.wait(remaining)?;
+        match subscription.take() {
+            Ok(msg) => return Ok(msg),
+            // Spurious wakeup, wait again
+            Err(RclrsError {
+                code: RclReturnCode::SubscriberError(SubscriberErrorCode::SubscriptionTakeFailed),
+                ..
+            }) => {}
+            Err(e) => return Err(e),
+        }
+        // Without std, there is no clock, so the full timeout is used again after a spurious
+        // wakeup.
+        #[cfg(feature = "std")]
+        if let Some(deadline) = deadline {
+            remaining = Some(deadline.saturating_duration_since(std::time::Instant::now()));
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use crate::error::ToResult;
+use crate::rcl_bindings::*;
+use crate::sync::Mutex;
+use crate::{ClockType, RclrsError, Time};
+
+use alloc::borrow::Cow;
+use alloc::ffi::CString;
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::ffi::{c_char, c_int, CStr};
+
+type OutputHandler = Arc<dyn Fn(&LogRecord) + Send + Sync + 'static>;
+
+// The handler installed with `set_logging_output_handler()`.
+static OUTPUT_HANDLER: Mutex<Option<OutputHandler>> = Mutex::new(None);
+
+/// The severity of a log message, see [`LogRecord`].
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum LogSeverity {
+    /// The severity is not set.
+    Unset,
+    /// Debug messages.
+    Debug,
+    /// Informational messages.
+    Info,
+    /// Warnings.
+    Warn,
+    /// Errors.
+    Error,
+    /// Fatal errors.
+    Fatal,
+}
+
+impl LogSeverity {
+    pub(crate) fn from_rcutils(severity: c_int) -> Self {
+        // Severities between the predefined levels belong to the level below them.
+        match severity as u32 {
+            s if s >= RCUTILS_LOG_SEVERITY::RCUTILS_LOG_SEVERITY_FATAL as u32 => Self::Fatal,
+            s if s >= RCUTILS_LOG_SEVERITY::RCUTILS_LOG_SEVERITY_ERROR as u32 => Self::Error,
+            s if s >= RCUTILS_LOG_SEVERITY::RCUTILS_LOG_SEVERITY_WARN as u32 => Self::Warn,
+            s if s >= RCUTILS_LOG_SEVERITY::RCUTILS_LOG_SEVERITY_INFO as u32 => Self::Info,
+            s if s >= RCUTILS_LOG_SEVERITY::RCUTILS_LOG_SEVERITY_DEBUG as u32 => Self::Debug,
+            _ => Self::Unset,
+        }
+    }
+
+    pub(crate) fn to_rcutils(self) -> c_int {
+        let severity = match self {
+            Self::Unset => RCUTILS_LOG_SEVERITY::RCUTILS_LOG_SEVERITY_UNSET,
+            Self::Debug => RCUTILS_LOG_SEVERITY::RCUTILS_LOG_SEVERITY_DEBUG,
+            Self::Info => RCUTILS_LOG_SEVERITY::RCUTILS_LOG_SEVERITY_INFO,
+            Self::Warn => RCUTILS_LOG_SEVERITY::RCUTILS_LOG_SEVERITY_WARN,
+            Self::Error => RCUTILS_LOG_SEVERITY::RCUTILS_LOG_SEVERITY_ERROR,
+            Self::Fatal => RCUTILS_LOG_SEVERITY::RCUTILS_LOG_SEVERITY_FATAL,
+        };
+        severity as c_int
+    }
+}
+
+/// Where a log message was emitted in the source code, see [`LogRecord`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LogLocation<'a> {
+    /// The name of the function.
+    pub function_name: &'a str,
+    /// The name of the source file.
+    pub file_name: &'a str,
+    /// The line number in the source file.
+    pub line_number: usize,
+}
+
+/// A log message of `rcl`, the RMW implementation or another library that logs through `rcutils`,
+/// see [`set_logging_output_handler`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LogRecord<'a> {
+    /// The severity of the message.
+    pub severity: LogSeverity,
+    /// The name of the logger, e.g. `rcl` or the name of a node.
+    pub logger_name: &'a str,
+    /// The formatted message.
+    pub message: &'a str,
+    /// When the message was emitted, in system time.
+    pub timestamp: Time,
+    /// Where the message was emitted, if known.
+    pub location: Option<LogLocation<'a>>,
+}
+
+/// Installs a handler for the log messages of `rcutils`, in place of printing them to stderr.
+///
+/// This makes the internal warnings and errors of `rcl` and the RMW implementation flow into the
+/// application's logging, e.g. into the `log` or `tracing` crates, a file or syslog. The handler
+/// receives each message that passes the severity threshold of its logger.
+///
+/// The handler is global for the process, and replaces any previous handler, including the one
+/// that `rclcpp` installs when `rclrs` is embedded with [`Context::from_raw`][1]. It may be called
+/// from any thread, and must not panic, since it is called from C code.
+///
+/// # Example
+/// ```
+/// # use rclrs::{set_logging_output_handler, LogSeverity, RclrsError};
+/// set_logging_output_handler(|record| {
+///     if record.severity >= LogSeverity::Warn {
+///         eprintln!("[{}] {}", record.logger_name, record.message);
+///     }
+/// })?;
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::Context::from_raw
+pub fn set_logging_output_handler<F>(handler: F) -> Result<(), RclrsError>
+where
+    F: Fn(&LogRecord) + Send + Sync + 'static,
+{
+    // Logging is initialized first, since that would reset the output handler otherwise.
+    // SAFETY: No preconditions for this function.
+    unsafe { rcutils_logging_initialize() }.ok()?;
+    *OUTPUT_HANDLER.lock() = Some(Arc::new(handler));
+    // SAFETY: The handler is a valid function for the whole lifetime of the process.
+    unsafe { rcutils_logging_set_output_handler(Some(output_handler)) };
+    Ok(())
+}
+
+/// Removes the handler installed with [`set_logging_output_handler`], so that log messages are
+/// printed to stderr again.
+pub fn reset_logging_output_handler() {
+    // SAFETY: The console output handler is a valid function for the whole lifetime of the
+    // process.
+    unsafe { rcutils_logging_set_output_handler(Some(rcutils_logging_console_output_handler)) };
+    *OUTPUT_HANDLER.lock() = None;
+}
+
+// Logs a message through `rcutils`, like the logging macros of `rclcpp`, with the location of
+// the caller.
+#[track_caller]
+pub(crate) fn log(logger_name: &str, severity: LogSeverity, message: &str) {
+    let caller = core::panic::Location::caller();
+    let location = LogLocation {
+        function_name: "",
+        file_name: caller.file(),
+        line_number: caller.line() as usize,
+    };
+    log_with_location(logger_name, severity, location, || message.into());
+}
+
+// Logs a message through `rcutils`. The message is only formatted when the logger is enabled for
+// the severity, so that disabled calls are cheap.
+pub(crate) fn log_with_location(
+    logger_name: &str,
+    severity: LogSeverity,
+    location: LogLocation,
+    message: impl FnOnce() -> String,
+) {
+    let Ok(logger_name) = CString::new(logger_name) else {
+        return;
+    };
+    let severity = severity.to_rcutils();
+    // SAFETY: The logger name is a valid string. Initializing logging more than once is a no-op.
+    let enabled = unsafe {
+        rcutils_logging_initialize();
+        rcutils_logging_logger_is_enabled_for(logger_name.as_ptr(), severity)
+    };
+    if !enabled {
+        return;
+    }
+    // Null characters would end the C string early, so they are dropped.
+    let text = CString::new(message().replace('\0', "")).unwrap_or_default();
+    let function_name = CString::new(location.function_name).unwrap_or_default();
+    let file_name = CString::new(location.file_name).unwrap_or_default();
+    let location = rcutils_log_location_t {
+        function_name: function_name.as_ptr(),
+        file_name: file_name.as_ptr(),
+        line_number: location.line_number,
+    };
+    let format = CString::new("%s").unwrap();
+    // SAFETY: The strings are valid for the duration of the call, and the format string consumes
+    // exactly the one argument.
+    unsafe {
+        rcutils_log(
+            &location,
+            severity,
+            logger_name.as_ptr(),
+            format.as_ptr(),
+            text.as_ptr(),
+        );
+    }
+}
+
+unsafe extern "C" fn output_handler(
+    location: *const rcutils_log_location_t,
+    severity: c_int,
+    name: *const c_char,
+    timestamp: rcutils_time_point_value_t,
+    format: *const c_char,
+    args: *mut va_list,
+) {
+    // The handler is called without holding the lock, so that it can log itself.
+    let Some(handler) = OUTPUT_HANDLER.lock().clone() else {
+        return;
+    };
+    let allocator = rcutils_get_default_allocator();
+    let mut message = rcutils_get_zero_initialized_char_array();
+    if rcutils_char_array_init(&mut message, 256, &allocator)
+        .ok()
+        .is_err()
+    {
+        return;
+    }
+    if rcutils_char_array_vsprintf(&mut message, format, va_list_arg(args))
+        .ok()
+        .is_ok()
+    {
+        let logger_name = lossy_from_ptr(name);
+        let text = lossy_from_ptr(message.buffer);
+        let location = location.as_ref().map(|location| {
+            (
+                lossy_from_ptr(location.function_name),
+                lossy_from_ptr(location.file_name),
+                location.line_number,
+            )
+        });
+        handler(&LogRecord {
+            severity: LogSeverity::from_rcutils(severity),
+            logger_name: &logger_name,
+            message: &text,
+            timestamp: Time {
+                nsec: timestamp,
+                clock_type: ClockType::SystemTime,
+            },
+            location: location
+                .as_ref()
+                .map(|(function_name, file_name, line_number)| LogLocation {
+                    function_name,
+                    file_name,
+                    line_number: *line_number,
+                }),
+        });
+    }
+    rcutils_char_array_fini(&mut message);
+}
+
+// Passes on the arguments of a log message to a function that takes a `va_list`. In the System V
+// ABI of x86_64, a `va_list` is an array, which decays to a pointer. Elsewhere, e.g. on aarch64
+// or on Windows, where it is a plain `char *`, it is passed by value, and copying it is
+// equivalent to `va_copy()`.
+#[cfg(all(target_arch = "x86_64", not(windows)))]
+unsafe fn va_list_arg(args: *mut va_list) -> *mut __va_list_tag {
+    (*args).as_mut_ptr()
+}
+
+#[cfg(not(all(target_arch = "x86_64", not(windows))))]
+unsafe fn va_list_arg(args: *mut va_list) -> va_list {
+    core::ptr::read(args)
+}
+
+/// Copies a string owned by rcutils, or returns an empty string for a null pointer.
+///
+/// # Safety
+/// The pointer must be null or point to a valid null-terminated string.
+unsafe fn lossy_from_ptr<'a>(ptr: *const c_char) -> Cow<'a, str> {
+    if ptr.is_null() {
+        Cow::Borrowed("")
+    } else {
+        CStr::from_ptr(ptr).to_string_lossy()
+    }
+}
@@ -0,0 +1,235 @@
+use crate::future::{promise, RclFuture};
+use crate::sync::Mutex;
+use crate::{Client, MessageCow, RclReturnCode, RclrsError};
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use rosidl_runtime_rs::Service;
+
+// Implemented by the entities bound to an activation, to cancel their work when it is deactivated.
+trait Deactivate {
+    fn deactivate(&self);
+}
+
+struct ActivationState {
+    active: AtomicBool,
+    bound: Mutex<Vec<Weak<dyn Deactivate>>>,
+}
+
+/// The activation state of a managed component, e.g. of a lifecycle node.
+///
+/// Clients can be bound to the activation with [`Activation::bind_client`]. When the activation is
+/// deactivated, e.g. in the `on_deactivate` transition of a lifecycle node, the in-flight requests
+/// of the bound clients are canceled. Their responses are ignored when they arrive later, so that
+/// no callback fires into the deactivated state.
+///
+/// The activation can be cloned, and all clones share the same state.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let activation = Activation::new(false);
+/// let client = activation.bind_client(
+///     node.create_client::<example_interfaces::srv::AddTwoInts>(
+///         "add_two_ints",
+///         QOS_PROFILE_SERVICES_DEFAULT,
+///     )?,
+/// );
+/// activation.activate();
+/// let future = client.call_async(&request)?;
+/// // The future completes with `None` if the activation is deactivated before the response
+/// // arrives.
+/// activation.deactivate();
+/// ```
+#[derive(Clone)]
+pub struct Activation {
+    state: Arc<ActivationState>,
+}
+
+impl Activation {
+    /// Creates an activation in the given state.
+    pub fn new(active: bool) -> Self {
+        Self {
+            state: Arc::new(ActivationState {
+                active: AtomicBool::new(active),
+                bound: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Returns true if the activation is active.
+    pub fn is_active(&self) -> bool {
+        self.state.active.load(Ordering::Acquire)
+    }
+
+    /// Activates the activation, so that the bound clients can send requests again.
+    pub fn activate(&self) {
+        self.state.active.store(true, Ordering::Release);
+    }
+
+    /// Deactivates the activation, and cancels the in-flight requests of the bound clients.
+    ///
+    /// The callbacks of the canceled requests run with `None`, and their futures complete with
+    /// `None`, before this function returns.
+    pub fn deactivate(&self) {
+        self.state.active.store(false, Ordering::Release);
+        // The entities are collected first, so that the callbacks can bind new clients.
+        let bound: Vec<_> = {
+            let mut bound = self.state.bound.lock();
+            bound.retain(|weak| weak.strong_count() > 0);
+            bound.iter().filter_map(Weak::upgrade).collect()
+        };
+        for entity in bound {
+            entity.deactivate();
+        }
+    }
+
+    /// Binds a client to the activation.
+    ///
+    /// See [`ActivatedClient`].
+    pub fn bind_client<T>(&self, client: Arc<Client<T>>) -> ActivatedClient<T>
+    where
+        T: Service,
+    {
+        let pending = Arc::new(PendingRequests {
+            activation: self.clone(),
+            next_id: Mutex::new(0),
+            callbacks: Mutex::new(BTreeMap::new()),
+        });
+        let mut bound = self.state.bound.lock();
+        bound.retain(|weak| weak.strong_count() > 0);
+        let weak: Weak<PendingRequests<T>> = Arc::downgrade(&pending);
+        bound.push(weak);
+        ActivatedClient { client, pending }
+    }
+}
+
+type CancelableCallback<T> = Box<dyn FnOnce(Option<<T as Service>::Response>) + 'static>;
+
+// The callbacks of the requests that an activated client has sent, by a local id that is known
+// before the request is sent.
+struct PendingRequests<T>
+where
+    T: Service,
+{
+    activation: Activation,
+    next_id: Mutex<u64>,
+    callbacks: Mutex<BTreeMap<u64, CancelableCallback<T>>>,
+}
+
+impl<T> PendingRequests<T>
+where
+    T: Service,
+{
+    fn take(&self, id: u64) -> Option<CancelableCallback<T>> {
+        self.callbacks.lock().remove(&id)
+    }
+}
+
+impl<T> Deactivate for PendingRequests<T>
+where
+    T: Service,
+{
+    fn deactivate(&self) {
+        // The callbacks are run after releasing the lock, so that they can send new requests.
+        let canceled = core::mem::take(&mut *self.callbacks.lock());
+        for callback in canceled.into_values() {
+            callback(None);
+        }
+    }
+}
+
+/// A [`Client`] bound to an [`Activation`], created with [`Activation::bind_client`].
+///
+/// Requests can only be sent while the activation is active. When the activation is deactivated,
+/// the in-flight requests are canceled: their callbacks run with `None`, and the responses that
+/// arrive later are ignored.
+pub struct ActivatedClient<T>
+where
+    T: Service,
+{
+    client: Arc<Client<T>>,
+    pending: Arc<PendingRequests<T>>,
+}
+
+impl<T> ActivatedClient<T>
+where
+    T: Service,
+{
+    /// Sends a request and runs the callback with the response when it arrives, or with `None`
+    /// when the request is canceled.
+    ///
+    /// Returns an [`Error`][1] if the activation is not active. See
+    /// [`Client::async_send_request_with_callback`] for the other errors.
+    ///
+    /// [1]: crate::RclReturnCode::Error
+    pub fn async_send_request_with_callback<'a, R, F>(
+        &self,
+        request: R,
+        callback: F,
+    ) -> Result<i64, RclrsError>
+    where
+        R: MessageCow<'a, T::Request>,
+        F: FnOnce(Option<T::Response>) + 'static,
+    {
+        let id = {
+            // The activation is checked while the callbacks are locked, so that the request is
+            // either rejected here, or canceled by a concurrent deactivation.
+            let mut callbacks = self.pending.callbacks.lock();
+            if !self.pending.activation.is_active() {
+                return Err(RclrsError::with_message(
+                    RclReturnCode::Error,
+                    "The activation of the client is not active",
+                ));
+            }
+            let mut next_id = self.pending.next_id.lock();
+            let id = *next_id;
+            *next_id += 1;
+            callbacks.insert(id, Box::new(callback));
+            id
+        };
+        let pending = Arc::clone(&self.pending);
+        let result = self
+            .client
+            .async_send_request_with_callback(request, move |response| {
+                // The callback is gone if the request has been canceled.
+                if let Some(callback) = pending.take(id) {
+                    callback(Some(response));
+                }
+            });
+        if result.is_err() {
+            self.pending.take(id);
+        }
+        result
+    }
+
+    /// Sends a request and returns a future for the response, which completes with `None` when
+    /// the request is canceled.
+    ///
+    /// See [`ActivatedClient::async_send_request_with_callback`].
+    pub fn call_async<'a, R>(
+        &self,
+        request: R,
+    ) -> Result<RclFuture<Option<T::Response>>, RclrsError>
+    where
+        R: MessageCow<'a, T::Request>,
+    {
+        let (promise, future) = promise();
+        self.async_send_request_with_callback(request, move |response| promise.set(response))?;
+        Ok(future)
+    }
+
+    /// Returns the number of requests that are neither answered nor canceled.
+    pub fn pending_requests(&self) -> usize {
+        self.pending.callbacks.lock().len()
+    }
+
+    /// Returns the underlying client, e.g. for checking if its service is ready.
+    pub fn client(&self) -> &Arc<Client<T>> {
+        &self.client
+    }
+}
@@ -0,0 +1,117 @@
+use crate::sync::Mutex;
+use crate::{Client, GraphEventHandler, RclrsError};
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use rosidl_runtime_rs::Service;
+
+type ResponseCallback<T> = Arc<Mutex<Option<Box<dyn FnMut(<T as Service>::Response) + 'static>>>>;
+
+/// A client that remembers its last request, and sends it again whenever the service server
+/// (re)appears.
+///
+/// This is for pushing configuration to servers that restart frequently, e.g. a driver that
+/// must be reconfigured after each restart. The request is sent right away if the server is
+/// available, and otherwise as soon as it appears. Every time the server disappears from the ROS
+/// graph and comes back, the last request is sent again. The server should therefore handle
+/// repeated requests gracefully.
+///
+/// Like the [`ServiceWatchdog`][1], the client is woken up by changes of the ROS graph while its
+/// node is spinning. Create a cached client with [`Node::create_cached_client`][2].
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let client = node.create_cached_client::<rcl_interfaces::srv::SetParameters>(
+///     "/driver/set_parameters",
+///     QOS_PROFILE_SERVICES_DEFAULT,
+/// )?;
+/// client.on_response(|response| println!("Driver configured: {response:?}"));
+/// client.send(request)?;
+/// ```
+///
+/// [1]: crate::ServiceWatchdog
+/// [2]: crate::Node::create_cached_client
+pub struct CachedClient<T: Service> {
+    client: Arc<Client<T>>,
+    last_request: Mutex<Option<T::Request>>,
+    available: AtomicBool,
+    on_response: ResponseCallback<T>,
+}
+
+impl<T: Service> CachedClient<T> {
+    pub(crate) fn new(client: Arc<Client<T>>) -> Result<Self, RclrsError> {
+        let available = client.service_is_ready()?;
+        Ok(Self {
+            client,
+            last_request: Mutex::new(None),
+            available: AtomicBool::new(available),
+            on_response: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Stores the request, and sends it if the service server is available.
+    ///
+    /// The request replaces the previous one, which is not sent again. If the server is not
+    /// available, the request is sent when it appears.
+    pub fn send(&self, request: T::Request) -> Result<(), RclrsError> {
+        *self.last_request.lock() = Some(request.clone());
+        if self.available.load(Ordering::Acquire) {
+            self.send_impl(request)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the last request, which is sent again when the server reappears.
+    pub fn last_request(&self) -> Option<T::Request> {
+        self.last_request.lock().clone()
+    }
+
+    /// Forgets the last request, so that it is not sent again.
+    pub fn clear(&self) {
+        *self.last_request.lock() = None;
+    }
+
+    /// Sets the callback that runs with the response to each request that is sent, including
+    /// the repeated ones.
+    pub fn on_response(&self, callback: impl FnMut(T::Response) + 'static) {
+        *self.on_response.lock() = Some(Box::new(callback));
+    }
+
+    /// Returns whether the service server was available when the graph was last checked.
+    pub fn is_available(&self) -> bool {
+        self.available.load(Ordering::Acquire)
+    }
+
+    /// Returns the underlying client, e.g. for sending requests that are not cached.
+    pub fn client(&self) -> &Arc<Client<T>> {
+        &self.client
+    }
+
+    fn send_impl(&self, request: T::Request) -> Result<(), RclrsError> {
+        let on_response = Arc::clone(&self.on_response);
+        self.client
+            .async_send_request_with_callback(request, move |response| {
+                if let Some(callback) = &mut *on_response.lock() {
+                    callback(response);
+                }
+            })
+            .map(|_| ())
+    }
+}
+
+impl<T: Service> GraphEventHandler for CachedClient<T> {
+    fn handle_graph_event(&self) -> Result<(), RclrsError> {
+        let available = self.client.service_is_ready()?;
+        let was_available = self.available.swap(available, Ordering::AcqRel);
+        if available && !was_available {
+            let request = self.last_request.lock().clone();
+            if let Some(request) = request {
+                self.send_impl(request)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,557 @@
+use crate::node::payload_transform::{deserialize, serialize};
+use crate::qos::QoSProfile;
+use crate::rcl_bindings::*;
+use crate::rosidl_macros::{impl_message, impl_sequence_alloc};
+use crate::{MessageCow, Node, Publisher, RclReturnCode, RclrsError, Subscription};
+
+use alloc::collections::btree_map::Entry;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+use rosidl_runtime_rs::{Message, RmwMessage, Sequence, String as RosString};
+
+// Corresponds to std_msgs__msg__MultiArrayDimension
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct MultiArrayDimension {
+    label: RosString,
+    size: u32,
+    stride: u32,
+}
+
+impl_message!(
+    MultiArrayDimension,
+    "std_msgs/msg/MultiArrayDimension",
+    std_msgs__msg__MultiArrayDimension__init,
+    rosidl_typesupport_c__get_message_type_support_handle__std_msgs__msg__MultiArrayDimension
+);
+impl_sequence_alloc!(
+    MultiArrayDimension,
+    std_msgs__msg__MultiArrayDimension__Sequence__init,
+    std_msgs__msg__MultiArrayDimension__Sequence__fini
+);
+
+// Corresponds to std_msgs__msg__MultiArrayLayout
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct MultiArrayLayout {
+    pub(crate) dim: Sequence<MultiArrayDimension>,
+    pub(crate) data_offset: u32,
+}
+
+// Corresponds to std_msgs__msg__UInt8MultiArray, which carries the chunks.
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct UInt8MultiArray {
+    pub(crate) layout: MultiArrayLayout,
+    pub(crate) data: Sequence<u8>,
+}
+
+impl_message!(
+    UInt8MultiArray,
+    "std_msgs/msg/UInt8MultiArray",
+    std_msgs__msg__UInt8MultiArray__init,
+    rosidl_typesupport_c__get_message_type_support_handle__std_msgs__msg__UInt8MultiArray
+);
+
+// Each chunk starts with a header of little-endian integers: the ID of the publisher (u64), the
+// sequence number of the message (u32), and the index and number of chunks of the message (u32).
+const HEADER_SIZE: usize = 20;
+
+struct ChunkHeader {
+    publisher_id: u64,
+    sequence: u32,
+    index: u32,
+    count: u32,
+}
+
+impl ChunkHeader {
+    fn write(&self, chunk: &mut Vec<u8>) {
+        chunk.extend_from_slice(&self.publisher_id.to_le_bytes());
+        chunk.extend_from_slice(&self.sequence.to_le_bytes());
+        chunk.extend_from_slice(&self.index.to_le_bytes());
+        chunk.extend_from_slice(&self.count.to_le_bytes());
+    }
+
+    // Returns the header and the payload of a chunk, or `None` if it is too short.
+    fn read(chunk: &[u8]) -> Option<(Self, &[u8])> {
+        if chunk.len() < HEADER_SIZE {
+            return None;
+        }
+        let u32_at = |i: usize| u32::from_le_bytes(chunk[i..i + 4].try_into().unwrap());
+        let header = Self {
+            publisher_id: u64::from_le_bytes(chunk[..8].try_into().unwrap()),
+            sequence: u32_at(8),
+            index: u32_at(12),
+            count: u32_at(16),
+        };
+        Some((header, &chunk[HEADER_SIZE..]))
+    }
+}
+
+/// Options for a [`ChunkedPublisher`] and a [`ChunkedSubscription`].
+///
+/// New options may be added in the future, so it is best to create this with the
+/// `..Default::default()` syntax.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChunkingOptions {
+    /// The maximum size in bytes of the data of a chunk, including a header of 20 bytes. This
+    /// should be below the maximum message size of the transport. Only used by the publisher.
+    ///
+    /// The default is 64 KiB.
+    pub max_chunk_size: usize,
+    /// The maximum size in bytes of a reassembled message. Messages that would exceed it are
+    /// dropped, so that a faulty publisher can not exhaust the memory of the subscription. Only
+    /// used by the subscription.
+    ///
+    /// The default is 256 MiB.
+    pub max_message_size: usize,
+}
+
+impl Default for ChunkingOptions {
+    fn default() -> Self {
+        Self {
+            max_chunk_size: 64 * 1024,
+            max_message_size: 256 * 1024 * 1024,
+        }
+    }
+}
+
+/// A publisher that splits messages into chunks, for transports and middlewares with a small
+/// maximum message size.
+///
+/// Each message is serialized, and the serialized message is published in chunks of at most
+/// [`max_chunk_size`][1] bytes, as `std_msgs/msg/UInt8MultiArray` messages. The chunks are
+/// reassembled by a [`ChunkedSubscription`]. Since a message is lost when one of its chunks is,
+/// the topic should usually use the reliable QoS policy.
+///
+/// Create a chunked publisher with [`Node::create_chunked_publisher`].
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let options = ChunkingOptions {
+///     max_chunk_size: 8 * 1024,
+///     ..Default::default()
+/// };
+/// let publisher = node.create_chunked_publisher::<sensor_msgs::msg::PointCloud2>(
+///     "points_chunked",
+///     QOS_PROFILE_DEFAULT,
+///     options.clone(),
+/// )?;
+/// publisher.publish(&cloud)?;
+///
+/// let _subscription = node.create_chunked_subscription(
+///     "points_chunked",
+///     QOS_PROFILE_DEFAULT,
+///     options,
+///     |cloud: sensor_msgs::msg::PointCloud2| println!("{} points", cloud.width),
+/// )?;
+/// ```
+///
+/// [1]: ChunkingOptions::max_chunk_size
+pub struct ChunkedPublisher<T: Message> {
+    publisher: Publisher<UInt8MultiArray>,
+    // Identifies the messages of this publisher on the topic.
+    publisher_id: u64,
+    sequence: AtomicU32,
+    max_chunk_size: usize,
+    message: PhantomData<T>,
+}
+
+// The publisher ID is derived from the GID of the publisher, with the 64-bit FNV-1a hash.
+pub(crate) fn publisher_id(gid: &[u8]) -> u64 {
+    gid.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+impl<T: Message> ChunkedPublisher<T> {
+    pub(crate) fn new(
+        node: &mut Node,
+        topic: &str,
+        qos: QoSProfile,
+        options: ChunkingOptions,
+    ) -> Result<Self, RclrsError> {
+        if options.max_chunk_size <= HEADER_SIZE {
+            return Err(RclrsError::with_message(
+                RclReturnCode::InvalidArgument,
+                format!(
+                    "The maximum chunk size must be larger than the chunk header of \
+                     {HEADER_SIZE} bytes, but it is {}",
+                    options.max_chunk_size
+                ),
+            ));
+        }
+        let publisher = node.create_publisher::<UInt8MultiArray>(topic, qos)?;
+        Ok(Self {
+            publisher_id: publisher_id(publisher.gid().as_bytes()),
+            publisher,
+            sequence: AtomicU32::new(0),
+            max_chunk_size: options.max_chunk_size,
+            message: PhantomData,
+        })
+    }
+
+    /// Publishes a message in chunks.
+    ///
+    /// The chunks are published like with [`Publisher::publish_batch`], so publishing stops at
+    /// the first error, and the message can then not be reassembled.
+    ///
+    /// Returns an [`InvalidArgument`][1] error if the message needs more than `u32::MAX` chunks.
+    ///
+    /// [1]: crate::RclReturnCode::InvalidArgument
+    pub fn publish<'a, M: MessageCow<'a, T>>(&self, message: M) -> Result<(), RclrsError> {
+        let buffer = message.into_cow().with_rmw_message(|rmw_message| {
+            serialize(
+                rmw_message as *const <T as Message>::RmwMsg as *const _,
+                <T as Message>::RmwMsg::get_type_support() as *const rosidl_message_type_support_t,
+            )
+        })?;
+        let payload = buffer.as_slice();
+        let chunk_size = self.max_chunk_size - HEADER_SIZE;
+        // An empty payload is still published as one chunk.
+        let count = u32::try_from(payload.len().div_ceil(chunk_size).max(1)).map_err(|_| {
+            RclrsError::with_message(
+                RclReturnCode::InvalidArgument,
+                format!(
+                    "The message of {} bytes needs too many chunks",
+                    payload.len()
+                ),
+            )
+        })?;
+        let sequence = self.sequence.fetch_add(1, Ordering::Relaxed);
+        let chunks = (0..count).map(|index| {
+            let start = index as usize * chunk_size;
+            let end = (start + chunk_size).min(payload.len());
+            let mut data = Vec::with_capacity(HEADER_SIZE + end - start);
+            ChunkHeader {
+                publisher_id: self.publisher_id,
+                sequence,
+                index,
+                count,
+            }
+            .write(&mut data);
+            data.extend_from_slice(&payload[start..end]);
+            UInt8MultiArray {
+                layout: MultiArrayLayout {
+                    dim: Sequence::default(),
+                    data_offset: HEADER_SIZE as u32,
+                },
+                data: Sequence::from(data),
+            }
+        });
+        self.publisher.publish_batch(chunks)
+    }
+
+    /// Returns the number of subscriptions that are matched to the topic of the chunks.
+    pub fn get_subscription_count(&self) -> Result<usize, RclrsError> {
+        self.publisher.get_subscription_count()
+    }
+}
+
+// A message whose chunks are being received.
+struct PartialMessage {
+    sequence: u32,
+    count: u32,
+    // The received chunks by index, which are only stored as they arrive, so that the memory
+    // does not depend on the number of chunks in the header.
+    chunks: BTreeMap<u32, Vec<u8>>,
+    size: usize,
+}
+
+// Reassembles the chunks of the publishers on a topic.
+struct Reassembler {
+    max_message_size: usize,
+    // At most one message per publisher is reassembled at a time, since the chunks of a message
+    // are published together.
+    partial: BTreeMap<u64, PartialMessage>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl Reassembler {
+    // Adds a chunk, and returns the serialized message once all of its chunks have arrived.
+    fn add(&mut self, chunk: &[u8]) -> Option<Vec<u8>> {
+        let Some((header, payload)) = ChunkHeader::read(chunk) else {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        // Each chunk carries at least one byte, except the only chunk of an empty message, so
+        // more chunks than the maximum message size are invalid.
+        if header.count == 0
+            || header.index >= header.count
+            || header.count as usize > self.max_message_size.max(1)
+        {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        let partial = match self.partial.get_mut(&header.publisher_id) {
+            Some(partial)
+                if partial.sequence == header.sequence && partial.count == header.count =>
+            {
+                partial
+            }
+            // A late chunk of a message that has already been dropped, which must not replace
+            // the newer message. The sequence numbers wrap around.
+            Some(partial) if (header.sequence.wrapping_sub(partial.sequence) as i32) < 0 => {
+                return None;
+            }
+            existing => {
+                // A chunk of a newer message means that the rest of the old one was lost.
+                if existing.is_some() {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                self.partial.insert(
+                    header.publisher_id,
+                    PartialMessage {
+                        sequence: header.sequence,
+                        count: header.count,
+                        chunks: BTreeMap::new(),
+                        size: 0,
+                    },
+                );
+                self.partial.get_mut(&header.publisher_id).unwrap()
+            }
+        };
+        if let Entry::Vacant(slot) = partial.chunks.entry(header.index) {
+            partial.size += payload.len();
+            slot.insert(payload.to_vec());
+        }
+        if partial.size > self.max_message_size {
+            self.partial.remove(&header.publisher_id);
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        if partial.chunks.len() < partial.count as usize {
+            return None;
+        }
+        let partial = self.partial.remove(&header.publisher_id)?;
+        let mut message = Vec::with_capacity(partial.size);
+        for chunk in partial.chunks.into_values() {
+            message.extend_from_slice(&chunk);
+        }
+        Some(message)
+    }
+}
+
+/// A subscription that reassembles the messages of a [`ChunkedPublisher`].
+///
+/// The callback runs once all chunks of a message have arrived. The chunks of several
+/// publishers on the same topic are reassembled separately. A message is dropped when one of its
+/// chunks is lost, which is detected when a chunk of the next message of the same publisher
+/// arrives, or when it can not be deserialized.
+///
+/// Create a chunked subscription with [`Node::create_chunked_subscription`].
+pub struct ChunkedSubscription<T: Message> {
+    subscription: Arc<Subscription<UInt8MultiArray>>,
+    dropped: Arc<AtomicU64>,
+    message: PhantomData<T>,
+}
+
+impl<T: Message> ChunkedSubscription<T> {
+    pub(crate) fn new<F>(
+        node: &mut Node,
+        topic: &str,
+        qos: QoSProfile,
+        options: ChunkingOptions,
+        mut callback: F,
+    ) -> Result<Self, RclrsError>
+    where
+        F: FnMut(T) + 'static,
+    {
+        let dropped = Arc::new(AtomicU64::new(0));
+        let mut reassembler = Reassembler {
+            max_message_size: options.max_message_size,
+            partial: BTreeMap::new(),
+            dropped: Arc::clone(&dropped),
+        };
+        let callback_dropped = Arc::clone(&dropped);
+        let subscription =
+            node.create_subscription(topic, qos, move |chunk: UInt8MultiArray| {
+                let Some(payload) = reassembler.add(&chunk.data) else {
+                    return;
+                };
+                let mut rmw_message = <T as Message>::RmwMsg::default();
+                let deserialized = deserialize(
+                    &payload,
+                    <T as Message>::RmwMsg::get_type_support()
+                        as *const rosidl_message_type_support_t,
+                    &mut rmw_message as *mut <T as Message>::RmwMsg as *mut _,
+                );
+                match deserialized {
+                    Ok(()) => callback(T::from_rmw_message(rmw_message)),
+                    Err(_) => {
+                        callback_dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            })?;
+        Ok(Self {
+            subscription,
+            dropped,
+            message: PhantomData,
+        })
+    }
+
+    /// Returns the number of messages that were dropped because chunks were lost, or because
+    /// they were invalid or too large.
+    pub fn dropped_messages(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of publishers that are matched to the topic of the chunks.
+    pub fn get_publisher_count(&self) -> Result<usize, RclrsError> {
+        self.subscription.get_publisher_count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reassembler(max_message_size: usize) -> Reassembler {
+        Reassembler {
+            max_message_size,
+            partial: BTreeMap::new(),
+            dropped: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    fn chunk(publisher_id: u64, sequence: u32, index: u32, count: u32, payload: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        ChunkHeader {
+            publisher_id,
+            sequence,
+            index,
+            count,
+        }
+        .write(&mut data);
+        data.extend_from_slice(payload);
+        data
+    }
+
+    fn dropped(reassembler: &Reassembler) -> u64 {
+        reassembler.dropped.load(Ordering::Relaxed)
+    }
+
+    #[test]
+    fn test_reassemble_in_any_order() {
+        let mut reassembler = reassembler(1024);
+        assert_eq!(reassembler.add(&chunk(1, 0, 0, 1, b"")), Some(Vec::new()));
+        assert_eq!(reassembler.add(&chunk(1, 1, 2, 3, b"ghi")), None);
+        assert_eq!(reassembler.add(&chunk(1, 1, 0, 3, b"abc")), None);
+        // A duplicate chunk is ignored.
+        assert_eq!(reassembler.add(&chunk(1, 1, 0, 3, b"xyz")), None);
+        assert_eq!(
+            reassembler.add(&chunk(1, 1, 1, 3, b"def")),
+            Some(b"abcdefghi".to_vec())
+        );
+        assert_eq!(dropped(&reassembler), 0);
+        assert!(reassembler.partial.is_empty());
+    }
+
+    #[test]
+    fn test_reassemble_publishers_separately() {
+        let mut reassembler = reassembler(1024);
+        assert_eq!(reassembler.add(&chunk(1, 0, 0, 2, b"a1")), None);
+        assert_eq!(reassembler.add(&chunk(2, 0, 0, 2, b"b1")), None);
+        assert_eq!(
+            reassembler.add(&chunk(2, 0, 1, 2, b"b2")),
+            Some(b"b1b2".to_vec())
+        );
+        assert_eq!(
+            reassembler.add(&chunk(1, 0, 1, 2, b"a2")),
+            Some(b"a1a2".to_vec())
+        );
+        assert_eq!(dropped(&reassembler), 0);
+    }
+
+    #[test]
+    fn test_drop_message_with_lost_chunk() {
+        let mut reassembler = reassembler(1024);
+        assert_eq!(reassembler.add(&chunk(1, 0, 0, 2, b"old")), None);
+        // The next message of the publisher starts before the old one is complete.
+        assert_eq!(reassembler.add(&chunk(1, 1, 0, 2, b"new")), None);
+        assert_eq!(dropped(&reassembler), 1);
+        // A late chunk of the old message is ignored, and does not replace the new one.
+        assert_eq!(reassembler.add(&chunk(1, 0, 1, 2, b"old")), None);
+        assert_eq!(dropped(&reassembler), 1);
+        assert_eq!(
+            reassembler.add(&chunk(1, 1, 1, 2, b"!")),
+            Some(b"new!".to_vec())
+        );
+        // Chunks with a different count belong to another message, even with the same sequence.
+        assert_eq!(reassembler.add(&chunk(1, 2, 0, 2, b"a")), None);
+        assert_eq!(
+            reassembler.add(&chunk(1, 2, 0, 1, b"b")),
+            Some(b"b".to_vec())
+        );
+        assert_eq!(dropped(&reassembler), 2);
+    }
+
+    #[test]
+    fn test_sequence_wraps_around() {
+        let mut reassembler = reassembler(1024);
+        assert_eq!(reassembler.add(&chunk(1, u32::MAX, 0, 2, b"a")), None);
+        assert_eq!(reassembler.add(&chunk(1, 0, 0, 2, b"b")), None);
+        assert_eq!(dropped(&reassembler), 1);
+        assert_eq!(reassembler.add(&chunk(1, u32::MAX, 1, 2, b"a")), None);
+        assert_eq!(
+            reassembler.add(&chunk(1, 0, 1, 2, b"c")),
+            Some(b"bc".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_drop_invalid_chunks() {
+        let mut reassembler = reassembler(1024);
+        let valid = chunk(1, 0, 0, 1, b"payload");
+        assert_eq!(reassembler.add(&valid[..HEADER_SIZE - 1]), None);
+        assert_eq!(reassembler.add(&chunk(1, 0, 0, 0, b"")), None);
+        assert_eq!(reassembler.add(&chunk(1, 0, 1, 1, b"")), None);
+        assert_eq!(dropped(&reassembler), 3);
+        assert!(reassembler.partial.is_empty());
+        assert_eq!(reassembler.add(&valid), Some(b"payload".to_vec()));
+    }
+
+    #[test]
+    fn test_drop_chunks_with_too_many_chunks() {
+        // An empty message has one chunk, also with a maximum size of zero.
+        let mut empty = reassembler(0);
+        assert_eq!(empty.add(&chunk(1, 0, 0, 1, b"")), Some(Vec::new()));
+        let mut reassembler = reassembler(1024);
+        assert_eq!(reassembler.add(&chunk(1, 0, 0, u32::MAX, b"a")), None);
+        assert_eq!(reassembler.add(&chunk(1, 0, 0, 1025, b"a")), None);
+        assert_eq!(dropped(&reassembler), 2);
+        assert!(reassembler.partial.is_empty());
+        // As many chunks as the maximum size are allowed, since each carries at least one byte.
+        assert_eq!(reassembler.add(&chunk(1, 1, 0, 1024, b"a")), None);
+        assert_eq!(dropped(&reassembler), 2);
+    }
+
+    #[test]
+    fn test_drop_messages_above_maximum_size() {
+        let mut reassembler = reassembler(4);
+        assert_eq!(reassembler.add(&chunk(1, 0, 0, 2, b"abc")), None);
+        assert_eq!(reassembler.add(&chunk(1, 0, 1, 2, b"de")), None);
+        assert_eq!(dropped(&reassembler), 1);
+        assert!(reassembler.partial.is_empty());
+        // A message of exactly the maximum size is not dropped.
+        assert_eq!(
+            reassembler.add(&chunk(1, 1, 0, 1, b"abcd")),
+            Some(b"abcd".to_vec())
+        );
+        assert_eq!(reassembler.add(&chunk(1, 2, 0, 1, b"abcde")), None);
+        assert_eq!(dropped(&reassembler), 2);
+    }
+
+    #[test]
+    fn test_publisher_id_is_fnv1a() {
+        assert_eq!(publisher_id(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(publisher_id(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_ne!(publisher_id(&[1; 16]), publisher_id(&[2; 16]));
+    }
+}
@@ -0,0 +1,561 @@
+use crate::allocator::copy_rcutils_allocator;
+use crate::distro::client_writer_guid;
+use crate::error::{ClientErrorCode, RclReturnCode, ToResult};
+use crate::future::{promise, RclFuture};
+#[cfg(feature = "testing")]
+use crate::node::payload_transform::{deserialize, serialize};
+use crate::node::trace_context::RunTraceHooks;
+use crate::qos::QoSProfile;
+#[cfg(feature = "testing")]
+use crate::testing::{EndpointKind, LoopbackEndpoint};
+use crate::tracetools;
+use crate::{rcl_bindings::*, RclrsError};
+use crate::{MessageCow, Node, NodeHandle, RequestId, TraceContext, TraceHooks};
+
+use crate::sync::{Mutex, MutexGuard};
+
+use alloc::borrow::Borrow;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::ffi::CString;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ffi::c_void;
+
+#[cfg(feature = "testing")]
+use rosidl_runtime_rs::RmwMessage;
+use rosidl_runtime_rs::{Message, Service};
+
+/// Internal struct used by clients.
+pub struct ClientHandle {
+    handle: Mutex<rcl_client_t>,
+    node_handle: Arc<NodeHandle>,
+    // Set if the client sends its requests through a `testing::Loopback`.
+    #[cfg(feature = "testing")]
+    loopback: Option<LoopbackEndpoint>,
+}
+
+impl ClientHandle {
+    pub(crate) fn lock(&self) -> MutexGuard<rcl_client_t> {
+        self.handle.lock()
+    }
+}
+
+impl Drop for ClientHandle {
+    fn drop(&mut self) {
+        let handle = self.handle.get_mut();
+        let node_handle = &mut *self.node_handle.lock();
+        // SAFETY: No preconditions for this function (besides the arguments being valid).
+        unsafe {
+            rcl_client_fini(handle, node_handle);
+        }
+    }
+}
+
+/// Trait to be implemented by concrete [`Client`]s.
+pub trait ClientBase {
+    /// Internal function to get a reference to the `rcl` handle.
+    fn handle(&self) -> &ClientHandle;
+    /// Tries to take a new response and run the callback of its request with it.
+    fn execute(&self) -> Result<(), RclrsError>;
+}
+
+type RequestCallback<T> = Box<dyn FnOnce(<T as Service>::Response, RequestId) + 'static>;
+
+type BufferedResponses<T> = Mutex<BTreeMap<i64, (<T as Service>::Response, RequestId)>>;
+
+/// Options for a [`Client`], in addition to its QoS profile.
+///
+/// New options may be added in the future, so it is best to create this with the
+/// `..Default::default()` syntax.
+///
+/// # Example
+/// ```
+/// # use rclrs::ClientOptions;
+/// let options = ClientOptions {
+///     ordered_responses: true,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ClientOptions {
+    /// If true, the callbacks of requests are run in the order in which the requests were sent.
+    ///
+    /// Services may answer requests out of order, e.g. when they are served by several
+    /// executors. With this option, a response that arrives before the responses to earlier
+    /// requests is buffered until those have arrived, which is useful for clients that must apply
+    /// the responses sequentially. Note that a lost response then holds back all later ones,
+    /// until its request is canceled with [`Client::cancel_request`].
+    pub ordered_responses: bool,
+}
+
+/// Struct for sending requests to a [`Service`][1] of type `T`, and receiving its responses.
+///
+/// Receiving responses requires calling [`spin_once`][2] or [`spin`][3] on the client's node.
+///
+/// [1]: crate::Service
+/// [2]: crate::spin_once
+/// [3]: crate::spin
+pub struct Client<T>
+where
+    T: Service,
+{
+    pub(crate) handle: Arc<ClientHandle>,
+    // The callbacks of the requests that have been sent but not answered yet, by sequence number.
+    requests: Mutex<BTreeMap<i64, RequestCallback<T>>>,
+    // With ordered responses, the responses that arrived before those of earlier requests.
+    // There is at most one for each pending request, so this is bounded by the limit as well.
+    buffered_responses: Option<BufferedResponses<T>>,
+    // The maximum number of pending requests, in static memory mode.
+    max_pending_requests: Option<usize>,
+    trace_hooks: Option<Arc<TraceHooks>>,
+    // The GUID that services see in the request IDs. Before Iron, it is only known once the first
+    // response has arrived.
+    writer_guid: Mutex<Option<[u8; 16]>>,
+}
+
+impl<T> Client<T>
+where
+    T: Service,
+{
+    /// Creates a new client.
+    ///
+    /// # Panics
+    /// When the service name contains interior null bytes.
+    pub fn new(node: &Node, service_name: &str, qos: QoSProfile) -> Result<Self, RclrsError> {
+        Self::new_with_options(node, service_name, qos, node.entity_defaults.client_options)
+    }
+
+    /// Creates a new client with additional options.
+    ///
+    /// # Panics
+    /// When the service name contains interior null bytes.
+    pub fn new_with_options(
+        node: &Node,
+        service_name: &str,
+        qos: QoSProfile,
+        options: ClientOptions,
+    ) -> Result<Self, RclrsError> {
+        // This is declared before the node handle guard, since dropping it in the error case
+        // locks the node handle.
+        let service_name = node.expand_topic_name(service_name)?;
+        #[cfg_attr(not(feature = "testing"), allow(unused_variables))]
+        let handle = Arc::new_cyclic(|handle| ClientHandle {
+            // SAFETY: Getting a zero-initialized value is always safe.
+            handle: Mutex::new(unsafe { rcl_get_zero_initialized_client() }),
+            node_handle: node.handle.clone(),
+            #[cfg(feature = "testing")]
+            loopback: LoopbackEndpoint::new(node, handle, EndpointKind::Client, &service_name),
+        });
+        let type_support = T::get_type_support() as *const rosidl_service_type_support_t;
+        let service_name_c_string = CString::new(service_name).unwrap();
+        let node_handle = &mut *node.handle.lock();
+
+        // SAFETY: No preconditions for this function.
+        let mut client_options = unsafe { rcl_client_get_default_options() };
+        client_options.qos = qos.into();
+        client_options.allocator = copy_rcutils_allocator(&node.allocator);
+        unsafe {
+            // SAFETY: The client handle is zero-initialized as expected by this function.
+            // The node handle is kept alive because it is co-owned by the client.
+            // The service name and the options are copied by this function, so they can be dropped
+            // afterwards.
+            rcl_client_init(
+                &mut *handle.lock(),
+                node_handle,
+                type_support,
+                service_name_c_string.as_ptr(),
+                &client_options,
+            )
+            .ok()?;
+        }
+        // SAFETY: The client has been initialized.
+        let writer_guid = unsafe { client_writer_guid(&handle.lock()) };
+        #[cfg(feature = "testing")]
+        let writer_guid = handle
+            .loopback
+            .as_ref()
+            .map(LoopbackEndpoint::writer_guid)
+            .or(writer_guid);
+
+        Ok(Self {
+            handle,
+            requests: Mutex::new(BTreeMap::new()),
+            buffered_responses: options
+                .ordered_responses
+                .then(|| Mutex::new(BTreeMap::new())),
+            max_pending_requests: node.max_pending_requests(),
+            trace_hooks: node.trace_hooks.clone(),
+            writer_guid: Mutex::new(writer_guid),
+        })
+    }
+
+    /// Sends a request and runs the callback with the response when it arrives.
+    ///
+    /// Like [`Publisher::publish`][1], this accepts the request by value or by reference.
+    ///
+    /// Returns the sequence number of the request. In [static memory mode][2], this returns a
+    /// [`BadAlloc`][3] error instead of sending the request when the maximum number of pending
+    /// requests has been reached.
+    ///
+    /// [1]: crate::Publisher::publish
+    /// [2]: crate::Node::enable_static_memory
+    /// [3]: crate::RclReturnCode::BadAlloc
+    pub fn async_send_request_with_callback<'a, R, F>(
+        &self,
+        request: R,
+        callback: F,
+    ) -> Result<i64, RclrsError>
+    where
+        R: MessageCow<'a, T::Request>,
+        F: FnOnce(T::Response) + 'static,
+    {
+        self.send_request(request, BTreeMap::new(), move |response, _| {
+            callback(response)
+        })
+    }
+
+    /// Sends a request with metadata for tracing, and runs the callback with the response and the
+    /// [`TraceContext`] of the request when it arrives.
+    ///
+    /// The metadata is not sent to the service, but returned with the response, after the
+    /// [`TraceHooks`] of the node have had access to it. The request ID in the trace context is
+    /// the one that the service sees, so that the [`TraceContext::correlation_id`] is the same on
+    /// both sides. Before Iron, it is only known once the client has received a response, see
+    /// [`TraceContext::request_id`].
+    ///
+    /// Returns the sequence number of the request, see
+    /// [`Client::async_send_request_with_callback`] for the errors.
+    pub fn async_send_request_with_metadata<'a, R, F>(
+        &self,
+        request: R,
+        metadata: BTreeMap<String, String>,
+        callback: F,
+    ) -> Result<i64, RclrsError>
+    where
+        R: MessageCow<'a, T::Request>,
+        F: FnOnce(T::Response, TraceContext) + 'static,
+    {
+        self.send_request(request, metadata, callback)
+    }
+
+    fn send_request<'a, R, F>(
+        &self,
+        request: R,
+        metadata: BTreeMap<String, String>,
+        callback: F,
+    ) -> Result<i64, RclrsError>
+    where
+        R: MessageCow<'a, T::Request>,
+        F: FnOnce(T::Response, TraceContext) + 'static,
+    {
+        #[cfg(feature = "fault_injection")]
+        self.check_fault(crate::fault_injection::FaultKind::SendRequest)?;
+        let rmw_message = T::Request::into_rmw_message(request.into_cow());
+        let rmw_message_ptr = rmw_message.as_ref() as *const <T::Request as Message>::RmwMsg;
+        // The requests are locked before sending, so that the response can't be taken before the
+        // callback has been stored.
+        let mut requests = self.requests.lock();
+        if let Some(max_pending_requests) = self.max_pending_requests {
+            if requests.len() >= max_pending_requests {
+                return Err(RclrsError {
+                    code: RclReturnCode::BadAlloc,
+                    msg: None,
+                });
+            }
+        }
+        let sequence_number = self.send_rmw_request(rmw_message_ptr)?;
+        let request_id = self
+            .writer_guid
+            .lock()
+            .map(|writer_guid| RequestId::new(writer_guid, sequence_number));
+        let mut context = TraceContext::new(request_id, metadata);
+        self.trace_hooks.request_sent(&mut context);
+        let trace_hooks = self.trace_hooks.clone();
+        requests.insert(
+            sequence_number,
+            Box::new(move |response, request_id| {
+                context.set_request_id(request_id);
+                trace_hooks.response_received(&context);
+                callback(response, context);
+            }),
+        );
+        Ok(sequence_number)
+    }
+
+    // Sends the request through the middleware or the loopback, and returns its sequence number.
+    fn send_rmw_request(
+        &self,
+        rmw_message_ptr: *const <T::Request as Message>::RmwMsg,
+    ) -> Result<i64, RclrsError> {
+        #[cfg(feature = "testing")]
+        if let Some(loopback) = &self.handle.loopback {
+            let type_support = <T::Request as Message>::RmwMsg::get_type_support()
+                as *const rosidl_message_type_support_t;
+            let payload = serialize(rmw_message_ptr as *const _, type_support)?;
+            return Ok(loopback.send_request(payload.as_slice()));
+        }
+        let mut sequence_number = -1;
+        unsafe {
+            // SAFETY: The request type is guaranteed to match the client type by the type system.
+            // The request does not need to be valid beyond the duration of this function call.
+            rcl_send_request(
+                &*self.handle.lock(),
+                rmw_message_ptr as *mut _,
+                &mut sequence_number,
+            )
+        }
+        .ok()?;
+        Ok(sequence_number)
+    }
+
+    /// Sends a request and returns a future for the response.
+    ///
+    /// See [`Client::async_send_request_with_callback`].
+    pub fn call_async<'a, R>(&self, request: R) -> Result<RclFuture<T::Response>, RclrsError>
+    where
+        R: MessageCow<'a, T::Request>,
+    {
+        let (promise, future) = promise();
+        self.async_send_request_with_callback(request, move |response| promise.set(response))?;
+        Ok(future)
+    }
+
+    /// Stops waiting for the response to a request, e.g. because it is considered lost.
+    ///
+    /// The callback of the request is dropped without being run, and a response that arrives
+    /// later is ignored. With [`ClientOptions::ordered_responses`], the callbacks of the later
+    /// requests whose responses were held back by this one are run.
+    ///
+    /// Returns false if the request is not pending, e.g. because its response has already been
+    /// received.
+    pub fn cancel_request(&self, sequence_number: i64) -> bool {
+        // The callbacks are run after releasing the locks, like in `execute()`.
+        let (canceled, ready) = {
+            let mut requests = self.requests.lock();
+            let canceled = requests.remove(&sequence_number);
+            let ready = match (&canceled, &self.buffered_responses) {
+                (Some(_), Some(buffered_responses)) => {
+                    let mut buffered_responses = buffered_responses.lock();
+                    buffered_responses.remove(&sequence_number);
+                    take_ready_responses(&mut requests, &mut buffered_responses)
+                }
+                _ => Vec::new(),
+            };
+            (canceled, ready)
+        };
+        for (callback, (response, request_id)) in ready {
+            self.run_callback(callback, response, request_id);
+        }
+        canceled.is_some()
+    }
+
+    /// Returns the number of requests that have been sent, but whose response has not been
+    /// received yet.
+    pub fn pending_requests(&self) -> usize {
+        self.requests.lock().len()
+    }
+
+    /// Checks if there is a service server for this client.
+    pub fn service_is_ready(&self) -> Result<bool, RclrsError> {
+        #[cfg(feature = "fault_injection")]
+        if self
+            .check_fault(crate::fault_injection::FaultKind::ServiceUnavailable)
+            .is_err()
+        {
+            return Ok(false);
+        }
+        let mut is_ready = false;
+        let client = &*self.handle.lock();
+        let node_handle = &*self.handle.node_handle.lock();
+        // SAFETY: No preconditions for this function (besides passing in valid handles).
+        unsafe { rcl_service_server_is_available(node_handle, client, &mut is_ready) }.ok()?;
+        Ok(is_ready)
+    }
+
+    /// Returns a pointer to the underlying `rcl` client, for calling functions that are not
+    /// wrapped by `rclrs`.
+    ///
+    /// # Safety
+    /// See [`Node::raw_handle`]. The client must not be finalized, and requests must not be sent
+    /// through the pointer, since their responses could not be matched to a callback.
+    pub unsafe fn raw_handle(&self) -> *mut rcl_client_t {
+        &mut *self.handle.lock()
+    }
+
+    /// The ID of the response callbacks in tracepoints, which is the address of the field that
+    /// holds them.
+    ///
+    /// It is stable as long as the client is not moved, which it is not after being put into an
+    /// `Arc` by [`Node::create_client`].
+    pub(crate) fn callback_id(&self) -> *const c_void {
+        &self.requests as *const _ as *const c_void
+    }
+
+    /// Emits the tracepoint that registers the response callbacks of the client.
+    ///
+    /// This must be called once the client is at its final address. There is no tracepoint that
+    /// links the callbacks to the client, so they are registered with the type name of the client.
+    pub(crate) fn trace_init(&self) {
+        // Only allocate the symbol if the tracepoints are enabled.
+        #[cfg(feature = "tracetools")]
+        {
+            let symbol = CString::new(core::any::type_name::<Self>()).unwrap_or_default();
+            tracetools::callback_register(self.callback_id(), symbol.as_ptr());
+        }
+    }
+
+    // Runs the callback of a request with its response, between the callback tracepoints.
+    fn run_callback(
+        &self,
+        callback: RequestCallback<T>,
+        response: T::Response,
+        request_id: RequestId,
+    ) {
+        let callback_id = self.callback_id();
+        tracetools::callback_start(callback_id);
+        callback(response, request_id);
+        tracetools::callback_end(callback_id);
+    }
+
+    #[cfg(feature = "fault_injection")]
+    fn check_fault(&self, kind: crate::fault_injection::FaultKind) -> Result<(), RclrsError> {
+        crate::fault_injection::check(kind, || unsafe {
+            // SAFETY: The client is locked, and the name is copied while it is.
+            crate::fault_injection::name_from_ptr(rcl_client_get_service_name(&*self.handle.lock()))
+        })
+    }
+
+    /// Fetches a new response, together with the ID of its request.
+    ///
+    /// When there is no new response, this will return a
+    /// [`ClientTakeFailed`][1] wrapped in an [`RclrsError`][2].
+    ///
+    /// [1]: crate::ClientErrorCode
+    /// [2]: crate::RclrsError
+    fn take_response(&self) -> Result<(T::Response, RequestId), RclrsError> {
+        let mut rmw_message = <T::Response as Message>::RmwMsg::default();
+        #[cfg(feature = "testing")]
+        if let Some(loopback) = &self.handle.loopback {
+            let frame = loopback.take().ok_or(RclrsError {
+                code: RclReturnCode::ClientError(ClientErrorCode::ClientTakeFailed),
+                msg: None,
+            })?;
+            let type_support = <T::Response as Message>::RmwMsg::get_type_support()
+                as *const rosidl_message_type_support_t;
+            deserialize(
+                &frame.payload,
+                type_support,
+                &mut rmw_message as *mut <T::Response as Message>::RmwMsg as *mut _,
+            )?;
+            return Ok((T::Response::from_rmw_message(rmw_message), frame.request_id));
+        }
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut request_id = unsafe { core::mem::zeroed::<rmw_request_id_t>() };
+        unsafe {
+            // SAFETY: The three pointers are valid/initialized, and do not need to be valid beyond
+            // the function call.
+            rcl_take_response(
+                &*self.handle.lock(),
+                &mut request_id,
+                &mut rmw_message as *mut <T::Response as Message>::RmwMsg as *mut _,
+            )
+        }
+        .ok()?;
+        Ok((
+            T::Response::from_rmw_message(rmw_message),
+            RequestId::from(&request_id),
+        ))
+    }
+}
+
+impl<T> ClientBase for Client<T>
+where
+    T: Service,
+{
+    fn handle(&self) -> &ClientHandle {
+        self.handle.borrow()
+    }
+
+    fn execute(&self) -> Result<(), RclrsError> {
+        let (response, request_id) = match self.take_response() {
+            Ok(response) => response,
+            Err(RclrsError {
+                code: RclReturnCode::ClientError(ClientErrorCode::ClientTakeFailed),
+                ..
+            }) => {
+                // Spurious wakeup – this may happen even when a waitset indicated that this
+                // client was ready, so it shouldn't be an error.
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        self.writer_guid
+            .lock()
+            .get_or_insert(*request_id.writer_guid());
+        let sequence_number = request_id.sequence_number();
+        let buffered_responses = match &self.buffered_responses {
+            Some(buffered_responses) => buffered_responses,
+            None => {
+                // A response without a pending request, e.g. a duplicate, is ignored.
+                let callback = self.requests.lock().remove(&sequence_number);
+                if let Some(callback) = callback {
+                    self.run_callback(callback, response, request_id);
+                }
+                return Ok(());
+            }
+        };
+        // The callbacks are run after releasing the locks, so that they can send new requests.
+        let ready = {
+            let mut requests = self.requests.lock();
+            if !requests.contains_key(&sequence_number) {
+                return Ok(());
+            }
+            let mut buffered_responses = buffered_responses.lock();
+            buffered_responses.insert(sequence_number, (response, request_id));
+            take_ready_responses(&mut requests, &mut buffered_responses)
+        };
+        for (callback, (response, request_id)) in ready {
+            self.run_callback(callback, response, request_id);
+        }
+        Ok(())
+    }
+}
+
+// Removes the oldest pending requests whose responses have arrived, up to the first one whose
+// response is missing, and returns them in order together with their responses.
+fn take_ready_responses<C, R>(
+    requests: &mut BTreeMap<i64, C>,
+    buffered_responses: &mut BTreeMap<i64, R>,
+) -> Vec<(C, R)> {
+    // Sequence numbers are increasing, so the oldest pending request comes first.
+    let mut ready = Vec::new();
+    while let Some(entry) = requests.first_entry() {
+        match buffered_responses.remove(entry.key()) {
+            Some(response) => ready.push((entry.remove(), response)),
+            None => break,
+        }
+    }
+    ready
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_responses_are_ready_up_to_the_first_missing_one() {
+        let mut requests: BTreeMap<i64, &str> = [(1, "a"), (2, "b"), (3, "c")].into();
+        let mut buffered_responses: BTreeMap<i64, i32> = [(2, 20), (3, 30)].into();
+        assert!(take_ready_responses(&mut requests, &mut buffered_responses).is_empty());
+        // Canceling the request whose response is missing releases the later ones.
+        requests.remove(&1);
+        assert_eq!(
+            take_ready_responses(&mut requests, &mut buffered_responses),
+            [("b", 20), ("c", 30)]
+        );
+        assert!(requests.is_empty());
+        assert!(buffered_responses.is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+use crate::future::RclFuture;
+use crate::{Client, MessageCow, RclrsError};
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use rosidl_runtime_rs::Service;
+
+/// A set of clients for the same service, which spreads concurrent requests across them.
+///
+/// A single client handles its responses one by one, in the order of their sequence numbers. For
+/// high-throughput workloads with many outstanding requests, such as a map tile server, this can
+/// become a bottleneck. A pool sends each request with the client that has the fewest pending
+/// requests, and every response is delivered to the callback or future of its own request.
+///
+/// Create a pool with [`Node::create_client_pool`][1].
+///
+/// [1]: crate::Node::create_client_pool
+pub struct ClientPool<T>
+where
+    T: Service,
+{
+    clients: Vec<Arc<Client<T>>>,
+    // Where the search for the least busy client starts, so that ties are broken round-robin.
+    next: AtomicUsize,
+}
+
+impl<T> ClientPool<T>
+where
+    T: Service,
+{
+    /// Creates a pool from existing clients, which must not be empty.
+    pub(crate) fn new(clients: Vec<Arc<Client<T>>>) -> Self {
+        debug_assert!(!clients.is_empty());
+        Self {
+            clients,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Sends a request with the least busy client and runs the callback with the response.
+    ///
+    /// See [`Client::async_send_request_with_callback`]. Note that the returned sequence number is
+    /// only unique per client, not per pool.
+    pub fn async_send_request_with_callback<'a, R, F>(
+        &self,
+        request: R,
+        callback: F,
+    ) -> Result<i64, RclrsError>
+    where
+        R: MessageCow<'a, T::Request>,
+        F: FnOnce(T::Response) + 'static,
+    {
+        self.least_busy_client()
+            .async_send_request_with_callback(request, callback)
+    }
+
+    /// Sends a request with the least busy client and returns a future for the response.
+    ///
+    /// See [`Client::call_async`].
+    pub fn call_async<'a, R>(&self, request: R) -> Result<RclFuture<T::Response>, RclrsError>
+    where
+        R: MessageCow<'a, T::Request>,
+    {
+        self.least_busy_client().call_async(request)
+    }
+
+    /// Returns the total number of requests that have been sent, but whose response has not been
+    /// received yet.
+    pub fn pending_requests(&self) -> usize {
+        self.clients
+            .iter()
+            .map(|client| client.pending_requests())
+            .sum()
+    }
+
+    /// Checks if there is a service server for the clients of this pool.
+    pub fn service_is_ready(&self) -> Result<bool, RclrsError> {
+        self.clients[0].service_is_ready()
+    }
+
+    /// Returns the clients of this pool.
+    pub fn clients(&self) -> &[Arc<Client<T>>] {
+        &self.clients
+    }
+
+    fn least_busy_client(&self) -> &Client<T> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let len = self.clients.len();
+        (0..len)
+            .map(|offset| &self.clients[(start + offset) % len])
+            .min_by_key(|client| client.pending_requests())
+            .unwrap()
+    }
+}
@@ -0,0 +1,302 @@
+use crate::node::statistics::{Accumulator, MetricsMessage};
+use crate::sync::Mutex;
+use crate::topic_gate::{Trigger, Trigger_Request, Trigger_Response};
+use crate::{
+    Client, Clock, ClockType, Node, Publisher, RclrsError, Service, Time, Timer,
+    QOS_PROFILE_DEFAULT, QOS_PROFILE_SERVICES_DEFAULT,
+};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rosidl_runtime_rs::String as RosString;
+
+// The name of the service that returns the ROS time of a node, relative to the node.
+const TIME_SERVICE_NAME: &str = "~/get_time";
+
+/// Options for a [`ClockSyncMonitor`].
+///
+/// New options may be added in the future, so it is best to create this with the
+/// `..Default::default()` syntax.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClockSyncOptions {
+    /// The topic that the offsets are published on, `/clock_sync` by default.
+    pub topic: String,
+    /// How often the offsets are measured, 100 milliseconds by default.
+    pub sample_period: Duration,
+    /// How often the offsets are published, 1 second by default.
+    pub publish_period: Duration,
+    /// The fully qualified names of the nodes to compare the ROS time with, e.g.
+    /// `/robot/controller`.
+    ///
+    /// The time of each node is requested from its `get_time` service, see
+    /// [`ClockSyncOptions::serve_time`].
+    pub peers: Vec<String>,
+    /// Whether the node serves its own ROS time, so that other nodes can compare their time with
+    /// it. This is `true` by default.
+    pub serve_time: bool,
+}
+
+impl Default for ClockSyncOptions {
+    fn default() -> Self {
+        Self {
+            topic: "/clock_sync".into(),
+            sample_period: Duration::from_millis(100),
+            publish_period: Duration::from_secs(1),
+            peers: Vec::new(),
+            serve_time: true,
+        }
+    }
+}
+
+// The measurements of the offset to another node.
+struct Peer {
+    name: String,
+    client: Arc<Client<Trigger>>,
+    // The ROS time at which the request that has not been responded to yet was sent.
+    pending: Option<Time>,
+    last_offset: Option<i64>,
+    offset: Accumulator,
+    round_trip: Accumulator,
+}
+
+struct ClockSyncState {
+    last_ros_time_offset: Option<i64>,
+    ros_time_offset: Accumulator,
+    peers: Vec<Peer>,
+}
+
+impl ClockSyncState {
+    // Records the response of a peer, using the midpoint of the round trip as the local time at
+    // which the peer read its clock.
+    fn record_response(&mut self, peer: usize, sent: Time, response: Trigger_Response, now: Time) {
+        let peer = &mut self.peers[peer];
+        // A response to a request that was given up is not counted.
+        if peer.pending != Some(sent) {
+            return;
+        }
+        peer.pending = None;
+        if !response.success {
+            return;
+        }
+        let Ok(peer_time) = response.message.to_string().parse::<i64>() else {
+            return;
+        };
+        let round_trip = now.nsec - sent.nsec;
+        let offset = peer_time - (sent.nsec + round_trip / 2);
+        peer.last_offset = Some(offset);
+        peer.offset.add(offset as f64 / 1e6);
+        peer.round_trip.add(round_trip as f64 / 1e6);
+    }
+}
+
+/// Measures the offsets of the ROS time of a node to the system time and to other nodes, and
+/// publishes them, for debugging time synchronization problems.
+///
+/// Two kinds of offsets are measured:
+/// - `ros_time_offset`: The ROS time of the node minus the system time. This is zero unless the
+///   node uses simulated time, and shows e.g. how far a simulation lags behind the wall clock.
+/// - `clock_offset:<peer>`: The ROS time of the peer node minus the ROS time of this node, for
+///   each of the [`ClockSyncOptions::peers`]. It is estimated from the round trip of a request
+///   to the `get_time` service of the peer, whose duration is published as `round_trip:<peer>`.
+///   The estimate is only as accurate as the round trip is symmetric.
+///
+/// The offsets are published in milliseconds as `statistics_msgs/msg/MetricsMessage`s, like the
+/// [node statistics][1], with the fully qualified node name as the measurement source name.
+///
+/// The `get_time` service of a node has the type `std_srvs/srv/Trigger`, and responds with the
+/// ROS time of the node in nanoseconds, formatted as a decimal integer in the `message` field.
+/// Nodes in other languages can serve the same, to be compared with `rclrs` nodes.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let monitor = ClockSyncMonitor::new(
+///     &mut node,
+///     ClockSyncOptions {
+///         peers: vec!["/simulator".into()],
+///         ..Default::default()
+///     },
+/// )?;
+/// rclrs::spin(&node)?;
+/// ```
+///
+/// [1]: crate::NodeStatisticsOptions
+pub struct ClockSyncMonitor {
+    state: Arc<Mutex<ClockSyncState>>,
+    _sample_timer: Arc<Timer>,
+    _publish_timer: Arc<Timer>,
+    _service: Option<Arc<Service<Trigger>>>,
+}
+
+impl ClockSyncMonitor {
+    /// Creates the timers, clients and service of the monitor on the node.
+    ///
+    /// The offsets are only measured and published while the monitor is kept, and the node is
+    /// spinning.
+    pub fn new(node: &mut Node, options: ClockSyncOptions) -> Result<Self, RclrsError> {
+        let clock = node.get_clock();
+        let service = if options.serve_time {
+            let clock = clock.clone();
+            Some(node.create_service::<Trigger, _>(
+                TIME_SERVICE_NAME,
+                QOS_PROFILE_SERVICES_DEFAULT,
+                move |_: Trigger_Request| match clock.now() {
+                    Ok(now) => Trigger_Response {
+                        success: true,
+                        message: now.nsec.to_string().as_str().into(),
+                    },
+                    Err(e) => Trigger_Response {
+                        success: false,
+                        message: e.to_string().as_str().into(),
+                    },
+                },
+            )?)
+        } else {
+            None
+        };
+        let peers = options
+            .peers
+            .iter()
+            .map(|name| {
+                let service_name = format!("{}/get_time", name.trim_end_matches('/'));
+                Ok(Peer {
+                    name: name.clone(),
+                    client: node.create_client(&service_name, QOS_PROFILE_SERVICES_DEFAULT)?,
+                    pending: None,
+                    last_offset: None,
+                    offset: Accumulator::default(),
+                    round_trip: Accumulator::default(),
+                })
+            })
+            .collect::<Result<_, RclrsError>>()?;
+        let state = Arc::new(Mutex::new(ClockSyncState {
+            last_ros_time_offset: None,
+            ros_time_offset: Accumulator::default(),
+            peers,
+        }));
+        let sample_timer = Self::create_sample_timer(node, &state, &options, &clock)?;
+        let publisher =
+            node.create_publisher::<MetricsMessage>(&options.topic, QOS_PROFILE_DEFAULT)?;
+        let publish_timer = Self::create_publish_timer(node, &state, &options, publisher, clock)?;
+        Ok(Self {
+            state,
+            _sample_timer: sample_timer,
+            _publish_timer: publish_timer,
+            _service: service,
+        })
+    }
+
+    /// Returns the last measured offset of the ROS time to the system time, in nanoseconds.
+    pub fn ros_time_offset(&self) -> Option<i64> {
+        self.state.lock().last_ros_time_offset
+    }
+
+    /// Returns the last measured offset of the ROS time of a peer to the ROS time of this node,
+    /// in nanoseconds.
+    ///
+    /// Returns `None` if the peer has not responded yet, or is not one of the
+    /// [`ClockSyncOptions::peers`].
+    pub fn peer_offset(&self, peer: &str) -> Option<i64> {
+        let state = self.state.lock();
+        let peer = state.peers.iter().find(|p| p.name == peer)?;
+        peer.last_offset
+    }
+
+    fn create_sample_timer(
+        node: &mut Node,
+        state: &Arc<Mutex<ClockSyncState>>,
+        options: &ClockSyncOptions,
+        clock: &Clock,
+    ) -> Result<Arc<Timer>, RclrsError> {
+        let state = Arc::clone(state);
+        let clock = clock.clone();
+        let system_clock = Clock::new(ClockType::SystemTime)?;
+        // A request that is not responded to within this time is given up.
+        let request_timeout = options.publish_period.max(options.sample_period);
+        node.add_timer(
+            options.sample_period,
+            Clock::new(ClockType::SteadyTime)?,
+            move || {
+                let now = clock.now()?;
+                let ros_time_offset = now.nsec - system_clock.now()?.nsec;
+                let mut guard = state.lock();
+                guard.last_ros_time_offset = Some(ros_time_offset);
+                guard.ros_time_offset.add(ros_time_offset as f64 / 1e6);
+                for index in 0..guard.peers.len() {
+                    let peer = &mut guard.peers[index];
+                    if let Some(sent) = peer.pending {
+                        if now.saturating_duration_since(sent) < request_timeout {
+                            continue;
+                        }
+                    }
+                    if !peer.client.service_is_ready()? {
+                        peer.pending = None;
+                        continue;
+                    }
+                    let state = Arc::clone(&state);
+                    let clock = clock.clone();
+                    peer.client.async_send_request_with_callback(
+                        &Trigger_Request::default(),
+                        move |response: Trigger_Response| {
+                            if let Ok(received) = clock.now() {
+                                state.lock().record_response(index, now, response, received);
+                            }
+                        },
+                    )?;
+                    peer.pending = Some(now);
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn create_publish_timer(
+        node: &mut Node,
+        state: &Arc<Mutex<ClockSyncState>>,
+        options: &ClockSyncOptions,
+        publisher: Publisher<MetricsMessage>,
+        clock: Clock,
+    ) -> Result<Arc<Timer>, RclrsError> {
+        let state = Arc::clone(state);
+        let measurement_source_name = RosString::from(node.fully_qualified_name().as_str());
+        let mut window_start = clock.now()?;
+        node.add_timer(
+            options.publish_period,
+            Clock::new(ClockType::SteadyTime)?,
+            move || {
+                let window_stop = clock.now()?;
+                let mut metrics = Vec::new();
+                {
+                    let state = &mut *state.lock();
+                    metrics.push((
+                        String::from("ros_time_offset"),
+                        core::mem::take(&mut state.ros_time_offset),
+                    ));
+                    for peer in &mut state.peers {
+                        metrics.push((
+                            format!("clock_offset:{}", peer.name),
+                            core::mem::take(&mut peer.offset),
+                        ));
+                        metrics.push((
+                            format!("round_trip:{}", peer.name),
+                            core::mem::take(&mut peer.round_trip),
+                        ));
+                    }
+                }
+                for (metrics_source, accumulator) in metrics {
+                    publisher.publish(MetricsMessage {
+                        measurement_source_name: measurement_source_name.clone(),
+                        metrics_source: metrics_source.as_str().into(),
+                        unit: "ms".into(),
+                        window_start: window_start.into(),
+                        window_stop: window_stop.into(),
+                        statistics: accumulator.data_points(),
+                    })?;
+                }
+                window_start = window_stop;
+                Ok(())
+            },
+        )
+    }
+}
@@ -0,0 +1,368 @@
+use crate::allocator::copy_rcutils_allocator;
+use crate::error::ToResult;
+use crate::node::service::{
+    send_response, take_pending_requests, CachedResponse, OverflowHandler, ResponseCache,
+    ServiceState,
+};
+use crate::node::trace_context::RunTraceHooks;
+use crate::qos::QoSProfile;
+use crate::sync::Mutex;
+#[cfg(feature = "testing")]
+use crate::testing::{EndpointKind, LoopbackEndpoint};
+use crate::tracetools;
+use crate::{rcl_bindings::*, RclrsError};
+use crate::{
+    Node, RequestId, ServiceBase, ServiceHandle, ServiceOptions, ServiceSheddingPolicy,
+    ServiceStatistics, TraceContext, TraceHooks,
+};
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::ffi::CString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicI32, Ordering};
+
+use rosidl_runtime_rs::Service;
+
+type DeferredServiceCallback<T> =
+    Box<dyn FnMut(<T as Service>::Request, ServiceResponder<T>) + 'static>;
+
+/// The handle for responding to one request of a [`DeferredService`].
+///
+/// The responder can be sent to another thread, and used after the callback has returned. The
+/// request counts as in flight until the responder is consumed by [`ServiceResponder::respond`]
+/// or dropped. Dropping it without responding abandons the request, and the client never
+/// receives a response.
+pub struct ServiceResponder<T>
+where
+    T: Service,
+{
+    handle: Arc<ServiceHandle>,
+    state: Arc<ServiceState>,
+    response_cache: Arc<Mutex<ResponseCache<T::Response>>>,
+    request_id: RequestId,
+    trace_context: TraceContext,
+    trace_hooks: Option<Arc<TraceHooks>>,
+    responded: bool,
+    _service: PhantomData<fn() -> T>,
+}
+
+impl<T> Drop for ServiceResponder<T>
+where
+    T: Service,
+{
+    fn drop(&mut self) {
+        if !self.responded {
+            self.response_cache.lock().abandon(&self.request_id);
+        }
+        self.state.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl<T> ServiceResponder<T>
+where
+    T: Service,
+{
+    /// Returns the ID of the request, e.g. for correlating it with log output of the client.
+    pub fn request_id(&self) -> &RequestId {
+        &self.request_id
+    }
+
+    /// Returns the [`TraceContext`] of the request, with the metadata that the
+    /// [`TraceHooks::on_request_received`][1] hook has added.
+    ///
+    /// [1]: crate::TraceHooks::on_request_received
+    pub fn trace_context(&self) -> &TraceContext {
+        &self.trace_context
+    }
+
+    /// Returns the [`TraceContext`] of the request for adding metadata, which is visible to the
+    /// [`TraceHooks::on_response_sent`][1] hook.
+    ///
+    /// [1]: crate::TraceHooks::on_response_sent
+    pub fn trace_context_mut(&mut self) -> &mut TraceContext {
+        &mut self.trace_context
+    }
+
+    /// Sends the response to the request.
+    pub fn respond(mut self, response: T::Response) -> Result<(), RclrsError> {
+        let request_id = self.request_id;
+        {
+            let response_cache = &mut *self.response_cache.lock();
+            if response_cache.is_enabled() {
+                response_cache.insert(&request_id, response.clone());
+            }
+        }
+        self.responded = true;
+        send_response::<T>(&self.handle, &request_id, response)?;
+        self.state.statistics.lock().responses_sent += 1;
+        self.trace_hooks.response_sent(&self.trace_context);
+        Ok(())
+    }
+}
+
+/// A service whose callback responds to requests later, through a [`ServiceResponder`].
+///
+/// This allows serving several requests concurrently, e.g. by passing the work and the responder
+/// to a thread pool, without blocking the executor. To keep an overloaded service from
+/// accumulating an unbounded amount of work, the number of requests in flight can be limited with
+/// [`ServiceOptions::max_concurrent_requests`]. The requests that arrive while the limit is
+/// reached are shed according to the [`ServiceSheddingPolicy`], and counted in the
+/// [`ServiceStatistics`].
+///
+/// Create it with [`Node::create_deferred_service`][1]. Receiving requests requires spinning the
+/// service's node, like for a [`Service`][2].
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let options = ServiceOptions {
+///     max_concurrent_requests: Some(4),
+///     shedding_policy: ServiceSheddingPolicy::RespondDefault,
+///     ..Default::default()
+/// };
+/// let service = node.create_deferred_service::<example_interfaces::srv::AddTwoInts, _>(
+///     "add_two_ints",
+///     QOS_PROFILE_SERVICES_DEFAULT,
+///     options,
+///     |request, responder| {
+///         std::thread::spawn(move || {
+///             let sum = request.a + request.b;
+///             let _ = responder.respond(example_interfaces::srv::AddTwoInts_Response { sum });
+///         });
+///     },
+/// )?;
+/// ```
+///
+/// [1]: crate::Node::create_deferred_service
+/// [2]: crate::Service
+pub struct DeferredService<T>
+where
+    T: Service,
+{
+    handle: Arc<ServiceHandle>,
+    callback: Mutex<DeferredServiceCallback<T>>,
+    options: ServiceOptions,
+    overflow_handler: OverflowHandler,
+    state: Arc<ServiceState>,
+    response_cache: Arc<Mutex<ResponseCache<T::Response>>>,
+    // Reused between executions, so that no allocations happen once it has grown to the depth.
+    // In static memory mode, it is allocated with the maximum length up front.
+    pending_requests: Mutex<Vec<(T::Request, RequestId)>>,
+    max_pending_requests: Option<usize>,
+    trace_hooks: Option<Arc<TraceHooks>>,
+}
+
+impl<T> DeferredService<T>
+where
+    T: Service,
+{
+    /// Creates a new deferred service.
+    ///
+    /// # Panics
+    /// When the service name contains interior null bytes.
+    pub fn new<F>(
+        node: &Node,
+        service_name: &str,
+        qos: QoSProfile,
+        options: ServiceOptions,
+        callback: F,
+    ) -> Result<Self, RclrsError>
+    where
+        F: FnMut(T::Request, ServiceResponder<T>) + 'static,
+    {
+        // This is declared before the node handle guard, since dropping it in the error case
+        // locks the node handle.
+        let service_name = node.expand_topic_name(service_name)?;
+        #[cfg_attr(not(feature = "testing"), allow(unused_variables))]
+        let handle = Arc::new_cyclic(|handle| ServiceHandle {
+            // SAFETY: Getting a zero-initialized value is always safe.
+            handle: Mutex::new(unsafe { rcl_get_zero_initialized_service() }),
+            node_handle: node.handle.clone(),
+            priority: AtomicI32::new(0),
+            #[cfg(feature = "testing")]
+            loopback: LoopbackEndpoint::new(node, handle, EndpointKind::Service, &service_name),
+        });
+        let type_support =
+            <T as Service>::get_type_support() as *const rosidl_service_type_support_t;
+        let service_name_c_string = CString::new(service_name).unwrap();
+        let node_handle = &mut *node.handle.lock();
+
+        // SAFETY: No preconditions for this function.
+        let mut service_options = unsafe { rcl_service_get_default_options() };
+        service_options.qos = qos.into();
+        service_options.allocator = copy_rcutils_allocator(&node.allocator);
+        unsafe {
+            // SAFETY: The service handle is zero-initialized as expected by this function.
+            // The node handle is kept alive because it is co-owned by the service.
+            // The service name and the options are copied by this function, so they can be dropped
+            // afterwards.
+            rcl_service_init(
+                &mut *handle.lock(),
+                node_handle,
+                type_support,
+                service_name_c_string.as_ptr(),
+                &service_options,
+            )
+            .ok()?;
+        }
+
+        Ok(Self {
+            handle,
+            callback: Mutex::new(Box::new(callback)),
+            options,
+            overflow_handler: OverflowHandler::new(node, &qos, options.overflow_policy),
+            state: ServiceState::new(),
+            response_cache: Arc::new(Mutex::new(ResponseCache::new(options.response_cache_size))),
+            pending_requests: Mutex::new(Vec::with_capacity(
+                node.max_pending_requests().unwrap_or(0),
+            )),
+            max_pending_requests: node.max_pending_requests(),
+            trace_hooks: node.trace_hooks.clone(),
+        })
+    }
+
+    /// Returns the depth of the request queue, from the history policy of the QoS profile.
+    ///
+    /// Returns `None` for the `KeepAll` history policy, with which the queue is unbounded.
+    pub fn request_queue_depth(&self) -> Option<usize> {
+        self.overflow_handler.request_queue_depth
+    }
+
+    /// Returns the number of requests that have been passed to the callback, and whose responder
+    /// has not been consumed or dropped yet.
+    pub fn in_flight_requests(&self) -> usize {
+        self.state.in_flight.load(Ordering::Acquire)
+    }
+
+    /// Returns the counters of received requests, shed requests etc. since the service was
+    /// created.
+    pub fn statistics(&self) -> ServiceStatistics {
+        *self.state.statistics.lock()
+    }
+
+    /// Returns the priority of the service for the executor.
+    ///
+    /// See [`Subscription::set_priority`][1].
+    ///
+    /// [1]: crate::Subscription::set_priority
+    pub fn priority(&self) -> i32 {
+        self.handle.priority()
+    }
+
+    /// Sets the priority of the service for the executor.
+    ///
+    /// See [`Subscription::set_priority`][1].
+    ///
+    /// [1]: crate::Subscription::set_priority
+    pub fn set_priority(&self, priority: i32) {
+        self.handle.priority.store(priority, Ordering::Relaxed);
+    }
+
+    // Reserves a slot for a request, unless the limit of concurrent requests is reached.
+    fn try_reserve(&self) -> bool {
+        let limit = self.options.max_concurrent_requests.unwrap_or(usize::MAX);
+        let reserved =
+            self.state
+                .in_flight
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |in_flight| {
+                    (in_flight < limit).then_some(in_flight + 1)
+                });
+        match reserved {
+            Ok(in_flight) => {
+                let statistics = &mut *self.state.statistics.lock();
+                statistics.max_in_flight_requests =
+                    statistics.max_in_flight_requests.max(in_flight + 1);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// The ID of the callback in tracepoints, which is the address of the callback field.
+    ///
+    /// It is stable as long as the deferred service is not moved, which it is not after being put into an
+    /// `Arc` by [`Node::create_deferred_service`].
+    pub(crate) fn callback_id(&self) -> *const c_void {
+        &self.callback as *const _ as *const c_void
+    }
+
+    /// Emits the tracepoints that register the callback of the deferred service.
+    ///
+    /// This must be called once the deferred service is at its final address.
+    #[cfg_attr(not(feature = "tracetools"), allow(unused_variables))]
+    pub(crate) fn trace_init(&self, callback_symbol: &str) {
+        // Only lock the handle and allocate the symbol if the tracepoints are enabled.
+        #[cfg(feature = "tracetools")]
+        {
+            let handle = &*self.handle.lock() as *const rcl_service_t;
+            tracetools::service_callback_added(handle as *const _, self.callback_id());
+            let symbol = CString::new(callback_symbol).unwrap_or_default();
+            tracetools::callback_register(self.callback_id(), symbol.as_ptr());
+        }
+    }
+}
+
+impl<T> ServiceBase for DeferredService<T>
+where
+    T: Service,
+{
+    fn handle(&self) -> &ServiceHandle {
+        &self.handle
+    }
+
+    fn execute(&self) -> Result<(), RclrsError> {
+        let pending_requests = &mut *self.pending_requests.lock();
+        take_pending_requests::<T>(&self.handle, pending_requests, self.max_pending_requests)?;
+        if pending_requests.is_empty() {
+            return Ok(());
+        }
+        self.overflow_handler
+            .handle(&mut self.state.statistics.lock(), pending_requests);
+        for (request, request_id) in pending_requests.drain(..) {
+            match self.response_cache.lock().check(&request_id) {
+                CachedResponse::New => {}
+                CachedResponse::Pending => {
+                    self.state.statistics.lock().duplicate_requests += 1;
+                    continue;
+                }
+                CachedResponse::Ready(response) => {
+                    self.state.statistics.lock().duplicate_requests += 1;
+                    send_response::<T>(&self.handle, &request_id, response.clone())?;
+                    self.state.statistics.lock().responses_sent += 1;
+                    continue;
+                }
+            }
+            if !self.try_reserve() {
+                // A retransmission of a shed request may be served once there is capacity.
+                self.response_cache.lock().abandon(&request_id);
+                self.state.statistics.lock().requests_shed += 1;
+                if self.options.shedding_policy == ServiceSheddingPolicy::RespondDefault {
+                    send_response::<T>(&self.handle, &request_id, Default::default())?;
+                    self.state.statistics.lock().responses_sent += 1;
+                }
+                continue;
+            }
+            let mut trace_context = TraceContext::new(Some(request_id), BTreeMap::new());
+            self.trace_hooks.request_received(&mut trace_context);
+            let responder = ServiceResponder {
+                handle: Arc::clone(&self.handle),
+                state: Arc::clone(&self.state),
+                response_cache: Arc::clone(&self.response_cache),
+                request_id,
+                trace_context,
+                trace_hooks: self.trace_hooks.clone(),
+                responded: false,
+                _service: PhantomData,
+            };
+            let callback_id = self.callback_id();
+            tracetools::callback_start(callback_id);
+            (*self.callback.lock())(request, responder);
+            tracetools::callback_end(callback_id);
+        }
+        Ok(())
+    }
+}
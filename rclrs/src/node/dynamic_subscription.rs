@@ -0,0 +1,177 @@
+use crate::allocator::copy_rcutils_allocator;
+use crate::error::{RclReturnCode, SubscriberErrorCode, ToResult};
+use crate::qos::QoSProfile;
+use crate::sync::Mutex;
+use crate::tracetools;
+use crate::{rcl_bindings::*, RclrsError};
+use crate::{
+    DynamicMessage, DynamicMessageMetadata, Node, SubscriptionBase, SubscriptionHandle, TypeHash,
+};
+
+use std::borrow::Borrow;
+use std::ffi::{c_void, CString};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Arc;
+
+/// Struct for receiving messages of a type that is only known at runtime.
+///
+/// This is like a [`Subscription`][1], but receives [`DynamicMessage`]s. The type support of the
+/// message type is loaded by name, see [`DynamicMessageMetadata::new`].
+///
+/// Create a dynamic subscription with [`Node::create_dynamic_subscription`].
+///
+/// [1]: crate::Subscription
+pub struct DynamicSubscription {
+    pub(crate) handle: Arc<SubscriptionHandle>,
+    metadata: DynamicMessageMetadata,
+    /// The callback function that runs when a message was received.
+    pub callback: Mutex<Box<dyn FnMut(DynamicMessage) + 'static>>,
+}
+
+impl DynamicSubscription {
+    /// Creates a new dynamic subscription.
+    ///
+    /// # Panics
+    /// When the topic contains interior null bytes.
+    pub fn new<F>(
+        node: &Node,
+        topic: &str,
+        type_name: &str,
+        qos: QoSProfile,
+        callback: F,
+    ) -> Result<Self, RclrsError>
+    where
+        F: FnMut(DynamicMessage) + 'static,
+    {
+        let metadata = DynamicMessageMetadata::new(type_name)?;
+        // This is declared before the node handle guard, since dropping it in the error case
+        // locks the node handle.
+        let handle = Arc::new(SubscriptionHandle {
+            // SAFETY: Getting a zero-initialized value is always safe.
+            handle: Mutex::new(unsafe { rcl_get_zero_initialized_subscription() }),
+            node_handle: node.handle.clone(),
+            _rmw_specific_options: None,
+            _qos_parameters: Vec::new(),
+            priority: AtomicI32::new(0),
+            owned: true,
+            #[cfg(feature = "testing")]
+            loopback: None,
+        });
+        let topic_c_string = CString::new(node.expand_topic_name(topic)?).unwrap();
+        let node_handle = &mut *node.handle.lock();
+
+        // SAFETY: No preconditions for this function.
+        let mut subscription_options = unsafe { rcl_subscription_get_default_options() };
+        subscription_options.qos = qos.into();
+        subscription_options.allocator = copy_rcutils_allocator(&node.allocator);
+        unsafe {
+            // SAFETY: The subscription handle is zero-initialized as expected by this function.
+            // The node handle is kept alive because it is co-owned by the subscription.
+            // The type support is kept alive by the metadata.
+            // The topic name and the options are copied by this function, so they can be dropped
+            // afterwards.
+            rcl_subscription_init(
+                &mut *handle.lock(),
+                node_handle,
+                metadata.type_support(),
+                topic_c_string.as_ptr(),
+                &subscription_options,
+            )
+            .ok()?;
+        }
+
+        Ok(Self {
+            handle,
+            metadata,
+            callback: Mutex::new(Box::new(callback)),
+        })
+    }
+
+    /// Returns the type support of the message type.
+    pub fn metadata(&self) -> &DynamicMessageMetadata {
+        &self.metadata
+    }
+
+    /// Returns the [REP 2011][1] hash of the message type, or `None` before ROS 2 Iron.
+    ///
+    /// [1]: https://ros.org/reps/rep-2011.html
+    pub fn type_hash(&self) -> Option<TypeHash> {
+        // SAFETY: The type support is valid, since the metadata keeps its library loaded.
+        unsafe { TypeHash::from_type_support(self.metadata.type_support()) }
+    }
+
+    /// Returns the priority of the subscription for the executor.
+    ///
+    /// See [`Subscription::set_priority`][1].
+    ///
+    /// [1]: crate::Subscription::set_priority
+    pub fn priority(&self) -> i32 {
+        self.handle.priority()
+    }
+
+    /// Sets the priority of the subscription for the executor.
+    ///
+    /// See [`Subscription::set_priority`][1].
+    ///
+    /// [1]: crate::Subscription::set_priority
+    pub fn set_priority(&self, priority: i32) {
+        self.handle.priority.store(priority, Ordering::Relaxed);
+    }
+
+    /// Fetches a new message.
+    ///
+    /// See [`Subscription::take`][1] for the errors. Additionally, an [`Unsupported`][2] error
+    /// is returned for messages with `long double` fields.
+    ///
+    /// [1]: crate::Subscription::take
+    /// [2]: crate::RclReturnCode::Unsupported
+    pub fn take(&self) -> Result<DynamicMessage, RclrsError> {
+        let mut raw_message = self.metadata.new_raw_message();
+        unsafe {
+            // SAFETY: The message has the type that the subscription was created with. The
+            // message info and the allocation are explicitly allowed to be NULL.
+            rcl_take(
+                &*self.handle.lock(),
+                raw_message.as_mut_ptr(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        }
+        .ok()?;
+        raw_message.to_dynamic_message()
+    }
+
+    fn callback_id(&self) -> *const c_void {
+        &self.callback as *const _ as *const c_void
+    }
+}
+
+impl SubscriptionBase for DynamicSubscription {
+    fn handle(&self) -> &SubscriptionHandle {
+        self.handle.borrow()
+    }
+
+    fn execute(&self) -> Result<(), RclrsError> {
+        // Only lock the handle for getting its address if the tracepoint is enabled.
+        #[cfg(feature = "tracetools")]
+        tracetools::executor_execute(&*self.handle.lock() as *const _ as *const _);
+        let msg = match self.take() {
+            Ok(msg) => msg,
+            Err(RclrsError {
+                code: RclReturnCode::SubscriberError(SubscriberErrorCode::SubscriptionTakeFailed),
+                ..
+            }) => {
+                // Spurious wakeup – this may happen even when a waitset indicated that this
+                // subscription was ready, so it shouldn't be an error.
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        let callback = &mut *self.callback.lock();
+        let callback_id = self.callback_id();
+        tracetools::callback_start(callback_id);
+        callback(msg);
+        tracetools::callback_end(callback_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use crate::allocator::copy_rcutils_allocator;
+use crate::error::ToResult;
+use crate::node::payload_transform::borrowed_serialized_message;
+use crate::qos::QoSProfile;
+use crate::sync::Mutex;
+use crate::{rcl_bindings::*, RclrsError};
+use crate::{DynamicMessageMetadata, Node, PublisherHandle};
+
+use std::ffi::CString;
+use std::sync::Arc;
+
+/// Struct for sending messages of a type that is only known at runtime, in their serialized form.
+///
+/// This is the counterpart of a [`GenericSubscription`][1], e.g. for replaying recorded messages
+/// with the [`Player`][2]. The messages must be serialized in the format of the RMW
+/// implementation, which is CDR for all Tier 1 RMW implementations.
+///
+/// Create a generic publisher with [`Node::create_generic_publisher`].
+///
+/// [1]: crate::GenericSubscription
+/// [2]: crate::Player
+pub struct GenericPublisher {
+    handle: Arc<PublisherHandle>,
+    metadata: DynamicMessageMetadata,
+}
+
+impl GenericPublisher {
+    /// Creates a new generic publisher.
+    ///
+    /// # Panics
+    /// When the topic contains interior null bytes.
+    pub fn new(
+        node: &Node,
+        topic: &str,
+        type_name: &str,
+        qos: QoSProfile,
+    ) -> Result<Self, RclrsError> {
+        let metadata = DynamicMessageMetadata::new(type_name)?;
+        // This is declared before the node handle guard, since dropping it in the error case
+        // locks the node handle.
+        let handle = Arc::new(PublisherHandle {
+            // SAFETY: Getting a zero-initialized value is always safe.
+            handle: Mutex::new(unsafe { rcl_get_zero_initialized_publisher() }),
+            node_handle: node.handle.clone(),
+            _rmw_specific_options: None,
+            _qos_parameters: Vec::new(),
+            owned: true,
+        });
+        let topic_c_string = CString::new(node.expand_topic_name(topic)?).unwrap();
+        let node_handle = &mut *node.handle.lock();
+
+        // SAFETY: No preconditions for this function.
+        let mut publisher_options = unsafe { rcl_publisher_get_default_options() };
+        publisher_options.qos = qos.into();
+        publisher_options.allocator = copy_rcutils_allocator(&node.allocator);
+        unsafe {
+            // SAFETY: The publisher handle is zero-initialized as expected by this function.
+            // The node handle is kept alive because it is co-owned by the publisher.
+            // The type support is kept alive by the metadata.
+            // The topic name and the options are copied by this function, so they can be dropped
+            // afterwards.
+            rcl_publisher_init(
+                &mut *handle.lock(),
+                node_handle,
+                metadata.type_support(),
+                topic_c_string.as_ptr(),
+                &publisher_options,
+            )
+            .ok()?;
+        }
+
+        Ok(Self { handle, metadata })
+    }
+
+    /// Returns the type support of the message type.
+    pub fn metadata(&self) -> &DynamicMessageMetadata {
+        &self.metadata
+    }
+
+    /// Publishes a message in its serialized form.
+    pub fn publish(&self, data: &[u8]) -> Result<(), RclrsError> {
+        // The message only borrows the data, since it is not modified or resized by rcl.
+        let serialized_message = borrowed_serialized_message(data);
+        unsafe {
+            // SAFETY: The serialized message is valid for the duration of the call. The
+            // allocation is explicitly allowed to be NULL.
+            rcl_publish_serialized_message(
+                &*self.handle.lock(),
+                &serialized_message,
+                std::ptr::null_mut(),
+            )
+        }
+        .ok()
+    }
+}
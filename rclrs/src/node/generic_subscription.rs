@@ -0,0 +1,184 @@
+use crate::allocator::copy_rcutils_allocator;
+use crate::error::{RclReturnCode, SubscriberErrorCode, ToResult};
+use crate::node::payload_transform::SerializedMessageBuffer;
+use crate::qos::QoSProfile;
+use crate::sync::Mutex;
+use crate::tracetools;
+use crate::{rcl_bindings::*, RclrsError};
+use crate::{DynamicMessageMetadata, MessageInfo, Node, SubscriptionBase, SubscriptionHandle};
+
+use std::borrow::Borrow;
+use std::ffi::{c_void, CString};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Arc;
+
+type GenericSubscriptionCallback = Box<dyn FnMut(&[u8], MessageInfo) + 'static>;
+
+/// Struct for receiving the messages of a type that is only known at runtime, in their serialized
+/// form.
+///
+/// This is like a [`DynamicSubscription`][1], but the messages are not deserialized, which makes
+/// it suited for tools that store or forward messages, such as the [`Recorder`][2]. The
+/// serialization format is that of the RMW implementation, which is CDR for all Tier 1 RMW
+/// implementations.
+///
+/// Create a generic subscription with [`Node::create_generic_subscription`].
+///
+/// [1]: crate::DynamicSubscription
+/// [2]: crate::Recorder
+pub struct GenericSubscription {
+    pub(crate) handle: Arc<SubscriptionHandle>,
+    metadata: DynamicMessageMetadata,
+    // Reused between messages, so that it only allocates when a message is larger than before.
+    buffer: Mutex<SerializedMessageBuffer>,
+    /// The callback function that runs when a message was received.
+    pub callback: Mutex<GenericSubscriptionCallback>,
+}
+
+impl GenericSubscription {
+    /// Creates a new generic subscription.
+    ///
+    /// # Panics
+    /// When the topic contains interior null bytes.
+    pub fn new<F>(
+        node: &Node,
+        topic: &str,
+        type_name: &str,
+        qos: QoSProfile,
+        callback: F,
+    ) -> Result<Self, RclrsError>
+    where
+        F: FnMut(&[u8], MessageInfo) + 'static,
+    {
+        let metadata = DynamicMessageMetadata::new(type_name)?;
+        let buffer = SerializedMessageBuffer::new()?;
+        // This is declared before the node handle guard, since dropping it in the error case
+        // locks the node handle.
+        let handle = Arc::new(SubscriptionHandle {
+            // SAFETY: Getting a zero-initialized value is always safe.
+            handle: Mutex::new(unsafe { rcl_get_zero_initialized_subscription() }),
+            node_handle: node.handle.clone(),
+            _rmw_specific_options: None,
+            _qos_parameters: Vec::new(),
+            priority: AtomicI32::new(0),
+            owned: true,
+            #[cfg(feature = "testing")]
+            loopback: None,
+        });
+        let topic_c_string = CString::new(node.expand_topic_name(topic)?).unwrap();
+        let node_handle = &mut *node.handle.lock();
+
+        // SAFETY: No preconditions for this function.
+        let mut subscription_options = unsafe { rcl_subscription_get_default_options() };
+        subscription_options.qos = qos.into();
+        subscription_options.allocator = copy_rcutils_allocator(&node.allocator);
+        unsafe {
+            // SAFETY: The subscription handle is zero-initialized as expected by this function.
+            // The node handle is kept alive because it is co-owned by the subscription.
+            // The type support is kept alive by the metadata.
+            // The topic name and the options are copied by this function, so they can be dropped
+            // afterwards.
+            rcl_subscription_init(
+                &mut *handle.lock(),
+                node_handle,
+                metadata.type_support(),
+                topic_c_string.as_ptr(),
+                &subscription_options,
+            )
+            .ok()?;
+        }
+
+        Ok(Self {
+            handle,
+            metadata,
+            buffer: Mutex::new(buffer),
+            callback: Mutex::new(Box::new(callback)),
+        })
+    }
+
+    /// Returns the type support of the message type.
+    pub fn metadata(&self) -> &DynamicMessageMetadata {
+        &self.metadata
+    }
+
+    /// Returns the priority of the subscription for the executor.
+    ///
+    /// See [`Subscription::set_priority`][1].
+    ///
+    /// [1]: crate::Subscription::set_priority
+    pub fn priority(&self) -> i32 {
+        self.handle.priority()
+    }
+
+    /// Sets the priority of the subscription for the executor.
+    ///
+    /// See [`Subscription::set_priority`][1].
+    ///
+    /// [1]: crate::Subscription::set_priority
+    pub fn set_priority(&self, priority: i32) {
+        self.handle.priority.store(priority, Ordering::Relaxed);
+    }
+
+    /// Fetches a new message in its serialized form, together with information about it.
+    ///
+    /// See [`Subscription::take`][1] for the errors.
+    ///
+    /// [1]: crate::Subscription::take
+    pub fn take(&self) -> Result<(Vec<u8>, MessageInfo), RclrsError> {
+        let buffer = &mut *self.buffer.lock();
+        let message_info = self.take_into(buffer)?;
+        Ok((buffer.as_slice().to_vec(), message_info))
+    }
+
+    fn take_into(&self, buffer: &mut SerializedMessageBuffer) -> Result<MessageInfo, RclrsError> {
+        // SAFETY: No preconditions for this function.
+        let mut message_info = unsafe { rmw_get_zero_initialized_message_info() };
+        unsafe {
+            // SAFETY: The buffer is initialized, and is resized by the RMW implementation if
+            // needed. The allocation is explicitly allowed to be NULL.
+            rcl_take_serialized_message(
+                &*self.handle.lock(),
+                &mut buffer.0,
+                &mut message_info,
+                std::ptr::null_mut(),
+            )
+        }
+        .ok()?;
+        Ok(MessageInfo::from(&message_info))
+    }
+
+    fn callback_id(&self) -> *const c_void {
+        &self.callback as *const _ as *const c_void
+    }
+}
+
+impl SubscriptionBase for GenericSubscription {
+    fn handle(&self) -> &SubscriptionHandle {
+        self.handle.borrow()
+    }
+
+    fn execute(&self) -> Result<(), RclrsError> {
+        // Only lock the handle for getting its address if the tracepoint is enabled.
+        #[cfg(feature = "tracetools")]
+        tracetools::executor_execute(&*self.handle.lock() as *const _ as *const _);
+        let buffer = &mut *self.buffer.lock();
+        let message_info = match self.take_into(buffer) {
+            Ok(message_info) => message_info,
+            Err(RclrsError {
+                code: RclReturnCode::SubscriberError(SubscriberErrorCode::SubscriptionTakeFailed),
+                ..
+            }) => {
+                // Spurious wakeup – this may happen even when a waitset indicated that this
+                // subscription was ready, so it shouldn't be an error.
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        let callback = &mut *self.callback.lock();
+        let callback_id = self.callback_id();
+        tracetools::callback_start(callback_id);
+        callback(buffer.as_slice(), message_info);
+        tracetools::callback_end(callback_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,423 @@
+use crate::allocator::copy_rcutils_allocator;
+use crate::error::{RclrsError, ToResult};
+use crate::logging::log;
+use crate::rcl_bindings::*;
+use crate::sync::Mutex;
+use crate::{GraphEventHandler, LogSeverity, Node, NodeHandle, RclReturnCode};
+
+use alloc::collections::BTreeMap;
+use alloc::ffi::CString;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::ffi::{c_char, CStr};
+
+/// What to do when a publisher or subscription is created on a topic that already has another
+/// type in the ROS graph.
+///
+/// Publishers and subscriptions with different types don't match, so nothing is received,
+/// without any error. The check catches this early, but it only sees the publishers and
+/// subscriptions that have already been discovered, and costs a graph query per entity.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TypeMismatchPolicy {
+    /// Don't check the type of the topic.
+    #[default]
+    Ignore,
+    /// Log a warning with the logger of the node, and create the entity anyway.
+    Warn,
+    /// Return an [`InvalidArgument`][1] error.
+    ///
+    /// [1]: crate::RclReturnCode::InvalidArgument
+    Error,
+}
+
+// The results of graph queries, which are kept until the graph changes.
+#[derive(Default)]
+struct GraphCacheState {
+    topics: Option<BTreeMap<String, Vec<String>>>,
+    nodes: Option<Vec<String>>,
+    // The numbers of publishers and subscriptions, by topic.
+    endpoints: BTreeMap<String, (usize, usize)>,
+    // Incremented on each change of the graph, for the counts that are cached by the entities.
+    generation: u64,
+}
+
+/// Caches the graph queries of a node, see [`Node::enable_graph_cache`].
+#[derive(Default)]
+pub(crate) struct GraphCache {
+    state: Mutex<GraphCacheState>,
+}
+
+impl GraphCache {
+    fn get_or_query<T: Clone>(
+        &self,
+        field: impl Fn(&mut GraphCacheState) -> &mut Option<T>,
+        query: impl FnOnce() -> Result<T, RclrsError>,
+    ) -> Result<T, RclrsError> {
+        let generation = {
+            let state = &mut *self.state.lock();
+            if let Some(cached) = field(state) {
+                return Ok(cached.clone());
+            }
+            state.generation
+        };
+        // The lock is not held during the query, so that a graph event is not blocked by it.
+        let value = query()?;
+        let state = &mut *self.state.lock();
+        // If the graph has changed during the query, the result may already be outdated.
+        if state.generation == generation {
+            *field(state) = Some(value.clone());
+        }
+        Ok(value)
+    }
+}
+
+impl GraphEventHandler for GraphCache {
+    fn handle_graph_event(&self) -> Result<(), RclrsError> {
+        let state = &mut *self.state.lock();
+        *state = GraphCacheState {
+            generation: state.generation.wrapping_add(1),
+            ..Default::default()
+        };
+        Ok(())
+    }
+}
+
+// The number of entities that a publisher or subscription is matched with, which is cached until
+// the graph changes if its node has a graph cache.
+pub(crate) struct MatchedCount {
+    graph_cache: Option<Arc<GraphCache>>,
+    // The count, and the generation of the graph cache that it was queried in.
+    cached: Mutex<Option<(u64, usize)>>,
+}
+
+impl MatchedCount {
+    pub(crate) fn new(node: &Node) -> Self {
+        Self {
+            graph_cache: node.graph_cache.clone(),
+            cached: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn get_or_query(
+        &self,
+        query: impl FnOnce() -> Result<usize, RclrsError>,
+    ) -> Result<usize, RclrsError> {
+        let Some(graph_cache) = &self.graph_cache else {
+            return query();
+        };
+        // The generation is read before the query, so that the result is queried again if the
+        // graph changes in the meantime.
+        let generation = graph_cache.state.lock().generation;
+        if let Some((cached_generation, count)) = *self.cached.lock() {
+            if cached_generation == generation {
+                return Ok(count);
+            }
+        }
+        let count = query()?;
+        *self.cached.lock() = Some((generation, count));
+        Ok(count)
+    }
+}
+
+impl Node {
+    /// Returns the topics in the ROS graph, together with their types.
+    ///
+    /// The topics are discovered asynchronously, so a topic may appear only some time after its
+    /// first publisher or subscription has been created. A topic can have several types, if
+    /// different publishers or subscriptions use different types for it.
+    ///
+    /// The type names have the form `package/msg/Type`, e.g. `std_msgs/msg/String`.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, RclrsError};
+    /// let context = Context::new([])?;
+    /// let node = context.create_node("graph_node")?;
+    /// for (topic, types) in node.get_topic_names_and_types()? {
+    ///     println!("{topic}: {}", types.join(", "));
+    /// }
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn get_topic_names_and_types(&self) -> Result<BTreeMap<String, Vec<String>>, RclrsError> {
+        let query = || topic_names_and_types(&self.handle, &self.allocator);
+        match &self.graph_cache {
+            Some(cache) => cache.get_or_query(|state| &mut state.topics, query),
+            None => query(),
+        }
+    }
+
+    /// Returns the fully qualified names of the nodes in the ROS graph, e.g. `/ns/my_node`.
+    ///
+    /// Like topics, nodes are discovered asynchronously. The list includes this node.
+    pub fn get_node_names(&self) -> Result<Vec<String>, RclrsError> {
+        let query = || node_names(&self.handle, &self.allocator);
+        match &self.graph_cache {
+            Some(cache) => cache.get_or_query(|state| &mut state.nodes, query),
+            None => query(),
+        }
+    }
+
+    /// Returns the number of publishers on a topic in the ROS graph.
+    ///
+    /// The topic name must be fully qualified, e.g. `/ns/chatter`.
+    pub fn count_publishers(&self, topic: &str) -> Result<usize, RclrsError> {
+        self.count_endpoints(topic)
+            .map(|(publishers, _)| publishers)
+    }
+
+    /// Returns the number of subscriptions on a topic in the ROS graph.
+    ///
+    /// The topic name must be fully qualified, e.g. `/ns/chatter`.
+    pub fn count_subscriptions(&self, topic: &str) -> Result<usize, RclrsError> {
+        self.count_endpoints(topic)
+            .map(|(_, subscriptions)| subscriptions)
+    }
+
+    /// Caches the results of the graph queries of this node, until the ROS graph changes.
+    ///
+    /// Each call of [`Node::get_topic_names_and_types`], [`Node::get_node_names`],
+    /// [`Node::count_publishers`], [`Node::count_subscriptions`],
+    /// [`Publisher::get_subscription_count`][2] and [`Subscription::get_publisher_count`][3]
+    /// otherwise queries the middleware, which is costly in loops that e.g. wait for a subscriber.
+    /// With the cache, the middleware is only queried again after the graph guard condition of the
+    /// node has been triggered, i.e. after a change of the ROS graph. Publishers and subscriptions
+    /// only use the cache if they are created after it has been enabled.
+    ///
+    /// The cache is cleared while the node is spinning, like other graph event handlers such as
+    /// the [`ServiceWatchdog`][1]. A node that does not spin therefore keeps returning the cached
+    /// results. Enabling the cache more than once has no further effect.
+    ///
+    /// [1]: crate::ServiceWatchdog
+    /// [2]: crate::Publisher::get_subscription_count
+    /// [3]: crate::Subscription::get_publisher_count
+    pub fn enable_graph_cache(&mut self) {
+        if self.graph_cache.is_some() {
+            return;
+        }
+        let cache = Arc::new(GraphCache::default());
+        self.graph_event_handlers
+            .push(Arc::downgrade(&cache) as Weak<dyn GraphEventHandler>);
+        self.graph_cache = Some(cache);
+    }
+
+    fn count_endpoints(&self, topic: &str) -> Result<(usize, usize), RclrsError> {
+        let Some(cache) = &self.graph_cache else {
+            return count_endpoints(&self.handle, topic);
+        };
+        let generation = {
+            let state = cache.state.lock();
+            if let Some(&counts) = state.endpoints.get(topic) {
+                return Ok(counts);
+            }
+            state.generation
+        };
+        let counts = count_endpoints(&self.handle, topic)?;
+        let state = &mut *cache.state.lock();
+        // Like in `GraphCache::get_or_query`, outdated counts are not stored.
+        if state.generation == generation {
+            state.endpoints.insert(topic.into(), counts);
+        }
+        Ok(counts)
+    }
+}
+
+impl Node {
+    /// Checks the type of a topic according to the policy, before creating an entity on it.
+    ///
+    /// The topic name must be expanded.
+    pub(crate) fn check_topic_type(
+        &self,
+        topic: &str,
+        type_name: &str,
+        policy: TypeMismatchPolicy,
+        entity: &str,
+    ) -> Result<(), RclrsError> {
+        if policy == TypeMismatchPolicy::Ignore {
+            return Ok(());
+        }
+        let topics = self.get_topic_names_and_types()?;
+        let Some(types) = topics.get(topic) else {
+            return Ok(());
+        };
+        if types.iter().all(|t| t == type_name) {
+            return Ok(());
+        }
+        let msg = format!(
+            "Creating a {entity} of type '{type_name}' on topic '{topic}', which already has \
+             the type(s) {}",
+            types.join(", ")
+        );
+        match policy {
+            TypeMismatchPolicy::Error => Err(RclrsError::with_message(
+                RclReturnCode::InvalidArgument,
+                msg,
+            )),
+            _ => {
+                log(&self.logger_name(), LogSeverity::Warn, &msg);
+                Ok(())
+            }
+        }
+    }
+}
+
+pub(crate) fn topic_names_and_types(
+    handle: &NodeHandle,
+    allocator: &rcutils_allocator_t,
+) -> Result<BTreeMap<String, Vec<String>>, RclrsError> {
+    // SAFETY: Getting a zero-initialized value is always safe.
+    let mut names_and_types = unsafe { rmw_get_zero_initialized_names_and_types() };
+    let mut allocator = copy_rcutils_allocator(allocator);
+    unsafe {
+        // SAFETY: The node handle is valid, and the names and types are zero-initialized as
+        // expected by this function. They are finalized below.
+        rcl_get_topic_names_and_types(&*handle.lock(), &mut allocator, false, &mut names_and_types)
+            .ok()?;
+    }
+
+    let mut topics = BTreeMap::new();
+    for i in 0..names_and_types.names.size {
+        // SAFETY: On success, there is one list of types for each of the names, and all the
+        // strings are valid.
+        unsafe {
+            let name = string_from_ptr(*names_and_types.names.data.add(i));
+            let types = &*names_and_types.types.add(i);
+            let types = (0..types.size)
+                .map(|j| string_from_ptr(*types.data.add(j)))
+                .collect();
+            topics.insert(name, types);
+        }
+    }
+    // SAFETY: The names and types have been initialized above, and are not used anymore.
+    unsafe { rcl_names_and_types_fini(&mut names_and_types) }.ok()?;
+    Ok(topics)
+}
+
+pub(crate) fn node_names(
+    handle: &NodeHandle,
+    allocator: &rcutils_allocator_t,
+) -> Result<Vec<String>, RclrsError> {
+    // SAFETY: Getting a zero-initialized value is always safe.
+    let mut names = unsafe { rcutils_get_zero_initialized_string_array() };
+    // SAFETY: Getting a zero-initialized value is always safe.
+    let mut namespaces = unsafe { rcutils_get_zero_initialized_string_array() };
+    unsafe {
+        // SAFETY: The node handle is valid, and the arrays are zero-initialized as expected by
+        // this function. They are finalized below.
+        rcl_get_node_names(
+            &*handle.lock(),
+            copy_rcutils_allocator(allocator),
+            &mut names,
+            &mut namespaces,
+        )
+        .ok()?;
+    }
+    let node_names = (0..names.size)
+        .map(|i| {
+            // SAFETY: On success, there is one namespace for each of the names, and all the
+            // strings are valid.
+            let (name, namespace) = unsafe {
+                (
+                    string_from_ptr(*names.data.add(i)),
+                    string_from_ptr(*namespaces.data.add(i)),
+                )
+            };
+            if namespace.ends_with('/') {
+                format!("{namespace}{name}")
+            } else {
+                format!("{namespace}/{name}")
+            }
+        })
+        .collect();
+    // SAFETY: The arrays have been initialized above, and are not used anymore.
+    unsafe {
+        rcutils_string_array_fini(&mut names);
+        rcutils_string_array_fini(&mut namespaces);
+    }
+    Ok(node_names)
+}
+
+// Returns the numbers of publishers and subscriptions of a topic.
+pub(crate) fn count_endpoints(
+    handle: &NodeHandle,
+    topic: &str,
+) -> Result<(usize, usize), RclrsError> {
+    let topic_c_string = CString::new(topic).unwrap();
+    let node_handle = &*handle.lock();
+    let (mut publishers, mut subscriptions) = (0, 0);
+    unsafe {
+        // SAFETY: The node handle and the topic name are valid, and are not stored.
+        rcl_count_publishers(node_handle, topic_c_string.as_ptr(), &mut publishers).ok()?;
+        rcl_count_subscribers(node_handle, topic_c_string.as_ptr(), &mut subscriptions).ok()?;
+    }
+    Ok((publishers, subscriptions))
+}
+
+/// Copies a string owned by rcl.
+///
+/// # Safety
+/// The pointer must point to a valid null-terminated string.
+unsafe fn string_from_ptr(ptr: *const c_char) -> String {
+    CStr::from_ptr(ptr).to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matched_count_is_queried_again_after_graph_event() {
+        let graph_cache = Arc::new(GraphCache::default());
+        let matched_count = MatchedCount {
+            graph_cache: Some(Arc::clone(&graph_cache)),
+            cached: Mutex::new(None),
+        };
+        assert_eq!(matched_count.get_or_query(|| Ok(1)).unwrap(), 1);
+        assert_eq!(matched_count.get_or_query(|| Ok(2)).unwrap(), 1);
+        graph_cache.handle_graph_event().unwrap();
+        assert_eq!(matched_count.get_or_query(|| Ok(2)).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_query_during_graph_event_is_not_cached() {
+        let graph_cache = GraphCache::default();
+        let nodes = |name: &str| Ok(alloc::vec![String::from(name)]);
+        let queried = graph_cache.get_or_query(
+            |state| &mut state.nodes,
+            || {
+                graph_cache.handle_graph_event()?;
+                nodes("old")
+            },
+        );
+        assert_eq!(queried.unwrap(), ["old"]);
+        let queried = graph_cache.get_or_query(|state| &mut state.nodes, || nodes("new"));
+        assert_eq!(queried.unwrap(), ["new"]);
+        let queried = graph_cache.get_or_query(|state| &mut state.nodes, || nodes("newer"));
+        assert_eq!(queried.unwrap(), ["new"]);
+    }
+
+    #[test]
+    fn test_matched_count_without_graph_cache() {
+        let matched_count = MatchedCount {
+            graph_cache: None,
+            cached: Mutex::new(None),
+        };
+        assert_eq!(matched_count.get_or_query(|| Ok(1)).unwrap(), 1);
+        assert_eq!(matched_count.get_or_query(|| Ok(2)).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_failed_query_is_not_cached() {
+        let matched_count = MatchedCount {
+            graph_cache: Some(Arc::new(GraphCache::default())),
+            cached: Mutex::new(None),
+        };
+        let error = RclrsError {
+            code: RclReturnCode::Error,
+            msg: None,
+        };
+        assert!(matched_count.get_or_query(|| Err(error)).is_err());
+        assert_eq!(matched_count.get_or_query(|| Ok(3)).unwrap(), 3);
+    }
+}
@@ -0,0 +1,205 @@
+use crate::node::graph::{count_endpoints, node_names, topic_names_and_types};
+use crate::rcl_bindings::rcutils_allocator_t;
+use crate::sync::Mutex;
+use crate::{GraphEventHandler, NodeHandle, RclrsError};
+
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+/// A change of the ROS graph, see [`GraphEvents`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GraphEvent {
+    /// A node appeared, with its fully qualified name.
+    NodeAdded(String),
+    /// A node disappeared, with its fully qualified name.
+    NodeRemoved(String),
+    /// A topic appeared, i.e. it got its first publisher or subscription.
+    TopicAdded {
+        /// The name of the topic.
+        topic: String,
+        /// The types of the topic.
+        types: Vec<String>,
+    },
+    /// A topic disappeared, i.e. it lost its last publisher or subscription.
+    TopicRemoved {
+        /// The name of the topic.
+        topic: String,
+    },
+    /// The publishers or subscriptions of an existing topic changed.
+    EndpointsChanged {
+        /// The name of the topic.
+        topic: String,
+        /// The types of the topic.
+        types: Vec<String>,
+        /// The number of publishers of the topic.
+        publishers: usize,
+        /// The number of subscriptions of the topic.
+        subscriptions: usize,
+    },
+}
+
+// The part of the graph that is diffed.
+#[derive(Default)]
+struct GraphSnapshot {
+    nodes: BTreeSet<String>,
+    // The types and the numbers of publishers and subscriptions, by topic.
+    topics: BTreeMap<String, (Vec<String>, usize, usize)>,
+}
+
+impl GraphSnapshot {
+    fn query(handle: &NodeHandle, allocator: &rcutils_allocator_t) -> Result<Self, RclrsError> {
+        let nodes = node_names(handle, allocator)?.into_iter().collect();
+        let topics = topic_names_and_types(handle, allocator)?
+            .into_iter()
+            .map(|(topic, types)| {
+                let (publishers, subscriptions) = count_endpoints(handle, &topic)?;
+                Ok((topic, (types, publishers, subscriptions)))
+            })
+            .collect::<Result<_, RclrsError>>()?;
+        Ok(Self { nodes, topics })
+    }
+
+    // Returns the events that lead from this snapshot to the newer one.
+    fn diff(&self, newer: &Self) -> Vec<GraphEvent> {
+        let mut events = Vec::new();
+        events.extend(
+            newer
+                .nodes
+                .difference(&self.nodes)
+                .cloned()
+                .map(GraphEvent::NodeAdded),
+        );
+        events.extend(
+            self.nodes
+                .difference(&newer.nodes)
+                .cloned()
+                .map(GraphEvent::NodeRemoved),
+        );
+        for (topic, (types, publishers, subscriptions)) in &newer.topics {
+            match self.topics.get(topic) {
+                None => events.push(GraphEvent::TopicAdded {
+                    topic: topic.clone(),
+                    types: types.clone(),
+                }),
+                Some(old) if old != &(types.clone(), *publishers, *subscriptions) => {
+                    events.push(GraphEvent::EndpointsChanged {
+                        topic: topic.clone(),
+                        types: types.clone(),
+                        publishers: *publishers,
+                        subscriptions: *subscriptions,
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        for topic in self.topics.keys() {
+            if !newer.topics.contains_key(topic) {
+                events.push(GraphEvent::TopicRemoved {
+                    topic: topic.clone(),
+                });
+            }
+        }
+        events
+    }
+}
+
+/// A stream of the changes of the ROS graph, as seen by a node.
+///
+/// Create it with [`Node::create_graph_events`][1]. Whenever the graph guard condition of the
+/// node is triggered while the node is spinning, the graph is queried and compared with the
+/// previous query, and the differences are queued as [`GraphEvent`]s. The graph at the time of
+/// creation is the baseline, so it does not produce events.
+///
+/// The events can be awaited with [`GraphEvents::next`], e.g. together with
+/// [`spin_async`][2], or taken without waiting with [`GraphEvents::try_next`].
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let events = node.create_graph_events()?;
+/// let mut next = events.next();
+/// let event = spin_until_future_complete(&node, &mut next, None)?;
+/// if let GraphEvent::NodeRemoved(name) = event {
+///     println!("{name} is gone");
+/// }
+/// ```
+///
+/// [1]: crate::Node::create_graph_events
+/// [2]: crate::spin_async
+pub struct GraphEvents {
+    handle: Arc<NodeHandle>,
+    allocator: rcutils_allocator_t,
+    snapshot: Mutex<GraphSnapshot>,
+    queue: Mutex<VecDeque<GraphEvent>>,
+    waker: Mutex<Option<Waker>>,
+}
+
+impl GraphEvents {
+    pub(crate) fn new(
+        handle: Arc<NodeHandle>,
+        allocator: rcutils_allocator_t,
+    ) -> Result<Self, RclrsError> {
+        let snapshot = GraphSnapshot::query(&handle, &allocator)?;
+        Ok(Self {
+            handle,
+            allocator,
+            snapshot: Mutex::new(snapshot),
+            queue: Mutex::new(VecDeque::new()),
+            waker: Mutex::new(None),
+        })
+    }
+
+    /// Takes the oldest queued event, if any.
+    pub fn try_next(&self) -> Option<GraphEvent> {
+        self.queue.lock().pop_front()
+    }
+
+    /// Returns a future for the next event.
+    pub fn next(&self) -> NextGraphEvent<'_> {
+        NextGraphEvent { events: self }
+    }
+}
+
+impl GraphEventHandler for GraphEvents {
+    fn handle_graph_event(&self) -> Result<(), RclrsError> {
+        let newer = GraphSnapshot::query(&self.handle, &self.allocator)?;
+        let events = {
+            let mut snapshot = self.snapshot.lock();
+            let events = snapshot.diff(&newer);
+            *snapshot = newer;
+            events
+        };
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.queue.lock().extend(events);
+        if let Some(waker) = self.waker.lock().take() {
+            waker.wake();
+        }
+        Ok(())
+    }
+}
+
+/// The future returned by [`GraphEvents::next`].
+pub struct NextGraphEvent<'a> {
+    events: &'a GraphEvents,
+}
+
+impl Future for NextGraphEvent<'_> {
+    type Output = GraphEvent;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // The waker is stored before checking the queue, so that an event that is queued in
+        // between is not missed.
+        *self.events.waker.lock() = Some(cx.waker().clone());
+        match self.events.try_next() {
+            Some(event) => Poll::Ready(event),
+            None => Poll::Pending,
+        }
+    }
+}
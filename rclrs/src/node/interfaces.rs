@@ -0,0 +1,153 @@
+use crate::{
+    Node, Publisher, PublisherOptions, QoSProfile, RclrsError, Subscription, SubscriptionOptions,
+};
+
+use alloc::string::String;
+use alloc::sync::Arc;
+
+use rosidl_runtime_rs::Message;
+
+/// The basic information about a node.
+///
+/// This and the other node interface traits allow reusable libraries to accept anything that
+/// behaves like a node, instead of only [`Node`] itself. A wrapper type, such as a node with
+/// additional state, can implement them by forwarding to its inner node.
+///
+/// # Example
+/// ```
+/// # use rclrs::{Context, NodeBaseInterface, RclrsError};
+/// fn describe(node: &impl NodeBaseInterface) -> String {
+///     format!("Node {} in domain {}", node.fully_qualified_name(), node.domain_id())
+/// }
+/// let context = Context::new([])?;
+/// let node = context.create_node_with_namespace("/my/namespace", "my_node")?;
+/// assert!(describe(&node).starts_with("Node /my/namespace/my_node"));
+/// # Ok::<(), RclrsError>(())
+/// ```
+pub trait NodeBaseInterface {
+    /// See [`Node::name`].
+    fn name(&self) -> String;
+    /// See [`Node::namespace`].
+    fn namespace(&self) -> String;
+    /// See [`Node::fully_qualified_name`].
+    fn fully_qualified_name(&self) -> String;
+    /// See [`Node::domain_id`].
+    fn domain_id(&self) -> usize;
+}
+
+/// The ability to create publishers and subscriptions.
+///
+/// See [`NodeBaseInterface`] for the purpose of the node interface traits.
+pub trait NodeTopicsInterface: NodeBaseInterface {
+    /// See [`Node::create_publisher`].
+    fn create_publisher<T>(
+        &mut self,
+        topic: &str,
+        qos: QoSProfile,
+    ) -> Result<Publisher<T>, RclrsError>
+    where
+        T: Message;
+
+    /// See [`Node::create_publisher_with_options`].
+    fn create_publisher_with_options<T>(
+        &mut self,
+        topic: &str,
+        qos: QoSProfile,
+        options: PublisherOptions,
+    ) -> Result<Publisher<T>, RclrsError>
+    where
+        T: Message;
+
+    /// See [`Node::create_subscription`].
+    fn create_subscription<T, F>(
+        &mut self,
+        topic: &str,
+        qos: QoSProfile,
+        callback: F,
+    ) -> Result<Arc<Subscription<T>>, RclrsError>
+    where
+        T: Message,
+        F: FnMut(T) + 'static;
+
+    /// See [`Node::create_subscription_with_options`].
+    fn create_subscription_with_options<T, F>(
+        &mut self,
+        topic: &str,
+        qos: QoSProfile,
+        options: SubscriptionOptions,
+        callback: F,
+    ) -> Result<Arc<Subscription<T>>, RclrsError>
+    where
+        T: Message,
+        F: FnMut(T) + 'static;
+}
+
+impl NodeBaseInterface for Node {
+    fn name(&self) -> String {
+        Node::name(self)
+    }
+
+    fn namespace(&self) -> String {
+        Node::namespace(self)
+    }
+
+    fn fully_qualified_name(&self) -> String {
+        Node::fully_qualified_name(self)
+    }
+
+    fn domain_id(&self) -> usize {
+        Node::domain_id(self)
+    }
+}
+
+impl NodeTopicsInterface for Node {
+    fn create_publisher<T>(
+        &mut self,
+        topic: &str,
+        qos: QoSProfile,
+    ) -> Result<Publisher<T>, RclrsError>
+    where
+        T: Message,
+    {
+        Node::create_publisher(self, topic, qos)
+    }
+
+    fn create_publisher_with_options<T>(
+        &mut self,
+        topic: &str,
+        qos: QoSProfile,
+        options: PublisherOptions,
+    ) -> Result<Publisher<T>, RclrsError>
+    where
+        T: Message,
+    {
+        Node::create_publisher_with_options(self, topic, qos, options)
+    }
+
+    fn create_subscription<T, F>(
+        &mut self,
+        topic: &str,
+        qos: QoSProfile,
+        callback: F,
+    ) -> Result<Arc<Subscription<T>>, RclrsError>
+    where
+        T: Message,
+        F: FnMut(T) + 'static,
+    {
+        Node::create_subscription(self, topic, qos, callback)
+    }
+
+    fn create_subscription_with_options<T, F>(
+        &mut self,
+        topic: &str,
+        qos: QoSProfile,
+        options: SubscriptionOptions,
+        callback: F,
+    ) -> Result<Arc<Subscription<T>>, RclrsError>
+    where
+        T: Message,
+        F: FnMut(T) + 'static,
+    {
+        Node::create_subscription_with_options(self, topic, qos, options, callback)
+    }
+}
@@ -0,0 +1,106 @@
+use crate::error::{RclrsError, ToResult};
+use crate::rcl_bindings::*;
+use crate::{tracetools, Publisher};
+
+use core::ops::{Deref, DerefMut};
+
+use rosidl_runtime_rs::{Message, RmwMessage};
+
+/// A message that is owned by the middleware, loaned for sending.
+///
+/// This type is returned by [`Publisher::borrow_loaned_message()`] and dereferences to the
+/// message, so that it can be filled in place, e.g. directly in shared memory. It is sent with
+/// [`LoanedMessage::publish()`]. When it is dropped without being published, it is returned to
+/// the middleware.
+///
+/// Only messages of fixed size can be loaned, since the contents of strings and sequences would
+/// be allocated outside of the loan, and would neither reach the subscriptions in shared memory
+/// nor be freed after publishing.
+pub struct LoanedMessage<'a, T>
+where
+    T: RmwMessage + Message,
+{
+    msg_ptr: *mut T,
+    publisher: &'a Publisher<T>,
+}
+
+impl<'a, T> Deref for LoanedMessage<'a, T>
+where
+    T: RmwMessage + Message,
+{
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: The pointer is valid, since the loan has not been returned yet.
+        unsafe { &*self.msg_ptr }
+    }
+}
+
+impl<'a, T> DerefMut for LoanedMessage<'a, T>
+where
+    T: RmwMessage + Message,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: The pointer is valid, since the loan has not been returned yet.
+        unsafe { &mut *self.msg_ptr }
+    }
+}
+
+impl<'a, T> Drop for LoanedMessage<'a, T>
+where
+    T: RmwMessage + Message,
+{
+    fn drop(&mut self) {
+        // The message is null after it has been published.
+        if self.msg_ptr.is_null() {
+            return;
+        }
+        // SAFETY: The message was initialized when it was borrowed, and is not used anymore.
+        // The loan is returned to the publisher that it was borrowed from. Errors are ignored,
+        // since there is nothing to be done about them here.
+        unsafe {
+            core::ptr::drop_in_place(self.msg_ptr);
+            rcl_return_loaned_message_from_publisher(
+                &*self.publisher.handle.lock(),
+                self.msg_ptr as *mut _,
+            );
+        }
+    }
+}
+
+// SAFETY: The loan is owned exclusively by this value, like a `Box<T>`.
+unsafe impl<'a, T> Send for LoanedMessage<'a, T> where T: RmwMessage + Message + Send {}
+// SAFETY: The message can only be accessed through shared references with `&self`.
+unsafe impl<'a, T> Sync for LoanedMessage<'a, T> where T: RmwMessage + Message + Sync {}
+
+impl<'a, T> LoanedMessage<'a, T>
+where
+    T: RmwMessage + Message,
+{
+    pub(crate) fn new(publisher: &'a Publisher<T>) -> Result<Self, RclrsError> {
+        let mut msg_ptr = core::ptr::null_mut();
+        let type_support = T::get_type_support() as *const rosidl_message_type_support_t;
+        unsafe {
+            // SAFETY: The message pointer is null, as expected by this function.
+            rcl_borrow_loaned_message(&*publisher.handle.lock(), type_support, &mut msg_ptr)
+                .ok()?;
+        }
+        let msg_ptr = msg_ptr as *mut T;
+        // SAFETY: The loaned memory is uninitialized, so the message is written without dropping
+        // the previous contents, like the placement new of rclcpp.
+        unsafe { msg_ptr.write(T::default()) };
+        Ok(Self { msg_ptr, publisher })
+    }
+
+    /// Publishes the message, and gives it back to the middleware.
+    pub fn publish(mut self) -> Result<(), RclrsError> {
+        let handle = &mut *self.publisher.handle.lock();
+        tracetools::publish(handle as *const _ as *const _, self.msg_ptr as *const _);
+        // The middleware owns the message from here on, also when publishing fails.
+        let msg_ptr = core::mem::replace(&mut self.msg_ptr, core::ptr::null_mut());
+        unsafe {
+            // SAFETY: The message was borrowed from this publisher and is valid.
+            // The third argument is explictly allowed to be NULL.
+            rcl_publish_loaned_message(handle, msg_ptr as *mut _, core::ptr::null_mut()).ok()
+        }
+    }
+}
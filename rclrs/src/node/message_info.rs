@@ -0,0 +1,157 @@
+use crate::rcl_bindings::*;
+use crate::{Publisher, ToResult};
+
+use core::fmt;
+
+use rosidl_runtime_rs::Message;
+
+/// The size of a [`Gid`] in bytes.
+const GID_SIZE: usize = RMW_GID_STORAGE_SIZE as usize;
+
+/// A globally unique identifier for a publisher.
+///
+/// GIDs can be compared and hashed, e.g. for keeping per-publisher state.
+#[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Gid {
+    data: [u8; GID_SIZE],
+}
+
+impl Gid {
+    /// Returns the raw bytes of the GID.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl From<&rmw_gid_t> for Gid {
+    fn from(gid: &rmw_gid_t) -> Self {
+        Self { data: gid.data }
+    }
+}
+
+impl fmt::Debug for Gid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Gid(")?;
+        for byte in self.data {
+            write!(f, "{:02x}", byte)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// The ID of a service request, i.e. the GUID of the client that sent it, and the sequence number
+/// of the request at that client.
+///
+/// Request IDs can be compared and hashed, e.g. for tracking requests that are answered later
+/// with a [`ServiceResponder`][1].
+///
+/// [1]: crate::ServiceResponder
+#[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct RequestId {
+    writer_guid: [u8; 16],
+    sequence_number: i64,
+}
+
+impl RequestId {
+    pub(crate) fn new(writer_guid: [u8; 16], sequence_number: i64) -> Self {
+        Self {
+            writer_guid,
+            sequence_number,
+        }
+    }
+
+    /// Returns the GUID of the client that sent the request.
+    pub fn writer_guid(&self) -> &[u8; 16] {
+        &self.writer_guid
+    }
+
+    /// Returns the sequence number of the request, which is the number returned by
+    /// [`Client::async_send_request_with_callback`][1] in the client that sent it.
+    ///
+    /// [1]: crate::Client::async_send_request_with_callback
+    pub fn sequence_number(&self) -> i64 {
+        self.sequence_number
+    }
+
+    pub(crate) fn to_rmw(self) -> rmw_request_id_t {
+        rmw_request_id_t {
+            writer_guid: self.writer_guid.map(|byte| byte as i8),
+            sequence_number: self.sequence_number,
+        }
+    }
+}
+
+impl From<&rmw_request_id_t> for RequestId {
+    fn from(request_id: &rmw_request_id_t) -> Self {
+        Self {
+            writer_guid: request_id.writer_guid.map(|byte| byte as u8),
+            sequence_number: request_id.sequence_number,
+        }
+    }
+}
+
+impl fmt::Debug for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RequestId(")?;
+        for byte in self.writer_guid {
+            write!(f, "{:02x}", byte)?;
+        }
+        write!(f, ", {})", self.sequence_number)
+    }
+}
+
+/// Information about a received message, returned by [`Subscription::take_with_info`][1].
+///
+/// [1]: crate::Subscription::take_with_info
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageInfo {
+    /// The time when the message was published, in nanoseconds since the Unix epoch.
+    ///
+    /// Not every RMW implementation sets this.
+    pub source_timestamp: i64,
+    /// The GID of the publisher that sent the message.
+    pub publisher_gid: Gid,
+    /// Whether the message was delivered within the same process.
+    pub from_intra_process: bool,
+}
+
+impl MessageInfo {
+    /// Returns true if the message was sent by the given publisher.
+    ///
+    /// This can be used to ignore the messages of a node's own publisher, see also
+    /// [`SubscriptionOptions::ignore_local_publications`][1].
+    ///
+    /// The GIDs are compared by the RMW implementation, since only it knows which of their bytes
+    /// identify the publisher.
+    ///
+    /// [1]: crate::SubscriptionOptions::ignore_local_publications
+    pub fn is_from<T: Message>(&self, publisher: &Publisher<T>) -> bool {
+        // SAFETY: No preconditions for this function.
+        let implementation_identifier = unsafe { rmw_get_implementation_identifier() };
+        // Both GIDs come from the RMW implementation of this process, so they have its identifier.
+        let to_rmw_gid = |gid: &Gid| rmw_gid_t {
+            implementation_identifier,
+            data: gid.data,
+        };
+        let mut is_equal = false;
+        // SAFETY: The GIDs are valid for the duration of the call.
+        let result = unsafe {
+            rmw_compare_gids_equal(
+                &to_rmw_gid(&self.publisher_gid),
+                &to_rmw_gid(publisher.gid()),
+                &mut is_equal,
+            )
+        };
+        result.ok().is_ok() && is_equal
+    }
+}
+
+impl From<&rmw_message_info_t> for MessageInfo {
+    fn from(message_info: &rmw_message_info_t) -> Self {
+        Self {
+            source_timestamp: message_info.source_timestamp,
+            publisher_gid: Gid::from(&message_info.publisher_gid),
+            from_intra_process: message_info.from_intra_process,
+        }
+    }
+}
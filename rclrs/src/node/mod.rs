@@ -1,26 +1,139 @@
+mod activation;
+mod cached_client;
+#[cfg(feature = "std_msgs")]
+mod chunked;
+mod client;
+mod client_pool;
+#[cfg(all(feature = "statistics_msgs", feature = "std_srvs"))]
+mod clock_sync;
+mod deferred_service;
+#[cfg(feature = "dyn_msg")]
+mod dynamic_subscription;
+#[cfg(feature = "dyn_msg")]
+mod generic_publisher;
+#[cfg(feature = "dyn_msg")]
+mod generic_subscription;
+mod graph;
+mod graph_events;
+mod interfaces;
+mod loaned_message;
+mod message_info;
+mod multi_subscription;
+mod namespace_fanout;
+mod node_monitor;
+mod options;
+mod payload_transform;
 mod publisher;
+mod qos_event;
+mod rmw_specific_options;
+mod service;
+mod service_watchdog;
+mod stamp;
+mod static_memory;
+#[cfg(feature = "statistics_msgs")]
+mod statistics;
 mod subscription;
+mod timer;
+#[cfg(feature = "std_msgs")]
+mod topic_service;
+mod trace_context;
+#[cfg(not(any(
+    ros_distro = "foxy",
+    ros_distro = "galactic",
+    ros_distro = "humble",
+    ros_distro = "iron"
+)))]
+mod type_description_service;
+mod type_hash;
+pub use self::activation::*;
+pub use self::cached_client::*;
+#[cfg(feature = "std_msgs")]
+pub use self::chunked::*;
+pub use self::client::*;
+pub use self::client_pool::*;
+#[cfg(all(feature = "statistics_msgs", feature = "std_srvs"))]
+pub use self::clock_sync::*;
+pub use self::deferred_service::*;
+#[cfg(feature = "dyn_msg")]
+pub use self::dynamic_subscription::*;
+#[cfg(feature = "dyn_msg")]
+pub use self::generic_publisher::*;
+#[cfg(feature = "dyn_msg")]
+pub use self::generic_subscription::*;
+pub use self::graph::*;
+pub use self::graph_events::*;
+pub use self::interfaces::*;
+pub use self::loaned_message::*;
+pub use self::message_info::*;
+pub use self::multi_subscription::*;
+pub use self::namespace_fanout::*;
+pub use self::node_monitor::*;
+pub use self::options::*;
+pub use self::payload_transform::*;
 pub use self::publisher::*;
+pub use self::qos_event::*;
+pub use self::rmw_specific_options::*;
+pub use self::service::*;
+pub use self::service_watchdog::*;
+pub use self::stamp::*;
+pub use self::static_memory::*;
+#[cfg(feature = "statistics_msgs")]
+pub use self::statistics::*;
 pub use self::subscription::*;
+pub use self::timer::*;
+#[cfg(feature = "std_msgs")]
+pub use self::topic_service::*;
+pub use self::trace_context::*;
+pub use self::type_hash::*;
 
+use crate::allocator::copy_rcutils_allocator;
+use crate::logging::log;
+use crate::parameter::{ParameterInterface, ParameterService};
 use crate::rcl_bindings::*;
-use crate::{Context, QoSProfile, RclrsError, ToResult};
-use std::ffi::{CStr, CString};
+use crate::sync::{Mutex, MutexGuard};
+#[cfg(feature = "std")]
+use crate::Time;
+use crate::{
+    CallbackHooks, Clock, ClockType, Context, ContextHandle, DispatchPolicy, GuardCondition,
+    LogSeverity, QoSProfile, RclReturnCode, RclrsError, ToResult,
+};
 
-use std::cmp::PartialEq;
-use std::fmt;
-use std::sync::{Arc, Weak};
-use std::vec::Vec;
+use alloc::ffi::CString;
+use alloc::string::{String, ToString};
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::cmp::PartialEq;
+use core::ffi::CStr;
+use core::fmt;
+use core::time::Duration;
 
 use libc::c_char;
-use parking_lot::Mutex;
 
 use rosidl_runtime_rs::Message;
 
-impl Drop for rcl_node_t {
+/// Internal struct that owns the `rcl` node, unless it was created with [`Node::from_raw`].
+pub(crate) struct NodeHandle {
+    rcl_node: Mutex<rcl_node_t>,
+    // Nodes from `Node::from_raw` are finalized by their owner.
+    owned: bool,
+}
+
+// SAFETY: The node is only accessed through a mutex, and rcl does not require it to be used from
+// the thread that created it.
+unsafe impl Send for rcl_node_t {}
+
+impl NodeHandle {
+    pub(crate) fn lock(&self) -> MutexGuard<rcl_node_t> {
+        self.rcl_node.lock()
+    }
+}
+
+impl Drop for NodeHandle {
     fn drop(&mut self) {
-        // SAFETY: No preconditions for this function
-        unsafe { rcl_node_fini(self).ok().unwrap() };
+        if self.owned {
+            // SAFETY: No preconditions for this function
+            unsafe { rcl_node_fini(self.rcl_node.get_mut()).ok().unwrap() };
+        }
     }
 }
 
@@ -29,15 +142,43 @@ impl Drop for rcl_node_t {
 /// Nodes are a core concept in ROS 2. Refer to the official ["Understanding ROS 2 nodes"][1]
 /// tutorial for an introduction.
 ///
-/// Ownership of the node is shared with all [`Publisher`]s and [`Subscription`]s created from it.
+/// Ownership of the node is shared with all [`Publisher`]s, [`Subscription`]s, [`Timer`]s,
+/// [`Client`]s and [`Service`]s created from it.
 /// That means that even after the node itself is dropped, it will continue to exist and be
-/// displayed by e.g. `ros2 topic` as long as its publishers and subscriptions are not dropped.
+/// displayed by e.g. `ros2 topic` as long as its publishers, subscriptions, timers, clients and
+/// services are not dropped.
 ///
 /// [1]: https://docs.ros.org/en/rolling/Tutorials/Understanding-ROS2-Nodes.html
 pub struct Node {
-    handle: Arc<Mutex<rcl_node_t>>,
-    pub(crate) context: Arc<Mutex<rcl_context_t>>,
+    handle: Arc<NodeHandle>,
+    pub(crate) context: Arc<ContextHandle>,
+    pub(crate) allocator: rcutils_allocator_t,
     pub(crate) subscriptions: Vec<Weak<dyn SubscriptionBase>>,
+    pub(crate) timers: Vec<Weak<dyn TimerBase>>,
+    pub(crate) clients: Vec<Weak<dyn ClientBase>>,
+    pub(crate) services: Vec<Weak<dyn ServiceBase>>,
+    pub(crate) qos_events: Vec<Weak<dyn QoSEventBase>>,
+    pub(crate) graph_event_handlers: Vec<Weak<dyn GraphEventHandler>>,
+    // Triggered by rcl when the ROS graph changes, for the graph event handlers.
+    pub(crate) graph_guard_condition: Arc<GuardCondition>,
+    // The cache of graph queries, see `Node::enable_graph_cache`.
+    pub(crate) graph_cache: Option<Arc<GraphCache>>,
+    pub(crate) static_memory: Option<Mutex<StaticMemory>>,
+    clock: Clock,
+    pub(crate) dispatch_policy: DispatchPolicy,
+    pub(crate) callback_hooks: Option<CallbackHooks>,
+    pub(crate) trace_hooks: Option<Arc<TraceHooks>>,
+    pub(crate) entity_defaults: EntityDefaults,
+    #[cfg(feature = "statistics_msgs")]
+    pub(crate) statistics: Option<Arc<NodeStatistics>>,
+    // The timer that publishes the statistics is kept alive here, and executed through `timers`.
+    #[cfg(feature = "statistics_msgs")]
+    _statistics_timer: Option<Arc<Timer>>,
+    pub(crate) parameters: ParameterInterface,
+    // The parameter services are kept alive here, and executed through `services`.
+    _parameter_service: Option<ParameterService>,
+    // The `~/get_type_description` service is kept alive here, and executed through `services`.
+    _type_description_service: Option<Arc<dyn ServiceBase>>,
 }
 
 impl Eq for Node {}
@@ -120,38 +261,257 @@ impl Node {
         node_ns: &str,
         node_name: &str,
         context: &Context,
+    ) -> Result<Node, RclrsError> {
+        Self::new_with_options(node_ns, node_name, context, NodeOptions::default())
+    }
+
+    /// Creates a new node in a namespace, with additional options.
+    ///
+    /// See [`Node::new_with_namespace`] and [`NodeOptions`].
+    pub fn new_with_options(
+        node_ns: &str,
+        node_name: &str,
+        context: &Context,
+        options: NodeOptions,
     ) -> Result<Node, RclrsError> {
         let raw_node_name = CString::new(node_name).unwrap();
         let raw_node_ns = CString::new(node_ns).unwrap();
 
-        // SAFETY: Getting a zero-initialized value is always safe.
-        let mut node_handle = unsafe { rcl_get_zero_initialized_node() };
-        let context_handle = &mut *context.handle.lock();
+        let raw_arguments: Vec<CString> = options
+            .arguments
+            .iter()
+            .map(|arg| CString::new(arg.as_str()).unwrap())
+            .collect();
+        let c_arguments: Vec<*const c_char> =
+            raw_arguments.iter().map(|arg| arg.as_ptr()).collect();
 
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut rcl_node = unsafe { rcl_get_zero_initialized_node() };
         unsafe {
             // SAFETY: No preconditions for this function.
-            let node_options = rcl_node_get_default_options();
+            let mut node_options = rcl_node_get_default_options();
+            node_options.allocator = copy_rcutils_allocator(&context.allocator);
+            node_options.use_global_arguments = options.use_global_arguments;
+            node_options.enable_rosout = options.enable_rosout;
+            #[cfg(not(any(ros_distro = "foxy", ros_distro = "galactic")))]
+            {
+                node_options.rosout_qos = options.rosout_qos.into();
+            }
+            if !c_arguments.is_empty() {
+                // SAFETY: The arguments of the default options are zero-initialized, as expected
+                // by this function. The strings are copied, and the parsed arguments are
+                // finalized together with the options below.
+                rcl_parse_arguments(
+                    c_arguments.len() as i32,
+                    c_arguments.as_ptr(),
+                    copy_rcutils_allocator(&context.allocator),
+                    &mut node_options.arguments,
+                )
+                .ok()?;
+            }
             // SAFETY: The node handle is zero-initialized as expected by this function.
             // The strings and node options are copied by this function, so we don't need
             // to keep them alive.
             // The context handle is kept alive because it is co-owned by the node.
-            rcl_node_init(
-                &mut node_handle,
+            let ret = rcl_node_init(
+                &mut rcl_node,
                 raw_node_name.as_ptr(),
                 raw_node_ns.as_ptr(),
-                context_handle,
+                &mut *context.handle.lock(),
                 &node_options,
             )
-            .ok()?;
+            .ok();
+            // SAFETY: The options are initialized, and not used anymore.
+            rcl_node_options_fini(&mut node_options).ok()?;
+            ret?;
+        }
+        let handle = NodeHandle {
+            rcl_node: Mutex::new(rcl_node),
+            owned: true,
+        };
+        Self::new_from_handle(handle, context, options)
+    }
+
+    /// Creates a node from an `rcl` node that was initialized outside of `rclrs`, e.g. by
+    /// `rclcpp` in the process that `rclrs` is embedded into.
+    ///
+    /// Publishers, subscriptions and other entities can then be created on the shared node as
+    /// usual, and existing ones can be wrapped with [`Publisher::from_raw`] and
+    /// [`Node::create_subscription_from_raw`]. Like any node, it must be spun to execute the
+    /// callbacks of its subscriptions.
+    ///
+    /// The owner of the node usually offers the parameter services and the
+    /// `~/get_type_description` service already, e.g. `rclcpp` does. So unlike for other nodes,
+    /// `rclrs` only starts these services if the caller opts in by setting
+    /// [`NodeOptions::entity_defaults`] with [`EntityDefaults::start_parameter_services`] or
+    /// [`EntityDefaults::start_type_description_service`]. Otherwise, the defaults of the
+    /// context are used without the services. The parameters that are declared through `rclrs`
+    /// are then only visible to `rclrs`.
+    ///
+    /// # Safety
+    /// The node must be initialized from the given context, e.g. one created with
+    /// [`Context::from_raw`]. It is not finalized by `rclrs`: Its owner remains responsible for
+    /// that, and must only do it after the returned `Node` and everything created from it has
+    /// been dropped.
+    ///
+    /// `rclrs` synchronizes its own accesses to the node, but not those of its owner. The owner
+    /// must not use the node while `rclrs` uses it, unless the `rcl` function is documented as
+    /// thread-safe.
+    ///
+    /// The `rcl_node_t` struct is copied, so the pointer does not need to stay valid, but the
+    /// state that it refers to does.
+    pub unsafe fn from_raw(
+        rcl_node: *const rcl_node_t,
+        context: &Context,
+        mut options: NodeOptions,
+    ) -> Result<Node, RclrsError> {
+        // A second server for the same service would answer each request twice.
+        if options.entity_defaults.is_none() {
+            options.entity_defaults = Some(EntityDefaults {
+                start_parameter_services: false,
+                start_type_description_service: false,
+                ..context.entity_defaults()
+            });
         }
+        let handle = NodeHandle {
+            rcl_node: Mutex::new(core::ptr::read(rcl_node)),
+            owned: false,
+        };
+        Self::new_from_handle(handle, context, options)
+    }
+
+    fn new_from_handle(
+        handle: NodeHandle,
+        context: &Context,
+        options: NodeOptions,
+    ) -> Result<Node, RclrsError> {
+        // SAFETY: The node handle is valid, and the returned string is copied immediately.
+        let fully_qualified_name =
+            unsafe { CStr::from_ptr(rcl_node_get_fully_qualified_name(&*handle.lock())) }
+                .to_string_lossy()
+                .into_owned();
+        let parameters = ParameterInterface::new(
+            &context.handle.lock(),
+            &handle.lock(),
+            &fully_qualified_name,
+            &options,
+        )?;
+
+        let handle = Arc::new(handle);
+        let entity_defaults = match &options.entity_defaults {
+            Some(entity_defaults) => entity_defaults.clone(),
+            None => context.entity_defaults(),
+        };
 
-        let handle = Arc::new(Mutex::new(node_handle));
+        #[cfg(any(
+            ros_distro = "foxy",
+            ros_distro = "galactic",
+            ros_distro = "humble",
+            ros_distro = "iron"
+        ))]
+        let type_description_service: Option<Arc<dyn ServiceBase>> = None;
+        #[cfg(not(any(
+            ros_distro = "foxy",
+            ros_distro = "galactic",
+            ros_distro = "humble",
+            ros_distro = "iron"
+        )))]
+        let type_description_service: Option<Arc<dyn ServiceBase>> =
+            if entity_defaults.start_type_description_service {
+                Some(Arc::new(
+                    type_description_service::TypeDescriptionService::new(&handle)?,
+                ))
+            } else {
+                None
+            };
+        let services = type_description_service
+            .iter()
+            .map(Arc::downgrade)
+            .collect();
 
-        Ok(Node {
-            handle,
+        let mut node = Node {
+            handle: handle.clone(),
             context: context.handle.clone(),
-            subscriptions: std::vec![],
-        })
+            allocator: copy_rcutils_allocator(&context.allocator),
+            subscriptions: Vec::new(),
+            timers: Vec::new(),
+            clients: Vec::new(),
+            services,
+            qos_events: Vec::new(),
+            graph_event_handlers: Vec::new(),
+            graph_guard_condition: Arc::new(GuardCondition::graph(&handle, &context.handle)?),
+            graph_cache: None,
+            static_memory: None,
+            clock: Clock::new(ClockType::RosTime)?,
+            dispatch_policy: options.dispatch_policy,
+            callback_hooks: None,
+            trace_hooks: None,
+            entity_defaults,
+            #[cfg(feature = "statistics_msgs")]
+            statistics: options
+                .statistics
+                .as_ref()
+                .map(|_| Arc::new(NodeStatistics::default())),
+            #[cfg(feature = "statistics_msgs")]
+            _statistics_timer: None,
+            parameters,
+            _parameter_service: None,
+            _type_description_service: type_description_service,
+        };
+        if node.entity_defaults.start_parameter_services {
+            let parameter_service = ParameterService::new(&node)?;
+            node.services.extend(parameter_service.services());
+            node._parameter_service = Some(parameter_service);
+        }
+        #[cfg(feature = "statistics_msgs")]
+        if let (Some(statistics), Some(statistics_options)) =
+            (node.statistics.clone(), &options.statistics)
+        {
+            let timer = create_statistics_timer(&mut node, statistics, statistics_options)?;
+            node._statistics_timer = Some(timer);
+        }
+        Ok(node)
+    }
+
+    /// Returns the defaults for the entities of the node, see [`EntityDefaults`].
+    pub fn entity_defaults(&self) -> &EntityDefaults {
+        &self.entity_defaults
+    }
+
+    /// Sets the defaults for the entities that the node creates from now on.
+    ///
+    /// The services of the node have already been created, so
+    /// [`EntityDefaults::start_parameter_services`] and
+    /// [`EntityDefaults::start_type_description_service`] have no effect here. Set them with
+    /// [`NodeOptions::entity_defaults`] or [`Context::set_entity_defaults`] instead.
+    pub fn set_entity_defaults(&mut self, defaults: EntityDefaults) {
+        self.entity_defaults = defaults;
+    }
+
+    /// Returns the clock of the node, which provides the [ROS time][1].
+    ///
+    /// [1]: crate::ClockType::RosTime
+    pub fn get_clock(&self) -> Clock {
+        self.clock.clone()
+    }
+
+    /// Blocks the current thread until the given duration has passed on the node's clock.
+    ///
+    /// This respects simulated time, and wakes up early when the context is shut down, see
+    /// [`Clock::sleep_until`]. Returns `true` if the full duration has passed.
+    ///
+    /// Note that the node's callbacks are not executed while sleeping.
+    #[cfg(feature = "std")]
+    pub fn sleep_for(&self, duration: Duration) -> Result<bool, RclrsError> {
+        self.clock.sleep_for(duration, &self.get_context())
+    }
+
+    /// Blocks the current thread until the node's clock has reached the given time.
+    ///
+    /// See [`Node::sleep_for`].
+    #[cfg(feature = "std")]
+    pub fn sleep_until(&self, until: Time) -> Result<bool, RclrsError> {
+        self.clock.sleep_until(until, &self.get_context())
     }
 
     /// Returns the name of the node.
@@ -217,7 +577,19 @@ impl Node {
         self.get_string(rcl_node_get_fully_qualified_name)
     }
 
-    // Helper for name(), namespace(), fully_qualified_name()
+    /// Returns the name of the node's logger.
+    ///
+    /// This is the fully qualified name of the node, with dots instead of slashes and without the
+    /// leading slash, e.g. `my.namespace.my_node`. `rclrs` logs the warnings and errors that
+    /// concern the node with this logger, and [`set_logging_output_handler`][1] receives them under
+    /// this name.
+    ///
+    /// [1]: crate::set_logging_output_handler
+    pub fn logger_name(&self) -> String {
+        self.get_string(rcl_node_get_logger_name)
+    }
+
+    // Helper for name(), namespace(), fully_qualified_name(), logger_name()
     fn get_string(
         &self,
         getter: unsafe extern "C" fn(*const rcl_node_t) -> *const c_char,
@@ -236,24 +608,173 @@ impl Node {
         cstr.to_string_lossy().into_owned()
     }
 
+    /// Expands a topic or service name into a fully qualified name.
+    ///
+    /// This follows the [ROS 2 naming conventions][1]:
+    /// - A relative name, such as `chatter`, is prefixed with the namespace of the node.
+    /// - A private name, such as `~/chatter`, is prefixed with the fully qualified name of the node.
+    /// - The substitutions `{node}` and `{namespace}` (or `{ns}`) are replaced by the name and
+    ///   namespace of the node.
+    ///
+    /// Remapping rules are not applied, since they are applied when creating an entity. The
+    /// entities of `rclrs` expand their names with this function when they are created.
+    ///
+    /// Returns a [`TopicNameInvalid`][2] or [`UnknownSubstitution`][2] error for names that can
+    /// not be expanded.
+    ///
+    /// # Panics
+    /// When the name contains interior null bytes.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, RclrsError};
+    /// let context = Context::new([])?;
+    /// let node = context.create_node_with_namespace("/my_ns", "my_node")?;
+    /// assert_eq!(node.expand_topic_name("chatter")?, "/my_ns/chatter");
+    /// assert_eq!(node.expand_topic_name("~/chatter")?, "/my_ns/my_node/chatter");
+    /// assert_eq!(node.expand_topic_name("/{node}/chatter")?, "/my_node/chatter");
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    ///
+    /// [1]: https://design.ros2.org/articles/topic_and_service_names.html
+    /// [2]: crate::RclReturnCode
+    pub fn expand_topic_name(&self, topic_name: &str) -> Result<String, RclrsError> {
+        let topic_name_c_string = CString::new(topic_name).unwrap();
+        let node_handle = &*self.handle.lock();
+        let mut expanded_topic_name: *mut c_char = core::ptr::null_mut();
+        unsafe {
+            // SAFETY: Getting a zero-initialized value is always safe.
+            let mut substitutions = rcutils_get_zero_initialized_string_map();
+            // SAFETY: The string map is zero-initialized as expected by this function.
+            rcutils_string_map_init(
+                &mut substitutions,
+                0,
+                copy_rcutils_allocator(&self.allocator),
+            )
+            .ok()?;
+            // SAFETY: The node handle is valid, so its name and namespace are valid strings.
+            // The substitutions are initialized. The expanded name is allocated with the
+            // allocator of the node, and freed below.
+            let result = rcl_get_default_topic_name_substitutions(&mut substitutions)
+                .ok()
+                .and_then(|()| {
+                    rcl_expand_topic_name(
+                        topic_name_c_string.as_ptr(),
+                        rcl_node_get_name(node_handle),
+                        rcl_node_get_namespace(node_handle),
+                        &substitutions,
+                        copy_rcutils_allocator(&self.allocator),
+                        &mut expanded_topic_name,
+                    )
+                    .ok()
+                });
+            // SAFETY: The string map has been initialized above.
+            rcutils_string_map_fini(&mut substitutions);
+            result?;
+        }
+        // SAFETY: On success, the expanded name is a valid null-terminated string.
+        let expanded = unsafe { CStr::from_ptr(expanded_topic_name) }
+            .to_string_lossy()
+            .into_owned();
+        if let Some(deallocate) = self.allocator.deallocate {
+            // SAFETY: The expanded name was allocated with this allocator, and is not used anymore.
+            unsafe { deallocate(expanded_topic_name as *mut _, self.allocator.state) };
+        }
+        Ok(expanded)
+    }
+
     /// Creates a [`Publisher`][1].
     ///
+    /// The publisher uses the default options of the node, see [`Node::entity_defaults`].
+    /// Unless disabled with [`PublisherOptions::use_default_callbacks`][2], a warning is logged
+    /// when a subscription with an incompatible QoS profile is discovered.
+    ///
     /// [1]: crate::Publisher
+    /// [2]: crate::PublisherOptions::use_default_callbacks
     // TODO: make publisher's lifetime depend on node's lifetime
     pub fn create_publisher<T>(
-        &self,
+        &mut self,
         topic: &str,
         qos: QoSProfile,
     ) -> Result<Publisher<T>, RclrsError>
     where
         T: Message,
     {
-        Publisher::<T>::new(self, topic, qos)
+        let options = self.entity_defaults.publisher_options.clone();
+        self.create_publisher_with_options(topic, qos, options)
+    }
+
+    /// Creates a [`Publisher`][1] with additional options.
+    ///
+    /// See [`Node::create_publisher`] and [`PublisherOptions`][2].
+    ///
+    /// [1]: crate::Publisher
+    /// [2]: crate::PublisherOptions
+    pub fn create_publisher_with_options<T>(
+        &mut self,
+        topic: &str,
+        qos: QoSProfile,
+        options: PublisherOptions,
+    ) -> Result<Publisher<T>, RclrsError>
+    where
+        T: Message,
+    {
+        let use_default_callbacks = options.use_default_callbacks;
+        let mut publisher = Publisher::<T>::new_with_options(self, topic, qos, options)?;
+        if use_default_callbacks && self.static_memory.is_none() {
+            publisher.incompatible_qos_event =
+                self.create_offered_incompatible_qos_warning(&publisher);
+        }
+        Ok(publisher)
+    }
+
+    /// Creates a [`ChunkedPublisher`][1], which splits messages into chunks for transports with a
+    /// small maximum message size.
+    ///
+    /// See [`Node::create_publisher`] for the errors. Returns an [`InvalidArgument`][2] error if
+    /// the maximum chunk size is too small for the chunk header.
+    ///
+    /// [1]: crate::ChunkedPublisher
+    /// [2]: crate::RclReturnCode::InvalidArgument
+    #[cfg(feature = "std_msgs")]
+    pub fn create_chunked_publisher<T>(
+        &mut self,
+        topic: &str,
+        qos: QoSProfile,
+        options: ChunkingOptions,
+    ) -> Result<ChunkedPublisher<T>, RclrsError>
+    where
+        T: Message,
+    {
+        ChunkedPublisher::new(self, topic, qos, options)
+    }
+
+    /// Creates a [`GenericPublisher`][1], which publishes messages of a type that is only known
+    /// at runtime in their serialized form.
+    ///
+    /// The type name has the form `package/msg/Type`, e.g. `std_msgs/msg/String`.
+    ///
+    /// [1]: crate::GenericPublisher
+    #[cfg(feature = "dyn_msg")]
+    pub fn create_generic_publisher(
+        &self,
+        topic: &str,
+        type_name: &str,
+        qos: QoSProfile,
+    ) -> Result<GenericPublisher, RclrsError> {
+        GenericPublisher::new(self, topic, type_name, qos)
     }
 
     /// Creates a [`Subscription`][1].
     ///
+    /// The subscription uses the default options of the node, see [`Node::entity_defaults`].
+    ///
+    /// In [static memory mode][2], this returns a [`BadAlloc`][3] error when the maximum number of
+    /// live subscriptions has been reached.
+    ///
     /// [1]: crate::Subscription
+    /// [2]: Node::enable_static_memory
+    /// [3]: crate::RclReturnCode::BadAlloc
     // TODO: make subscription's lifetime depend on node's lifetime
     pub fn create_subscription<T, F>(
         &mut self,
@@ -265,12 +786,1003 @@ impl Node {
         T: Message,
         F: FnMut(T) + 'static,
     {
-        let subscription = Arc::new(Subscription::<T>::new(self, topic, qos, callback)?);
+        let options = self.entity_defaults.subscription_options.clone();
+        self.create_subscription_with_options(topic, qos, options, callback)
+    }
+
+    /// Creates a [`Subscription`][1] with additional options.
+    ///
+    /// See [`Node::create_subscription`] and [`SubscriptionOptions`][2].
+    ///
+    /// [1]: crate::Subscription
+    /// [2]: crate::SubscriptionOptions
+    pub fn create_subscription_with_options<T, F>(
+        &mut self,
+        topic: &str,
+        qos: QoSProfile,
+        options: SubscriptionOptions,
+        callback: F,
+    ) -> Result<Arc<Subscription<T>>, RclrsError>
+    where
+        T: Message,
+        F: FnMut(T) + 'static,
+    {
+        if let Some(static_memory) = &self.static_memory {
+            let max_subscriptions = static_memory.lock().limits.max_subscriptions;
+            reserve_static_slot(&mut self.subscriptions, max_subscriptions)?;
+        }
+        let use_default_callbacks = options.use_default_callbacks;
+        let mut subscription =
+            Subscription::<T>::new_with_options(self, topic, qos, options, callback)?;
+        if use_default_callbacks && self.static_memory.is_none() {
+            subscription.incompatible_qos_event =
+                self.create_requested_incompatible_qos_warning(&subscription);
+        }
+        #[cfg(all(feature = "statistics_msgs", not(ros_distro = "foxy")))]
+        if self.static_memory.is_none() {
+            subscription.message_lost_event = self.create_message_lost_counter(&subscription);
+        }
+        let subscription = Arc::new(subscription);
+        subscription.trace_init(core::any::type_name::<F>());
+        self.subscriptions
+            .push(Arc::downgrade(&subscription) as Weak<dyn SubscriptionBase>);
+        Ok(subscription)
+    }
+
+    /// Creates a [`MultiSubscription`][1] to several topics of the same type, with one callback
+    /// that receives the expanded topic name together with each message.
+    ///
+    /// The subscriptions use the default [`SubscriptionOptions`][2] of this node. See
+    /// [`Node::create_subscription`] for the errors.
+    ///
+    /// [1]: crate::MultiSubscription
+    /// [2]: crate::SubscriptionOptions
+    pub fn create_multi_subscription<T, F>(
+        &mut self,
+        topics: &[&str],
+        qos: QoSProfile,
+        callback: F,
+    ) -> Result<Arc<MultiSubscription<T>>, RclrsError>
+    where
+        T: Message,
+        F: FnMut(&str, T) + 'static,
+    {
+        let options = self.entity_defaults.subscription_options.clone();
+        let multi_subscription = Arc::new(MultiSubscription::new(qos, options, callback));
+        for topic in topics {
+            multi_subscription.add_topic(self, topic)?;
+        }
+        Ok(multi_subscription)
+    }
+
+    /// Creates a [`ChunkedSubscription`][1], which reassembles the messages of a
+    /// [`ChunkedPublisher`][2].
+    ///
+    /// See [`Node::create_subscription`] for the errors.
+    ///
+    /// [1]: crate::ChunkedSubscription
+    /// [2]: crate::ChunkedPublisher
+    #[cfg(feature = "std_msgs")]
+    pub fn create_chunked_subscription<T, F>(
+        &mut self,
+        topic: &str,
+        qos: QoSProfile,
+        options: ChunkingOptions,
+        callback: F,
+    ) -> Result<ChunkedSubscription<T>, RclrsError>
+    where
+        T: Message,
+        F: FnMut(T) + 'static,
+    {
+        ChunkedSubscription::new(self, topic, qos, options, callback)
+    }
+
+    /// Creates a [`Subscription`][1] from an `rcl` subscription that was initialized outside of
+    /// `rclrs`, and adds it to this node so that its callback is executed when spinning.
+    ///
+    /// # Safety
+    /// See [`Subscription::from_raw`][2].
+    ///
+    /// [1]: crate::Subscription
+    /// [2]: crate::Subscription::from_raw
+    pub unsafe fn create_subscription_from_raw<T, F>(
+        &mut self,
+        rcl_subscription: *const rcl_subscription_t,
+        callback: F,
+    ) -> Result<Arc<Subscription<T>>, RclrsError>
+    where
+        T: Message,
+        F: FnMut(T) + 'static,
+    {
+        if let Some(static_memory) = &self.static_memory {
+            let max_subscriptions = static_memory.lock().limits.max_subscriptions;
+            reserve_static_slot(&mut self.subscriptions, max_subscriptions)?;
+        }
+        let subscription = Arc::new(Subscription::<T>::from_raw(
+            self,
+            rcl_subscription,
+            callback,
+        ));
+        self.subscriptions
+            .push(Arc::downgrade(&subscription) as Weak<dyn SubscriptionBase>);
+        Ok(subscription)
+    }
+
+    /// Creates a [`DynamicSubscription`][1], for a message type that is only known at runtime.
+    ///
+    /// The type name has the form `package/msg/Type`, as returned by
+    /// [`Node::get_topic_names_and_types`]. Like [`Node::create_subscription`], this returns a
+    /// [`BadAlloc`][2] error in static memory mode when the maximum number of live subscriptions
+    /// has been reached.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use rclrs::{Context, RclrsError, QOS_PROFILE_DEFAULT};
+    /// let context = Context::new([])?;
+    /// let mut node = context.create_node("dynamic_listener")?;
+    /// let _subscription = node.create_dynamic_subscription(
+    ///     "chatter",
+    ///     "std_msgs/msg/String",
+    ///     QOS_PROFILE_DEFAULT,
+    ///     |msg| println!("{msg}"),
+    /// )?;
+    /// rclrs::spin(&node)?;
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    ///
+    /// [1]: crate::DynamicSubscription
+    /// [2]: crate::RclReturnCode::BadAlloc
+    #[cfg(feature = "dyn_msg")]
+    pub fn create_dynamic_subscription<F>(
+        &mut self,
+        topic: &str,
+        type_name: &str,
+        qos: QoSProfile,
+        callback: F,
+    ) -> Result<Arc<DynamicSubscription>, RclrsError>
+    where
+        F: FnMut(crate::DynamicMessage) + 'static,
+    {
+        if let Some(static_memory) = &self.static_memory {
+            let max_subscriptions = static_memory.lock().limits.max_subscriptions;
+            reserve_static_slot(&mut self.subscriptions, max_subscriptions)?;
+        }
+        let subscription = Arc::new(DynamicSubscription::new(
+            self, topic, type_name, qos, callback,
+        )?);
         self.subscriptions
             .push(Arc::downgrade(&subscription) as Weak<dyn SubscriptionBase>);
         Ok(subscription)
     }
 
+    /// Creates a [`GenericSubscription`][1], which receives messages of a type that is only known
+    /// at runtime in their serialized form.
+    ///
+    /// The type name has the form `package/msg/Type`, e.g. `std_msgs/msg/String`.
+    ///
+    /// In [static memory mode][2], this returns a [`BadAlloc`][3] error when the maximum number of
+    /// live subscriptions has been reached.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use rclrs::{Context, RclrsError, QOS_PROFILE_DEFAULT};
+    /// let context = Context::new([])?;
+    /// let mut node = context.create_node("generic_listener")?;
+    /// let _subscription = node.create_generic_subscription(
+    ///     "chatter",
+    ///     "std_msgs/msg/String",
+    ///     QOS_PROFILE_DEFAULT,
+    ///     |data, _info| println!("Received {} bytes", data.len()),
+    /// )?;
+    /// rclrs::spin(&node)?;
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    ///
+    /// [1]: crate::GenericSubscription
+    /// [2]: Node::enable_static_memory
+    /// [3]: crate::RclReturnCode::BadAlloc
+    #[cfg(feature = "dyn_msg")]
+    pub fn create_generic_subscription<F>(
+        &mut self,
+        topic: &str,
+        type_name: &str,
+        qos: QoSProfile,
+        callback: F,
+    ) -> Result<Arc<GenericSubscription>, RclrsError>
+    where
+        F: FnMut(&[u8], crate::MessageInfo) + 'static,
+    {
+        if let Some(static_memory) = &self.static_memory {
+            let max_subscriptions = static_memory.lock().limits.max_subscriptions;
+            reserve_static_slot(&mut self.subscriptions, max_subscriptions)?;
+        }
+        let subscription = Arc::new(GenericSubscription::new(
+            self, topic, type_name, qos, callback,
+        )?);
+        self.subscriptions
+            .push(Arc::downgrade(&subscription) as Weak<dyn SubscriptionBase>);
+        Ok(subscription)
+    }
+
+    /// Creates a [`Client`][1].
+    ///
+    /// The client uses the default options of the node, see [`Node::entity_defaults`].
+    ///
+    /// In [static memory mode][2], this returns a [`BadAlloc`][3] error when the maximum number of
+    /// live clients has been reached.
+    ///
+    /// [1]: crate::Client
+    /// [2]: Node::enable_static_memory
+    /// [3]: crate::RclReturnCode::BadAlloc
+    pub fn create_client<T>(
+        &mut self,
+        service_name: &str,
+        qos: QoSProfile,
+    ) -> Result<Arc<Client<T>>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+    {
+        let options = self.entity_defaults.client_options;
+        self.create_client_with_options(service_name, qos, options)
+    }
+
+    /// Creates a [`Client`][1] with additional options.
+    ///
+    /// See [`Node::create_client`] and [`ClientOptions`][2].
+    ///
+    /// [1]: crate::Client
+    /// [2]: crate::ClientOptions
+    pub fn create_client_with_options<T>(
+        &mut self,
+        service_name: &str,
+        qos: QoSProfile,
+        options: ClientOptions,
+    ) -> Result<Arc<Client<T>>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+    {
+        if let Some(static_memory) = &self.static_memory {
+            let max_clients = static_memory.lock().limits.max_clients;
+            reserve_static_slot(&mut self.clients, max_clients)?;
+        }
+        let client = Arc::new(Client::<T>::new_with_options(
+            self,
+            service_name,
+            qos,
+            options,
+        )?);
+        client.trace_init();
+        self.clients
+            .push(Arc::downgrade(&client) as Weak<dyn ClientBase>);
+        Ok(client)
+    }
+
+    /// Creates a [`CachedClient`][1], which sends its last request again whenever the service
+    /// server (re)appears.
+    ///
+    /// The client uses the default options of the node. See [`Node::create_client`] for the
+    /// errors.
+    ///
+    /// [1]: crate::CachedClient
+    pub fn create_cached_client<T>(
+        &mut self,
+        service_name: &str,
+        qos: QoSProfile,
+    ) -> Result<Arc<CachedClient<T>>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+    {
+        let client = self.create_client::<T>(service_name, qos)?;
+        let cached_client = Arc::new(CachedClient::new(client)?);
+        self.graph_event_handlers
+            .push(Arc::downgrade(&cached_client) as Weak<dyn GraphEventHandler>);
+        Ok(cached_client)
+    }
+
+    /// Creates a [`ServiceWatchdog`][1] that tracks whether the service server of a client is
+    /// available.
+    ///
+    /// [1]: crate::ServiceWatchdog
+    pub fn create_service_watchdog<T>(
+        &mut self,
+        client: &Arc<Client<T>>,
+    ) -> Result<Arc<ServiceWatchdog>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+    {
+        let watchdog = Arc::new(ServiceWatchdog::new(client)?);
+        self.graph_event_handlers
+            .push(Arc::downgrade(&watchdog) as Weak<dyn GraphEventHandler>);
+        Ok(watchdog)
+    }
+
+    /// Creates [`GraphEvents`][1], a stream of the changes of the ROS graph.
+    ///
+    /// [1]: crate::GraphEvents
+    pub fn create_graph_events(&mut self) -> Result<Arc<GraphEvents>, RclrsError> {
+        let events = Arc::new(GraphEvents::new(
+            self.handle.clone(),
+            copy_rcutils_allocator(&self.allocator),
+        )?);
+        self.graph_event_handlers
+            .push(Arc::downgrade(&events) as Weak<dyn GraphEventHandler>);
+        Ok(events)
+    }
+
+    /// Creates a [`NodeMonitor`][1] that watches whether the given nodes are in the ROS graph,
+    /// and whether they send heartbeats.
+    ///
+    /// The node names are fully qualified, e.g. `/ns/my_node`. With a heartbeat timeout, this
+    /// also creates a timer on this node for checking the heartbeats, so see
+    /// [`Node::create_timer`] for the errors.
+    ///
+    /// [1]: crate::NodeMonitor
+    pub fn create_node_monitor<'a>(
+        &mut self,
+        nodes: impl IntoIterator<Item = &'a str>,
+        options: NodeMonitorOptions,
+    ) -> Result<Arc<NodeMonitor>, RclrsError> {
+        let monitor = Arc::new(NodeMonitor::new(
+            self.handle.clone(),
+            copy_rcutils_allocator(&self.allocator),
+            nodes,
+            options,
+        )?);
+        if let Some(timeout) = monitor.heartbeat_timeout() {
+            // The timer is owned by the monitor, so it only holds a weak reference to it.
+            let weak_monitor = Arc::downgrade(&monitor);
+            let timer =
+                self.add_timer(timeout / 4, Clock::new(ClockType::SteadyTime)?, move || {
+                    match weak_monitor.upgrade() {
+                        Some(monitor) => monitor.check_heartbeats(),
+                        None => Ok(()),
+                    }
+                })?;
+            monitor.set_timer(timer);
+        }
+        self.graph_event_handlers
+            .push(Arc::downgrade(&monitor) as Weak<dyn GraphEventHandler>);
+        Ok(monitor)
+    }
+
+    /// Creates a [`ClientPool`][1] of `size` clients for the same service.
+    ///
+    /// Returns an [`InvalidArgument`][2] error if the size is zero. See [`Node::create_client`]
+    /// for the other errors.
+    ///
+    /// [1]: crate::ClientPool
+    /// [2]: crate::RclReturnCode::InvalidArgument
+    pub fn create_client_pool<T>(
+        &mut self,
+        service_name: &str,
+        qos: QoSProfile,
+        size: usize,
+    ) -> Result<ClientPool<T>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+    {
+        if size == 0 {
+            return Err(RclrsError {
+                code: RclReturnCode::InvalidArgument,
+                msg: None,
+            });
+        }
+        let clients = (0..size)
+            .map(|_| self.create_client::<T>(service_name, qos))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ClientPool::new(clients))
+    }
+
+    /// Creates a [`Service`][1].
+    ///
+    /// The service uses the default options of the node, see [`Node::entity_defaults`].
+    ///
+    /// In [static memory mode][2], this returns a [`BadAlloc`][3] error when the maximum number of
+    /// live services has been reached.
+    ///
+    /// [1]: crate::Service
+    /// [2]: Node::enable_static_memory
+    /// [3]: crate::RclReturnCode::BadAlloc
+    pub fn create_service<T, F>(
+        &mut self,
+        service_name: &str,
+        qos: QoSProfile,
+        callback: F,
+    ) -> Result<Arc<Service<T>>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+        F: FnMut(T::Request) -> T::Response + 'static,
+    {
+        let options = self.entity_defaults.service_options;
+        self.create_service_with_options(service_name, qos, options, callback)
+    }
+
+    /// Creates a [`Service`][1] with additional options.
+    ///
+    /// See [`Node::create_service`] and [`ServiceOptions`][2].
+    ///
+    /// [1]: crate::Service
+    /// [2]: crate::ServiceOptions
+    pub fn create_service_with_options<T, F>(
+        &mut self,
+        service_name: &str,
+        qos: QoSProfile,
+        options: ServiceOptions,
+        callback: F,
+    ) -> Result<Arc<Service<T>>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+        F: FnMut(T::Request) -> T::Response + 'static,
+    {
+        if let Some(static_memory) = &self.static_memory {
+            let max_services = static_memory.lock().limits.max_services;
+            reserve_static_slot(&mut self.services, max_services)?;
+        }
+        let service = Arc::new(Service::<T>::new_with_options(
+            self,
+            service_name,
+            qos,
+            options,
+            callback,
+        )?);
+        service.trace_init(core::any::type_name::<F>());
+        self.services
+            .push(Arc::downgrade(&service) as Weak<dyn ServiceBase>);
+        Ok(service)
+    }
+
+    /// Creates a [`DeferredService`][1], whose callback responds through a
+    /// [`ServiceResponder`][2].
+    ///
+    /// The limit of concurrent requests and the shedding policy are set in the
+    /// [`ServiceOptions`][3]. In [static memory mode][4], this counts towards the maximum number of
+    /// live services, like [`Node::create_service`].
+    ///
+    /// [1]: crate::DeferredService
+    /// [2]: crate::ServiceResponder
+    /// [3]: crate::ServiceOptions
+    /// [4]: Node::enable_static_memory
+    pub fn create_deferred_service<T, F>(
+        &mut self,
+        service_name: &str,
+        qos: QoSProfile,
+        options: ServiceOptions,
+        callback: F,
+    ) -> Result<Arc<DeferredService<T>>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+        F: FnMut(T::Request, ServiceResponder<T>) + 'static,
+    {
+        if let Some(static_memory) = &self.static_memory {
+            let max_services = static_memory.lock().limits.max_services;
+            reserve_static_slot(&mut self.services, max_services)?;
+        }
+        let service = Arc::new(DeferredService::<T>::new(
+            self,
+            service_name,
+            qos,
+            options,
+            callback,
+        )?);
+        service.trace_init(core::any::type_name::<F>());
+        self.services
+            .push(Arc::downgrade(&service) as Weak<dyn ServiceBase>);
+        Ok(service)
+    }
+
+    /// Creates a [`TopicClient`][1], which sends requests over topics to a [`TopicService`][2].
+    ///
+    /// See [`Node::create_publisher`] and [`Node::create_subscription`] for the errors.
+    ///
+    /// [1]: crate::TopicClient
+    /// [2]: crate::TopicService
+    #[cfg(feature = "std_msgs")]
+    pub fn create_topic_client<TReq, TRes>(
+        &mut self,
+        service_name: &str,
+        qos: QoSProfile,
+    ) -> Result<TopicClient<TReq, TRes>, RclrsError>
+    where
+        TReq: Message,
+        TRes: Message,
+    {
+        TopicClient::new(self, service_name, qos)
+    }
+
+    /// Creates a [`TopicService`][1], which answers the requests of [`TopicClient`][2]s over
+    /// topics.
+    ///
+    /// See [`Node::create_publisher`] and [`Node::create_subscription`] for the errors.
+    ///
+    /// [1]: crate::TopicService
+    /// [2]: crate::TopicClient
+    #[cfg(feature = "std_msgs")]
+    pub fn create_topic_service<TReq, TRes, F>(
+        &mut self,
+        service_name: &str,
+        qos: QoSProfile,
+        callback: F,
+    ) -> Result<TopicService<TReq, TRes>, RclrsError>
+    where
+        TReq: Message,
+        TRes: Message,
+        F: FnMut(TReq) -> TRes + 'static,
+    {
+        TopicService::new(self, service_name, qos, callback)
+    }
+
+    /// Creates a [`Timer`][1] that runs the callback every `period`.
+    ///
+    /// Returns an [`InvalidArgument`][2] error if the period is zero.
+    ///
+    /// In [static memory mode][3], this returns a [`BadAlloc`][4] error when the maximum number of
+    /// live timers has been reached.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, RclrsError};
+    /// # use std::time::Duration;
+    /// let context = Context::new([])?;
+    /// let mut node = context.create_node("my_node")?;
+    /// let _timer = node.create_timer(Duration::from_millis(500), || println!("Tick"))?;
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    ///
+    /// [1]: crate::Timer
+    /// [2]: crate::RclReturnCode::InvalidArgument
+    /// [3]: Node::enable_static_memory
+    /// [4]: crate::RclReturnCode::BadAlloc
+    pub fn create_timer<F>(
+        &mut self,
+        period: Duration,
+        mut callback: F,
+    ) -> Result<Arc<Timer>, RclrsError>
+    where
+        F: FnMut() + 'static,
+    {
+        self.add_timer(period, Clock::new(ClockType::SteadyTime)?, move || {
+            callback();
+            Ok(())
+        })
+    }
+
+    /// Creates a [`Timer`][1] that runs the callback every `period` on the given clock.
+    ///
+    /// With the node's [clock][2], the timer follows the ROS time, so it is driven by simulated
+    /// time, or by a [`ManualClock`][3] in tests. See [`Node::create_timer`] for the errors.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, RclrsError};
+    /// # use std::time::Duration;
+    /// let context = Context::new([])?;
+    /// let mut node = context.create_node("my_node")?;
+    /// let clock = node.get_clock();
+    /// let _timer = node.create_timer_with_clock(Duration::from_secs(1), &clock, || {
+    ///     println!("One second of ROS time has passed")
+    /// })?;
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    ///
+    /// [1]: crate::Timer
+    /// [2]: Node::get_clock
+    /// [3]: crate::testing::ManualClock
+    pub fn create_timer_with_clock<F>(
+        &mut self,
+        period: Duration,
+        clock: &Clock,
+        mut callback: F,
+    ) -> Result<Arc<Timer>, RclrsError>
+    where
+        F: FnMut() + 'static,
+    {
+        self.add_timer(period, clock.clone(), move || {
+            callback();
+            Ok(())
+        })
+    }
+
+    /// Creates a [`Timer`][1] that publishes the message returned by the callback every `period`.
+    ///
+    /// This is a shorthand for creating a [`Publisher`] and a timer whose callback publishes to
+    /// it. When the callback returns `None`, nothing is published in that period, e.g. when no
+    /// new data is available yet. The publisher is owned by the timer, so it is dropped together
+    /// with it.
+    ///
+    /// Errors from publishing are returned by [`spin_once`][2] or [`spin`][3]. See
+    /// [`Node::create_timer`] for the other errors.
+    ///
+    /// # Example
+    /// ```ignore
+    /// # use rclrs::{Context, RclrsError, QOS_PROFILE_DEFAULT};
+    /// # use std::time::Duration;
+    /// let context = Context::new([])?;
+    /// let mut node = context.create_node("minimal_publisher")?;
+    /// let mut publish_count: u32 = 1;
+    /// let _timer = node.create_publisher_timer(
+    ///     "topic",
+    ///     QOS_PROFILE_DEFAULT,
+    ///     Duration::from_millis(500),
+    ///     move || {
+    ///         let data = format!("Hello, world! {}", publish_count);
+    ///         publish_count += 1;
+    ///         Some(std_msgs::msg::String { data })
+    ///     },
+    /// )?;
+    /// rclrs::spin(&node)?;
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    ///
+    /// [1]: crate::Timer
+    /// [2]: crate::spin_once
+    /// [3]: crate::spin
+    pub fn create_publisher_timer<T, F>(
+        &mut self,
+        topic: &str,
+        qos: QoSProfile,
+        period: Duration,
+        mut callback: F,
+    ) -> Result<Arc<Timer>, RclrsError>
+    where
+        T: Message,
+        F: FnMut() -> Option<T> + 'static,
+    {
+        let publisher = self.create_publisher::<T>(topic, qos)?;
+        self.add_timer(
+            period,
+            Clock::new(ClockType::SteadyTime)?,
+            move || match callback() {
+                Some(msg) => publisher.publish(msg),
+                None => Ok(()),
+            },
+        )
+    }
+
+    fn add_timer<F>(
+        &mut self,
+        period: Duration,
+        clock: Clock,
+        callback: F,
+    ) -> Result<Arc<Timer>, RclrsError>
+    where
+        F: FnMut() -> Result<(), RclrsError> + 'static,
+    {
+        if let Some(static_memory) = &self.static_memory {
+            let max_timers = static_memory.lock().limits.max_timers;
+            reserve_static_slot(&mut self.timers, max_timers)?;
+        }
+        let timer = Arc::new(Timer::new(self, period, clock, callback)?);
+        timer.trace_init(self, core::any::type_name::<F>());
+        self.timers
+            .push(Arc::downgrade(&timer) as Weak<dyn TimerBase>);
+        Ok(timer)
+    }
+
+    /// Creates a [`QoSEvent`][1] handler for a publisher of this node.
+    ///
+    /// The status type `S` determines which event is handled. A warning for
+    /// [`OfferedIncompatibleQoS`][4] is already logged by default, see
+    /// [`PublisherOptions::use_default_callbacks`][5].
+    ///
+    /// In [static memory mode][2], this returns a [`BadAlloc`][3] error when the maximum number of
+    /// live event handlers has been reached.
+    ///
+    /// # Example
+    /// ```ignore
+    /// # use rclrs::{Context, OfferedIncompatibleQoS, PublisherOptions, RclrsError, QOS_PROFILE_DEFAULT};
+    /// let context = Context::new([])?;
+    /// let mut node = context.create_node("my_node")?;
+    /// let options = PublisherOptions {
+    ///     use_default_callbacks: false,
+    ///     ..Default::default()
+    /// };
+    /// let publisher = node.create_publisher_with_options::<std_msgs::msg::String>(
+    ///     "topic",
+    ///     QOS_PROFILE_DEFAULT,
+    ///     options,
+    /// )?;
+    /// let _event = node.create_publisher_event(&publisher, |status: OfferedIncompatibleQoS| {
+    ///     eprintln!("{}", status);
+    /// })?;
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    ///
+    /// [1]: crate::QoSEvent
+    /// [2]: Node::enable_static_memory
+    /// [3]: crate::RclReturnCode::BadAlloc
+    /// [4]: crate::OfferedIncompatibleQoS
+    /// [5]: crate::PublisherOptions::use_default_callbacks
+    pub fn create_publisher_event<S, T, F>(
+        &mut self,
+        publisher: &Publisher<T>,
+        callback: F,
+    ) -> Result<Arc<QoSEvent<S>>, RclrsError>
+    where
+        S: PublisherEventStatus,
+        T: Message,
+        F: FnMut(S) + 'static,
+    {
+        self.reserve_qos_event_slot()?;
+        let event = Arc::new(QoSEvent::new_for_publisher(publisher, callback)?);
+        self.qos_events
+            .push(Arc::downgrade(&event) as Weak<dyn QoSEventBase>);
+        Ok(event)
+    }
+
+    /// Creates a [`QoSEvent`][1] handler for a subscription of this node.
+    ///
+    /// The status type `S` determines which event is handled. A warning for
+    /// [`RequestedIncompatibleQoS`][2] is already logged by default, see
+    /// [`SubscriptionOptions::use_default_callbacks`][5].
+    ///
+    /// In [static memory mode][3], this returns a [`BadAlloc`][4] error when the maximum number of
+    /// live event handlers has been reached.
+    ///
+    /// [1]: crate::QoSEvent
+    /// [2]: crate::RequestedIncompatibleQoS
+    /// [3]: Node::enable_static_memory
+    /// [4]: crate::RclReturnCode::BadAlloc
+    /// [5]: crate::SubscriptionOptions::use_default_callbacks
+    pub fn create_subscription_event<S, T, F>(
+        &mut self,
+        subscription: &Subscription<T>,
+        callback: F,
+    ) -> Result<Arc<QoSEvent<S>>, RclrsError>
+    where
+        S: SubscriptionEventStatus,
+        T: Message,
+        F: FnMut(S) + 'static,
+    {
+        self.reserve_qos_event_slot()?;
+        let event = Arc::new(QoSEvent::new_for_subscription(subscription, callback)?);
+        self.qos_events
+            .push(Arc::downgrade(&event) as Weak<dyn QoSEventBase>);
+        Ok(event)
+    }
+
+    /// Runs a callback whenever messages for a subscription of this node were lost.
+    ///
+    /// This is a shorthand for [`Node::create_subscription_event`] with the [`MessageLost`][1]
+    /// status, which carries the number of lost messages. It makes data loss observable, e.g.
+    /// when messages are dropped because the [history depth][2] of the subscription is too small
+    /// for the rate at which the node is spun. Not every RMW implementation reports lost messages,
+    /// in which case an [`Unsupported`][3] error is returned.
+    ///
+    /// # Example
+    /// ```ignore
+    /// # use rclrs::{Context, RclrsError, QOS_PROFILE_DEFAULT};
+    /// let context = Context::new([])?;
+    /// let mut node = context.create_node("my_node")?;
+    /// let subscription = node.create_subscription(
+    ///     "topic",
+    ///     QOS_PROFILE_DEFAULT,
+    ///     |_msg: std_msgs::msg::String| {},
+    /// )?;
+    /// let _handler = node.on_message_lost(&subscription, |status| {
+    ///     eprintln!("Lost {} messages", status.total_count_change);
+    /// })?;
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    ///
+    /// [1]: crate::MessageLost
+    /// [2]: crate::QoSProfile::history
+    /// [3]: crate::RclReturnCode::Unsupported
+    #[cfg(not(ros_distro = "foxy"))]
+    pub fn on_message_lost<T, F>(
+        &mut self,
+        subscription: &Subscription<T>,
+        callback: F,
+    ) -> Result<Arc<QoSEvent<MessageLost>>, RclrsError>
+    where
+        T: Message,
+        F: FnMut(MessageLost) + 'static,
+    {
+        self.create_subscription_event(subscription, callback)
+    }
+
+    // Creates the event handler that logs a warning for publishers with an incompatible QoS
+    // profile, like the default callback of rclcpp. RMW implementations that do not support the
+    // event are skipped silently, like in rclcpp.
+    fn create_requested_incompatible_qos_warning<T: Message>(
+        &mut self,
+        subscription: &Subscription<T>,
+    ) -> Option<Arc<QoSEvent<RequestedIncompatibleQoS>>> {
+        let logger_name = self.logger_name();
+        let event = QoSEvent::new_for_subscription(
+            subscription,
+            move |status: RequestedIncompatibleQoS| {
+                log(&logger_name, LogSeverity::Warn, &status.to_string())
+            },
+        );
+        let event = Arc::new(event.ok()?);
+        self.qos_events
+            .push(Arc::downgrade(&event) as Weak<dyn QoSEventBase>);
+        Some(event)
+    }
+
+    // Creates the event handler that logs a warning for subscriptions with an incompatible QoS
+    // profile, the counterpart of `create_requested_incompatible_qos_warning`.
+    fn create_offered_incompatible_qos_warning<T: Message>(
+        &mut self,
+        publisher: &Publisher<T>,
+    ) -> Option<Arc<QoSEvent<OfferedIncompatibleQoS>>> {
+        let logger_name = self.logger_name();
+        let event =
+            QoSEvent::new_for_publisher(publisher, move |status: OfferedIncompatibleQoS| {
+                log(&logger_name, LogSeverity::Warn, &status.to_string())
+            });
+        let event = Arc::new(event.ok()?);
+        self.qos_events
+            .push(Arc::downgrade(&event) as Weak<dyn QoSEventBase>);
+        Some(event)
+    }
+
+    // Creates the event handler that adds the lost messages of a subscription to the statistics
+    // of the node, if it collects statistics. Like for the incompatible QoS warning, RMW
+    // implementations that do not support the event are skipped silently.
+    #[cfg(all(feature = "statistics_msgs", not(ros_distro = "foxy")))]
+    fn create_message_lost_counter<T: Message>(
+        &mut self,
+        subscription: &Subscription<T>,
+    ) -> Option<Arc<QoSEvent<MessageLost>>> {
+        let statistics = self.statistics.clone()?;
+        let event = QoSEvent::new_for_subscription(subscription, move |status: MessageLost| {
+            statistics.record_lost_messages(status.total_count_change)
+        });
+        let event = Arc::new(event.ok()?);
+        self.qos_events
+            .push(Arc::downgrade(&event) as Weak<dyn QoSEventBase>);
+        Some(event)
+    }
+
+    fn reserve_qos_event_slot(&mut self) -> Result<(), RclrsError> {
+        if let Some(static_memory) = &self.static_memory {
+            let max_qos_events = static_memory.lock().limits.max_qos_events;
+            reserve_static_slot(&mut self.qos_events, max_qos_events)?;
+        }
+        Ok(())
+    }
+
+    /// Enables static memory mode for this node.
+    ///
+    /// In static memory mode, the bookkeeping storage needed for creating subscriptions, timers,
+    /// clients, services and QoS event handlers and for spinning the node is allocated up front, by
+    /// this function. Afterwards,
+    /// - creating more live entities of a kind than its limit in [`StaticMemoryLimits`], e.g.
+    ///   more live subscriptions than [`StaticMemoryLimits::max_subscriptions`], returns a
+    ///   [`BadAlloc`][1] error instead of growing the corresponding list
+    /// - [`spin_once`][2] reuses a preallocated wait set instead of creating a new one in every call
+    /// - sending more pending requests from a client than
+    ///   [`StaticMemoryLimits::max_pending_requests`] returns a [`BadAlloc`][1] error, which also
+    ///   bounds the responses that are buffered for [ordered responses][3]
+    /// - services take at most [`StaticMemoryLimits::max_pending_requests`] requests at once, into
+    ///   storage that is allocated when the service is created, and leave the rest in the queue
+    ///
+    /// This bounds the memory that the node uses after initialization, but it does not make it
+    /// allocation-free: storing the callback of a client request, taking and publishing messages
+    /// and the `rmw` implementation may still allocate, depending on the message type. Prefer
+    /// RMW-native messages with bounded strings and sequences in real-time code.
+    ///
+    /// Returns a [`BadAlloc`][1] error if the node already has more live entities than the limits
+    /// allow.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, RclrsError, StaticMemoryLimits};
+    /// let context = Context::new([])?;
+    /// let mut node = context.create_node("static_node")?;
+    /// node.enable_static_memory(StaticMemoryLimits {
+    ///     max_subscriptions: 4,
+    ///     max_timers: 1,
+    ///     max_clients: 0,
+    ///     max_services: 2,
+    ///     max_qos_events: 0,
+    ///     max_pending_requests: 8,
+    /// })?;
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    ///
+    /// [1]: crate::RclReturnCode::BadAlloc
+    /// [2]: crate::spin_once
+    /// [3]: crate::ClientOptions::ordered_responses
+    pub fn enable_static_memory(&mut self, limits: StaticMemoryLimits) -> Result<(), RclrsError> {
+        self.subscriptions.retain(|weak| weak.strong_count() > 0);
+        self.timers.retain(|weak| weak.strong_count() > 0);
+        self.clients.retain(|weak| weak.strong_count() > 0);
+        self.services.retain(|weak| weak.strong_count() > 0);
+        self.qos_events.retain(|weak| weak.strong_count() > 0);
+        if self.subscriptions.len() > limits.max_subscriptions
+            || self.timers.len() > limits.max_timers
+            || self.clients.len() > limits.max_clients
+            || self.services.len() > limits.max_services
+            || self.qos_events.len() > limits.max_qos_events
+        {
+            return Err(RclrsError {
+                code: RclReturnCode::BadAlloc,
+                msg: None,
+            });
+        }
+        self.subscriptions
+            .reserve(limits.max_subscriptions - self.subscriptions.len());
+        self.timers.reserve(limits.max_timers - self.timers.len());
+        self.clients
+            .reserve(limits.max_clients - self.clients.len());
+        self.services
+            .reserve(limits.max_services - self.services.len());
+        self.qos_events
+            .reserve(limits.max_qos_events - self.qos_events.len());
+        // The graph guard condition is always waited on, since graph event handlers may be added
+        // later.
+        self.static_memory = Some(Mutex::new(StaticMemory::new(
+            limits,
+            self.graph_guard_condition.clone(),
+            &self.get_context(),
+        )?));
+        Ok(())
+    }
+
+    // The limit of pending requests for the clients and services of this node, in static memory
+    // mode.
+    pub(crate) fn max_pending_requests(&self) -> Option<usize> {
+        self.static_memory
+            .as_ref()
+            .map(|static_memory| static_memory.lock().limits.max_pending_requests)
+    }
+
+    /// Sets the hooks that run around each callback when spinning this node.
+    ///
+    /// This replaces previously set hooks. See [`CallbackHooks`].
+    pub fn set_callback_hooks(&mut self, hooks: CallbackHooks) {
+        self.callback_hooks = Some(hooks);
+    }
+
+    /// Sets the hooks that run when the clients and services of this node send, receive and
+    /// answer requests, e.g. for correlating their log output.
+    ///
+    /// This replaces previously set hooks, for the clients and services that are created
+    /// afterwards. See [`TraceHooks`].
+    pub fn set_trace_hooks(&mut self, hooks: TraceHooks) {
+        self.trace_hooks = Some(Arc::new(hooks));
+    }
+
+    /// Returns a `Context` that shares its handle and allocator with this node.
+    pub(crate) fn get_context(&self) -> Context {
+        Context {
+            handle: self.context.clone(),
+            allocator: copy_rcutils_allocator(&self.allocator),
+        }
+    }
+
+    /// Returns the QoS event handlers that have not been dropped yet.
+    pub(crate) fn live_qos_events(&self) -> Vec<Arc<dyn QoSEventBase>> {
+        self.qos_events.iter().filter_map(Weak::upgrade).collect()
+    }
+
+    /// Returns the guard conditions that the executor waits on for this node, which is the
+    /// graph guard condition if there are graph event handlers.
+    pub(crate) fn guard_conditions(&self) -> Vec<Arc<GuardCondition>> {
+        if self.graph_event_handlers.is_empty() {
+            Vec::new()
+        } else {
+            alloc::vec![self.graph_guard_condition.clone()]
+        }
+    }
+
+    /// Returns the clients that have not been dropped yet.
+    pub(crate) fn live_clients(&self) -> Vec<Arc<dyn ClientBase>> {
+        self.clients.iter().filter_map(Weak::upgrade).collect()
+    }
+
+    /// Returns the services that have not been dropped yet.
+    pub(crate) fn live_services(&self) -> Vec<Arc<dyn ServiceBase>> {
+        self.services.iter().filter_map(Weak::upgrade).collect()
+    }
+
+    /// Returns the timers that have not been dropped yet.
+    pub(crate) fn live_timers(&self) -> Vec<Arc<dyn TimerBase>> {
+        self.timers.iter().filter_map(Weak::upgrade).collect()
+    }
+
     /// Returns the subscriptions that have not been dropped yet.
     pub(crate) fn live_subscriptions(&self) -> Vec<Arc<dyn SubscriptionBase>> {
         self.subscriptions
@@ -311,4 +1823,39 @@ impl Node {
         debug_assert_eq!(ret, 0);
         domain_id
     }
+
+    /// Returns a pointer to the underlying `rcl` node, for calling `rcl` and `rmw` functions that
+    /// are not wrapped by `rclrs`. The types and functions are available in [`rcl_bindings`][1].
+    ///
+    /// The pointer stays valid as long as the node, or any of the entities created from it, is
+    /// alive.
+    ///
+    /// # Safety
+    /// `rclrs` accesses the handle only while holding an internal lock, which the pointer bypasses.
+    /// The caller must therefore make sure that the handle is not used by `rclrs` at the same
+    /// time, e.g. by not spinning the node or creating entities on another thread meanwhile,
+    /// unless the called function is documented as thread-safe.
+    /// The node must not be finalized through the pointer.
+    ///
+    /// [1]: crate::rcl_bindings
+    pub unsafe fn raw_handle(&self) -> *mut rcl_node_t {
+        &mut *self.handle.lock()
+    }
+}
+
+/// Frees up the slots of dropped entities, and checks that one more fits into the limit.
+///
+/// This does not allocate, since the list has been reserved by [`Node::enable_static_memory`].
+fn reserve_static_slot<E: ?Sized>(
+    entities: &mut Vec<Weak<E>>,
+    max: usize,
+) -> Result<(), RclrsError> {
+    entities.retain(|weak| weak.strong_count() > 0);
+    if entities.len() >= max {
+        return Err(RclrsError {
+            code: RclReturnCode::BadAlloc,
+            msg: None,
+        });
+    }
+    Ok(())
 }
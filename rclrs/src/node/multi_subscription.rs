@@ -0,0 +1,125 @@
+use crate::qos::QoSProfile;
+use crate::sync::Mutex;
+use crate::{Node, RclrsError, Subscription, SubscriptionOptions};
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use rosidl_runtime_rs::{Message, RmwMessage};
+
+type TopicCallback<T> = Arc<Mutex<Box<dyn FnMut(&str, T) + 'static>>>;
+
+/// A group of subscriptions to several topics of the same type, which share one callback.
+///
+/// The callback receives the name of the topic that each message arrived on, e.g. for an
+/// aggregator that merges the odometry of several robots. Topics can be added and removed at any
+/// time, and [`MultiSubscription::discover`] subscribes to all topics of the type that are in the
+/// ROS graph, e.g. after a [`GraphEvent::TopicAdded`][1].
+///
+/// Create a multi-subscription with [`Node::create_multi_subscription`].
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let odometry = node.create_multi_subscription(
+///     &["/robot1/odom", "/robot2/odom"],
+///     QOS_PROFILE_DEFAULT,
+///     |topic: &str, msg: nav_msgs::msg::Odometry| {
+///         println!("{topic}: {:?}", msg.pose.pose.position);
+///     },
+/// )?;
+/// // Also subscribe to the odometry of robots that are started later.
+/// odometry.discover(&mut node, |topic| topic.ends_with("/odom"))?;
+/// ```
+///
+/// [1]: crate::GraphEvent::TopicAdded
+pub struct MultiSubscription<T: Message> {
+    qos: QoSProfile,
+    options: SubscriptionOptions,
+    callback: TopicCallback<T>,
+    // The subscriptions, by expanded topic name.
+    subscriptions: Mutex<BTreeMap<String, Arc<Subscription<T>>>>,
+}
+
+impl<T: Message> MultiSubscription<T> {
+    pub(crate) fn new<F>(qos: QoSProfile, options: SubscriptionOptions, callback: F) -> Self
+    where
+        F: FnMut(&str, T) + 'static,
+    {
+        Self {
+            qos,
+            options,
+            callback: Arc::new(Mutex::new(Box::new(callback))),
+            subscriptions: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Subscribes to a topic, with the QoS profile and options of the group.
+    ///
+    /// Returns `false` if the group already subscribes to the topic.
+    pub fn add_topic(&self, node: &mut Node, topic: &str) -> Result<bool, RclrsError> {
+        let topic = node.expand_topic_name(topic)?;
+        if self.subscriptions.lock().contains_key(&topic) {
+            return Ok(false);
+        }
+        let callback = Arc::clone(&self.callback);
+        let callback_topic = topic.clone();
+        let subscription = node.create_subscription_with_options(
+            &topic,
+            self.qos,
+            self.options.clone(),
+            move |msg: T| (callback.lock())(&callback_topic, msg),
+        )?;
+        self.subscriptions.lock().insert(topic, subscription);
+        Ok(true)
+    }
+
+    /// Unsubscribes from a topic.
+    ///
+    /// The topic name must be expanded, as returned by [`MultiSubscription::topics`]. Returns
+    /// `false` if the group does not subscribe to the topic.
+    pub fn remove_topic(&self, topic: &str) -> bool {
+        self.subscriptions.lock().remove(topic).is_some()
+    }
+
+    /// Returns the expanded names of the topics that the group subscribes to.
+    pub fn topics(&self) -> Vec<String> {
+        self.subscriptions.lock().keys().cloned().collect()
+    }
+
+    /// Returns the subscription to a topic, e.g. for its statistics or publisher count.
+    ///
+    /// The topic name must be expanded, as returned by [`MultiSubscription::topics`].
+    pub fn subscription(&self, topic: &str) -> Option<Arc<Subscription<T>>> {
+        self.subscriptions.lock().get(topic).cloned()
+    }
+
+    /// Subscribes to the topics in the ROS graph that have the message type of the group, and
+    /// that the filter accepts, and returns the names of the newly subscribed topics.
+    ///
+    /// The topics are discovered asynchronously, so this should be called again when the graph
+    /// changes, e.g. on a [`GraphEvent::TopicAdded`][1]. Topics are not removed when they
+    /// disappear from the graph, since their publishers may come back.
+    ///
+    /// [1]: crate::GraphEvent::TopicAdded
+    pub fn discover(
+        &self,
+        node: &mut Node,
+        filter: impl Fn(&str) -> bool,
+    ) -> Result<Vec<String>, RclrsError> {
+        let type_name = <T as Message>::RmwMsg::TYPE_NAME;
+        let mut added = Vec::new();
+        for (topic, types) in node.get_topic_names_and_types()? {
+            if types.iter().any(|t| t == type_name)
+                && filter(&topic)
+                && self.add_topic(node, &topic)?
+            {
+                added.push(topic);
+            }
+        }
+        Ok(added)
+    }
+}
@@ -0,0 +1,192 @@
+use crate::qos::QoSProfile;
+use crate::{Client, Node, Publisher, RclReturnCode, RclrsError};
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use rosidl_runtime_rs::{Message, Service};
+
+/// Creates one publisher or client per namespace from a template name, e.g. for a fleet
+/// controller that addresses many robots.
+///
+/// The template is either a relative name, which is put into each namespace, or contains the
+/// placeholder `{ns}`, which is replaced with each namespace:
+///
+/// | Namespace | Template | Name |
+/// | -- | -- | -- |
+/// | `/robot1` | `cmd_vel` | `/robot1/cmd_vel` |
+/// | `/robot1` | `/fleet{ns}/goal` | `/fleet/robot1/goal` |
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let fanout = NamespaceFanout::new(["robot1", "robot2", "robot3"]);
+/// let cmd_vel = fanout.create_publishers::<geometry_msgs::msg::Twist>(
+///     &mut node,
+///     "cmd_vel",
+///     QOS_PROFILE_DEFAULT,
+/// )?;
+/// // Stop all robots
+/// cmd_vel.publish_all(&geometry_msgs::msg::Twist::default())?;
+/// // Move one of them
+/// cmd_vel["/robot2"].publish(&twist)?;
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NamespaceFanout {
+    namespaces: Vec<String>,
+}
+
+impl NamespaceFanout {
+    /// Creates a fanout for the namespaces, e.g. `robot1` or `/fleet/robot1`.
+    ///
+    /// The namespaces are made absolute, and duplicates are removed.
+    pub fn new<I, S>(namespaces: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut normalized: Vec<String> = Vec::new();
+        for namespace in namespaces {
+            let namespace = namespace.as_ref().trim_matches('/');
+            let namespace = format!("/{namespace}");
+            if !normalized.contains(&namespace) {
+                normalized.push(namespace);
+            }
+        }
+        Self {
+            namespaces: normalized,
+        }
+    }
+
+    /// Returns the absolute namespaces, in the order they were given.
+    pub fn namespaces(&self) -> &[String] {
+        &self.namespaces
+    }
+
+    /// Returns the name that the template gives for a namespace.
+    ///
+    /// Returns an [`InvalidArgument`][1] error if the template is an absolute name without the
+    /// `{ns}` placeholder, since it would give the same name for all namespaces.
+    ///
+    /// [1]: crate::RclReturnCode::InvalidArgument
+    pub fn name_for(&self, namespace: &str, template: &str) -> Result<String, RclrsError> {
+        let namespace = namespace.trim_end_matches('/');
+        if template.contains("{ns}") {
+            Ok(template.replace("{ns}", namespace))
+        } else if template.starts_with('/') || template.starts_with('~') {
+            Err(RclrsError::with_message(
+                RclReturnCode::InvalidArgument,
+                format!(
+                    "The template '{template}' must be a relative name, or contain the \
+                     placeholder {{ns}}"
+                ),
+            ))
+        } else {
+            Ok(format!("{namespace}/{template}"))
+        }
+    }
+
+    /// Creates a [`Publisher`] in each namespace.
+    ///
+    /// See [`Node::create_publisher`] for the errors, and [`NamespaceFanout::name_for`] for the
+    /// template.
+    pub fn create_publishers<T: Message>(
+        &self,
+        node: &mut Node,
+        template: &str,
+        qos: QoSProfile,
+    ) -> Result<Fanout<Publisher<T>>, RclrsError> {
+        self.create_each(template, |name| node.create_publisher(name, qos))
+    }
+
+    /// Creates a [`Client`] in each namespace.
+    ///
+    /// See [`Node::create_client`] for the errors, and [`NamespaceFanout::name_for`] for the
+    /// template.
+    pub fn create_clients<T: Service>(
+        &self,
+        node: &mut Node,
+        template: &str,
+        qos: QoSProfile,
+    ) -> Result<Fanout<Arc<Client<T>>>, RclrsError> {
+        self.create_each(template, |name| node.create_client(name, qos))
+    }
+
+    fn create_each<E>(
+        &self,
+        template: &str,
+        mut create: impl FnMut(&str) -> Result<E, RclrsError>,
+    ) -> Result<Fanout<E>, RclrsError> {
+        let entities = self
+            .namespaces
+            .iter()
+            .map(|namespace| {
+                let name = self.name_for(namespace, template)?;
+                Ok((namespace.clone(), create(&name)?))
+            })
+            .collect::<Result<_, RclrsError>>()?;
+        Ok(Fanout { entities })
+    }
+}
+
+/// The entities that a [`NamespaceFanout`] created, by namespace.
+///
+/// The entities can be looked up with [`Fanout::get`] or by indexing with the absolute
+/// namespace, e.g. `fanout["/robot1"]`.
+pub struct Fanout<E> {
+    entities: BTreeMap<String, E>,
+}
+
+impl<E> Fanout<E> {
+    /// Returns the entity of a namespace, e.g. `robot1` or `/robot1`.
+    pub fn get(&self, namespace: &str) -> Option<&E> {
+        self.entities
+            .get(&format!("/{}", namespace.trim_matches('/')))
+    }
+
+    /// Returns the namespaces and their entities, ordered by namespace.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &E)> {
+        self.entities
+            .iter()
+            .map(|(namespace, entity)| (namespace.as_str(), entity))
+    }
+
+    /// Returns the number of entities.
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Returns `true` if there are no entities.
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+impl<E> core::ops::Index<&str> for Fanout<E> {
+    type Output = E;
+
+    fn index(&self, namespace: &str) -> &E {
+        self.get(namespace)
+            .unwrap_or_else(|| panic!("No entity for the namespace '{namespace}'"))
+    }
+}
+
+impl<T: Message> Fanout<Publisher<T>> {
+    /// Publishes a message in all namespaces.
+    ///
+    /// The message is published to every publisher, even if some of them fail. Returns the first
+    /// error, if any.
+    pub fn publish_all(&self, message: &T) -> Result<(), RclrsError> {
+        let mut result = Ok(());
+        for publisher in self.entities.values() {
+            let published = publisher.publish(message);
+            if result.is_ok() {
+                result = published;
+            }
+        }
+        result
+    }
+}
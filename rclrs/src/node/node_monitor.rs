@@ -0,0 +1,250 @@
+use crate::node::graph::node_names;
+use crate::rcl_bindings::rcutils_allocator_t;
+use crate::sync::Mutex;
+use crate::{Clock, ClockType, GraphEventHandler, NodeHandle, RclrsError, Time, Timer};
+
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+/// The status of a node that is watched by a [`NodeMonitor`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NodeStatus {
+    /// The node is in the ROS graph, and has sent a heartbeat within the timeout, if heartbeats
+    /// are checked.
+    Alive,
+    /// The node is in the ROS graph, but has not sent a heartbeat within the timeout.
+    Unresponsive,
+    /// The node is not in the ROS graph.
+    Gone,
+}
+
+/// The options for a [`NodeMonitor`], see [`Node::create_node_monitor`][1].
+///
+/// [1]: crate::Node::create_node_monitor
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NodeMonitorOptions {
+    /// The time after which a node that is in the graph, but has not sent a heartbeat, is
+    /// considered [unresponsive][1]. Heartbeats are not checked if this is `None`.
+    ///
+    /// [1]: NodeStatus::Unresponsive
+    pub heartbeat_timeout: Option<Duration>,
+}
+
+type StatusCallback = Box<dyn FnMut(&str, NodeStatus) + 'static>;
+
+struct WatchedNode {
+    status: NodeStatus,
+    // The time of the last heartbeat, or of when the node appeared in the graph.
+    last_heartbeat: Time,
+}
+
+/// Watches a set of nodes, and runs a callback when one of them disappears from the ROS graph,
+/// reappears, or stops sending heartbeats.
+///
+/// Create a monitor with [`Node::create_node_monitor`][1]. This is the building block for
+/// supervisors, e.g. a lifecycle manager that restarts or deactivates its nodes when one of them
+/// is lost, similar to `bond` in ROS 1.
+///
+/// A node that crashes may stay in the graph until the RMW implementation notices that it is
+/// gone, which can take several seconds. Heartbeats detect hanging or crashed nodes sooner: with
+/// a [`heartbeat_timeout`][2], the monitored nodes are expected to send a heartbeat regularly,
+/// e.g. by publishing to a topic, and the receiving side reports them with
+/// [`NodeMonitor::heartbeat`]. The monitor checks the heartbeats with a timer on the node, at a
+/// quarter of the timeout.
+///
+/// The callback only runs on changes: nodes are reported by [`NodeMonitor::status`] from the
+/// start, but the callback is not run for their initial status.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// # use std::time::Duration;
+/// let monitor = node.create_node_monitor(
+///     ["/driver", "/planner"],
+///     NodeMonitorOptions {
+///         heartbeat_timeout: Some(Duration::from_secs(1)),
+///     },
+/// )?;
+/// monitor.on_status_changed(|name, status| println!("{name} is now {status:?}"));
+/// let heartbeat_monitor = Arc::clone(&monitor);
+/// let _subscription = node.create_subscription(
+///     "heartbeat",
+///     QOS_PROFILE_DEFAULT,
+///     move |msg: std_msgs::msg::String| heartbeat_monitor.heartbeat(&msg.data),
+/// )?;
+/// ```
+///
+/// [1]: crate::Node::create_node_monitor
+/// [2]: NodeMonitorOptions::heartbeat_timeout
+pub struct NodeMonitor {
+    handle: Arc<NodeHandle>,
+    allocator: rcutils_allocator_t,
+    clock: Clock,
+    heartbeat_timeout: Option<Duration>,
+    nodes: Mutex<BTreeMap<String, WatchedNode>>,
+    on_status_changed: Mutex<Option<StatusCallback>>,
+    // The timer that checks the heartbeats, which is owned by the monitor.
+    timer: Mutex<Option<Arc<Timer>>>,
+}
+
+// Node names may be given without the leading slash of the root namespace.
+fn fully_qualified(name: &str) -> String {
+    if name.starts_with('/') {
+        String::from(name)
+    } else {
+        format!("/{name}")
+    }
+}
+
+impl NodeMonitor {
+    pub(crate) fn new<'a>(
+        handle: Arc<NodeHandle>,
+        allocator: rcutils_allocator_t,
+        nodes: impl IntoIterator<Item = &'a str>,
+        options: NodeMonitorOptions,
+    ) -> Result<Self, RclrsError> {
+        let clock = Clock::new(ClockType::SteadyTime)?;
+        let now = clock.now()?;
+        let present: BTreeSet<String> = node_names(&handle, &allocator)?.into_iter().collect();
+        let nodes = nodes
+            .into_iter()
+            .map(|name| {
+                let name = fully_qualified(name);
+                let status = if present.contains(&name) {
+                    NodeStatus::Alive
+                } else {
+                    NodeStatus::Gone
+                };
+                let watched = WatchedNode {
+                    status,
+                    last_heartbeat: now,
+                };
+                (name, watched)
+            })
+            .collect();
+        Ok(Self {
+            handle,
+            allocator,
+            clock,
+            heartbeat_timeout: options.heartbeat_timeout,
+            nodes: Mutex::new(nodes),
+            on_status_changed: Mutex::new(None),
+            timer: Mutex::new(None),
+        })
+    }
+
+    pub(crate) fn heartbeat_timeout(&self) -> Option<Duration> {
+        self.heartbeat_timeout
+    }
+
+    pub(crate) fn set_timer(&self, timer: Arc<Timer>) {
+        *self.timer.lock() = Some(timer);
+    }
+
+    /// Returns the status of a watched node, or `None` if the node is not watched.
+    pub fn status(&self, node: &str) -> Option<NodeStatus> {
+        self.nodes
+            .lock()
+            .get(&fully_qualified(node))
+            .map(|watched| watched.status)
+    }
+
+    /// Returns the watched nodes, with their status.
+    pub fn statuses(&self) -> Vec<(String, NodeStatus)> {
+        self.nodes
+            .lock()
+            .iter()
+            .map(|(name, watched)| (name.clone(), watched.status))
+            .collect()
+    }
+
+    /// Sets the callback that runs when the status of a watched node changes, with the fully
+    /// qualified name of the node.
+    pub fn on_status_changed(&self, callback: impl FnMut(&str, NodeStatus) + 'static) {
+        *self.on_status_changed.lock() = Some(Box::new(callback));
+    }
+
+    /// Records a heartbeat of a watched node, e.g. from a subscription callback.
+    ///
+    /// An unresponsive node that is still in the graph becomes alive again. Heartbeats of nodes
+    /// that are not watched are ignored.
+    pub fn heartbeat(&self, node: &str) {
+        let Ok(now) = self.clock.now() else {
+            return;
+        };
+        let mut changes = Vec::new();
+        if let Some(watched) = self.nodes.lock().get_mut(&fully_qualified(node)) {
+            watched.last_heartbeat = now;
+            if watched.status == NodeStatus::Unresponsive {
+                watched.status = NodeStatus::Alive;
+                changes.push((fully_qualified(node), NodeStatus::Alive));
+            }
+        }
+        self.notify(changes);
+    }
+
+    /// Marks the nodes whose heartbeats have timed out as unresponsive. This is run by the timer
+    /// of the monitor.
+    pub(crate) fn check_heartbeats(&self) -> Result<(), RclrsError> {
+        let Some(timeout) = self.heartbeat_timeout else {
+            return Ok(());
+        };
+        let now = self.clock.now()?;
+        let mut changes = Vec::new();
+        for (name, watched) in self.nodes.lock().iter_mut() {
+            if watched.status == NodeStatus::Alive
+                && now.saturating_duration_since(watched.last_heartbeat) > timeout
+            {
+                watched.status = NodeStatus::Unresponsive;
+                changes.push((name.clone(), NodeStatus::Unresponsive));
+            }
+        }
+        self.notify(changes);
+        Ok(())
+    }
+
+    // Runs the callback for the changes, after the lock on the nodes has been released, so that
+    // the callback can query the monitor.
+    fn notify(&self, changes: Vec<(String, NodeStatus)>) {
+        if changes.is_empty() {
+            return;
+        }
+        if let Some(callback) = &mut *self.on_status_changed.lock() {
+            for (name, status) in changes {
+                callback(&name, status);
+            }
+        }
+    }
+}
+
+impl GraphEventHandler for NodeMonitor {
+    fn handle_graph_event(&self) -> Result<(), RclrsError> {
+        let present: BTreeSet<String> = node_names(&self.handle, &self.allocator)?
+            .into_iter()
+            .collect();
+        let now = self.clock.now()?;
+        let mut changes = Vec::new();
+        for (name, watched) in self.nodes.lock().iter_mut() {
+            let status = match (present.contains(name), watched.status) {
+                (false, _) => NodeStatus::Gone,
+                (true, NodeStatus::Gone) => {
+                    // The heartbeat timeout starts when the node (re)appears.
+                    watched.last_heartbeat = now;
+                    NodeStatus::Alive
+                }
+                (true, status) => status,
+            };
+            if status != watched.status {
+                watched.status = status;
+                changes.push((name.clone(), status));
+            }
+        }
+        self.notify(changes);
+        Ok(())
+    }
+}
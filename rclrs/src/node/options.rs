@@ -0,0 +1,176 @@
+#[cfg(feature = "statistics_msgs")]
+use crate::NodeStatisticsOptions;
+use crate::{
+    ClientOptions, DispatchPolicy, PublisherOptions, QoSProfile, ServiceOptions,
+    SubscriptionOptions, QOS_PROFILE_DEFAULT, QOS_PROFILE_ROSOUT_DEFAULT,
+    QOS_PROFILE_SERVICES_DEFAULT,
+};
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Options for a [`Node`][1], in addition to its name and namespace.
+///
+/// New options may be added in the future, so it is best to create this with the
+/// `..Default::default()` syntax.
+///
+/// # Example
+/// ```
+/// # use rclrs::{Context, NodeOptions, RclrsError};
+/// let context = Context::new([])?;
+/// let options = NodeOptions {
+///     automatically_declare_parameters_from_overrides: true,
+///     ..Default::default()
+/// };
+/// let node = context.create_node_with_options("", "my_node", options)?;
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::Node
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NodeOptions {
+    /// Whether parameters that have not been declared can be set, both with
+    /// [`Node::set_parameter`][1] and through the parameter services, e.g. with `ros2 param set`.
+    ///
+    /// Setting an undeclared parameter declares it implicitly, with a type that changes with
+    /// its value. Unsetting it undeclares it again.
+    ///
+    /// [1]: crate::Node::set_parameter
+    pub allow_undeclared_parameters: bool,
+    /// Whether all parameter overrides, as given with `--ros-args -p` or parameter files, are
+    /// declared when the node is created.
+    ///
+    /// These parameters can be read with [`Node::get_parameter`][1], and be declared again
+    /// with a fixed type, e.g. with [`Node::declare_parameter`][2].
+    ///
+    /// [1]: crate::Node::get_parameter
+    /// [2]: crate::Node::declare_parameter
+    pub automatically_declare_parameters_from_overrides: bool,
+    /// How the executor dispatches the callbacks of the node by their priorities, see
+    /// [`DispatchPolicy`].
+    pub dispatch_policy: DispatchPolicy,
+    /// If set, the node publishes statistics about its callbacks, see [`NodeStatisticsOptions`].
+    #[cfg(feature = "statistics_msgs")]
+    pub statistics: Option<NodeStatisticsOptions>,
+    /// ROS arguments that only apply to this node, e.g.
+    /// `["--ros-args", "-r", "chatter:=talk", "-p", "rate:=10"]`.
+    ///
+    /// Like command line arguments, they are ignored unless they follow `--ros-args`. They are
+    /// applied after the global arguments of the context, so they take precedence.
+    pub arguments: Vec<String>,
+    /// Whether the global arguments of the context, e.g. remappings and parameter overrides from
+    /// the command line, apply to this node. `true` by default.
+    pub use_global_arguments: bool,
+    /// Whether the log messages of the node are published on `/rosout`. `true` by default.
+    ///
+    /// Nodes only have a `/rosout` publisher when logging has been configured for the process,
+    /// e.g. by `rclcpp` when `rclrs` is embedded with [`Context::from_raw`][1]. Disabling this
+    /// avoids creating that publisher for each node.
+    ///
+    /// [1]: crate::Context::from_raw
+    pub enable_rosout: bool,
+    /// The QoS profile of the `/rosout` publisher, [`QOS_PROFILE_ROSOUT_DEFAULT`] by default.
+    ///
+    /// This is ignored before ROS 2 Humble, where the profile cannot be configured.
+    pub rosout_qos: QoSProfile,
+    /// The defaults for the entities of the node. If `None`, the defaults of the context are
+    /// used, see [`Context::set_entity_defaults`][1].
+    ///
+    /// [1]: crate::Context::set_entity_defaults
+    pub entity_defaults: Option<EntityDefaults>,
+}
+
+impl Default for NodeOptions {
+    fn default() -> Self {
+        Self {
+            allow_undeclared_parameters: false,
+            automatically_declare_parameters_from_overrides: false,
+            dispatch_policy: DispatchPolicy::default(),
+            #[cfg(feature = "statistics_msgs")]
+            statistics: None,
+            arguments: Vec::new(),
+            use_global_arguments: true,
+            enable_rosout: true,
+            rosout_qos: QOS_PROFILE_ROSOUT_DEFAULT,
+            entity_defaults: None,
+        }
+    }
+}
+
+/// Defaults for the entities of nodes, so that a large application can apply a consistent policy
+/// without repeating it at every call site.
+///
+/// The defaults are registered for all nodes of a context with
+/// [`Context::set_entity_defaults`][1], or for a single node with
+/// [`NodeOptions::entity_defaults`]. A node copies them when it is created, and they can be read
+/// with [`Node::entity_defaults`][2].
+///
+/// The default options are used by the `create_*` functions of the node that take no options,
+/// e.g. [`Node::create_publisher`][3]. The default QoS profiles are not applied implicitly, since
+/// the `create_*` functions take a QoS profile, but can be passed from the node's defaults.
+///
+/// New fields may be added in the future, so it is best to create this with the
+/// `..Default::default()` syntax.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let context = Context::new(std::env::args())?;
+/// context.set_entity_defaults(EntityDefaults {
+///     subscription_qos: QOS_PROFILE_SENSOR_DATA,
+///     subscription_options: SubscriptionOptions {
+///         type_mismatch_policy: TypeMismatchPolicy::Error,
+///         ..Default::default()
+///     },
+///     start_type_description_service: false,
+///     ..Default::default()
+/// });
+/// let mut node = context.create_node("my_node")?;
+/// let qos = node.entity_defaults().subscription_qos;
+/// let subscription = node.create_subscription("scan", qos, |msg: sensor_msgs::msg::LaserScan| {})?;
+/// ```
+///
+/// [1]: crate::Context::set_entity_defaults
+/// [2]: crate::Node::entity_defaults
+/// [3]: crate::Node::create_publisher
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EntityDefaults {
+    /// The default QoS profile of publishers, [`QOS_PROFILE_DEFAULT`] by default.
+    pub publisher_qos: QoSProfile,
+    /// The default QoS profile of subscriptions, [`QOS_PROFILE_DEFAULT`] by default.
+    pub subscription_qos: QoSProfile,
+    /// The default QoS profile of services, [`QOS_PROFILE_SERVICES_DEFAULT`] by default.
+    pub service_qos: QoSProfile,
+    /// The default QoS profile of clients, [`QOS_PROFILE_SERVICES_DEFAULT`] by default.
+    pub client_qos: QoSProfile,
+    /// The options of publishers created without options.
+    pub publisher_options: PublisherOptions,
+    /// The options of subscriptions created without options.
+    pub subscription_options: SubscriptionOptions,
+    /// The options of services created without options.
+    pub service_options: ServiceOptions,
+    /// The options of clients created without options.
+    pub client_options: ClientOptions,
+    /// Whether nodes offer the parameter services, e.g. for `ros2 param`. `true` by default.
+    pub start_parameter_services: bool,
+    /// Whether nodes offer the `~/get_type_description` service, with which tools introspect the
+    /// types of their topics. `true` by default. This service only exists since ROS 2 Jazzy.
+    pub start_type_description_service: bool,
+}
+
+impl Default for EntityDefaults {
+    fn default() -> Self {
+        Self {
+            publisher_qos: QOS_PROFILE_DEFAULT,
+            subscription_qos: QOS_PROFILE_DEFAULT,
+            service_qos: QOS_PROFILE_SERVICES_DEFAULT,
+            client_qos: QOS_PROFILE_SERVICES_DEFAULT,
+            publisher_options: PublisherOptions::default(),
+            subscription_options: SubscriptionOptions::default(),
+            service_options: ServiceOptions::default(),
+            client_options: ClientOptions::default(),
+            start_parameter_services: true,
+            start_type_description_service: true,
+        }
+    }
+}
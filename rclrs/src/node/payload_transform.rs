@@ -0,0 +1,207 @@
+use crate::error::{RclReturnCode, ToResult};
+use crate::{rcl_bindings::*, RclrsError};
+
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// A transform of serialized messages, e.g. for compression, encryption or tagging payloads with
+/// a schema version.
+///
+/// A publisher with a transform serializes each message, passes it through
+/// [`encode()`][Self::encode] and publishes the result. A subscription with a transform takes the
+/// serialized payload, passes it through [`decode()`][Self::decode] and deserializes the result.
+/// Both sides of a topic therefore need compatible transforms, and the topic is unreadable for
+/// other subscribers, e.g. `ros2 topic echo`. Recording and replaying the topic with a
+/// [`GenericSubscription`][1] and [`GenericPublisher`][2] works, since they do not deserialize
+/// the payloads.
+///
+/// The serialization format is that of the RMW implementation, which is CDR for all Tier 1 RMW
+/// implementations. Not every RMW implementation accepts payloads that are not valid for their
+/// message type, e.g. with intra-process communication or shared memory transports.
+///
+/// # Example
+/// ```
+/// # use rclrs::PayloadTransform;
+/// // Flips all bits, which is its own inverse.
+/// struct Invert;
+///
+/// impl PayloadTransform for Invert {
+///     fn encode(&self, mut payload: Vec<u8>) -> Result<Vec<u8>, String> {
+///         payload.iter_mut().for_each(|b| *b = !*b);
+///         Ok(payload)
+///     }
+///
+///     fn decode(&self, payload: Vec<u8>) -> Result<Vec<u8>, String> {
+///         self.encode(payload)
+///     }
+/// }
+/// ```
+///
+/// [1]: crate::GenericSubscription
+/// [2]: crate::GenericPublisher
+pub trait PayloadTransform: Send + Sync {
+    /// Transforms a serialized message before it is published.
+    fn encode(&self, payload: Vec<u8>) -> Result<Vec<u8>, String>;
+
+    /// Reverses [`encode()`][Self::encode] on a taken payload, before it is deserialized.
+    ///
+    /// When this fails while the subscription is executed, the message is dropped with a warning
+    /// on the logger of the node, and spinning continues. [`Subscription::take`][1] returns the
+    /// error instead.
+    ///
+    /// [1]: crate::Subscription::take
+    fn decode(&self, payload: Vec<u8>) -> Result<Vec<u8>, String>;
+}
+
+/// A [`PayloadTransform`] for the options of publishers and subscriptions.
+///
+/// Cloning this only clones a reference to the transform, so one transform can be shared by many
+/// entities.
+///
+/// `rcl` has no way of sending and taking serialized requests and responses, so it can only be set
+/// in [`PublisherOptions`][1] and [`SubscriptionOptions`][2], but not for clients and services.
+///
+/// [1]: crate::PublisherOptions
+/// [2]: crate::SubscriptionOptions
+#[derive(Clone)]
+pub struct PayloadMiddleware {
+    transform: Arc<dyn PayloadTransform>,
+}
+
+impl PayloadMiddleware {
+    /// Wraps a transform.
+    pub fn new<P: PayloadTransform + 'static>(transform: P) -> Self {
+        Self {
+            transform: Arc::new(transform),
+        }
+    }
+
+    // Serializes an RMW-native message and encodes it.
+    pub(crate) fn encode(
+        &self,
+        rmw_message: *const core::ffi::c_void,
+        type_support: *const rosidl_message_type_support_t,
+    ) -> Result<Vec<u8>, RclrsError> {
+        let buffer = serialize(rmw_message, type_support)?;
+        self.transform
+            .encode(buffer.as_slice().to_vec())
+            .map_err(|e| transform_error("encode", e))
+    }
+
+    // Decodes a serialized message and deserializes it into an RMW-native message.
+    pub(crate) fn decode(
+        &self,
+        payload: &[u8],
+        type_support: *const rosidl_message_type_support_t,
+        rmw_message: *mut core::ffi::c_void,
+    ) -> Result<(), RclrsError> {
+        let payload = self
+            .transform
+            .decode(payload.to_vec())
+            .map_err(|e| transform_error("decode", e))?;
+        deserialize(&payload, type_support, rmw_message)
+    }
+}
+
+// Serializes an RMW-native message.
+pub(crate) fn serialize(
+    rmw_message: *const core::ffi::c_void,
+    type_support: *const rosidl_message_type_support_t,
+) -> Result<SerializedMessageBuffer, RclrsError> {
+    let mut buffer = SerializedMessageBuffer::new()?;
+    unsafe {
+        // SAFETY: The message matches the type support, and the buffer is initialized. It is
+        // resized by the RMW implementation if needed.
+        rmw_serialize(rmw_message, type_support, &mut buffer.0).ok()?;
+    }
+    Ok(buffer)
+}
+
+// Deserializes a serialized message into an RMW-native message.
+pub(crate) fn deserialize(
+    payload: &[u8],
+    type_support: *const rosidl_message_type_support_t,
+    rmw_message: *mut core::ffi::c_void,
+) -> Result<(), RclrsError> {
+    let serialized_message = borrowed_serialized_message(payload);
+    unsafe {
+        // SAFETY: The serialized message is valid for the duration of the call, and the
+        // message matches the type support.
+        rmw_deserialize(&serialized_message, type_support, rmw_message).ok()
+    }
+}
+
+fn transform_error(direction: &str, e: String) -> RclrsError {
+    RclrsError::with_message(
+        RclReturnCode::Error,
+        format!("The payload transform failed to {direction} a message: {e}"),
+    )
+}
+
+impl fmt::Debug for PayloadMiddleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PayloadMiddleware")
+            .field("transform", &Arc::as_ptr(&self.transform))
+            .finish()
+    }
+}
+
+impl PartialEq for PayloadMiddleware {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.transform, &other.transform)
+    }
+}
+
+impl Eq for PayloadMiddleware {}
+
+/// A buffer for a message in its serialized form, e.g. CDR, which is owned by `rcutils`.
+pub(crate) struct SerializedMessageBuffer(pub(crate) rcl_serialized_message_t);
+
+// SAFETY: The buffer is exclusively owned, and rcutils does not require it to be used from the
+// thread that allocated it.
+unsafe impl Send for SerializedMessageBuffer {}
+
+impl SerializedMessageBuffer {
+    pub(crate) fn new() -> Result<Self, RclrsError> {
+        unsafe {
+            // SAFETY: No preconditions for these functions. The buffer grows when a message is
+            // taken into it.
+            let allocator = rcutils_get_default_allocator();
+            let mut buffer = rcutils_get_zero_initialized_uint8_array();
+            rcutils_uint8_array_init(&mut buffer, 0, &allocator).ok()?;
+            Ok(Self(buffer))
+        }
+    }
+
+    pub(crate) fn as_slice(&self) -> &[u8] {
+        if self.0.buffer.is_null() {
+            return &[];
+        }
+        // SAFETY: The buffer is valid for its length, and is not modified while borrowed.
+        unsafe { core::slice::from_raw_parts(self.0.buffer, self.0.buffer_length) }
+    }
+}
+
+impl Drop for SerializedMessageBuffer {
+    fn drop(&mut self) {
+        // SAFETY: The buffer was initialized in new().
+        unsafe {
+            rcutils_uint8_array_fini(&mut self.0);
+        }
+    }
+}
+
+/// Wraps data in a serialized message without copying it, for passing it to functions that do
+/// not modify or resize the message.
+pub(crate) fn borrowed_serialized_message(data: &[u8]) -> rcl_serialized_message_t {
+    rcl_serialized_message_t {
+        buffer: data.as_ptr() as *mut u8,
+        buffer_length: data.len(),
+        buffer_capacity: data.len(),
+        // SAFETY: No preconditions for this function.
+        allocator: unsafe { rcutils_get_default_allocator() },
+    }
+}
@@ -1,30 +1,57 @@
-use crate::error::{RclrsError, ToResult};
+use crate::allocator::copy_rcutils_allocator;
+use crate::error::{RclReturnCode, RclrsError, ToResult};
+use crate::node::graph::MatchedCount;
+use crate::node::payload_transform::borrowed_serialized_message;
+#[cfg(feature = "testing")]
+use crate::node::payload_transform::serialize;
+use crate::parameter::ParameterName;
 use crate::qos::QoSProfile;
 use crate::rcl_bindings::*;
-use crate::Node;
+#[cfg(feature = "testing")]
+use crate::testing::LoopbackPublisher;
+use crate::tracetools;
+use crate::{
+    Gid, LoanedMessage, Node, NodeHandle, OfferedIncompatibleQoS, PayloadMiddleware, QoSEvent,
+    QoSOverridingOptions, RmwSpecificOptions, TypeHash, TypeMismatchPolicy,
+};
 
-use std::borrow::Cow;
-use std::ffi::CString;
-use std::marker::PhantomData;
-use std::sync::Arc;
+use crate::sync::{Mutex, MutexGuard};
 
-use parking_lot::{Mutex, MutexGuard};
+use alloc::borrow::Cow;
+use alloc::ffi::CString;
+use alloc::format;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
 
 use rosidl_runtime_rs::{Message, RmwMessage};
 
 pub(crate) struct PublisherHandle {
-    handle: Mutex<rcl_publisher_t>,
-    node_handle: Arc<Mutex<rcl_node_t>>,
+    pub(crate) handle: Mutex<rcl_publisher_t>,
+    pub(crate) node_handle: Arc<NodeHandle>,
+    // Kept alive until the publisher is finalized, since the RMW may hold on to it.
+    pub(crate) _rmw_specific_options: Option<RmwSpecificOptions>,
+    // The read-only parameters that show the QoS overrides, see `QoSOverridingOptions`.
+    pub(crate) _qos_parameters: Vec<ParameterName>,
+    // Publishers from `Publisher::from_raw` are finalized by their owner.
+    pub(crate) owned: bool,
 }
 
+// SAFETY: The publisher is only accessed through a mutex, and rcl does not require it to be used
+// from the thread that created it.
+unsafe impl Send for rcl_publisher_t {}
+
 impl PublisherHandle {
-    fn lock(&self) -> MutexGuard<rcl_publisher_t> {
+    pub(crate) fn lock(&self) -> MutexGuard<rcl_publisher_t> {
         self.handle.lock()
     }
 }
 
 impl Drop for PublisherHandle {
     fn drop(&mut self) {
+        if !self.owned {
+            return;
+        }
         let handle = self.handle.get_mut();
         let node_handle = &mut *self.node_handle.lock();
         // SAFETY: No preconditions for this function (besides the arguments being valid).
@@ -34,6 +61,109 @@ impl Drop for PublisherHandle {
     }
 }
 
+/// Options for a [`Publisher`], in addition to its QoS profile.
+///
+/// New options may be added in the future, so it is best to create this with the
+/// `..Default::default()` syntax.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PublisherOptions {
+    /// Options that are passed on to the RMW implementation, see [`RmwSpecificOptions`].
+    pub rmw_specific_options: Option<RmwSpecificOptions>,
+    /// Which policies of the QoS profile can be overridden with parameters, see
+    /// [`QoSOverridingOptions`].
+    pub qos_overriding_options: QoSOverridingOptions,
+    /// What to do if the topic already has another type in the ROS graph, see
+    /// [`TypeMismatchPolicy`].
+    pub type_mismatch_policy: TypeMismatchPolicy,
+    /// A transform of the serialized messages before they are published, see
+    /// [`PayloadTransform`][1].
+    ///
+    /// [1]: crate::PayloadTransform
+    pub payload_middleware: Option<PayloadMiddleware>,
+    /// Whether a warning is logged to the [logger of the node][1] when a subscription with an
+    /// incompatible QoS profile is discovered. This is the default callback of `rclcpp`.
+    ///
+    /// Set this to false when handling [`OfferedIncompatibleQoS`][2] events with
+    /// [`Node::create_publisher_event`][3], to avoid the duplicate warning. The warning is only
+    /// set up by [`Node::create_publisher`][4], and never in [static memory mode][5], since it
+    /// would take up a QoS event slot.
+    ///
+    /// [1]: crate::Node::logger_name
+    /// [2]: crate::OfferedIncompatibleQoS
+    /// [3]: crate::Node::create_publisher_event
+    /// [4]: crate::Node::create_publisher
+    /// [5]: crate::Node::enable_static_memory
+    pub use_default_callbacks: bool,
+}
+
+impl Default for PublisherOptions {
+    fn default() -> Self {
+        Self {
+            rmw_specific_options: None,
+            qos_overriding_options: QoSOverridingOptions::default(),
+            type_mismatch_policy: TypeMismatchPolicy::default(),
+            payload_middleware: None,
+            use_default_callbacks: true,
+        }
+    }
+}
+
+/// How [`Publisher::publish_with_retry`] retries a message that could not be published.
+///
+/// The delay between attempts starts at `initial_backoff`, and doubles after each attempt up to
+/// `max_backoff`.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first one. At least one attempt is made.
+    pub max_attempts: u32,
+    /// The delay before the first retry.
+    pub initial_backoff: core::time::Duration,
+    /// The maximum delay between attempts.
+    pub max_backoff: core::time::Duration,
+}
+
+#[cfg(feature = "std")]
+impl Default for RetryPolicy {
+    /// Three attempts, with delays of 10 ms and 20 ms in between.
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: core::time::Duration::from_millis(10),
+            max_backoff: core::time::Duration::from_secs(1),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl RetryPolicy {
+    // Returns the delay after the given one, which does not overflow for large delays.
+    fn next_backoff(&self, backoff: core::time::Duration) -> core::time::Duration {
+        backoff.saturating_mul(2).min(self.max_backoff)
+    }
+}
+
+// Runs the operation until it succeeds, fails with a permanent error, or the attempts of the
+// policy are exhausted.
+#[cfg(feature = "std")]
+fn retry(
+    policy: &RetryPolicy,
+    mut operation: impl FnMut() -> Result<(), RclrsError>,
+) -> Result<(), RclrsError> {
+    let mut backoff = policy.initial_backoff;
+    let mut attempt = 1;
+    loop {
+        match operation() {
+            Err(e) if e.is_transient() && attempt < policy.max_attempts => {
+                std::thread::sleep(backoff);
+                backoff = policy.next_backoff(backoff);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
 /// Struct for sending messages of type `T`.
 ///
 /// Multiple publishers can be created for the same topic, in different nodes or the same node.
@@ -43,15 +173,62 @@ impl Drop for PublisherHandle {
 ///
 /// Sending messages does not require calling [`spin`][1] on the publisher's node.
 ///
+/// A publisher is cheap to clone, since all clones share the same `rcl` publisher. It is `Send`
+/// and `Sync` for messages that are, so it can be handed to worker threads and async tasks. The
+/// shared publisher is only locked while a message is passed to `rcl`, after it has been
+/// converted to its RMW-native type.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let publisher = node.create_publisher::<std_msgs::msg::String>("topic", QOS_PROFILE_DEFAULT)?;
+/// let workers: Vec<_> = (0..4)
+///     .map(|i| {
+///         let publisher = publisher.clone();
+///         std::thread::spawn(move || {
+///             let data = format!("Hello from worker {i}");
+///             publisher.publish(std_msgs::msg::String { data })
+///         })
+///     })
+///     .collect();
+/// ```
+///
 /// [1]: crate::spin
 pub struct Publisher<T>
 where
     T: Message,
 {
     pub(crate) handle: Arc<PublisherHandle>,
+    gid: Gid,
+    payload_middleware: Option<PayloadMiddleware>,
+    #[cfg(feature = "testing")]
+    loopback: Option<LoopbackPublisher>,
+    // The number of matched subscriptions, which is cached if the node has a graph cache.
+    subscription_count: Arc<MatchedCount>,
+    // The handler that logs incompatible QoS profiles, see
+    // `PublisherOptions::use_default_callbacks`. It is shared by the clones of the publisher.
+    pub(crate) incompatible_qos_event: Option<Arc<QoSEvent<OfferedIncompatibleQoS>>>,
     message: PhantomData<T>,
 }
 
+impl<T> Clone for Publisher<T>
+where
+    T: Message,
+{
+    fn clone(&self) -> Self {
+        Self {
+            handle: Arc::clone(&self.handle),
+            gid: self.gid,
+            payload_middleware: self.payload_middleware.clone(),
+            #[cfg(feature = "testing")]
+            loopback: self.loopback.clone(),
+            subscription_count: Arc::clone(&self.subscription_count),
+            incompatible_qos_event: self.incompatible_qos_event.clone(),
+            message: PhantomData,
+        }
+    }
+}
+
 impl<T> Publisher<T>
 where
     T: Message,
@@ -66,16 +243,62 @@ where
     where
         T: Message,
     {
-        // SAFETY: Getting a zero-initialized value is always safe.
-        let mut publisher_handle = unsafe { rcl_get_zero_initialized_publisher() };
+        let options = node.entity_defaults.publisher_options.clone();
+        Self::new_with_options(node, topic, qos, options)
+    }
+
+    /// Creates a new `Publisher` with additional options.
+    ///
+    /// # Panics
+    /// When the topic contains interior null bytes.
+    pub fn new_with_options(
+        node: &Node,
+        topic: &str,
+        mut qos: QoSProfile,
+        options: PublisherOptions,
+    ) -> Result<Self, RclrsError>
+    where
+        T: Message,
+    {
+        // The handle is initialized in place, because rcl uses its address to identify the
+        // publisher, e.g. in tracepoints.
+        // This is declared before the node handle guard, since dropping it in the error case
+        // locks the node handle.
+        let topic = node.expand_topic_name(topic)?;
+        let qos_parameters =
+            options
+                .qos_overriding_options
+                .apply(node, &topic, "publisher", &mut qos)?;
+        node.check_topic_type(
+            &topic,
+            <T as Message>::RmwMsg::TYPE_NAME,
+            options.type_mismatch_policy,
+            "publisher",
+        )?;
+        let handle = Arc::new(PublisherHandle {
+            // SAFETY: Getting a zero-initialized value is always safe.
+            handle: Mutex::new(unsafe { rcl_get_zero_initialized_publisher() }),
+            node_handle: node.handle.clone(),
+            _rmw_specific_options: options.rmw_specific_options.clone(),
+            _qos_parameters: qos_parameters,
+            owned: true,
+        });
         let type_support =
             <T as Message>::RmwMsg::get_type_support() as *const rosidl_message_type_support_t;
+        #[cfg(feature = "testing")]
+        let loopback = LoopbackPublisher::new(node, &topic);
         let topic_c_string = CString::new(topic).unwrap();
         let node_handle = &mut *node.handle.lock();
 
         // SAFETY: No preconditions for this function.
         let mut publisher_options = unsafe { rcl_publisher_get_default_options() };
         publisher_options.qos = qos.into();
+        publisher_options.allocator = copy_rcutils_allocator(&node.allocator);
+        if let Some(rmw_specific_options) = &options.rmw_specific_options {
+            publisher_options
+                .rmw_publisher_options
+                .rmw_specific_publisher_payload = rmw_specific_options.as_ptr();
+        }
         unsafe {
             // SAFETY: The publisher handle is zero-initialized as expected by this function.
             // The node handle is kept alive because it is co-owned by the subscription.
@@ -83,7 +306,7 @@ where
             // afterwards.
             // TODO: type support?
             rcl_publisher_init(
-                &mut publisher_handle,
+                &mut *handle.lock(),
                 node_handle,
                 type_support,
                 topic_c_string.as_ptr(),
@@ -92,13 +315,64 @@ where
             .ok()?;
         }
 
+        let mut publisher = Self::new_from_handle(node, handle)?;
+        publisher.payload_middleware = options.payload_middleware;
+        #[cfg(feature = "testing")]
+        {
+            publisher.loopback = loopback;
+        }
+        Ok(publisher)
+    }
+
+    /// Creates a publisher from an `rcl` publisher that was initialized outside of `rclrs`, e.g.
+    /// by `rclcpp` on a node shared with [`Node::from_raw`].
+    ///
+    /// # Safety
+    /// The publisher must be initialized on the given node, for the RMW-native message type of
+    /// `T`. It is not finalized by `rclrs`: Its owner remains responsible for that, and must only
+    /// do it after the returned `Publisher` has been dropped.
+    ///
+    /// `rclrs` synchronizes its own accesses to the publisher, but not those of its owner. The
+    /// owner must not publish with it while `rclrs` does.
+    ///
+    /// The `rcl_publisher_t` struct is copied, so the pointer does not need to stay valid, but
+    /// the state that it refers to does.
+    pub unsafe fn from_raw(
+        node: &Node,
+        rcl_publisher: *const rcl_publisher_t,
+    ) -> Result<Self, RclrsError> {
         let handle = Arc::new(PublisherHandle {
-            handle: Mutex::new(publisher_handle),
+            handle: Mutex::new(core::ptr::read(rcl_publisher)),
             node_handle: node.handle.clone(),
+            _rmw_specific_options: None,
+            _qos_parameters: Vec::new(),
+            owned: false,
         });
+        Self::new_from_handle(node, handle)
+    }
+
+    fn new_from_handle(node: &Node, handle: Arc<PublisherHandle>) -> Result<Self, RclrsError> {
+        let gid = {
+            let handle = &*handle.lock();
+            // SAFETY: Getting a zero-initialized value is always safe.
+            let mut rmw_gid = unsafe { core::mem::zeroed::<rmw_gid_t>() };
+            unsafe {
+                // SAFETY: The publisher handle is valid, since it has been initialized.
+                // The returned rmw handle is owned by the publisher and only used in this block.
+                let rmw_handle = rcl_publisher_get_rmw_handle(handle);
+                rmw_get_gid_for_publisher(rmw_handle, &mut rmw_gid).ok()?;
+            }
+            Gid::from(&rmw_gid)
+        };
 
         Ok(Self {
             handle,
+            gid,
+            payload_middleware: None,
+            #[cfg(feature = "testing")]
+            loopback: None,
+            subscription_count: Arc::new(MatchedCount::new(node)),
+            incompatible_qos_event: None,
             message: PhantomData,
         })
     }
@@ -108,32 +382,306 @@ where
     /// The [`MessageCow`] trait is implemented by any
     /// [`Message`] as well as any reference to a `Message`.
     ///
-    /// The reason for allowing owned messages is that publishing owned messages can be more
-    /// efficient in the case of idiomatic messages[^note].
+    /// Idiomatic messages[^note] are converted to RMW-native messages for publishing. Sequences of
+    /// primitive types, such as the `data` of a `sensor_msgs/Image`, are not copied in this
+    /// conversion, see [`Message::with_rmw_message`].
     ///
     /// [^note]: See the [`Message`] trait for an explanation of "idiomatic".
     ///
-    /// Hence, when a message will not be needed anymore after publishing, pass it by value.
-    /// When a message will be needed again after publishing, pass it by reference, instead of cloning and passing by value.
+    /// When a message will be needed again after publishing, pass it by reference, instead of
+    /// cloning and passing by value.
+    ///
+    /// With a [`PayloadMiddleware`] in the options, the message is serialized and transformed
+    /// before the publisher is locked.
     ///
     /// Calling `publish()` is a potentially blocking call, see [this issue][1] for details.
     ///
     /// [1]: https://github.com/ros2/ros2/issues/255
     pub fn publish<'a, M: MessageCow<'a, T>>(&self, message: M) -> Result<(), RclrsError> {
-        let rmw_message = T::into_rmw_message(message.into_cow());
+        message
+            .into_cow()
+            .with_rmw_message(|rmw_message| match &self.payload_middleware {
+                Some(payload_middleware) => {
+                    let payload = Self::encode(payload_middleware, rmw_message)?;
+                    self.publish_payload_with_handle(&mut self.handle.lock(), &payload)
+                }
+                None => self.publish_with_handle(&mut self.handle.lock(), rmw_message),
+            })
+    }
+
+    /// Publishes several messages in order.
+    ///
+    /// This is equivalent to calling [`Publisher::publish`] for each message, except that the
+    /// publisher is locked only once for the whole batch. This matters when many small messages
+    /// are published in a burst, e.g. when replaying chunks of a point cloud.
+    ///
+    /// Publishing stops at the first error. The messages before it have been published, the
+    /// messages after it have not.
+    ///
+    /// The publisher stays locked while the iterator produces the messages, so the iterator
+    /// should not block.
+    pub fn publish_batch<'a, M, I>(&self, messages: I) -> Result<(), RclrsError>
+    where
+        M: MessageCow<'a, T>,
+        I: IntoIterator<Item = M>,
+    {
         let handle = &mut *self.handle.lock();
+        for message in messages {
+            message
+                .into_cow()
+                .with_rmw_message(|rmw_message| match &self.payload_middleware {
+                    Some(payload_middleware) => {
+                        let payload = Self::encode(payload_middleware, rmw_message)?;
+                        self.publish_payload_with_handle(handle, &payload)
+                    }
+                    None => self.publish_with_handle(handle, rmw_message),
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Publishes a message, and retries with a backoff if publishing fails with a
+    /// [transient][1] error, e.g. on a flaky transport.
+    ///
+    /// The message is converted, and encoded by a [`PayloadMiddleware`], only once. Permanent
+    /// errors, e.g. [`PublisherInvalid`][2], are returned immediately, and the last error is
+    /// returned when the attempts of the policy are exhausted.
+    ///
+    /// This blocks the calling thread while it waits between attempts, so it should be called
+    /// from a worker thread rather than from a callback, unless the policy is short.
+    ///
+    /// # Example
+    /// ```ignore
+    /// # use rclrs::*;
+    /// # use std::time::Duration;
+    /// let policy = RetryPolicy {
+    ///     max_attempts: 5,
+    ///     initial_backoff: Duration::from_millis(50),
+    ///     ..Default::default()
+    /// };
+    /// publisher.publish_with_retry(&msg, &policy)?;
+    /// ```
+    ///
+    /// [1]: RclrsError::is_transient
+    /// [2]: crate::RclReturnCode::PublisherInvalid
+    #[cfg(feature = "std")]
+    pub fn publish_with_retry<'a, M: MessageCow<'a, T>>(
+        &self,
+        message: M,
+        policy: &RetryPolicy,
+    ) -> Result<(), RclrsError> {
+        message
+            .into_cow()
+            .with_rmw_message(|rmw_message| match &self.payload_middleware {
+                Some(payload_middleware) => {
+                    let payload = Self::encode(payload_middleware, rmw_message)?;
+                    retry(policy, || {
+                        self.publish_payload_with_handle(&mut self.handle.lock(), &payload)
+                    })
+                }
+                None => retry(policy, || {
+                    self.publish_with_handle(&mut self.handle.lock(), rmw_message)
+                }),
+            })
+    }
+
+    fn encode(
+        payload_middleware: &PayloadMiddleware,
+        rmw_message: &<T as Message>::RmwMsg,
+    ) -> Result<Vec<u8>, RclrsError> {
+        let type_support =
+            <T as Message>::RmwMsg::get_type_support() as *const rosidl_message_type_support_t;
+        payload_middleware.encode(
+            rmw_message as *const <T as Message>::RmwMsg as *const _,
+            type_support,
+        )
+    }
+
+    fn publish_payload_with_handle(
+        &self,
+        handle: &mut rcl_publisher_t,
+        payload: &[u8],
+    ) -> Result<(), RclrsError> {
+        #[cfg(feature = "fault_injection")]
+        Self::check_fault(handle)?;
+        #[cfg(feature = "testing")]
+        if let Some(loopback) = &self.loopback {
+            loopback.publish(payload, self.gid);
+            return Ok(());
+        }
+        let serialized_message = borrowed_serialized_message(payload);
+        unsafe {
+            // SAFETY: The serialized message is valid for the duration of the call. The
+            // allocation is explicitly allowed to be NULL.
+            rcl_publish_serialized_message(handle, &serialized_message, core::ptr::null_mut())
+        }
+        .ok()
+    }
+
+    fn publish_with_handle(
+        &self,
+        handle: &mut rcl_publisher_t,
+        rmw_message: &<T as Message>::RmwMsg,
+    ) -> Result<(), RclrsError> {
+        #[cfg(feature = "testing")]
+        if self.loopback.is_some() {
+            let type_support =
+                <T as Message>::RmwMsg::get_type_support() as *const rosidl_message_type_support_t;
+            let payload = serialize(
+                rmw_message as *const <T as Message>::RmwMsg as *const _,
+                type_support,
+            )?;
+            return self.publish_payload_with_handle(handle, payload.as_slice());
+        }
+        #[cfg(feature = "fault_injection")]
+        Self::check_fault(handle)?;
+        let rmw_message_ptr = rmw_message as *const <T as Message>::RmwMsg;
+        tracetools::publish(handle as *const _ as *const _, rmw_message_ptr as *const _);
         let ret = unsafe {
             // SAFETY: The message type is guaranteed to match the publisher type by the type system.
             // The message does not need to be valid beyond the duration of this function call.
             // The third argument is explictly allowed to be NULL.
-            rcl_publish(
-                handle,
-                rmw_message.as_ref() as *const <T as Message>::RmwMsg as *mut _,
-                std::ptr::null_mut(),
-            )
+            rcl_publish(handle, rmw_message_ptr as *mut _, core::ptr::null_mut())
         };
         ret.ok()
     }
+
+    #[cfg(feature = "fault_injection")]
+    fn check_fault(handle: &rcl_publisher_t) -> Result<(), RclrsError> {
+        crate::fault_injection::check(crate::fault_injection::FaultKind::Publish, || unsafe {
+            // SAFETY: The publisher is locked, and the name is copied while it is.
+            crate::fault_injection::name_from_ptr(rcl_publisher_get_topic_name(handle))
+        })
+    }
+
+    /// Manually asserts that this publisher is alive.
+    ///
+    /// This is only needed with the [`ManualByTopic`][1] liveliness policy, where a publisher that
+    /// neither publishes nor asserts its liveliness within the liveliness lease duration is
+    /// considered not alive, see [`LivelinessLost`][2] and [`LivelinessChanged`][3].
+    ///
+    /// [1]: crate::QoSLivelinessPolicy::ManualByTopic
+    /// [2]: crate::LivelinessLost
+    /// [3]: crate::LivelinessChanged
+    pub fn assert_liveliness(&self) -> Result<(), RclrsError> {
+        // SAFETY: No preconditions for this function (besides passing in a valid handle).
+        unsafe { rcl_publisher_assert_liveliness(&*self.handle.lock()) }.ok()
+    }
+
+    /// Returns the globally unique identifier of this publisher.
+    ///
+    /// Subscriptions can get the GID of the publisher of a received message from its
+    /// [`MessageInfo`][1].
+    ///
+    /// [1]: crate::MessageInfo
+    pub fn gid(&self) -> &Gid {
+        &self.gid
+    }
+
+    /// Returns the [REP 2011][1] hash of the message type, or `None` before ROS 2 Iron.
+    ///
+    /// [1]: https://ros.org/reps/rep-2011.html
+    pub fn type_hash(&self) -> Option<TypeHash> {
+        let type_support =
+            <T as Message>::RmwMsg::get_type_support() as *const rosidl_message_type_support_t;
+        // SAFETY: The type support is valid, since it is static data of the message package.
+        unsafe { TypeHash::from_type_support(type_support) }
+    }
+
+    /// Returns the QoS profile that the RMW implementation actually uses for this publisher.
+    ///
+    /// Unlike the profile that the publisher was created with, this has the `SystemDefault`
+    /// policies resolved, so it can be used to verify what the middleware granted. Not every RMW
+    /// implementation resolves every policy, though.
+    pub fn actual_qos(&self) -> Result<QoSProfile, RclrsError> {
+        // SAFETY: No preconditions for this function (besides passing in a valid handle).
+        // The returned profile is owned by the publisher, and only read while the handle is locked.
+        let handle = &*self.handle.lock();
+        unsafe { rcl_publisher_get_actual_qos(handle).as_ref() }
+            .map(QoSProfile::from)
+            .ok_or(RclrsError {
+                code: RclReturnCode::PublisherInvalid,
+                msg: None,
+            })
+    }
+
+    /// Returns the number of subscriptions that are currently matched with this publisher.
+    ///
+    /// Subscriptions are matched asynchronously after discovery, so this can be used to wait
+    /// until a message will actually be received by someone. The count is cached until the ROS
+    /// graph changes if the node has a graph cache, see [`Node::enable_graph_cache`][1].
+    ///
+    /// [1]: crate::Node::enable_graph_cache
+    pub fn get_subscription_count(&self) -> Result<usize, RclrsError> {
+        self.subscription_count.get_or_query(|| {
+            let mut subscription_count = 0;
+            // SAFETY: No preconditions for this function (besides passing in a valid handle).
+            unsafe {
+                rcl_publisher_get_subscription_count(&*self.handle.lock(), &mut subscription_count)
+                    .ok()?;
+            }
+            Ok(subscription_count)
+        })
+    }
+
+    /// Returns a pointer to the underlying `rcl` publisher, for calling functions that are not
+    /// wrapped by `rclrs`.
+    ///
+    /// # Safety
+    /// See [`Node::raw_handle`]. The publisher must not be finalized.
+    pub unsafe fn raw_handle(&self) -> *mut rcl_publisher_t {
+        &mut *self.handle.lock()
+    }
+}
+
+impl<T> Publisher<T>
+where
+    T: RmwMessage + Message,
+{
+    /// Borrows a message from the middleware, for filling it in place and then publishing it
+    /// with [`LoanedMessage::publish()`].
+    ///
+    /// This avoids copying the message when the middleware supports loans, e.g. with shared
+    /// memory. The message is default-initialized.
+    ///
+    /// Loans are only available for RMW-native message types, and only if
+    /// [`Publisher::can_loan_messages()`] returns `true`. Like in `rclcpp`, they are limited to
+    /// messages of fixed size, i.e. without strings or sequences, whose contents would be
+    /// allocated outside of the loan, see [`RmwMessage::HAS_FIXED_SIZE`]. For other messages, an
+    /// [`Unsupported`][1] error is returned.
+    ///
+    /// [1]: crate::RclReturnCode::Unsupported
+    pub fn borrow_loaned_message(&self) -> Result<LoanedMessage<'_, T>, RclrsError> {
+        if !T::HAS_FIXED_SIZE {
+            return Err(RclrsError::with_message(
+                RclReturnCode::Unsupported,
+                format!(
+                    "Messages of type {} cannot be loaned, since they contain strings or sequences",
+                    T::TYPE_NAME
+                ),
+            ));
+        }
+        if self.payload_middleware.is_some() {
+            return Err(RclrsError::with_message(
+                RclReturnCode::Unsupported,
+                "Loaned messages cannot be published through a payload transform",
+            ));
+        }
+        LoanedMessage::new(self)
+    }
+
+    /// Returns whether the middleware can loan messages of this type, see
+    /// [`Publisher::borrow_loaned_message()`].
+    ///
+    /// This is `false` for publishers with a [`PayloadMiddleware`], since loaned messages would
+    /// bypass it.
+    pub fn can_loan_messages(&self) -> bool {
+        if self.payload_middleware.is_some() {
+            return false;
+        }
+        // SAFETY: No preconditions for this function (besides passing in a valid handle).
+        unsafe { rcl_publisher_can_loan_messages(&*self.handle.lock()) }
+    }
 }
 
 /// Convenience trait for [`Publisher::publish`].
@@ -153,3 +701,87 @@ impl<'a, T: Message> MessageCow<'a, T> for &'a T {
         Cow::Borrowed(self)
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use core::time::Duration;
+
+    // Runs `retry` with an operation that returns the given results in order, and returns the
+    // result of `retry` and the number of attempts.
+    fn run_retry(
+        policy: &RetryPolicy,
+        results: Vec<Result<(), RclReturnCode>>,
+    ) -> (Result<(), RclrsError>, usize) {
+        let mut results = results.into_iter();
+        let mut attempts = 0;
+        let result = retry(policy, || {
+            attempts += 1;
+            results
+                .next()
+                .expect("Too many attempts")
+                .map_err(|code| RclrsError { code, msg: None })
+        });
+        (result, attempts)
+    }
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::ZERO,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_retry_until_success() {
+        let results = vec![
+            Err(RclReturnCode::Error),
+            Err(RclReturnCode::Timeout),
+            Ok(()),
+        ];
+        assert_eq!(run_retry(&policy(3), results), (Ok(()), 3));
+        assert_eq!(run_retry(&policy(3), vec![Ok(())]), (Ok(()), 1));
+    }
+
+    #[test]
+    fn test_retry_returns_last_error_when_exhausted() {
+        let results = vec![Err(RclReturnCode::Error), Err(RclReturnCode::BadAlloc)];
+        let (result, attempts) = run_retry(&policy(2), results);
+        assert_eq!(result.unwrap_err().code, RclReturnCode::BadAlloc);
+        assert_eq!(attempts, 2);
+        // At least one attempt is made.
+        let (result, attempts) = run_retry(&policy(0), vec![Err(RclReturnCode::Error)]);
+        assert_eq!(result.unwrap_err().code, RclReturnCode::Error);
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn test_retry_stops_at_permanent_error() {
+        let results = vec![
+            Err(RclReturnCode::Error),
+            Err(RclReturnCode::PublisherInvalid),
+        ];
+        let (result, attempts) = run_retry(&policy(5), results);
+        assert_eq!(result.unwrap_err().code, RclReturnCode::PublisherInvalid);
+        assert_eq!(attempts, 2);
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_maximum() {
+        let policy = RetryPolicy::default();
+        let backoffs: Vec<_> = core::iter::successors(Some(policy.initial_backoff), |backoff| {
+            Some(policy.next_backoff(*backoff))
+        })
+        .take(9)
+        .map(|backoff| backoff.as_millis())
+        .collect();
+        assert_eq!(backoffs, [10, 20, 40, 80, 160, 320, 640, 1000, 1000]);
+        // Large delays do not overflow.
+        let policy = RetryPolicy {
+            max_backoff: Duration::MAX,
+            ..Default::default()
+        };
+        assert_eq!(policy.next_backoff(Duration::MAX), Duration::MAX);
+    }
+}
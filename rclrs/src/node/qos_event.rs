@@ -0,0 +1,474 @@
+use crate::error::{to_rcl_result, EventErrorCode, RclReturnCode, ToResult};
+use crate::qos::QoSPolicyKind;
+use crate::rcl_bindings::*;
+use crate::sync::{Mutex, MutexGuard};
+use crate::{Publisher, PublisherHandle, RclrsError, Subscription, SubscriptionHandle};
+
+use alloc::borrow::Borrow;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::fmt;
+use core::mem::MaybeUninit;
+
+use rosidl_runtime_rs::Message;
+
+/// Internal struct used by QoS events.
+pub struct QoSEventHandle {
+    handle: Mutex<rcl_event_t>,
+    // The event must be finalized before the publisher or subscription it belongs to.
+    _parent: EventParent,
+}
+
+// The handles are only stored to keep them alive, never read.
+#[allow(dead_code)]
+enum EventParent {
+    Publisher(Arc<PublisherHandle>),
+    Subscription(Arc<SubscriptionHandle>),
+}
+
+impl QoSEventHandle {
+    pub(crate) fn lock(&self) -> MutexGuard<rcl_event_t> {
+        self.handle.lock()
+    }
+}
+
+impl Drop for QoSEventHandle {
+    fn drop(&mut self) {
+        let handle = self.handle.get_mut();
+        // SAFETY: No preconditions for this function (besides passing in a valid event).
+        let rc = unsafe { rcl_event_fini(handle) };
+        if let Err(e) = to_rcl_result(rc) {
+            panic!("Unable to release QoSEvent. {:?}", e)
+        }
+    }
+}
+
+/// Trait to be implemented by concrete [`QoSEvent`]s.
+pub trait QoSEventBase {
+    /// Internal function to get a reference to the `rcl` handle.
+    fn handle(&self) -> &QoSEventHandle;
+    /// Tries to take the event status and run the callback with it.
+    fn execute(&self) -> Result<(), RclrsError>;
+}
+
+/// The status that is reported by a QoS event.
+pub trait QoSEventStatus: Sized + 'static {
+    /// The corresponding `rmw` status struct.
+    #[doc(hidden)]
+    type RmwStatus;
+    /// Converts the `rmw` status.
+    #[doc(hidden)]
+    fn from_rmw(status: &Self::RmwStatus) -> Self;
+}
+
+/// A [`QoSEventStatus`] that is reported for publishers.
+pub trait PublisherEventStatus: QoSEventStatus {
+    /// The `rcl` event type.
+    #[doc(hidden)]
+    const EVENT_TYPE: rcl_publisher_event_type_t;
+}
+
+/// A [`QoSEventStatus`] that is reported for subscriptions.
+pub trait SubscriptionEventStatus: QoSEventStatus {
+    /// The `rcl` event type.
+    #[doc(hidden)]
+    const EVENT_TYPE: rcl_subscription_event_type_t;
+}
+
+/// A handler for QoS events of a publisher or subscription, with status type `S`.
+///
+/// QoS events report conditions such as incompatible QoS profiles between a publisher and a
+/// subscription, missed deadlines and changes in liveliness. The status type determines which
+/// event is handled:
+///
+/// | Publisher | Subscription |
+/// | -- | -- |
+/// | [`OfferedIncompatibleQoS`] | [`RequestedIncompatibleQoS`] |
+/// | [`OfferedDeadlineMissed`] | [`RequestedDeadlineMissed`] |
+/// | [`LivelinessLost`] | [`LivelinessChanged`] |
+/// | | [`MessageLost`] (not in Foxy) |
+///
+/// For example, a subscription with a [`deadline`][4] QoS policy and a handler for
+/// [`RequestedDeadlineMissed`] acts as a watchdog for its topic.
+///
+/// Like messages, events are delivered by calling [`spin_once`][1] or [`spin`][2] on the node that
+/// created the event handler.
+///
+/// Not every RMW implementation supports every event. Creating a handler for an unsupported event
+/// returns an [`Unsupported`][3] error.
+///
+/// [1]: crate::spin_once
+/// [2]: crate::spin
+/// [3]: crate::RclReturnCode::Unsupported
+/// [4]: crate::QoSProfile::deadline
+pub struct QoSEvent<S>
+where
+    S: QoSEventStatus,
+{
+    pub(crate) handle: Arc<QoSEventHandle>,
+    /// The callback function that runs when the event occurred.
+    pub callback: Mutex<Box<dyn FnMut(S) + 'static>>,
+}
+
+impl<S> QoSEvent<S>
+where
+    S: QoSEventStatus,
+{
+    /// Creates a new event handler for a publisher.
+    ///
+    /// Usually, [`Node::create_publisher_event`][1] should be used instead, since only event
+    /// handlers known to the node are executed by [`spin`][2].
+    ///
+    /// [1]: crate::Node::create_publisher_event
+    /// [2]: crate::spin
+    pub fn new_for_publisher<T, F>(
+        publisher: &Publisher<T>,
+        callback: F,
+    ) -> Result<Self, RclrsError>
+    where
+        S: PublisherEventStatus,
+        T: Message,
+        F: FnMut(S) + 'static,
+    {
+        let handle = Arc::new(QoSEventHandle {
+            // SAFETY: Getting a zero-initialized value is always safe.
+            handle: Mutex::new(unsafe { rcl_get_zero_initialized_event() }),
+            _parent: EventParent::Publisher(Arc::clone(&publisher.handle)),
+        });
+        unsafe {
+            // SAFETY: The event handle is zero-initialized as expected by this function.
+            // The publisher handle is kept alive because it is co-owned by the event.
+            rcl_publisher_event_init(
+                &mut *handle.lock(),
+                &*publisher.handle.lock(),
+                S::EVENT_TYPE,
+            )
+            .ok()?;
+        }
+        Ok(Self {
+            handle,
+            callback: Mutex::new(Box::new(callback)),
+        })
+    }
+
+    /// Creates a new event handler for a subscription.
+    ///
+    /// Usually, [`Node::create_subscription_event`][1] should be used instead, since only event
+    /// handlers known to the node are executed by [`spin`][2].
+    ///
+    /// [1]: crate::Node::create_subscription_event
+    /// [2]: crate::spin
+    pub fn new_for_subscription<T, F>(
+        subscription: &Subscription<T>,
+        callback: F,
+    ) -> Result<Self, RclrsError>
+    where
+        S: SubscriptionEventStatus,
+        T: Message,
+        F: FnMut(S) + 'static,
+    {
+        let handle = Arc::new(QoSEventHandle {
+            // SAFETY: Getting a zero-initialized value is always safe.
+            handle: Mutex::new(unsafe { rcl_get_zero_initialized_event() }),
+            _parent: EventParent::Subscription(Arc::clone(&subscription.handle)),
+        });
+        unsafe {
+            // SAFETY: The event handle is zero-initialized as expected by this function.
+            // The subscription handle is kept alive because it is co-owned by the event.
+            rcl_subscription_event_init(
+                &mut *handle.lock(),
+                &*subscription.handle.lock(),
+                S::EVENT_TYPE,
+            )
+            .ok()?;
+        }
+        Ok(Self {
+            handle,
+            callback: Mutex::new(Box::new(callback)),
+        })
+    }
+
+    /// Fetches the current status of the event.
+    ///
+    /// When the event has not occurred since the last call, this will return an
+    /// [`EventTakeFailed`][1] wrapped in an [`RclrsError`].
+    ///
+    /// [1]: crate::EventErrorCode
+    pub fn take(&self) -> Result<S, RclrsError> {
+        let mut rmw_status = MaybeUninit::<S::RmwStatus>::uninit();
+        unsafe {
+            // SAFETY: The event handle is valid, and the status pointer points to memory of the
+            // type that corresponds to the event type.
+            rcl_take_event(&*self.handle.lock(), rmw_status.as_mut_ptr() as *mut _).ok()?;
+        }
+        // SAFETY: rcl_take_event has written the status, since it returned successfully.
+        let rmw_status = unsafe { rmw_status.assume_init() };
+        Ok(S::from_rmw(&rmw_status))
+    }
+}
+
+impl<S> QoSEventBase for QoSEvent<S>
+where
+    S: QoSEventStatus,
+{
+    fn handle(&self) -> &QoSEventHandle {
+        self.handle.borrow()
+    }
+
+    fn execute(&self) -> Result<(), RclrsError> {
+        let status = match self.take() {
+            Ok(status) => status,
+            Err(RclrsError {
+                code: RclReturnCode::EventError(EventErrorCode::EventTakeFailed),
+                ..
+            }) => {
+                // Spurious wakeup – this may happen even when a waitset indicated that this
+                // event was ready, so it shouldn't be an error.
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        (*self.callback.lock())(status);
+        Ok(())
+    }
+}
+
+/// A subscription requested a QoS profile that is incompatible with a discovered publisher.
+///
+/// No messages will be received from that publisher.
+///
+/// The `Display` implementation produces the same warning that `rclcpp` logs by default, e.g.
+/// ```text
+/// New publisher discovered, offering incompatible QoS. No messages will be received from it. Last incompatible policy: RELIABILITY_QOS_POLICY
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RequestedIncompatibleQoS {
+    /// The total number of incompatible publishers discovered so far.
+    pub total_count: i32,
+    /// The change of `total_count` since the last time the status was taken.
+    pub total_count_change: i32,
+    /// The policy that was found to be incompatible most recently.
+    pub last_policy_kind: QoSPolicyKind,
+}
+
+impl QoSEventStatus for RequestedIncompatibleQoS {
+    type RmwStatus = rmw_requested_qos_incompatible_event_status_t;
+
+    fn from_rmw(status: &Self::RmwStatus) -> Self {
+        Self {
+            total_count: status.total_count,
+            total_count_change: status.total_count_change,
+            last_policy_kind: status.last_policy_kind.into(),
+        }
+    }
+}
+
+impl SubscriptionEventStatus for RequestedIncompatibleQoS {
+    const EVENT_TYPE: rcl_subscription_event_type_t =
+        rcl_subscription_event_type_t::RCL_SUBSCRIPTION_REQUESTED_INCOMPATIBLE_QOS;
+}
+
+impl fmt::Display for RequestedIncompatibleQoS {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "New publisher discovered, offering incompatible QoS. No messages will be received \
+             from it. Last incompatible policy: {}",
+            self.last_policy_kind
+        )
+    }
+}
+
+/// A publisher offered a QoS profile that is incompatible with a discovered subscription.
+///
+/// No messages will be sent to that subscription.
+///
+/// The `Display` implementation produces the same warning that `rclcpp` logs by default, e.g.
+/// ```text
+/// New subscription discovered, requesting incompatible QoS. No messages will be sent to it. Last incompatible policy: DURABILITY_QOS_POLICY
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct OfferedIncompatibleQoS {
+    /// The total number of incompatible subscriptions discovered so far.
+    pub total_count: i32,
+    /// The change of `total_count` since the last time the status was taken.
+    pub total_count_change: i32,
+    /// The policy that was found to be incompatible most recently.
+    pub last_policy_kind: QoSPolicyKind,
+}
+
+impl QoSEventStatus for OfferedIncompatibleQoS {
+    type RmwStatus = rmw_offered_qos_incompatible_event_status_t;
+
+    fn from_rmw(status: &Self::RmwStatus) -> Self {
+        Self {
+            total_count: status.total_count,
+            total_count_change: status.total_count_change,
+            last_policy_kind: status.last_policy_kind.into(),
+        }
+    }
+}
+
+impl PublisherEventStatus for OfferedIncompatibleQoS {
+    const EVENT_TYPE: rcl_publisher_event_type_t =
+        rcl_publisher_event_type_t::RCL_PUBLISHER_OFFERED_INCOMPATIBLE_QOS;
+}
+
+impl fmt::Display for OfferedIncompatibleQoS {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "New subscription discovered, requesting incompatible QoS. No messages will be sent \
+             to it. Last incompatible policy: {}",
+            self.last_policy_kind
+        )
+    }
+}
+
+/// A publisher did not publish a message within the period of its deadline QoS policy.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct OfferedDeadlineMissed {
+    /// The total number of missed deadlines so far.
+    pub total_count: i32,
+    /// The change of `total_count` since the last time the status was taken.
+    pub total_count_change: i32,
+}
+
+impl QoSEventStatus for OfferedDeadlineMissed {
+    type RmwStatus = rmw_offered_deadline_missed_status_t;
+
+    fn from_rmw(status: &Self::RmwStatus) -> Self {
+        Self {
+            total_count: status.total_count,
+            total_count_change: status.total_count_change,
+        }
+    }
+}
+
+impl PublisherEventStatus for OfferedDeadlineMissed {
+    const EVENT_TYPE: rcl_publisher_event_type_t =
+        rcl_publisher_event_type_t::RCL_PUBLISHER_OFFERED_DEADLINE_MISSED;
+}
+
+/// A subscription did not receive a message within the period of its deadline QoS policy.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RequestedDeadlineMissed {
+    /// The total number of missed deadlines so far.
+    pub total_count: i32,
+    /// The change of `total_count` since the last time the status was taken.
+    pub total_count_change: i32,
+}
+
+impl QoSEventStatus for RequestedDeadlineMissed {
+    type RmwStatus = rmw_requested_deadline_missed_status_t;
+
+    fn from_rmw(status: &Self::RmwStatus) -> Self {
+        Self {
+            total_count: status.total_count,
+            total_count_change: status.total_count_change,
+        }
+    }
+}
+
+impl SubscriptionEventStatus for RequestedDeadlineMissed {
+    const EVENT_TYPE: rcl_subscription_event_type_t =
+        rcl_subscription_event_type_t::RCL_SUBSCRIPTION_REQUESTED_DEADLINE_MISSED;
+}
+
+/// A publisher failed to show that it is alive within its liveliness lease duration.
+///
+/// With a manual liveliness policy, this happens when the publisher neither published nor called
+/// [`Publisher::assert_liveliness`][1] in time.
+///
+/// [1]: crate::Publisher::assert_liveliness
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LivelinessLost {
+    /// The total number of times that liveliness was lost so far.
+    pub total_count: i32,
+    /// The change of `total_count` since the last time the status was taken.
+    pub total_count_change: i32,
+}
+
+impl QoSEventStatus for LivelinessLost {
+    type RmwStatus = rmw_liveliness_lost_status_t;
+
+    fn from_rmw(status: &Self::RmwStatus) -> Self {
+        Self {
+            total_count: status.total_count,
+            total_count_change: status.total_count_change,
+        }
+    }
+}
+
+impl PublisherEventStatus for LivelinessLost {
+    const EVENT_TYPE: rcl_publisher_event_type_t =
+        rcl_publisher_event_type_t::RCL_PUBLISHER_LIVELINESS_LOST;
+}
+
+/// The liveliness of one or more publishers matched with a subscription changed.
+///
+/// This can be used to detect that a publisher stopped working, without a separate heartbeat
+/// topic.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LivelinessChanged {
+    /// The number of matched publishers that are currently alive.
+    pub alive_count: i32,
+    /// The number of matched publishers that are currently not alive.
+    pub not_alive_count: i32,
+    /// The change of `alive_count` since the last time the status was taken.
+    pub alive_count_change: i32,
+    /// The change of `not_alive_count` since the last time the status was taken.
+    pub not_alive_count_change: i32,
+}
+
+impl QoSEventStatus for LivelinessChanged {
+    type RmwStatus = rmw_liveliness_changed_status_t;
+
+    fn from_rmw(status: &Self::RmwStatus) -> Self {
+        Self {
+            alive_count: status.alive_count,
+            not_alive_count: status.not_alive_count,
+            alive_count_change: status.alive_count_change,
+            not_alive_count_change: status.not_alive_count_change,
+        }
+    }
+}
+
+impl SubscriptionEventStatus for LivelinessChanged {
+    const EVENT_TYPE: rcl_subscription_event_type_t =
+        rcl_subscription_event_type_t::RCL_SUBSCRIPTION_LIVELINESS_CHANGED;
+}
+
+/// Messages were lost before they could be taken by a subscription.
+///
+/// Whether this is reported, and for which causes, depends on the RMW implementation. Typically,
+/// it covers messages that were dropped because the history of the subscription was full.
+/// See also [`Node::on_message_lost`][1].
+///
+/// [1]: crate::Node::on_message_lost
+#[cfg(not(ros_distro = "foxy"))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MessageLost {
+    /// The total number of lost messages so far.
+    pub total_count: usize,
+    /// The change of `total_count` since the last time the status was taken.
+    pub total_count_change: usize,
+}
+
+#[cfg(not(ros_distro = "foxy"))]
+impl QoSEventStatus for MessageLost {
+    type RmwStatus = rmw_message_lost_status_t;
+
+    fn from_rmw(status: &Self::RmwStatus) -> Self {
+        Self {
+            total_count: status.total_count,
+            total_count_change: status.total_count_change,
+        }
+    }
+}
+
+#[cfg(not(ros_distro = "foxy"))]
+impl SubscriptionEventStatus for MessageLost {
+    const EVENT_TYPE: rcl_subscription_event_type_t =
+        rcl_subscription_event_type_t::RCL_SUBSCRIPTION_MESSAGE_LOST;
+}
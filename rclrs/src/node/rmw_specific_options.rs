@@ -0,0 +1,66 @@
+use alloc::sync::Arc;
+use core::any::Any;
+use core::ffi::c_void;
+use core::fmt;
+
+/// An opaque payload with options that are specific to an RMW implementation.
+///
+/// This is passed on unchanged to the `rmw_specific_publisher_payload` or
+/// `rmw_specific_subscription_payload` field of the `rmw` options of an entity, for tuning
+/// middleware behavior that is not covered by the QoS profile, e.g. the publication mode of
+/// Fast DDS. Whether and how the payload is used depends entirely on the RMW implementation, see
+/// its documentation. Most RMW implementations ignore it.
+///
+/// The payload is kept alive as long as the entity that it was passed to, and cloning this only
+/// clones a reference to it.
+///
+/// `rcl` has no such payload for clients and services, so it can only be set in
+/// [`PublisherOptions`][1] and [`SubscriptionOptions`][2].
+///
+/// [1]: crate::PublisherOptions
+/// [2]: crate::SubscriptionOptions
+#[derive(Clone)]
+pub struct RmwSpecificOptions {
+    payload: Arc<dyn Any + Send + Sync>,
+}
+
+impl RmwSpecificOptions {
+    /// Wraps a payload, usually a `#[repr(C)]` struct defined by the RMW implementation.
+    ///
+    /// # Safety
+    /// The RMW implementation in use must accept a pointer to a `P` as its payload, e.g. by
+    /// checking that [`rmw_get_implementation_identifier()`][1] returns the expected value.
+    ///
+    /// [1]: crate::rcl_bindings::rmw_get_implementation_identifier
+    pub unsafe fn new<P: Send + Sync + 'static>(payload: P) -> Self {
+        Self {
+            payload: Arc::new(payload),
+        }
+    }
+
+    /// Returns the payload, if it has the type `P`.
+    pub fn downcast_ref<P: 'static>(&self) -> Option<&P> {
+        self.payload.downcast_ref()
+    }
+
+    /// The pointer that is passed to the RMW implementation.
+    pub(crate) fn as_ptr(&self) -> *mut c_void {
+        Arc::as_ptr(&self.payload) as *const c_void as *mut c_void
+    }
+}
+
+impl fmt::Debug for RmwSpecificOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RmwSpecificOptions")
+            .field("payload", &self.as_ptr())
+            .finish()
+    }
+}
+
+impl PartialEq for RmwSpecificOptions {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.payload, &other.payload)
+    }
+}
+
+impl Eq for RmwSpecificOptions {}
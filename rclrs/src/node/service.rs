@@ -0,0 +1,796 @@
+use crate::allocator::copy_rcutils_allocator;
+use crate::error::{RclReturnCode, ServiceErrorCode, ToResult};
+use crate::logging::log;
+#[cfg(feature = "testing")]
+use crate::node::payload_transform::{deserialize, serialize};
+use crate::node::trace_context::RunTraceHooks;
+use crate::qos::{QoSHistoryPolicy, QoSProfile};
+#[cfg(feature = "testing")]
+use crate::testing::{EndpointKind, LoopbackEndpoint};
+use crate::tracetools;
+use crate::{rcl_bindings::*, RclrsError};
+use crate::{LogSeverity, Node, NodeHandle, RequestId, TraceContext, TraceHooks};
+
+use crate::sync::{Mutex, MutexGuard};
+
+use alloc::borrow::{Borrow, Cow};
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::ffi::CString;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ffi::c_void;
+use core::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+
+use rosidl_runtime_rs::Message;
+#[cfg(feature = "testing")]
+use rosidl_runtime_rs::RmwMessage;
+
+/// Internal struct used by services.
+pub struct ServiceHandle {
+    pub(crate) handle: Mutex<rcl_service_t>,
+    pub(crate) node_handle: Arc<NodeHandle>,
+    // The priority for the executor, see `Subscription::set_priority`.
+    pub(crate) priority: AtomicI32,
+    // Set if the service receives its requests from a `testing::Loopback`.
+    #[cfg(feature = "testing")]
+    pub(crate) loopback: Option<LoopbackEndpoint>,
+}
+
+// SAFETY: The service is only accessed through a mutex, and rcl does not require it to be used
+// from the thread that created it.
+unsafe impl Send for rcl_service_t {}
+
+impl ServiceHandle {
+    pub(crate) fn lock(&self) -> MutexGuard<rcl_service_t> {
+        self.handle.lock()
+    }
+
+    pub(crate) fn priority(&self) -> i32 {
+        self.priority.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for ServiceHandle {
+    fn drop(&mut self) {
+        let handle = self.handle.get_mut();
+        let node_handle = &mut *self.node_handle.lock();
+        // SAFETY: No preconditions for this function (besides the arguments being valid).
+        unsafe {
+            rcl_service_fini(handle, node_handle);
+        }
+    }
+}
+
+/// Trait to be implemented by concrete [`Service`]s.
+pub trait ServiceBase {
+    /// Internal function to get a reference to the `rcl` handle.
+    fn handle(&self) -> &ServiceHandle;
+    /// Tries to take a new request, run the callback with it and send the response.
+    fn execute(&self) -> Result<(), RclrsError>;
+}
+
+type ServiceCallback<Request, Response> = Box<dyn FnMut(Request) -> Response + 'static>;
+
+/// What a [`Service`] does when its request queue was full, i.e. when the executor could not keep
+/// up with the incoming requests.
+///
+/// The request queue holds as many requests as the history depth of the service's QoS profile,
+/// and the RMW implementation silently drops the oldest requests when it overflows. A service
+/// detects this when it finds as many pending requests as the depth. With a `KeepAll` history,
+/// the queue never overflows.
+///
+/// Overflows are always counted in the [`ServiceStatistics`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ServiceOverflowPolicy {
+    /// Only count the overflow.
+    #[default]
+    Count,
+    /// Log a warning with the logger of the node, and count the overflow.
+    Warn,
+    /// Drop all pending requests except for the newest one, without responding to them, and count
+    /// the overflow.
+    ///
+    /// This lets an overloaded service catch up at once, which is useful when only a response to
+    /// the latest request is meaningful, e.g. for queries of the current state. The clients of
+    /// the dropped requests never receive a response.
+    DropOldest,
+}
+
+/// What a [`DeferredService`][1] does with new requests while it has reached its
+/// [limit of concurrent requests][2].
+///
+/// [1]: crate::DeferredService
+/// [2]: ServiceOptions::max_concurrent_requests
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ServiceSheddingPolicy {
+    /// Drop the new requests without responding to them.
+    ///
+    /// The clients of the dropped requests never receive a response, so they should use a
+    /// timeout.
+    #[default]
+    Drop,
+    /// Respond to the new requests with the default response, without running the callback.
+    ///
+    /// This tells the clients right away that their request was not served, if the default
+    /// response is distinguishable from a real one, e.g. with a `success` field.
+    RespondDefault,
+}
+
+/// Options for a [`Service`], in addition to its QoS profile.
+///
+/// New options may be added in the future, so it is best to create this with the
+/// `..Default::default()` syntax.
+///
+/// # Example
+/// ```
+/// # use rclrs::{ServiceOptions, ServiceOverflowPolicy};
+/// let options = ServiceOptions {
+///     overflow_policy: ServiceOverflowPolicy::Warn,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ServiceOptions {
+    /// What to do when the request queue was full, see [`ServiceOverflowPolicy`].
+    pub overflow_policy: ServiceOverflowPolicy,
+    /// The maximum number of requests that a [`DeferredService`][1] has passed to its callback
+    /// without having responded yet. `None`, the default, means no limit.
+    ///
+    /// A [`Service`] responds to each request before it takes the next one, so this has no
+    /// effect on it.
+    ///
+    /// [1]: crate::DeferredService
+    pub max_concurrent_requests: Option<usize>,
+    /// What to do with new requests while the limit of concurrent requests is reached, see
+    /// [`ServiceSheddingPolicy`].
+    pub shedding_policy: ServiceSheddingPolicy,
+    /// The number of recent responses to keep for answering retransmitted requests. `0`, the
+    /// default, disables the cache.
+    ///
+    /// Requests are identified by the GUID of the client's writer and the sequence number that the
+    /// client assigned to them. When a request arrives again, e.g. because the RMW implementation
+    /// or the client retried it over an unreliable transport, the cached response is sent again
+    /// instead of running the callback a second time. This makes requests with side effects
+    /// idempotent, as long as the retransmission arrives before the response is evicted. The
+    /// oldest responses are evicted first.
+    ///
+    /// A [`DeferredService`][1] drops retransmissions of requests that it has not responded to
+    /// yet, since the response will be sent for the original request.
+    ///
+    /// [1]: crate::DeferredService
+    pub response_cache_size: usize,
+}
+
+/// Counters for diagnosing overloaded services, see [`Service::statistics`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ServiceStatistics {
+    /// The number of requests that have been taken from the request queue.
+    pub requests_received: u64,
+    /// The number of responses that have been sent.
+    pub responses_sent: u64,
+    /// The number of requests that have been dropped by [`ServiceOverflowPolicy::DropOldest`].
+    ///
+    /// This does not include requests that the RMW implementation dropped when the queue
+    /// overflowed, since their number is not known.
+    pub requests_dropped: u64,
+    /// The number of times that the request queue was found full.
+    pub overflows: u64,
+    /// The largest number of requests that were pending at once.
+    pub max_pending_requests: usize,
+    /// The number of requests that have been shed by the [`ServiceSheddingPolicy`], because the
+    /// limit of concurrent requests was reached.
+    pub requests_shed: u64,
+    /// The largest number of requests that were in flight at once, i.e. passed to the callback
+    /// of a [`DeferredService`][1] and not responded to yet.
+    ///
+    /// [1]: crate::DeferredService
+    pub max_in_flight_requests: usize,
+    /// The number of retransmitted requests that have been detected by the
+    /// [response cache][1], and answered from it or dropped.
+    ///
+    /// [1]: ServiceOptions::response_cache_size
+    pub duplicate_requests: u64,
+}
+
+// The state that is shared between a service and the responders of its requests.
+pub(crate) struct ServiceState {
+    pub(crate) statistics: Mutex<ServiceStatistics>,
+    // The number of requests that have been passed to the callback and not responded to yet.
+    pub(crate) in_flight: AtomicUsize,
+}
+
+impl ServiceState {
+    pub(crate) fn new() -> Arc<Self> {
+        Arc::new(Self {
+            statistics: Mutex::new(ServiceStatistics::default()),
+            in_flight: AtomicUsize::new(0),
+        })
+    }
+}
+
+// What the `ResponseCache` knows about a request.
+pub(crate) enum CachedResponse<'a, R> {
+    // The request has not been seen before, or its response has been evicted.
+    New,
+    // The request has been passed to the callback, but has not been responded to yet.
+    Pending,
+    // The request has been responded to with this response.
+    Ready(&'a R),
+}
+
+// Remembers the responses to recent requests, see `ServiceOptions::response_cache_size`.
+pub(crate) struct ResponseCache<R> {
+    capacity: usize,
+    responses: BTreeMap<RequestId, Option<R>>,
+    // The keys in the order in which they were inserted, for evicting the oldest entry.
+    order: VecDeque<RequestId>,
+}
+
+impl<R> ResponseCache<R> {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            responses: BTreeMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    // Looks up a request, and marks it as pending when it is new.
+    pub(crate) fn check(&mut self, request_id: &RequestId) -> CachedResponse<'_, R> {
+        if self.capacity == 0 {
+            return CachedResponse::New;
+        }
+        let key = *request_id;
+        if self.responses.contains_key(&key) {
+            return match &self.responses[&key] {
+                Some(response) => CachedResponse::Ready(response),
+                None => CachedResponse::Pending,
+            };
+        }
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.responses.remove(&oldest);
+            }
+        }
+        self.responses.insert(key, None);
+        self.order.push_back(key);
+        CachedResponse::New
+    }
+
+    // Stores the response to a request that was marked as pending by `check()`, unless it has
+    // been evicted in the meantime.
+    pub(crate) fn insert(&mut self, request_id: &RequestId, response: R) {
+        if let Some(entry) = self.responses.get_mut(request_id) {
+            *entry = Some(response);
+        }
+    }
+
+    // Forgets a pending request that will not be responded to, so that a retransmission of it is
+    // passed to the callback again.
+    pub(crate) fn abandon(&mut self, request_id: &RequestId) {
+        let key = *request_id;
+        if let Some(None) = self.responses.get(&key) {
+            self.responses.remove(&key);
+            self.order.retain(|k| *k != key);
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+}
+
+// Applies the overflow policy of a service, which is shared by `Service` and `DeferredService`.
+pub(crate) struct OverflowHandler {
+    pub(crate) request_queue_depth: Option<usize>,
+    pub(crate) policy: ServiceOverflowPolicy,
+    // The logger of the node, for `ServiceOverflowPolicy::Warn`.
+    pub(crate) logger_name: String,
+    #[cfg(feature = "statistics_msgs")]
+    pub(crate) node_statistics: Option<Arc<crate::NodeStatistics>>,
+}
+
+impl OverflowHandler {
+    pub(crate) fn new(node: &Node, qos: &QoSProfile, policy: ServiceOverflowPolicy) -> Self {
+        let request_queue_depth = match qos.history {
+            QoSHistoryPolicy::SystemDefault { depth }
+            | QoSHistoryPolicy::KeepLast { depth }
+            | QoSHistoryPolicy::Unknown { depth, .. } => Some(depth as usize),
+            QoSHistoryPolicy::KeepAll => None,
+        };
+        Self {
+            request_queue_depth,
+            policy,
+            logger_name: node.logger_name(),
+            #[cfg(feature = "statistics_msgs")]
+            node_statistics: node.statistics.clone(),
+        }
+    }
+
+    // Updates the statistics for a batch of pending requests, and applies the overflow policy.
+    pub(crate) fn handle<R>(
+        &self,
+        statistics: &mut ServiceStatistics,
+        pending_requests: &mut Vec<(R, RequestId)>,
+    ) {
+        statistics.requests_received += pending_requests.len() as u64;
+        statistics.max_pending_requests =
+            statistics.max_pending_requests.max(pending_requests.len());
+        let overflowed = match self.request_queue_depth {
+            Some(depth) => depth > 0 && pending_requests.len() >= depth,
+            None => false,
+        };
+        if !overflowed {
+            return;
+        }
+        statistics.overflows += 1;
+        match self.policy {
+            ServiceOverflowPolicy::Count => {}
+            ServiceOverflowPolicy::Warn => {
+                log(
+                    &self.logger_name,
+                    LogSeverity::Warn,
+                    &format!(
+                        "The request queue of a service was full with {} requests, older \
+                         requests may have been lost",
+                        pending_requests.len()
+                    ),
+                );
+            }
+            ServiceOverflowPolicy::DropOldest => {
+                let dropped = pending_requests.len() - 1;
+                pending_requests.drain(..dropped);
+                statistics.requests_dropped += dropped as u64;
+                #[cfg(feature = "statistics_msgs")]
+                if let Some(node_statistics) = &self.node_statistics {
+                    node_statistics.record_dropped_requests(dropped);
+                }
+            }
+        }
+    }
+}
+
+// Takes all pending requests of a service, to find out whether the queue was full.
+//
+// In static memory mode, this stops at the limit, so that the list doesn't grow. The remaining
+// requests are taken in the next execution, since the service stays ready.
+pub(crate) fn take_pending_requests<T>(
+    handle: &ServiceHandle,
+    pending_requests: &mut Vec<(T::Request, RequestId)>,
+    max_pending_requests: Option<usize>,
+) -> Result<(), RclrsError>
+where
+    T: rosidl_runtime_rs::Service,
+{
+    loop {
+        if max_pending_requests.is_some_and(|max| pending_requests.len() >= max) {
+            return Ok(());
+        }
+        match take_request::<T>(handle) {
+            Ok(request) => pending_requests.push(request),
+            Err(RclrsError {
+                code: RclReturnCode::ServiceError(ServiceErrorCode::ServiceTakeFailed),
+                ..
+            }) => return Ok(()),
+            Err(e) => {
+                pending_requests.clear();
+                return Err(e);
+            }
+        }
+    }
+}
+
+// Fetches a new request, together with the ID that the response must be sent with.
+//
+// When there is no new request, this returns a `ServiceTakeFailed` error.
+fn take_request<T>(handle: &ServiceHandle) -> Result<(T::Request, RequestId), RclrsError>
+where
+    T: rosidl_runtime_rs::Service,
+{
+    let mut rmw_message = <T::Request as Message>::RmwMsg::default();
+    #[cfg(feature = "testing")]
+    if let Some(loopback) = &handle.loopback {
+        let frame = loopback.take().ok_or(RclrsError {
+            code: RclReturnCode::ServiceError(ServiceErrorCode::ServiceTakeFailed),
+            msg: None,
+        })?;
+        let type_support = <T::Request as Message>::RmwMsg::get_type_support()
+            as *const rosidl_message_type_support_t;
+        deserialize(
+            &frame.payload,
+            type_support,
+            &mut rmw_message as *mut <T::Request as Message>::RmwMsg as *mut _,
+        )?;
+        return Ok((T::Request::from_rmw_message(rmw_message), frame.request_id));
+    }
+    // SAFETY: Getting a zero-initialized value is always safe.
+    let mut request_id = unsafe { core::mem::zeroed::<rmw_request_id_t>() };
+    unsafe {
+        // SAFETY: The three pointers are valid/initialized, and do not need to be valid beyond
+        // the function call.
+        rcl_take_request(
+            &*handle.lock(),
+            &mut request_id,
+            &mut rmw_message as *mut <T::Request as Message>::RmwMsg as *mut _,
+        )
+    }
+    .ok()?;
+    Ok((
+        T::Request::from_rmw_message(rmw_message),
+        RequestId::from(&request_id),
+    ))
+}
+
+// Sends the response to the request with the given ID.
+pub(crate) fn send_response<T>(
+    handle: &ServiceHandle,
+    request_id: &RequestId,
+    response: T::Response,
+) -> Result<(), RclrsError>
+where
+    T: rosidl_runtime_rs::Service,
+{
+    let rmw_message = <T::Response as Message>::into_rmw_message(Cow::Owned(response));
+    #[cfg(feature = "testing")]
+    if let Some(loopback) = &handle.loopback {
+        let type_support = <T::Response as Message>::RmwMsg::get_type_support()
+            as *const rosidl_message_type_support_t;
+        let payload = serialize(
+            rmw_message.as_ref() as *const <T::Response as Message>::RmwMsg as *const _,
+            type_support,
+        )?;
+        loopback.send_response(request_id, payload.as_slice());
+        return Ok(());
+    }
+    let mut request_id = request_id.to_rmw();
+    unsafe {
+        // SAFETY: The response type is guaranteed to match the service type by the type
+        // system. The response does not need to be valid beyond the duration of this
+        // function call.
+        rcl_send_response(
+            &*handle.lock(),
+            &mut request_id,
+            rmw_message.as_ref() as *const <T::Response as Message>::RmwMsg as *mut _,
+        )
+    }
+    .ok()
+}
+
+/// Struct for responding to requests of [`Client`][1]s for a service of type `T`.
+///
+/// Receiving requests requires calling [`spin_once`][2] or [`spin`][3] on the service's node.
+///
+/// [1]: crate::Client
+/// [2]: crate::spin_once
+/// [3]: crate::spin
+pub struct Service<T>
+where
+    T: rosidl_runtime_rs::Service,
+{
+    pub(crate) handle: Arc<ServiceHandle>,
+    /// The callback function that computes the response to a request.
+    pub callback: Mutex<ServiceCallback<T::Request, T::Response>>,
+    // A callback set by `set_callback()` while the callback was running, which replaces it when
+    // it returns.
+    next_callback: Mutex<Option<ServiceCallback<T::Request, T::Response>>>,
+    overflow_handler: OverflowHandler,
+    state: Arc<ServiceState>,
+    response_cache: Mutex<ResponseCache<T::Response>>,
+    // Reused between executions, so that no allocations happen once it has grown to the depth.
+    // In static memory mode, it is allocated with the maximum length up front.
+    pending_requests: Mutex<Vec<(T::Request, RequestId)>>,
+    max_pending_requests: Option<usize>,
+    trace_hooks: Option<Arc<TraceHooks>>,
+}
+
+impl<T> Service<T>
+where
+    T: rosidl_runtime_rs::Service,
+{
+    /// Creates a new service.
+    ///
+    /// # Panics
+    /// When the service name contains interior null bytes.
+    pub fn new<F>(
+        node: &Node,
+        service_name: &str,
+        qos: QoSProfile,
+        callback: F,
+    ) -> Result<Self, RclrsError>
+    where
+        F: FnMut(T::Request) -> T::Response + 'static,
+    {
+        Self::new_with_options(
+            node,
+            service_name,
+            qos,
+            node.entity_defaults.service_options,
+            callback,
+        )
+    }
+
+    /// Creates a new service with additional options.
+    ///
+    /// # Panics
+    /// When the service name contains interior null bytes.
+    pub fn new_with_options<F>(
+        node: &Node,
+        service_name: &str,
+        qos: QoSProfile,
+        options: ServiceOptions,
+        callback: F,
+    ) -> Result<Self, RclrsError>
+    where
+        F: FnMut(T::Request) -> T::Response + 'static,
+    {
+        // This is declared before the node handle guard, since dropping it in the error case
+        // locks the node handle.
+        let service_name = node.expand_topic_name(service_name)?;
+        #[cfg_attr(not(feature = "testing"), allow(unused_variables))]
+        let handle = Arc::new_cyclic(|handle| ServiceHandle {
+            // SAFETY: Getting a zero-initialized value is always safe.
+            handle: Mutex::new(unsafe { rcl_get_zero_initialized_service() }),
+            node_handle: node.handle.clone(),
+            priority: AtomicI32::new(0),
+            #[cfg(feature = "testing")]
+            loopback: LoopbackEndpoint::new(node, handle, EndpointKind::Service, &service_name),
+        });
+        let type_support = <T as rosidl_runtime_rs::Service>::get_type_support()
+            as *const rosidl_service_type_support_t;
+        let service_name_c_string = CString::new(service_name).unwrap();
+        let node_handle = &mut *node.handle.lock();
+
+        // SAFETY: No preconditions for this function.
+        let mut service_options = unsafe { rcl_service_get_default_options() };
+        service_options.qos = qos.into();
+        service_options.allocator = copy_rcutils_allocator(&node.allocator);
+        unsafe {
+            // SAFETY: The service handle is zero-initialized as expected by this function.
+            // The node handle is kept alive because it is co-owned by the service.
+            // The service name and the options are copied by this function, so they can be dropped
+            // afterwards.
+            rcl_service_init(
+                &mut *handle.lock(),
+                node_handle,
+                type_support,
+                service_name_c_string.as_ptr(),
+                &service_options,
+            )
+            .ok()?;
+        }
+
+        Ok(Self {
+            handle,
+            callback: Mutex::new(Box::new(callback)),
+            next_callback: Mutex::new(None),
+            overflow_handler: OverflowHandler::new(node, &qos, options.overflow_policy),
+            state: ServiceState::new(),
+            response_cache: Mutex::new(ResponseCache::new(options.response_cache_size)),
+            pending_requests: Mutex::new(Vec::with_capacity(
+                node.max_pending_requests().unwrap_or(0),
+            )),
+            max_pending_requests: node.max_pending_requests(),
+            trace_hooks: node.trace_hooks.clone(),
+        })
+    }
+
+    /// Returns the depth of the request queue, from the history policy of the QoS profile.
+    ///
+    /// Returns `None` for the `KeepAll` history policy, with which the queue is unbounded.
+    pub fn request_queue_depth(&self) -> Option<usize> {
+        self.overflow_handler.request_queue_depth
+    }
+
+    /// Returns the counters of received requests, overflows etc. since the service was created.
+    pub fn statistics(&self) -> ServiceStatistics {
+        *self.state.statistics.lock()
+    }
+
+    /// Returns the priority of the service for the executor.
+    ///
+    /// See [`Subscription::set_priority`][1].
+    ///
+    /// [1]: crate::Subscription::set_priority
+    pub fn priority(&self) -> i32 {
+        self.handle.priority()
+    }
+
+    /// Sets the priority of the service for the executor.
+    ///
+    /// See [`Subscription::set_priority`][1].
+    ///
+    /// [1]: crate::Subscription::set_priority
+    pub fn set_priority(&self, priority: i32) {
+        self.handle.priority.store(priority, Ordering::Relaxed);
+    }
+
+    /// Replaces the callback that computes the response to a request.
+    ///
+    /// See [`Subscription::set_callback`][1].
+    ///
+    /// [1]: crate::Subscription::set_callback
+    pub fn set_callback<F>(&self, callback: F)
+    where
+        F: FnMut(T::Request) -> T::Response + 'static,
+    {
+        match self.callback.try_lock() {
+            Some(mut current) => *current = Box::new(callback),
+            None => *self.next_callback.lock() = Some(Box::new(callback)),
+        }
+    }
+
+    /// Returns a pointer to the underlying `rcl` service, for calling functions that are not
+    /// wrapped by `rclrs`.
+    ///
+    /// # Safety
+    /// See [`Node::raw_handle`]. The service must not be finalized.
+    pub unsafe fn raw_handle(&self) -> *mut rcl_service_t {
+        &mut *self.handle.lock()
+    }
+
+    /// The ID of the callback in tracepoints, which is the address of the callback field.
+    ///
+    /// It is stable as long as the service is not moved, which it is not after being put into an
+    /// `Arc` by [`Node::create_service`].
+    pub(crate) fn callback_id(&self) -> *const c_void {
+        &self.callback as *const _ as *const c_void
+    }
+
+    /// Emits the tracepoints that register the callback of the service.
+    ///
+    /// This must be called once the service is at its final address.
+    #[cfg_attr(not(feature = "tracetools"), allow(unused_variables))]
+    pub(crate) fn trace_init(&self, callback_symbol: &str) {
+        // Only lock the handle and allocate the symbol if the tracepoints are enabled.
+        #[cfg(feature = "tracetools")]
+        {
+            let handle = &*self.handle.lock() as *const rcl_service_t;
+            tracetools::service_callback_added(handle as *const _, self.callback_id());
+            let symbol = CString::new(callback_symbol).unwrap_or_default();
+            tracetools::callback_register(self.callback_id(), symbol.as_ptr());
+        }
+    }
+}
+
+impl<T> ServiceBase for Service<T>
+where
+    T: rosidl_runtime_rs::Service,
+{
+    fn handle(&self) -> &ServiceHandle {
+        self.handle.borrow()
+    }
+
+    fn execute(&self) -> Result<(), RclrsError> {
+        let pending_requests = &mut *self.pending_requests.lock();
+        take_pending_requests::<T>(&self.handle, pending_requests, self.max_pending_requests)?;
+        // Spurious wakeup – this may happen even when a waitset indicated that this
+        // service was ready, so it shouldn't be an error.
+        if pending_requests.is_empty() {
+            return Ok(());
+        }
+        self.overflow_handler
+            .handle(&mut self.state.statistics.lock(), pending_requests);
+        for (request, request_id) in pending_requests.drain(..) {
+            if let CachedResponse::Ready(response) = self.response_cache.lock().check(&request_id) {
+                self.state.statistics.lock().duplicate_requests += 1;
+                send_response::<T>(&self.handle, &request_id, response.clone())?;
+                self.state.statistics.lock().responses_sent += 1;
+                continue;
+            }
+            let mut context = TraceContext::new(Some(request_id), BTreeMap::new());
+            self.trace_hooks.request_received(&mut context);
+            let response = {
+                let callback = &mut *self.callback.lock();
+                let callback_id = self.callback_id();
+                tracetools::callback_start(callback_id);
+                #[cfg(feature = "std")]
+                let response =
+                    crate::current::with_trace_context(&mut context, || callback(request));
+                #[cfg(not(feature = "std"))]
+                let response = callback(request);
+                tracetools::callback_end(callback_id);
+                if let Some(next_callback) = self.next_callback.lock().take() {
+                    *callback = next_callback;
+                }
+                response
+            };
+            {
+                let response_cache = &mut *self.response_cache.lock();
+                if response_cache.is_enabled() {
+                    response_cache.insert(&request_id, response.clone());
+                }
+            }
+            send_response::<T>(&self.handle, &request_id, response)?;
+            self.state.statistics.lock().responses_sent += 1;
+            self.trace_hooks.response_sent(&context);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_id(sequence_number: i64) -> RequestId {
+        RequestId::new([1; 16], sequence_number)
+    }
+
+    #[test]
+    fn test_response_cache_replays_responses() {
+        let mut cache = ResponseCache::new(4);
+        assert!(cache.is_enabled());
+        assert!(matches!(cache.check(&request_id(1)), CachedResponse::New));
+        // A retransmission before the response is ready is not passed to the callback again.
+        assert!(matches!(
+            cache.check(&request_id(1)),
+            CachedResponse::Pending
+        ));
+        cache.insert(&request_id(1), "one");
+        assert!(matches!(
+            cache.check(&request_id(1)),
+            CachedResponse::Ready(&"one")
+        ));
+        // Requests from another client with the same sequence number are different requests.
+        let other_client = RequestId::new([2; 16], 1);
+        assert!(matches!(cache.check(&other_client), CachedResponse::New));
+    }
+
+    #[test]
+    fn test_response_cache_evicts_oldest_request() {
+        let mut cache = ResponseCache::new(2);
+        for sequence_number in 1..=2 {
+            assert!(matches!(
+                cache.check(&request_id(sequence_number)),
+                CachedResponse::New
+            ));
+            cache.insert(&request_id(sequence_number), sequence_number);
+        }
+        assert!(matches!(cache.check(&request_id(3)), CachedResponse::New));
+        // The first request has been evicted, so its response is not known anymore.
+        assert!(matches!(
+            cache.check(&request_id(2)),
+            CachedResponse::Ready(2)
+        ));
+        assert!(matches!(cache.check(&request_id(1)), CachedResponse::New));
+        assert!(matches!(cache.check(&request_id(2)), CachedResponse::New));
+        // A response to an evicted request is dropped.
+        cache.insert(&request_id(3), 3);
+        assert!(matches!(cache.check(&request_id(3)), CachedResponse::New));
+    }
+
+    #[test]
+    fn test_response_cache_abandon() {
+        let mut cache = ResponseCache::new(2);
+        assert!(matches!(cache.check(&request_id(1)), CachedResponse::New));
+        cache.abandon(&request_id(1));
+        assert!(matches!(cache.check(&request_id(1)), CachedResponse::New));
+        // Abandoning frees the slot, so no other request is evicted.
+        cache.abandon(&request_id(1));
+        assert!(matches!(cache.check(&request_id(2)), CachedResponse::New));
+        cache.insert(&request_id(2), 2);
+        assert!(matches!(cache.check(&request_id(3)), CachedResponse::New));
+        assert!(matches!(
+            cache.check(&request_id(2)),
+            CachedResponse::Ready(2)
+        ));
+        // Requests that have been responded to are kept.
+        cache.abandon(&request_id(2));
+        assert!(matches!(
+            cache.check(&request_id(2)),
+            CachedResponse::Ready(2)
+        ));
+    }
+
+    #[test]
+    fn test_response_cache_disabled() {
+        let mut cache = ResponseCache::new(0);
+        assert!(!cache.is_enabled());
+        assert!(matches!(cache.check(&request_id(1)), CachedResponse::New));
+        cache.insert(&request_id(1), 1);
+        assert!(matches!(cache.check(&request_id(1)), CachedResponse::New));
+    }
+}
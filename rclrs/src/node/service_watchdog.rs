@@ -0,0 +1,96 @@
+use crate::sync::Mutex;
+use crate::{Client, RclrsError};
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Trait to be implemented by entities that react to changes of the ROS graph, which are
+/// executed when the graph guard condition of their node is triggered.
+pub(crate) trait GraphEventHandler {
+    /// Checks the part of the graph that the entity is interested in.
+    fn handle_graph_event(&self) -> Result<(), RclrsError>;
+}
+
+type AvailabilityCallback = Box<dyn FnMut() + 'static>;
+
+/// Tracks whether the service server of a [`Client`] is available, and runs callbacks when it
+/// appears or disappears.
+///
+/// Create a watchdog with [`Node::create_service_watchdog`][1]. It is woken up by changes of the
+/// ROS graph while its node is spinning, so clients can switch to a fallback server or a degraded
+/// mode without calling [`Client::service_is_ready`] in a loop.
+///
+/// The callbacks only run on changes: a server that is already available when the watchdog is
+/// created is reported by [`ServiceWatchdog::is_available`], but does not run the
+/// [`on_available`][2] callback.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let client = node.create_client::<example_interfaces::srv::AddTwoInts>(
+///     "add_two_ints",
+///     QOS_PROFILE_SERVICES_DEFAULT,
+/// )?;
+/// let watchdog = node.create_service_watchdog(&client)?;
+/// watchdog.on_unavailable(|| println!("Server lost, switching to the backup"));
+/// watchdog.on_available(|| println!("Server is back"));
+/// ```
+///
+/// [1]: crate::Node::create_service_watchdog
+/// [2]: ServiceWatchdog::on_available
+pub struct ServiceWatchdog {
+    is_ready: Box<dyn Fn() -> Result<bool, RclrsError> + 'static>,
+    available: AtomicBool,
+    on_available: Mutex<Option<AvailabilityCallback>>,
+    on_unavailable: Mutex<Option<AvailabilityCallback>>,
+}
+
+impl ServiceWatchdog {
+    pub(crate) fn new<T>(client: &Arc<Client<T>>) -> Result<Self, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+    {
+        let client = Arc::clone(client);
+        let available = client.service_is_ready()?;
+        Ok(Self {
+            is_ready: Box::new(move || client.service_is_ready()),
+            available: AtomicBool::new(available),
+            on_available: Mutex::new(None),
+            on_unavailable: Mutex::new(None),
+        })
+    }
+
+    /// Returns whether the service server was available when the graph was last checked.
+    pub fn is_available(&self) -> bool {
+        self.available.load(Ordering::Acquire)
+    }
+
+    /// Sets the callback that runs when the service server becomes available.
+    pub fn on_available(&self, callback: impl FnMut() + 'static) {
+        *self.on_available.lock() = Some(Box::new(callback));
+    }
+
+    /// Sets the callback that runs when the service server becomes unavailable.
+    pub fn on_unavailable(&self, callback: impl FnMut() + 'static) {
+        *self.on_unavailable.lock() = Some(Box::new(callback));
+    }
+}
+
+impl GraphEventHandler for ServiceWatchdog {
+    fn handle_graph_event(&self) -> Result<(), RclrsError> {
+        let available = (self.is_ready)()?;
+        if self.available.swap(available, Ordering::AcqRel) == available {
+            return Ok(());
+        }
+        let callback = if available {
+            &self.on_available
+        } else {
+            &self.on_unavailable
+        };
+        if let Some(callback) = &mut *callback.lock() {
+            callback();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{Clock, Node, RclrsError, Time};
+
+use alloc::string::String;
+
+use rosidl_runtime_rs::Stamped;
+
+impl Node {
+    /// Sets the stamp of a header to the current time of the node's [clock][1].
+    ///
+    /// This accepts both a message with a `std_msgs/Header` field and the header itself, see
+    /// [`Stamped`]. To also set the frame ID, use a [`HeaderStamper`].
+    ///
+    /// # Example
+    /// ```ignore
+    /// # use rclrs::{Context, RclrsError};
+    /// let context = Context::new([])?;
+    /// let node = context.create_node("my_node")?;
+    /// let mut msg = geometry_msgs::msg::PointStamped::default();
+    /// node.stamp_now(&mut msg.header)?;
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    ///
+    /// [1]: Node::get_clock
+    pub fn stamp_now(&self, header: &mut impl Stamped) -> Result<Time, RclrsError> {
+        let now = self.get_clock().now()?;
+        let (sec, nanosec) = now.to_sec_nanosec();
+        header.set_stamp(sec, nanosec);
+        Ok(now)
+    }
+}
+
+/// Sets the stamp and frame ID of headers, for publishers that always publish in the same frame.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::{Context, HeaderStamper, RclrsError};
+/// let context = Context::new([])?;
+/// let node = context.create_node("my_node")?;
+/// let stamper = HeaderStamper::new(node.get_clock(), "base_link");
+/// let mut msg = geometry_msgs::msg::PointStamped::default();
+/// stamper.stamp(&mut msg)?;
+/// assert_eq!(msg.header.frame_id, "base_link");
+/// # Ok::<(), RclrsError>(())
+/// ```
+#[derive(Clone)]
+pub struct HeaderStamper {
+    clock: Clock,
+    frame_id: String,
+}
+
+impl HeaderStamper {
+    /// Creates a stamper that takes the stamps from the given clock.
+    pub fn new(clock: Clock, frame_id: impl Into<String>) -> Self {
+        Self {
+            clock,
+            frame_id: frame_id.into(),
+        }
+    }
+
+    /// Returns the frame ID that is set by this stamper.
+    pub fn frame_id(&self) -> &str {
+        &self.frame_id
+    }
+
+    /// Sets the stamp of a header to the current time, and its frame ID.
+    ///
+    /// Returns the time of the stamp.
+    pub fn stamp(&self, header: &mut impl Stamped) -> Result<Time, RclrsError> {
+        let now = self.clock.now()?;
+        let (sec, nanosec) = now.to_sec_nanosec();
+        header.set_stamp(sec, nanosec);
+        header.set_frame_id(&self.frame_id);
+        Ok(now)
+    }
+}
@@ -0,0 +1,54 @@
+use crate::{Context, GuardCondition, RclrsError, ReusableWaitSet, WaitSetCapacities};
+
+use alloc::sync::Arc;
+
+/// Limits for a node in static memory mode, see [`Node::enable_static_memory`][1].
+///
+/// [1]: crate::Node::enable_static_memory
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StaticMemoryLimits {
+    /// The maximum number of subscriptions of the node that can be alive at the same time.
+    pub max_subscriptions: usize,
+    /// The maximum number of timers of the node that can be alive at the same time.
+    pub max_timers: usize,
+    /// The maximum number of clients of the node that can be alive at the same time.
+    pub max_clients: usize,
+    /// The maximum number of services of the node that can be alive at the same time.
+    pub max_services: usize,
+    /// The maximum number of QoS event handlers of the node that can be alive at the same time.
+    pub max_qos_events: usize,
+    /// The maximum number of requests that each client of the node can have pending, i.e. sent
+    /// and not answered yet, and that each service of the node takes from its request queue at
+    /// once.
+    pub max_pending_requests: usize,
+}
+
+/// Storage that is allocated once when static memory mode is enabled, and reused afterwards.
+pub(crate) struct StaticMemory {
+    pub(crate) limits: StaticMemoryLimits,
+    pub(crate) wait_set: ReusableWaitSet,
+}
+
+impl StaticMemory {
+    pub(crate) fn new(
+        limits: StaticMemoryLimits,
+        graph_guard_condition: Arc<GuardCondition>,
+        context: &Context,
+    ) -> Result<Self, RclrsError> {
+        Ok(Self {
+            limits,
+            wait_set: ReusableWaitSet::new(
+                WaitSetCapacities {
+                    subscriptions: limits.max_subscriptions,
+                    timers: limits.max_timers,
+                    clients: limits.max_clients,
+                    services: limits.max_services,
+                    qos_events: limits.max_qos_events,
+                    ..Default::default()
+                },
+                alloc::vec![graph_guard_condition],
+                context,
+            )?,
+        })
+    }
+}
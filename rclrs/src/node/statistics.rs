@@ -0,0 +1,231 @@
+use crate::rcl_interfaces::BuiltinTime;
+use crate::rosidl_macros::{impl_message, impl_sequence_alloc};
+use crate::sync::Mutex;
+use crate::{Clock, ClockType, Node, RclrsError, Timer, QOS_PROFILE_DEFAULT};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rosidl_runtime_rs::{Sequence, String as RosString};
+
+/// Options for publishing the runtime statistics of a node, see [`NodeOptions::statistics`][1].
+///
+/// The statistics are published as `statistics_msgs/msg/MetricsMessage`s, like the topic
+/// statistics of `rclcpp`, so that the same tools can display them. In each period, one message
+/// is published for each of these metrics, with the fully qualified node name as the measurement
+/// source name:
+/// - `callback_duration`: How long the callbacks of subscriptions, timers, clients, services and
+///   QoS event handlers ran, in milliseconds.
+/// - `ready_entities`: How many entities were ready in a wakeup of the executor, i.e. the depth
+///   of the executor's queue.
+/// - `dropped_requests`: How many requests were dropped at once by services with the
+///   [`DropOldest`][2] overflow policy.
+/// - `dropped_messages`: How many messages were dropped at once by subscriptions, because they
+///   were coalesced with [`latest_only`][3], arrived within the period of [`throttled`][4], or
+///   could not be decoded by a [`PayloadTransform`][6].
+/// - `lost_messages`: How many messages the middleware reported as lost for the subscriptions of
+///   the node, see [`MessageLost`][5]. This is not collected for Foxy, and stays empty for RMW
+///   implementations that do not report lost messages.
+///
+/// Each message contains the average, minimum, maximum, standard deviation and count of the
+/// samples since the previous message.
+///
+/// [1]: crate::NodeOptions::statistics
+/// [2]: crate::ServiceOverflowPolicy::DropOldest
+/// [3]: crate::Subscription::latest_only
+/// [4]: crate::Subscription::throttled
+/// [5]: crate::MessageLost
+/// [6]: crate::PayloadTransform
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NodeStatisticsOptions {
+    /// The topic that the statistics are published on, `/statistics` by default.
+    pub topic: String,
+    /// How often the statistics are published, 1 second by default.
+    pub publish_period: Duration,
+}
+
+impl Default for NodeStatisticsOptions {
+    fn default() -> Self {
+        Self {
+            topic: "/statistics".into(),
+            publish_period: Duration::from_secs(1),
+        }
+    }
+}
+
+// The values of the `data_type` field, from statistics_msgs/msg/StatisticDataType.
+const STATISTICS_DATA_TYPE_AVERAGE: u8 = 1;
+const STATISTICS_DATA_TYPE_MINIMUM: u8 = 2;
+const STATISTICS_DATA_TYPE_MAXIMUM: u8 = 3;
+const STATISTICS_DATA_TYPE_STDDEV: u8 = 4;
+const STATISTICS_DATA_TYPE_SAMPLE_COUNT: u8 = 5;
+
+// Corresponds to statistics_msgs__msg__StatisticDataPoint
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct StatisticDataPoint {
+    data_type: u8,
+    data: f64,
+}
+
+impl_message!(
+    StatisticDataPoint,
+    "statistics_msgs/msg/StatisticDataPoint",
+    statistics_msgs__msg__StatisticDataPoint__init,
+    rosidl_typesupport_c__get_message_type_support_handle__statistics_msgs__msg__StatisticDataPoint
+);
+impl_sequence_alloc!(
+    StatisticDataPoint,
+    statistics_msgs__msg__StatisticDataPoint__Sequence__init,
+    statistics_msgs__msg__StatisticDataPoint__Sequence__fini
+);
+
+// Corresponds to statistics_msgs__msg__MetricsMessage
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct MetricsMessage {
+    pub(crate) measurement_source_name: RosString,
+    pub(crate) metrics_source: RosString,
+    pub(crate) unit: RosString,
+    pub(crate) window_start: BuiltinTime,
+    pub(crate) window_stop: BuiltinTime,
+    pub(crate) statistics: Sequence<StatisticDataPoint>,
+}
+
+impl_message!(
+    MetricsMessage,
+    "statistics_msgs/msg/MetricsMessage",
+    statistics_msgs__msg__MetricsMessage__init,
+    rosidl_typesupport_c__get_message_type_support_handle__statistics_msgs__msg__MetricsMessage
+);
+
+// The running statistics of one metric within a window.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Accumulator {
+    count: u64,
+    sum: f64,
+    sum_of_squares: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Accumulator {
+    pub(crate) fn add(&mut self, sample: f64) {
+        if self.count == 0 {
+            self.min = sample;
+            self.max = sample;
+        } else {
+            self.min = self.min.min(sample);
+            self.max = self.max.max(sample);
+        }
+        self.count += 1;
+        self.sum += sample;
+        self.sum_of_squares += sample * sample;
+    }
+
+    pub(crate) fn data_points(&self) -> Sequence<StatisticDataPoint> {
+        // Like in rclcpp, the statistics of an empty window are NaN, except for the count.
+        let (average, stddev, min, max) = if self.count == 0 {
+            (f64::NAN, f64::NAN, f64::NAN, f64::NAN)
+        } else {
+            let count = self.count as f64;
+            let average = self.sum / count;
+            let variance = (self.sum_of_squares / count - average * average).max(0.0);
+            (average, variance.sqrt(), self.min, self.max)
+        };
+        [
+            (STATISTICS_DATA_TYPE_AVERAGE, average),
+            (STATISTICS_DATA_TYPE_MINIMUM, min),
+            (STATISTICS_DATA_TYPE_MAXIMUM, max),
+            (STATISTICS_DATA_TYPE_STDDEV, stddev),
+            (STATISTICS_DATA_TYPE_SAMPLE_COUNT, self.count as f64),
+        ]
+        .into_iter()
+        .map(|(data_type, data)| StatisticDataPoint { data_type, data })
+        .collect()
+    }
+}
+
+#[derive(Default)]
+struct StatisticsWindow {
+    callback_duration: Accumulator,
+    ready_entities: Accumulator,
+    dropped_requests: Accumulator,
+    dropped_messages: Accumulator,
+    lost_messages: Accumulator,
+}
+
+/// The statistics of a node since they were last published, which are collected by the executor
+/// and the entities of the node.
+#[derive(Default)]
+pub(crate) struct NodeStatistics {
+    window: Mutex<StatisticsWindow>,
+}
+
+impl NodeStatistics {
+    pub(crate) fn record_callback(&self, duration: Duration) {
+        let milliseconds = duration.as_secs_f64() * 1000.0;
+        self.window.lock().callback_duration.add(milliseconds);
+    }
+
+    pub(crate) fn record_wakeup(&self, ready_entities: usize) {
+        self.window.lock().ready_entities.add(ready_entities as f64);
+    }
+
+    pub(crate) fn record_dropped_requests(&self, dropped_requests: usize) {
+        self.window
+            .lock()
+            .dropped_requests
+            .add(dropped_requests as f64);
+    }
+
+    pub(crate) fn record_dropped_messages(&self, dropped_messages: usize) {
+        self.window
+            .lock()
+            .dropped_messages
+            .add(dropped_messages as f64);
+    }
+
+    pub(crate) fn record_lost_messages(&self, lost_messages: usize) {
+        self.window.lock().lost_messages.add(lost_messages as f64);
+    }
+}
+
+// Creates the timer that publishes the statistics of the node.
+pub(crate) fn create_statistics_timer(
+    node: &mut Node,
+    statistics: Arc<NodeStatistics>,
+    options: &NodeStatisticsOptions,
+) -> Result<Arc<Timer>, RclrsError> {
+    let publisher = node.create_publisher::<MetricsMessage>(&options.topic, QOS_PROFILE_DEFAULT)?;
+    let measurement_source_name = RosString::from(node.fully_qualified_name().as_str());
+    let clock = node.get_clock();
+    let mut window_start = clock.now()?;
+    node.add_timer(
+        options.publish_period,
+        Clock::new(ClockType::SteadyTime)?,
+        move || {
+            let window_stop = clock.now()?;
+            let window = core::mem::take(&mut *statistics.window.lock());
+            let metrics = [
+                ("callback_duration", "ms", window.callback_duration),
+                ("ready_entities", "count", window.ready_entities),
+                ("dropped_requests", "count", window.dropped_requests),
+                ("dropped_messages", "count", window.dropped_messages),
+                ("lost_messages", "count", window.lost_messages),
+            ];
+            for (metrics_source, unit, accumulator) in metrics {
+                publisher.publish(MetricsMessage {
+                    measurement_source_name: measurement_source_name.clone(),
+                    metrics_source: metrics_source.into(),
+                    unit: unit.into(),
+                    window_start: window_start.into(),
+                    window_stop: window_stop.into(),
+                    statistics: accumulator.data_points(),
+                })?;
+            }
+            window_start = window_stop;
+            Ok(())
+        },
+    )
+}
@@ -1,32 +1,72 @@
+use crate::allocator::copy_rcutils_allocator;
 use crate::error::{RclReturnCode, SubscriberErrorCode, ToResult};
+use crate::logging::log;
+use crate::node::graph::MatchedCount;
+#[cfg(feature = "testing")]
+use crate::node::payload_transform::deserialize;
+use crate::node::payload_transform::SerializedMessageBuffer;
+use crate::parameter::ParameterName;
 use crate::qos::QoSProfile;
-use crate::Node;
+#[cfg(feature = "testing")]
+use crate::testing::{EndpointKind, LoopbackEndpoint};
+use crate::tracetools;
 use crate::{rcl_bindings::*, RclrsError};
+use crate::{
+    LogSeverity, MessageInfo, Node, NodeHandle, PayloadMiddleware, QoSEvent, QoSOverridingOptions,
+    RequestedIncompatibleQoS, RmwSpecificOptions, TypeHash, TypeMismatchPolicy,
+};
 
-use std::borrow::Borrow;
-use std::boxed::Box;
-use std::ffi::CString;
-use std::marker::PhantomData;
-use std::sync::Arc;
+use crate::sync::{Mutex, MutexGuard};
 
-use rosidl_runtime_rs::{Message, RmwMessage};
+use alloc::borrow::Borrow;
+use alloc::boxed::Box;
+use alloc::ffi::CString;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+#[cfg(feature = "std")]
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
 
-use parking_lot::{Mutex, MutexGuard};
+use rosidl_runtime_rs::{Message, RmwMessage};
 
 /// Internal struct used by subscriptions.
 pub struct SubscriptionHandle {
-    handle: Mutex<rcl_subscription_t>,
-    node_handle: Arc<Mutex<rcl_node_t>>,
+    pub(crate) handle: Mutex<rcl_subscription_t>,
+    pub(crate) node_handle: Arc<NodeHandle>,
+    // Kept alive until the subscription is finalized, since the RMW may hold on to it.
+    pub(crate) _rmw_specific_options: Option<RmwSpecificOptions>,
+    // The read-only parameters that show the QoS overrides, see `QoSOverridingOptions`.
+    pub(crate) _qos_parameters: Vec<ParameterName>,
+    // The priority for the executor, see `Subscription::set_priority`.
+    pub(crate) priority: AtomicI32,
+    // Subscriptions from `Subscription::from_raw` are finalized by their owner.
+    pub(crate) owned: bool,
+    // Set if the subscription receives its messages from a `testing::Loopback`.
+    #[cfg(feature = "testing")]
+    pub(crate) loopback: Option<LoopbackEndpoint>,
 }
 
 impl SubscriptionHandle {
     pub(crate) fn lock(&self) -> MutexGuard<rcl_subscription_t> {
         self.handle.lock()
     }
+
+    pub(crate) fn priority(&self) -> i32 {
+        self.priority.load(Ordering::Relaxed)
+    }
 }
 
 impl Drop for SubscriptionHandle {
     fn drop(&mut self) {
+        if !self.owned {
+            return;
+        }
         let handle = self.handle.get_mut();
         let node_handle = &mut *self.node_handle.lock();
         // SAFETY: No preconditions for this function (besides the arguments being valid).
@@ -36,6 +76,119 @@ impl Drop for SubscriptionHandle {
     }
 }
 
+/// Options for a [`Subscription`], in addition to its QoS profile.
+///
+/// New options may be added in the future, so it is best to create this with the
+/// `..Default::default()` syntax.
+///
+/// # Example
+/// ```
+/// # use rclrs::SubscriptionOptions;
+/// let options = SubscriptionOptions {
+///     ignore_local_publications: true,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SubscriptionOptions {
+    /// If true, messages from publishers of the same node are not received.
+    ///
+    /// This is useful for nodes that publish and subscribe on the same topic, such as relays.
+    /// Not every RMW implementation supports this option.
+    pub ignore_local_publications: bool,
+    /// Options that are passed on to the RMW implementation, see [`RmwSpecificOptions`].
+    pub rmw_specific_options: Option<RmwSpecificOptions>,
+    /// Which policies of the QoS profile can be overridden with parameters, see
+    /// [`QoSOverridingOptions`].
+    pub qos_overriding_options: QoSOverridingOptions,
+    /// What to do if the topic already has another type in the ROS graph, see
+    /// [`TypeMismatchPolicy`].
+    pub type_mismatch_policy: TypeMismatchPolicy,
+    /// A transform of the serialized messages before they are deserialized, see
+    /// [`PayloadTransform`][1].
+    ///
+    /// [1]: crate::PayloadTransform
+    pub payload_middleware: Option<PayloadMiddleware>,
+    /// If true, which is the default, a warning is logged with the [logger of the node][1] when
+    /// a publisher with an incompatible QoS profile is discovered, like in `rclcpp`.
+    ///
+    /// Set this to false when handling [`RequestedIncompatibleQoS`][2] events with
+    /// [`Node::create_subscription_event`][3], to avoid the duplicate warning. In
+    /// [static memory mode][4], the warning is never set up, since it would take up a QoS event
+    /// slot.
+    ///
+    /// [1]: crate::Node::logger_name
+    /// [2]: crate::RequestedIncompatibleQoS
+    /// [3]: crate::Node::create_subscription_event
+    /// [4]: crate::Node::enable_static_memory
+    pub use_default_callbacks: bool,
+}
+
+impl Default for SubscriptionOptions {
+    fn default() -> Self {
+        Self {
+            ignore_local_publications: false,
+            rmw_specific_options: None,
+            qos_overriding_options: QoSOverridingOptions::default(),
+            type_mismatch_policy: TypeMismatchPolicy::default(),
+            payload_middleware: None,
+            use_default_callbacks: true,
+        }
+    }
+}
+
+type SubscriptionCallback<T> = Box<dyn FnMut(T) + 'static>;
+
+// Why a message could not be taken.
+enum TakeError {
+    // The error of taking the message from `rcl`, or of another step before decoding it.
+    Take(RclrsError),
+    // The message was taken, but its payload could not be decoded or deserialized. The message
+    // is lost, but the next one may be fine.
+    Decode(RclrsError),
+}
+
+impl From<RclrsError> for TakeError {
+    fn from(error: RclrsError) -> Self {
+        Self::Take(error)
+    }
+}
+
+impl From<TakeError> for RclrsError {
+    fn from(error: TakeError) -> Self {
+        match error {
+            TakeError::Take(error) | TakeError::Decode(error) => error,
+        }
+    }
+}
+
+// The state of `Subscription::throttled()` and `Subscription::throttled_with_trailing()`.
+#[cfg(feature = "std")]
+struct Throttle<T> {
+    period: Duration,
+    last_callback: Option<Instant>,
+    // The timer that delivers the pending message at the end of the period, only with a trailing
+    // edge.
+    trailing_timer: Option<Arc<crate::Timer>>,
+    // The newest message that was dropped within the period, only with a trailing edge.
+    pending: Option<T>,
+}
+
+#[cfg(feature = "std")]
+impl<T> Throttle<T> {
+    // Returns whether a callback may run now, and if so, starts a new period.
+    fn admit(&mut self) -> bool {
+        let now = Instant::now();
+        match self.last_callback {
+            Some(last_callback) if now.duration_since(last_callback) < self.period => false,
+            _ => {
+                self.last_callback = Some(now);
+                true
+            }
+        }
+    }
+}
+
 /// Trait to be implemented by concrete [`Subscription`]s.
 pub trait SubscriptionBase {
     /// Internal function to get a reference to the `rcl` handle.
@@ -61,7 +214,32 @@ where
 {
     pub(crate) handle: Arc<SubscriptionHandle>,
     /// The callback function that runs when a message was received.
-    pub callback: Mutex<Box<dyn FnMut(T) + 'static>>,
+    pub callback: Mutex<SubscriptionCallback<T>>,
+    // A callback set by `set_callback()` while the callback was running, which replaces it when
+    // it returns.
+    next_callback: Mutex<Option<SubscriptionCallback<T>>>,
+    // Whether `next_callback` is set, so that executing the subscription only locks it then.
+    has_next_callback: AtomicBool,
+    #[cfg(feature = "std")]
+    throttle: Mutex<Option<Throttle<T>>>,
+    // Whether `throttle` is set, so that executing the subscription only locks it then.
+    #[cfg(feature = "std")]
+    is_throttled: AtomicBool,
+    latest_only: AtomicBool,
+    payload_middleware: Option<PayloadMiddleware>,
+    // The logger of the node, for messages that can not be decoded.
+    logger_name: String,
+    // The handler that logs incompatible QoS profiles, see
+    // `SubscriptionOptions::use_default_callbacks`.
+    pub(crate) incompatible_qos_event: Option<Arc<QoSEvent<RequestedIncompatibleQoS>>>,
+    // The handler that counts lost messages, if the node collects statistics.
+    #[cfg(all(feature = "statistics_msgs", not(ros_distro = "foxy")))]
+    pub(crate) message_lost_event: Option<Arc<QoSEvent<crate::MessageLost>>>,
+    // For counting the messages that are dropped by `latest_only` and `throttled`.
+    #[cfg(feature = "statistics_msgs")]
+    node_statistics: Option<Arc<crate::NodeStatistics>>,
+    // The number of matched publishers, which is cached if the node has a graph cache.
+    publisher_count: MatchedCount,
     message: PhantomData<T>,
 }
 
@@ -83,16 +261,69 @@ where
         T: Message,
         F: FnMut(T) + 'static,
     {
-        // SAFETY: Getting a zero-initialized value is always safe.
-        let mut subscription_handle = unsafe { rcl_get_zero_initialized_subscription() };
+        let options = node.entity_defaults.subscription_options.clone();
+        Self::new_with_options(node, topic, qos, options, callback)
+    }
+
+    /// Creates a new subscription with additional options.
+    ///
+    /// # Panics
+    /// When the topic contains interior null bytes.
+    pub fn new_with_options<F>(
+        node: &Node,
+        topic: &str,
+        mut qos: QoSProfile,
+        options: SubscriptionOptions,
+        callback: F,
+    ) -> Result<Self, RclrsError>
+    where
+        T: Message,
+        F: FnMut(T) + 'static,
+    {
+        // The handle is initialized in place, because rcl uses its address to identify the
+        // subscription, e.g. in tracepoints.
+        // This is declared before the node handle guard, since dropping it in the error case
+        // locks the node handle.
+        let topic = node.expand_topic_name(topic)?;
+        let qos_parameters =
+            options
+                .qos_overriding_options
+                .apply(node, &topic, "subscription", &mut qos)?;
+        #[cfg_attr(not(feature = "testing"), allow(unused_variables))]
+        let handle = Arc::new_cyclic(|handle| SubscriptionHandle {
+            // SAFETY: Getting a zero-initialized value is always safe.
+            handle: Mutex::new(unsafe { rcl_get_zero_initialized_subscription() }),
+            node_handle: node.handle.clone(),
+            _rmw_specific_options: options.rmw_specific_options.clone(),
+            _qos_parameters: qos_parameters,
+            priority: AtomicI32::new(0),
+            owned: true,
+            #[cfg(feature = "testing")]
+            loopback: LoopbackEndpoint::new(node, handle, EndpointKind::Subscription, &topic),
+        });
         let type_support =
             <T as Message>::RmwMsg::get_type_support() as *const rosidl_message_type_support_t;
+        node.check_topic_type(
+            &topic,
+            <T as Message>::RmwMsg::TYPE_NAME,
+            options.type_mismatch_policy,
+            "subscription",
+        )?;
         let topic_c_string = CString::new(topic).unwrap();
         let node_handle = &mut *node.handle.lock();
 
         // SAFETY: No preconditions for this function.
         let mut subscription_options = unsafe { rcl_subscription_get_default_options() };
         subscription_options.qos = qos.into();
+        subscription_options.allocator = copy_rcutils_allocator(&node.allocator);
+        subscription_options
+            .rmw_subscription_options
+            .ignore_local_publications = options.ignore_local_publications;
+        if let Some(rmw_specific_options) = &options.rmw_specific_options {
+            subscription_options
+                .rmw_subscription_options
+                .rmw_specific_subscription_payload = rmw_specific_options.as_ptr();
+        }
         unsafe {
             // SAFETY: The subscription handle is zero-initialized as expected by this function.
             // The node handle is kept alive because it is co-owned by the subscription.
@@ -100,7 +331,7 @@ where
             // afterwards.
             // TODO: type support?
             rcl_subscription_init(
-                &mut subscription_handle,
+                &mut *handle.lock(),
                 node_handle,
                 type_support,
                 topic_c_string.as_ptr(),
@@ -109,22 +340,115 @@ where
             .ok()?;
         }
 
+        Ok(Self {
+            handle,
+            callback: Mutex::new(Box::new(callback)),
+            next_callback: Mutex::new(None),
+            has_next_callback: AtomicBool::new(false),
+            #[cfg(feature = "std")]
+            throttle: Mutex::new(None),
+            #[cfg(feature = "std")]
+            is_throttled: AtomicBool::new(false),
+            latest_only: AtomicBool::new(false),
+            payload_middleware: options.payload_middleware,
+            logger_name: node.logger_name(),
+            incompatible_qos_event: None,
+            #[cfg(all(feature = "statistics_msgs", not(ros_distro = "foxy")))]
+            message_lost_event: None,
+            #[cfg(feature = "statistics_msgs")]
+            node_statistics: node.statistics.clone(),
+            publisher_count: MatchedCount::new(node),
+            message: PhantomData,
+        })
+    }
+
+    /// Creates a subscription from an `rcl` subscription that was initialized outside of
+    /// `rclrs`, e.g. by `rclcpp` on a node shared with [`Node::from_raw`].
+    ///
+    /// The subscription needs to be added to its node to be executed, so it is usually created
+    /// with [`Node::create_subscription_from_raw`].
+    ///
+    /// # Safety
+    /// The subscription must be initialized on the given node, for the RMW-native message type
+    /// of `T`. It is not finalized by `rclrs`: Its owner remains responsible for that, and must
+    /// only do it after the returned `Subscription` has been dropped.
+    ///
+    /// The owner must not take messages from the subscription, or wait on it, while `rclrs`
+    /// uses it.
+    ///
+    /// The `rcl_subscription_t` struct is copied, so the pointer does not need to stay valid,
+    /// but the state that it refers to does.
+    pub unsafe fn from_raw<F>(
+        node: &Node,
+        rcl_subscription: *const rcl_subscription_t,
+        callback: F,
+    ) -> Self
+    where
+        F: FnMut(T) + 'static,
+    {
         let handle = Arc::new(SubscriptionHandle {
-            handle: Mutex::new(subscription_handle),
+            handle: Mutex::new(core::ptr::read(rcl_subscription)),
             node_handle: node.handle.clone(),
+            _rmw_specific_options: None,
+            _qos_parameters: Vec::new(),
+            priority: AtomicI32::new(0),
+            owned: false,
+            #[cfg(feature = "testing")]
+            loopback: None,
         });
-
-        Ok(Self {
+        Self {
             handle,
             callback: Mutex::new(Box::new(callback)),
+            next_callback: Mutex::new(None),
+            has_next_callback: AtomicBool::new(false),
+            #[cfg(feature = "std")]
+            throttle: Mutex::new(None),
+            #[cfg(feature = "std")]
+            is_throttled: AtomicBool::new(false),
+            latest_only: AtomicBool::new(false),
+            payload_middleware: None,
+            logger_name: node.logger_name(),
+            incompatible_qos_event: None,
+            #[cfg(all(feature = "statistics_msgs", not(ros_distro = "foxy")))]
+            message_lost_event: None,
+            #[cfg(feature = "statistics_msgs")]
+            node_statistics: node.statistics.clone(),
+            publisher_count: MatchedCount::new(node),
             message: PhantomData,
-        })
+        }
+    }
+
+    /// The ID of the callback in tracepoints, which is the address of the callback field.
+    ///
+    /// It is stable as long as the subscription is not moved, which it is not after being put
+    /// into an `Arc` by [`Node::create_subscription`].
+    pub(crate) fn callback_id(&self) -> *const c_void {
+        &self.callback as *const _ as *const c_void
+    }
+
+    /// Emits the tracepoints that register the subscription and its callback.
+    ///
+    /// This must be called once the subscription is at its final address.
+    #[cfg_attr(not(feature = "tracetools"), allow(unused_variables))]
+    pub(crate) fn trace_init(&self, callback_symbol: &str) {
+        // Only lock the handle and allocate the symbol if the tracepoints are enabled.
+        #[cfg(feature = "tracetools")]
+        {
+            let subscription_id = self as *const Self as *const c_void;
+            let handle = &*self.handle.lock() as *const rcl_subscription_t;
+            tracetools::subscription_init(handle as *const _, subscription_id);
+            tracetools::subscription_callback_added(subscription_id, self.callback_id());
+            let symbol = CString::new(callback_symbol).unwrap_or_default();
+            tracetools::callback_register(self.callback_id(), symbol.as_ptr());
+        }
     }
 
     /// Fetches a new message.
     ///
     /// When there is no new message, this will return a
-    /// [`SubscriptionTakeFailed`][1] wrapped in an [`RclrsError`][2].
+    /// [`SubscriptionTakeFailed`][1] wrapped in an [`RclrsError`][2]. With a
+    /// [`PayloadMiddleware`] in the options, a payload that cannot be decoded is also returned as
+    /// an error.
     ///
     /// [1]: crate::SubscriberErrorCode
     /// [2]: crate::RclrsError
@@ -145,22 +469,420 @@ where
     // +-------------+
     // ```
     pub fn take(&self) -> Result<T, RclrsError> {
+        Ok(self.take_impl(core::ptr::null_mut())?)
+    }
+
+    /// Fetches a new message, together with information about it such as its publisher.
+    ///
+    /// See [`Subscription::take`] for the errors.
+    pub fn take_with_info(&self) -> Result<(T, MessageInfo), RclrsError> {
+        // SAFETY: No preconditions for this function.
+        let mut message_info = unsafe { rmw_get_zero_initialized_message_info() };
+        let msg = self.take_impl(&mut message_info)?;
+        Ok((msg, MessageInfo::from(&message_info)))
+    }
+
+    fn take_impl(&self, message_info: *mut rmw_message_info_t) -> Result<T, TakeError> {
+        #[cfg(feature = "fault_injection")]
+        crate::fault_injection::check(crate::fault_injection::FaultKind::Take, || unsafe {
+            // SAFETY: The subscription is locked, and the name is copied while it is.
+            crate::fault_injection::name_from_ptr(rcl_subscription_get_topic_name(
+                &*self.handle.lock(),
+            ))
+        })?;
         let mut rmw_message = <T as Message>::RmwMsg::default();
+        #[cfg(feature = "testing")]
+        if let Some(loopback) = &self.handle.loopback {
+            let frame = loopback.take().ok_or(RclrsError {
+                code: RclReturnCode::SubscriberError(SubscriberErrorCode::SubscriptionTakeFailed),
+                msg: None,
+            })?;
+            let type_support =
+                <T as Message>::RmwMsg::get_type_support() as *const rosidl_message_type_support_t;
+            let rmw_message_ptr = &mut rmw_message as *mut <T as Message>::RmwMsg as *mut _;
+            match &self.payload_middleware {
+                Some(payload_middleware) => {
+                    payload_middleware.decode(&frame.payload, type_support, rmw_message_ptr)
+                }
+                None => deserialize(&frame.payload, type_support, rmw_message_ptr),
+            }
+            .map_err(TakeError::Decode)?;
+            // SAFETY: The message info is either NULL or valid.
+            if let (Some(info), Some(message_info)) =
+                (&frame.message_info, unsafe { message_info.as_mut() })
+            {
+                message_info.source_timestamp = info.source_timestamp;
+                message_info
+                    .publisher_gid
+                    .data
+                    .copy_from_slice(info.publisher_gid.as_bytes());
+                message_info.from_intra_process = info.from_intra_process;
+            }
+            return Ok(T::from_rmw_message(rmw_message));
+        }
+        if let Some(payload_middleware) = &self.payload_middleware {
+            let mut buffer = SerializedMessageBuffer::new()?;
+            unsafe {
+                // SAFETY: The buffer is initialized, and is resized by the RMW implementation if
+                // needed. The message info is either NULL or valid, and the allocation is
+                // explicitly allowed to be NULL.
+                rcl_take_serialized_message(
+                    &*self.handle.lock(),
+                    &mut buffer.0,
+                    message_info,
+                    core::ptr::null_mut(),
+                )
+            }
+            .ok()?;
+            let type_support =
+                <T as Message>::RmwMsg::get_type_support() as *const rosidl_message_type_support_t;
+            payload_middleware
+                .decode(
+                    buffer.as_slice(),
+                    type_support,
+                    &mut rmw_message as *mut <T as Message>::RmwMsg as *mut _,
+                )
+                .map_err(TakeError::Decode)?;
+            tracetools::take(&rmw_message as *const <T as Message>::RmwMsg as *const _);
+            return Ok(T::from_rmw_message(rmw_message));
+        }
         let handle = &mut *self.handle.lock();
         let ret = unsafe {
             // SAFETY: The first two pointers are valid/initialized, and do not need to be valid
             // beyond the function call.
-            // The latter two pointers are explicitly allowed to be NULL.
+            // The message info is either NULL or valid, and the allocation is explicitly allowed
+            // to be NULL.
             rcl_take(
                 handle,
                 &mut rmw_message as *mut <T as Message>::RmwMsg as *mut _,
-                std::ptr::null_mut(),
-                std::ptr::null_mut(),
+                message_info,
+                core::ptr::null_mut(),
             )
         };
         ret.ok()?;
+        tracetools::take(&rmw_message as *const <T as Message>::RmwMsg as *const _);
         Ok(T::from_rmw_message(rmw_message))
     }
+
+    // Takes a message, or returns `None` if there is none.
+    //
+    // Messages that can not be decoded are logged and skipped, so that a single malformed or
+    // foreign payload on the topic does not end spinning.
+    fn try_take(&self) -> Result<Option<T>, RclrsError> {
+        loop {
+            match self.take_impl(core::ptr::null_mut()) {
+                Ok(msg) => return Ok(Some(msg)),
+                Err(TakeError::Take(RclrsError {
+                    code:
+                        RclReturnCode::SubscriberError(SubscriberErrorCode::SubscriptionTakeFailed),
+                    ..
+                })) => return Ok(None),
+                Err(TakeError::Take(e)) => return Err(e),
+                Err(TakeError::Decode(e)) => {
+                    log(
+                        &self.logger_name,
+                        LogSeverity::Warn,
+                        &format!("Dropping a message that could not be decoded: {e}"),
+                    );
+                    self.record_dropped_messages(1);
+                }
+            }
+        }
+    }
+
+    /// Returns the [REP 2011][1] hash of the message type, or `None` before ROS 2 Iron.
+    ///
+    /// [1]: https://ros.org/reps/rep-2011.html
+    pub fn type_hash(&self) -> Option<TypeHash> {
+        let type_support =
+            <T as Message>::RmwMsg::get_type_support() as *const rosidl_message_type_support_t;
+        // SAFETY: The type support is valid, since it is static data of the message package.
+        unsafe { TypeHash::from_type_support(type_support) }
+    }
+
+    /// Returns the QoS profile that the RMW implementation actually uses for this subscription.
+    ///
+    /// Unlike the profile that the subscription was created with, this has the `SystemDefault`
+    /// policies resolved, so it can be used to verify what the middleware granted. Not every RMW
+    /// implementation resolves every policy, though.
+    pub fn actual_qos(&self) -> Result<QoSProfile, RclrsError> {
+        // SAFETY: No preconditions for this function (besides passing in a valid handle).
+        // The returned profile is owned by the subscription, and only read while the handle is locked.
+        let handle = &*self.handle.lock();
+        unsafe { rcl_subscription_get_actual_qos(handle).as_ref() }
+            .map(QoSProfile::from)
+            .ok_or(RclrsError {
+                code: RclReturnCode::SubscriberError(SubscriberErrorCode::SubscriptionInvalid),
+                msg: None,
+            })
+    }
+
+    /// Returns the number of publishers that are currently matched with this subscription.
+    ///
+    /// The count is cached until the ROS graph changes if the node has a graph cache, see
+    /// [`Node::enable_graph_cache`][1].
+    ///
+    /// [1]: crate::Node::enable_graph_cache
+    pub fn get_publisher_count(&self) -> Result<usize, RclrsError> {
+        self.publisher_count.get_or_query(|| {
+            let mut publisher_count = 0;
+            // SAFETY: No preconditions for this function (besides passing in a valid handle).
+            unsafe {
+                rcl_subscription_get_publisher_count(&*self.handle.lock(), &mut publisher_count)
+                    .ok()?;
+            }
+            Ok(publisher_count)
+        })
+    }
+
+    /// Returns the priority of the subscription for the executor, see
+    /// [`Subscription::set_priority`].
+    pub fn priority(&self) -> i32 {
+        self.handle.priority()
+    }
+
+    /// Sets the priority of the subscription for the executor.
+    ///
+    /// Ready entities of a node with a higher priority are executed before those with a lower
+    /// priority. The [`DispatchPolicy`][1] of the node determines whether the lower priorities are
+    /// executed in the same wakeup. The default priority of subscriptions, timers and services is
+    /// 0, and negative priorities can be used to execute an entity after the others.
+    ///
+    /// [1]: crate::DispatchPolicy
+    pub fn set_priority(&self, priority: i32) {
+        self.handle.priority.store(priority, Ordering::Relaxed);
+    }
+
+    /// Replaces the callback that runs when a message was received.
+    ///
+    /// The subscription stays registered with the middleware, so this is cheaper than recreating
+    /// it, e.g. for switching between modes. When called from the callback of this subscription,
+    /// the new callback is used from the next message on.
+    pub fn set_callback<F>(&self, callback: F)
+    where
+        F: FnMut(T) + 'static,
+    {
+        match self.callback.try_lock() {
+            Some(mut current) => *current = Box::new(callback),
+            None => {
+                *self.next_callback.lock() = Some(Box::new(callback));
+                self.has_next_callback.store(true, Ordering::Release);
+            }
+        }
+    }
+
+    /// Replaces the callback with one that sends the messages into a bounded channel, for
+    /// processing them on another thread.
+    ///
+    /// The policy decides what happens when the receiver falls behind and the channel is full,
+    /// see [`BackpressurePolicy`][1]. The subscription must be kept, and its node spun, for
+    /// messages to arrive. When the subscription is dropped or its callback is replaced, the
+    /// receiver gets the remaining messages, and then [`ChannelError::Disconnected`][2]. Like for
+    /// [`channel`][3], the receiver wakes up with [`ChannelError::Shutdown`][4] when the context
+    /// is shut down.
+    ///
+    /// # Example
+    /// ```ignore
+    /// # use rclrs::*;
+    /// let subscription = node.create_subscription(
+    ///     "image",
+    ///     QOS_PROFILE_SENSOR_DATA,
+    ///     |_: sensor_msgs::msg::Image| {},
+    /// )?;
+    /// let images = subscription.into_channel(&context, 4, BackpressurePolicy::DropOldest);
+    /// let worker = std::thread::spawn(move || {
+    ///     while let Ok(image) = images.recv() {
+    ///         detect_objects(image);
+    ///     }
+    /// });
+    /// rclrs::spin(&node)?;
+    /// ```
+    ///
+    /// # Panics
+    /// When the capacity is 0.
+    ///
+    /// [1]: crate::BackpressurePolicy
+    /// [2]: crate::ChannelError::Disconnected
+    /// [3]: crate::channel
+    /// [4]: crate::ChannelError::Shutdown
+    #[cfg(feature = "std")]
+    pub fn into_channel(
+        &self,
+        context: &crate::Context,
+        capacity: usize,
+        policy: crate::BackpressurePolicy,
+    ) -> crate::Receiver<T>
+    where
+        T: Send,
+    {
+        let (sender, receiver) = crate::channel(context, capacity);
+        self.set_callback(move |msg| {
+            // The only errors are a dropped receiver, in which case the message is not needed.
+            let _ = sender.send_with_policy(msg, policy);
+        });
+        receiver
+    }
+
+    /// Limits the callback to running at most once per period, for high-rate topics whose
+    /// processing is slower than the publisher.
+    ///
+    /// The messages that arrive within the period after a callback are taken and dropped, so that
+    /// they do not pile up in the middleware. Combined with [`Subscription::latest_only`], the
+    /// callback that runs after the period receives the newest message. A period of zero removes
+    /// the limit.
+    ///
+    /// The period is measured in steady time, independently of the ROS time of the node.
+    ///
+    /// # Example
+    /// ```ignore
+    /// # use rclrs::*;
+    /// # use std::time::Duration;
+    /// let subscription = node.create_subscription(
+    ///     "image",
+    ///     QOS_PROFILE_SENSOR_DATA,
+    ///     |msg: sensor_msgs::msg::Image| detect_objects(msg),
+    /// )?;
+    /// subscription.throttled(Duration::from_millis(100)).latest_only(true);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn throttled(&self, period: Duration) -> &Self {
+        let throttle = &mut *self.throttle.lock();
+        *throttle = (!period.is_zero()).then_some(Throttle {
+            period,
+            last_callback: None,
+            trailing_timer: None,
+            pending: None,
+        });
+        self.is_throttled
+            .store(throttle.is_some(), Ordering::Relaxed);
+        self
+    }
+
+    /// Like [`Subscription::throttled`], but the newest message that arrived within a period is
+    /// delivered at the end of the period, instead of being dropped.
+    ///
+    /// This is a throttle with a trailing edge, also known as a debounce: the callback still runs
+    /// at most once per period, but the last message of a burst is never lost, e.g. the final
+    /// goal of a user dragging a marker in RViz. The end of the period is waited for with a timer
+    /// on the node, which is why the node is needed. The older messages within a period are
+    /// dropped.
+    ///
+    /// Returns an [`InvalidArgument`][1] error if the period is zero. See
+    /// [`Node::create_timer`] for the other errors.
+    ///
+    /// # Example
+    /// ```ignore
+    /// # use rclrs::*;
+    /// # use std::time::Duration;
+    /// let subscription = node.create_subscription(
+    ///     "goal_pose",
+    ///     QOS_PROFILE_DEFAULT,
+    ///     |msg: geometry_msgs::msg::PoseStamped| plan_path(msg),
+    /// )?;
+    /// subscription.throttled_with_trailing(&mut node, Duration::from_millis(200))?;
+    /// ```
+    ///
+    /// [1]: crate::RclReturnCode::InvalidArgument
+    #[cfg(feature = "std")]
+    pub fn throttled_with_trailing(
+        self: &Arc<Self>,
+        node: &mut Node,
+        period: Duration,
+    ) -> Result<&Self, RclrsError>
+    where
+        T: 'static,
+    {
+        let subscription = Arc::downgrade(self);
+        let timer = node.create_timer(period, move || {
+            if let Some(subscription) = subscription.upgrade() {
+                subscription.execute_trailing();
+            }
+        })?;
+        // The timer is only running while a message may be pending.
+        timer.cancel()?;
+        let throttle = &mut *self.throttle.lock();
+        *throttle = Some(Throttle {
+            period,
+            last_callback: None,
+            trailing_timer: Some(timer),
+            pending: None,
+        });
+        self.is_throttled.store(true, Ordering::Relaxed);
+        Ok(self)
+    }
+
+    // Delivers the pending message of the throttle at the end of its period, see
+    // `Subscription::throttled_with_trailing`.
+    #[cfg(feature = "std")]
+    fn execute_trailing(&self) {
+        let msg = {
+            let Some(throttle) = &mut *self.throttle.lock() else {
+                return;
+            };
+            match throttle.pending.take() {
+                Some(msg) => {
+                    throttle.last_callback = Some(Instant::now());
+                    msg
+                }
+                None => {
+                    // No message arrived within the period, so the next one is a leading edge.
+                    if let Some(timer) = &throttle.trailing_timer {
+                        let _ = timer.cancel();
+                    }
+                    return;
+                }
+            }
+        };
+        self.run_callback(msg);
+    }
+
+    /// Sets whether the queued messages are coalesced, so that the callback only receives the
+    /// newest one.
+    ///
+    /// When enabled, all messages that have arrived since the last execution are taken when the
+    /// subscription is executed, and the older ones are dropped. This keeps a slow callback from
+    /// working through a backlog of outdated messages, even with a deep QoS history. It is
+    /// disabled by default.
+    pub fn latest_only(&self, latest_only: bool) -> &Self {
+        self.latest_only.store(latest_only, Ordering::Relaxed);
+        self
+    }
+
+    fn run_callback(&self, msg: T) {
+        let callback = &mut *self.callback.lock();
+        let callback_id = self.callback_id();
+        tracetools::callback_start(callback_id);
+        callback(msg);
+        tracetools::callback_end(callback_id);
+        if self.has_next_callback.swap(false, Ordering::Acquire) {
+            if let Some(next_callback) = self.next_callback.lock().take() {
+                *callback = next_callback;
+            }
+        }
+    }
+
+    fn record_dropped_messages(&self, dropped_messages: usize) {
+        #[cfg(feature = "statistics_msgs")]
+        if let Some(statistics) = self
+            .node_statistics
+            .as_ref()
+            .filter(|_| dropped_messages > 0)
+        {
+            statistics.record_dropped_messages(dropped_messages);
+        }
+        #[cfg(not(feature = "statistics_msgs"))]
+        let _ = dropped_messages;
+    }
+
+    /// Returns a pointer to the underlying `rcl` subscription, for calling functions that are not
+    /// wrapped by `rclrs`.
+    ///
+    /// # Safety
+    /// See [`Node::raw_handle`]. The subscription must not be finalized, and messages must not be
+    /// taken through the pointer while the node is spinning.
+    pub unsafe fn raw_handle(&self) -> *mut rcl_subscription_t {
+        &mut *self.handle.lock()
+    }
 }
 
 impl<T> SubscriptionBase for Subscription<T>
@@ -172,19 +894,48 @@ where
     }
 
     fn execute(&self) -> Result<(), RclrsError> {
-        let msg = match self.take() {
-            Ok(msg) => msg,
-            Err(RclrsError {
-                code: RclReturnCode::SubscriberError(SubscriberErrorCode::SubscriptionTakeFailed),
-                ..
-            }) => {
-                // Spurious wakeup – this may happen even when a waitset indicated that this
-                // subscription was ready, so it shouldn't be an error.
-                return Ok(());
-            }
-            Err(e) => return Err(e),
+        // Only lock the handle for getting its address if the tracepoint is enabled.
+        #[cfg(feature = "tracetools")]
+        tracetools::executor_execute(&*self.handle.lock() as *const _ as *const _);
+        let mut msg = match self.try_take()? {
+            Some(msg) => msg,
+            // Spurious wakeup – this may happen even when a waitset indicated that this
+            // subscription was ready, so it shouldn't be an error.
+            None => return Ok(()),
         };
-        (*self.callback.lock())(msg);
+        let mut dropped_messages = 0;
+        if self.latest_only.load(Ordering::Relaxed) {
+            while let Some(newer) = self.try_take()? {
+                msg = newer;
+                dropped_messages += 1;
+            }
+        }
+        #[cfg(feature = "std")]
+        if self.is_throttled.load(Ordering::Relaxed) {
+            if let Some(throttle) = &mut *self.throttle.lock() {
+                if !throttle.admit() {
+                    let Some(timer) = &throttle.trailing_timer else {
+                        self.record_dropped_messages(dropped_messages + 1);
+                        return Ok(());
+                    };
+                    // The message is kept for the end of the period, replacing an older one.
+                    if throttle.pending.replace(msg).is_some() {
+                        dropped_messages += 1;
+                    }
+                    self.record_dropped_messages(dropped_messages);
+                    if timer.is_canceled()? {
+                        timer.reset()?;
+                    }
+                    return Ok(());
+                }
+                // A leading edge starts a new period, at whose end the timer fires.
+                if let Some(timer) = &throttle.trailing_timer {
+                    timer.reset()?;
+                }
+            }
+        }
+        self.record_dropped_messages(dropped_messages);
+        self.run_callback(msg);
         Ok(())
     }
 }
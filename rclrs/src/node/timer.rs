@@ -0,0 +1,361 @@
+use crate::allocator::copy_rcutils_allocator;
+use crate::distro::timer_init;
+use crate::error::{RclReturnCode, RclrsError, TimerErrorCode, ToResult};
+use crate::rcl_bindings::*;
+use crate::tracetools;
+use crate::{Clock, ContextHandle, Node};
+
+use crate::sync::{Mutex, MutexGuard};
+
+use alloc::boxed::Box;
+#[cfg(feature = "tracetools")]
+use alloc::ffi::CString;
+use alloc::sync::Arc;
+use core::borrow::Borrow;
+use core::ffi::c_void;
+use core::sync::atomic::{AtomicI32, Ordering};
+use core::time::Duration;
+
+/// Internal struct used by timers.
+pub struct TimerHandle {
+    handle: Mutex<rcl_timer_t>,
+    // The timer stores a pointer to the clock, which the clock keeps at a stable address.
+    clock: Clock,
+    // Used to ensure the context is alive while the timer is alive.
+    _context_handle: Arc<ContextHandle>,
+    // The priority for the executor, see `Subscription::set_priority`.
+    priority: AtomicI32,
+}
+
+impl TimerHandle {
+    pub(crate) fn lock(&self) -> MutexGuard<rcl_timer_t> {
+        self.handle.lock()
+    }
+
+    pub(crate) fn priority(&self) -> i32 {
+        self.priority.load(Ordering::Relaxed)
+    }
+
+    // Returns the current time of the timer's clock.
+    fn now(&self) -> Result<i64, RclrsError> {
+        Ok(self.clock.now()?.nsec)
+    }
+}
+
+impl Drop for TimerHandle {
+    fn drop(&mut self) {
+        let handle = self.handle.get_mut();
+        // SAFETY: No preconditions for this function (besides passing in a valid handle).
+        // The clock is only dropped afterwards, since it is a field of the handle.
+        unsafe {
+            rcl_timer_fini(handle);
+        }
+    }
+}
+
+/// Trait to be implemented by concrete [`Timer`]s.
+pub trait TimerBase {
+    /// Internal function to get a reference to the `rcl` handle.
+    fn handle(&self) -> &TimerHandle;
+    /// Runs the callback if the timer has not been canceled.
+    fn execute(&self) -> Result<(), RclrsError>;
+}
+
+type OverrunCallback = Box<dyn FnMut(Duration) + 'static>;
+
+/// Statistics of a [`Timer`], for tuning the periods and callbacks of real-time nodes.
+///
+/// See [`Timer::statistics`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct TimerStatistics {
+    /// The number of times the callback was called.
+    pub calls: u64,
+    /// The number of times the callback ran longer than the period, see [`Timer::on_overrun`].
+    pub overruns: u64,
+    /// How much longer than the period the time between the last two calls was.
+    ///
+    /// This includes the time that the executor was busy with other callbacks.
+    pub last_jitter: Duration,
+    /// The largest jitter of all calls.
+    pub max_jitter: Duration,
+    /// How long the last callback ran.
+    pub last_callback_duration: Duration,
+    /// How long the longest callback ran.
+    pub max_callback_duration: Duration,
+}
+
+/// Struct for running a callback periodically.
+///
+/// Timers created with [`Node::create_timer`] use the steady clock of the system, i.e. they are
+/// not affected by simulated time. Timers created with [`Node::create_timer_with_clock`] follow
+/// the given clock instead, e.g. the [ROS time][3] of the node.
+///
+/// Like for subscriptions, running the callback requires calling [`spin_once`][1] or
+/// [`spin`][2] on the timer's node. The callback is therefore called _at the earliest_ when the
+/// period has elapsed, and later when the node is busy with other callbacks.
+///
+/// Create a timer with [`Node::create_timer`], or with [`Node::create_publisher_timer`] for the
+/// common case of publishing a message periodically.
+///
+/// [1]: crate::spin_once
+/// [2]: crate::spin
+/// [3]: crate::ClockType::RosTime
+pub struct Timer {
+    pub(crate) handle: Arc<TimerHandle>,
+    pub(crate) callback: Mutex<Box<dyn FnMut() -> Result<(), RclrsError> + 'static>>,
+    statistics: Mutex<TimerStatistics>,
+    overrun_callback: Mutex<Option<OverrunCallback>>,
+}
+
+impl Timer {
+    /// Creates a new timer.
+    ///
+    /// Returns an [`InvalidArgument`][1] error if the period is zero, or so large as to overflow
+    /// an `i64` with its nanosecond representation.
+    ///
+    /// [1]: crate::RclReturnCode::InvalidArgument
+    pub(crate) fn new<F>(
+        node: &Node,
+        period: Duration,
+        clock: Clock,
+        callback: F,
+    ) -> Result<Self, RclrsError>
+    where
+        F: FnMut() -> Result<(), RclrsError> + 'static,
+    {
+        let period_ns = match i64::try_from(period.as_nanos()) {
+            Ok(ns) if ns > 0 => ns,
+            _ => {
+                return Err(RclrsError {
+                    code: RclReturnCode::InvalidArgument,
+                    msg: None,
+                })
+            }
+        };
+        // The handle is initialized in place, because the timer stores the address of the clock.
+        let handle = Arc::new(TimerHandle {
+            // SAFETY: Getting a zero-initialized value is always safe.
+            handle: Mutex::new(unsafe { rcl_get_zero_initialized_timer() }),
+            clock,
+            _context_handle: node.context.clone(),
+            priority: AtomicI32::new(0),
+        });
+        {
+            let allocator = copy_rcutils_allocator(&node.allocator);
+            let context_handle = &mut *node.context.lock();
+            let clock = &mut *handle.clock.lock();
+            unsafe {
+                // SAFETY: The timer handle is zero-initialized as expected by this function.
+                // The clock and the context are kept alive because they are co-owned by the timer.
+                // The rcl callback is NULL, since the callback is called by rclrs instead.
+                timer_init(
+                    &mut *handle.lock(),
+                    clock,
+                    context_handle,
+                    period_ns,
+                    allocator,
+                )
+                .ok()?;
+            }
+        }
+
+        Ok(Self {
+            handle,
+            callback: Mutex::new(Box::new(callback)),
+            statistics: Mutex::new(TimerStatistics::default()),
+            overrun_callback: Mutex::new(None),
+        })
+    }
+
+    /// The ID of the callback in tracepoints, which is the address of the callback field.
+    ///
+    /// It is stable as long as the timer is not moved, which it is not after being put into an
+    /// `Arc` by [`Node::create_timer`].
+    pub(crate) fn callback_id(&self) -> *const c_void {
+        &self.callback as *const _ as *const c_void
+    }
+
+    /// Emits the tracepoints that register the timer and its callback with its node.
+    ///
+    /// This must be called once the timer is at its final address. `rcl` itself has already
+    /// emitted the `rcl_timer_init` tracepoint when the timer was created.
+    #[cfg_attr(not(feature = "tracetools"), allow(unused_variables))]
+    pub(crate) fn trace_init(&self, node: &Node, callback_symbol: &str) {
+        // Only lock the handles and allocate the symbol if the tracepoints are enabled.
+        #[cfg(feature = "tracetools")]
+        {
+            let handle = &*self.handle.lock() as *const rcl_timer_t;
+            tracetools::timer_callback_added(handle as *const _, self.callback_id());
+            let node_handle = &*node.handle.lock() as *const rcl_node_t;
+            tracetools::timer_link_node(handle as *const _, node_handle as *const _);
+            let symbol = CString::new(callback_symbol).unwrap_or_default();
+            tracetools::callback_register(self.callback_id(), symbol.as_ptr());
+        }
+    }
+
+    /// Returns the statistics of the calls since the timer was created.
+    pub fn statistics(&self) -> TimerStatistics {
+        *self.statistics.lock()
+    }
+
+    /// Sets a callback that is called after the timer's callback ran longer than the period.
+    ///
+    /// The overrun callback receives how long the timer's callback ran. This replaces a
+    /// previously set overrun callback. Overruns are always counted in the [`TimerStatistics`].
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, RclrsError};
+    /// # use std::time::Duration;
+    /// let context = Context::new([])?;
+    /// let mut node = context.create_node("my_node")?;
+    /// let timer = node.create_timer(Duration::from_millis(10), || {
+    ///     std::thread::sleep(Duration::from_millis(20));
+    /// })?;
+    /// timer.on_overrun(|duration| eprintln!("The control loop took {duration:?}"));
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn on_overrun<F>(&self, callback: F)
+    where
+        F: FnMut(Duration) + 'static,
+    {
+        *self.overrun_callback.lock() = Some(Box::new(callback));
+    }
+
+    // Updates the statistics after a call, and returns whether the callback overran the period.
+    fn update_statistics(
+        &self,
+        period: Duration,
+        since_last_call: Duration,
+        callback_duration: Duration,
+    ) -> bool {
+        let statistics = &mut *self.statistics.lock();
+        let jitter = since_last_call.saturating_sub(period);
+        let overrun = callback_duration > period;
+        statistics.calls += 1;
+        statistics.overruns += u64::from(overrun);
+        statistics.last_jitter = jitter;
+        statistics.max_jitter = statistics.max_jitter.max(jitter);
+        statistics.last_callback_duration = callback_duration;
+        statistics.max_callback_duration = statistics.max_callback_duration.max(callback_duration);
+        overrun
+    }
+
+    /// Cancels the timer.
+    ///
+    /// A canceled timer does not run its callback anymore, until it is [reset][1].
+    ///
+    /// [1]: Timer::reset
+    pub fn cancel(&self) -> Result<(), RclrsError> {
+        // SAFETY: No preconditions for this function (besides passing in a valid handle).
+        unsafe { rcl_timer_cancel(&mut *self.handle.lock()) }.ok()
+    }
+
+    /// Returns true if the timer has been canceled.
+    pub fn is_canceled(&self) -> Result<bool, RclrsError> {
+        let mut is_canceled = false;
+        // SAFETY: No preconditions for this function (besides passing in a valid handle).
+        unsafe { rcl_timer_is_canceled(&*self.handle.lock(), &mut is_canceled) }.ok()?;
+        Ok(is_canceled)
+    }
+
+    /// Restarts the period of the timer from now, and undoes a [cancel][1].
+    ///
+    /// [1]: Timer::cancel
+    pub fn reset(&self) -> Result<(), RclrsError> {
+        // SAFETY: No preconditions for this function (besides passing in a valid handle).
+        unsafe { rcl_timer_reset(&mut *self.handle.lock()) }.ok()
+    }
+
+    /// Returns the period of the timer.
+    pub fn period(&self) -> Result<Duration, RclrsError> {
+        let mut period_ns = 0;
+        // SAFETY: No preconditions for this function (besides passing in a valid handle).
+        unsafe { rcl_timer_get_period(&*self.handle.lock(), &mut period_ns) }.ok()?;
+        Ok(nanoseconds_to_duration(period_ns))
+    }
+
+    /// Returns the time until the timer is ready.
+    ///
+    /// This is zero when the timer is already overdue.
+    pub fn time_until_next_call(&self) -> Result<Duration, RclrsError> {
+        let mut time_ns = 0;
+        // SAFETY: No preconditions for this function (besides passing in a valid handle).
+        unsafe { rcl_timer_get_time_until_next_call(&*self.handle.lock(), &mut time_ns) }.ok()?;
+        Ok(nanoseconds_to_duration(time_ns))
+    }
+
+    /// Returns the priority of the timer for the executor.
+    ///
+    /// See [`Subscription::set_priority`][1].
+    ///
+    /// [1]: crate::Subscription::set_priority
+    pub fn priority(&self) -> i32 {
+        self.handle.priority()
+    }
+
+    /// Sets the priority of the timer for the executor.
+    ///
+    /// See [`Subscription::set_priority`][1].
+    ///
+    /// [1]: crate::Subscription::set_priority
+    pub fn set_priority(&self, priority: i32) {
+        self.handle.priority.store(priority, Ordering::Relaxed);
+    }
+
+    /// Returns the time since the callback was last called, or since the timer was created or
+    /// reset.
+    pub fn time_since_last_call(&self) -> Result<Duration, RclrsError> {
+        let mut time_ns = 0;
+        // SAFETY: No preconditions for this function (besides passing in a valid handle).
+        unsafe { rcl_timer_get_time_since_last_call(&*self.handle.lock(), &mut time_ns) }.ok()?;
+        Ok(nanoseconds_to_duration(time_ns))
+    }
+}
+
+impl TimerBase for Timer {
+    fn handle(&self) -> &TimerHandle {
+        self.handle.borrow()
+    }
+
+    fn execute(&self) -> Result<(), RclrsError> {
+        // Read before the call, which resets the time since the last call.
+        let period = self.period()?;
+        let since_last_call = self.time_since_last_call()?;
+        // This updates the time of the last call, which makes the timer not ready anymore.
+        // SAFETY: No preconditions for this function (besides passing in a valid handle).
+        match unsafe { rcl_timer_call(&mut *self.handle.lock()) }.ok() {
+            Ok(()) => {}
+            Err(RclrsError {
+                code: RclReturnCode::TimerError(TimerErrorCode::TimerCanceled),
+                ..
+            }) => {
+                // The timer was canceled after the wait set indicated that it was ready, so it
+                // shouldn't be an error.
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        }
+        let start = self.handle.now()?;
+        let result = {
+            let callback = &mut *self.callback.lock();
+            let callback_id = self.callback_id();
+            tracetools::callback_start(callback_id);
+            let result = callback();
+            tracetools::callback_end(callback_id);
+            result
+        };
+        let callback_duration = nanoseconds_to_duration(self.handle.now()? - start);
+        if self.update_statistics(period, since_last_call, callback_duration) {
+            if let Some(overrun_callback) = &mut *self.overrun_callback.lock() {
+                overrun_callback(callback_duration);
+            }
+        }
+        result
+    }
+}
+
+// Negative values, e.g. for an overdue timer, are clamped to zero.
+fn nanoseconds_to_duration(ns: i64) -> Duration {
+    Duration::from_nanos(u64::try_from(ns).unwrap_or(0))
+}
@@ -0,0 +1,290 @@
+use crate::future::{promise, RclFuture};
+use crate::node::chunked::{publisher_id, MultiArrayLayout, UInt8MultiArray};
+use crate::node::payload_transform::{deserialize, serialize};
+use crate::qos::QoSProfile;
+use crate::rcl_bindings::*;
+use crate::sync::Mutex;
+use crate::{MessageCow, Node, Publisher, RclrsError, Subscription};
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+
+use rosidl_runtime_rs::{Message, RmwMessage, Sequence};
+
+// Each request and response starts with a header of little-endian integers: the ID of the client
+// (u64) and the sequence number of the request (i64), which together correlate a response with
+// its request.
+const HEADER_SIZE: usize = 16;
+
+type ResponseCallbacks<T> = Mutex<BTreeMap<i64, Box<dyn FnOnce(T) + 'static>>>;
+
+// The names of the request and response topics of a topic service.
+fn topic_names(service_name: &str) -> (String, String) {
+    let service_name = service_name.trim_end_matches('/');
+    (
+        format!("{service_name}/request"),
+        format!("{service_name}/response"),
+    )
+}
+
+// Serializes a message, with the header in front of it.
+fn encode<'a, T: Message>(
+    client_id: u64,
+    sequence_number: i64,
+    message: impl MessageCow<'a, T>,
+) -> Result<UInt8MultiArray, RclrsError> {
+    let buffer = message.into_cow().with_rmw_message(|rmw_message| {
+        serialize(
+            rmw_message as *const <T as Message>::RmwMsg as *const _,
+            <T as Message>::RmwMsg::get_type_support() as *const rosidl_message_type_support_t,
+        )
+    })?;
+    let payload = buffer.as_slice();
+    let mut data = Vec::with_capacity(HEADER_SIZE + payload.len());
+    data.extend_from_slice(&client_id.to_le_bytes());
+    data.extend_from_slice(&sequence_number.to_le_bytes());
+    data.extend_from_slice(payload);
+    Ok(UInt8MultiArray {
+        layout: MultiArrayLayout {
+            dim: Sequence::default(),
+            data_offset: HEADER_SIZE as u32,
+        },
+        data: Sequence::from(data),
+    })
+}
+
+// Returns the client ID and the sequence number of a request or response, and its payload, or
+// `None` if it is too short.
+fn read_header(data: &[u8]) -> Option<(u64, i64, &[u8])> {
+    if data.len() < HEADER_SIZE {
+        return None;
+    }
+    let client_id = u64::from_le_bytes(data[..8].try_into().unwrap());
+    let sequence_number = i64::from_le_bytes(data[8..HEADER_SIZE].try_into().unwrap());
+    Some((client_id, sequence_number, &data[HEADER_SIZE..]))
+}
+
+fn decode<T: Message>(payload: &[u8]) -> Option<T> {
+    let mut rmw_message = <T as Message>::RmwMsg::default();
+    deserialize(
+        payload,
+        <T as Message>::RmwMsg::get_type_support() as *const rosidl_message_type_support_t,
+        &mut rmw_message as *mut <T as Message>::RmwMsg as *mut _,
+    )
+    .ok()?;
+    Some(T::from_rmw_message(rmw_message))
+}
+
+/// The client side of the request/response pattern over topics, for middlewares or networks
+/// where services are unreliable.
+///
+/// This has the same API as a [`Client`][1], but the requests and responses are published on the
+/// topics `<service_name>/request` and `<service_name>/response`, and answered by a
+/// [`TopicService`]. The request and response types are ordinary message types, since no
+/// service type is needed. Each message is serialized, and published as a
+/// `std_msgs/msg/UInt8MultiArray` after a header of 16 bytes, which contains the ID of the client
+/// and the sequence number of the request as little-endian `u64` and `i64`. The service copies the
+/// header into the response, so that each client only runs the callbacks for its own requests.
+///
+/// Unlike with services, a request is not retried when it is lost, and is answered by every
+/// topic service on the topics. The topics should usually use the reliable QoS policy.
+///
+/// Create a topic client with [`Node::create_topic_client`][2].
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let _service = node.create_topic_service(
+///     "add",
+///     QOS_PROFILE_SERVICES_DEFAULT,
+///     |request: example_interfaces::msg::Int64MultiArray| example_interfaces::msg::Int64 {
+///         data: request.data.iter().sum(),
+///     },
+/// )?;
+/// let client = node.create_topic_client::<
+///     example_interfaces::msg::Int64MultiArray,
+///     example_interfaces::msg::Int64,
+/// >("add", QOS_PROFILE_SERVICES_DEFAULT)?;
+/// client.async_send_request_with_callback(&request, |sum| println!("Sum: {}", sum.data))?;
+/// ```
+///
+/// [1]: crate::Client
+/// [2]: crate::Node::create_topic_client
+pub struct TopicClient<TReq: Message, TRes: Message> {
+    publisher: Publisher<UInt8MultiArray>,
+    subscription: Arc<Subscription<UInt8MultiArray>>,
+    client_id: u64,
+    sequence_number: AtomicI64,
+    callbacks: Arc<ResponseCallbacks<TRes>>,
+    request: PhantomData<TReq>,
+}
+
+impl<TReq: Message, TRes: Message> TopicClient<TReq, TRes> {
+    pub(crate) fn new(
+        node: &mut Node,
+        service_name: &str,
+        qos: QoSProfile,
+    ) -> Result<Self, RclrsError> {
+        let (request_topic, response_topic) = topic_names(service_name);
+        let publisher = node.create_publisher::<UInt8MultiArray>(&request_topic, qos)?;
+        let client_id = publisher_id(publisher.gid().as_bytes());
+        let callbacks: Arc<ResponseCallbacks<TRes>> = Arc::new(Mutex::new(BTreeMap::new()));
+        let subscription_callbacks = Arc::clone(&callbacks);
+        let subscription =
+            node.create_subscription(&response_topic, qos, move |msg: UInt8MultiArray| {
+                let Some((id, sequence_number, payload)) = read_header(&msg.data) else {
+                    return;
+                };
+                if id != client_id {
+                    return;
+                }
+                // The callback is removed before running it, so that it can send new requests.
+                let callback = subscription_callbacks.lock().remove(&sequence_number);
+                if let (Some(callback), Some(response)) = (callback, decode::<TRes>(payload)) {
+                    callback(response);
+                }
+            })?;
+        Ok(Self {
+            publisher,
+            subscription,
+            client_id,
+            sequence_number: AtomicI64::new(1),
+            callbacks,
+            request: PhantomData,
+        })
+    }
+
+    /// Sends a request and runs the callback with the response when it arrives.
+    ///
+    /// Like [`Publisher::publish`][1], this accepts the request by value or by reference.
+    ///
+    /// Returns the sequence number of the request.
+    ///
+    /// [1]: crate::Publisher::publish
+    pub fn async_send_request_with_callback<'a, R, F>(
+        &self,
+        request: R,
+        callback: F,
+    ) -> Result<i64, RclrsError>
+    where
+        R: MessageCow<'a, TReq>,
+        F: FnOnce(TRes) + 'static,
+    {
+        let sequence_number = self.sequence_number.fetch_add(1, Ordering::Relaxed);
+        let msg = encode::<TReq>(self.client_id, sequence_number, request)?;
+        // The callback is stored before sending, so that it is there when the response arrives.
+        self.callbacks
+            .lock()
+            .insert(sequence_number, Box::new(callback));
+        if let Err(e) = self.publisher.publish(msg) {
+            self.callbacks.lock().remove(&sequence_number);
+            return Err(e);
+        }
+        Ok(sequence_number)
+    }
+
+    /// Sends a request and returns a future for the response.
+    ///
+    /// See [`TopicClient::async_send_request_with_callback`].
+    pub fn call_async<'a, R>(&self, request: R) -> Result<RclFuture<TRes>, RclrsError>
+    where
+        R: MessageCow<'a, TReq>,
+    {
+        let (promise, future) = promise();
+        self.async_send_request_with_callback(request, move |response| promise.set(response))?;
+        Ok(future)
+    }
+
+    /// Returns the number of requests that have been sent, but whose response has not been
+    /// received yet.
+    ///
+    /// Since lost requests are not retried, this also counts the requests that will never be
+    /// answered, see [`TopicClient::cancel_pending_requests`].
+    pub fn pending_requests(&self) -> usize {
+        self.callbacks.lock().len()
+    }
+
+    /// Forgets the requests that have not been answered yet, e.g. after a timeout, so that their
+    /// callbacks do not run.
+    pub fn cancel_pending_requests(&self) {
+        self.callbacks.lock().clear();
+    }
+
+    /// Checks if there is a topic service for this client, i.e. if both topics are matched.
+    pub fn service_is_ready(&self) -> Result<bool, RclrsError> {
+        Ok(self.publisher.get_subscription_count()? > 0
+            && self.subscription.get_publisher_count()? > 0)
+    }
+}
+
+/// The service side of the request/response pattern over topics, which answers the requests of
+/// [`TopicClient`]s.
+///
+/// The callback receives each request, and its response is published with the header of the
+/// request. See [`TopicClient`] for the topics and the format of the messages. Requests that
+/// can not be deserialized, and responses that can not be published, are counted in
+/// [`TopicService::dropped_requests`].
+///
+/// Create a topic service with [`Node::create_topic_service`][1].
+///
+/// [1]: crate::Node::create_topic_service
+pub struct TopicService<TReq: Message, TRes: Message> {
+    subscription: Arc<Subscription<UInt8MultiArray>>,
+    dropped: Arc<AtomicU64>,
+    message: PhantomData<(TReq, TRes)>,
+}
+
+impl<TReq: Message, TRes: Message> TopicService<TReq, TRes> {
+    pub(crate) fn new<F>(
+        node: &mut Node,
+        service_name: &str,
+        qos: QoSProfile,
+        mut callback: F,
+    ) -> Result<Self, RclrsError>
+    where
+        F: FnMut(TReq) -> TRes + 'static,
+    {
+        let (request_topic, response_topic) = topic_names(service_name);
+        let publisher = node.create_publisher::<UInt8MultiArray>(&response_topic, qos)?;
+        let dropped = Arc::new(AtomicU64::new(0));
+        let callback_dropped = Arc::clone(&dropped);
+        let subscription =
+            node.create_subscription(&request_topic, qos, move |msg: UInt8MultiArray| {
+                let request = read_header(&msg.data).and_then(|(client_id, seq, payload)| {
+                    Some((client_id, seq, decode::<TReq>(payload)?))
+                });
+                let Some((client_id, sequence_number, request)) = request else {
+                    callback_dropped.fetch_add(1, Ordering::Relaxed);
+                    return;
+                };
+                let response = callback(request);
+                let published = encode::<TRes>(client_id, sequence_number, response)
+                    .and_then(|msg| publisher.publish(msg));
+                if published.is_err() {
+                    callback_dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            })?;
+        Ok(Self {
+            subscription,
+            dropped,
+            message: PhantomData,
+        })
+    }
+
+    /// Returns the number of requests that were invalid, or whose response could not be
+    /// published.
+    pub fn dropped_requests(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of [`TopicClient`]s that are matched to the request topic.
+    pub fn get_client_count(&self) -> Result<usize, RclrsError> {
+        self.subscription.get_publisher_count()
+    }
+}
@@ -0,0 +1,196 @@
+use crate::RequestId;
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::fmt::Write;
+
+/// The tracing information of one service request, on the client or on the service side.
+///
+/// The context is identified by the [`RequestId`] of the request, i.e. the GUID of the client and
+/// the sequence number of the request, which the client and the service both know. This makes
+/// the [`TraceContext::correlation_id`] the same on both sides, without sending anything in
+/// addition to the request, so that e.g. the log output of both sides can be correlated.
+///
+/// The [`TraceContext::metadata`] is not sent, since ROS 2 requests have no headers to carry it
+/// in, so the metadata of the client and of the service are independent of each other. Trace
+/// contexts therefore can not propagate the spans of a distributed tracing library from the
+/// client to the service. On the client, the metadata is returned with the response, see
+/// [`Client::async_send_request_with_metadata`][1]. On the service, it is shared between the
+/// hooks and the callback, see [`with_current_trace_context`][2].
+///
+/// [1]: crate::Client::async_send_request_with_metadata
+/// [2]: crate::with_current_trace_context
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceContext {
+    // Unknown on a client before Iron, until the client has received a response.
+    request_id: Option<RequestId>,
+    /// User-defined key/value pairs, e.g. the ID of a span of a tracing library, or the user on
+    /// whose behalf the request is made.
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl TraceContext {
+    pub(crate) fn new(request_id: Option<RequestId>, metadata: BTreeMap<String, String>) -> Self {
+        Self {
+            request_id,
+            metadata,
+        }
+    }
+
+    /// Returns the ID of the request.
+    ///
+    /// Before Iron, RMW has no function for the GUID of a client, so a client only learns it
+    /// from its first response. Until then, this returns `None` in
+    /// [`TraceHooks::on_request_sent`], and the ID is only known in
+    /// [`TraceHooks::on_response_received`]. On the service, the ID is always known.
+    pub fn request_id(&self) -> Option<&RequestId> {
+        self.request_id.as_ref()
+    }
+
+    pub(crate) fn set_request_id(&mut self, request_id: RequestId) {
+        self.request_id = Some(request_id);
+    }
+
+    /// Returns an ID of the request that is unique in the ROS graph, in the format
+    /// `<GUID of the client in hex>-<sequence number>`, or `None` if the
+    /// [`TraceContext::request_id`] is not known yet.
+    pub fn correlation_id(&self) -> Option<String> {
+        let request_id = self.request_id.as_ref()?;
+        let mut id = String::with_capacity(48);
+        for byte in request_id.writer_guid() {
+            let _ = write!(id, "{byte:02x}");
+        }
+        let _ = write!(id, "-{}", request_id.sequence_number());
+        Some(id)
+    }
+}
+
+/// Functions that run when a service request is sent, received and answered, e.g. to log them or
+/// to time them, see [`Node::set_trace_hooks`][1].
+///
+/// The hooks of a node apply to the clients, services and deferred services that are created
+/// after they have been set. They may run on any thread, e.g. when a [`ServiceResponder`][2]
+/// responds from a thread pool.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// node.set_trace_hooks(TraceHooks {
+///     on_request_received: Some(Box::new(|context| {
+///         let received = format!("{:?}", std::time::SystemTime::now());
+///         context.metadata.insert("received".into(), received);
+///     })),
+///     ..Default::default()
+/// });
+/// let _service = node.create_service::<example_interfaces::srv::AddTwoInts, _>(
+///     "add_two_ints",
+///     QOS_PROFILE_SERVICES_DEFAULT,
+///     |request| {
+///         rclrs::with_current_trace_context(|context| {
+///             if let Some(context) = context {
+///                 println!(
+///                     "Handling request {:?}, received at {}",
+///                     context.correlation_id(),
+///                     context.metadata["received"],
+///                 );
+///             }
+///         });
+///         example_interfaces::srv::AddTwoInts_Response {
+///             sum: request.a + request.b,
+///         }
+///     },
+/// )?;
+/// ```
+///
+/// [1]: crate::Node::set_trace_hooks
+/// [2]: crate::ServiceResponder
+#[allow(clippy::type_complexity)]
+#[derive(Default)]
+pub struct TraceHooks {
+    /// Runs on the client after a request has been sent, e.g. to record when. Changes to the
+    /// metadata are kept, and returned with the response.
+    ///
+    /// This runs while the client is locked, so it must not send requests with the same client.
+    pub on_request_sent: Option<Box<dyn Fn(&mut TraceContext) + Send + Sync + 'static>>,
+    /// Runs on the client when the response to a request arrives, before its callback.
+    pub on_response_received: Option<Box<dyn Fn(&TraceContext) + Send + Sync + 'static>>,
+    /// Runs on the service when a request arrives, before the callback. Changes to the metadata
+    /// are visible to the callback.
+    pub on_request_received: Option<Box<dyn Fn(&mut TraceContext) + Send + Sync + 'static>>,
+    /// Runs on the service after the response to a request has been sent.
+    pub on_response_sent: Option<Box<dyn Fn(&TraceContext) + Send + Sync + 'static>>,
+}
+
+// The hooks are optional on each node, so they are run through its `Option<Arc<TraceHooks>>`.
+pub(crate) trait RunTraceHooks {
+    fn request_sent(&self, context: &mut TraceContext);
+    fn response_received(&self, context: &TraceContext);
+    fn request_received(&self, context: &mut TraceContext);
+    fn response_sent(&self, context: &TraceContext);
+}
+
+impl RunTraceHooks for Option<Arc<TraceHooks>> {
+    fn request_sent(&self, context: &mut TraceContext) {
+        if let Some(hook) = self
+            .as_ref()
+            .and_then(|hooks| hooks.on_request_sent.as_ref())
+        {
+            hook(context);
+        }
+    }
+
+    fn response_received(&self, context: &TraceContext) {
+        if let Some(hook) = self
+            .as_ref()
+            .and_then(|hooks| hooks.on_response_received.as_ref())
+        {
+            hook(context);
+        }
+    }
+
+    fn request_received(&self, context: &mut TraceContext) {
+        if let Some(hook) = self
+            .as_ref()
+            .and_then(|hooks| hooks.on_request_received.as_ref())
+        {
+            hook(context);
+        }
+    }
+
+    fn response_sent(&self, context: &TraceContext) {
+        if let Some(hook) = self
+            .as_ref()
+            .and_then(|hooks| hooks.on_response_sent.as_ref())
+        {
+            hook(context);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_correlation_id() {
+        let mut writer_guid = [0; 16];
+        writer_guid[0] = 0xab;
+        writer_guid[15] = 0x01;
+        let context = TraceContext::new(Some(RequestId::new(writer_guid, 42)), BTreeMap::new());
+        assert_eq!(
+            context.correlation_id().as_deref(),
+            Some("ab000000000000000000000000000001-42")
+        );
+    }
+
+    #[test]
+    fn test_unknown_request_id() {
+        let mut context = TraceContext::new(None, BTreeMap::new());
+        assert_eq!(context.request_id(), None);
+        assert_eq!(context.correlation_id(), None);
+        context.set_request_id(RequestId::new([1; 16], 7));
+        assert_eq!(context.request_id(), Some(&RequestId::new([1; 16], 7)));
+    }
+}
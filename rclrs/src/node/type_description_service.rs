@@ -0,0 +1,92 @@
+use crate::error::{RclReturnCode, ServiceErrorCode, ToResult};
+use crate::rcl_bindings::*;
+use crate::sync::Mutex;
+use crate::{NodeHandle, RclrsError, ServiceBase, ServiceHandle};
+
+use alloc::sync::Arc;
+use core::sync::atomic::AtomicI32;
+
+/// The `~/get_type_description` service of a node, which `rcl` implements.
+///
+/// The types of all publishers and subscriptions of the node are registered with the node's type
+/// cache by `rcl` itself, so this only needs to pass on the requests.
+pub(crate) struct TypeDescriptionService {
+    handle: ServiceHandle,
+}
+
+impl TypeDescriptionService {
+    pub(crate) fn new(node_handle: &Arc<NodeHandle>) -> Result<Self, RclrsError> {
+        // This is declared before the node handle guard, since dropping it in the error case
+        // locks the node handle.
+        let handle = ServiceHandle {
+            // SAFETY: Getting a zero-initialized value is always safe.
+            handle: Mutex::new(unsafe { rcl_get_zero_initialized_service() }),
+            node_handle: node_handle.clone(),
+            priority: AtomicI32::new(0),
+            #[cfg(feature = "testing")]
+            loopback: None,
+        };
+        // SAFETY: The service handle is zero-initialized as expected by this function.
+        // The node handle is kept alive because it is co-owned by the service.
+        unsafe {
+            rcl_node_type_description_service_init(&mut *handle.lock(), &*node_handle.lock())
+                .ok()?;
+        }
+        Ok(Self { handle })
+    }
+}
+
+impl ServiceBase for TypeDescriptionService {
+    fn handle(&self) -> &ServiceHandle {
+        &self.handle
+    }
+
+    fn execute(&self) -> Result<(), RclrsError> {
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut request_id = unsafe { core::mem::zeroed::<rmw_request_id_t>() };
+        // SAFETY: The messages are zero-initialized before being initialized, and finalized
+        // exactly once below.
+        let mut request = unsafe { core::mem::zeroed() };
+        let mut response = unsafe { core::mem::zeroed() };
+        unsafe {
+            type_description_interfaces__srv__GetTypeDescription_Request__init(&mut request);
+            type_description_interfaces__srv__GetTypeDescription_Response__init(&mut response);
+        }
+        // SAFETY: The pointers are valid, and the messages have the type of the service.
+        let result = unsafe {
+            rcl_take_request(
+                &*self.handle.lock(),
+                &mut request_id,
+                &mut request as *mut _ as *mut _,
+            )
+            .ok()
+            .and_then(|()| {
+                rcl_node_type_description_service_handle_request(
+                    &mut *self.handle.node_handle.lock(),
+                    &request_id,
+                    &request,
+                    &mut response,
+                );
+                rcl_send_response(
+                    &*self.handle.lock(),
+                    &mut request_id,
+                    &mut response as *mut _ as *mut _,
+                )
+                .ok()
+            })
+        };
+        unsafe {
+            type_description_interfaces__srv__GetTypeDescription_Request__fini(&mut request);
+            type_description_interfaces__srv__GetTypeDescription_Response__fini(&mut response);
+        }
+        match result {
+            // Spurious wakeup – this may happen even when a waitset indicated that this
+            // service was ready, so it shouldn't be an error.
+            Err(RclrsError {
+                code: RclReturnCode::ServiceError(ServiceErrorCode::ServiceTakeFailed),
+                ..
+            }) => Ok(()),
+            other => other,
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::rcl_bindings::*;
+
+use core::fmt;
+
+/// The hash of a message type, as defined by [REP 2011][1].
+///
+/// Type hashes are used for detecting whether two endpoints on a topic agree on the definition of
+/// its message type, and for looking up the full type description with the
+/// `~/get_type_description` service of the publishing node. The [`Display`][2] implementation
+/// gives the usual string form, e.g. `RIHS01_5c1e…`.
+///
+/// Type hashes are only available from ROS 2 Iron on.
+///
+/// [1]: https://ros.org/reps/rep-2011.html
+/// [2]: core::fmt::Display
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+pub struct TypeHash {
+    /// The version of the hashing algorithm.
+    pub version: u8,
+    /// The hash value.
+    pub value: [u8; 32],
+}
+
+impl TypeHash {
+    /// Gets the type hash from the type support of a message type.
+    ///
+    /// Returns `None` if the distro predates type hashes, or if the type support does not provide
+    /// one.
+    ///
+    /// # Safety
+    /// The type support must be valid.
+    #[cfg_attr(
+        any(ros_distro = "foxy", ros_distro = "galactic", ros_distro = "humble"),
+        allow(unused_variables)
+    )]
+    pub(crate) unsafe fn from_type_support(
+        type_support: *const rosidl_message_type_support_t,
+    ) -> Option<Self> {
+        #[cfg(any(ros_distro = "foxy", ros_distro = "galactic", ros_distro = "humble"))]
+        return None;
+        #[cfg(not(any(ros_distro = "foxy", ros_distro = "galactic", ros_distro = "humble")))]
+        {
+            let get_type_hash = (*type_support).get_type_hash_func?;
+            let hash = get_type_hash(type_support).as_ref()?;
+            Some(Self {
+                version: hash.version,
+                value: hash.value,
+            })
+        }
+    }
+}
+
+impl fmt::Display for TypeHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RIHS{:02}_", self.version)?;
+        for byte in self.value {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for TypeHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TypeHash({})", self)
+    }
+}
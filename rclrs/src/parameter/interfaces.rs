@@ -0,0 +1,309 @@
+//! The `rcl_interfaces` messages and services that the parameter services use, in addition to
+//! the public ones in [`crate::rcl_interfaces`].
+//!
+//! `rclrs` can not depend on the generated message crates, so these are written out by hand, in
+//! the same way as the RMW-native types that `rosidl_generator_rs` generates. Their layout must
+//! match the C types of `rosidl_generator_c`.
+#![allow(non_camel_case_types)]
+
+use crate::rosidl_macros::{impl_message, impl_sequence_alloc, impl_service};
+
+use rosidl_runtime_rs::{BoundedSequence, Sequence, String};
+
+pub(crate) use crate::rcl_interfaces::*;
+
+// Corresponds to rcl_interfaces__msg__FloatingPointRange
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct FloatingPointRange {
+    pub(crate) from_value: f64,
+    pub(crate) to_value: f64,
+    pub(crate) step: f64,
+}
+
+impl_message!(
+    FloatingPointRange,
+    "rcl_interfaces/msg/FloatingPointRange",
+    rcl_interfaces__msg__FloatingPointRange__init,
+    rosidl_typesupport_c__get_message_type_support_handle__rcl_interfaces__msg__FloatingPointRange
+);
+impl_sequence_alloc!(
+    FloatingPointRange,
+    rcl_interfaces__msg__FloatingPointRange__Sequence__init,
+    rcl_interfaces__msg__FloatingPointRange__Sequence__fini
+);
+
+// Corresponds to rcl_interfaces__msg__IntegerRange
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct IntegerRange {
+    pub(crate) from_value: i64,
+    pub(crate) to_value: i64,
+    pub(crate) step: u64,
+}
+
+impl_message!(
+    IntegerRange,
+    "rcl_interfaces/msg/IntegerRange",
+    rcl_interfaces__msg__IntegerRange__init,
+    rosidl_typesupport_c__get_message_type_support_handle__rcl_interfaces__msg__IntegerRange
+);
+impl_sequence_alloc!(
+    IntegerRange,
+    rcl_interfaces__msg__IntegerRange__Sequence__init,
+    rcl_interfaces__msg__IntegerRange__Sequence__fini
+);
+
+// Corresponds to rcl_interfaces__msg__ParameterDescriptor
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ParameterDescriptor {
+    pub(crate) name: String,
+    pub(crate) type_: u8,
+    pub(crate) description: String,
+    pub(crate) additional_constraints: String,
+    pub(crate) read_only: bool,
+    // Added in Galactic
+    #[cfg(not(ros_distro = "foxy"))]
+    pub(crate) dynamic_typing: bool,
+    pub(crate) floating_point_range: BoundedSequence<FloatingPointRange, 1>,
+    pub(crate) integer_range: BoundedSequence<IntegerRange, 1>,
+}
+
+impl_message!(
+    ParameterDescriptor,
+    "rcl_interfaces/msg/ParameterDescriptor",
+    rcl_interfaces__msg__ParameterDescriptor__init,
+    rosidl_typesupport_c__get_message_type_support_handle__rcl_interfaces__msg__ParameterDescriptor
+);
+impl_sequence_alloc!(
+    ParameterDescriptor,
+    rcl_interfaces__msg__ParameterDescriptor__Sequence__init,
+    rcl_interfaces__msg__ParameterDescriptor__Sequence__fini
+);
+
+// Corresponds to rcl_interfaces__msg__ListParametersResult
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ListParametersResult {
+    pub(crate) names: Sequence<String>,
+    pub(crate) prefixes: Sequence<String>,
+}
+
+impl_message!(
+    ListParametersResult,
+    "rcl_interfaces/msg/ListParametersResult",
+    rcl_interfaces__msg__ListParametersResult__init,
+    rosidl_typesupport_c__get_message_type_support_handle__rcl_interfaces__msg__ListParametersResult
+);
+
+// Corresponds to rcl_interfaces__srv__DescribeParameters_Request
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct DescribeParameters_Request {
+    pub(crate) names: Sequence<String>,
+}
+
+impl_message!(
+    DescribeParameters_Request,
+    "rcl_interfaces/srv/DescribeParameters_Request",
+    rcl_interfaces__srv__DescribeParameters_Request__init,
+    rosidl_typesupport_c__get_message_type_support_handle__rcl_interfaces__srv__DescribeParameters_Request
+);
+
+// Corresponds to rcl_interfaces__srv__DescribeParameters_Response
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct DescribeParameters_Response {
+    pub(crate) descriptors: Sequence<ParameterDescriptor>,
+}
+
+impl_message!(
+    DescribeParameters_Response,
+    "rcl_interfaces/srv/DescribeParameters_Response",
+    rcl_interfaces__srv__DescribeParameters_Response__init,
+    rosidl_typesupport_c__get_message_type_support_handle__rcl_interfaces__srv__DescribeParameters_Response
+);
+
+impl_service!(
+    DescribeParameters,
+    DescribeParameters_Request,
+    DescribeParameters_Response,
+    rosidl_typesupport_c__get_service_type_support_handle__rcl_interfaces__srv__DescribeParameters
+);
+
+// Corresponds to rcl_interfaces__srv__GetParameters_Request
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct GetParameters_Request {
+    pub(crate) names: Sequence<String>,
+}
+
+impl_message!(
+    GetParameters_Request,
+    "rcl_interfaces/srv/GetParameters_Request",
+    rcl_interfaces__srv__GetParameters_Request__init,
+    rosidl_typesupport_c__get_message_type_support_handle__rcl_interfaces__srv__GetParameters_Request
+);
+
+// Corresponds to rcl_interfaces__srv__GetParameters_Response
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct GetParameters_Response {
+    pub(crate) values: Sequence<ParameterValue>,
+}
+
+impl_message!(
+    GetParameters_Response,
+    "rcl_interfaces/srv/GetParameters_Response",
+    rcl_interfaces__srv__GetParameters_Response__init,
+    rosidl_typesupport_c__get_message_type_support_handle__rcl_interfaces__srv__GetParameters_Response
+);
+
+impl_service!(
+    GetParameters,
+    GetParameters_Request,
+    GetParameters_Response,
+    rosidl_typesupport_c__get_service_type_support_handle__rcl_interfaces__srv__GetParameters
+);
+
+// Corresponds to rcl_interfaces__srv__GetParameterTypes_Request
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct GetParameterTypes_Request {
+    pub(crate) names: Sequence<String>,
+}
+
+impl_message!(
+    GetParameterTypes_Request,
+    "rcl_interfaces/srv/GetParameterTypes_Request",
+    rcl_interfaces__srv__GetParameterTypes_Request__init,
+    rosidl_typesupport_c__get_message_type_support_handle__rcl_interfaces__srv__GetParameterTypes_Request
+);
+
+// Corresponds to rcl_interfaces__srv__GetParameterTypes_Response
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct GetParameterTypes_Response {
+    pub(crate) types: Sequence<u8>,
+}
+
+impl_message!(
+    GetParameterTypes_Response,
+    "rcl_interfaces/srv/GetParameterTypes_Response",
+    rcl_interfaces__srv__GetParameterTypes_Response__init,
+    rosidl_typesupport_c__get_message_type_support_handle__rcl_interfaces__srv__GetParameterTypes_Response
+);
+
+impl_service!(
+    GetParameterTypes,
+    GetParameterTypes_Request,
+    GetParameterTypes_Response,
+    rosidl_typesupport_c__get_service_type_support_handle__rcl_interfaces__srv__GetParameterTypes
+);
+
+// Corresponds to rcl_interfaces__srv__ListParameters_Request
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ListParameters_Request {
+    pub(crate) prefixes: Sequence<String>,
+    pub(crate) depth: u64,
+}
+
+impl_message!(
+    ListParameters_Request,
+    "rcl_interfaces/srv/ListParameters_Request",
+    rcl_interfaces__srv__ListParameters_Request__init,
+    rosidl_typesupport_c__get_message_type_support_handle__rcl_interfaces__srv__ListParameters_Request
+);
+
+// Corresponds to rcl_interfaces__srv__ListParameters_Response
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ListParameters_Response {
+    pub(crate) result: ListParametersResult,
+}
+
+impl_message!(
+    ListParameters_Response,
+    "rcl_interfaces/srv/ListParameters_Response",
+    rcl_interfaces__srv__ListParameters_Response__init,
+    rosidl_typesupport_c__get_message_type_support_handle__rcl_interfaces__srv__ListParameters_Response
+);
+
+impl_service!(
+    ListParameters,
+    ListParameters_Request,
+    ListParameters_Response,
+    rosidl_typesupport_c__get_service_type_support_handle__rcl_interfaces__srv__ListParameters
+);
+
+// Corresponds to rcl_interfaces__srv__SetParameters_Request
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SetParameters_Request {
+    pub(crate) parameters: Sequence<Parameter>,
+}
+
+impl_message!(
+    SetParameters_Request,
+    "rcl_interfaces/srv/SetParameters_Request",
+    rcl_interfaces__srv__SetParameters_Request__init,
+    rosidl_typesupport_c__get_message_type_support_handle__rcl_interfaces__srv__SetParameters_Request
+);
+
+// Corresponds to rcl_interfaces__srv__SetParameters_Response
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SetParameters_Response {
+    pub(crate) results: Sequence<SetParametersResult>,
+}
+
+impl_message!(
+    SetParameters_Response,
+    "rcl_interfaces/srv/SetParameters_Response",
+    rcl_interfaces__srv__SetParameters_Response__init,
+    rosidl_typesupport_c__get_message_type_support_handle__rcl_interfaces__srv__SetParameters_Response
+);
+
+impl_service!(
+    SetParameters,
+    SetParameters_Request,
+    SetParameters_Response,
+    rosidl_typesupport_c__get_service_type_support_handle__rcl_interfaces__srv__SetParameters
+);
+
+// Corresponds to rcl_interfaces__srv__SetParametersAtomically_Request
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SetParametersAtomically_Request {
+    pub(crate) parameters: Sequence<Parameter>,
+}
+
+impl_message!(
+    SetParametersAtomically_Request,
+    "rcl_interfaces/srv/SetParametersAtomically_Request",
+    rcl_interfaces__srv__SetParametersAtomically_Request__init,
+    rosidl_typesupport_c__get_message_type_support_handle__rcl_interfaces__srv__SetParametersAtomically_Request
+);
+
+// Corresponds to rcl_interfaces__srv__SetParametersAtomically_Response
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SetParametersAtomically_Response {
+    pub(crate) result: SetParametersResult,
+}
+
+impl_message!(
+    SetParametersAtomically_Response,
+    "rcl_interfaces/srv/SetParametersAtomically_Response",
+    rcl_interfaces__srv__SetParametersAtomically_Response__init,
+    rosidl_typesupport_c__get_message_type_support_handle__rcl_interfaces__srv__SetParametersAtomically_Response
+);
+
+impl_service!(
+    SetParametersAtomically,
+    SetParametersAtomically_Request,
+    SetParametersAtomically_Response,
+    rosidl_typesupport_c__get_service_type_support_handle__rcl_interfaces__srv__SetParametersAtomically
+);
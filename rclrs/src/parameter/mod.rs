@@ -0,0 +1,676 @@
+mod interfaces;
+mod options;
+mod overrides;
+mod service;
+mod value;
+#[cfg(feature = "yaml")]
+mod yaml;
+
+pub use options::*;
+use overrides::*;
+pub(crate) use service::*;
+pub use value::*;
+
+use crate::rcl_bindings::*;
+use crate::sync::Mutex;
+use crate::{Node, NodeOptions, RclReturnCode, RclrsError};
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+// The kinds of parameter handles.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ParameterFlavor {
+    Mandatory,
+    ReadOnly,
+    Optional,
+    // A parameter without a handle, which was set while undeclared or declared from an override.
+    // Its type changes with its value, and it is undeclared when it is unset.
+    Dynamic,
+}
+
+// A declared parameter. Its value is always of the type that it was declared with, and within its
+// range.
+pub(crate) struct DeclaredParameter {
+    value: Option<ParameterValue>,
+    kind: ParameterKind,
+    flavor: ParameterFlavor,
+    options: ParameterOptions,
+}
+
+impl DeclaredParameter {
+    // Checks whether the parameter can be set to the value, and returns the reason if not.
+    fn check(&self, name: &str, value: Option<&ParameterValue>) -> Result<(), String> {
+        if self.flavor == ParameterFlavor::ReadOnly {
+            return Err(format!(
+                "Parameter {{{name}}} cannot be set because it is read-only"
+            ));
+        }
+        self.check_value(name, value)
+    }
+
+    // Like check(), but for the initial value, which is also allowed for read-only parameters.
+    fn check_value(&self, name: &str, value: Option<&ParameterValue>) -> Result<(), String> {
+        let value = match value {
+            Some(value) => value,
+            None if matches!(
+                self.flavor,
+                ParameterFlavor::Optional | ParameterFlavor::Dynamic
+            ) =>
+            {
+                return Ok(())
+            }
+            None => return Err(format!("Parameter {{{name}}} cannot be unset")),
+        };
+        if self.flavor != ParameterFlavor::Dynamic && value.kind() != self.kind {
+            return Err(format!(
+                "Wrong parameter type, parameter {{{name}}} is of type {:?}, setting it to {:?} \
+                 is not allowed",
+                self.kind,
+                value.kind()
+            ));
+        }
+        match &self.options.range {
+            Some(range) => range.check(name, value),
+            None => Ok(()),
+        }
+    }
+}
+
+type ParameterMap = BTreeMap<String, DeclaredParameter>;
+
+// Checks whether a parameter can be set to the value, and returns the reason if not.
+fn check_set_parameter(
+    parameters: &ParameterMap,
+    name: &str,
+    value: Option<&ParameterValue>,
+    allow_undeclared: bool,
+) -> Result<(), String> {
+    match parameters.get(name) {
+        Some(parameter) => parameter.check(name, value),
+        None if allow_undeclared => Ok(()),
+        None => Err(format!("Parameter {{{name}}} has not been declared")),
+    }
+}
+
+// Sets a parameter after it has been checked with check_set_parameter(). Like in rclcpp, setting
+// an undeclared parameter declares it, and unsetting a dynamic parameter undeclares it.
+fn set_parameter(parameters: &mut ParameterMap, name: String, value: Option<ParameterValue>) {
+    let value = match value {
+        Some(value) => value,
+        None => {
+            match parameters.get_mut(&name) {
+                Some(parameter) if parameter.flavor == ParameterFlavor::Dynamic => {
+                    parameters.remove(&name);
+                }
+                Some(parameter) => parameter.value = None,
+                None => {}
+            }
+            return;
+        }
+    };
+    let parameter = parameters.entry(name).or_insert_with(|| DeclaredParameter {
+        value: None,
+        kind: value.kind(),
+        flavor: ParameterFlavor::Dynamic,
+        options: ParameterOptions::default(),
+    });
+    parameter.kind = value.kind();
+    parameter.value = Some(value);
+}
+
+/// The separator of parameter namespaces, e.g. in `pid.p`.
+const PARAMETER_SEPARATOR: char = '.';
+
+/// The depth for [`Node::list_parameters`] that lists the parameters of all nested namespaces.
+pub const PARAMETER_DEPTH_RECURSIVE: u64 = 0;
+
+/// The result of [`Node::list_parameters`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParameterList {
+    /// The names of the parameters that matched.
+    pub names: Vec<String>,
+    /// The namespaces of the parameters that matched, e.g. `pid` for `pid.p`.
+    pub prefixes: Vec<String>,
+}
+
+// The same semantics as rclcpp's NodeParameters::list_parameters().
+fn list_parameters(parameters: &ParameterMap, prefixes: &[String], depth: u64) -> ParameterList {
+    // The number of namespace levels below the given name.
+    let within_depth = |name: &str| {
+        depth == PARAMETER_DEPTH_RECURSIVE
+            || (name.matches(PARAMETER_SEPARATOR).count() as u64) < depth
+    };
+    let mut list = ParameterList::default();
+    for name in parameters.keys() {
+        let matches_all = prefixes.is_empty() && within_depth(name);
+        let matches_prefix = prefixes.iter().any(|prefix| {
+            if name == prefix {
+                return true;
+            }
+            // Like in rclcpp, the separator after the prefix counts towards the depth, so e.g.
+            // `pid.p` is only listed for the prefix `pid` with a depth of at least 2.
+            match name.strip_prefix(prefix.as_str()) {
+                Some(rest) if rest.starts_with(PARAMETER_SEPARATOR) => within_depth(rest),
+                _ => false,
+            }
+        });
+        if !matches_all && !matches_prefix {
+            continue;
+        }
+        list.names.push(name.clone());
+        if let Some((prefix, _)) = name.rsplit_once(PARAMETER_SEPARATOR) {
+            if !list.prefixes.iter().any(|p| p == prefix) {
+                list.prefixes.push(prefix.into());
+            }
+        }
+    }
+    list
+}
+
+/// The parameters of a node, and the overrides that they are initialized from.
+pub(crate) struct ParameterInterface {
+    overrides: BTreeMap<String, ParameterValue>,
+    allow_undeclared: bool,
+    // Shared with the parameter handles, so that they can be used from other threads.
+    parameters: Arc<Mutex<ParameterMap>>,
+}
+
+impl ParameterInterface {
+    pub(crate) fn new(
+        context: &rcl_context_t,
+        node: &rcl_node_t,
+        fully_qualified_name: &str,
+        options: &NodeOptions,
+    ) -> Result<Self, RclrsError> {
+        let overrides = get_parameter_overrides(context, node, fully_qualified_name)?;
+        let mut parameters = BTreeMap::new();
+        if options.automatically_declare_parameters_from_overrides {
+            for (name, value) in &overrides {
+                set_parameter(&mut parameters, name.clone(), Some(value.clone()));
+            }
+        }
+        Ok(Self {
+            overrides,
+            allow_undeclared: options.allow_undeclared_parameters,
+            parameters: Arc::new(Mutex::new(parameters)),
+        })
+    }
+
+    /// Returns the parameter overrides, as given with `--ros-args -p` or parameter files.
+    pub(crate) fn overrides(&self) -> &BTreeMap<String, ParameterValue> {
+        &self.overrides
+    }
+
+    /// Declares a read-only parameter with the given value, unless a parameter with that name has
+    /// already been declared with a handle, in which case `None` is returned.
+    ///
+    /// This is used for the parameters that show the QoS overrides of publishers and
+    /// subscriptions, which several entities on the same topic may share.
+    pub(crate) fn declare_read_only_value(
+        &self,
+        name: &str,
+        value: ParameterValue,
+    ) -> Result<Option<ParameterName>, RclrsError> {
+        let is_declared = matches!(
+            self.parameters.lock().get(name),
+            Some(parameter) if parameter.flavor != ParameterFlavor::Dynamic
+        );
+        if is_declared {
+            return Ok(None);
+        }
+        self.declare(
+            name,
+            value.kind(),
+            ParameterFlavor::ReadOnly,
+            Some(value),
+            ParameterOptions::default(),
+        )
+        .map(Some)
+    }
+
+    // Declares a parameter, and returns the name of the handle.
+    fn declare(
+        &self,
+        name: &str,
+        kind: ParameterKind,
+        flavor: ParameterFlavor,
+        default_value: Option<ParameterValue>,
+        options: ParameterOptions,
+    ) -> Result<ParameterName, RclrsError> {
+        let invalid = |msg: String| RclrsError::with_message(RclReturnCode::InvalidArgument, msg);
+        if name.is_empty() {
+            return Err(invalid("The parameter name must not be empty".into()));
+        }
+        if let Some(range) = &options.range {
+            if !range.applies_to(kind) {
+                return Err(invalid(format!(
+                    "The range of parameter '{name}' does not apply to its type {kind:?}"
+                )));
+            }
+        }
+        let parameters = &mut *self.parameters.lock();
+        // A dynamic parameter is taken over, with its current value instead of the override.
+        let initial_value = match parameters.get(name) {
+            Some(parameter) if parameter.flavor == ParameterFlavor::Dynamic => {
+                parameter.value.as_ref()
+            }
+            Some(_) => {
+                return Err(invalid(format!(
+                    "The parameter '{name}' has already been declared"
+                )))
+            }
+            None => self.overrides.get(name),
+        };
+        let value = match initial_value {
+            Some(value) if value.kind() != kind => {
+                return Err(invalid(format!(
+                    "The value of parameter '{name}' is of type {:?}, but the parameter is \
+                     declared with type {kind:?}",
+                    value.kind()
+                )))
+            }
+            Some(value) => Some(value.clone()),
+            None => default_value,
+        };
+        let parameter = DeclaredParameter {
+            value,
+            kind,
+            flavor,
+            options,
+        };
+        parameter
+            .check_value(name, parameter.value.as_ref())
+            .map_err(invalid)?;
+        parameters.insert(name.into(), parameter);
+        Ok(ParameterName {
+            name: name.into(),
+            parameters: self.parameters.clone(),
+        })
+    }
+}
+
+// The name of a declared parameter, which undeclares it when dropped.
+pub(crate) struct ParameterName {
+    name: String,
+    parameters: Arc<Mutex<ParameterMap>>,
+}
+
+impl Drop for ParameterName {
+    fn drop(&mut self) {
+        self.parameters.lock().remove(&self.name);
+    }
+}
+
+impl ParameterName {
+    fn get<T: ParameterVariant>(&self) -> Option<T> {
+        let value = self.parameters.lock()[&self.name].value.clone()?;
+        // The value always has the kind that the parameter was declared with.
+        Some(T::from_parameter_value(value).unwrap())
+    }
+
+    fn set(&self, value: Option<ParameterValue>) -> Result<(), RclrsError> {
+        let parameters = &mut *self.parameters.lock();
+        // The parameter is declared as long as its handle exists.
+        let parameter = parameters.get_mut(&self.name).unwrap();
+        parameter
+            .check(&self.name, value.as_ref())
+            .map_err(|msg| RclrsError::with_message(RclReturnCode::InvalidArgument, msg))?;
+        parameter.value = value;
+        Ok(())
+    }
+}
+
+/// A parameter that always has a value of type `T`, see [`Node::declare_parameter`].
+///
+/// The parameter is undeclared when this handle is dropped. The handle can be sent to other
+/// threads, e.g. to read the parameter in a worker thread.
+pub struct MandatoryParameter<T: ParameterVariant> {
+    name: ParameterName,
+    _type: PhantomData<T>,
+}
+
+impl<T: ParameterVariant> MandatoryParameter<T> {
+    /// Returns the name of the parameter.
+    pub fn name(&self) -> &str {
+        &self.name.name
+    }
+
+    /// Returns the current value of the parameter.
+    pub fn get(&self) -> T {
+        // A mandatory parameter always has a value.
+        self.name.get().unwrap()
+    }
+
+    /// Sets the value of the parameter.
+    ///
+    /// Returns an [`InvalidArgument`][1] error with the reason if the value is outside of the
+    /// parameter's [range][2].
+    ///
+    /// [1]: crate::RclReturnCode::InvalidArgument
+    /// [2]: crate::ParameterOptions::range
+    pub fn set(&self, value: T) -> Result<(), RclrsError> {
+        self.name.set(Some(value.into()))
+    }
+}
+
+/// A parameter of type `T` that can not be changed after it has been declared, see
+/// [`Node::declare_read_only_parameter`].
+///
+/// The parameter is undeclared when this handle is dropped.
+pub struct ReadOnlyParameter<T: ParameterVariant> {
+    name: ParameterName,
+    _type: PhantomData<T>,
+}
+
+impl<T: ParameterVariant> ReadOnlyParameter<T> {
+    /// Returns the name of the parameter.
+    pub fn name(&self) -> &str {
+        &self.name.name
+    }
+
+    /// Returns the value of the parameter.
+    pub fn get(&self) -> T {
+        // A read-only parameter always has a value.
+        self.name.get().unwrap()
+    }
+}
+
+/// A parameter of type `T` that may be unset, see [`Node::declare_optional_parameter`].
+///
+/// The parameter is undeclared when this handle is dropped.
+pub struct OptionalParameter<T: ParameterVariant> {
+    name: ParameterName,
+    _type: PhantomData<T>,
+}
+
+impl<T: ParameterVariant> OptionalParameter<T> {
+    /// Returns the name of the parameter.
+    pub fn name(&self) -> &str {
+        &self.name.name
+    }
+
+    /// Returns the current value of the parameter, or `None` if it is unset.
+    pub fn get(&self) -> Option<T> {
+        self.name.get()
+    }
+
+    /// Sets the value of the parameter, or unsets it with `None`.
+    ///
+    /// See [`MandatoryParameter::set`].
+    pub fn set(&self, value: Option<T>) -> Result<(), RclrsError> {
+        self.name.set(value.map(Into::into))
+    }
+}
+
+impl Node {
+    /// Declares a parameter of type `T`, which always has a value.
+    ///
+    /// The parameter is initialized from its override, as given with `--ros-args -p` or a
+    /// parameter file, and otherwise with the default value. Its type is fixed by `T`, so the
+    /// returned handle reads and writes values of type `T` directly.
+    ///
+    /// Returns an [`InvalidArgument`][1] error if the parameter has already been declared, or if
+    /// its override has a different type.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, RclrsError};
+    /// let context = Context::new([])?;
+    /// let node = context.create_node("my_node")?;
+    /// let gain = node.declare_parameter::<f64>("gain", 1.0)?;
+    /// assert_eq!(gain.get(), 1.0);
+    /// gain.set(2.0)?;
+    /// assert_eq!(gain.get(), 2.0);
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    ///
+    /// [1]: crate::RclReturnCode::InvalidArgument
+    pub fn declare_parameter<T: ParameterVariant>(
+        &self,
+        name: &str,
+        default_value: T,
+    ) -> Result<MandatoryParameter<T>, RclrsError> {
+        self.declare_parameter_with_options(name, default_value, ParameterOptions::default())
+    }
+
+    /// Declares a parameter of type `T` with additional options, such as a range.
+    ///
+    /// See [`Node::declare_parameter`] and [`ParameterOptions`]. This also returns an
+    /// [`InvalidArgument`][1] error if the initial value is outside of the range, or if the range
+    /// does not apply to `T`.
+    ///
+    /// [1]: crate::RclReturnCode::InvalidArgument
+    pub fn declare_parameter_with_options<T: ParameterVariant>(
+        &self,
+        name: &str,
+        default_value: T,
+        options: ParameterOptions,
+    ) -> Result<MandatoryParameter<T>, RclrsError> {
+        let name = self.parameters.declare(
+            name,
+            T::KIND,
+            ParameterFlavor::Mandatory,
+            Some(default_value.into()),
+            options,
+        )?;
+        Ok(MandatoryParameter {
+            name,
+            _type: PhantomData,
+        })
+    }
+
+    /// Declares a parameter of type `T` that can not be changed after it has been declared.
+    ///
+    /// This is useful for configuration that is only read at startup. The value can still be
+    /// given as an override. See [`Node::declare_parameter`].
+    pub fn declare_read_only_parameter<T: ParameterVariant>(
+        &self,
+        name: &str,
+        default_value: T,
+    ) -> Result<ReadOnlyParameter<T>, RclrsError> {
+        self.declare_read_only_parameter_with_options(
+            name,
+            default_value,
+            ParameterOptions::default(),
+        )
+    }
+
+    /// Declares a read-only parameter of type `T` with additional options.
+    ///
+    /// See [`Node::declare_read_only_parameter`] and [`Node::declare_parameter_with_options`].
+    pub fn declare_read_only_parameter_with_options<T: ParameterVariant>(
+        &self,
+        name: &str,
+        default_value: T,
+        options: ParameterOptions,
+    ) -> Result<ReadOnlyParameter<T>, RclrsError> {
+        let name = self.parameters.declare(
+            name,
+            T::KIND,
+            ParameterFlavor::ReadOnly,
+            Some(default_value.into()),
+            options,
+        )?;
+        Ok(ReadOnlyParameter {
+            name,
+            _type: PhantomData,
+        })
+    }
+
+    /// Declares a parameter of type `T` that may be unset.
+    ///
+    /// The parameter is unset if there is neither an override nor a default value. See
+    /// [`Node::declare_parameter`].
+    pub fn declare_optional_parameter<T: ParameterVariant>(
+        &self,
+        name: &str,
+        default_value: Option<T>,
+    ) -> Result<OptionalParameter<T>, RclrsError> {
+        self.declare_optional_parameter_with_options(
+            name,
+            default_value,
+            ParameterOptions::default(),
+        )
+    }
+
+    /// Declares an optional parameter of type `T` with additional options.
+    ///
+    /// See [`Node::declare_optional_parameter`] and [`Node::declare_parameter_with_options`].
+    pub fn declare_optional_parameter_with_options<T: ParameterVariant>(
+        &self,
+        name: &str,
+        default_value: Option<T>,
+        options: ParameterOptions,
+    ) -> Result<OptionalParameter<T>, RclrsError> {
+        let name = self.parameters.declare(
+            name,
+            T::KIND,
+            ParameterFlavor::Optional,
+            default_value.map(Into::into),
+            options,
+        )?;
+        Ok(OptionalParameter {
+            name,
+            _type: PhantomData,
+        })
+    }
+
+    /// Lists the declared parameters, like the `~/list_parameters` service, e.g. for
+    /// `ros2 param list`.
+    ///
+    /// Parameters can be grouped into namespaces with `.`, e.g. `pid.p` and `pid.i`. With empty
+    /// `prefixes`, all parameters are listed, and otherwise only those in the given namespaces.
+    /// `depth` limits how many levels of nested namespaces are listed, and
+    /// [`PARAMETER_DEPTH_RECURSIVE`] lists all levels. Like in `rclcpp`, the depth is the number
+    /// of separators that a name may contain after the prefix, plus one, and the separator right
+    /// after the prefix counts too: `pid.p` is listed for the prefix `pid` with a depth of 2, but
+    /// not of 1.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, RclrsError, PARAMETER_DEPTH_RECURSIVE};
+    /// let context = Context::new([])?;
+    /// let node = context.create_node("my_node")?;
+    /// let p = node.declare_parameter("pid.p", 1.0)?;
+    /// let i = node.declare_parameter("pid.i", 0.1)?;
+    /// let list = node.list_parameters(&["pid"], PARAMETER_DEPTH_RECURSIVE);
+    /// assert_eq!(list.names, ["pid.i", "pid.p"]);
+    /// assert_eq!(list.prefixes, ["pid"]);
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn list_parameters(&self, prefixes: &[&str], depth: u64) -> ParameterList {
+        let prefixes: Vec<String> = prefixes.iter().map(|&prefix| prefix.into()).collect();
+        list_parameters(&self.parameters.parameters.lock(), &prefixes, depth)
+    }
+
+    /// Returns the value of a parameter, or `None` if it is unset or has not been declared.
+    ///
+    /// This is useful for parameters whose type is not known at compile time, e.g. those declared
+    /// with [`NodeOptions::automatically_declare_parameters_from_overrides`] or set while
+    /// undeclared with [`NodeOptions::allow_undeclared_parameters`]. Otherwise, it is simpler to
+    /// use the handle returned by e.g. [`Node::declare_parameter`].
+    pub fn get_parameter(&self, name: &str) -> Option<ParameterValue> {
+        self.parameters
+            .parameters
+            .lock()
+            .get(name)
+            .and_then(|parameter| parameter.value.clone())
+    }
+
+    /// Sets the value of a parameter.
+    ///
+    /// Like when the parameter is set through the parameter services, the value must match the
+    /// type and range of a declared parameter. An undeclared parameter can only be set with
+    /// [`NodeOptions::allow_undeclared_parameters`], which declares it.
+    ///
+    /// Returns an [`InvalidArgument`][1] error with the reason if the parameter can not be set.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, NodeOptions, ParameterValue, RclrsError};
+    /// let context = Context::new([])?;
+    /// let options = NodeOptions {
+    ///     allow_undeclared_parameters: true,
+    ///     ..Default::default()
+    /// };
+    /// let node = context.create_node_with_options("", "my_node", options)?;
+    /// node.set_parameter("mode", ParameterValue::String("fast".into()))?;
+    /// assert_eq!(
+    ///     node.get_parameter("mode"),
+    ///     Some(ParameterValue::String("fast".into()))
+    /// );
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    ///
+    /// [1]: crate::RclReturnCode::InvalidArgument
+    pub fn set_parameter(&self, name: &str, value: ParameterValue) -> Result<(), RclrsError> {
+        let parameters = &mut *self.parameters.parameters.lock();
+        check_set_parameter(
+            parameters,
+            name,
+            Some(&value),
+            self.parameters.allow_undeclared,
+        )
+        .map_err(|msg| RclrsError::with_message(RclReturnCode::InvalidArgument, msg))?;
+        set_parameter(parameters, name.into(), Some(value));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameters(names: &[&str]) -> ParameterMap {
+        let mut parameters = ParameterMap::new();
+        for &name in names {
+            set_parameter(
+                &mut parameters,
+                name.into(),
+                Some(ParameterValue::Bool(true)),
+            );
+        }
+        parameters
+    }
+
+    #[test]
+    fn test_list_parameters_depth() {
+        let parameters = parameters(&["a", "pid.p", "pid.i", "pid.limits.max", "pidx.p"]);
+        let cases: [(&[&str], u64, &[&str]); 8] = [
+            (
+                &[],
+                PARAMETER_DEPTH_RECURSIVE,
+                &["a", "pid.i", "pid.limits.max", "pid.p", "pidx.p"],
+            ),
+            (&[], 1, &["a"]),
+            (&[], 2, &["a", "pid.i", "pid.p", "pidx.p"]),
+            (
+                &["pid"],
+                PARAMETER_DEPTH_RECURSIVE,
+                &["pid.i", "pid.limits.max", "pid.p"],
+            ),
+            // The separator after the prefix counts towards the depth, like in rclcpp.
+            (&["pid"], 1, &[]),
+            (&["pid"], 2, &["pid.i", "pid.p"]),
+            (&["pid"], 3, &["pid.i", "pid.limits.max", "pid.p"]),
+            (&["pid.p", "a"], 1, &["a", "pid.p"]),
+        ];
+        for (prefixes, depth, expected) in cases {
+            let prefixes: Vec<String> = prefixes.iter().map(|&prefix| prefix.into()).collect();
+            let list = list_parameters(&parameters, &prefixes, depth);
+            assert_eq!(list.names, expected, "{prefixes:?}, depth {depth}");
+        }
+    }
+
+    #[test]
+    fn test_list_parameters_prefixes() {
+        let parameters = parameters(&["a", "pid.p", "pid.i", "pid.limits.max"]);
+        let list = list_parameters(&parameters, &[], PARAMETER_DEPTH_RECURSIVE);
+        assert_eq!(list.prefixes, ["pid", "pid.limits"]);
+    }
+}
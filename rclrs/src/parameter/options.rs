@@ -0,0 +1,333 @@
+use crate::{ParameterKind, ParameterValue};
+
+use alloc::format;
+use alloc::string::String;
+
+/// Options for a parameter, in addition to its name and default value.
+///
+/// These correspond to the fields of an `rcl_interfaces/msg/ParameterDescriptor`, and are
+/// returned by the `~/describe_parameters` service, e.g. for `ros2 param describe`.
+///
+/// New options may be added in the future, so it is best to create this with the
+/// `..Default::default()` syntax.
+///
+/// # Example
+/// ```
+/// # use rclrs::{ParameterOptions, ParameterRange};
+/// let options = ParameterOptions {
+///     description: "The proportional gain".into(),
+///     range: Some(ParameterRange::FloatingPoint {
+///         from_value: 0.0,
+///         to_value: 10.0,
+///         step: 0.5,
+///     }),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParameterOptions {
+    /// A description of the parameter, for humans.
+    pub description: String,
+    /// A description of constraints that are not covered by the range, for humans.
+    pub additional_constraints: String,
+    /// The range of allowed values, which is enforced whenever the parameter is set.
+    pub range: Option<ParameterRange>,
+}
+
+/// The range of allowed values of a numeric parameter.
+///
+/// For array parameters, the range applies to each element.
+///
+/// Like in `rclcpp`, the bounds are inclusive, and a value is only allowed if it is a multiple of
+/// `step` away from `from_value`, or equal to `to_value`. A `step` of zero allows any value in the
+/// range.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParameterRange {
+    /// A range for `i64` and `Vec<i64>` parameters.
+    Integer {
+        /// The lower bound.
+        from_value: i64,
+        /// The upper bound.
+        to_value: i64,
+        /// The step size.
+        step: u64,
+    },
+    /// A range for `f64` and `Vec<f64>` parameters.
+    FloatingPoint {
+        /// The lower bound.
+        from_value: f64,
+        /// The upper bound.
+        to_value: f64,
+        /// The step size.
+        step: f64,
+    },
+}
+
+// The same tolerance as in rclcpp. Infinite values are only equal to themselves, since the
+// tolerance would be infinite too.
+fn are_doubles_equal(x: f64, y: f64) -> bool {
+    x == y || (x - y).abs() <= f64::EPSILON * (x + y).abs() * 100.0 && (x + y).is_finite()
+}
+
+impl ParameterRange {
+    // Returns whether the range can be used for parameters of the given type.
+    pub(crate) fn applies_to(&self, kind: ParameterKind) -> bool {
+        match self {
+            Self::Integer { .. } => {
+                matches!(kind, ParameterKind::Integer | ParameterKind::IntegerArray)
+            }
+            Self::FloatingPoint { .. } => {
+                matches!(kind, ParameterKind::Double | ParameterKind::DoubleArray)
+            }
+        }
+    }
+
+    // Checks a value against the range, and returns the reason if it is outside of the range.
+    pub(crate) fn check(&self, name: &str, value: &ParameterValue) -> Result<(), String> {
+        match (self, value) {
+            (Self::Integer { .. }, ParameterValue::Integer(value)) => {
+                self.check_integer(name, *value)
+            }
+            (Self::Integer { .. }, ParameterValue::IntegerArray(values)) => values
+                .iter()
+                .try_for_each(|value| self.check_integer(name, *value)),
+            (Self::FloatingPoint { .. }, ParameterValue::Double(value)) => {
+                self.check_double(name, *value)
+            }
+            (Self::FloatingPoint { .. }, ParameterValue::DoubleArray(values)) => values
+                .iter()
+                .try_for_each(|value| self.check_double(name, *value)),
+            // The type is checked separately.
+            _ => Ok(()),
+        }
+    }
+
+    fn check_integer(&self, name: &str, value: i64) -> Result<(), String> {
+        let (from_value, to_value, step) = match *self {
+            Self::Integer {
+                from_value,
+                to_value,
+                step,
+            } => (from_value, to_value, step),
+            Self::FloatingPoint { .. } => return Ok(()),
+        };
+        if value == from_value || value == to_value {
+            return Ok(());
+        }
+        if value < from_value || value > to_value {
+            return Err(format!(
+                "Parameter {{{name}}} doesn't comply with integer range: {value} is not in \
+                 [{from_value}, {to_value}]"
+            ));
+        }
+        if step != 0 && (value.abs_diff(from_value) % step) != 0 {
+            return Err(format!(
+                "Parameter {{{name}}} doesn't comply with integer range: {value} is not a \
+                 multiple of the step {step} away from {from_value}"
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_double(&self, name: &str, value: f64) -> Result<(), String> {
+        let (from_value, to_value, step) = match *self {
+            Self::FloatingPoint {
+                from_value,
+                to_value,
+                step,
+            } => (from_value, to_value, step),
+            Self::Integer { .. } => return Ok(()),
+        };
+        if are_doubles_equal(value, from_value) || are_doubles_equal(value, to_value) {
+            return Ok(());
+        }
+        if !(from_value..=to_value).contains(&value) {
+            return Err(format!(
+                "Parameter {{{name}}} doesn't comply with floating point range: {value} is not \
+                 in [{from_value}, {to_value}]"
+            ));
+        }
+        if step != 0.0 {
+            // The value is at least from_value here, so this rounds down. f64::round() is not
+            // available without std.
+            let lower = from_value + ((value - from_value) / step) as u64 as f64 * step;
+            if !are_doubles_equal(value, lower) && !are_doubles_equal(value, lower + step) {
+                return Err(format!(
+                    "Parameter {{{name}}} doesn't comply with floating point range: {value} is \
+                     not a multiple of the step {step} away from {from_value}"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_integer_range() {
+        let range = ParameterRange::Integer {
+            from_value: -10,
+            to_value: 11,
+            step: 3,
+        };
+        let cases = [
+            (-10, true),
+            (-7, true),
+            (8, true),
+            // The upper bound is allowed even if it is not on a step.
+            (11, true),
+            (-11, false),
+            (12, false),
+            (0, false),
+            (i64::MIN, false),
+            (i64::MAX, false),
+        ];
+        for (value, allowed) in cases {
+            let result = range.check("p", &ParameterValue::Integer(value));
+            assert_eq!(result.is_ok(), allowed, "{value}: {result:?}");
+        }
+    }
+
+    #[test]
+    fn test_integer_range_without_step() {
+        let range = ParameterRange::Integer {
+            from_value: i64::MIN,
+            to_value: i64::MAX,
+            step: 0,
+        };
+        for value in [i64::MIN, -1, 0, 1, i64::MAX] {
+            assert!(range.check("p", &ParameterValue::Integer(value)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_floating_point_range() {
+        let range = ParameterRange::FloatingPoint {
+            from_value: -1.0,
+            to_value: 1.25,
+            step: 0.5,
+        };
+        let cases = [
+            (-1.0, true),
+            (-0.5, true),
+            (1.0, true),
+            (1.25, true),
+            // Within the tolerance of a step.
+            (0.1 + 0.2 + 0.2 - 0.5, true),
+            (-1.0 - 1e-9, false),
+            (1.5, false),
+            (0.25, false),
+            (f64::NAN, false),
+            (f64::INFINITY, false),
+            (f64::NEG_INFINITY, false),
+        ];
+        for (value, allowed) in cases {
+            let result = range.check("p", &ParameterValue::Double(value));
+            assert_eq!(result.is_ok(), allowed, "{value}: {result:?}");
+        }
+    }
+
+    #[test]
+    fn test_floating_point_range_without_step() {
+        let range = ParameterRange::FloatingPoint {
+            from_value: 0.0,
+            to_value: 1.0,
+            step: 0.0,
+        };
+        let cases = [(0.0, true), (0.123, true), (1.0, true), (1.001, false)];
+        for (value, allowed) in cases {
+            let result = range.check("p", &ParameterValue::Double(value));
+            assert_eq!(result.is_ok(), allowed, "{value}: {result:?}");
+        }
+        let range = ParameterRange::FloatingPoint {
+            from_value: 0.0,
+            to_value: f64::INFINITY,
+            step: 0.0,
+        };
+        let cases = [
+            (0.0, true),
+            (1e300, true),
+            (f64::INFINITY, true),
+            (-1.0, false),
+        ];
+        for (value, allowed) in cases {
+            let result = range.check("p", &ParameterValue::Double(value));
+            assert_eq!(result.is_ok(), allowed, "{value}: {result:?}");
+        }
+    }
+
+    #[test]
+    fn test_range_applies_to_each_array_element() {
+        let range = ParameterRange::Integer {
+            from_value: 0,
+            to_value: 10,
+            step: 2,
+        };
+        let cases = [
+            (vec![], true),
+            (vec![0, 2, 10], true),
+            (vec![0, 3], false),
+            (vec![12], false),
+        ];
+        for (values, allowed) in cases {
+            let result = range.check("p", &ParameterValue::IntegerArray(values.clone()));
+            assert_eq!(result.is_ok(), allowed, "{values:?}: {result:?}");
+        }
+        let range = ParameterRange::FloatingPoint {
+            from_value: 0.0,
+            to_value: 1.0,
+            step: 0.5,
+        };
+        let values = ParameterValue::DoubleArray(vec![0.0, 0.5, 1.0]);
+        assert!(range.check("p", &values).is_ok());
+        let values = ParameterValue::DoubleArray(vec![0.0, 0.75]);
+        assert!(range.check("p", &values).is_err());
+    }
+
+    #[test]
+    fn test_range_applies_to_kinds() {
+        let integer = ParameterRange::Integer {
+            from_value: 0,
+            to_value: 1,
+            step: 0,
+        };
+        let floating_point = ParameterRange::FloatingPoint {
+            from_value: 0.0,
+            to_value: 1.0,
+            step: 0.0,
+        };
+        let cases = [
+            (ParameterKind::Integer, true, false),
+            (ParameterKind::IntegerArray, true, false),
+            (ParameterKind::Double, false, true),
+            (ParameterKind::DoubleArray, false, true),
+            (ParameterKind::Bool, false, false),
+            (ParameterKind::String, false, false),
+        ];
+        for (kind, for_integer, for_floating_point) in cases {
+            assert_eq!(integer.applies_to(kind), for_integer, "{kind:?}");
+            assert_eq!(
+                floating_point.applies_to(kind),
+                for_floating_point,
+                "{kind:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_error_message_contains_name() {
+        let range = ParameterRange::Integer {
+            from_value: 0,
+            to_value: 1,
+            step: 0,
+        };
+        let error = range
+            .check("gain", &ParameterValue::Integer(2))
+            .unwrap_err();
+        assert!(error.contains("{gain}"), "{error}");
+    }
+}
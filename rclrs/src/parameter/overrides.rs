@@ -0,0 +1,152 @@
+use crate::rcl_bindings::*;
+use crate::{ParameterValue, RclrsError, ToResult};
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use core::ffi::CStr;
+use core::slice;
+
+use libc::c_char;
+
+// SAFETY: The string must be a valid null-terminated string.
+unsafe fn string_from_ptr(ptr: *const c_char) -> String {
+    CStr::from_ptr(ptr).to_string_lossy().into_owned()
+}
+
+// SAFETY: The pointer must be null or point to `size` valid elements.
+unsafe fn slice_from_raw<'a, T>(values: *const T, size: usize) -> &'a [T] {
+    if values.is_null() {
+        &[]
+    } else {
+        slice::from_raw_parts(values, size)
+    }
+}
+
+impl ParameterValue {
+    // Returns `None` for a variant without a value, which the parser does not produce.
+    //
+    // SAFETY: The variant must be valid, i.e. as produced by the rcl YAML parser.
+    unsafe fn from_rcl_variant(variant: &rcl_variant_t) -> Option<Self> {
+        if let Some(value) = variant.bool_value.as_ref() {
+            Some(Self::Bool(*value))
+        } else if let Some(value) = variant.integer_value.as_ref() {
+            Some(Self::Integer(*value))
+        } else if let Some(value) = variant.double_value.as_ref() {
+            Some(Self::Double(*value))
+        } else if !variant.string_value.is_null() {
+            Some(Self::String(string_from_ptr(variant.string_value)))
+        } else if let Some(array) = variant.byte_array_value.as_ref() {
+            Some(Self::ByteArray(
+                slice_from_raw(array.values, array.size).to_vec(),
+            ))
+        } else if let Some(array) = variant.bool_array_value.as_ref() {
+            Some(Self::BoolArray(
+                slice_from_raw(array.values, array.size).to_vec(),
+            ))
+        } else if let Some(array) = variant.integer_array_value.as_ref() {
+            Some(Self::IntegerArray(
+                slice_from_raw(array.values, array.size).to_vec(),
+            ))
+        } else if let Some(array) = variant.double_array_value.as_ref() {
+            Some(Self::DoubleArray(
+                slice_from_raw(array.values, array.size).to_vec(),
+            ))
+        } else if let Some(array) = variant.string_array_value.as_ref() {
+            let strings = slice_from_raw(array.data, array.size);
+            Some(Self::StringArray(
+                strings.iter().map(|&s| string_from_ptr(s)).collect(),
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the parameter overrides that apply to a node, from the global arguments of its context
+/// and the arguments of the node.
+///
+/// The arguments of the node take precedence over the global arguments, and within each, overrides
+/// for the node's fully qualified name take precedence over those for all nodes, i.e. for `/**`.
+pub(crate) fn get_parameter_overrides(
+    context: &rcl_context_t,
+    node: &rcl_node_t,
+    fully_qualified_name: &str,
+) -> Result<BTreeMap<String, ParameterValue>, RclrsError> {
+    let mut overrides = BTreeMap::new();
+    // SAFETY: The options of a valid node are valid, and owned by the node.
+    let node_options = unsafe { rcl_node_get_options(node).as_ref() };
+    let use_global_arguments = node_options.is_none_or(|options| options.use_global_arguments);
+    if use_global_arguments {
+        add_parameter_overrides(
+            &context.global_arguments,
+            fully_qualified_name,
+            &mut overrides,
+        )?;
+    }
+    // The arguments are zero-initialized if the node has none.
+    if let Some(node_options) = node_options.filter(|options| !options.arguments.impl_.is_null()) {
+        add_parameter_overrides(
+            &node_options.arguments,
+            fully_qualified_name,
+            &mut overrides,
+        )?;
+    }
+    Ok(overrides)
+}
+
+fn add_parameter_overrides(
+    arguments: &rcl_arguments_t,
+    fully_qualified_name: &str,
+    overrides: &mut BTreeMap<String, ParameterValue>,
+) -> Result<(), RclrsError> {
+    let mut params: *mut rcl_params_t = core::ptr::null_mut();
+    // SAFETY: The arguments are valid.
+    // The returned parameters are copied, and finalized below.
+    unsafe {
+        rcl_arguments_get_param_overrides(arguments, &mut params).ok()?;
+    }
+    // SAFETY: The parameters are null if there are no overrides, and valid otherwise.
+    let params = match unsafe { params.as_mut() } {
+        Some(params) => params,
+        None => return Ok(()),
+    };
+    // SAFETY: The parser fills in num_nodes node names and parameter lists.
+    let (node_names, node_params) = unsafe {
+        (
+            slice_from_raw(params.node_names, params.num_nodes),
+            slice_from_raw(params.params, params.num_nodes),
+        )
+    };
+    // The wildcard comes first, so that overrides for this node replace it.
+    for wildcard in [true, false] {
+        for (&node_name, node_params) in node_names.iter().zip(node_params) {
+            // SAFETY: The node names are valid strings.
+            let node_name = unsafe { string_from_ptr(node_name) };
+            let matches = if wildcard {
+                node_name == "/**"
+            } else {
+                node_name == fully_qualified_name
+            };
+            if !matches {
+                continue;
+            }
+            // SAFETY: The parser fills in num_params names and values.
+            let (names, values) = unsafe {
+                (
+                    slice_from_raw(node_params.parameter_names, node_params.num_params),
+                    slice_from_raw(node_params.parameter_values, node_params.num_params),
+                )
+            };
+            for (&name, value) in names.iter().zip(values) {
+                // SAFETY: The names and values are valid.
+                if let Some(value) = unsafe { ParameterValue::from_rcl_variant(value) } {
+                    overrides.insert(unsafe { string_from_ptr(name) }, value);
+                }
+            }
+        }
+    }
+    // SAFETY: The parameters were allocated by rcl_arguments_get_param_overrides, and are not
+    // used anymore.
+    unsafe { rcl_yaml_node_struct_fini(params) };
+    Ok(())
+}
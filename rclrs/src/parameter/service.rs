@@ -0,0 +1,238 @@
+use super::interfaces::{self, *};
+use super::{check_set_parameter, list_parameters, set_parameter, DeclaredParameter, ParameterMap};
+use crate::{
+    Node, ParameterRange, ParameterValue, RclrsError, Service, ServiceBase, QOS_PROFILE_PARAMETERS,
+};
+
+use alloc::string::{String, ToString};
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+
+fn value_to_rmw(value: Option<&ParameterValue>) -> interfaces::ParameterValue {
+    value.map(Into::into).unwrap_or_default()
+}
+
+fn descriptor_to_rmw(name: &str, parameter: Option<&DeclaredParameter>) -> ParameterDescriptor {
+    let mut msg = ParameterDescriptor {
+        name: name.into(),
+        ..Default::default()
+    };
+    let parameter = match parameter {
+        Some(parameter) => parameter,
+        None => return msg,
+    };
+    msg.type_ = kind_to_type(parameter.kind);
+    msg.description = parameter.options.description.as_str().into();
+    msg.additional_constraints = parameter.options.additional_constraints.as_str().into();
+    msg.read_only = parameter.flavor == super::ParameterFlavor::ReadOnly;
+    match parameter.options.range {
+        Some(ParameterRange::Integer {
+            from_value,
+            to_value,
+            step,
+        }) => {
+            msg.integer_range = core::iter::once(IntegerRange {
+                from_value,
+                to_value,
+                step,
+            })
+            .collect()
+        }
+        Some(ParameterRange::FloatingPoint {
+            from_value,
+            to_value,
+            step,
+        }) => {
+            msg.floating_point_range = core::iter::once(FloatingPointRange {
+                from_value,
+                to_value,
+                step,
+            })
+            .collect()
+        }
+        None => {}
+    }
+    msg
+}
+
+fn set_result(result: Result<(), String>) -> SetParametersResult {
+    result.into()
+}
+
+// Checks whether a parameter can be set to the value, and returns the new value.
+fn check_set(
+    parameters: &ParameterMap,
+    parameter: &Parameter,
+    allow_undeclared: bool,
+) -> Result<(String, Option<ParameterValue>), String> {
+    let name = parameter.name.to_string();
+    let value = parameter.value.to_value()?;
+    check_set_parameter(parameters, &name, value.as_ref(), allow_undeclared)?;
+    Ok((name, value))
+}
+
+/// The services of a node for getting and setting its parameters from other nodes, e.g. with
+/// `ros2 param`.
+pub(crate) struct ParameterService {
+    describe_parameters: Arc<Service<DescribeParameters>>,
+    get_parameters: Arc<Service<GetParameters>>,
+    get_parameter_types: Arc<Service<GetParameterTypes>>,
+    list_parameters: Arc<Service<ListParameters>>,
+    set_parameters: Arc<Service<SetParameters>>,
+    set_parameters_atomically: Arc<Service<SetParametersAtomically>>,
+}
+
+impl ParameterService {
+    pub(crate) fn new(node: &Node) -> Result<Self, RclrsError> {
+        let parameters = node.parameters.parameters.clone();
+        let allow_undeclared = node.parameters.allow_undeclared;
+        let describe_parameters = {
+            let parameters = parameters.clone();
+            Service::new(
+                node,
+                "~/describe_parameters",
+                QOS_PROFILE_PARAMETERS,
+                move |request: DescribeParameters_Request| {
+                    let parameters = &*parameters.lock();
+                    let descriptors = request
+                        .names
+                        .iter()
+                        .map(|name| {
+                            let name = name.to_string();
+                            descriptor_to_rmw(&name, parameters.get(&name))
+                        })
+                        .collect();
+                    DescribeParameters_Response { descriptors }
+                },
+            )?
+        };
+        let get_parameters = {
+            let parameters = parameters.clone();
+            Service::new(
+                node,
+                "~/get_parameters",
+                QOS_PROFILE_PARAMETERS,
+                move |request: GetParameters_Request| {
+                    let parameters = &*parameters.lock();
+                    // Undeclared parameters are returned as unset.
+                    let values = request
+                        .names
+                        .iter()
+                        .map(|name| {
+                            let parameter = parameters.get(&name.to_string());
+                            value_to_rmw(parameter.and_then(|parameter| parameter.value.as_ref()))
+                        })
+                        .collect();
+                    GetParameters_Response { values }
+                },
+            )?
+        };
+        let get_parameter_types = {
+            let parameters = parameters.clone();
+            Service::new(
+                node,
+                "~/get_parameter_types",
+                QOS_PROFILE_PARAMETERS,
+                move |request: GetParameterTypes_Request| {
+                    let parameters = &*parameters.lock();
+                    let types = request
+                        .names
+                        .iter()
+                        .map(|name| match parameters.get(&name.to_string()) {
+                            Some(parameter) => kind_to_type(parameter.kind),
+                            None => PARAMETER_NOT_SET,
+                        })
+                        .collect();
+                    GetParameterTypes_Response { types }
+                },
+            )?
+        };
+        let list_parameters = {
+            let parameters = parameters.clone();
+            Service::new(
+                node,
+                "~/list_parameters",
+                QOS_PROFILE_PARAMETERS,
+                move |request: ListParameters_Request| {
+                    let prefixes: Vec<String> =
+                        request.prefixes.iter().map(ToString::to_string).collect();
+                    let list = list_parameters(&parameters.lock(), &prefixes, request.depth);
+                    let to_rmw = |names: Vec<String>| {
+                        names.iter().map(|name| name.as_str().into()).collect()
+                    };
+                    ListParameters_Response {
+                        result: ListParametersResult {
+                            names: to_rmw(list.names),
+                            prefixes: to_rmw(list.prefixes),
+                        },
+                    }
+                },
+            )?
+        };
+        let set_parameters = {
+            let parameters = parameters.clone();
+            Service::new(
+                node,
+                "~/set_parameters",
+                QOS_PROFILE_PARAMETERS,
+                move |request: SetParameters_Request| {
+                    let parameters = &mut *parameters.lock();
+                    // Each parameter is set individually, so some may fail while others succeed.
+                    let results = request
+                        .parameters
+                        .iter()
+                        .map(|parameter| {
+                            let result = check_set(parameters, parameter, allow_undeclared);
+                            set_result(result.map(|(name, value)| {
+                                set_parameter(parameters, name, value);
+                            }))
+                        })
+                        .collect();
+                    SetParameters_Response { results }
+                },
+            )?
+        };
+        let set_parameters_atomically = Service::new(
+            node,
+            "~/set_parameters_atomically",
+            QOS_PROFILE_PARAMETERS,
+            move |request: SetParametersAtomically_Request| {
+                let parameters = &mut *parameters.lock();
+                // All parameters are checked before any of them is set.
+                let new_values = request
+                    .parameters
+                    .iter()
+                    .map(|parameter| check_set(parameters, parameter, allow_undeclared))
+                    .collect::<Result<Vec<_>, _>>();
+                let result = new_values.map(|new_values| {
+                    for (name, value) in new_values {
+                        set_parameter(parameters, name, value);
+                    }
+                });
+                SetParametersAtomically_Response {
+                    result: set_result(result),
+                }
+            },
+        )?;
+        Ok(Self {
+            describe_parameters: Arc::new(describe_parameters),
+            get_parameters: Arc::new(get_parameters),
+            get_parameter_types: Arc::new(get_parameter_types),
+            list_parameters: Arc::new(list_parameters),
+            set_parameters: Arc::new(set_parameters),
+            set_parameters_atomically: Arc::new(set_parameters_atomically),
+        })
+    }
+
+    /// Returns the services, for executing them with the other services of the node.
+    pub(crate) fn services(&self) -> Vec<Weak<dyn ServiceBase>> {
+        alloc::vec![
+            Arc::downgrade(&self.describe_parameters) as Weak<dyn ServiceBase>,
+            Arc::downgrade(&self.get_parameters) as Weak<dyn ServiceBase>,
+            Arc::downgrade(&self.get_parameter_types) as Weak<dyn ServiceBase>,
+            Arc::downgrade(&self.list_parameters) as Weak<dyn ServiceBase>,
+            Arc::downgrade(&self.set_parameters) as Weak<dyn ServiceBase>,
+            Arc::downgrade(&self.set_parameters_atomically) as Weak<dyn ServiceBase>,
+        ]
+    }
+}
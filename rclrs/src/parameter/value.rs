@@ -0,0 +1,113 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// The value of a parameter.
+///
+/// This is only needed for handling parameters whose type is not known at compile time. Declared
+/// parameters are accessed with their Rust type, see [`Node::declare_parameter`][1].
+///
+/// [1]: crate::Node::declare_parameter
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParameterValue {
+    /// A boolean value.
+    Bool(bool),
+    /// An integer value.
+    Integer(i64),
+    /// A floating-point value.
+    Double(f64),
+    /// A string value.
+    String(String),
+    /// An array of bytes.
+    ByteArray(Vec<u8>),
+    /// An array of booleans.
+    BoolArray(Vec<bool>),
+    /// An array of integers.
+    IntegerArray(Vec<i64>),
+    /// An array of floating-point values.
+    DoubleArray(Vec<f64>),
+    /// An array of strings.
+    StringArray(Vec<String>),
+}
+
+/// The type of a [`ParameterValue`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ParameterKind {
+    /// See [`ParameterValue::Bool`].
+    Bool,
+    /// See [`ParameterValue::Integer`].
+    Integer,
+    /// See [`ParameterValue::Double`].
+    Double,
+    /// See [`ParameterValue::String`].
+    String,
+    /// See [`ParameterValue::ByteArray`].
+    ByteArray,
+    /// See [`ParameterValue::BoolArray`].
+    BoolArray,
+    /// See [`ParameterValue::IntegerArray`].
+    IntegerArray,
+    /// See [`ParameterValue::DoubleArray`].
+    DoubleArray,
+    /// See [`ParameterValue::StringArray`].
+    StringArray,
+}
+
+impl ParameterValue {
+    /// Returns the type of the value.
+    pub fn kind(&self) -> ParameterKind {
+        match self {
+            Self::Bool(_) => ParameterKind::Bool,
+            Self::Integer(_) => ParameterKind::Integer,
+            Self::Double(_) => ParameterKind::Double,
+            Self::String(_) => ParameterKind::String,
+            Self::ByteArray(_) => ParameterKind::ByteArray,
+            Self::BoolArray(_) => ParameterKind::BoolArray,
+            Self::IntegerArray(_) => ParameterKind::IntegerArray,
+            Self::DoubleArray(_) => ParameterKind::DoubleArray,
+            Self::StringArray(_) => ParameterKind::StringArray,
+        }
+    }
+}
+
+/// A Rust type that can be the value of a parameter.
+///
+/// This is implemented for `bool`, `i64`, `f64`, `String`, and `Vec`s of `u8`, `bool`, `i64`,
+/// `f64` and `String`, which correspond to the parameter types of ROS 2.
+pub trait ParameterVariant: Into<ParameterValue> + Clone + Send + Sync + 'static {
+    /// The type of the parameter values of this Rust type.
+    const KIND: ParameterKind;
+
+    /// Converts the value back, or returns `None` if it is of a different type.
+    fn from_parameter_value(value: ParameterValue) -> Option<Self>;
+}
+
+macro_rules! impl_parameter_variant {
+    ($type:ty, $variant:ident) => {
+        impl From<$type> for ParameterValue {
+            fn from(value: $type) -> Self {
+                Self::$variant(value)
+            }
+        }
+
+        impl ParameterVariant for $type {
+            const KIND: ParameterKind = ParameterKind::$variant;
+
+            fn from_parameter_value(value: ParameterValue) -> Option<Self> {
+                match value {
+                    ParameterValue::$variant(value) => Some(value),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+impl_parameter_variant!(bool, Bool);
+impl_parameter_variant!(i64, Integer);
+impl_parameter_variant!(f64, Double);
+impl_parameter_variant!(String, String);
+impl_parameter_variant!(Vec<u8>, ByteArray);
+impl_parameter_variant!(Vec<bool>, BoolArray);
+impl_parameter_variant!(Vec<i64>, IntegerArray);
+impl_parameter_variant!(Vec<f64>, DoubleArray);
+impl_parameter_variant!(Vec<String>, StringArray);
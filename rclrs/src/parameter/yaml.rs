@@ -0,0 +1,420 @@
+//! Snapshots of the parameters of a node, in the YAML format of `ros2 param dump`.
+
+use super::PARAMETER_SEPARATOR;
+use crate::{Node, ParameterKind, ParameterValue, RclReturnCode, RclrsError};
+
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::Path;
+
+// The key under the node name that contains the parameters.
+const ROS_PARAMETERS_KEY: &str = "ros__parameters";
+
+fn invalid(msg: String) -> RclrsError {
+    RclrsError::with_message(RclReturnCode::InvalidArgument, msg)
+}
+
+fn yaml_from_value(value: &ParameterValue) -> Value {
+    match value {
+        ParameterValue::Bool(v) => Value::from(*v),
+        ParameterValue::Integer(v) => Value::from(*v),
+        ParameterValue::Double(v) => Value::from(*v),
+        ParameterValue::String(v) => Value::from(v.as_str()),
+        ParameterValue::ByteArray(v) => v.iter().map(|&b| Value::from(b)).collect(),
+        ParameterValue::BoolArray(v) => v.iter().map(|&b| Value::from(b)).collect(),
+        ParameterValue::IntegerArray(v) => v.iter().map(|&i| Value::from(i)).collect(),
+        ParameterValue::DoubleArray(v) => v.iter().map(|&d| Value::from(d)).collect(),
+        ParameterValue::StringArray(v) => v.iter().map(|s| Value::from(s.as_str())).collect(),
+    }
+}
+
+// Converts a YAML value to a parameter value. A YAML sequence has no element type of its own, so
+// the kind of the current value of the parameter is used for byte arrays and empty sequences.
+fn value_from_yaml(
+    name: &str,
+    yaml: &Value,
+    current: Option<ParameterKind>,
+) -> Result<ParameterValue, RclrsError> {
+    let unsupported = || {
+        invalid(format!(
+            "Unsupported value for parameter '{name}': {yaml:?}"
+        ))
+    };
+    let value = match yaml {
+        Value::Bool(v) => ParameterValue::Bool(*v),
+        Value::Number(n) if n.is_f64() => ParameterValue::Double(n.as_f64().unwrap_or_default()),
+        Value::Number(n) => ParameterValue::Integer(n.as_i64().ok_or_else(unsupported)?),
+        Value::String(s) => ParameterValue::String(s.clone()),
+        Value::Sequence(items) => match (items.first(), current) {
+            (_, Some(ParameterKind::ByteArray)) => ParameterValue::ByteArray(
+                items
+                    .iter()
+                    .map(|item| item.as_u64().and_then(|b| u8::try_from(b).ok()))
+                    .collect::<Option<_>>()
+                    .ok_or_else(unsupported)?,
+            ),
+            (Some(Value::Bool(_)), _) | (None, Some(ParameterKind::BoolArray)) => {
+                ParameterValue::BoolArray(
+                    items
+                        .iter()
+                        .map(Value::as_bool)
+                        .collect::<Option<_>>()
+                        .ok_or_else(unsupported)?,
+                )
+            }
+            (Some(Value::Number(n)), _) if n.is_f64() => ParameterValue::DoubleArray(
+                items
+                    .iter()
+                    .map(Value::as_f64)
+                    .collect::<Option<_>>()
+                    .ok_or_else(unsupported)?,
+            ),
+            (Some(Value::Number(_)), _) | (None, Some(ParameterKind::IntegerArray))
+                if items.iter().all(|item| item.as_i64().is_some()) =>
+            {
+                ParameterValue::IntegerArray(items.iter().filter_map(Value::as_i64).collect())
+            }
+            (None, Some(ParameterKind::DoubleArray)) => ParameterValue::DoubleArray(Vec::new()),
+            (Some(Value::String(_)), _) | (None, _) => ParameterValue::StringArray(
+                items
+                    .iter()
+                    .map(|item| item.as_str().map(String::from))
+                    .collect::<Option<_>>()
+                    .ok_or_else(unsupported)?,
+            ),
+            _ => return Err(unsupported()),
+        },
+        _ => return Err(unsupported()),
+    };
+    Ok(value)
+}
+
+// Flattens the nested namespaces of the parameters, e.g. `pid: {p: 1.0}` becomes `pid.p`.
+fn flatten(prefix: &str, mapping: &Mapping, flattened: &mut Vec<(String, Value)>) {
+    for (key, value) in mapping {
+        let key = match key {
+            Value::String(key) => key.clone(),
+            key => serde_yaml::to_string(key)
+                .unwrap_or_default()
+                .trim_end()
+                .to_string(),
+        };
+        let name = if prefix.is_empty() {
+            key
+        } else {
+            format!("{prefix}{PARAMETER_SEPARATOR}{key}")
+        };
+        match value {
+            Value::Mapping(nested) => flatten(&name, nested, flattened),
+            value => flattened.push((name, value.clone())),
+        }
+    }
+}
+
+// Writes the values of parameters as the YAML of a node's parameter file, with nested mappings
+// for the namespaces of the parameters.
+fn parameters_to_yaml<'a>(
+    fully_qualified_name: &str,
+    values: impl IntoIterator<Item = (&'a str, &'a ParameterValue)>,
+) -> Result<String, serde_yaml::Error> {
+    let mut parameters = Mapping::new();
+    for (name, value) in values {
+        let mut namespace = &mut parameters;
+        let mut segments = name.split(PARAMETER_SEPARATOR).peekable();
+        while let Some(segment) = segments.next() {
+            if segments.peek().is_none() {
+                namespace.insert(segment.into(), yaml_from_value(value));
+                break;
+            }
+            let nested = namespace
+                .entry(segment.into())
+                .or_insert_with(|| Value::Mapping(Mapping::new()));
+            // A parameter with the name of a namespace, e.g. `pid` next to `pid.p`, can not be
+            // written as YAML, and is replaced by the namespace.
+            if !nested.is_mapping() {
+                *nested = Value::Mapping(Mapping::new());
+            }
+            namespace = nested.as_mapping_mut().unwrap();
+        }
+    }
+    let mut node = Mapping::new();
+    node.insert(ROS_PARAMETERS_KEY.into(), Value::Mapping(parameters));
+    let mut root = Mapping::new();
+    root.insert(fully_qualified_name.into(), Value::Mapping(node));
+    serde_yaml::to_string(&root)
+}
+
+// Reads the parameters for a node from the YAML of a parameter file, with flattened names. The
+// parameters for the node come after those for the wildcard, so that they take precedence.
+fn parameters_from_yaml(
+    yaml: &str,
+    fully_qualified_name: &str,
+) -> Result<Vec<(String, Value)>, String> {
+    let root: Mapping = serde_yaml::from_str(yaml).map_err(|e| e.to_string())?;
+    let mut loaded = Vec::new();
+    for node_name in ["/**", fully_qualified_name] {
+        // Like with --params-file, the leading slash of a node name is optional.
+        let node = root
+            .get(node_name)
+            .or_else(|| root.get(node_name.trim_start_matches('/')));
+        let Some(node) = node else {
+            continue;
+        };
+        let parameters = node
+            .get(ROS_PARAMETERS_KEY)
+            .and_then(Value::as_mapping)
+            .ok_or_else(|| format!("the node '{node_name}' has no {ROS_PARAMETERS_KEY}"))?;
+        flatten("", parameters, &mut loaded);
+    }
+    Ok(loaded)
+}
+
+impl Node {
+    /// Writes the values of the parameters of the node to a YAML file, like `ros2 param dump`.
+    ///
+    /// The file can be restored with [`Node::load_parameters_from_yaml`], passed to a node at
+    /// startup with `--params-file`, or loaded with `ros2 param load`. Parameters in namespaces,
+    /// e.g. `pid.p`, are written as nested mappings, and parameters without a value are left out.
+    /// This requires the `yaml` feature.
+    ///
+    /// Returns an [`Error`][1] if the file can not be written.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, RclrsError};
+    /// let context = Context::new([])?;
+    /// let node = context.create_node("my_node")?;
+    /// let p = node.declare_parameter("pid.p", 1.0)?;
+    /// let path = std::env::temp_dir().join("my_node_params.yaml");
+    /// node.export_parameters_to_yaml(&path)?;
+    /// p.set(2.0)?;
+    /// node.load_parameters_from_yaml(&path)?;
+    /// assert_eq!(p.get(), 1.0);
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    ///
+    /// [1]: crate::RclReturnCode::Error
+    pub fn export_parameters_to_yaml(&self, path: impl AsRef<Path>) -> Result<(), RclrsError> {
+        let yaml = {
+            let parameters = self.parameters.parameters.lock();
+            let values = parameters
+                .iter()
+                .filter_map(|(name, parameter)| Some((name.as_ref(), parameter.value.as_ref()?)));
+            parameters_to_yaml(&self.fully_qualified_name(), values)
+        };
+        let path = path.as_ref();
+        let yaml = yaml.map_err(|e| {
+            RclrsError::with_message(
+                RclReturnCode::Error,
+                format!("Could not serialize the parameters: {e}"),
+            )
+        })?;
+        fs::write(path, yaml).map_err(|e| {
+            RclrsError::with_message(
+                RclReturnCode::Error,
+                format!("Could not write '{}': {e}", path.display()),
+            )
+        })
+    }
+
+    /// Sets the parameters of the node from a YAML file, like `ros2 param load`.
+    ///
+    /// The file has the format of `ros2 param dump` and `--params-file`, as written by
+    /// [`Node::export_parameters_to_yaml`]. The parameters under the fully qualified name of the
+    /// node and under the wildcard `/**` are loaded, with those for the node taking precedence.
+    /// This requires the `yaml` feature.
+    ///
+    /// Each parameter is set like with [`Node::set_parameter`], so the same checks apply.
+    /// Parameters that already have the loaded value are skipped, so that a snapshot with
+    /// read-only parameters can be restored. A parameter that can not be set does not keep the
+    /// others from being set, and all failures are reported in one [`InvalidArgument`][1] error.
+    ///
+    /// Returns an [`Error`][2] if the file can not be read, and an [`InvalidArgument`][1] error if
+    /// it is not valid YAML in this format.
+    ///
+    /// [1]: crate::RclReturnCode::InvalidArgument
+    /// [2]: crate::RclReturnCode::Error
+    pub fn load_parameters_from_yaml(&self, path: impl AsRef<Path>) -> Result<(), RclrsError> {
+        let path = path.as_ref();
+        let yaml = fs::read_to_string(path).map_err(|e| {
+            RclrsError::with_message(
+                RclReturnCode::Error,
+                format!("Could not read '{}': {e}", path.display()),
+            )
+        })?;
+        let loaded = parameters_from_yaml(&yaml, &self.fully_qualified_name())
+            .map_err(|e| invalid(format!("Invalid parameter file '{}': {e}", path.display())))?;
+        let mut failures = Vec::new();
+        for (name, yaml) in loaded {
+            let current = self.get_parameter(&name);
+            let result = value_from_yaml(&name, &yaml, current.as_ref().map(|v| v.kind()))
+                .and_then(|value| {
+                    if current.as_ref() == Some(&value) {
+                        return Ok(());
+                    }
+                    self.set_parameter(&name, value)
+                });
+            if let Err(e) = result {
+                failures.push(match e.msg {
+                    Some(msg) => msg.to_string(),
+                    None => format!("{name}: {}", e.code),
+                });
+            }
+        }
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(invalid(format!(
+                "Could not load all parameters from '{}': {}",
+                path.display(),
+                failures.join("; ")
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NODE: &str = "/ns/my_node";
+
+    fn all_kinds() -> Vec<(&'static str, ParameterValue)> {
+        vec![
+            ("bool", ParameterValue::Bool(true)),
+            ("integer", ParameterValue::Integer(-42)),
+            ("double", ParameterValue::Double(1.0)),
+            ("string", ParameterValue::String("true".into())),
+            ("bytes", ParameterValue::ByteArray(vec![0, 255])),
+            ("bools", ParameterValue::BoolArray(vec![true, false])),
+            ("integers", ParameterValue::IntegerArray(vec![1, -2])),
+            ("doubles", ParameterValue::DoubleArray(vec![1.0, -2.5])),
+            (
+                "strings",
+                ParameterValue::StringArray(vec!["a".into(), "".into()]),
+            ),
+            ("empty_strings", ParameterValue::StringArray(vec![])),
+            ("empty_doubles", ParameterValue::DoubleArray(vec![])),
+            ("pid.p", ParameterValue::Double(0.5)),
+            ("pid.limits.max", ParameterValue::Integer(10)),
+        ]
+    }
+
+    // Loads the parameters, with the kinds of the given values as the current kinds.
+    fn load(
+        yaml: &str,
+        current: &[(&str, ParameterValue)],
+    ) -> Result<Vec<(String, ParameterValue)>, RclrsError> {
+        parameters_from_yaml(yaml, NODE)
+            .map_err(invalid)?
+            .into_iter()
+            .map(|(name, yaml)| {
+                let kind = current
+                    .iter()
+                    .find(|(current_name, _)| *current_name == name)
+                    .map(|(_, value)| value.kind());
+                let value = value_from_yaml(&name, &yaml, kind)?;
+                Ok((name, value))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let parameters = all_kinds();
+        let yaml = parameters_to_yaml(NODE, parameters.iter().map(|(n, v)| (*n, v))).unwrap();
+        let mut loaded = load(&yaml, &parameters).unwrap();
+        loaded.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut expected: Vec<_> = parameters
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect();
+        expected.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(loaded, expected);
+    }
+
+    #[test]
+    fn test_namespaces_are_nested() {
+        let p = ParameterValue::Double(0.5);
+        let yaml = parameters_to_yaml(NODE, [("pid.p", &p)]).unwrap();
+        let root: Value = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(root[NODE][ROS_PARAMETERS_KEY]["pid"]["p"], Value::from(0.5));
+    }
+
+    #[test]
+    fn test_node_parameters_take_precedence_over_wildcard() {
+        let yaml = "
+/**:
+  ros__parameters:
+    rate: 10
+    frame: map
+ns/my_node:
+  ros__parameters:
+    rate: 20
+other_node:
+  ros__parameters:
+    rate: 30
+";
+        let loaded = load(yaml, &[]).unwrap();
+        // The later value is the one that is set last.
+        let rates: Vec<_> = loaded.iter().filter(|(name, _)| name == "rate").collect();
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[1].1, ParameterValue::Integer(20));
+        assert!(loaded.contains(&("frame".into(), ParameterValue::String("map".into()))));
+    }
+
+    #[test]
+    fn test_sequences_use_current_kind() {
+        let yaml = format!("{NODE}:\n  ros__parameters:\n    bytes: [1, 2]\n    empty: []\n");
+        let current = [
+            ("bytes", ParameterValue::ByteArray(vec![])),
+            ("empty", ParameterValue::IntegerArray(vec![1])),
+        ];
+        let loaded = load(&yaml, &current).unwrap();
+        assert_eq!(loaded[0].1, ParameterValue::ByteArray(vec![1, 2]));
+        assert_eq!(loaded[1].1, ParameterValue::IntegerArray(vec![]));
+        // Without a current value, a sequence of small integers is an integer array.
+        let loaded = load(&yaml, &[]).unwrap();
+        assert_eq!(loaded[0].1, ParameterValue::IntegerArray(vec![1, 2]));
+        assert_eq!(loaded[1].1, ParameterValue::StringArray(vec![]));
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        let with_parameters =
+            |parameters: &str| format!("{NODE}:\n  ros__parameters:\n    {parameters}\n");
+        for yaml in [
+            // Not YAML.
+            "{ unclosed".to_string(),
+            // Not a mapping of node names.
+            "- a\n- b\n".to_string(),
+            // No parameters for the node.
+            format!("{NODE}:\n  rate: 10\n"),
+            format!("{NODE}:\n  ros__parameters: 10\n"),
+        ] {
+            assert!(parameters_from_yaml(&yaml, NODE).is_err(), "{yaml}");
+        }
+        for parameters in [
+            // Mixed sequences.
+            "mixed: [1, a]",
+            "mixed_numbers: [1, 2.5]",
+            "mixed_bools: [true, 1]",
+            // Nested sequences.
+            "nested: [[1]]",
+            // Integers out of range.
+            "huge: 18446744073709551615",
+            // Null values.
+            "null_value: ~",
+        ] {
+            let error = load(&with_parameters(parameters), &[]).unwrap_err();
+            assert_eq!(error.code, RclReturnCode::InvalidArgument, "{parameters}");
+        }
+        let current = [("bytes", ParameterValue::ByteArray(vec![]))];
+        for parameters in ["bytes: [256]", "bytes: [-1]", "bytes: [a]"] {
+            assert!(load(&with_parameters(parameters), &current).is_err());
+        }
+        // Files for other nodes are valid, but contain no parameters for this node.
+        let other = "other_node:\n  ros__parameters:\n    rate: 10\n";
+        assert_eq!(load(other, &[]).unwrap(), vec![]);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::rcl_bindings::*;
 
-use std::time::Duration;
+use core::time::Duration;
 
 /// The `HISTORY` DDS QoS policy.
 ///
@@ -23,6 +23,7 @@ use std::time::Duration;
 /// | KeepAll | KeepAll | yes |
 ///
 #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[non_exhaustive]
 pub enum QoSHistoryPolicy {
     /// Use the default policy of the RMW layer.
     ///
@@ -38,6 +39,16 @@ pub enum QoSHistoryPolicy {
     },
     /// Keep all messages, at least until other resource limits are exceeded.
     KeepAll,
+    /// A policy that `rclrs` does not know, with its raw `rmw` value.
+    ///
+    /// The history of endpoints of other participants is usually unknown, since it is not
+    /// communicated during discovery.
+    Unknown {
+        /// The raw value of the policy.
+        value: u32,
+        /// The length of the publisher/subscription queue.
+        depth: u32,
+    },
 }
 
 /// The `RELIABILITY` DDS QoS policy.
@@ -54,13 +65,22 @@ pub enum QoSHistoryPolicy {
 /// | BestEffort | BestEffort | yes | Best effort |
 ///
 #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[non_exhaustive]
 pub enum QoSReliabilityPolicy {
     /// Use the default policy of the RMW layer.
-    SystemDefault = 0,
+    SystemDefault,
     /// Guarantee delivery of messages.
-    Reliable = 1,
+    Reliable,
     /// Send messages but do not guarantee delivery.
-    BestEffort = 2,
+    BestEffort,
+    /// Match the majority of the publishers or subscriptions that exist when the entity is
+    /// created, while keeping the highest level of service possible.
+    ///
+    /// This is supported from Iron on. Older RMW implementations reject it.
+    BestAvailable,
+    /// A policy that `rclrs` does not know, with its raw `rmw` value, e.g. one of a newer RMW
+    /// version.
+    Unknown(u32),
 }
 
 /// The `DURABILITY` DDS QoS policy.
@@ -79,15 +99,24 @@ pub enum QoSReliabilityPolicy {
 /// | Volatile | Volatile | yes | Deliver only new messages |
 ///
 #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[non_exhaustive]
 pub enum QoSDurabilityPolicy {
     /// Use the default policy of the RMW layer.
-    SystemDefault = 0,
+    SystemDefault,
     /// Re-deliver old messages.
     /// - For publishers: Retain messages for later delivery.
     /// - For subscriptions: Request delivery of old messages.
-    TransientLocal = 1,
+    TransientLocal,
     /// Do not retain/request old messages.
-    Volatile = 2,
+    Volatile,
+    /// Match the majority of the publishers or subscriptions that exist when the entity is
+    /// created, while keeping the highest level of service possible.
+    ///
+    /// This is supported from Iron on. Older RMW implementations reject it.
+    BestAvailable,
+    /// A policy that `rclrs` does not know, with its raw `rmw` value, e.g. one of a newer RMW
+    /// version.
+    Unknown(u32),
 }
 
 /// The `LIVELINESS` DDS QoS policy.
@@ -104,15 +133,29 @@ pub enum QoSDurabilityPolicy {
 /// | ManualByTopic | ManualByTopic | yes |
 ///
 #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[non_exhaustive]
 pub enum QoSLivelinessPolicy {
     /// Use the default policy of the RMW layer.
-    SystemDefault = 0,
+    SystemDefault,
     /// The signal that establishes that a topic is alive comes from the ROS `rmw` layer.
-    Automatic = 1,
+    Automatic,
+    /// The signal that establishes that a topic is alive is sent for the whole node.
+    ///
+    /// This is deprecated in ROS 2, and only needed for reporting the policy of endpoints that
+    /// other client libraries created with it.
+    ManualByNode,
     /// The signal that establishes that a topic is alive is sent explicitly. Only publishing a message
     /// on the topic or an explicit signal from the application to assert liveliness on the topic
     /// will mark the topic as being alive.
-    ManualByTopic = 3,
+    ManualByTopic,
+    /// Match the majority of the publishers or subscriptions that exist when the entity is
+    /// created, while keeping the highest level of service possible.
+    ///
+    /// This is supported from Iron on. Older RMW implementations reject it.
+    BestAvailable,
+    /// A policy that `rclrs` does not know, with its raw `rmw` value, e.g. one of a newer RMW
+    /// version.
+    Unknown(u32),
 }
 
 /// A duration that can take two special values: System default and infinite.
@@ -123,10 +166,92 @@ pub enum QoSDuration {
     SystemDefault,
     /// This will act as an infinite duration.
     Infinite,
+    /// For a deadline or liveliness lease duration, match the majority of the publishers or
+    /// subscriptions that exist when the entity is created, see
+    /// [`QoSReliabilityPolicy::BestAvailable`].
+    BestAvailable,
     /// A specific duration.
     Custom(Duration),
 }
 
+// The best available policies, which are only in the bindings from Iron on. Their values are
+// the same in all distros, and older RMW implementations reject them as unknown.
+const RELIABILITY_BEST_AVAILABLE: rmw_qos_reliability_policy_t = rmw_qos_reliability_policy_t(4);
+const DURABILITY_BEST_AVAILABLE: rmw_qos_durability_policy_t = rmw_qos_durability_policy_t(4);
+const LIVELINESS_BEST_AVAILABLE: rmw_qos_liveliness_policy_t = rmw_qos_liveliness_policy_t(5);
+// See RMW_QOS_DEADLINE_BEST_AVAILABLE and RMW_QOS_LIVELINESS_LEASE_DURATION_BEST_AVAILABLE.
+const DURATION_BEST_AVAILABLE: (u64, u64) = (9223372036, 854775806);
+
+/// A kind of QoS policy, used for reporting which policy made two QoS profiles incompatible.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum QoSPolicyKind {
+    /// No or an unknown policy.
+    Invalid,
+    /// The durability policy.
+    Durability,
+    /// The deadline policy.
+    Deadline,
+    /// The liveliness policy.
+    Liveliness,
+    /// The reliability policy.
+    Reliability,
+    /// The history policy.
+    History,
+    /// The lifespan policy.
+    Lifespan,
+    /// The history depth.
+    Depth,
+    /// The liveliness lease duration.
+    LivelinessLeaseDuration,
+    /// The option to avoid ROS namespace conventions.
+    AvoidRosNamespaceConventions,
+}
+
+impl core::fmt::Display for QoSPolicyKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // The same names as in rclcpp's warnings
+        let name = match self {
+            Self::Invalid => "INVALID_QOS_POLICY",
+            Self::Durability => "DURABILITY_QOS_POLICY",
+            Self::Deadline => "DEADLINE_QOS_POLICY",
+            Self::Liveliness => "LIVELINESS_QOS_POLICY",
+            Self::Reliability => "RELIABILITY_QOS_POLICY",
+            Self::History => "HISTORY_QOS_POLICY",
+            Self::Lifespan => "LIFESPAN_QOS_POLICY",
+            Self::Depth => "DEPTH_QOS_POLICY",
+            Self::LivelinessLeaseDuration => "LIVELINESS_LEASE_DURATION_QOS_POLICY",
+            Self::AvoidRosNamespaceConventions => "AVOID_ROS_NAMESPACE_CONVENTION_QOS_POLICY",
+        };
+        f.write_str(name)
+    }
+}
+
+impl From<rmw_qos_policy_kind_t> for QoSPolicyKind {
+    // Older distros have fewer policy kinds, so the match is not exhaustive everywhere.
+    #[allow(unreachable_patterns)]
+    fn from(kind: rmw_qos_policy_kind_t) -> Self {
+        match kind {
+            rmw_qos_policy_kind_t::RMW_QOS_POLICY_DURABILITY => Self::Durability,
+            rmw_qos_policy_kind_t::RMW_QOS_POLICY_DEADLINE => Self::Deadline,
+            rmw_qos_policy_kind_t::RMW_QOS_POLICY_LIVELINESS => Self::Liveliness,
+            rmw_qos_policy_kind_t::RMW_QOS_POLICY_RELIABILITY => Self::Reliability,
+            rmw_qos_policy_kind_t::RMW_QOS_POLICY_HISTORY => Self::History,
+            rmw_qos_policy_kind_t::RMW_QOS_POLICY_LIFESPAN => Self::Lifespan,
+            #[cfg(not(any(ros_distro = "foxy", ros_distro = "galactic")))]
+            rmw_qos_policy_kind_t::RMW_QOS_POLICY_DEPTH => Self::Depth,
+            #[cfg(not(any(ros_distro = "foxy", ros_distro = "galactic")))]
+            rmw_qos_policy_kind_t::RMW_QOS_POLICY_LIVELINESS_LEASE_DURATION => {
+                Self::LivelinessLeaseDuration
+            }
+            #[cfg(not(any(ros_distro = "foxy", ros_distro = "galactic")))]
+            rmw_qos_policy_kind_t::RMW_QOS_POLICY_AVOID_ROS_NAMESPACE_CONVENTIONS => {
+                Self::AvoidRosNamespaceConventions
+            }
+            _ => Self::Invalid,
+        }
+    }
+}
+
 /// A Quality of Service profile.
 ///
 /// See [docs.ros.org][1] on Quality of Service settings in general.
@@ -187,6 +312,7 @@ impl From<QoSProfile> for rmw_qos_profile_t {
                 QoSHistoryPolicy::SystemDefault { depth } => depth as usize,
                 QoSHistoryPolicy::KeepLast { depth } => depth as usize,
                 QoSHistoryPolicy::KeepAll => 0,
+                QoSHistoryPolicy::Unknown { depth, .. } => depth as usize,
             },
             reliability: qos.reliability.into(),
             durability: qos.durability.into(),
@@ -199,6 +325,45 @@ impl From<QoSProfile> for rmw_qos_profile_t {
     }
 }
 
+impl From<&rmw_qos_profile_t> for QoSProfile {
+    /// Converts a profile that has been resolved by the RMW implementation, e.g. one returned by
+    /// [`Publisher::actual_qos`][1], or one of an endpoint in the ROS graph.
+    ///
+    /// Policy values that `rclrs` does not know, including the `UNKNOWN` values of `rmw`, are
+    /// kept as `Unknown`, so that converting the profile back gives the same `rmw` profile.
+    ///
+    /// [1]: crate::Publisher::actual_qos
+    fn from(qos: &rmw_qos_profile_t) -> Self {
+        let depth = u32::try_from(qos.depth).unwrap_or(u32::MAX);
+        Self {
+            history: QoSHistoryPolicy::from_rmw(qos.history, depth),
+            reliability: qos.reliability.into(),
+            durability: qos.durability.into(),
+            deadline: (&qos.deadline).into(),
+            lifespan: (&qos.lifespan).into(),
+            liveliness: qos.liveliness.into(),
+            liveliness_lease_duration: (&qos.liveliness_lease_duration).into(),
+            avoid_ros_namespace_conventions: qos.avoid_ros_namespace_conventions,
+        }
+    }
+}
+
+impl QoSHistoryPolicy {
+    fn from_rmw(policy: rmw_qos_history_policy_t, depth: u32) -> Self {
+        match policy {
+            rmw_qos_history_policy_t::RMW_QOS_POLICY_HISTORY_SYSTEM_DEFAULT => {
+                Self::SystemDefault { depth }
+            }
+            rmw_qos_history_policy_t::RMW_QOS_POLICY_HISTORY_KEEP_LAST => Self::KeepLast { depth },
+            rmw_qos_history_policy_t::RMW_QOS_POLICY_HISTORY_KEEP_ALL => Self::KeepAll,
+            other => Self::Unknown {
+                value: other.0 as u32,
+                depth,
+            },
+        }
+    }
+}
+
 impl From<QoSHistoryPolicy> for rmw_qos_history_policy_t {
     fn from(policy: QoSHistoryPolicy) -> Self {
         match policy {
@@ -209,6 +374,7 @@ impl From<QoSHistoryPolicy> for rmw_qos_history_policy_t {
                 rmw_qos_history_policy_t::RMW_QOS_POLICY_HISTORY_KEEP_LAST
             }
             QoSHistoryPolicy::KeepAll => rmw_qos_history_policy_t::RMW_QOS_POLICY_HISTORY_KEEP_ALL,
+            QoSHistoryPolicy::Unknown { value, .. } => rmw_qos_history_policy_t(value as _),
         }
     }
 }
@@ -225,6 +391,24 @@ impl From<QoSReliabilityPolicy> for rmw_qos_reliability_policy_t {
             QoSReliabilityPolicy::BestEffort => {
                 rmw_qos_reliability_policy_t::RMW_QOS_POLICY_RELIABILITY_BEST_EFFORT
             }
+            QoSReliabilityPolicy::BestAvailable => RELIABILITY_BEST_AVAILABLE,
+            QoSReliabilityPolicy::Unknown(value) => rmw_qos_reliability_policy_t(value as _),
+        }
+    }
+}
+
+impl From<rmw_qos_reliability_policy_t> for QoSReliabilityPolicy {
+    fn from(policy: rmw_qos_reliability_policy_t) -> Self {
+        match policy {
+            rmw_qos_reliability_policy_t::RMW_QOS_POLICY_RELIABILITY_SYSTEM_DEFAULT => {
+                Self::SystemDefault
+            }
+            rmw_qos_reliability_policy_t::RMW_QOS_POLICY_RELIABILITY_RELIABLE => Self::Reliable,
+            rmw_qos_reliability_policy_t::RMW_QOS_POLICY_RELIABILITY_BEST_EFFORT => {
+                Self::BestEffort
+            }
+            RELIABILITY_BEST_AVAILABLE => Self::BestAvailable,
+            other => Self::Unknown(other.0 as u32),
         }
     }
 }
@@ -241,6 +425,24 @@ impl From<QoSDurabilityPolicy> for rmw_qos_durability_policy_t {
             QoSDurabilityPolicy::Volatile => {
                 rmw_qos_durability_policy_t::RMW_QOS_POLICY_DURABILITY_VOLATILE
             }
+            QoSDurabilityPolicy::BestAvailable => DURABILITY_BEST_AVAILABLE,
+            QoSDurabilityPolicy::Unknown(value) => rmw_qos_durability_policy_t(value as _),
+        }
+    }
+}
+
+impl From<rmw_qos_durability_policy_t> for QoSDurabilityPolicy {
+    fn from(policy: rmw_qos_durability_policy_t) -> Self {
+        match policy {
+            rmw_qos_durability_policy_t::RMW_QOS_POLICY_DURABILITY_SYSTEM_DEFAULT => {
+                Self::SystemDefault
+            }
+            rmw_qos_durability_policy_t::RMW_QOS_POLICY_DURABILITY_TRANSIENT_LOCAL => {
+                Self::TransientLocal
+            }
+            rmw_qos_durability_policy_t::RMW_QOS_POLICY_DURABILITY_VOLATILE => Self::Volatile,
+            DURABILITY_BEST_AVAILABLE => Self::BestAvailable,
+            other => Self::Unknown(other.0 as u32),
         }
     }
 }
@@ -254,9 +456,33 @@ impl From<QoSLivelinessPolicy> for rmw_qos_liveliness_policy_t {
             QoSLivelinessPolicy::Automatic => {
                 rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_AUTOMATIC
             }
+            QoSLivelinessPolicy::ManualByNode => {
+                rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_MANUAL_BY_NODE
+            }
             QoSLivelinessPolicy::ManualByTopic => {
                 rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_MANUAL_BY_TOPIC
             }
+            QoSLivelinessPolicy::BestAvailable => LIVELINESS_BEST_AVAILABLE,
+            QoSLivelinessPolicy::Unknown(value) => rmw_qos_liveliness_policy_t(value as _),
+        }
+    }
+}
+
+impl From<rmw_qos_liveliness_policy_t> for QoSLivelinessPolicy {
+    fn from(policy: rmw_qos_liveliness_policy_t) -> Self {
+        match policy {
+            rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_SYSTEM_DEFAULT => {
+                Self::SystemDefault
+            }
+            rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_AUTOMATIC => Self::Automatic,
+            rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_MANUAL_BY_NODE => {
+                Self::ManualByNode
+            }
+            rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_MANUAL_BY_TOPIC => {
+                Self::ManualByTopic
+            }
+            LIVELINESS_BEST_AVAILABLE => Self::BestAvailable,
+            other => Self::Unknown(other.0 as u32),
         }
     }
 }
@@ -278,6 +504,119 @@ impl From<QoSDuration> for rmw_time_t {
                 sec: 9223372036,
                 nsec: 854775807,
             },
+            QoSDuration::BestAvailable => Self {
+                sec: DURATION_BEST_AVAILABLE.0,
+                nsec: DURATION_BEST_AVAILABLE.1,
+            },
+        }
+    }
+}
+
+impl From<&rmw_time_t> for QoSDuration {
+    fn from(time: &rmw_time_t) -> Self {
+        match (time.sec, time.nsec) {
+            // See RMW_DURATION_DEFAULT
+            (0, 0) => QoSDuration::SystemDefault,
+            // See RMW_DURATION_INFINITE
+            (9223372036, 854775807) => QoSDuration::Infinite,
+            DURATION_BEST_AVAILABLE => QoSDuration::BestAvailable,
+            (sec, nsec) => {
+                QoSDuration::Custom(Duration::from_secs(sec) + Duration::from_nanos(nsec))
+            }
+        }
+    }
+}
+
+// The lowercase policy names, as used in parameters and in YAML files.
+
+impl QoSHistoryPolicy {
+    pub(crate) fn from_name(name: &str, depth: u32) -> Option<Self> {
+        match name {
+            "system_default" => Some(Self::SystemDefault { depth }),
+            "keep_last" => Some(Self::KeepLast { depth }),
+            "keep_all" => Some(Self::KeepAll),
+            _ => None,
+        }
+    }
+
+    /// Returns `None` for unknown policies.
+    pub(crate) fn name(&self) -> Option<&'static str> {
+        match self {
+            Self::SystemDefault { .. } => Some("system_default"),
+            Self::KeepLast { .. } => Some("keep_last"),
+            Self::KeepAll => Some("keep_all"),
+            Self::Unknown { .. } => None,
+        }
+    }
+}
+
+impl QoSReliabilityPolicy {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "system_default" => Some(Self::SystemDefault),
+            "reliable" => Some(Self::Reliable),
+            "best_effort" => Some(Self::BestEffort),
+            "best_available" => Some(Self::BestAvailable),
+            _ => None,
+        }
+    }
+
+    /// Returns `None` for unknown policies.
+    pub(crate) fn name(&self) -> Option<&'static str> {
+        match self {
+            Self::SystemDefault => Some("system_default"),
+            Self::Reliable => Some("reliable"),
+            Self::BestEffort => Some("best_effort"),
+            Self::BestAvailable => Some("best_available"),
+            Self::Unknown(_) => None,
+        }
+    }
+}
+
+impl QoSDurabilityPolicy {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "system_default" => Some(Self::SystemDefault),
+            "transient_local" => Some(Self::TransientLocal),
+            "volatile" => Some(Self::Volatile),
+            "best_available" => Some(Self::BestAvailable),
+            _ => None,
+        }
+    }
+
+    /// Returns `None` for unknown policies.
+    pub(crate) fn name(&self) -> Option<&'static str> {
+        match self {
+            Self::SystemDefault => Some("system_default"),
+            Self::TransientLocal => Some("transient_local"),
+            Self::Volatile => Some("volatile"),
+            Self::BestAvailable => Some("best_available"),
+            Self::Unknown(_) => None,
+        }
+    }
+}
+
+impl QoSLivelinessPolicy {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "system_default" => Some(Self::SystemDefault),
+            "automatic" => Some(Self::Automatic),
+            "manual_by_node" => Some(Self::ManualByNode),
+            "manual_by_topic" => Some(Self::ManualByTopic),
+            "best_available" => Some(Self::BestAvailable),
+            _ => None,
+        }
+    }
+
+    /// Returns `None` for unknown policies.
+    pub(crate) fn name(&self) -> Option<&'static str> {
+        match self {
+            Self::SystemDefault => Some("system_default"),
+            Self::Automatic => Some("automatic"),
+            Self::ManualByNode => Some("manual_by_node"),
+            Self::ManualByTopic => Some("manual_by_topic"),
+            Self::BestAvailable => Some("best_available"),
+            Self::Unknown(_) => None,
         }
     }
 }
@@ -352,6 +691,21 @@ pub const QOS_PROFILE_PARAMETER_EVENTS: QoSProfile = QoSProfile {
     avoid_ros_namespace_conventions: false,
 };
 
+/// Equivalent to `rcl_qos_profile_rosout_default` from the [`rcl` package][1], which is used for
+/// the `/rosout` publisher of nodes.
+///
+/// [1]: https://github.com/ros2/rcl/blob/master/rcl/include/rcl/logging_rosout.h
+pub const QOS_PROFILE_ROSOUT_DEFAULT: QoSProfile = QoSProfile {
+    history: QoSHistoryPolicy::KeepLast { depth: 1000 },
+    reliability: QoSReliabilityPolicy::Reliable,
+    durability: QoSDurabilityPolicy::TransientLocal,
+    deadline: QoSDuration::SystemDefault,
+    lifespan: QoSDuration::Custom(Duration::from_secs(10)),
+    liveliness: QoSLivelinessPolicy::SystemDefault,
+    liveliness_lease_duration: QoSDuration::SystemDefault,
+    avoid_ros_namespace_conventions: false,
+};
+
 /// Equivalent to `rmw_qos_profile_system_default` from the [`rmw` package][1].
 ///
 /// [1]: https://github.com/ros2/rmw/blob/master/rmw/include/rmw/qos_profiles.h
@@ -0,0 +1,277 @@
+use crate::{
+    parameter::ParameterName, Node, ParameterValue, QoSDurabilityPolicy, QoSDuration,
+    QoSHistoryPolicy, QoSLivelinessPolicy, QoSPolicyKind, QoSProfile, QoSReliabilityPolicy,
+    RclReturnCode, RclrsError,
+};
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+/// Options for overriding the QoS profile of a publisher or subscription with parameters.
+///
+/// This is the same mechanism as in `rclcpp`: for each policy in `policy_kinds`, the parameter
+/// `qos_overrides.<topic>.<entity>.<policy>` can be set to override the policy, where `<topic>`
+/// is the fully expanded topic name, `<entity>` is `publisher` or `subscription`, and `<policy>`
+/// is e.g. `reliability`. If an `id` is given, `<entity>` becomes `publisher_<id>` or
+/// `subscription_<id>`, to distinguish several publishers or subscriptions on the same topic.
+///
+/// The values are taken from the parameter overrides of the node, i.e. from `--ros-args -p` or
+/// from parameter files, so they can be set by the user or in a launch file, e.g.
+/// ```text
+/// /my_node:
+///   ros__parameters:
+///     qos_overrides:
+///       /chatter:
+///         publisher:
+///           reliability: best_effort
+///           depth: 100
+/// ```
+///
+/// Policies are given by their lowercase names, e.g. `keep_last` or `transient_local`, the depth
+/// as an integer, and durations as integers in nanoseconds.
+///
+/// Like in `rclcpp`, the parameters are declared as read-only when the publisher or subscription
+/// is created, with the values of the resulting QoS profile, so that they can be listed with
+/// `ros2 param list`. They are undeclared again when the publisher or subscription is dropped.
+///
+/// By default, no policies can be overridden.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct QoSOverridingOptions {
+    /// The policies that can be overridden.
+    pub policy_kinds: Vec<QoSPolicyKind>,
+    /// An optional suffix for the parameter names.
+    pub id: Option<String>,
+}
+
+impl QoSOverridingOptions {
+    /// Allows overriding the history, depth and reliability policies, like
+    /// `rclcpp::QosOverridingOptions::with_default_policies()`.
+    pub fn with_default_policies() -> Self {
+        Self {
+            policy_kinds: alloc::vec![
+                QoSPolicyKind::History,
+                QoSPolicyKind::Depth,
+                QoSPolicyKind::Reliability,
+            ],
+            id: None,
+        }
+    }
+}
+
+fn invalid_override(name: &str, value: &ParameterValue) -> RclrsError {
+    RclrsError::with_message(
+        RclReturnCode::InvalidArgument,
+        format!("Invalid value {value:?} for QoS override parameter '{name}'"),
+    )
+}
+
+fn duration_from_nanoseconds(nanoseconds: i64) -> Option<QoSDuration> {
+    match nanoseconds {
+        // See RMW_DURATION_DEFAULT
+        0 => Some(QoSDuration::SystemDefault),
+        // See RMW_DURATION_INFINITE
+        i64::MAX => Some(QoSDuration::Infinite),
+        // See RMW_QOS_DEADLINE_BEST_AVAILABLE
+        ns if ns == i64::MAX - 1 => Some(QoSDuration::BestAvailable),
+        ns if ns < 0 => None,
+        ns => Some(QoSDuration::Custom(Duration::from_nanos(ns as u64))),
+    }
+}
+
+fn duration_to_nanoseconds(duration: QoSDuration) -> i64 {
+    match duration {
+        QoSDuration::SystemDefault => 0,
+        QoSDuration::Infinite => i64::MAX,
+        QoSDuration::BestAvailable => i64::MAX - 1,
+        QoSDuration::Custom(duration) => i64::try_from(duration.as_nanos()).unwrap_or(i64::MAX),
+    }
+}
+
+fn policy_name(kind: QoSPolicyKind) -> Option<&'static str> {
+    match kind {
+        QoSPolicyKind::History => Some("history"),
+        QoSPolicyKind::Depth => Some("depth"),
+        QoSPolicyKind::Reliability => Some("reliability"),
+        QoSPolicyKind::Durability => Some("durability"),
+        QoSPolicyKind::Deadline => Some("deadline"),
+        QoSPolicyKind::Lifespan => Some("lifespan"),
+        QoSPolicyKind::Liveliness => Some("liveliness"),
+        QoSPolicyKind::LivelinessLeaseDuration => Some("liveliness_lease_duration"),
+        QoSPolicyKind::AvoidRosNamespaceConventions => Some("avoid_ros_namespace_conventions"),
+        QoSPolicyKind::Invalid => None,
+    }
+}
+
+// Applies a single override to the profile, or returns None if the value is invalid.
+fn apply_override(qos: &mut QoSProfile, kind: QoSPolicyKind, value: &ParameterValue) -> Option<()> {
+    use ParameterValue::{Bool, Integer, String};
+    match (kind, value) {
+        (QoSPolicyKind::History, String(name)) => {
+            let depth = match qos.history {
+                QoSHistoryPolicy::SystemDefault { depth }
+                | QoSHistoryPolicy::KeepLast { depth }
+                | QoSHistoryPolicy::Unknown { depth, .. } => depth,
+                QoSHistoryPolicy::KeepAll => 0,
+            };
+            qos.history = QoSHistoryPolicy::from_name(name, depth)?;
+        }
+        (QoSPolicyKind::Depth, Integer(value)) => {
+            let value = u32::try_from(*value).ok()?;
+            match &mut qos.history {
+                QoSHistoryPolicy::SystemDefault { depth }
+                | QoSHistoryPolicy::KeepLast { depth }
+                | QoSHistoryPolicy::Unknown { depth, .. } => *depth = value,
+                // Like in rclcpp, the depth is ignored when keeping all messages.
+                QoSHistoryPolicy::KeepAll => {}
+            }
+        }
+        (QoSPolicyKind::Reliability, String(name)) => {
+            qos.reliability = QoSReliabilityPolicy::from_name(name)?;
+        }
+        (QoSPolicyKind::Durability, String(name)) => {
+            qos.durability = QoSDurabilityPolicy::from_name(name)?;
+        }
+        (QoSPolicyKind::Deadline, Integer(ns)) => {
+            qos.deadline = duration_from_nanoseconds(*ns)?;
+        }
+        (QoSPolicyKind::Lifespan, Integer(ns)) => {
+            qos.lifespan = duration_from_nanoseconds(*ns)?;
+        }
+        (QoSPolicyKind::Liveliness, String(name)) => {
+            qos.liveliness = QoSLivelinessPolicy::from_name(name)?;
+        }
+        (QoSPolicyKind::LivelinessLeaseDuration, Integer(ns)) => {
+            qos.liveliness_lease_duration = duration_from_nanoseconds(*ns)?;
+        }
+        (QoSPolicyKind::AvoidRosNamespaceConventions, Bool(value)) => {
+            qos.avoid_ros_namespace_conventions = *value;
+        }
+        _ => return None,
+    }
+    Some(())
+}
+
+// The value of a policy in the profile, in the form of an override, or None for unknown policies.
+fn policy_value(qos: &QoSProfile, kind: QoSPolicyKind) -> Option<ParameterValue> {
+    use ParameterValue::{Bool, Integer, String};
+    let value = match kind {
+        QoSPolicyKind::History => String(qos.history.name()?.into()),
+        QoSPolicyKind::Depth => match qos.history {
+            QoSHistoryPolicy::SystemDefault { depth }
+            | QoSHistoryPolicy::KeepLast { depth }
+            | QoSHistoryPolicy::Unknown { depth, .. } => Integer(depth.into()),
+            QoSHistoryPolicy::KeepAll => Integer(0),
+        },
+        QoSPolicyKind::Reliability => String(qos.reliability.name()?.into()),
+        QoSPolicyKind::Durability => String(qos.durability.name()?.into()),
+        QoSPolicyKind::Deadline => Integer(duration_to_nanoseconds(qos.deadline)),
+        QoSPolicyKind::Lifespan => Integer(duration_to_nanoseconds(qos.lifespan)),
+        QoSPolicyKind::Liveliness => String(qos.liveliness.name()?.into()),
+        QoSPolicyKind::LivelinessLeaseDuration => {
+            Integer(duration_to_nanoseconds(qos.liveliness_lease_duration))
+        }
+        QoSPolicyKind::AvoidRosNamespaceConventions => Bool(qos.avoid_ros_namespace_conventions),
+        QoSPolicyKind::Invalid => return None,
+    };
+    Some(value)
+}
+
+impl QoSOverridingOptions {
+    /// Applies the parameter overrides of the node to the QoS profile of a publisher or
+    /// subscription.
+    ///
+    /// `entity` is `"publisher"` or `"subscription"`, and `topic` is the expanded topic name.
+    ///
+    /// Returns the read-only parameters that are declared with the resulting values, which must be
+    /// kept for as long as the entity exists. Parameters that another entity on the same topic has
+    /// already declared are skipped.
+    pub(crate) fn apply(
+        &self,
+        node: &Node,
+        topic: &str,
+        entity: &str,
+        qos: &mut QoSProfile,
+    ) -> Result<Vec<ParameterName>, RclrsError> {
+        if self.policy_kinds.is_empty() {
+            return Ok(Vec::new());
+        }
+        let prefix = match &self.id {
+            Some(id) => format!("qos_overrides.{topic}.{entity}_{id}."),
+            None => format!("qos_overrides.{topic}.{entity}."),
+        };
+        let overrides = node.parameters.overrides();
+        // The history policy is applied before the depth, so that the depth is not lost.
+        let mut policy_kinds = self.policy_kinds.clone();
+        policy_kinds.sort_by_key(|&kind| kind != QoSPolicyKind::History);
+        let mut names = Vec::with_capacity(policy_kinds.len());
+        for kind in policy_kinds {
+            let name = match policy_name(kind) {
+                Some(policy) => format!("{prefix}{policy}"),
+                None => {
+                    return Err(RclrsError::with_message(
+                        RclReturnCode::InvalidArgument,
+                        "The invalid QoS policy kind can not be overridden",
+                    ))
+                }
+            };
+            if let Some(value) = overrides.get(&name) {
+                apply_override(qos, kind, value).ok_or_else(|| invalid_override(&name, value))?;
+            }
+            names.push((kind, name));
+        }
+        // The parameters are only declared once all overrides have been applied, since the
+        // history override may change the depth.
+        let mut parameters = Vec::with_capacity(names.len());
+        for (kind, name) in names {
+            let Some(value) = policy_value(qos, kind) else {
+                continue;
+            };
+            parameters.extend(node.parameters.declare_read_only_value(&name, value)?);
+        }
+        Ok(parameters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{QOS_PROFILE_DEFAULT, QOS_PROFILE_SENSOR_DATA};
+
+    const POLICY_KINDS: [QoSPolicyKind; 9] = [
+        QoSPolicyKind::History,
+        QoSPolicyKind::Depth,
+        QoSPolicyKind::Reliability,
+        QoSPolicyKind::Durability,
+        QoSPolicyKind::Deadline,
+        QoSPolicyKind::Lifespan,
+        QoSPolicyKind::Liveliness,
+        QoSPolicyKind::LivelinessLeaseDuration,
+        QoSPolicyKind::AvoidRosNamespaceConventions,
+    ];
+
+    #[test]
+    fn test_policy_values_round_trip() {
+        let mut source = QOS_PROFILE_SENSOR_DATA;
+        source.deadline = QoSDuration::Custom(Duration::from_millis(100));
+        source.lifespan = QoSDuration::Infinite;
+        source.liveliness_lease_duration = QoSDuration::BestAvailable;
+        source.avoid_ros_namespace_conventions = true;
+        let mut target = QOS_PROFILE_DEFAULT;
+        for kind in POLICY_KINDS {
+            let value = policy_value(&source, kind).unwrap();
+            assert!(apply_override(&mut target, kind, &value).is_some());
+        }
+        assert_eq!(target, source);
+    }
+
+    #[test]
+    fn test_unknown_policies_have_no_value() {
+        let mut qos = QOS_PROFILE_DEFAULT;
+        qos.reliability = QoSReliabilityPolicy::Unknown(42);
+        assert!(policy_value(&qos, QoSPolicyKind::Reliability).is_none());
+        assert!(policy_value(&qos, QoSPolicyKind::Invalid).is_none());
+        assert!(policy_value(&qos, QoSPolicyKind::Durability).is_some());
+    }
+}
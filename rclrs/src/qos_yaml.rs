@@ -0,0 +1,483 @@
+//! Parsing of QoS profiles in the YAML format of rosbag2's QoS override files.
+
+use crate::rcl_bindings::*;
+use crate::{
+    QoSDurabilityPolicy, QoSDuration, QoSHistoryPolicy, QoSLivelinessPolicy, QoSProfile,
+    QoSReliabilityPolicy, RclReturnCode, RclrsError, QOS_PROFILE_DEFAULT,
+};
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+// The profile as it is written by rosbag2. All keys are optional, and missing ones are taken from
+// the default profile.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct YamlProfile {
+    history: Option<Policy>,
+    depth: Option<u32>,
+    reliability: Option<Policy>,
+    durability: Option<Policy>,
+    deadline: Option<YamlDuration>,
+    lifespan: Option<YamlDuration>,
+    liveliness: Option<Policy>,
+    liveliness_lease_duration: Option<YamlDuration>,
+    avoid_ros_namespace_conventions: Option<bool>,
+}
+
+// Older versions of rosbag2 write policies as the integer values of the rmw enums, newer ones
+// as lowercase names.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum Policy {
+    Name(String),
+    Value(u32),
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct YamlDuration {
+    sec: u64,
+    nsec: u64,
+}
+
+fn invalid(msg: String) -> RclrsError {
+    RclrsError::with_message(RclReturnCode::InvalidArgument, msg)
+}
+
+impl Policy {
+    // Parses the policy by its name. Integer values are mapped to the rmw names first, and values
+    // without a known name are kept as unknown, like in the profiles of the rmw implementation.
+    fn parse<T>(
+        &self,
+        kind: &str,
+        values: &[&str],
+        from_name: impl Fn(&str) -> Option<T>,
+        unknown: impl FnOnce(u32) -> T,
+    ) -> Result<T, RclrsError> {
+        match self {
+            Policy::Name(name) => from_name(name).ok_or_else(|| unknown_policy(kind, name)),
+            Policy::Value(value) => Ok(values
+                .get(*value as usize)
+                .and_then(|name| from_name(name))
+                .unwrap_or_else(|| unknown(*value))),
+        }
+    }
+}
+
+fn unknown_policy(kind: &str, name: &str) -> RclrsError {
+    invalid(format!("Unknown {kind} policy '{name}'"))
+}
+
+impl YamlDuration {
+    fn into_qos_duration(self) -> QoSDuration {
+        let infinite = rmw_time_t::from(QoSDuration::Infinite);
+        let best_available = rmw_time_t::from(QoSDuration::BestAvailable);
+        match (self.sec, self.nsec) {
+            (0, 0) => QoSDuration::SystemDefault,
+            (sec, nsec) if sec == infinite.sec && nsec == infinite.nsec => QoSDuration::Infinite,
+            (sec, nsec) if sec == best_available.sec && nsec == best_available.nsec => {
+                QoSDuration::BestAvailable
+            }
+            // Older versions of rosbag2 write the infinite duration of DDS.
+            (0x7FFF_FFFF, 0xFFFF_FFFF) => QoSDuration::Infinite,
+            (sec, nsec) => {
+                QoSDuration::Custom(Duration::from_secs(sec) + Duration::from_nanos(nsec))
+            }
+        }
+    }
+}
+
+impl From<QoSDuration> for YamlDuration {
+    fn from(duration: QoSDuration) -> Self {
+        let time = rmw_time_t::from(duration);
+        Self {
+            sec: time.sec,
+            nsec: time.nsec,
+        }
+    }
+}
+
+impl From<&QoSProfile> for YamlProfile {
+    fn from(qos: &QoSProfile) -> Self {
+        // Unknown policies are written as their integer values, so that they can be read back.
+        let name = |name: &str| Some(Policy::Name(name.to_string()));
+        let (history, depth) = match qos.history {
+            QoSHistoryPolicy::SystemDefault { depth } => (name("system_default"), depth),
+            QoSHistoryPolicy::KeepLast { depth } => (name("keep_last"), depth),
+            QoSHistoryPolicy::KeepAll => (name("keep_all"), 0),
+            QoSHistoryPolicy::Unknown { value, depth } => (Some(Policy::Value(value)), depth),
+        };
+        Self {
+            history,
+            depth: Some(depth),
+            reliability: match qos.reliability {
+                QoSReliabilityPolicy::SystemDefault => name("system_default"),
+                QoSReliabilityPolicy::Reliable => name("reliable"),
+                QoSReliabilityPolicy::BestEffort => name("best_effort"),
+                QoSReliabilityPolicy::BestAvailable => name("best_available"),
+                QoSReliabilityPolicy::Unknown(value) => Some(Policy::Value(value)),
+            },
+            durability: match qos.durability {
+                QoSDurabilityPolicy::SystemDefault => name("system_default"),
+                QoSDurabilityPolicy::TransientLocal => name("transient_local"),
+                QoSDurabilityPolicy::Volatile => name("volatile"),
+                QoSDurabilityPolicy::BestAvailable => name("best_available"),
+                QoSDurabilityPolicy::Unknown(value) => Some(Policy::Value(value)),
+            },
+            deadline: Some(qos.deadline.into()),
+            lifespan: Some(qos.lifespan.into()),
+            liveliness: match qos.liveliness {
+                QoSLivelinessPolicy::SystemDefault => name("system_default"),
+                QoSLivelinessPolicy::Automatic => name("automatic"),
+                QoSLivelinessPolicy::ManualByNode => name("manual_by_node"),
+                QoSLivelinessPolicy::ManualByTopic => name("manual_by_topic"),
+                QoSLivelinessPolicy::BestAvailable => name("best_available"),
+                QoSLivelinessPolicy::Unknown(value) => Some(Policy::Value(value)),
+            },
+            liveliness_lease_duration: Some(qos.liveliness_lease_duration.into()),
+            avoid_ros_namespace_conventions: Some(qos.avoid_ros_namespace_conventions),
+        }
+    }
+}
+
+impl TryFrom<YamlProfile> for QoSProfile {
+    type Error = RclrsError;
+
+    fn try_from(yaml: YamlProfile) -> Result<Self, Self::Error> {
+        let default = QOS_PROFILE_DEFAULT;
+        let depth = yaml.depth.unwrap_or(match default.history {
+            QoSHistoryPolicy::SystemDefault { depth }
+            | QoSHistoryPolicy::KeepLast { depth }
+            | QoSHistoryPolicy::Unknown { depth, .. } => depth,
+            _ => 0,
+        });
+        let history = match &yaml.history {
+            None => QoSHistoryPolicy::KeepLast { depth },
+            Some(policy) => policy.parse(
+                "history",
+                &["system_default", "keep_last", "keep_all"],
+                |name| QoSHistoryPolicy::from_name(name, depth),
+                |value| QoSHistoryPolicy::Unknown { value, depth },
+            )?,
+        };
+        let reliability = match &yaml.reliability {
+            None => default.reliability,
+            Some(policy) => policy.parse(
+                "reliability",
+                &[
+                    "system_default",
+                    "reliable",
+                    "best_effort",
+                    "unknown",
+                    "best_available",
+                ],
+                QoSReliabilityPolicy::from_name,
+                QoSReliabilityPolicy::Unknown,
+            )?,
+        };
+        let durability = match &yaml.durability {
+            None => default.durability,
+            Some(policy) => policy.parse(
+                "durability",
+                &[
+                    "system_default",
+                    "transient_local",
+                    "volatile",
+                    "unknown",
+                    "best_available",
+                ],
+                QoSDurabilityPolicy::from_name,
+                QoSDurabilityPolicy::Unknown,
+            )?,
+        };
+        let liveliness = match &yaml.liveliness {
+            None => default.liveliness,
+            Some(policy) => policy.parse(
+                "liveliness",
+                &[
+                    "system_default",
+                    "automatic",
+                    "manual_by_node",
+                    "manual_by_topic",
+                    "unknown",
+                    "best_available",
+                ],
+                QoSLivelinessPolicy::from_name,
+                QoSLivelinessPolicy::Unknown,
+            )?,
+        };
+        Ok(QoSProfile {
+            history,
+            reliability,
+            durability,
+            deadline: yaml
+                .deadline
+                .map_or(default.deadline, YamlDuration::into_qos_duration),
+            lifespan: yaml
+                .lifespan
+                .map_or(default.lifespan, YamlDuration::into_qos_duration),
+            liveliness,
+            liveliness_lease_duration: yaml.liveliness_lease_duration.map_or(
+                default.liveliness_lease_duration,
+                YamlDuration::into_qos_duration,
+            ),
+            avoid_ros_namespace_conventions: yaml
+                .avoid_ros_namespace_conventions
+                .unwrap_or(default.avoid_ros_namespace_conventions),
+        })
+    }
+}
+
+/// Parses a single QoS profile from YAML, in the format of rosbag2's QoS override files.
+///
+/// Policies are given by their lowercase names, e.g. `keep_last`, or by the integer values of the
+/// `rmw` enums, which older versions of rosbag2 write. Integer values without a name are kept as
+/// `Unknown`. Durations are mappings with `sec` and
+/// `nsec` keys. Missing keys are taken from [`QOS_PROFILE_DEFAULT`][1].
+///
+/// Returns an [`InvalidArgument`][2] error with a message if the YAML can not be parsed.
+///
+/// # Example
+/// ```
+/// # use rclrs::{QoSDurabilityPolicy, QoSProfile, RclrsError};
+/// let qos: QoSProfile = "
+/// history: keep_last
+/// depth: 1
+/// durability: transient_local
+/// deadline:
+///   sec: 0
+///   nsec: 500000000
+/// ".parse()?;
+/// assert_eq!(qos.durability, QoSDurabilityPolicy::TransientLocal);
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::QOS_PROFILE_DEFAULT
+/// [2]: crate::RclReturnCode::InvalidArgument
+impl FromStr for QoSProfile {
+    type Err = RclrsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let yaml: YamlProfile =
+            serde_yaml::from_str(s).map_err(|e| invalid(format!("Invalid QoS profile: {e}")))?;
+        yaml.try_into()
+    }
+}
+
+impl QoSProfile {
+    /// Formats the profile as YAML, in the format that [`FromStr`] parses. All policies are
+    /// written explicitly. This requires the `yaml` feature.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{QoSProfile, RclrsError, QOS_PROFILE_SENSOR_DATA};
+    /// let yaml = QOS_PROFILE_SENSOR_DATA.to_yaml();
+    /// assert!(yaml.contains("reliability: best_effort"));
+    /// assert_eq!(yaml.parse::<QoSProfile>()?, QOS_PROFILE_SENSOR_DATA);
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn to_yaml(&self) -> String {
+        // Serializing these types can not fail.
+        serde_yaml::to_string(&YamlProfile::from(self)).unwrap_or_default()
+    }
+
+    /// Parses a rosbag2 QoS override file, which maps topic names to QoS profiles.
+    ///
+    /// See the [`FromStr`] implementation for the format of the profiles. This requires the
+    /// `yaml` feature.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{QoSProfile, QoSReliabilityPolicy, RclrsError};
+    /// let overrides = QoSProfile::overrides_from_yaml("
+    /// /scan:
+    ///   reliability: best_effort
+    /// /map:
+    ///   durability: transient_local
+    /// ")?;
+    /// assert_eq!(overrides["/scan"].reliability, QoSReliabilityPolicy::BestEffort);
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn overrides_from_yaml(yaml: &str) -> Result<BTreeMap<String, QoSProfile>, RclrsError> {
+        let profiles: BTreeMap<String, YamlProfile> = serde_yaml::from_str(yaml)
+            .map_err(|e| invalid(format!("Invalid QoS override file: {e}")))?;
+        profiles
+            .into_iter()
+            .map(|(topic, profile)| Ok((topic, profile.try_into()?)))
+            .collect()
+    }
+}
+
+/// Formats a list of QoS profiles as YAML, like the offered QoS profiles in rosbag2's metadata.
+#[cfg(feature = "rosbag")]
+pub(crate) fn qos_profiles_to_yaml(profiles: &[QoSProfile]) -> String {
+    let profiles: Vec<YamlProfile> = profiles.iter().map(YamlProfile::from).collect();
+    // Serializing these types can not fail.
+    serde_yaml::to_string(&profiles).unwrap_or_default()
+}
+
+/// Parses a list of QoS profiles in YAML, like the offered QoS profiles in rosbag2's metadata.
+#[cfg(feature = "rosbag")]
+pub(crate) fn qos_profiles_from_yaml(yaml: &str) -> Result<Vec<QoSProfile>, RclrsError> {
+    let profiles: Vec<YamlProfile> =
+        serde_yaml::from_str(yaml).map_err(|e| invalid(format!("Invalid QoS profiles: {e}")))?;
+    profiles.into_iter().map(QoSProfile::try_from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::QOS_PROFILE_SENSOR_DATA;
+
+    #[test]
+    fn test_parse_names() {
+        let qos: QoSProfile = "
+history: keep_all
+reliability: best_effort
+durability: transient_local
+liveliness: manual_by_topic
+avoid_ros_namespace_conventions: true
+"
+        .parse()
+        .unwrap();
+        assert_eq!(qos.history, QoSHistoryPolicy::KeepAll);
+        assert_eq!(qos.reliability, QoSReliabilityPolicy::BestEffort);
+        assert_eq!(qos.durability, QoSDurabilityPolicy::TransientLocal);
+        assert_eq!(qos.liveliness, QoSLivelinessPolicy::ManualByTopic);
+        assert!(qos.avoid_ros_namespace_conventions);
+    }
+
+    #[test]
+    fn test_parse_integer_values() {
+        let qos: QoSProfile = "
+history: 1
+depth: 7
+reliability: 2
+durability: 1
+liveliness: 2
+"
+        .parse()
+        .unwrap();
+        assert_eq!(qos.history, QoSHistoryPolicy::KeepLast { depth: 7 });
+        assert_eq!(qos.reliability, QoSReliabilityPolicy::BestEffort);
+        assert_eq!(qos.durability, QoSDurabilityPolicy::TransientLocal);
+        assert_eq!(qos.liveliness, QoSLivelinessPolicy::ManualByNode);
+    }
+
+    #[test]
+    fn test_missing_keys_are_default() {
+        let qos: QoSProfile = "depth: 3".parse().unwrap();
+        assert_eq!(qos.history, QoSHistoryPolicy::KeepLast { depth: 3 });
+        assert_eq!(qos.reliability, QOS_PROFILE_DEFAULT.reliability);
+        assert_eq!(qos.durability, QOS_PROFILE_DEFAULT.durability);
+        assert_eq!(qos.deadline, QOS_PROFILE_DEFAULT.deadline);
+        assert_eq!("{}".parse::<QoSProfile>().unwrap(), QOS_PROFILE_DEFAULT);
+    }
+
+    #[test]
+    fn test_best_available() {
+        let qos: QoSProfile = "
+reliability: best_available
+durability: 4
+liveliness: best_available
+deadline:
+  sec: 9223372036
+  nsec: 854775806
+"
+        .parse()
+        .unwrap();
+        assert_eq!(qos.reliability, QoSReliabilityPolicy::BestAvailable);
+        assert_eq!(qos.durability, QoSDurabilityPolicy::BestAvailable);
+        assert_eq!(qos.liveliness, QoSLivelinessPolicy::BestAvailable);
+        assert_eq!(qos.deadline, QoSDuration::BestAvailable);
+        assert_eq!(qos.to_yaml().parse::<QoSProfile>().unwrap(), qos);
+    }
+
+    #[test]
+    fn test_unknown_policies_round_trip() {
+        let qos = QoSProfile {
+            history: QoSHistoryPolicy::Unknown {
+                value: 3,
+                depth: 10,
+            },
+            reliability: QoSReliabilityPolicy::Unknown(3),
+            durability: QoSDurabilityPolicy::Unknown(42),
+            liveliness: QoSLivelinessPolicy::Unknown(4),
+            ..QOS_PROFILE_DEFAULT
+        };
+        assert_eq!(qos.to_yaml().parse::<QoSProfile>().unwrap(), qos);
+    }
+
+    #[test]
+    fn test_durations() {
+        let qos: QoSProfile = "
+deadline:
+  sec: 0
+  nsec: 0
+lifespan:
+  sec: 2147483647
+  nsec: 4294967295
+liveliness_lease_duration:
+  sec: 1
+  nsec: 500000000
+"
+        .parse()
+        .unwrap();
+        assert_eq!(qos.deadline, QoSDuration::SystemDefault);
+        assert_eq!(qos.lifespan, QoSDuration::Infinite);
+        assert_eq!(
+            qos.liveliness_lease_duration,
+            QoSDuration::Custom(Duration::from_millis(1500))
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for qos in [QOS_PROFILE_DEFAULT, QOS_PROFILE_SENSOR_DATA] {
+            assert_eq!(qos.to_yaml().parse::<QoSProfile>().unwrap(), qos);
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        let errors = [
+            "reliability: sometimes",
+            "history: unknown",
+            "depth: -1",
+            "colour: blue",
+            "deadline: {sec: 1}",
+            "- reliable",
+        ];
+        for yaml in errors {
+            let error = yaml.parse::<QoSProfile>().unwrap_err();
+            assert_eq!(error.code, RclReturnCode::InvalidArgument, "{yaml}");
+            assert!(error.msg.is_some(), "{yaml}");
+        }
+    }
+
+    #[test]
+    fn test_overrides_from_yaml() {
+        let overrides = QoSProfile::overrides_from_yaml(
+            "
+/scan:
+  reliability: best_effort
+/map:
+  durability: transient_local
+",
+        )
+        .unwrap();
+        assert_eq!(overrides.len(), 2);
+        assert_eq!(
+            overrides["/scan"].reliability,
+            QoSReliabilityPolicy::BestEffort
+        );
+        assert_eq!(
+            overrides["/map"].durability,
+            QoSDurabilityPolicy::TransientLocal
+        );
+        assert!(QoSProfile::overrides_from_yaml("/scan: 1").is_err());
+    }
+}
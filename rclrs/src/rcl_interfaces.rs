@@ -0,0 +1,358 @@
+//! Typed `rcl_interfaces` messages, with conversions to and from the types of `rclrs`.
+//!
+//! These are the RMW-native types of the messages, with the same layout as the types in the
+//! `rmw` module of the generated `rcl_interfaces` crate. They can be published and subscribed to
+//! like any other message, e.g. `Publisher<rclrs::rcl_interfaces::Log>` on `/rosout`, without
+//! converting between the [`ParameterValue`][1] enum or the [`LogRecord`][2]s of `rclrs` and the
+//! fields of the messages by hand.
+//!
+//! # Example
+//! ```
+//! # use rclrs::{rcl_interfaces, ParameterValue, RclrsError};
+//! let msg = rcl_interfaces::ParameterValue::from(ParameterValue::Integer(42));
+//! assert_eq!(msg.type_, rcl_interfaces::PARAMETER_INTEGER);
+//! assert_eq!(ParameterValue::try_from(&msg)?, ParameterValue::Integer(42));
+//! # Ok::<(), RclrsError>(())
+//! ```
+//!
+//! [1]: crate::ParameterValue
+//! [2]: crate::LogRecord
+
+use crate::rosidl_macros::{impl_message, impl_sequence_alloc};
+use crate::{ClockType, LogLocation, LogRecord, LogSeverity, RclReturnCode, RclrsError, Time};
+
+use alloc::format;
+use alloc::string::{String, ToString};
+
+use rosidl_runtime_rs::Sequence;
+
+/// The value of the `type_` field of an unset [`ParameterValue`].
+pub const PARAMETER_NOT_SET: u8 = 0;
+/// The value of the `type_` field of a [`ParameterValue`] with a `bool_value`.
+pub const PARAMETER_BOOL: u8 = 1;
+/// The value of the `type_` field of a [`ParameterValue`] with an `integer_value`.
+pub const PARAMETER_INTEGER: u8 = 2;
+/// The value of the `type_` field of a [`ParameterValue`] with a `double_value`.
+pub const PARAMETER_DOUBLE: u8 = 3;
+/// The value of the `type_` field of a [`ParameterValue`] with a `string_value`.
+pub const PARAMETER_STRING: u8 = 4;
+/// The value of the `type_` field of a [`ParameterValue`] with a `byte_array_value`.
+pub const PARAMETER_BYTE_ARRAY: u8 = 5;
+/// The value of the `type_` field of a [`ParameterValue`] with a `bool_array_value`.
+pub const PARAMETER_BOOL_ARRAY: u8 = 6;
+/// The value of the `type_` field of a [`ParameterValue`] with an `integer_array_value`.
+pub const PARAMETER_INTEGER_ARRAY: u8 = 7;
+/// The value of the `type_` field of a [`ParameterValue`] with a `double_array_value`.
+pub const PARAMETER_DOUBLE_ARRAY: u8 = 8;
+/// The value of the `type_` field of a [`ParameterValue`] with a `string_array_value`.
+pub const PARAMETER_STRING_ARRAY: u8 = 9;
+
+/// An `rcl_interfaces/msg/ParameterValue`.
+///
+/// Only the field that corresponds to the `type_` is meaningful.
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+#[allow(missing_docs)]
+pub struct ParameterValue {
+    pub type_: u8,
+    pub bool_value: bool,
+    pub integer_value: i64,
+    pub double_value: f64,
+    pub string_value: rosidl_runtime_rs::String,
+    pub byte_array_value: Sequence<u8>,
+    pub bool_array_value: Sequence<bool>,
+    pub integer_array_value: Sequence<i64>,
+    pub double_array_value: Sequence<f64>,
+    pub string_array_value: Sequence<rosidl_runtime_rs::String>,
+}
+
+impl_message!(
+    ParameterValue,
+    "rcl_interfaces/msg/ParameterValue",
+    rcl_interfaces__msg__ParameterValue__init,
+    rosidl_typesupport_c__get_message_type_support_handle__rcl_interfaces__msg__ParameterValue
+);
+impl_sequence_alloc!(
+    ParameterValue,
+    rcl_interfaces__msg__ParameterValue__Sequence__init,
+    rcl_interfaces__msg__ParameterValue__Sequence__fini
+);
+
+impl ParameterValue {
+    /// Converts the message, or returns `None` if it is unset.
+    ///
+    /// Returns the reason as the error if the `type_` is invalid.
+    pub(crate) fn to_value(&self) -> Result<Option<crate::ParameterValue>, String> {
+        use crate::ParameterValue as V;
+        let value = match self.type_ {
+            PARAMETER_NOT_SET => return Ok(None),
+            PARAMETER_BOOL => V::Bool(self.bool_value),
+            PARAMETER_INTEGER => V::Integer(self.integer_value),
+            PARAMETER_DOUBLE => V::Double(self.double_value),
+            PARAMETER_STRING => V::String(self.string_value.to_string()),
+            PARAMETER_BYTE_ARRAY => V::ByteArray(self.byte_array_value.to_vec()),
+            PARAMETER_BOOL_ARRAY => V::BoolArray(self.bool_array_value.to_vec()),
+            PARAMETER_INTEGER_ARRAY => V::IntegerArray(self.integer_array_value.to_vec()),
+            PARAMETER_DOUBLE_ARRAY => V::DoubleArray(self.double_array_value.to_vec()),
+            PARAMETER_STRING_ARRAY => V::StringArray(
+                self.string_array_value
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+            ),
+            type_ => return Err(format!("Invalid parameter type {type_}")),
+        };
+        Ok(Some(value))
+    }
+}
+
+impl From<&crate::ParameterValue> for ParameterValue {
+    fn from(value: &crate::ParameterValue) -> Self {
+        use crate::ParameterValue as V;
+        let mut msg = Self {
+            type_: kind_to_type(value.kind()),
+            ..Default::default()
+        };
+        match value {
+            V::Bool(value) => msg.bool_value = *value,
+            V::Integer(value) => msg.integer_value = *value,
+            V::Double(value) => msg.double_value = *value,
+            V::String(value) => msg.string_value = value.as_str().into(),
+            V::ByteArray(values) => msg.byte_array_value = values.as_slice().into(),
+            V::BoolArray(values) => msg.bool_array_value = values.as_slice().into(),
+            V::IntegerArray(values) => msg.integer_array_value = values.as_slice().into(),
+            V::DoubleArray(values) => msg.double_array_value = values.as_slice().into(),
+            V::StringArray(values) => {
+                msg.string_array_value = values.iter().map(|value| value.as_str().into()).collect()
+            }
+        }
+        msg
+    }
+}
+
+impl From<crate::ParameterValue> for ParameterValue {
+    fn from(value: crate::ParameterValue) -> Self {
+        Self::from(&value)
+    }
+}
+
+/// Fails for an unset value, or an invalid `type_`.
+impl TryFrom<&ParameterValue> for crate::ParameterValue {
+    type Error = RclrsError;
+
+    fn try_from(msg: &ParameterValue) -> Result<Self, Self::Error> {
+        match msg.to_value() {
+            Ok(Some(value)) => Ok(value),
+            Ok(None) => Err(RclrsError::with_message(
+                RclReturnCode::InvalidArgument,
+                "The parameter value is not set",
+            )),
+            Err(reason) => Err(RclrsError::with_message(
+                RclReturnCode::InvalidArgument,
+                reason,
+            )),
+        }
+    }
+}
+
+/// Returns the value of the `type_` field of a [`ParameterValue`] for a kind of parameter.
+pub(crate) fn kind_to_type(kind: crate::ParameterKind) -> u8 {
+    use crate::ParameterKind as K;
+    match kind {
+        K::Bool => PARAMETER_BOOL,
+        K::Integer => PARAMETER_INTEGER,
+        K::Double => PARAMETER_DOUBLE,
+        K::String => PARAMETER_STRING,
+        K::ByteArray => PARAMETER_BYTE_ARRAY,
+        K::BoolArray => PARAMETER_BOOL_ARRAY,
+        K::IntegerArray => PARAMETER_INTEGER_ARRAY,
+        K::DoubleArray => PARAMETER_DOUBLE_ARRAY,
+        K::StringArray => PARAMETER_STRING_ARRAY,
+    }
+}
+
+/// An `rcl_interfaces/msg/Parameter`, i.e. a named [`ParameterValue`].
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+#[allow(missing_docs)]
+pub struct Parameter {
+    pub name: rosidl_runtime_rs::String,
+    pub value: ParameterValue,
+}
+
+impl_message!(
+    Parameter,
+    "rcl_interfaces/msg/Parameter",
+    rcl_interfaces__msg__Parameter__init,
+    rosidl_typesupport_c__get_message_type_support_handle__rcl_interfaces__msg__Parameter
+);
+impl_sequence_alloc!(
+    Parameter,
+    rcl_interfaces__msg__Parameter__Sequence__init,
+    rcl_interfaces__msg__Parameter__Sequence__fini
+);
+
+/// An `rcl_interfaces/msg/SetParametersResult`, which converts to and from a
+/// `Result<(), String>` with the reason for an unsuccessful result.
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+#[allow(missing_docs)]
+pub struct SetParametersResult {
+    pub successful: bool,
+    pub reason: rosidl_runtime_rs::String,
+}
+
+impl_message!(
+    SetParametersResult,
+    "rcl_interfaces/msg/SetParametersResult",
+    rcl_interfaces__msg__SetParametersResult__init,
+    rosidl_typesupport_c__get_message_type_support_handle__rcl_interfaces__msg__SetParametersResult
+);
+impl_sequence_alloc!(
+    SetParametersResult,
+    rcl_interfaces__msg__SetParametersResult__Sequence__init,
+    rcl_interfaces__msg__SetParametersResult__Sequence__fini
+);
+
+impl From<Result<(), String>> for SetParametersResult {
+    fn from(result: Result<(), String>) -> Self {
+        match result {
+            Ok(()) => Self {
+                successful: true,
+                reason: Default::default(),
+            },
+            Err(reason) => Self {
+                successful: false,
+                reason: reason.as_str().into(),
+            },
+        }
+    }
+}
+
+impl From<&SetParametersResult> for Result<(), String> {
+    fn from(msg: &SetParametersResult) -> Self {
+        if msg.successful {
+            Ok(())
+        } else {
+            Err(msg.reason.to_string())
+        }
+    }
+}
+
+/// A `builtin_interfaces/msg/Time`, e.g. the stamp of a [`Log`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[allow(missing_docs)]
+pub struct BuiltinTime {
+    pub sec: i32,
+    pub nanosec: u32,
+}
+
+impl From<Time> for BuiltinTime {
+    fn from(time: Time) -> Self {
+        let (sec, nanosec) = time.to_sec_nanosec();
+        Self { sec, nanosec }
+    }
+}
+
+/// An `rcl_interfaces/msg/Log`, as published on `/rosout`.
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+#[allow(missing_docs)]
+pub struct Log {
+    pub stamp: BuiltinTime,
+    pub level: u8,
+    pub name: rosidl_runtime_rs::String,
+    pub msg: rosidl_runtime_rs::String,
+    pub file: rosidl_runtime_rs::String,
+    pub function: rosidl_runtime_rs::String,
+    pub line: u32,
+}
+
+impl_message!(
+    Log,
+    "rcl_interfaces/msg/Log",
+    rcl_interfaces__msg__Log__init,
+    rosidl_typesupport_c__get_message_type_support_handle__rcl_interfaces__msg__Log
+);
+
+impl Log {
+    /// The `level` of debug messages.
+    pub const DEBUG: u8 = 10;
+    /// The `level` of informational messages.
+    pub const INFO: u8 = 20;
+    /// The `level` of warnings.
+    pub const WARN: u8 = 30;
+    /// The `level` of errors.
+    pub const ERROR: u8 = 40;
+    /// The `level` of fatal errors.
+    pub const FATAL: u8 = 50;
+}
+
+impl From<LogSeverity> for u8 {
+    fn from(severity: LogSeverity) -> Self {
+        match severity {
+            LogSeverity::Unset => 0,
+            LogSeverity::Debug => Log::DEBUG,
+            LogSeverity::Info => Log::INFO,
+            LogSeverity::Warn => Log::WARN,
+            LogSeverity::Error => Log::ERROR,
+            LogSeverity::Fatal => Log::FATAL,
+        }
+    }
+}
+
+impl From<&LogRecord<'_>> for Log {
+    fn from(record: &LogRecord<'_>) -> Self {
+        let mut msg = Self {
+            stamp: record.timestamp.into(),
+            level: record.severity.into(),
+            name: record.logger_name.into(),
+            msg: record.message.into(),
+            ..Default::default()
+        };
+        if let Some(location) = record.location {
+            msg.file = location.file_name.into();
+            msg.function = location.function_name.into();
+            msg.line = u32::try_from(location.line_number).unwrap_or(u32::MAX);
+        }
+        msg
+    }
+}
+
+/// Fails if a string of the message is not valid UTF-8. The location is `None` if the message
+/// has no file name.
+impl<'a> TryFrom<&'a Log> for LogRecord<'a> {
+    type Error = RclrsError;
+
+    fn try_from(msg: &'a Log) -> Result<Self, Self::Error> {
+        let to_str = |s: &'a rosidl_runtime_rs::String| {
+            core::str::from_utf8(s).map_err(|_| {
+                RclrsError::with_message(
+                    RclReturnCode::InvalidArgument,
+                    "The log message contains invalid UTF-8",
+                )
+            })
+        };
+        let location = if msg.file.is_empty() {
+            None
+        } else {
+            Some(LogLocation {
+                function_name: to_str(&msg.function)?,
+                file_name: to_str(&msg.file)?,
+                line_number: msg.line as usize,
+            })
+        };
+        Ok(LogRecord {
+            severity: LogSeverity::from_rcutils(msg.level.into()),
+            logger_name: to_str(&msg.name)?,
+            message: to_str(&msg.msg)?,
+            timestamp: Time::from_sec_nanosec(
+                msg.stamp.sec,
+                msg.stamp.nanosec,
+                ClockType::SystemTime,
+            ),
+            location,
+        })
+    }
+}
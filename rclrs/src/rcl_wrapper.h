@@ -1,2 +1,17 @@
+#include <rcl/expand_topic_name.h>
 #include <rcl/rcl.h>
-#include <rcutils/error_handling.h>
\ No newline at end of file
+#include <rcl_yaml_param_parser/parser.h>
+#include <rcutils/error_handling.h>
+#include <rcutils/logging.h>
+#include <rcutils/types/char_array.h>
+#include <rcutils/types/string_map.h>
+#ifdef RCLRS_TYPE_DESCRIPTION
+#include <type_description_interfaces/srv/get_type_description.h>
+#endif
+#ifdef RCLRS_DYN_MSG
+#include <rosidl_typesupport_introspection_c/field_types.h>
+#include <rosidl_typesupport_introspection_c/message_introspection.h>
+#endif
+#ifdef RCLRS_TRACETOOLS
+#include <tracetools/tracetools.h>
+#endif
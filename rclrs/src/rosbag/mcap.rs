@@ -0,0 +1,446 @@
+//! A minimal reader and writer for the [MCAP][1] container format, which rosbag2 uses by default.
+//!
+//! The files are written without chunks, compression and a summary section. rosbag2 and other
+//! MCAP readers scan such files instead of using the index. The reader also reads the chunks that
+//! rosbag2 writes, as long as they are not compressed.
+//!
+//! [1]: https://mcap.dev/spec
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8] = b"\x89MCAP0\r\n";
+
+// The record opcodes.
+const OP_HEADER: u8 = 0x01;
+const OP_FOOTER: u8 = 0x02;
+const OP_SCHEMA: u8 = 0x03;
+const OP_CHANNEL: u8 = 0x04;
+const OP_MESSAGE: u8 = 0x05;
+const OP_CHUNK: u8 = 0x06;
+const OP_DATA_END: u8 = 0x0F;
+
+// Builds the content of a record, with the primitive types of the format.
+#[derive(Default)]
+struct Record(Vec<u8>);
+
+impl Record {
+    fn u16(mut self, value: u16) -> Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn u32(mut self, value: u32) -> Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn u64(mut self, value: u64) -> Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn string(self, value: &str) -> Self {
+        self.bytes(value.as_bytes())
+    }
+
+    // Bytes with a length prefix.
+    fn bytes(self, value: &[u8]) -> Self {
+        self.u32(value.len() as u32).raw(value)
+    }
+
+    // Bytes that extend until the end of the record.
+    fn raw(mut self, value: &[u8]) -> Self {
+        self.0.extend_from_slice(value);
+        self
+    }
+
+    fn map(self, value: &BTreeMap<String, String>) -> Self {
+        let entries = value.iter().fold(Record::default(), |entries, (k, v)| {
+            entries.string(k).string(v)
+        });
+        self.bytes(&entries.0)
+    }
+}
+
+/// Writes the messages of a bag to an MCAP file.
+pub(crate) struct McapWriter {
+    file: BufWriter<File>,
+    next_schema_id: u16,
+    next_channel_id: u16,
+}
+
+impl McapWriter {
+    /// Creates the file, which must not exist yet, and writes the header.
+    pub(crate) fn create(path: &Path) -> io::Result<Self> {
+        let file = File::options().write(true).create_new(true).open(path)?;
+        let mut writer = Self {
+            file: BufWriter::new(file),
+            // The ID 0 means that a channel has no schema.
+            next_schema_id: 1,
+            next_channel_id: 0,
+        };
+        writer.file.write_all(MAGIC)?;
+        let header = Record::default().string("ros2").string("rclrs");
+        writer.write_record(OP_HEADER, header)?;
+        Ok(writer)
+    }
+
+    /// Adds a schema for a message type, e.g. `std_msgs/msg/String`, and returns its ID.
+    ///
+    /// The message definition is not known, so the schema is empty.
+    pub(crate) fn add_schema(&mut self, type_name: &str) -> io::Result<u16> {
+        let id = self.next_schema_id;
+        let schema = Record::default()
+            .u16(id)
+            .string(type_name)
+            .string("ros2msg")
+            .bytes(&[]);
+        self.write_record(OP_SCHEMA, schema)?;
+        self.next_schema_id += 1;
+        Ok(id)
+    }
+
+    /// Adds a channel for the CDR messages of a topic, and returns its ID.
+    pub(crate) fn add_channel(
+        &mut self,
+        schema_id: u16,
+        topic: &str,
+        metadata: &BTreeMap<String, String>,
+    ) -> io::Result<u16> {
+        let id = self.next_channel_id;
+        let channel = Record::default()
+            .u16(id)
+            .u16(schema_id)
+            .string(topic)
+            .string("cdr")
+            .map(metadata);
+        self.write_record(OP_CHANNEL, channel)?;
+        self.next_channel_id += 1;
+        Ok(id)
+    }
+
+    /// Writes a message, with its receive and publish times in nanoseconds since the epoch.
+    pub(crate) fn write_message(
+        &mut self,
+        channel_id: u16,
+        sequence: u32,
+        log_time: u64,
+        publish_time: u64,
+        data: &[u8],
+    ) -> io::Result<()> {
+        let message = Record::default()
+            .u16(channel_id)
+            .u32(sequence)
+            .u64(log_time)
+            .u64(publish_time)
+            .raw(data);
+        self.write_record(OP_MESSAGE, message)
+    }
+
+    /// Writes the end of the file, without a summary section.
+    pub(crate) fn finish(mut self) -> io::Result<()> {
+        // A CRC of 0 means that it was not calculated.
+        self.write_record(OP_DATA_END, Record::default().u32(0))?;
+        let footer = Record::default().u64(0).u64(0).u32(0);
+        self.write_record(OP_FOOTER, footer)?;
+        self.file.write_all(MAGIC)?;
+        self.file.flush()?;
+        self.file.get_ref().sync_all()
+    }
+
+    fn write_record(&mut self, opcode: u8, record: Record) -> io::Result<()> {
+        self.file.write_all(&[opcode])?;
+        self.file
+            .write_all(&(record.0.len() as u64).to_le_bytes())?;
+        self.file.write_all(&record.0)
+    }
+}
+
+/// A record that is relevant for playing a bag, see [`McapReader`].
+pub(crate) enum McapRecord {
+    Channel {
+        id: u16,
+        topic: String,
+    },
+    Message {
+        channel_id: u16,
+        log_time: u64,
+        data: Vec<u8>,
+    },
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+// Parses the content of a record, with the primitive types of the format.
+struct Fields<'a>(&'a [u8]);
+
+impl<'a> Fields<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(invalid_data("Truncated MCAP record"));
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        let bytes = self.bytes()?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| invalid_data("Invalid string in MCAP record"))
+    }
+
+    fn bytes(&mut self) -> io::Result<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+}
+
+/// Reads the channels and messages of an MCAP file, in the order of the file. The types and QoS
+/// profiles of the topics are taken from the metadata of the bag instead of the schemas.
+pub(crate) struct McapReader {
+    file: BufReader<File>,
+    // The records of the current chunk that have not been read yet.
+    chunk: Vec<u8>,
+    chunk_offset: usize,
+    // Whether the end of the data section has been reached.
+    done: bool,
+}
+
+impl McapReader {
+    pub(crate) fn open(path: &Path) -> io::Result<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+        let mut magic = [0; 8];
+        file.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(invalid_data("Not an MCAP file"));
+        }
+        Ok(Self {
+            file,
+            chunk: Vec::new(),
+            chunk_offset: 0,
+            done: false,
+        })
+    }
+
+    /// Returns the next schema, channel or message, or `None` at the end of the data section.
+    pub(crate) fn next_record(&mut self) -> io::Result<Option<McapRecord>> {
+        loop {
+            let (opcode, content) = if self.chunk_offset < self.chunk.len() {
+                let mut fields = Fields(&self.chunk[self.chunk_offset..]);
+                let opcode = fields.take(1)?[0];
+                let len = fields.u64()? as usize;
+                let content = fields.take(len)?.to_vec();
+                self.chunk_offset += 9 + len;
+                (opcode, content)
+            } else if self.done {
+                return Ok(None);
+            } else {
+                let mut header = [0; 9];
+                self.file.read_exact(&mut header)?;
+                let len = u64::from_le_bytes(header[1..].try_into().unwrap());
+                let mut content = Vec::new();
+                (&mut self.file).take(len).read_to_end(&mut content)?;
+                if content.len() as u64 != len {
+                    return Err(invalid_data("Truncated MCAP file"));
+                }
+                (header[0], content)
+            };
+            let mut fields = Fields(&content);
+            match opcode {
+                OP_CHANNEL => {
+                    let id = fields.u16()?;
+                    let _schema_id = fields.u16()?;
+                    return Ok(Some(McapRecord::Channel {
+                        id,
+                        topic: fields.string()?,
+                    }));
+                }
+                OP_MESSAGE => {
+                    let channel_id = fields.u16()?;
+                    let _sequence = fields.u32()?;
+                    let log_time = fields.u64()?;
+                    let _publish_time = fields.u64()?;
+                    return Ok(Some(McapRecord::Message {
+                        channel_id,
+                        log_time,
+                        data: fields.0.to_vec(),
+                    }));
+                }
+                OP_CHUNK => {
+                    let _message_start_time = fields.u64()?;
+                    let _message_end_time = fields.u64()?;
+                    let _uncompressed_size = fields.u64()?;
+                    let _uncompressed_crc = fields.u32()?;
+                    let compression = fields.string()?;
+                    if !compression.is_empty() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::Unsupported,
+                            format!("Compressed MCAP chunks ({compression}) are not supported"),
+                        ));
+                    }
+                    let len = fields.u64()? as usize;
+                    self.chunk = fields.take(len)?.to_vec();
+                    self.chunk_offset = 0;
+                }
+                OP_DATA_END | OP_FOOTER => self.done = true,
+                // Other records, e.g. the schemas, indexes and attachments, are not needed.
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A path in the temporary directory that no other test uses.
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("rclrs_{}_{name}.mcap", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn read_all(path: &Path) -> io::Result<Vec<McapRecord>> {
+        let mut reader = McapReader::open(path)?;
+        let mut records = Vec::new();
+        while let Some(record) = reader.next_record()? {
+            records.push(record);
+        }
+        Ok(records)
+    }
+
+    fn assert_records(records: &[McapRecord]) {
+        assert_eq!(records.len(), 4);
+        assert!(matches!(&records[0], McapRecord::Channel { id: 0, topic } if topic == "/chatter"));
+        assert!(matches!(&records[1], McapRecord::Channel { id: 1, topic } if topic == "/tf"));
+        assert!(matches!(
+            &records[2],
+            McapRecord::Message { channel_id: 0, log_time: 10, data } if data == b"hello"
+        ));
+        assert!(matches!(
+            &records[3],
+            McapRecord::Message { channel_id: 1, log_time: 20, data } if data.is_empty()
+        ));
+    }
+
+    #[test]
+    fn test_round_trip() -> io::Result<()> {
+        let path = temp_path("round_trip");
+        let mut writer = McapWriter::create(&path)?;
+        let schema_id = writer.add_schema("std_msgs/msg/String")?;
+        assert_eq!(schema_id, 1);
+        let metadata = BTreeMap::from([("key".to_string(), "value".to_string())]);
+        assert_eq!(writer.add_channel(schema_id, "/chatter", &metadata)?, 0);
+        assert_eq!(writer.add_channel(schema_id, "/tf", &BTreeMap::new())?, 1);
+        writer.write_message(0, 0, 10, 5, b"hello")?;
+        writer.write_message(1, 1, 20, 15, b"")?;
+        // A writer can not overwrite an existing bag.
+        assert!(McapWriter::create(&path).is_err());
+        writer.finish()?;
+        let records = read_all(&path);
+        std::fs::remove_file(&path)?;
+        assert_records(&records?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_chunks() -> io::Result<()> {
+        // rosbag2 writes the channels and messages into chunks.
+        let mut chunk = Vec::new();
+        for (opcode, record) in [
+            (
+                OP_CHANNEL,
+                Record::default()
+                    .u16(0)
+                    .u16(1)
+                    .string("/chatter")
+                    .string("cdr")
+                    .map(&BTreeMap::new()),
+            ),
+            (
+                OP_CHANNEL,
+                Record::default()
+                    .u16(1)
+                    .u16(1)
+                    .string("/tf")
+                    .string("cdr")
+                    .map(&BTreeMap::new()),
+            ),
+            (
+                OP_MESSAGE,
+                Record::default().u16(0).u32(0).u64(10).u64(5).raw(b"hello"),
+            ),
+            (
+                OP_MESSAGE,
+                Record::default().u16(1).u32(1).u64(20).u64(15).raw(b""),
+            ),
+        ] {
+            chunk.push(opcode);
+            chunk.extend((record.0.len() as u64).to_le_bytes());
+            chunk.extend(record.0);
+        }
+        let path = temp_path("chunks");
+        let mut writer = McapWriter::create(&path)?;
+        let chunk_record = |compression: &str| {
+            Record::default()
+                .u64(10)
+                .u64(20)
+                .u64(chunk.len() as u64)
+                .u32(0)
+                .string(compression)
+                .u64(chunk.len() as u64)
+                .raw(&chunk)
+        };
+        writer.write_record(OP_CHUNK, chunk_record(""))?;
+        writer.finish()?;
+        let records = read_all(&path);
+        std::fs::remove_file(&path)?;
+        assert_records(&records?);
+
+        let path = temp_path("compressed_chunks");
+        let mut writer = McapWriter::create(&path)?;
+        writer.write_record(OP_CHUNK, chunk_record("zstd"))?;
+        writer.finish()?;
+        let error = read_all(&path).err();
+        std::fs::remove_file(&path)?;
+        assert_eq!(error.map(|e| e.kind()), Some(io::ErrorKind::Unsupported));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_files() -> io::Result<()> {
+        let path = temp_path("invalid");
+        std::fs::write(&path, b"not a bag")?;
+        assert!(McapReader::open(&path).is_err());
+
+        let mut writer = McapWriter::create(&path.with_extension("truncated"))?;
+        writer.write_message(0, 0, 10, 5, b"hello")?;
+        writer.file.flush()?;
+        drop(writer);
+        let truncated = std::fs::read(path.with_extension("truncated"))?;
+        std::fs::write(&path, &truncated[..truncated.len() - 2])?;
+        let error = read_all(&path).err();
+        std::fs::remove_file(&path)?;
+        std::fs::remove_file(path.with_extension("truncated"))?;
+        assert_eq!(error.map(|e| e.kind()), Some(io::ErrorKind::InvalidData));
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+//! Recording and playing of rosbag2 bags.
+//!
+//! This requires the `rosbag` feature. A bag is a directory with the messages in an MCAP file,
+//! and a `metadata.yaml` file that describes the topics, in the same layout as rosbag2 writes
+//! them, so that the bags can be inspected and played with `ros2 bag`.
+
+mod mcap;
+mod player;
+mod recorder;
+
+pub use player::*;
+pub use recorder::*;
+
+use crate::{RclReturnCode, RclrsError};
+
+use serde::{Deserialize, Serialize};
+use std::io;
+
+fn io_error(context: &str, e: io::Error) -> RclrsError {
+    RclrsError::with_message(RclReturnCode::Error, format!("{context}: {e}"))
+}
+
+// The metadata.yaml file of a bag, in version 5 of the format of rosbag2.
+#[derive(Deserialize, Serialize)]
+struct BagMetadataFile {
+    rosbag2_bagfile_information: BagMetadata,
+}
+
+#[derive(Deserialize, Serialize)]
+struct BagMetadata {
+    version: u32,
+    storage_identifier: String,
+    duration: Nanoseconds,
+    starting_time: NanosecondsSinceEpoch,
+    message_count: u64,
+    topics_with_message_count: Vec<TopicWithMessageCount>,
+    #[serde(default)]
+    compression_format: String,
+    #[serde(default)]
+    compression_mode: String,
+    relative_file_paths: Vec<String>,
+    #[serde(default)]
+    files: Vec<FileInformation>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Nanoseconds {
+    nanoseconds: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+struct NanosecondsSinceEpoch {
+    nanoseconds_since_epoch: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+struct TopicWithMessageCount {
+    topic_metadata: TopicMetadata,
+    message_count: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+struct TopicMetadata {
+    name: String,
+    #[serde(rename = "type")]
+    type_name: String,
+    serialization_format: String,
+    #[serde(default)]
+    offered_qos_profiles: String,
+}
+
+#[derive(Deserialize, Serialize)]
+struct FileInformation {
+    path: String,
+    starting_time: NanosecondsSinceEpoch,
+    duration: Nanoseconds,
+    message_count: u64,
+}
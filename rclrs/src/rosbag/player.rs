@@ -0,0 +1,547 @@
+use super::mcap::{McapReader, McapRecord};
+use super::{io_error, BagMetadataFile, TopicSelection};
+use crate::logging::log;
+use crate::qos_yaml::qos_profiles_from_yaml;
+use crate::sync::Mutex;
+#[cfg(feature = "std_srvs")]
+use crate::topic_gate::{Trigger, Trigger_Response};
+use crate::{
+    spin_once, GenericPublisher, LogSeverity, Node, QoSDurabilityPolicy, QoSHistoryPolicy,
+    QoSProfile, QoSReliabilityPolicy, RclReturnCode, RclrsError, SubscriberErrorCode,
+    QOS_PROFILE_DEFAULT,
+};
+#[cfg(feature = "std_srvs")]
+use crate::{Service, QOS_PROFILE_SERVICES_DEFAULT};
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// The longest time that the player waits without checking for commands from other threads.
+const MAX_WAIT: Duration = Duration::from_millis(100);
+
+/// Options for a [`Player`].
+///
+/// New options may be added in the future, so it is best to create this with the
+/// `..Default::default()` syntax.
+#[derive(Clone, Debug)]
+pub struct PlayerOptions {
+    /// The factor by which playback is faster than the recording. `1.0` by default.
+    pub rate: f64,
+    /// Whether playback starts paused, see [`PlayerControl::resume`]. `false` by default.
+    pub start_paused: bool,
+    /// Which of the recorded topics are played. All topics by default.
+    pub topics: TopicSelection,
+    /// The names under which recorded topics are published, by recorded topic name.
+    pub remappings: BTreeMap<String, String>,
+    /// The QoS profiles of the publishers of specific topics, by recorded topic name. The other
+    /// publishers use the QoS profiles that were recorded.
+    pub qos_overrides: BTreeMap<String, QoSProfile>,
+    /// If set, the playback time is published on `/clock` with this frequency in Hz, so that
+    /// nodes with `use_sim_time` follow the bag. `None` by default.
+    pub clock_frequency: Option<f64>,
+}
+
+impl Default for PlayerOptions {
+    fn default() -> Self {
+        Self {
+            rate: 1.0,
+            start_paused: false,
+            topics: TopicSelection::default(),
+            remappings: BTreeMap::new(),
+            qos_overrides: BTreeMap::new(),
+            clock_frequency: None,
+        }
+    }
+}
+
+// The position of the playback in the bag.
+struct PlaybackState {
+    rate: f64,
+    paused: bool,
+    stopped: bool,
+    // The number of messages requested by `PlayerControl::play_next`.
+    steps: usize,
+    // The bag time at the anchor instant, from which the current bag time is extrapolated.
+    anchor_bag_time: u64,
+    anchor_instant: Instant,
+}
+
+impl PlaybackState {
+    fn bag_time(&self) -> u64 {
+        if self.paused {
+            return self.anchor_bag_time;
+        }
+        let elapsed = self.anchor_instant.elapsed().as_nanos() as f64 * self.rate;
+        self.anchor_bag_time + elapsed as u64
+    }
+
+    fn reanchor(&mut self) {
+        self.anchor_bag_time = self.bag_time();
+        self.anchor_instant = Instant::now();
+    }
+}
+
+/// A handle for controlling a [`Player`], e.g. from service callbacks or other threads.
+///
+/// Commands take effect within 100 ms when they are sent from another thread, and immediately
+/// when they are sent from a callback of the node that plays the bag.
+#[derive(Clone)]
+pub struct PlayerControl {
+    state: Arc<Mutex<PlaybackState>>,
+}
+
+impl PlayerControl {
+    /// Pauses playback. The playback time, and thus `/clock`, stands still while paused.
+    pub fn pause(&self) {
+        let state = &mut *self.state.lock();
+        state.reanchor();
+        state.paused = true;
+    }
+
+    /// Resumes playback.
+    pub fn resume(&self) {
+        let state = &mut *self.state.lock();
+        state.anchor_instant = Instant::now();
+        state.paused = false;
+    }
+
+    /// Pauses playback if it is running, and resumes it otherwise.
+    pub fn toggle_paused(&self) {
+        if self.is_paused() {
+            self.resume();
+        } else {
+            self.pause();
+        }
+    }
+
+    /// Returns whether playback is paused.
+    pub fn is_paused(&self) -> bool {
+        self.state.lock().paused
+    }
+
+    /// Publishes the next message while playback is paused, and advances the playback time to
+    /// it.
+    ///
+    /// Returns `false` if playback is not paused.
+    pub fn play_next(&self) -> bool {
+        let state = &mut *self.state.lock();
+        if state.paused {
+            state.steps += 1;
+        }
+        state.paused
+    }
+
+    /// Returns the factor by which playback is faster than the recording.
+    pub fn rate(&self) -> f64 {
+        self.state.lock().rate
+    }
+
+    /// Sets the factor by which playback is faster than the recording.
+    ///
+    /// Returns an [`InvalidArgument`][1] error if the rate is not positive.
+    ///
+    /// [1]: crate::RclReturnCode::InvalidArgument
+    pub fn set_rate(&self, rate: f64) -> Result<(), RclrsError> {
+        if !(rate.is_finite() && rate > 0.0) {
+            return Err(RclrsError::with_message(
+                RclReturnCode::InvalidArgument,
+                format!("Invalid playback rate {rate}"),
+            ));
+        }
+        let state = &mut *self.state.lock();
+        state.reanchor();
+        state.rate = rate;
+        Ok(())
+    }
+
+    /// Stops playback, so that [`Player::play`] returns.
+    pub fn stop(&self) {
+        self.state.lock().stopped = true;
+    }
+}
+
+/// Plays a rosbag2 bag, like `ros2 bag play`.
+///
+/// The messages are republished in their serialized form with [`GenericPublisher`]s, so the
+/// player does not depend on the message crates. They are published with their original timing,
+/// scaled by the rate. The playback time can be published on `/clock`, which makes the player
+/// suited for deterministic replays in simulation.
+///
+/// The bag must be a directory with a `metadata.yaml` file and MCAP files without compression,
+/// as written by the [`Recorder`][1], or by `ros2 bag record` with the `mcap` storage.
+///
+/// Playback is controlled through a [`PlayerControl`], or, with the `std_srvs` feature, through the
+/// services that are created with [`Player::create_services`].
+///
+/// This requires the `rosbag` feature.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let context = Context::new(std::env::args())?;
+/// let mut node = context.create_node("player")?;
+/// let options = PlayerOptions {
+///     rate: 2.0,
+///     clock_frequency: Some(100.0),
+///     ..Default::default()
+/// };
+/// let player = Player::new(&node, "my_bag", options)?;
+/// let _services = player.create_services(&mut node)?;
+/// player.play(&node)?;
+/// ```
+///
+/// [1]: crate::Recorder
+pub struct Player {
+    files: Vec<PathBuf>,
+    starting_time: u64,
+    // The publishers of the played topics, by recorded topic name.
+    publishers: BTreeMap<String, GenericPublisher>,
+    clock_publisher: Option<GenericPublisher>,
+    clock_period: Option<Duration>,
+    control: PlayerControl,
+}
+
+/// The services for controlling a [`Player`], see [`Player::create_services`].
+///
+/// The services are removed when this is dropped.
+#[cfg(feature = "std_srvs")]
+pub struct PlayerServices {
+    _services: Vec<Arc<Service<Trigger>>>,
+}
+
+impl Player {
+    /// Reads the metadata of the bag, and creates the publishers of the played topics.
+    ///
+    /// Returns an [`Error`][1] with a message if the bag can not be read, an [`Unsupported`][2]
+    /// error if it is compressed, and an [`InvalidArgument`][3] error if the rate or the clock
+    /// frequency is not positive.
+    ///
+    /// [1]: crate::RclReturnCode::Error
+    /// [2]: crate::RclReturnCode::Unsupported
+    /// [3]: crate::RclReturnCode::InvalidArgument
+    pub fn new(
+        node: &Node,
+        uri: impl AsRef<Path>,
+        options: PlayerOptions,
+    ) -> Result<Self, RclrsError> {
+        let directory = uri.as_ref();
+        let yaml = fs::read_to_string(directory.join("metadata.yaml")).map_err(|e| {
+            io_error(
+                &format!("Could not read the bag '{}'", directory.display()),
+                e,
+            )
+        })?;
+        let metadata = serde_yaml::from_str::<BagMetadataFile>(&yaml)
+            .map_err(|e| {
+                RclrsError::with_message(RclReturnCode::Error, format!("Invalid bag metadata: {e}"))
+            })?
+            .rosbag2_bagfile_information;
+        if !metadata.compression_mode.is_empty() || metadata.storage_identifier != "mcap" {
+            return Err(RclrsError::with_message(
+                RclReturnCode::Unsupported,
+                format!(
+                    "Only uncompressed MCAP bags are supported, but the bag uses the '{}' \
+                     storage with compression '{}'",
+                    metadata.storage_identifier, metadata.compression_format
+                ),
+            ));
+        }
+        if !(options.rate.is_finite() && options.rate > 0.0) {
+            return Err(RclrsError::with_message(
+                RclReturnCode::InvalidArgument,
+                format!("Invalid playback rate {}", options.rate),
+            ));
+        }
+        if let Some(frequency) = options
+            .clock_frequency
+            .filter(|frequency| !(frequency.is_finite() && *frequency > 0.0))
+        {
+            return Err(RclrsError::with_message(
+                RclReturnCode::InvalidArgument,
+                format!("Invalid clock frequency {frequency}"),
+            ));
+        }
+
+        let mut publishers = BTreeMap::new();
+        for topic in metadata.topics_with_message_count {
+            let topic = topic.topic_metadata;
+            let is_selected = match &options.topics {
+                TopicSelection::All => true,
+                TopicSelection::Names(names) => names.iter().any(|name| {
+                    node.expand_topic_name(name)
+                        .is_ok_and(|name| name == topic.name)
+                }),
+                TopicSelection::Regex(regex) => regex.is_match(&topic.name),
+            };
+            if !is_selected {
+                continue;
+            }
+            let qos = match options.qos_overrides.get(&topic.name) {
+                Some(qos) => *qos,
+                None => recorded_qos(&topic.offered_qos_profiles, &node.logger_name()),
+            };
+            let name = options.remappings.get(&topic.name).unwrap_or(&topic.name);
+            let publisher = node.create_generic_publisher(name, &topic.type_name, qos)?;
+            publishers.insert(topic.name, publisher);
+        }
+        let clock_publisher = match options.clock_frequency {
+            Some(_) => {
+                let clock_qos = QoSProfile {
+                    history: QoSHistoryPolicy::KeepLast { depth: 1 },
+                    reliability: QoSReliabilityPolicy::BestEffort,
+                    durability: QoSDurabilityPolicy::Volatile,
+                    ..QOS_PROFILE_DEFAULT
+                };
+                Some(node.create_generic_publisher(
+                    "/clock",
+                    "rosgraph_msgs/msg/Clock",
+                    clock_qos,
+                )?)
+            }
+            None => None,
+        };
+        let starting_time = metadata.starting_time.nanoseconds_since_epoch;
+        Ok(Self {
+            files: metadata
+                .relative_file_paths
+                .iter()
+                .map(|path| directory.join(path))
+                .collect(),
+            starting_time,
+            publishers,
+            clock_publisher,
+            clock_period: options
+                .clock_frequency
+                .map(|frequency| Duration::from_secs_f64(1.0 / frequency)),
+            control: PlayerControl {
+                state: Arc::new(Mutex::new(PlaybackState {
+                    rate: options.rate,
+                    paused: options.start_paused,
+                    stopped: false,
+                    steps: 0,
+                    anchor_bag_time: starting_time,
+                    anchor_instant: Instant::now(),
+                })),
+            },
+        })
+    }
+
+    /// Returns a handle for controlling playback.
+    pub fn control(&self) -> PlayerControl {
+        self.control.clone()
+    }
+
+    /// Creates services on the node for controlling playback, like the services of
+    /// `ros2 bag play`.
+    ///
+    /// The services have the type `std_srvs/srv/Trigger`, and are named after the functions of
+    /// [`PlayerControl`] that they call, in the private namespace of the node:
+    ///
+    /// - `~/pause` and `~/resume`
+    /// - `~/toggle_paused`
+    /// - `~/play_next`, which responds with `success: false` if playback is not paused
+    ///
+    /// The services are executed while the bag is played, since [`Player::play`] spins the node.
+    ///
+    /// This requires the `std_srvs` feature.
+    #[cfg(feature = "std_srvs")]
+    pub fn create_services(&self, node: &mut Node) -> Result<PlayerServices, RclrsError> {
+        type Command = fn(&PlayerControl) -> Trigger_Response;
+        let commands: [(&str, Command); 4] = [
+            ("~/pause", |control| {
+                control.pause();
+                Trigger_Response::from_success(true, "Paused")
+            }),
+            ("~/resume", |control| {
+                control.resume();
+                Trigger_Response::from_success(true, "Resumed")
+            }),
+            ("~/toggle_paused", |control| {
+                control.toggle_paused();
+                let message = if control.is_paused() {
+                    "Paused"
+                } else {
+                    "Resumed"
+                };
+                Trigger_Response::from_success(true, message)
+            }),
+            ("~/play_next", |control| {
+                if control.play_next() {
+                    Trigger_Response::from_success(true, "Playing the next message")
+                } else {
+                    Trigger_Response::from_success(false, "Playback is not paused")
+                }
+            }),
+        ];
+        let services = commands
+            .into_iter()
+            .map(|(service_name, command)| {
+                let control = self.control();
+                node.create_service::<Trigger, _>(
+                    service_name,
+                    QOS_PROFILE_SERVICES_DEFAULT,
+                    move |_request| command(&control),
+                )
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(PlayerServices {
+            _services: services,
+        })
+    }
+
+    /// Plays the bag, and spins the node in between messages, so that its callbacks are executed.
+    ///
+    /// Returns when all messages have been played, when playback is stopped with
+    /// [`PlayerControl::stop`], or when the context is shut down.
+    pub fn play(&self, node: &Node) -> Result<(), RclrsError> {
+        // The playback time starts at the start of the bag.
+        self.control.state.lock().anchor_instant = Instant::now();
+        let mut next_clock = Instant::now();
+        // The topics of the channels of the current file, by channel ID.
+        let mut channels = BTreeMap::new();
+        for file in &self.files {
+            let mut reader = McapReader::open(file).map_err(|e| {
+                io_error(
+                    &format!("Could not read the bag file '{}'", file.display()),
+                    e,
+                )
+            })?;
+            channels.clear();
+            while let Some(record) = reader
+                .next_record()
+                .map_err(|e| io_error("Could not read the bag", e))?
+            {
+                match record {
+                    McapRecord::Channel { id, topic } => {
+                        channels.insert(id, topic);
+                    }
+                    McapRecord::Message {
+                        channel_id,
+                        log_time,
+                        data,
+                    } => {
+                        let Some(publisher) = channels
+                            .get(&channel_id)
+                            .and_then(|topic| self.publishers.get(topic))
+                        else {
+                            continue;
+                        };
+                        // Messages from before the start of the bag are published immediately.
+                        let log_time = log_time.max(self.starting_time);
+                        if !self.wait_until(node, log_time, &mut next_clock)? {
+                            return Ok(());
+                        }
+                        publisher.publish(&data)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Waits until the bag time reaches the time of the next message, or it is requested with
+    // `PlayerControl::play_next()`, while spinning the node and publishing the clock. Returns
+    // false if playback has been stopped.
+    fn wait_until(
+        &self,
+        node: &Node,
+        bag_time: u64,
+        next_clock: &mut Instant,
+    ) -> Result<bool, RclrsError> {
+        let context = node.get_context();
+        loop {
+            if !context.ok() {
+                return Ok(false);
+            }
+            let remaining = {
+                let state = &mut *self.control.state.lock();
+                if state.stopped {
+                    return Ok(false);
+                }
+                if state.paused && state.steps > 0 {
+                    state.steps -= 1;
+                    state.anchor_bag_time = bag_time;
+                    return Ok(true);
+                }
+                let now = state.bag_time();
+                if !state.paused && now >= bag_time {
+                    return Ok(true);
+                }
+                match state.paused {
+                    true => MAX_WAIT,
+                    false => Duration::from_nanos(((bag_time - now) as f64 / state.rate) as u64),
+                }
+            };
+            let mut timeout = remaining.min(MAX_WAIT);
+            if let (Some(clock_publisher), Some(clock_period)) =
+                (&self.clock_publisher, self.clock_period)
+            {
+                let now = Instant::now();
+                if now >= *next_clock {
+                    let bag_now = self.control.state.lock().bag_time();
+                    clock_publisher.publish(&serialize_clock(bag_now))?;
+                    *next_clock = now + clock_period;
+                }
+                timeout = timeout.min(next_clock.saturating_duration_since(now));
+            }
+            match spin_once(node, Some(timeout)) {
+                Ok(())
+                | Err(RclrsError {
+                    code:
+                        RclReturnCode::Timeout
+                        | RclReturnCode::SubscriberError(SubscriberErrorCode::SubscriptionTakeFailed),
+                    ..
+                }) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+// Returns the QoS profile for republishing a topic, from the profiles of its publishers during
+// the recording. Like rosbag2, this only offers reliability and durability if all publishers
+// did.
+fn recorded_qos(offered_qos_profiles: &str, logger_name: &str) -> QoSProfile {
+    let profiles = match qos_profiles_from_yaml(offered_qos_profiles) {
+        Ok(profiles) => profiles,
+        Err(e) => {
+            log(
+                logger_name,
+                LogSeverity::Warn,
+                &format!("Ignoring the recorded QoS profiles: {e}"),
+            );
+            Vec::new()
+        }
+    };
+    let Some(first) = profiles.first() else {
+        return QOS_PROFILE_DEFAULT;
+    };
+    let mut qos = *first;
+    if profiles
+        .iter()
+        .any(|profile| profile.reliability == QoSReliabilityPolicy::BestEffort)
+    {
+        qos.reliability = QoSReliabilityPolicy::BestEffort;
+    }
+    if profiles
+        .iter()
+        .any(|profile| profile.durability != QoSDurabilityPolicy::TransientLocal)
+    {
+        qos.durability = QoSDurabilityPolicy::Volatile;
+    }
+    qos
+}
+
+// Serializes a `rosgraph_msgs/msg/Clock` message in little-endian CDR.
+fn serialize_clock(nanoseconds: u64) -> Vec<u8> {
+    let sec = (nanoseconds / 1_000_000_000) as i32;
+    let nanosec = (nanoseconds % 1_000_000_000) as u32;
+    // The encapsulation header, followed by the fields of builtin_interfaces/msg/Time.
+    let mut data = vec![0x00, 0x01, 0x00, 0x00];
+    data.extend_from_slice(&sec.to_le_bytes());
+    data.extend_from_slice(&nanosec.to_le_bytes());
+    data
+}
@@ -0,0 +1,481 @@
+use super::mcap::McapWriter;
+use super::{
+    io_error, BagMetadata, BagMetadataFile, FileInformation, Nanoseconds, NanosecondsSinceEpoch,
+    TopicMetadata, TopicWithMessageCount,
+};
+use crate::logging::log;
+use crate::qos_yaml::qos_profiles_to_yaml;
+use crate::sync::Mutex;
+use crate::{
+    Clock, GenericSubscription, LogSeverity, MessageInfo, Node, QoSDurabilityPolicy,
+    QoSHistoryPolicy, QoSProfile, QoSReliabilityPolicy, RclReturnCode, RclrsError,
+    QOS_PROFILE_DEFAULT,
+};
+
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Which topics a [`Recorder`] records.
+#[derive(Clone, Debug, Default)]
+pub enum TopicSelection {
+    /// All topics in the ROS graph, like `ros2 bag record --all`.
+    #[default]
+    All,
+    /// The topics with the given names, which are expanded like the topic names of subscriptions.
+    Names(Vec<String>),
+    /// The topics whose fully qualified names match the regular expression.
+    Regex(Regex),
+}
+
+/// Options for a [`Recorder`].
+///
+/// New options may be added in the future, so it is best to create this with the
+/// `..Default::default()` syntax.
+#[derive(Clone, Debug)]
+pub struct RecorderOptions {
+    /// Which topics are recorded. All topics by default.
+    pub topics: TopicSelection,
+    /// Topics whose fully qualified names match this regular expression are not recorded, even
+    /// if they are selected.
+    pub exclude: Option<Regex>,
+    /// Whether hidden topics, which have a name component that starts with an underscore, are
+    /// recorded. `false` by default.
+    pub include_hidden_topics: bool,
+    /// The QoS profile of the subscriptions, for topics without an override.
+    ///
+    /// By default, this is best effort and volatile, with a depth of 100, since such a
+    /// subscription is compatible with any publisher. Topics whose late-joining subscriptions
+    /// rely on transient local durability, e.g. `/tf_static`, need an override.
+    pub qos: QoSProfile,
+    /// The QoS profiles of specific topics, by fully qualified topic name. These can be read
+    /// from a rosbag2 QoS override file with [`QoSProfile::overrides_from_yaml`].
+    pub qos_overrides: BTreeMap<String, QoSProfile>,
+}
+
+impl Default for RecorderOptions {
+    fn default() -> Self {
+        Self {
+            topics: TopicSelection::default(),
+            exclude: None,
+            include_hidden_topics: false,
+            qos: QoSProfile {
+                history: QoSHistoryPolicy::KeepLast { depth: 100 },
+                reliability: QoSReliabilityPolicy::BestEffort,
+                durability: QoSDurabilityPolicy::Volatile,
+                ..QOS_PROFILE_DEFAULT
+            },
+            qos_overrides: BTreeMap::new(),
+        }
+    }
+}
+
+/// Statistics of a topic recorded by a [`Recorder`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RecordedTopic {
+    /// The message type, e.g. `std_msgs/msg/String`.
+    pub type_name: String,
+    /// The number of recorded messages.
+    pub message_count: u64,
+    /// The total size of the recorded messages, in their serialized form.
+    pub bytes: u64,
+}
+
+// A topic that is being recorded.
+struct TopicState {
+    channel_id: u16,
+    qos: QoSProfile,
+    statistics: RecordedTopic,
+}
+
+// The state shared between the recorder and the callbacks of its subscriptions.
+struct RecorderState {
+    writer: Option<McapWriter>,
+    clock: Clock,
+    topics: BTreeMap<String, TopicState>,
+    schemas: BTreeMap<String, u16>,
+    // The receive times of the first and the last message, in nanoseconds since the epoch.
+    time_range: Option<(u64, u64)>,
+    // The first error while writing, which is returned by `Recorder::finish`.
+    error: Option<RclrsError>,
+}
+
+impl RecorderState {
+    fn add_topic(
+        &mut self,
+        topic: &str,
+        type_name: &str,
+        qos: QoSProfile,
+    ) -> Result<(), RclrsError> {
+        let Some(writer) = &mut self.writer else {
+            return Ok(());
+        };
+        let schema_id = match self.schemas.get(type_name) {
+            Some(schema_id) => *schema_id,
+            None => {
+                let schema_id = writer
+                    .add_schema(type_name)
+                    .map_err(|e| io_error("Could not write to the bag", e))?;
+                self.schemas.insert(type_name.to_string(), schema_id);
+                schema_id
+            }
+        };
+        let metadata = BTreeMap::from([(
+            "offered_qos_profiles".to_string(),
+            qos_profiles_to_yaml(&[qos]),
+        )]);
+        let channel_id = writer
+            .add_channel(schema_id, topic, &metadata)
+            .map_err(|e| io_error("Could not write to the bag", e))?;
+        self.topics.insert(
+            topic.to_string(),
+            TopicState {
+                channel_id,
+                qos,
+                statistics: RecordedTopic {
+                    type_name: type_name.to_string(),
+                    ..Default::default()
+                },
+            },
+        );
+        Ok(())
+    }
+
+    fn write(&mut self, topic: &str, data: &[u8], message_info: &MessageInfo) {
+        let (Some(writer), Some(topic_state)) = (&mut self.writer, self.topics.get_mut(topic))
+        else {
+            return;
+        };
+        if self.error.is_some() {
+            return;
+        }
+        let log_time = self
+            .clock
+            .now()
+            .map_or(0, |time| u64::try_from(time.nsec).unwrap_or(0));
+        // Not every RMW implementation sets the source timestamp.
+        let publish_time = match u64::try_from(message_info.source_timestamp) {
+            Ok(source_timestamp) if source_timestamp > 0 => source_timestamp,
+            _ => log_time,
+        };
+        let statistics = &mut topic_state.statistics;
+        let sequence = statistics.message_count as u32;
+        if let Err(e) = writer.write_message(
+            topic_state.channel_id,
+            sequence,
+            log_time,
+            publish_time,
+            data,
+        ) {
+            self.error = Some(io_error("Could not write to the bag", e));
+            return;
+        }
+        statistics.message_count += 1;
+        statistics.bytes += data.len() as u64;
+        self.time_range = Some(match self.time_range {
+            None => (log_time, log_time),
+            Some((start, end)) => (start.min(log_time), end.max(log_time)),
+        });
+    }
+}
+
+/// Records topics into a rosbag2 bag, like `ros2 bag record`.
+///
+/// The messages are received with [`GenericSubscription`]s, so the recorder does not depend on
+/// the message crates, and are stored in their serialized form. The bag is a directory with an
+/// MCAP file and a `metadata.yaml` file, which can be inspected and played with `ros2 bag`.
+///
+/// The recorder subscribes to the selected topics that are in the ROS graph when it is created.
+/// Topics that appear later are subscribed by [`Recorder::discover`], which should be called
+/// periodically, e.g. from the spin loop, or whenever a [`GraphEvent`][1] arrives. Messages are
+/// recorded while the node is spinning. Topics with more than one type, and topics whose type
+/// support is not installed, are skipped with a warning.
+///
+/// The bag is completed by [`Recorder::finish`], or when the recorder is dropped.
+///
+/// This requires the `rosbag` feature.
+///
+/// # Example
+/// ```no_run
+/// # use rclrs::*;
+/// # use std::time::Duration;
+/// let context = Context::new(std::env::args())?;
+/// let mut node = context.create_node("recorder")?;
+/// let options = RecorderOptions {
+///     topics: TopicSelection::Names(vec!["/chatter".into(), "/tf_static".into()]),
+///     qos_overrides: QoSProfile::overrides_from_yaml(
+///         &std::fs::read_to_string("qos_overrides.yaml").unwrap_or_default(),
+///     )?,
+///     ..Default::default()
+/// };
+/// let recorder = Recorder::new(&mut node, "my_bag", options)?;
+/// while context.ok() {
+///     spin_once(&node, Some(Duration::from_millis(100)))?;
+///     recorder.discover(&mut node)?;
+/// }
+/// for (topic, statistics) in recorder.statistics() {
+///     println!("{topic}: {} messages", statistics.message_count);
+/// }
+/// recorder.finish()?;
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::GraphEvent
+pub struct Recorder {
+    directory: PathBuf,
+    file_name: String,
+    options: RecorderOptions,
+    // The expanded names of `TopicSelection::Names`.
+    topic_names: BTreeSet<String>,
+    state: Arc<Mutex<RecorderState>>,
+    subscriptions: Mutex<BTreeMap<String, Arc<GenericSubscription>>>,
+    // The topics that can not be recorded, so that they are not retried.
+    skipped: Mutex<BTreeSet<String>>,
+    // The logger of the node, for warnings about topics and about finishing the bag on drop.
+    logger_name: String,
+}
+
+impl Recorder {
+    /// Creates the bag directory and subscribes to the selected topics.
+    ///
+    /// Returns an [`Error`][1] with a message if the directory already exists, or can not be
+    /// created.
+    ///
+    /// [1]: crate::RclReturnCode::Error
+    pub fn new(
+        node: &mut Node,
+        uri: impl AsRef<Path>,
+        options: RecorderOptions,
+    ) -> Result<Self, RclrsError> {
+        let directory = uri.as_ref().to_path_buf();
+        fs::create_dir_all(directory.parent().unwrap_or(Path::new("")))
+            .and_then(|_| fs::create_dir(&directory))
+            .map_err(|e| {
+                io_error(
+                    &format!("Could not create the bag '{}'", directory.display()),
+                    e,
+                )
+            })?;
+        let bag_name = directory
+            .file_name()
+            .map_or("bag".into(), |name| name.to_string_lossy());
+        let file_name = format!("{bag_name}_0.mcap");
+        let writer = McapWriter::create(&directory.join(&file_name))
+            .map_err(|e| io_error("Could not create the bag file", e))?;
+        let topic_names = match &options.topics {
+            TopicSelection::Names(names) => names
+                .iter()
+                .map(|name| node.expand_topic_name(name))
+                .collect::<Result<_, _>>()?,
+            _ => BTreeSet::new(),
+        };
+        let recorder = Self {
+            directory,
+            file_name,
+            options,
+            topic_names,
+            state: Arc::new(Mutex::new(RecorderState {
+                writer: Some(writer),
+                clock: node.get_clock(),
+                topics: BTreeMap::new(),
+                schemas: BTreeMap::new(),
+                time_range: None,
+                error: None,
+            })),
+            subscriptions: Mutex::new(BTreeMap::new()),
+            skipped: Mutex::new(BTreeSet::new()),
+            logger_name: node.logger_name(),
+        };
+        recorder.discover(node)?;
+        Ok(recorder)
+    }
+
+    /// Subscribes to the selected topics that have appeared in the ROS graph since the last call,
+    /// and returns their names.
+    pub fn discover(&self, node: &mut Node) -> Result<Vec<String>, RclrsError> {
+        let mut discovered = Vec::new();
+        for (topic, types) in node.get_topic_names_and_types()? {
+            if !self.is_selected(&topic)
+                || self.subscriptions.lock().contains_key(&topic)
+                || self.skipped.lock().contains(&topic)
+            {
+                continue;
+            }
+            let [type_name] = &types[..] else {
+                log(
+                    &self.logger_name,
+                    LogSeverity::Warn,
+                    &format!(
+                        "Not recording the topic '{topic}', which has more than one type: {}",
+                        types.join(", ")
+                    ),
+                );
+                self.skipped.lock().insert(topic);
+                continue;
+            };
+            match self.subscribe(node, &topic, type_name) {
+                Ok(()) => discovered.push(topic),
+                // The type support of the message type is not installed.
+                Err(RclrsError {
+                    code: RclReturnCode::InvalidArgument,
+                    msg,
+                }) => {
+                    log(
+                        &self.logger_name,
+                        LogSeverity::Warn,
+                        &format!(
+                            "Not recording the topic '{topic}': {}",
+                            msg.map(|msg| msg.to_string()).unwrap_or_default()
+                        ),
+                    );
+                    self.skipped.lock().insert(topic);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(discovered)
+    }
+
+    /// Returns the statistics of the recorded topics, by topic name.
+    pub fn statistics(&self) -> BTreeMap<String, RecordedTopic> {
+        self.state
+            .lock()
+            .topics
+            .iter()
+            .map(|(topic, topic_state)| (topic.clone(), topic_state.statistics.clone()))
+            .collect()
+    }
+
+    /// Returns the directory of the bag.
+    pub fn uri(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Stops recording, and completes the bag by writing the end of the MCAP file and the
+    /// `metadata.yaml` file.
+    ///
+    /// Returns an [`Error`][1] with a message if writing the bag has failed, while recording or
+    /// now.
+    ///
+    /// [1]: crate::RclReturnCode::Error
+    pub fn finish(mut self) -> Result<(), RclrsError> {
+        self.finish_impl()
+    }
+
+    fn is_selected(&self, topic: &str) -> bool {
+        let is_hidden = topic.split('/').any(|part| part.starts_with('_'));
+        let is_selected = match &self.options.topics {
+            TopicSelection::All => true,
+            TopicSelection::Names(_) => self.topic_names.contains(topic),
+            TopicSelection::Regex(regex) => regex.is_match(topic),
+        };
+        let is_excluded = self
+            .options
+            .exclude
+            .as_ref()
+            .is_some_and(|exclude| exclude.is_match(topic));
+        is_selected && !is_excluded && (self.options.include_hidden_topics || !is_hidden)
+    }
+
+    fn subscribe(&self, node: &mut Node, topic: &str, type_name: &str) -> Result<(), RclrsError> {
+        let qos = self
+            .options
+            .qos_overrides
+            .get(topic)
+            .copied()
+            .unwrap_or(self.options.qos);
+        let state = Arc::clone(&self.state);
+        let callback_topic = topic.to_string();
+        let subscription =
+            node.create_generic_subscription(topic, type_name, qos, move |data, message_info| {
+                state.lock().write(&callback_topic, data, &message_info);
+            })?;
+        self.state.lock().add_topic(topic, type_name, qos)?;
+        self.subscriptions
+            .lock()
+            .insert(topic.to_string(), subscription);
+        Ok(())
+    }
+
+    fn finish_impl(&mut self) -> Result<(), RclrsError> {
+        self.subscriptions.lock().clear();
+        let state = &mut *self.state.lock();
+        let Some(writer) = state.writer.take() else {
+            return Ok(());
+        };
+        writer
+            .finish()
+            .map_err(|e| io_error("Could not write to the bag", e))?;
+        let (starting_time, ending_time) = state.time_range.unwrap_or_default();
+        let message_count = state
+            .topics
+            .values()
+            .map(|topic_state| topic_state.statistics.message_count)
+            .sum();
+        let metadata = BagMetadataFile {
+            rosbag2_bagfile_information: BagMetadata {
+                version: 5,
+                storage_identifier: "mcap".into(),
+                duration: Nanoseconds {
+                    nanoseconds: ending_time - starting_time,
+                },
+                starting_time: NanosecondsSinceEpoch {
+                    nanoseconds_since_epoch: starting_time,
+                },
+                message_count,
+                topics_with_message_count: state
+                    .topics
+                    .iter()
+                    .map(|(topic, topic_state)| TopicWithMessageCount {
+                        topic_metadata: TopicMetadata {
+                            name: topic.clone(),
+                            type_name: topic_state.statistics.type_name.clone(),
+                            serialization_format: "cdr".into(),
+                            offered_qos_profiles: qos_profiles_to_yaml(&[topic_state.qos]),
+                        },
+                        message_count: topic_state.statistics.message_count,
+                    })
+                    .collect(),
+                compression_format: String::new(),
+                compression_mode: String::new(),
+                relative_file_paths: vec![self.file_name.clone()],
+                files: vec![FileInformation {
+                    path: self.file_name.clone(),
+                    starting_time: NanosecondsSinceEpoch {
+                        nanoseconds_since_epoch: starting_time,
+                    },
+                    duration: Nanoseconds {
+                        nanoseconds: ending_time - starting_time,
+                    },
+                    message_count,
+                }],
+            },
+        };
+        let yaml = serde_yaml::to_string(&metadata).map_err(|e| {
+            RclrsError::with_message(
+                RclReturnCode::Error,
+                format!("Could not serialize the bag metadata: {e}"),
+            )
+        })?;
+        fs::write(self.directory.join("metadata.yaml"), yaml)
+            .map_err(|e| io_error("Could not write the bag metadata", e))?;
+        match state.error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish_impl() {
+            log(
+                &self.logger_name,
+                LogSeverity::Error,
+                &format!("Could not finish the bag: {e}"),
+            );
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Macros for writing out the RMW-native types of messages and services by hand.
+//!
+//! `rclrs` can not depend on the generated message crates, so the few interfaces that it uses
+//! itself are written out in the same way as the RMW-native types that `rosidl_generator_rs`
+//! generates. Their layout must match the C types of `rosidl_generator_c`, and the C functions
+//! that the macros declare come from the `rosidl_generator_c` and `rosidl_typesupport_c`
+//! libraries of the interface package.
+
+macro_rules! impl_message {
+    ($type:ident, $type_name:literal, $init:ident, $get_type_support:ident) => {
+        extern "C" {
+            fn $init(msg: *mut $type) -> bool;
+            fn $get_type_support() -> libc::uintptr_t;
+        }
+
+        impl Default for $type {
+            fn default() -> Self {
+                unsafe {
+                    // SAFETY: A zeroed bit pattern is the uninitialized message that init() expects.
+                    let mut msg = core::mem::zeroed();
+                    if !$init(&mut msg as *mut _) {
+                        panic!(concat!("Call to ", stringify!($init), "() failed"));
+                    }
+                    msg
+                }
+            }
+        }
+
+        impl rosidl_runtime_rs::Message for $type {
+            type RmwMsg = Self;
+            fn into_rmw_message(
+                msg_cow: alloc::borrow::Cow<'_, Self>,
+            ) -> alloc::borrow::Cow<'_, Self::RmwMsg> {
+                msg_cow
+            }
+            fn from_rmw_message(msg: Self::RmwMsg) -> Self {
+                msg
+            }
+        }
+
+        impl rosidl_runtime_rs::RmwMessage for $type {
+            const TYPE_NAME: &'static str = $type_name;
+
+            fn get_type_support() -> libc::uintptr_t {
+                // SAFETY: No preconditions for this function.
+                unsafe { $get_type_support() }
+            }
+        }
+    };
+}
+
+macro_rules! impl_sequence_alloc {
+    ($type:ident, $sequence_init:ident, $sequence_fini:ident) => {
+        extern "C" {
+            fn $sequence_init(
+                seq: *mut rosidl_runtime_rs::Sequence<$type>,
+                size: libc::size_t,
+            ) -> bool;
+            fn $sequence_fini(seq: *mut rosidl_runtime_rs::Sequence<$type>);
+        }
+
+        impl rosidl_runtime_rs::SequenceAlloc for $type {
+            fn sequence_init(
+                seq: &mut rosidl_runtime_rs::Sequence<Self>,
+                size: libc::size_t,
+            ) -> bool {
+                // SAFETY: The sequence is valid, as required by this function.
+                unsafe { $sequence_init(seq as *mut _, size) }
+            }
+            fn sequence_fini(seq: &mut rosidl_runtime_rs::Sequence<Self>) {
+                // SAFETY: The sequence is valid, as required by this function.
+                unsafe { $sequence_fini(seq as *mut _) }
+            }
+            fn sequence_copy(
+                in_seq: &Sequence<Self>,
+                out_seq: &mut rosidl_runtime_rs::Sequence<Self>,
+            ) -> bool {
+                out_seq.resize_to_at_least(in_seq.len());
+                out_seq.clone_from_slice(in_seq.as_slice());
+                true
+            }
+        }
+    };
+}
+
+macro_rules! impl_service {
+    ($type:ident, $request:ident, $response:ident, $get_type_support:ident) => {
+        extern "C" {
+            fn $get_type_support() -> libc::uintptr_t;
+        }
+
+        pub(crate) struct $type;
+
+        impl rosidl_runtime_rs::Service for $type {
+            type Request = $request;
+            type Response = $response;
+
+            fn get_type_support() -> libc::uintptr_t {
+                // SAFETY: No preconditions for this function.
+                unsafe { $get_type_support() }
+            }
+        }
+    };
+}
+
+pub(crate) use impl_message;
+pub(crate) use impl_sequence_alloc;
+pub(crate) use impl_service;
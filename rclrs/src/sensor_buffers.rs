@@ -0,0 +1,358 @@
+use crate::error::{RclReturnCode, RclrsError};
+
+use alloc::format;
+use core::marker::PhantomData;
+use core::mem::size_of;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// A primitive type that can be read from and written to the raw buffers of images and point
+/// clouds.
+///
+/// Any bit pattern is a valid value of these types, so they can be viewed in place. The bytes are
+/// interpreted in the native byte order, so the `is_bigendian` field of the message must match the
+/// host.
+pub trait PlainData: Copy + sealed::Sealed {
+    /// The `datatype` of a `sensor_msgs/PointField` with this type.
+    const POINT_FIELD_DATATYPE: u8;
+}
+
+macro_rules! impl_plain_data {
+    ($($type:ty => $datatype:expr),* $(,)?) => {
+        $(
+            impl sealed::Sealed for $type {}
+            impl PlainData for $type {
+                const POINT_FIELD_DATATYPE: u8 = $datatype;
+            }
+        )*
+    };
+}
+
+// The datatypes are the constants of sensor_msgs/msg/PointField.
+impl_plain_data!(
+    i8 => 1,
+    u8 => 2,
+    i16 => 3,
+    u16 => 4,
+    i32 => 5,
+    u32 => 6,
+    f32 => 7,
+    f64 => 8,
+);
+
+/// Returns the number of bytes per pixel of an image encoding from `sensor_msgs/image_encodings`.
+///
+/// Returns `None` for unknown encodings and for packed encodings whose pixels do not have a whole
+/// number of bytes.
+pub fn image_encoding_bytes_per_pixel(encoding: &str) -> Option<usize> {
+    let bytes_per_pixel = match encoding {
+        "mono8" | "bayer_rggb8" | "bayer_bggr8" | "bayer_gbrg8" | "bayer_grbg8" => 1,
+        "mono16" | "bayer_rggb16" | "bayer_bggr16" | "bayer_gbrg16" | "bayer_grbg16" => 2,
+        "yuv422" | "uyvy" | "yuv422_yuy2" | "yuyv" => 2,
+        "rgb8" | "bgr8" => 3,
+        "rgba8" | "bgra8" => 4,
+        "rgb16" | "bgr16" => 6,
+        "rgba16" | "bgra16" => 8,
+        // The OpenCV-style encodings, e.g. 32FC1, have the form <bits><U|S|F>C<channels>.
+        _ => {
+            let type_index = encoding.find(['U', 'S', 'F'])?;
+            let bits: usize = encoding[..type_index].parse().ok()?;
+            let channels: usize = encoding[type_index + 1..].strip_prefix('C')?.parse().ok()?;
+            if !matches!(bits, 8 | 16 | 32 | 64) || channels == 0 {
+                return None;
+            }
+            bits / 8 * channels
+        }
+    };
+    Some(bytes_per_pixel)
+}
+
+fn invalid_layout(msg: alloc::string::String) -> RclrsError {
+    RclrsError::with_message(RclReturnCode::InvalidArgument, msg)
+}
+
+/// A mutable view of the pixels of a `sensor_msgs/Image`, which takes the row stride into
+/// account.
+///
+/// Rows may be padded, i.e. the `step` of the image may be larger than its width times the bytes
+/// per pixel. The rows returned by this view exclude the padding.
+///
+/// The view is created from the fields of the message, so it works with the idiomatic as well as
+/// the RMW-native message type, without `rclrs` depending on `sensor_msgs`.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let mut image = sensor_msgs::msg::Image {
+///     width: 640,
+///     height: 480,
+///     step: 640 * 3,
+///     encoding: "rgb8".into(),
+///     data: vec![0; 640 * 3 * 480],
+///     ..Default::default()
+/// };
+/// let mut view = ImageViewMut::new(
+///     &mut image.data,
+///     image.width,
+///     image.height,
+///     image.step,
+///     &image.encoding,
+/// )?;
+/// for (y, row) in view.rows_mut().enumerate() {
+///     camera.read_row(y, row);
+/// }
+/// publisher.publish(image)?;
+/// ```
+pub struct ImageViewMut<'a> {
+    data: &'a mut [u8],
+    width: usize,
+    height: usize,
+    step: usize,
+    bytes_per_pixel: usize,
+}
+
+impl<'a> ImageViewMut<'a> {
+    /// Creates a view of the pixels of an image, from its `data`, `width`, `height`, `step` and
+    /// `encoding` fields.
+    ///
+    /// Returns an error if the encoding is unknown, see [`image_encoding_bytes_per_pixel`], or if
+    /// the layout of the image does not fit its data.
+    pub fn new(
+        data: &'a mut [u8],
+        width: u32,
+        height: u32,
+        step: u32,
+        encoding: &str,
+    ) -> Result<Self, RclrsError> {
+        let (width, height, step) = (width as usize, height as usize, step as usize);
+        let bytes_per_pixel = image_encoding_bytes_per_pixel(encoding)
+            .ok_or_else(|| invalid_layout(format!("Unsupported image encoding '{encoding}'")))?;
+        if step < width * bytes_per_pixel || data.len() < step * height {
+            return Err(invalid_layout(format!(
+                "Image of {width}x{height} pixels with a step of {step} bytes does not fit into \
+                 {} bytes",
+                data.len()
+            )));
+        }
+        Ok(Self {
+            data,
+            width,
+            height,
+            step,
+            bytes_per_pixel,
+        })
+    }
+
+    /// Returns the width of the image in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of the image in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the length of a row in bytes, including the padding.
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    /// Returns the number of bytes per pixel.
+    pub fn bytes_per_pixel(&self) -> usize {
+        self.bytes_per_pixel
+    }
+
+    /// Returns the bytes of a row, without the padding.
+    ///
+    /// # Panics
+    /// When the row is out of bounds.
+    pub fn row_mut(&mut self, y: usize) -> &mut [u8] {
+        assert!(y < self.height, "Row {y} is out of bounds");
+        let start = y * self.step;
+        &mut self.data[start..start + self.width * self.bytes_per_pixel]
+    }
+
+    /// Returns the pixels of a row as values of type `T`, e.g. `u16` for `mono16` images or `f32`
+    /// for `32FC1` images.
+    ///
+    /// Returns `None` if the row is not aligned for `T` or its length is not a multiple of the
+    /// size of `T`.
+    ///
+    /// # Panics
+    /// When the row is out of bounds.
+    pub fn row_as_mut<T: PlainData>(&mut self, y: usize) -> Option<&mut [T]> {
+        let row = self.row_mut(y);
+        // SAFETY: Any bit pattern is a valid value of a PlainData type.
+        let (prefix, values, suffix) = unsafe { row.align_to_mut::<T>() };
+        (prefix.is_empty() && suffix.is_empty()).then_some(values)
+    }
+
+    /// Returns an iterator over the bytes of the rows, without the padding.
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [u8]> {
+        let row_len = self.width * self.bytes_per_pixel;
+        self.data
+            .chunks_mut(self.step.max(1))
+            .take(self.height)
+            .map(move |row| &mut row[..row_len])
+    }
+
+    /// Returns the bytes of a pixel, e.g. the three channels of an `rgb8` pixel.
+    ///
+    /// # Panics
+    /// When the pixel is out of bounds.
+    pub fn pixel_mut(&mut self, x: usize, y: usize) -> &mut [u8] {
+        assert!(x < self.width, "Column {x} is out of bounds");
+        let bytes_per_pixel = self.bytes_per_pixel;
+        let start = x * bytes_per_pixel;
+        &mut self.row_mut(y)[start..start + bytes_per_pixel]
+    }
+}
+
+/// A field of the points in a point cloud, with a value of type `T`, e.g. the `x` coordinate.
+///
+/// It is created once from the `offset` and `datatype` of a `sensor_msgs/PointField`, and then
+/// used for accessing the field in each point through a [`PointCloudViewMut`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PointField<T: PlainData> {
+    offset: usize,
+    data_type: PhantomData<T>,
+}
+
+impl<T: PlainData> PointField<T> {
+    /// Creates a field from the `offset` and `datatype` fields of a `sensor_msgs/PointField`.
+    ///
+    /// Returns an error if the datatype does not match `T`.
+    pub fn new(offset: u32, datatype: u8) -> Result<Self, RclrsError> {
+        if datatype != T::POINT_FIELD_DATATYPE {
+            return Err(invalid_layout(format!(
+                "The field at offset {offset} has the datatype {datatype}, not {}",
+                T::POINT_FIELD_DATATYPE
+            )));
+        }
+        Ok(Self {
+            offset: offset as usize,
+            data_type: PhantomData,
+        })
+    }
+
+    /// Returns the offset of the field within a point, in bytes.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+/// A mutable view of the points of a `sensor_msgs/PointCloud2`, which takes the point and row
+/// strides into account.
+///
+/// The points are indexed in row-major order, so for an unorganized cloud with a height of 1 the
+/// index is simply the position in the cloud. Fields are accessed with [`PointField`]s, which
+/// may be unaligned within the buffer.
+///
+/// Like [`ImageViewMut`], the view is created from the fields of the message.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let field = |name| cloud.fields.iter().find(|field| field.name == name).unwrap();
+/// let x = PointField::<f32>::new(field("x").offset, field("x").datatype)?;
+/// let intensity = PointField::<f32>::new(field("intensity").offset, field("intensity").datatype)?;
+/// let mut view = PointCloudViewMut::new(
+///     &mut cloud.data,
+///     cloud.width,
+///     cloud.height,
+///     cloud.point_step,
+///     cloud.row_step,
+/// )?;
+/// for (i, return_) in scan.returns().enumerate() {
+///     view.set(i, x, return_.x);
+///     view.set(i, intensity, return_.intensity);
+/// }
+/// ```
+pub struct PointCloudViewMut<'a> {
+    data: &'a mut [u8],
+    width: usize,
+    height: usize,
+    point_step: usize,
+    row_step: usize,
+}
+
+impl<'a> PointCloudViewMut<'a> {
+    /// Creates a view of the points of a point cloud, from its `data`, `width`, `height`,
+    /// `point_step` and `row_step` fields.
+    ///
+    /// Returns an error if the layout of the point cloud does not fit its data.
+    pub fn new(
+        data: &'a mut [u8],
+        width: u32,
+        height: u32,
+        point_step: u32,
+        row_step: u32,
+    ) -> Result<Self, RclrsError> {
+        let (width, height) = (width as usize, height as usize);
+        let (point_step, row_step) = (point_step as usize, row_step as usize);
+        if row_step < width * point_step || data.len() < row_step * height {
+            return Err(invalid_layout(format!(
+                "Point cloud of {width}x{height} points with a point step of {point_step} and a \
+                 row step of {row_step} bytes does not fit into {} bytes",
+                data.len()
+            )));
+        }
+        Ok(Self {
+            data,
+            width,
+            height,
+            point_step,
+            row_step,
+        })
+    }
+
+    /// Returns the number of points.
+    pub fn len(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// Returns whether the point cloud has no points.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the bytes of a point.
+    ///
+    /// # Panics
+    /// When the point is out of bounds.
+    pub fn point_mut(&mut self, index: usize) -> &mut [u8] {
+        let range = self.point_range(index);
+        &mut self.data[range]
+    }
+
+    /// Returns the value of a field of a point.
+    ///
+    /// # Panics
+    /// When the point is out of bounds, or the field does not fit into the point.
+    pub fn get<T: PlainData>(&self, index: usize, field: PointField<T>) -> T {
+        let bytes =
+            &self.data[self.point_range(index)][field.offset..field.offset + size_of::<T>()];
+        // SAFETY: The bytes are in bounds, and any bit pattern is a valid value of a PlainData
+        // type.
+        unsafe { bytes.as_ptr().cast::<T>().read_unaligned() }
+    }
+
+    /// Sets the value of a field of a point.
+    ///
+    /// # Panics
+    /// When the point is out of bounds, or the field does not fit into the point.
+    pub fn set<T: PlainData>(&mut self, index: usize, field: PointField<T>, value: T) {
+        let bytes = &mut self.point_mut(index)[field.offset..field.offset + size_of::<T>()];
+        // SAFETY: The bytes are in bounds.
+        unsafe { bytes.as_mut_ptr().cast::<T>().write_unaligned(value) }
+    }
+
+    fn point_range(&self, index: usize) -> core::ops::Range<usize> {
+        assert!(index < self.len(), "Point {index} is out of bounds");
+        let start = index / self.width * self.row_step + index % self.width * self.point_step;
+        start..start + self.point_step
+    }
+}
@@ -0,0 +1,231 @@
+use crate::context::ShutdownObserver;
+use crate::error::ToResult;
+use crate::rcl_bindings::*;
+use crate::tracetools;
+use crate::{
+    GuardCondition, Node, RclReturnCode, RclrsError, ReadyEntities, WaitSet, WaitSetCapacities,
+};
+
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::task::{Context as TaskContext, Poll, Waker};
+
+/// Async version of [`spin`][1], for running a node on an async runtime such as `tokio` or
+/// `async-std`.
+///
+/// Waiting for work happens on a helper thread, which wakes up the task when the wait set is
+/// ready, so the runtime is not blocked between waits and can run other tasks on the same thread.
+/// The callbacks themselves are executed in the task, i.e. on the runtime's thread, so they must
+/// not block for long.
+///
+/// Since the node can not be sent between threads, the returned future can not either. It needs to
+/// be run e.g. with `block_on()`, `select!` or a `LocalSet` on a single-threaded runtime.
+///
+/// The future completes with an error when a callback returns one, or with `Ok(())` when the
+/// context is shut down. The helper thread waits on a guard condition together with the entities
+/// of the node, which is triggered when the context is shut down and when the future is dropped in
+/// the middle of a wait, e.g. by `select!`. Dropping blocks until the helper thread has woken up.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::{Context, RclrsError};
+/// let context = Context::new(std::env::args())?;
+/// let node = context.create_node("async_node")?;
+/// let runtime = tokio::runtime::Builder::new_current_thread()
+///     .enable_all()
+///     .build()
+///     .unwrap();
+/// runtime.block_on(async {
+///     tokio::select! {
+///         result = rclrs::spin_async(&node) => result,
+///         _ = tokio::signal::ctrl_c() => Ok(()),
+///     }
+/// })?;
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::spin
+pub async fn spin_async(node: &Node) -> Result<(), RclrsError> {
+    let context = node.get_context();
+    let interrupt = Arc::new(GuardCondition::new(&context)?);
+    // Without this, the helper thread would wait forever when the context is shut down.
+    let shutdown_interrupt: Arc<dyn ShutdownObserver> =
+        Arc::new(ShutdownInterrupt(interrupt.clone()));
+    context
+        .handle
+        .add_shutdown_observer(Arc::downgrade(&shutdown_interrupt));
+    let guard_conditions = node.guard_conditions();
+    // The set of entities of the node can not change while it is borrowed here, so the wait set
+    // never needs to grow.
+    let capacities = WaitSetCapacities {
+        guard_conditions: 1 + guard_conditions.len(),
+        ..WaitSetCapacities::for_node(node)
+    };
+    let mut wait_set = WaitSet::new(capacities, &context)?;
+    let mut ready_entities = ReadyEntities::new();
+    // The helper thread stops when the sender is dropped at the end of this function.
+    let sender = spawn_wait_thread();
+    while context.ok() {
+        wait_set.clear();
+        for subscription in node.subscriptions.iter().filter_map(Weak::upgrade) {
+            wait_set.add_subscription(subscription)?;
+        }
+        wait_set.add_guard_condition(interrupt.clone())?;
+        for guard_condition in &guard_conditions {
+            wait_set.add_guard_condition(guard_condition.clone())?;
+        }
+        for timer in node.timers.iter().filter_map(Weak::upgrade) {
+            wait_set.add_timer(timer)?;
+        }
+        for client in node.clients.iter().filter_map(Weak::upgrade) {
+            wait_set.add_client(client)?;
+        }
+        for service in node.services.iter().filter_map(Weak::upgrade) {
+            wait_set.add_service(service)?;
+        }
+        for qos_event in node.qos_events.iter().filter_map(Weak::upgrade) {
+            wait_set.add_qos_event(qos_event)?;
+        }
+        tracetools::executor_wait_for_work(-1);
+        match PendingWait::start(&sender, &mut wait_set, &interrupt).await {
+            Ok(()) => {}
+            Err(RclrsError {
+                code: RclReturnCode::Timeout,
+                ..
+            }) => continue,
+            Err(e) => return Err(e),
+        }
+        tracetools::executor_get_next_ready();
+        wait_set.collect_ready_into(&mut ready_entities);
+        for ready_subscription in &ready_entities.subscriptions {
+            ready_subscription.execute()?;
+        }
+        for ready_timer in &ready_entities.timers {
+            ready_timer.execute()?;
+        }
+        for ready_client in &ready_entities.clients {
+            ready_client.execute()?;
+        }
+        for ready_service in &ready_entities.services {
+            ready_service.execute()?;
+        }
+        for ready_qos_event in &ready_entities.qos_events {
+            ready_qos_event.execute()?;
+        }
+    }
+    Ok(())
+}
+
+/// Wakes up the helper thread when the context is shut down.
+struct ShutdownInterrupt(Arc<GuardCondition>);
+
+impl ShutdownObserver for ShutdownInterrupt {
+    fn on_shutdown(&self) {
+        // spin_async() stops once it sees that the context is no longer valid.
+        let _ = self.0.trigger();
+    }
+}
+
+/// The state of one wait, shared between the task and the helper thread.
+#[derive(Default)]
+struct WaitState {
+    result: Mutex<WaitResult>,
+    // Notified when the result has been set, for waiting on it in PendingWait::drop().
+    finished: Condvar,
+}
+
+#[derive(Default)]
+struct WaitResult {
+    ret: Option<rcl_ret_t>,
+    waker: Option<Waker>,
+}
+
+struct WaitRequest {
+    wait_set: *mut rcl_wait_set_t,
+    state: Arc<WaitState>,
+}
+
+// SAFETY: The wait set is not accessed by the task while the helper thread waits on it, which is
+// ensured by PendingWait.
+unsafe impl Send for WaitRequest {}
+
+fn spawn_wait_thread() -> Sender<WaitRequest> {
+    let (sender, receiver) = mpsc::channel::<WaitRequest>();
+    std::thread::spawn(move || {
+        for request in receiver {
+            // SAFETY: The wait set is valid and exclusively used by this thread until the result
+            // is set, see PendingWait.
+            let ret = unsafe { rcl_wait(request.wait_set, -1) };
+            let mut result = request.state.result.lock().unwrap();
+            result.ret = Some(ret);
+            if let Some(waker) = result.waker.take() {
+                waker.wake();
+            }
+            request.state.finished.notify_all();
+        }
+    });
+    sender
+}
+
+/// A future for a wait that runs on the helper thread.
+///
+/// It mutably borrows the wait set, and does not release it before the helper thread is done with
+/// it, even when dropped early.
+struct PendingWait<'a> {
+    state: Arc<WaitState>,
+    interrupt: &'a GuardCondition,
+    _wait_set: PhantomData<&'a mut WaitSet>,
+}
+
+impl<'a> PendingWait<'a> {
+    fn start(
+        sender: &Sender<WaitRequest>,
+        wait_set: &'a mut WaitSet,
+        interrupt: &'a GuardCondition,
+    ) -> Self {
+        let state = Arc::new(WaitState::default());
+        let request = WaitRequest {
+            wait_set: wait_set.rcl_handle(),
+            state: state.clone(),
+        };
+        // The helper thread only stops once the sender is dropped.
+        sender.send(request).unwrap();
+        Self {
+            state,
+            interrupt,
+            _wait_set: PhantomData,
+        }
+    }
+}
+
+impl Future for PendingWait<'_> {
+    type Output = Result<(), RclrsError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        let mut result = self.state.result.lock().unwrap();
+        match result.ret {
+            Some(ret) => Poll::Ready(ret.ok()),
+            None => {
+                result.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for PendingWait<'_> {
+    fn drop(&mut self) {
+        let mut result = self.state.result.lock().unwrap();
+        if result.ret.is_some() {
+            return;
+        }
+        // The wait set must not be released while the helper thread still waits on it.
+        let _ = self.interrupt.trigger();
+        while result.ret.is_none() {
+            result = self.state.finished.wait(result).unwrap();
+        }
+    }
+}
@@ -0,0 +1,10 @@
+//! The mutex used throughout rclrs.
+//!
+//! With the `std` feature, this is the `parking_lot` mutex. Without it, a spinlock is used,
+//! since there is no OS to park threads on.
+
+#[cfg(feature = "std")]
+pub(crate) use parking_lot::{Mutex, MutexGuard};
+
+#[cfg(not(feature = "std"))]
+pub(crate) use spin::{Mutex, MutexGuard};
@@ -0,0 +1,405 @@
+use crate::rcl_bindings::*;
+use crate::sync::Mutex;
+use crate::{
+    ClientBase, Context, Gid, MessageInfo, Node, RclrsError, ReadyEntities, RequestId, ServiceBase,
+    SubscriptionBase, TimerBase, ToResult,
+};
+
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+
+/// An in-process transport, which replaces the middleware for the publishers, subscriptions,
+/// clients and services of a context, so that tests can exercise them deterministically.
+///
+/// After a loopback has been attached to a context with [`Loopback::new`], the [`Publisher`][1]s,
+/// [`Subscription`][2]s, [`Client`][3]s and [`Service`][4]s that are created from the nodes of
+/// the context communicate only through the loopback. Messages, requests and responses are
+/// serialized as usual, but instead of being handed to the middleware, they are queued in the
+/// order in which they were sent. Nothing is lost to discovery or to the network, and nothing
+/// leaves the process.
+///
+/// The queue is not processed by [`spin`][5] and [`spin_once`][6], which wait on the middleware.
+/// Instead, [`Loopback::spin_node_until_idle`] and
+/// [`DeterministicExecutor::spin_until_idle`][7] deliver the queued items one after another,
+/// together with the timers that are due, until nothing is left. Items sent by the callbacks are
+/// delivered in the same call, after the ones that were already queued. A message is delivered to
+/// every subscription of its topic, in the order in which the subscriptions were created, and a
+/// request to the first service of its name.
+///
+/// The entities still exist in the middleware, and show up in the ROS graph. Only the typed
+/// entities listed above use the loopback. Entities created before the loopback was attached,
+/// generic and dynamic subscriptions and entities from `from_raw()` functions keep using the
+/// middleware. Topic and service names are matched after [expansion][8], but remapping rules
+/// are not applied. The [`MessageInfo`][9] of messages from the loopback contains the GID of
+/// their publisher and the system time at which they were published, and is marked as
+/// intra-process.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// use rclrs::testing::{Inbox, Loopback};
+/// let context = Context::new([])?;
+/// let loopback = Loopback::new(&context);
+/// let mut node = context.create_node("test_node")?;
+/// let inbox = Inbox::new();
+/// let _subscription = node.create_subscription::<std_msgs::msg::String, _>(
+///     "topic",
+///     QOS_PROFILE_DEFAULT,
+///     inbox.callback(),
+/// )?;
+/// let publisher = node.create_publisher::<std_msgs::msg::String>("topic", QOS_PROFILE_DEFAULT)?;
+/// publisher.publish(std_msgs::msg::String { data: "hello".into() })?;
+/// loopback.spin_node_until_idle(&node)?;
+/// assert_eq!(inbox.len(), 1);
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::Publisher
+/// [2]: crate::Subscription
+/// [3]: crate::Client
+/// [4]: crate::Service
+/// [5]: crate::spin
+/// [6]: crate::spin_once
+/// [7]: crate::testing::DeterministicExecutor::spin_until_idle
+/// [8]: crate::Node::expand_topic_name
+/// [9]: crate::MessageInfo
+#[derive(Clone)]
+pub struct Loopback {
+    state: Arc<Mutex<LoopbackState>>,
+}
+
+#[derive(Default)]
+struct LoopbackState {
+    // The subscriptions, services and clients, in the order in which they were created.
+    endpoints: Vec<Endpoint>,
+    // The addresses of the endpoints with a pending frame, once per frame, in the order in which
+    // the frames were sent.
+    ready: VecDeque<usize>,
+}
+
+// A subscription, service or client in the loopback, identified by the address of its handle.
+struct Endpoint {
+    address: usize,
+    kind: EndpointKind,
+    // The expanded topic or service name. Remapping rules are not applied.
+    name: String,
+    frames: VecDeque<Frame>,
+    // For clients, the sequence number of the next request.
+    next_sequence_number: i64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EndpointKind {
+    Subscription,
+    Service,
+    Client,
+}
+
+/// A serialized message, request or response in the loopback.
+pub(crate) struct Frame {
+    pub(crate) payload: Vec<u8>,
+    // The ID of the request, for requests and responses. It is zero for messages.
+    pub(crate) request_id: RequestId,
+    // The information about the message, for messages.
+    pub(crate) message_info: Option<MessageInfo>,
+}
+
+/// The registration of a subscription, service or client with a loopback, which is removed when
+/// this is dropped.
+pub(crate) struct LoopbackEndpoint {
+    loopback: Loopback,
+    address: usize,
+}
+
+/// The topic of a publisher that uses a loopback.
+#[derive(Clone)]
+pub(crate) struct LoopbackPublisher {
+    loopback: Loopback,
+    topic: String,
+}
+
+// The entities that the frames of a loopback can be delivered to.
+pub(crate) struct LoopbackTargets {
+    pub(crate) subscriptions: Vec<Arc<dyn SubscriptionBase>>,
+    pub(crate) timers: Vec<Arc<dyn TimerBase>>,
+    pub(crate) clients: Vec<Arc<dyn ClientBase>>,
+    pub(crate) services: Vec<Arc<dyn ServiceBase>>,
+}
+
+impl Loopback {
+    /// Creates an empty loopback, and attaches it to the context.
+    ///
+    /// This replaces a loopback that was attached before, but the entities that use the old one
+    /// keep using it. The loopback stays attached when the context is reinitialized.
+    pub fn new(context: &Context) -> Self {
+        let loopback = Self {
+            state: Arc::new(Mutex::new(LoopbackState::default())),
+        };
+        *context.handle.loopback.lock() = Some(loopback.clone());
+        loopback
+    }
+
+    /// Returns the number of messages, requests and responses that have not been delivered yet.
+    pub fn pending(&self) -> usize {
+        self.state.lock().ready.len()
+    }
+
+    /// Delivers the queued messages, requests and responses to the entities of the node, and
+    /// executes its timers that are due, until neither is left.
+    ///
+    /// Items for entities of other nodes stay queued. This does not return if the callbacks keep
+    /// sending items, e.g. a subscription that publishes on its own topic.
+    pub fn spin_node_until_idle(&self, node: &Node) -> Result<(), RclrsError> {
+        let targets = || LoopbackTargets {
+            subscriptions: node
+                .subscriptions
+                .iter()
+                .filter_map(Weak::upgrade)
+                .collect(),
+            timers: node.timers.iter().filter_map(Weak::upgrade).collect(),
+            clients: node.clients.iter().filter_map(Weak::upgrade).collect(),
+            services: node.services.iter().filter_map(Weak::upgrade).collect(),
+        };
+        self.run_until_idle(targets, |ready| {
+            ready.execute(Some(node), node.callback_hooks.as_ref())
+        })
+    }
+
+    // Executes the due timers and delivers the frames for the targets, until neither is left.
+    // Due timers go first, in the order of the targets, and the frames are delivered one at a
+    // time, so that the interleaving is the same in every run. The targets are fetched again
+    // for every step, since the callbacks may add or remove entities.
+    pub(crate) fn run_until_idle(
+        &self,
+        targets: impl Fn() -> LoopbackTargets,
+        mut execute: impl FnMut(&ReadyEntities) -> Result<(), RclrsError>,
+    ) -> Result<(), RclrsError> {
+        let mut ready = ReadyEntities::new();
+        loop {
+            let targets = targets();
+            for timer in &targets.timers {
+                let mut is_ready = false;
+                // SAFETY: No preconditions for this function (besides passing in a valid handle).
+                unsafe { rcl_timer_is_ready(&*timer.handle().lock(), &mut is_ready) }.ok()?;
+                if is_ready {
+                    ready.timers.push(Arc::clone(timer));
+                }
+            }
+            if ready.timers.is_empty() {
+                let Some(address) = self.next_ready(|address| targets.contains(address)) else {
+                    return Ok(());
+                };
+                targets.push_to(address, &mut ready);
+            }
+            let result = execute(&ready);
+            ready.clear();
+            result?;
+        }
+    }
+
+    // Removes and returns the address of the first endpoint with a pending frame that passes
+    // the filter.
+    fn next_ready(&self, filter: impl Fn(usize) -> bool) -> Option<usize> {
+        let ready = &mut self.state.lock().ready;
+        let index = ready.iter().position(|&address| filter(address))?;
+        ready.remove(index)
+    }
+
+    // Registers a subscription, service or client, by the address of its handle.
+    fn register(&self, address: usize, kind: EndpointKind, name: String) -> LoopbackEndpoint {
+        self.state.lock().endpoints.push(Endpoint {
+            address,
+            kind,
+            name,
+            frames: VecDeque::new(),
+            next_sequence_number: 1,
+        });
+        LoopbackEndpoint {
+            loopback: self.clone(),
+            address,
+        }
+    }
+
+    // Queues a serialized message for every subscription of the topic.
+    fn publish(&self, topic: &str, payload: &[u8], publisher_gid: Gid) {
+        let mut source_timestamp = 0;
+        // SAFETY: No preconditions for this function. If it fails, the timestamp stays zero, like
+        // with RMW implementations that don't set it.
+        unsafe { rcutils_system_time_now(&mut source_timestamp) };
+        let message_info = MessageInfo {
+            source_timestamp,
+            publisher_gid,
+            from_intra_process: true,
+        };
+        let state = &mut *self.state.lock();
+        for endpoint in &mut state.endpoints {
+            if endpoint.kind == EndpointKind::Subscription && endpoint.name == topic {
+                endpoint.frames.push_back(Frame {
+                    payload: payload.to_vec(),
+                    request_id: RequestId::new([0; 16], 0),
+                    message_info: Some(message_info.clone()),
+                });
+                state.ready.push_back(endpoint.address);
+            }
+        }
+    }
+}
+
+impl LoopbackPublisher {
+    /// Returns the publisher for the topic, if the node's context has a loopback.
+    pub(crate) fn new(node: &Node, topic: &str) -> Option<Self> {
+        Some(Self {
+            loopback: node.context.loopback.lock().clone()?,
+            topic: topic.into(),
+        })
+    }
+
+    /// Queues a serialized message of the publisher with the GID for every subscription of the
+    /// topic.
+    pub(crate) fn publish(&self, payload: &[u8], publisher_gid: Gid) {
+        self.loopback.publish(&self.topic, payload, publisher_gid)
+    }
+}
+
+impl LoopbackEndpoint {
+    /// Registers the subscription, service or client whose handle is being created in the
+    /// `Arc`, if the node's context has a loopback.
+    pub(crate) fn new<T>(
+        node: &Node,
+        handle: &Weak<T>,
+        kind: EndpointKind,
+        name: &str,
+    ) -> Option<Self> {
+        let loopback = node.context.loopback.lock().clone()?;
+        Some(loopback.register(handle.as_ptr() as usize, kind, name.into()))
+    }
+
+    /// Removes and returns the oldest pending frame of this endpoint.
+    pub(crate) fn take(&self) -> Option<Frame> {
+        let state = &mut *self.loopback.state.lock();
+        state
+            .endpoints
+            .iter_mut()
+            .find(|endpoint| endpoint.address == self.address)?
+            .frames
+            .pop_front()
+    }
+
+    /// Queues a serialized request of this client for the first service with its name, and
+    /// returns the sequence number of the request.
+    ///
+    /// Like with the middleware, a request without a service is lost.
+    pub(crate) fn send_request(&self, payload: &[u8]) -> i64 {
+        let state = &mut *self.loopback.state.lock();
+        let Some(client) = state
+            .endpoints
+            .iter_mut()
+            .find(|endpoint| endpoint.address == self.address)
+        else {
+            return -1;
+        };
+        let sequence_number = client.next_sequence_number;
+        client.next_sequence_number += 1;
+        let service_name = client.name.clone();
+        let request_id = RequestId::new(self.writer_guid(), sequence_number);
+        if let Some(service) = state.endpoints.iter_mut().find(|endpoint| {
+            endpoint.kind == EndpointKind::Service && endpoint.name == service_name
+        }) {
+            service.frames.push_back(Frame {
+                payload: payload.to_vec(),
+                request_id,
+                message_info: None,
+            });
+            state.ready.push_back(service.address);
+        }
+        sequence_number
+    }
+
+    /// Queues a serialized response of this service for the client that sent the request.
+    ///
+    /// A response for a client that has been dropped is lost.
+    pub(crate) fn send_response(&self, request_id: &RequestId, payload: &[u8]) {
+        const SIZE: usize = core::mem::size_of::<usize>();
+        let mut address = [0; SIZE];
+        address.copy_from_slice(&request_id.writer_guid()[..SIZE]);
+        let address = usize::from_le_bytes(address);
+        let state = &mut *self.loopback.state.lock();
+        if let Some(client) = state
+            .endpoints
+            .iter_mut()
+            .find(|endpoint| endpoint.kind == EndpointKind::Client && endpoint.address == address)
+        {
+            client.frames.push_back(Frame {
+                payload: payload.to_vec(),
+                request_id: *request_id,
+                message_info: None,
+            });
+            state.ready.push_back(address);
+        }
+    }
+
+    /// Returns the GUID of this client in the request IDs that services see, which encodes the
+    /// address of the endpoint.
+    pub(crate) fn writer_guid(&self) -> [u8; 16] {
+        let mut writer_guid = [0; 16];
+        let address = self.address.to_le_bytes();
+        writer_guid[..address.len()].copy_from_slice(&address);
+        writer_guid
+    }
+}
+
+impl Drop for LoopbackEndpoint {
+    fn drop(&mut self) {
+        let state = &mut *self.loopback.state.lock();
+        state
+            .endpoints
+            .retain(|endpoint| endpoint.address != self.address);
+        state.ready.retain(|&address| address != self.address);
+    }
+}
+
+impl LoopbackTargets {
+    fn contains(&self, address: usize) -> bool {
+        self.subscriptions
+            .iter()
+            .any(|entity| handle_address(entity.handle()) == address)
+            || self
+                .clients
+                .iter()
+                .any(|entity| handle_address(entity.handle()) == address)
+            || self
+                .services
+                .iter()
+                .any(|entity| handle_address(entity.handle()) == address)
+    }
+
+    // Adds the entity with the handle at the address to the ready entities.
+    fn push_to(&self, address: usize, ready: &mut ReadyEntities) {
+        if let Some(entity) = self
+            .subscriptions
+            .iter()
+            .find(|entity| handle_address(entity.handle()) == address)
+        {
+            ready.subscriptions.push(Arc::clone(entity));
+        } else if let Some(entity) = self
+            .clients
+            .iter()
+            .find(|entity| handle_address(entity.handle()) == address)
+        {
+            ready.clients.push(Arc::clone(entity));
+        } else if let Some(entity) = self
+            .services
+            .iter()
+            .find(|entity| handle_address(entity.handle()) == address)
+        {
+            ready.services.push(Arc::clone(entity));
+        }
+    }
+}
+
+/// Returns the address of a handle, which identifies its entity in the loopback.
+pub(crate) fn handle_address<T>(handle: &T) -> usize {
+    handle as *const T as usize
+}
@@ -0,0 +1,296 @@
+//! Utilities for testing code that uses `rclrs`.
+//!
+//! These make it possible to test subscription callbacks deterministically, without depending on
+//! discovery and message delivery in the middleware:
+//! - [`deliver`] runs a subscription's callback directly with a given message.
+//! - [`Inbox`] collects the messages passed to a callback, so that a test can inspect them.
+//! - [`ManualClock`] controls the ROS time of a node, so that timers fire exactly when the test
+//!   advances the time.
+//! - [`DeterministicExecutor`] executes entities in a reproducible order, driven by a
+//!   [`ManualClock`].
+//! - [`Loopback`] replaces the middleware with an in-process queue, so that publishers,
+//!   subscriptions, clients and services can be tested end to end, without discovery.
+//!
+//! # Example
+//! ```ignore
+//! # use rclrs::{Context, RclrsError, QOS_PROFILE_DEFAULT};
+//! use rclrs::testing::{deliver, Inbox};
+//! let context = Context::new([])?;
+//! let mut node = context.create_node("test_node")?;
+//! let inbox = Inbox::new();
+//! let subscription = node.create_subscription::<std_msgs::msg::String, _>(
+//!     "topic",
+//!     QOS_PROFILE_DEFAULT,
+//!     inbox.callback(),
+//! )?;
+//! let message = std_msgs::msg::String { data: "hello".into() };
+//! deliver(&subscription, message.clone());
+//! assert_eq!(inbox.take_all(), vec![message]);
+//! # Ok::<(), RclrsError>(())
+//! ```
+
+mod loopback;
+
+pub use loopback::*;
+
+use crate::sync::Mutex;
+use crate::{Clock, ClockType, Context, Executor, RclReturnCode, RclrsError, Subscription, Time};
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use rosidl_runtime_rs::Message;
+
+/// Runs the subscription's callback with the given message, as if it had been received.
+///
+/// This bypasses the middleware entirely, so the message is not seen by any other subscription.
+pub fn deliver<T: Message>(subscription: &Subscription<T>, message: T) {
+    (*subscription.callback.lock())(message);
+}
+
+/// A shared list of the messages received by a callback.
+///
+/// Clones of an inbox refer to the same list.
+pub struct Inbox<T> {
+    messages: Arc<Mutex<Vec<T>>>,
+}
+
+impl<T> Clone for Inbox<T> {
+    fn clone(&self) -> Self {
+        Self {
+            messages: Arc::clone(&self.messages),
+        }
+    }
+}
+
+impl<T> Default for Inbox<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Inbox<T> {
+    /// Creates an empty inbox.
+    pub fn new() -> Self {
+        Self {
+            messages: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Returns a callback that appends each message to this inbox.
+    pub fn callback(&self) -> impl FnMut(T) + 'static
+    where
+        T: 'static,
+    {
+        let messages = Arc::clone(&self.messages);
+        move |message| messages.lock().push(message)
+    }
+
+    /// Returns the number of messages in the inbox.
+    pub fn len(&self) -> usize {
+        self.messages.lock().len()
+    }
+
+    /// Returns true if the inbox contains no messages.
+    pub fn is_empty(&self) -> bool {
+        self.messages.lock().is_empty()
+    }
+
+    /// Removes and returns all messages, in the order they were received.
+    pub fn take_all(&self) -> Vec<T> {
+        core::mem::take(&mut *self.messages.lock())
+    }
+}
+
+/// Takes control of a ROS-time clock, so that it only advances when the test says so.
+///
+/// Create it from the clock of the node under test, and create timers on that clock with
+/// [`Node::create_timer_with_clock`][1]. After advancing the clock, the timers that are due are
+/// executed by the next [`spin_once`][2], independently of the system time. The clock starts at
+/// zero.
+///
+/// The clock returns to the system time when this is dropped.
+///
+/// # Example
+/// ```
+/// # use rclrs::{Context, RclrsError};
+/// use rclrs::testing::ManualClock;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// let context = Context::new([])?;
+/// let mut node = context.create_node("test_node")?;
+/// let clock = ManualClock::new(node.get_clock())?;
+/// let ticks = Arc::new(AtomicUsize::new(0));
+/// let ticks_in_callback = ticks.clone();
+/// let _timer = node.create_timer_with_clock(Duration::from_secs(1), &clock.clock(), move || {
+///     ticks_in_callback.fetch_add(1, Ordering::SeqCst);
+/// })?;
+/// clock.advance(Duration::from_secs(1))?;
+/// rclrs::spin_once(&node, Some(Duration::ZERO))?;
+/// assert_eq!(ticks.load(Ordering::SeqCst), 1);
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::Node::create_timer_with_clock
+/// [2]: crate::spin_once
+pub struct ManualClock {
+    clock: Clock,
+}
+
+impl ManualClock {
+    /// Takes control of the clock, and sets it to zero.
+    ///
+    /// Returns an [`InvalidArgument`][1] error if the clock is not a [ROS-time][2] clock.
+    ///
+    /// [1]: crate::RclReturnCode::InvalidArgument
+    /// [2]: crate::ClockType::RosTime
+    pub fn new(clock: Clock) -> Result<Self, RclrsError> {
+        if clock.clock_type() != ClockType::RosTime {
+            return Err(RclrsError::with_message(
+                RclReturnCode::InvalidArgument,
+                "Only a ROS-time clock can be controlled manually",
+            ));
+        }
+        clock.set_ros_time_override(Some(0))?;
+        Ok(Self { clock })
+    }
+
+    /// Returns the controlled clock, e.g. for creating timers on it.
+    pub fn clock(&self) -> Clock {
+        self.clock.clone()
+    }
+
+    /// Returns the current time of the clock.
+    pub fn now(&self) -> Result<Time, RclrsError> {
+        self.clock.now()
+    }
+
+    /// Sets the clock to the given time.
+    ///
+    /// Setting an earlier time is a backward jump, which restarts the periods of the timers on
+    /// the clock.
+    pub fn set_time(&self, time: Time) -> Result<(), RclrsError> {
+        self.clock.set_ros_time_override(Some(time.nsec))
+    }
+
+    /// Advances the clock by the given duration.
+    ///
+    /// Returns an [`InvalidArgument`][1] error if the time would overflow.
+    ///
+    /// [1]: crate::RclReturnCode::InvalidArgument
+    pub fn advance(&self, duration: Duration) -> Result<(), RclrsError> {
+        let time = self.now()?.checked_add(duration).ok_or_else(|| {
+            RclrsError::with_message(
+                RclReturnCode::InvalidArgument,
+                "The time of the manual clock would overflow",
+            )
+        })?;
+        self.set_time(time)
+    }
+}
+
+impl Drop for ManualClock {
+    fn drop(&mut self) {
+        // The clock is valid, so this can not fail.
+        let _ = self.clock.set_ros_time_override(None);
+    }
+}
+
+/// An [`Executor`] that executes its entities in a deterministic order, driven by a
+/// [`ManualClock`].
+///
+/// The entities are added through [`DeterministicExecutor::executor`]. Entities that are ready at
+/// the same time are executed in the order in which they were added, see
+/// [`Executor::new_deterministic`]. [`DeterministicExecutor::advance`] moves the clock forward
+/// from one timer deadline to the next, and executes everything that became ready at each of
+/// them, so timers with different periods fire in the order of their deadlines, and interleave
+/// with the messages they cause in the same way in every run.
+///
+/// The executor attaches a [`Loopback`] to the context, and delivers the messages, requests and
+/// responses of its entities only through the loopback, instead of waiting on the middleware.
+/// The publishers, subscriptions, clients and services must therefore be created after the
+/// executor, and guard conditions are not executed. The timers must be created on the clock of
+/// the [`ManualClock`], see [`Node::create_timer_with_clock`][1].
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// use rclrs::testing::{DeterministicExecutor, ManualClock};
+/// let mut node = context.create_node("test_node")?;
+/// let executor = DeterministicExecutor::new(&context, ManualClock::new(node.get_clock())?)?;
+/// let clock = executor.clock().clock();
+/// executor.executor().add_timer(node.create_timer_with_clock(fast_period, &clock, fast)?)?;
+/// executor.executor().add_timer(node.create_timer_with_clock(slow_period, &clock, slow)?)?;
+/// executor.advance(Duration::from_secs(10))?;
+/// ```
+///
+/// [1]: crate::Node::create_timer_with_clock
+pub struct DeterministicExecutor {
+    executor: Executor,
+    clock: ManualClock,
+    loopback: Loopback,
+}
+
+impl DeterministicExecutor {
+    /// Creates a deterministic executor without entities, whose time is driven by the clock.
+    ///
+    /// This uses the loopback of the context, or attaches a new one if there is none.
+    pub fn new(context: &Context, clock: ManualClock) -> Result<Self, RclrsError> {
+        let loopback = context.handle.loopback.lock().clone();
+        Ok(Self {
+            executor: Executor::new_deterministic(context)?,
+            clock,
+            loopback: loopback.unwrap_or_else(|| Loopback::new(context)),
+        })
+    }
+
+    /// Returns the executor, for adding and removing entities.
+    pub fn executor(&self) -> &Executor {
+        &self.executor
+    }
+
+    /// Returns the clock that drives the executor.
+    pub fn clock(&self) -> &ManualClock {
+        &self.clock
+    }
+
+    /// Returns the loopback that the entities of the executor communicate through.
+    pub fn loopback(&self) -> &Loopback {
+        &self.loopback
+    }
+
+    /// Delivers the pending messages, requests and responses of the loopback and executes the
+    /// timers that are due, until neither is left, without advancing the clock.
+    ///
+    /// Since nothing waits on the middleware, this returns as soon as the queue is empty. It does
+    /// not return if the callbacks keep sending items, e.g. a subscription that publishes on its
+    /// own topic.
+    pub fn spin_until_idle(&self) -> Result<(), RclrsError> {
+        self.executor.spin_loopback_until_idle(&self.loopback)
+    }
+
+    /// Advances the clock by the given duration, stopping at each timer deadline on the way, and
+    /// executes the entities that are ready at each stop with
+    /// [`DeterministicExecutor::spin_until_idle`].
+    ///
+    /// Returns an [`InvalidArgument`][1] error if the time would overflow.
+    ///
+    /// [1]: crate::RclReturnCode::InvalidArgument
+    pub fn advance(&self, duration: Duration) -> Result<(), RclrsError> {
+        let mut remaining = duration;
+        loop {
+            let step = match self.executor.time_until_next_timer()? {
+                Some(until_timer) if until_timer <= remaining => until_timer,
+                _ => remaining,
+            };
+            self.clock.advance(step)?;
+            remaining -= step;
+            self.spin_until_idle()?;
+            if remaining.is_zero() {
+                return Ok(());
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use crate::ClockType;
+
+use core::fmt;
+use core::time::Duration;
+
+const NSEC_PER_SEC: i64 = 1_000_000_000;
+
+/// A point in time of a [`Clock`][1].
+///
+/// Times of different clock types can not be compared meaningfully, e.g. the steady time usually
+/// counts from the boot of the system, while the system time counts from the Unix epoch.
+///
+/// The [`Display`][2] implementation prints the time in seconds with nanosecond precision, like
+/// `ros2 topic echo` does for stamps, e.g. `1700000000.500000000`.
+///
+/// [1]: crate::Clock
+/// [2]: core::fmt::Display
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Time {
+    /// The nanoseconds since the start of the clock.
+    pub nsec: i64,
+    /// The type of the clock that this time was taken from.
+    pub clock_type: ClockType,
+}
+
+impl Time {
+    /// Creates a time from the `sec` and `nanosec` fields of a `builtin_interfaces/Time` message.
+    pub fn from_sec_nanosec(sec: i32, nanosec: u32, clock_type: ClockType) -> Self {
+        Self {
+            nsec: i64::from(sec) * NSEC_PER_SEC + i64::from(nanosec),
+            clock_type,
+        }
+    }
+
+    /// Returns the `sec` and `nanosec` fields of a `builtin_interfaces/Time` message, e.g. for
+    /// stamping a header.
+    ///
+    /// The nanoseconds are always positive, so times before the start of the clock have negative
+    /// seconds. Times that do not fit into the message are saturated.
+    pub fn to_sec_nanosec(&self) -> (i32, u32) {
+        let sec = self.nsec.div_euclid(NSEC_PER_SEC);
+        let nanosec = self.nsec.rem_euclid(NSEC_PER_SEC) as u32;
+        match i32::try_from(sec) {
+            Ok(sec) => (sec, nanosec),
+            Err(_) if sec < 0 => (i32::MIN, 0),
+            Err(_) => (i32::MAX, 999_999_999),
+        }
+    }
+
+    /// Returns the time that is `duration` later, or `None` if it can not be represented.
+    pub fn checked_add(&self, duration: Duration) -> Option<Time> {
+        let nsec = i64::try_from(duration.as_nanos()).ok()?;
+        Some(Time {
+            nsec: self.nsec.checked_add(nsec)?,
+            clock_type: self.clock_type,
+        })
+    }
+
+    /// Returns the time that is `duration` earlier, or `None` if it can not be represented.
+    pub fn checked_sub(&self, duration: Duration) -> Option<Time> {
+        let nsec = i64::try_from(duration.as_nanos()).ok()?;
+        Some(Time {
+            nsec: self.nsec.checked_sub(nsec)?,
+            clock_type: self.clock_type,
+        })
+    }
+
+    /// Returns the time elapsed from `earlier` to this time, or zero if `earlier` is later.
+    pub fn saturating_duration_since(&self, earlier: Time) -> Duration {
+        let nsec = self.nsec.saturating_sub(earlier.nsec).max(0);
+        Duration::from_nanos(nsec as u64)
+    }
+
+    /// Converts the time into a [`SystemTime`][1].
+    ///
+    /// Returns `None` for [steady time][2], which does not count from the Unix epoch. Note that
+    /// [ROS time][3] only corresponds to the system time when no simulated time is used.
+    ///
+    /// [1]: std::time::SystemTime
+    /// [2]: crate::ClockType::SteadyTime
+    /// [3]: crate::ClockType::RosTime
+    #[cfg(feature = "std")]
+    pub fn to_system_time(&self) -> Option<std::time::SystemTime> {
+        if self.clock_type == ClockType::SteadyTime {
+            return None;
+        }
+        let since_epoch = Duration::from_nanos(self.nsec.unsigned_abs());
+        if self.nsec >= 0 {
+            std::time::UNIX_EPOCH.checked_add(since_epoch)
+        } else {
+            std::time::UNIX_EPOCH.checked_sub(since_epoch)
+        }
+    }
+
+    /// Converts the time into a UTC date and time.
+    ///
+    /// Returns `None` for [steady time][1], like [`Time::to_system_time`].
+    ///
+    /// [1]: crate::ClockType::SteadyTime
+    #[cfg(feature = "chrono")]
+    pub fn to_date_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        if self.clock_type == ClockType::SteadyTime {
+            return None;
+        }
+        Some(chrono::DateTime::from_timestamp_nanos(self.nsec))
+    }
+}
+
+/// Converts a system time into a [`Time`] of the [system clock][1].
+///
+/// Times that are too far from the Unix epoch to be represented are saturated.
+///
+/// [1]: crate::ClockType::SystemTime
+#[cfg(feature = "std")]
+impl From<std::time::SystemTime> for Time {
+    fn from(time: std::time::SystemTime) -> Self {
+        let nsec = match time.duration_since(std::time::UNIX_EPOCH) {
+            Ok(after) => i64::try_from(after.as_nanos()).unwrap_or(i64::MAX),
+            Err(before) => i64::try_from(before.duration().as_nanos())
+                .map(|nsec| -nsec)
+                .unwrap_or(i64::MIN),
+        };
+        Self {
+            nsec,
+            clock_type: ClockType::SystemTime,
+        }
+    }
+}
+
+/// Converts a date and time into a [`Time`] of the [system clock][1].
+///
+/// Dates that are too far from the Unix epoch to be represented, i.e. outside of the years 1677 to
+/// 2262, are saturated.
+///
+/// [1]: crate::ClockType::SystemTime
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> From<chrono::DateTime<Tz>> for Time {
+    fn from(time: chrono::DateTime<Tz>) -> Self {
+        let nsec = time
+            .timestamp_nanos_opt()
+            .unwrap_or(if time.timestamp() < 0 {
+                i64::MIN
+            } else {
+                i64::MAX
+            });
+        Self {
+            nsec,
+            clock_type: ClockType::SystemTime,
+        }
+    }
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.nsec < 0 { "-" } else { "" };
+        let abs = self.nsec.unsigned_abs();
+        let nsec_per_sec = NSEC_PER_SEC as u64;
+        write!(
+            f,
+            "{}{}.{:09}",
+            sign,
+            abs / nsec_per_sec,
+            abs % nsec_per_sec
+        )
+    }
+}
@@ -0,0 +1,243 @@
+use crate::{ClockType, Stamped, Time};
+
+use alloc::collections::VecDeque;
+use core::time::Duration;
+
+/// A type whose values can be interpolated between two stamped values, for
+/// [`TimeCache::interpolate`].
+pub trait Interpolate: Sized {
+    /// Returns the value at `ratio` between `earlier` and `later`, where a ratio of `0.0`
+    /// corresponds to `earlier` and `1.0` to `later`.
+    ///
+    /// The stamp of the returned value is set by the cache afterwards.
+    fn interpolate(earlier: &Self, later: &Self, ratio: f64) -> Self;
+}
+
+/// A cache of stamped messages, ordered by their stamps, like the time cache of `tf2`.
+///
+/// Messages are pruned when they are older than the cache duration, relative to the latest
+/// message in the cache. This makes the cache usable for buffering transforms, for message
+/// filters that match messages by their stamps, and for sensor fusion.
+///
+/// The stamps of messages have no clock type, so they are treated as [ROS time][1], and the clock
+/// types of the [`Time`]s given to the cache are ignored.
+///
+/// # Example
+/// ```
+/// # use rclrs::{ClockType, Stamped, Time, TimeCache};
+/// # use std::time::Duration;
+/// #[derive(Clone)]
+/// struct Reading {
+///     stamp: (i32, u32),
+///     value: f64,
+/// }
+///
+/// impl Stamped for Reading {
+///     fn stamp(&self) -> (i32, u32) {
+///         self.stamp
+///     }
+///     fn set_stamp(&mut self, sec: i32, nanosec: u32) {
+///         self.stamp = (sec, nanosec);
+///     }
+///     fn set_frame_id(&mut self, _frame_id: &str) {}
+/// }
+///
+/// let mut cache = TimeCache::new(Duration::from_secs(10));
+/// cache.insert(Reading { stamp: (1, 0), value: 1.0 });
+/// cache.insert(Reading { stamp: (2, 0), value: 2.0 });
+/// let time = Time::from_sec_nanosec(1, 500_000_000, ClockType::RosTime);
+/// let (earlier, later) = cache.surrounding(time).unwrap();
+/// assert_eq!((earlier.value, later.value), (1.0, 2.0));
+/// ```
+///
+/// [1]: crate::ClockType::RosTime
+#[derive(Clone, Debug)]
+pub struct TimeCache<T: Stamped> {
+    // Ordered by the stamps in nanoseconds, oldest first. Messages with the same stamp are kept in
+    // the order of insertion.
+    entries: VecDeque<(i64, T)>,
+    cache_duration: Duration,
+}
+
+fn stamp_nsec(msg: &impl Stamped) -> i64 {
+    let (sec, nanosec) = msg.stamp();
+    Time::from_sec_nanosec(sec, nanosec, ClockType::RosTime).nsec
+}
+
+fn ros_time(nsec: i64) -> Time {
+    Time {
+        nsec,
+        clock_type: ClockType::RosTime,
+    }
+}
+
+impl<T: Stamped> TimeCache<T> {
+    /// Creates an empty cache that keeps messages for the given duration.
+    pub fn new(cache_duration: Duration) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            cache_duration,
+        }
+    }
+
+    /// Returns the duration that messages are kept for.
+    pub fn cache_duration(&self) -> Duration {
+        self.cache_duration
+    }
+
+    /// Returns the number of messages in the cache.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes all messages from the cache.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Inserts a message at the position of its stamp, and prunes the messages that are older
+    /// than the cache duration.
+    ///
+    /// Returns `false` and drops the message if it is already older than the cache duration.
+    pub fn insert(&mut self, msg: T) -> bool {
+        let nsec = stamp_nsec(&msg);
+        if let Some(oldest_allowed) = self.oldest_allowed() {
+            if nsec < oldest_allowed {
+                return false;
+            }
+        }
+        let index = self.entries.partition_point(|(stamp, _)| *stamp <= nsec);
+        self.entries.insert(index, (nsec, msg));
+        if let Some(oldest_allowed) = self.oldest_allowed() {
+            self.prune_before_nsec(oldest_allowed);
+        }
+        true
+    }
+
+    /// Removes all messages that are stamped before the given time.
+    pub fn prune_before(&mut self, time: Time) {
+        self.prune_before_nsec(time.nsec);
+    }
+
+    /// Returns the stamp of the oldest message.
+    pub fn oldest_time(&self) -> Option<Time> {
+        self.entries.front().map(|(nsec, _)| ros_time(*nsec))
+    }
+
+    /// Returns the stamp of the latest message.
+    pub fn latest_time(&self) -> Option<Time> {
+        self.entries.back().map(|(nsec, _)| ros_time(*nsec))
+    }
+
+    /// Returns the latest message.
+    pub fn latest(&self) -> Option<&T> {
+        self.entries.back().map(|(_, msg)| msg)
+    }
+
+    /// Returns the message with exactly the given stamp.
+    pub fn get(&self, time: Time) -> Option<&T> {
+        let index = self
+            .entries
+            .partition_point(|(stamp, _)| *stamp < time.nsec);
+        match self.entries.get(index) {
+            Some((stamp, msg)) if *stamp == time.nsec => Some(msg),
+            _ => None,
+        }
+    }
+
+    /// Returns the message whose stamp is closest to the given time, preferring the earlier one.
+    pub fn closest(&self, time: Time) -> Option<&T> {
+        let index = self
+            .entries
+            .partition_point(|(stamp, _)| *stamp < time.nsec);
+        let later = self.entries.get(index);
+        let earlier = index
+            .checked_sub(1)
+            .and_then(|index| self.entries.get(index));
+        match (earlier, later) {
+            (Some((earlier_stamp, earlier)), Some((later_stamp, later))) => {
+                if time.nsec - earlier_stamp <= later_stamp - time.nsec {
+                    Some(earlier)
+                } else {
+                    Some(later)
+                }
+            }
+            (Some((_, msg)), None) | (None, Some((_, msg))) => Some(msg),
+            (None, None) => None,
+        }
+    }
+
+    /// Returns the messages directly before and after the given time, for interpolating between
+    /// them.
+    ///
+    /// If a message has exactly the given stamp, it is returned as both messages. Returns `None`
+    /// if the time is outside of the time span of the cache.
+    pub fn surrounding(&self, time: Time) -> Option<(&T, &T)> {
+        let index = self
+            .entries
+            .partition_point(|(stamp, _)| *stamp < time.nsec);
+        let (later_stamp, later) = self.entries.get(index)?;
+        if *later_stamp == time.nsec {
+            return Some((later, later));
+        }
+        let (_, earlier) = self.entries.get(index.checked_sub(1)?)?;
+        Some((earlier, later))
+    }
+
+    /// Returns the messages whose stamps are within the given time span, including both ends,
+    /// oldest first.
+    pub fn range(&self, from: Time, to: Time) -> impl Iterator<Item = &T> {
+        let start = self
+            .entries
+            .partition_point(|(stamp, _)| *stamp < from.nsec);
+        let end = self.entries.partition_point(|(stamp, _)| *stamp <= to.nsec);
+        self.entries
+            .range(start..end.max(start))
+            .map(|(_, msg)| msg)
+    }
+
+    /// Returns all messages, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.entries.iter().map(|(_, msg)| msg)
+    }
+
+    /// Returns the message at the given time, interpolated between the surrounding messages with
+    /// their [`Interpolate`] implementation.
+    ///
+    /// Returns `None` if the time is outside of the time span of the cache.
+    pub fn interpolate(&self, time: Time) -> Option<T>
+    where
+        T: Interpolate + Clone,
+    {
+        let index = self
+            .entries
+            .partition_point(|(stamp, _)| *stamp < time.nsec);
+        let (later_stamp, later) = self.entries.get(index)?;
+        if *later_stamp == time.nsec {
+            return Some(later.clone());
+        }
+        let (earlier_stamp, earlier) = self.entries.get(index.checked_sub(1)?)?;
+        let ratio = (time.nsec - earlier_stamp) as f64 / (later_stamp - earlier_stamp) as f64;
+        let mut msg = T::interpolate(earlier, later, ratio);
+        let (sec, nanosec) = time.to_sec_nanosec();
+        msg.set_stamp(sec, nanosec);
+        Some(msg)
+    }
+
+    // The oldest stamp that is kept, relative to the latest message.
+    fn oldest_allowed(&self) -> Option<i64> {
+        let (latest, _) = self.entries.back()?;
+        let cache_duration = i64::try_from(self.cache_duration.as_nanos()).unwrap_or(i64::MAX);
+        Some(latest.saturating_sub(cache_duration))
+    }
+
+    fn prune_before_nsec(&mut self, nsec: i64) {
+        let count = self.entries.partition_point(|(stamp, _)| *stamp < nsec);
+        self.entries.drain(..count);
+    }
+}
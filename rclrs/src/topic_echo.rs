@@ -0,0 +1,128 @@
+use crate::{
+    spin_once, DynamicMessage, DynamicSubscription, Node, QoSProfile, RclReturnCode, RclrsError,
+};
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Receives the messages on any topic as [`DynamicMessage`]s, like `ros2 topic echo`.
+///
+/// The message type of the topic can be discovered from the ROS graph, so this is suited for
+/// tools that inspect topics which are only known at runtime. The received messages are queued
+/// until they are taken with [`TopicEcho::try_recv`], or iterated over with [`TopicEcho::iter`].
+///
+/// This requires the `dyn_msg` feature.
+///
+/// # Example
+/// ```no_run
+/// # use rclrs::{Context, RclrsError, TopicEcho, QOS_PROFILE_DEFAULT};
+/// let context = Context::new(std::env::args())?;
+/// let mut node = context.create_node("echo")?;
+/// let echo = TopicEcho::new(&mut node, "/chatter", QOS_PROFILE_DEFAULT)?;
+/// for msg in echo.iter(&node) {
+///     print!("{}---\n", msg?);
+/// }
+/// # Ok::<(), RclrsError>(())
+/// ```
+pub struct TopicEcho {
+    subscription: Arc<DynamicSubscription>,
+    messages: Arc<Mutex<VecDeque<DynamicMessage>>>,
+}
+
+impl TopicEcho {
+    /// Subscribes to a topic, whose message type is looked up in the ROS graph.
+    ///
+    /// Topics are discovered asynchronously, so a topic that has just been created may not be
+    /// found yet. Returns an [`InvalidArgument`][1] error if the topic is not in the graph, or if
+    /// it is used with more than one message type, in which case
+    /// [`TopicEcho::new_with_type`] must be used.
+    ///
+    /// [1]: crate::RclReturnCode::InvalidArgument
+    pub fn new(node: &mut Node, topic: &str, qos: QoSProfile) -> Result<Self, RclrsError> {
+        let topic = node.expand_topic_name(topic)?;
+        let types = node
+            .get_topic_names_and_types()?
+            .remove(&topic)
+            .unwrap_or_default();
+        match &types[..] {
+            [type_name] => Self::new_with_type(node, &topic, type_name, qos),
+            [] => Err(RclrsError::with_message(
+                RclReturnCode::InvalidArgument,
+                format!("The topic '{topic}' does not exist in the ROS graph"),
+            )),
+            _ => Err(RclrsError::with_message(
+                RclReturnCode::InvalidArgument,
+                format!(
+                    "The topic '{topic}' has more than one type: {}",
+                    types.join(", ")
+                ),
+            )),
+        }
+    }
+
+    /// Subscribes to a topic with the given message type, e.g. `std_msgs/msg/String`.
+    pub fn new_with_type(
+        node: &mut Node,
+        topic: &str,
+        type_name: &str,
+        qos: QoSProfile,
+    ) -> Result<Self, RclrsError> {
+        let messages = Arc::new(Mutex::new(VecDeque::new()));
+        let callback_messages = messages.clone();
+        let subscription = node.create_dynamic_subscription(topic, type_name, qos, move |msg| {
+            callback_messages.lock().unwrap().push_back(msg);
+        })?;
+        Ok(Self {
+            subscription,
+            messages,
+        })
+    }
+
+    /// Returns the message type of the topic, in the form `package/msg/Type`.
+    pub fn type_name(&self) -> &str {
+        self.subscription.metadata().type_name()
+    }
+
+    /// Takes the oldest message that has been received, if any.
+    ///
+    /// Messages are only received while the node is spinning.
+    pub fn try_recv(&self) -> Option<DynamicMessage> {
+        self.messages.lock().unwrap().pop_front()
+    }
+
+    /// Returns an iterator that spins the node until the next message is received.
+    ///
+    /// The iterator ends when the context is shut down, and yields an error if spinning fails.
+    pub fn iter<'a>(&'a self, node: &'a Node) -> TopicEchoIter<'a> {
+        TopicEchoIter { echo: self, node }
+    }
+}
+
+/// Iterator returned by [`TopicEcho::iter`].
+pub struct TopicEchoIter<'a> {
+    echo: &'a TopicEcho,
+    node: &'a Node,
+}
+
+impl Iterator for TopicEchoIter<'_> {
+    type Item = Result<DynamicMessage, RclrsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(msg) = self.echo.try_recv() {
+                return Some(Ok(msg));
+            }
+            if !self.node.get_context().ok() {
+                return None;
+            }
+            match spin_once(self.node, None) {
+                Ok(())
+                | Err(RclrsError {
+                    code: RclReturnCode::Timeout,
+                    ..
+                }) => {}
+                Err(error) => return Some(Err(error)),
+            }
+        }
+    }
+}
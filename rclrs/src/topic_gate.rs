@@ -0,0 +1,212 @@
+use crate::rosidl_macros::{impl_message, impl_service};
+use crate::sync::Mutex;
+use crate::{
+    Node, Publisher, QoSProfile, RclrsError, Service, Subscription, QOS_PROFILE_SERVICES_DEFAULT,
+};
+
+use alloc::string::String;
+use alloc::sync::Arc;
+
+use rosidl_runtime_rs::{Message, String as RosString};
+
+// Corresponds to std_srvs__srv__Trigger_Request
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+#[allow(non_camel_case_types)]
+pub(crate) struct Trigger_Request {
+    pub(crate) structure_needs_at_least_one_member: u8,
+}
+
+impl_message!(
+    Trigger_Request,
+    "std_srvs/srv/Trigger_Request",
+    std_srvs__srv__Trigger_Request__init,
+    rosidl_typesupport_c__get_message_type_support_handle__std_srvs__srv__Trigger_Request
+);
+
+// Corresponds to std_srvs__srv__Trigger_Response
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+#[allow(non_camel_case_types)]
+pub(crate) struct Trigger_Response {
+    pub(crate) success: bool,
+    pub(crate) message: RosString,
+}
+
+impl_message!(
+    Trigger_Response,
+    "std_srvs/srv/Trigger_Response",
+    std_srvs__srv__Trigger_Response__init,
+    rosidl_typesupport_c__get_message_type_support_handle__std_srvs__srv__Trigger_Response
+);
+
+impl Trigger_Response {
+    pub(crate) fn from_success(success: bool, message: &str) -> Self {
+        Self {
+            success,
+            message: message.into(),
+        }
+    }
+}
+
+impl_service!(
+    Trigger,
+    Trigger_Request,
+    Trigger_Response,
+    rosidl_typesupport_c__get_service_type_support_handle__std_srvs__srv__Trigger
+);
+
+/// Options for a [`TopicGate`], in addition to its topics and QoS profile.
+///
+/// New options may be added in the future, so it is best to create this with the
+/// `..Default::default()` syntax.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TopicGateOptions {
+    /// The name of a `std_srvs/srv/Trigger` service that forwards the buffered message when it
+    /// is called, or `None` for no service.
+    ///
+    /// The service responds with `success: false` if there was no message to forward.
+    pub trigger_service: Option<String>,
+    /// Whether the buffered message is discarded once it has been forwarded, so that each
+    /// received message is forwarded at most once.
+    ///
+    /// By default, the message is kept, and forwarded again until a newer message arrives.
+    pub forward_once: bool,
+}
+
+// The state that is shared with the callbacks of the subscription and the trigger service.
+struct GateState<T: Message> {
+    latest: Mutex<Option<T>>,
+    publisher: Publisher<T>,
+    forward_once: bool,
+}
+
+impl<T: Message> GateState<T> {
+    fn forward(&self) -> Result<bool, RclrsError> {
+        let msg = if self.forward_once {
+            self.latest.lock().take()
+        } else {
+            self.latest.lock().clone()
+        };
+        match msg {
+            Some(msg) => self.publisher.publish(msg).map(|()| true),
+            None => Ok(false),
+        }
+    }
+}
+
+/// Buffers the latest message of a topic, and republishes it on another topic on demand.
+///
+/// This is a common pattern for gating command topics, e.g. for safety: Commands are only
+/// forwarded to the actuators when [`TopicGate::forward`] is called, e.g. by a supervisor after
+/// checking them, or when the trigger service from [`TopicGateOptions::trigger_service`] is
+/// called by another node.
+///
+/// Receiving messages and serving the trigger service requires spinning the node.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let gate = TopicGate::<geometry_msgs::msg::Twist>::new_with_options(
+///     &mut node,
+///     "cmd_vel_in",
+///     "cmd_vel",
+///     QOS_PROFILE_DEFAULT,
+///     TopicGateOptions {
+///         trigger_service: Some("~/release".into()),
+///         ..Default::default()
+///     },
+/// )?;
+/// if supervisor.is_safe(gate.latest().as_ref()) {
+///     gate.forward()?;
+/// }
+/// ```
+pub struct TopicGate<T: Message> {
+    state: Arc<GateState<T>>,
+    _subscription: Arc<Subscription<T>>,
+    _trigger_service: Option<Arc<Service<Trigger>>>,
+}
+
+impl<T: Message> TopicGate<T> {
+    /// Subscribes to the input topic and creates a publisher for the output topic, both with the
+    /// given QoS profile.
+    pub fn new(
+        node: &mut Node,
+        input_topic: &str,
+        output_topic: &str,
+        qos: QoSProfile,
+    ) -> Result<Self, RclrsError> {
+        Self::new_with_options(
+            node,
+            input_topic,
+            output_topic,
+            qos,
+            TopicGateOptions::default(),
+        )
+    }
+
+    /// Creates a gate with additional options.
+    ///
+    /// See [`TopicGate::new`] and [`TopicGateOptions`].
+    pub fn new_with_options(
+        node: &mut Node,
+        input_topic: &str,
+        output_topic: &str,
+        qos: QoSProfile,
+        options: TopicGateOptions,
+    ) -> Result<Self, RclrsError> {
+        let state = Arc::new(GateState {
+            latest: Mutex::new(None),
+            publisher: node.create_publisher(output_topic, qos)?,
+            forward_once: options.forward_once,
+        });
+        let subscription = {
+            let state = Arc::clone(&state);
+            node.create_subscription(input_topic, qos, move |msg: T| {
+                *state.latest.lock() = Some(msg);
+            })?
+        };
+        let trigger_service = match &options.trigger_service {
+            Some(service_name) => {
+                let state = Arc::clone(&state);
+                Some(node.create_service::<Trigger, _>(
+                    service_name,
+                    QOS_PROFILE_SERVICES_DEFAULT,
+                    move |_request| match state.forward() {
+                        Ok(success) => Trigger_Response::from_success(
+                            success,
+                            if success { "" } else { "No message to forward" },
+                        ),
+                        Err(error) => {
+                            Trigger_Response::from_success(false, &alloc::format!("{error}"))
+                        }
+                    },
+                )?)
+            }
+            None => None,
+        };
+        Ok(Self {
+            state,
+            _subscription: subscription,
+            _trigger_service: trigger_service,
+        })
+    }
+
+    /// Publishes the buffered message on the output topic.
+    ///
+    /// Returns `false` if there was no message to forward, i.e. none has been received yet, or it
+    /// has already been forwarded with [`TopicGateOptions::forward_once`].
+    pub fn forward(&self) -> Result<bool, RclrsError> {
+        self.state.forward()
+    }
+
+    /// Returns a copy of the buffered message, e.g. for checking it before forwarding it.
+    pub fn latest(&self) -> Option<T> {
+        self.state.latest.lock().clone()
+    }
+
+    /// Discards the buffered message, so that nothing is forwarded until a new message arrives.
+    pub fn clear(&self) {
+        *self.state.latest.lock() = None;
+    }
+}
@@ -0,0 +1,153 @@
+//! Tracepoints for [ros2_tracing][1].
+//!
+//! With the `tracetools` feature, these functions call the tracepoints of the `tracetools`
+//! library. They are the same tracepoints that `rclcpp` emits, so that analysis tools such as
+//! `tracetools_analysis` work on Rust nodes too. Without the feature, they do nothing.
+//!
+//! Tracepoints only record the pointers passed to them, they never dereference them. The pointers
+//! serve as IDs that link the events together, e.g. a subscription to its callback, so they must
+//! stay the same for the lifetime of the entity.
+//!
+//! [1]: https://github.com/ros2/ros2_tracing
+#![cfg_attr(not(feature = "tracetools"), allow(dead_code, unused_variables))]
+
+#[cfg(feature = "tracetools")]
+use crate::rcl_bindings::*;
+
+use core::ffi::{c_char, c_void};
+
+/// Links the `rcl` subscription handle to the `rclrs` subscription.
+pub(crate) fn subscription_init(subscription_handle: *const c_void, subscription: *const c_void) {
+    #[cfg(feature = "tracetools")]
+    // SAFETY: The pointers are not dereferenced.
+    unsafe {
+        ros_trace_rclcpp_subscription_init(subscription_handle, subscription)
+    };
+}
+
+/// Links the subscription to its callback.
+pub(crate) fn subscription_callback_added(subscription: *const c_void, callback: *const c_void) {
+    #[cfg(feature = "tracetools")]
+    // SAFETY: The pointers are not dereferenced.
+    unsafe {
+        ros_trace_rclcpp_subscription_callback_added(subscription, callback)
+    };
+}
+
+/// Links the `rcl` service handle to the callback of the service.
+///
+/// `rcl` itself emits the `rcl_service_init` tracepoint for the handle.
+pub(crate) fn service_callback_added(service_handle: *const c_void, callback: *const c_void) {
+    #[cfg(feature = "tracetools")]
+    // SAFETY: The pointers are not dereferenced.
+    unsafe {
+        ros_trace_rclcpp_service_callback_added(service_handle, callback)
+    };
+}
+
+/// Links the `rcl` timer handle to the callback of the timer.
+///
+/// `rcl` itself emits the `rcl_timer_init` tracepoint for the handle.
+pub(crate) fn timer_callback_added(timer_handle: *const c_void, callback: *const c_void) {
+    #[cfg(feature = "tracetools")]
+    // SAFETY: The pointers are not dereferenced.
+    unsafe {
+        ros_trace_rclcpp_timer_callback_added(timer_handle, callback)
+    };
+}
+
+/// Links the `rcl` timer handle to the `rcl` handle of its node.
+pub(crate) fn timer_link_node(timer_handle: *const c_void, node_handle: *const c_void) {
+    #[cfg(feature = "tracetools")]
+    // SAFETY: The pointers are not dereferenced.
+    unsafe {
+        ros_trace_rclcpp_timer_link_node(timer_handle, node_handle)
+    };
+}
+
+/// Records a human-readable name for the callback, usually its type name.
+pub(crate) fn callback_register(callback: *const c_void, symbol: *const c_char) {
+    #[cfg(feature = "tracetools")]
+    // SAFETY: The callback pointer is not dereferenced, and the symbol is a valid C string that is
+    // copied by the tracepoint.
+    unsafe {
+        ros_trace_rclcpp_callback_register(callback, symbol)
+    };
+}
+
+/// Marks the start of a callback execution.
+pub(crate) fn callback_start(callback: *const c_void) {
+    #[cfg(feature = "tracetools")]
+    // SAFETY: The pointer is not dereferenced.
+    unsafe {
+        ros_trace_callback_start(callback, false)
+    };
+}
+
+/// Marks the end of a callback execution.
+pub(crate) fn callback_end(callback: *const c_void) {
+    #[cfg(feature = "tracetools")]
+    // SAFETY: The pointer is not dereferenced.
+    unsafe {
+        ros_trace_callback_end(callback)
+    };
+}
+
+/// Marks that a message is about to be published.
+///
+/// `rcl` itself emits the `rcl_publish` tracepoint with the same message pointer afterwards.
+pub(crate) fn publish(publisher_handle: *const c_void, message: *const c_void) {
+    // This tracepoint was added after Foxy.
+    #[cfg(all(feature = "tracetools", not(ros_distro = "foxy")))]
+    // SAFETY: The pointers are not dereferenced.
+    unsafe {
+        ros_trace_rclcpp_publish(publisher_handle, message)
+    };
+}
+
+/// Marks that a message has been taken.
+pub(crate) fn take(message: *const c_void) {
+    // This tracepoint was added after Foxy.
+    #[cfg(all(feature = "tracetools", not(ros_distro = "foxy")))]
+    // SAFETY: The pointer is not dereferenced.
+    unsafe {
+        ros_trace_rclcpp_take(message)
+    };
+}
+
+/// Marks the start of waiting on a wait set, with the timeout in nanoseconds (or -1).
+pub(crate) fn executor_wait_for_work(timeout_ns: i64) {
+    // The executor tracepoints were added after Galactic.
+    #[cfg(all(
+        feature = "tracetools",
+        not(any(ros_distro = "foxy", ros_distro = "galactic"))
+    ))]
+    // SAFETY: No preconditions for this function.
+    unsafe {
+        ros_trace_rclcpp_executor_wait_for_work(timeout_ns)
+    };
+}
+
+/// Marks the end of waiting on a wait set, when the ready entities are collected.
+pub(crate) fn executor_get_next_ready() {
+    #[cfg(all(
+        feature = "tracetools",
+        not(any(ros_distro = "foxy", ros_distro = "galactic"))
+    ))]
+    // SAFETY: No preconditions for this function.
+    unsafe {
+        ros_trace_rclcpp_executor_get_next_ready()
+    };
+}
+
+/// Marks that the entity with the given `rcl` handle is about to be executed.
+pub(crate) fn executor_execute(handle: *const c_void) {
+    #[cfg(all(
+        feature = "tracetools",
+        not(any(ros_distro = "foxy", ros_distro = "galactic"))
+    ))]
+    // SAFETY: The pointer is not dereferenced.
+    unsafe {
+        ros_trace_rclcpp_executor_execute(handle)
+    };
+}
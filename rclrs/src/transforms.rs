@@ -0,0 +1,1039 @@
+use crate::logging::log;
+use crate::{
+    Clock, Interpolate, LogSeverity, Node, Publisher, QoSDurabilityPolicy, QoSHistoryPolicy,
+    QoSProfile, RclFutureExt, RclReturnCode, RclrsError, Stamped, Subscription, Time, TimeCache,
+    WithTimeout, QOS_PROFILE_DEFAULT,
+};
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use crate::sync::Mutex;
+use rosidl_runtime_rs::{Message, TfMessage, TransformMessage};
+
+// The maximum number of links between a frame and its root, to detect loops in the frame tree.
+const MAX_TREE_DEPTH: usize = 1000;
+
+/// The QoS profile of `/tf`, like in `tf2_ros`.
+const QOS_PROFILE_TF: QoSProfile = QoSProfile {
+    history: QoSHistoryPolicy::KeepLast { depth: 100 },
+    ..QOS_PROFILE_DEFAULT
+};
+
+/// The QoS profile of `/tf_static`, like in `tf2_ros`. Static transforms are published once, so
+/// they are kept for late-joining subscriptions.
+const QOS_PROFILE_TF_STATIC: QoSProfile = QoSProfile {
+    history: QoSHistoryPolicy::KeepLast { depth: 100 },
+    durability: QoSDurabilityPolicy::TransientLocal,
+    ..QOS_PROFILE_DEFAULT
+};
+
+/// The QoS profile of publishers on `/tf_static`, like in `tf2_ros`. Only the latest message is
+/// kept, since it contains all static transforms of the publisher.
+const QOS_PROFILE_TF_STATIC_PUBLISHER: QoSProfile = QoSProfile {
+    history: QoSHistoryPolicy::KeepLast { depth: 1 },
+    durability: QoSDurabilityPolicy::TransientLocal,
+    ..QOS_PROFILE_DEFAULT
+};
+
+/// A rigid transform, consisting of a translation and a rotation.
+///
+/// The transform of a child frame relative to its parent frame maps points in the child frame to
+/// the parent frame, like the transforms on `/tf`.
+///
+/// # Example
+/// ```
+/// # use rclrs::Transform;
+/// // A quarter turn around the z axis, then one unit along x.
+/// let half_sqrt2 = std::f64::consts::FRAC_1_SQRT_2;
+/// let transform = Transform {
+///     translation: [1.0, 0.0, 0.0],
+///     rotation: [0.0, 0.0, half_sqrt2, half_sqrt2],
+/// };
+/// let point = transform.apply([1.0, 0.0, 0.0]);
+/// assert!((point[0] - 1.0).abs() < 1e-9 && (point[1] - 1.0).abs() < 1e-9);
+/// let back = transform.inverse().apply(point);
+/// assert!((back[0] - 1.0).abs() < 1e-9 && back[1].abs() < 1e-9);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    /// The translation `[x, y, z]`.
+    pub translation: [f64; 3],
+    /// The rotation as a unit quaternion `[x, y, z, w]`.
+    pub rotation: [f64; 4],
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    /// The transform that changes nothing.
+    pub const IDENTITY: Self = Self {
+        translation: [0.0; 3],
+        rotation: [0.0, 0.0, 0.0, 1.0],
+    };
+
+    /// Reads the transform from a `geometry_msgs/TransformStamped` message.
+    pub fn from_message(msg: &impl TransformMessage) -> Self {
+        Self {
+            translation: msg.translation(),
+            rotation: msg.rotation(),
+        }
+    }
+
+    /// Creates a transform from a translation and a rotation in roll, pitch and yaw angles, in
+    /// radians.
+    ///
+    /// The rotation is around the fixed x, y and z axes, in this order, like `tf2`'s `setRPY`.
+    pub fn from_rpy(translation: [f64; 3], roll: f64, pitch: f64, yaw: f64) -> Self {
+        let (sr, cr) = (roll / 2.0).sin_cos();
+        let (sp, cp) = (pitch / 2.0).sin_cos();
+        let (sy, cy) = (yaw / 2.0).sin_cos();
+        Self {
+            translation,
+            rotation: [
+                sr * cp * cy - cr * sp * sy,
+                cr * sp * cy + sr * cp * sy,
+                cr * cp * sy - sr * sp * cy,
+                cr * cp * cy + sr * sp * sy,
+            ],
+        }
+    }
+
+    /// Returns the transform that applies `other` first, and then `self`.
+    pub fn compose(&self, other: &Self) -> Self {
+        let rotated = rotate(self.rotation, other.translation);
+        Self {
+            translation: [
+                self.translation[0] + rotated[0],
+                self.translation[1] + rotated[1],
+                self.translation[2] + rotated[2],
+            ],
+            rotation: multiply(self.rotation, other.rotation),
+        }
+    }
+
+    /// Returns the transform that reverses this one.
+    pub fn inverse(&self) -> Self {
+        let [x, y, z, w] = self.rotation;
+        let rotation = [-x, -y, -z, w];
+        let [tx, ty, tz] = rotate(rotation, self.translation);
+        Self {
+            translation: [-tx, -ty, -tz],
+            rotation,
+        }
+    }
+
+    /// Transforms a point.
+    pub fn apply(&self, point: [f64; 3]) -> [f64; 3] {
+        let rotated = rotate(self.rotation, point);
+        [
+            rotated[0] + self.translation[0],
+            rotated[1] + self.translation[1],
+            rotated[2] + self.translation[2],
+        ]
+    }
+}
+
+// The Hamilton product of two quaternions.
+fn multiply(a: [f64; 4], b: [f64; 4]) -> [f64; 4] {
+    let [ax, ay, az, aw] = a;
+    let [bx, by, bz, bw] = b;
+    [
+        aw * bx + ax * bw + ay * bz - az * by,
+        aw * by - ax * bz + ay * bw + az * bx,
+        aw * bz + ax * by - ay * bx + az * bw,
+        aw * bw - ax * bx - ay * by - az * bz,
+    ]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+// Rotates a vector by a unit quaternion.
+fn rotate(q: [f64; 4], v: [f64; 3]) -> [f64; 3] {
+    let axis = [q[0], q[1], q[2]];
+    let t = cross(axis, v).map(|c| 2.0 * c);
+    let u = cross(axis, t);
+    [
+        v[0] + q[3] * t[0] + u[0],
+        v[1] + q[3] * t[1] + u[1],
+        v[2] + q[3] * t[2] + u[2],
+    ]
+}
+
+// Spherical linear interpolation between two unit quaternions.
+fn slerp(a: [f64; 4], mut b: [f64; 4], ratio: f64) -> [f64; 4] {
+    let mut dot: f64 = a.iter().zip(&b).map(|(a, b)| a * b).sum();
+    // The quaternions q and -q are the same rotation, the shorter way is taken.
+    if dot < 0.0 {
+        b = b.map(|c| -c);
+        dot = -dot;
+    }
+    let (wa, wb) = if dot > 0.9995 {
+        // The rotations are almost the same, where linear interpolation is accurate enough.
+        (1.0 - ratio, ratio)
+    } else {
+        let theta = dot.acos();
+        let sin_theta = theta.sin();
+        (
+            ((1.0 - ratio) * theta).sin() / sin_theta,
+            (ratio * theta).sin() / sin_theta,
+        )
+    };
+    let q = [0, 1, 2, 3].map(|i| wa * a[i] + wb * b[i]);
+    let norm = q.iter().map(|c| c * c).sum::<f64>().sqrt();
+    q.map(|c| c / norm)
+}
+
+// A transform of a frame relative to its parent, as stored in the time cache of the frame.
+#[derive(Clone)]
+struct FrameTransform {
+    stamp: (i32, u32),
+    parent: String,
+    transform: Transform,
+}
+
+impl Stamped for FrameTransform {
+    fn stamp(&self) -> (i32, u32) {
+        self.stamp
+    }
+
+    fn set_stamp(&mut self, sec: i32, nanosec: u32) {
+        self.stamp = (sec, nanosec);
+    }
+
+    fn set_frame_id(&mut self, frame_id: &str) {
+        self.parent = frame_id.to_owned();
+    }
+}
+
+impl Interpolate for FrameTransform {
+    fn interpolate(earlier: &Self, later: &Self, ratio: f64) -> Self {
+        let translation = [0, 1, 2].map(|i| {
+            earlier.transform.translation[i]
+                + ratio * (later.transform.translation[i] - earlier.transform.translation[i])
+        });
+        Self {
+            stamp: earlier.stamp,
+            parent: earlier.parent.clone(),
+            transform: Transform {
+                translation,
+                rotation: slerp(earlier.transform.rotation, later.transform.rotation, ratio),
+            },
+        }
+    }
+}
+
+// One link of the frame tree, from a frame to its parent.
+struct Link {
+    parent: String,
+    transform: Transform,
+    // The latest stamp of the link, or `None` for a static link.
+    latest: Option<Time>,
+}
+
+#[derive(Default)]
+struct BufferState {
+    dynamic: BTreeMap<String, TimeCache<FrameTransform>>,
+    // The parent frame and the transform, by child frame.
+    static_: BTreeMap<String, (String, Transform)>,
+    // All frames that have been a parent or a child of a transform. Like in `tf2`, frames are not
+    // removed when their transforms expire.
+    frames: BTreeSet<String>,
+    // The tasks that wait for a transform, see `TransformBuffer::lookup_transform_async`.
+    wakers: Vec<Waker>,
+}
+
+impl BufferState {
+    fn frame_exists(&self, frame: &str) -> bool {
+        self.frames.contains(frame)
+    }
+
+    // Inserts a transform of the child frame relative to the parent frame, which is static if
+    // there is no stamp.
+    fn insert(
+        &mut self,
+        parent: &str,
+        child: &str,
+        transform: Transform,
+        stamp: Option<(i32, u32)>,
+        cache_duration: Duration,
+    ) {
+        for frame in [parent, child] {
+            if !self.frames.contains(frame) {
+                self.frames.insert(frame.to_owned());
+            }
+        }
+        match stamp {
+            None => {
+                self.static_
+                    .insert(child.to_owned(), (parent.to_owned(), transform));
+            }
+            Some(stamp) => {
+                self.dynamic
+                    .entry(child.to_owned())
+                    .or_insert_with(|| TimeCache::new(cache_duration))
+                    .insert(FrameTransform {
+                        stamp,
+                        parent: parent.to_owned(),
+                        transform,
+                    });
+            }
+        }
+    }
+
+    // Returns the link from the frame to its parent at the given time, or the latest link if the
+    // time is `None`. Returns `Ok(None)` for a root frame.
+    fn link(&self, frame: &str, time: Option<Time>) -> Result<Option<Link>, RclrsError> {
+        if let Some((parent, transform)) = self.static_.get(frame) {
+            return Ok(Some(Link {
+                parent: parent.clone(),
+                transform: *transform,
+                latest: None,
+            }));
+        }
+        let Some(cache) = self.dynamic.get(frame) else {
+            return Ok(None);
+        };
+        let entry = match time {
+            Some(time) => cache.interpolate(time).ok_or_else(|| {
+                RclrsError::with_message(
+                    RclReturnCode::Error,
+                    format!(
+                        "Lookup would require extrapolation for frame '{frame}', which has \
+                         transforms from {:?} to {:?} nanoseconds, but not at {} nanoseconds",
+                        cache.oldest_time().map(|time| time.nsec),
+                        cache.latest_time().map(|time| time.nsec),
+                        time.nsec
+                    ),
+                )
+            })?,
+            None => cache.latest().cloned().ok_or_else(|| {
+                RclrsError::with_message(
+                    RclReturnCode::Error,
+                    format!("Frame '{frame}' has no transforms"),
+                )
+            })?,
+        };
+        Ok(Some(Link {
+            parent: entry.parent,
+            transform: entry.transform,
+            latest: cache.latest_time(),
+        }))
+    }
+
+    // Returns the frames from the given frame up to its root, with the transforms of the frame
+    // relative to each of them.
+    fn chain(&self, frame: &str, time: Option<Time>) -> Result<Vec<(String, Link)>, RclrsError> {
+        let mut chain = Vec::new();
+        let mut current = frame.to_owned();
+        let mut accumulated = Transform::IDENTITY;
+        while let Some(link) = self.link(&current, time)? {
+            if chain.len() >= MAX_TREE_DEPTH {
+                return Err(RclrsError::with_message(
+                    RclReturnCode::Error,
+                    format!("The frame tree above '{frame}' contains a loop"),
+                ));
+            }
+            accumulated = link.transform.compose(&accumulated);
+            current = link.parent.clone();
+            chain.push((
+                current.clone(),
+                Link {
+                    transform: accumulated,
+                    ..link
+                },
+            ));
+        }
+        Ok(chain)
+    }
+
+    fn lookup(&self, target: &str, source: &str, time: Time) -> Result<Transform, RclrsError> {
+        // The frame IDs are stored without leading slashes, see `TransformBuffer::set_transform`.
+        let (target, source) = (
+            target.trim_start_matches('/'),
+            source.trim_start_matches('/'),
+        );
+        for frame in [target, source] {
+            if !self.frame_exists(frame) {
+                return Err(RclrsError::with_message(
+                    RclReturnCode::Error,
+                    format!("Frame '{frame}' does not exist"),
+                ));
+            }
+        }
+        if target == source {
+            return Ok(Transform::IDENTITY);
+        }
+        // A time of zero means the latest time at which all links of the lookup are available.
+        let time = if time.nsec == 0 {
+            let latest_links = self
+                .chain(target, None)?
+                .into_iter()
+                .chain(self.chain(source, None)?)
+                .filter_map(|(_, link)| link.latest)
+                .min_by_key(|time| time.nsec);
+            match latest_links {
+                Some(time) => time,
+                None => time,
+            }
+        } else {
+            time
+        };
+        let time = Some(time);
+        // The transforms of the frames relative to each of their ancestors, with the frames
+        // themselves as the first entries.
+        let identity = |frame: &str| {
+            (
+                frame.to_owned(),
+                Link {
+                    parent: frame.to_owned(),
+                    transform: Transform::IDENTITY,
+                    latest: None,
+                },
+            )
+        };
+        let mut target_chain = vec![identity(target)];
+        target_chain.extend(self.chain(target, time)?);
+        let mut source_chain = vec![identity(source)];
+        source_chain.extend(self.chain(source, time)?);
+        for (ancestor, source_link) in &source_chain {
+            if let Some((_, target_link)) = target_chain.iter().find(|(frame, _)| frame == ancestor)
+            {
+                return Ok(target_link
+                    .transform
+                    .inverse()
+                    .compose(&source_link.transform));
+            }
+        }
+        Err(RclrsError::with_message(
+            RclReturnCode::Error,
+            format!("Frames '{target}' and '{source}' are not connected"),
+        ))
+    }
+}
+
+/// A buffer of the transforms between coordinate frames over time, like the buffer of `tf2`.
+///
+/// The buffer is filled by a [`TransformListener`] from `/tf` and `/tf_static`, or manually with
+/// [`TransformBuffer::set_transform`]. Dynamic transforms are kept for the cache duration, relative
+/// to the latest transform of each frame, and are interpolated between their stamps. Static
+/// transforms are valid at all times.
+///
+/// The buffer can be shared between threads.
+pub struct TransformBuffer {
+    clock: Clock,
+    cache_duration: Duration,
+    state: Mutex<BufferState>,
+}
+
+impl TransformBuffer {
+    /// Creates an empty buffer that keeps dynamic transforms for the given duration.
+    ///
+    /// The clock is used for the timeouts of [`TransformBuffer::lookup_transform_async`], usually
+    /// the clock of the node, see [`Node::get_clock`].
+    pub fn new(clock: Clock, cache_duration: Duration) -> Self {
+        Self {
+            clock,
+            cache_duration,
+            state: Mutex::new(BufferState::default()),
+        }
+    }
+
+    /// Returns the duration that dynamic transforms are kept for.
+    pub fn cache_duration(&self) -> Duration {
+        self.cache_duration
+    }
+
+    /// Inserts a transform, and wakes up the tasks waiting for transforms.
+    ///
+    /// Dynamic transforms that are older than the cache duration are ignored. Returns an
+    /// [`InvalidArgument`][1] error if the frame IDs are empty, equal or not valid UTF-8.
+    ///
+    /// [1]: crate::RclReturnCode::InvalidArgument
+    pub fn set_transform(
+        &self,
+        msg: &impl TransformMessage,
+        is_static: bool,
+    ) -> Result<(), RclrsError> {
+        let (parent, child) = msg.frame_ids();
+        let (Ok(parent), Ok(child)) = (std::str::from_utf8(parent), std::str::from_utf8(child))
+        else {
+            return Err(RclrsError::with_message(
+                RclReturnCode::InvalidArgument,
+                "The frame IDs of the transform are not valid UTF-8",
+            ));
+        };
+        // Leading slashes are not part of frame IDs in ROS 2, but are still published by some
+        // ROS 1 bridges.
+        let (parent, child) = (
+            parent.trim_start_matches('/'),
+            child.trim_start_matches('/'),
+        );
+        if parent.is_empty() || child.is_empty() || parent == child {
+            return Err(RclrsError::with_message(
+                RclReturnCode::InvalidArgument,
+                format!("Invalid frame IDs '{parent}' and '{child}' of a transform"),
+            ));
+        }
+        let transform = Transform::from_message(msg);
+        let wakers = {
+            let state = &mut *self.state.lock();
+            let stamp = (!is_static).then(|| msg.stamp());
+            state.insert(parent, child, transform, stamp, self.cache_duration);
+            std::mem::take(&mut state.wakers)
+        };
+        for waker in wakers {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    /// Returns the transform from the source frame to the target frame at the given time, i.e.
+    /// the transform that maps points in the source frame to the target frame.
+    ///
+    /// A time of zero nanoseconds means the latest time at which all transforms between the
+    /// frames are available, like `tf2::TimePointZero`. Leading slashes of the frame IDs are
+    /// ignored, like in [`TransformBuffer::set_transform`].
+    ///
+    /// Returns an [`Error`][1] if a frame does not exist, if the frames are not connected, or if
+    /// the lookup would require extrapolating beyond the cached transforms.
+    ///
+    /// [1]: crate::RclReturnCode::Error
+    pub fn lookup_transform(
+        &self,
+        target: &str,
+        source: &str,
+        time: Time,
+    ) -> Result<Transform, RclrsError> {
+        self.state.lock().lookup(target, source, time)
+    }
+
+    /// Returns true if [`TransformBuffer::lookup_transform`] would succeed.
+    pub fn can_transform(&self, target: &str, source: &str, time: Time) -> bool {
+        self.lookup_transform(target, source, time).is_ok()
+    }
+
+    /// Returns a future for the transform from the source frame to the target frame at the given
+    /// time, which completes when the transform becomes available.
+    ///
+    /// This is the asynchronous counterpart of `tf2_ros::Buffer::waitForTransform`. Whenever a
+    /// transform is inserted, e.g. by a [`TransformListener`] when a message arrives on `/tf`,
+    /// the lookup is tried again, so the future does not need to be polled periodically. It can
+    /// be awaited on an async runtime, or with [`spin_until_future_complete`][1] on the node of
+    /// the listener.
+    ///
+    /// The future fails with a [`Timeout`][2] error, which contains the error of the last lookup,
+    /// if the transform is not available after `timeout` has elapsed on the clock of the buffer.
+    /// See [`TransformBuffer::lookup_transform`] for the meaning of the time.
+    ///
+    /// # Example
+    /// ```ignore
+    /// # use rclrs::*;
+    /// let buffer = Arc::new(TransformBuffer::new(node.get_clock(), Duration::from_secs(10)));
+    /// let _listener = TransformListener::<tf2_msgs::msg::TFMessage>::new(&mut node, &buffer)?;
+    /// let latest = Time::from_sec_nanosec(0, 0, ClockType::RosTime);
+    /// let mut future =
+    ///     buffer.lookup_transform_async("map", "base_link", latest, Duration::from_secs(1))?;
+    /// let transform = spin_until_future_complete(&node, &mut future, None)??;
+    /// ```
+    ///
+    /// [1]: crate::spin_until_future_complete
+    /// [2]: crate::RclReturnCode::Timeout
+    pub fn lookup_transform_async(
+        self: &Arc<Self>,
+        target: &str,
+        source: &str,
+        time: Time,
+        timeout: Duration,
+    ) -> Result<LookupTransform, RclrsError> {
+        let wait = WaitForTransform {
+            buffer: Arc::clone(self),
+            target: target.to_owned(),
+            source: source.to_owned(),
+            time,
+        };
+        Ok(LookupTransform {
+            future: wait.with_timeout(&self.clock, timeout)?,
+            buffer: Arc::clone(self),
+        })
+    }
+}
+
+// The part of `LookupTransform` that waits without a timeout.
+struct WaitForTransform {
+    buffer: Arc<TransformBuffer>,
+    target: String,
+    source: String,
+    time: Time,
+}
+
+impl Future for WaitForTransform {
+    type Output = Transform;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // The waker is registered under the same lock as the lookup, so that a transform that is
+        // inserted in between is not missed.
+        let state = &mut *self.buffer.state.lock();
+        match state.lookup(&self.target, &self.source, self.time) {
+            Ok(transform) => Poll::Ready(transform),
+            Err(_) => {
+                if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    state.wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// The future returned by [`TransformBuffer::lookup_transform_async`].
+pub struct LookupTransform {
+    future: WithTimeout<WaitForTransform>,
+    buffer: Arc<TransformBuffer>,
+}
+
+impl Future for LookupTransform {
+    type Output = Result<Transform, RclrsError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        match Pin::new(&mut this.future).poll(cx) {
+            Poll::Ready(Err(RclrsError {
+                code: RclReturnCode::Timeout,
+                ..
+            })) => {
+                let wait = &this.future.future;
+                let reason =
+                    match this
+                        .buffer
+                        .lookup_transform(&wait.target, &wait.source, wait.time)
+                    {
+                        Ok(transform) => return Poll::Ready(Ok(transform)),
+                        Err(RclrsError { msg: Some(msg), .. }) => msg.to_string(),
+                        Err(error) => error.to_string(),
+                    };
+                Poll::Ready(Err(RclrsError::with_message(
+                    RclReturnCode::Timeout,
+                    format!(
+                        "Timed out waiting for the transform from '{}' to '{}': {reason}",
+                        wait.source, wait.target
+                    ),
+                )))
+            }
+            poll => poll,
+        }
+    }
+}
+
+/// Fills a [`TransformBuffer`] with the transforms on `/tf` and `/tf_static`, like
+/// `tf2_ros::TransformListener`.
+///
+/// The message type is `tf2_msgs::msg::TFMessage`, which is a type parameter since `rclrs` does
+/// not depend on `tf2_msgs`. The transforms are inserted while the node is spinning.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let buffer = Arc::new(TransformBuffer::new(node.get_clock(), Duration::from_secs(10)));
+/// let _listener = TransformListener::<tf2_msgs::msg::TFMessage>::new(&mut node, &buffer)?;
+/// ```
+pub struct TransformListener<M>
+where
+    M: TfMessage + Message,
+{
+    buffer: Arc<TransformBuffer>,
+    _tf: Arc<Subscription<M>>,
+    _tf_static: Arc<Subscription<M>>,
+}
+
+impl<M> TransformListener<M>
+where
+    M: TfMessage + Message,
+{
+    /// Subscribes to `/tf` and `/tf_static` on the node.
+    pub fn new(node: &mut Node, buffer: &Arc<TransformBuffer>) -> Result<Self, RclrsError> {
+        let logger_name = node.logger_name();
+        let subscribe = |node: &mut Node, topic: &'static str, qos: QoSProfile, is_static: bool| {
+            let buffer = Arc::clone(buffer);
+            let logger_name = logger_name.clone();
+            node.create_subscription::<M, _>(topic, qos, move |msg: M| {
+                for transform in msg.transforms() {
+                    if let Err(error) = buffer.set_transform(transform, is_static) {
+                        log(
+                            &logger_name,
+                            LogSeverity::Warn,
+                            &format!("Ignoring a transform on {topic}: {error}"),
+                        );
+                    }
+                }
+            })
+        };
+        Ok(Self {
+            buffer: Arc::clone(buffer),
+            _tf: subscribe(node, "/tf", QOS_PROFILE_TF, false)?,
+            _tf_static: subscribe(node, "/tf_static", QOS_PROFILE_TF_STATIC, true)?,
+        })
+    }
+
+    /// Returns the buffer that the transforms are inserted into.
+    pub fn buffer(&self) -> &Arc<TransformBuffer> {
+        &self.buffer
+    }
+}
+
+/// A transform between two frames that does not change over time, e.g. the mounting position of
+/// a sensor, see [`StaticTransformPublisher`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct StaticTransform {
+    /// The parent frame, i.e. the `header.frame_id` of the message.
+    pub parent_frame: String,
+    /// The child frame, i.e. the `child_frame_id` of the message.
+    pub child_frame: String,
+    /// The transform of the child frame relative to the parent frame.
+    pub transform: Transform,
+}
+
+impl StaticTransform {
+    /// Parses the command line arguments of the `static_transform_publisher` tool of `tf2_ros`.
+    ///
+    /// The arguments are `--frame-id` and `--child-frame-id`, which are required, the translation
+    /// `--x`, `--y` and `--z`, and the rotation either as a quaternion `--qx`, `--qy`, `--qz` and
+    /// `--qw`, or in radians as `--roll`, `--pitch` and `--yaw`. Omitted values are zero, except
+    /// for `--qw`, which is one. The quaternion is normalized.
+    ///
+    /// The arguments must not contain the program name and the ROS arguments, see
+    /// [`Context::non_ros_arguments`][1]. Returns an [`InvalidArgument`][2] error for unknown or
+    /// missing arguments and for invalid values.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::StaticTransform;
+    /// let args = "--x 0.1 --yaw 1.57 --frame-id base_link --child-frame-id laser";
+    /// let transform = StaticTransform::from_args(args.split(' '))?;
+    /// assert_eq!(transform.child_frame, "laser");
+    /// assert_eq!(transform.transform.translation, [0.1, 0.0, 0.0]);
+    /// # Ok::<(), rclrs::RclrsError>(())
+    /// ```
+    ///
+    /// [1]: crate::Context::non_ros_arguments
+    /// [2]: crate::RclReturnCode::InvalidArgument
+    pub fn from_args<I, S>(args: I) -> Result<Self, RclrsError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let invalid = |msg: String| RclrsError::with_message(RclReturnCode::InvalidArgument, msg);
+        let mut parent_frame = None;
+        let mut child_frame = None;
+        let mut translation = [0.0; 3];
+        let mut quaternion = None;
+        let mut rpy = None;
+        let mut args = args.into_iter();
+        while let Some(name) = args.next() {
+            let name = name.as_ref();
+            let value = args
+                .next()
+                .ok_or_else(|| invalid(format!("Missing value for '{name}'")))?;
+            let value = value.as_ref();
+            let number = || {
+                value
+                    .parse::<f64>()
+                    .map_err(|_| invalid(format!("Invalid value '{value}' for '{name}'")))
+            };
+            match name {
+                "--frame-id" => parent_frame = Some(value.to_owned()),
+                "--child-frame-id" => child_frame = Some(value.to_owned()),
+                "--x" => translation[0] = number()?,
+                "--y" => translation[1] = number()?,
+                "--z" => translation[2] = number()?,
+                "--qx" | "--qy" | "--qz" | "--qw" => {
+                    let index = ["--qx", "--qy", "--qz", "--qw"]
+                        .iter()
+                        .position(|q| *q == name)
+                        .unwrap();
+                    quaternion.get_or_insert([0.0, 0.0, 0.0, 1.0])[index] = number()?;
+                }
+                "--roll" => rpy.get_or_insert([0.0; 3])[0] = number()?,
+                "--pitch" => rpy.get_or_insert([0.0; 3])[1] = number()?,
+                "--yaw" => rpy.get_or_insert([0.0; 3])[2] = number()?,
+                _ => return Err(invalid(format!("Unknown argument '{name}'"))),
+            }
+        }
+        let transform = match (quaternion, rpy) {
+            (Some(_), Some(_)) => {
+                return Err(invalid(
+                    "The rotation can either be given as a quaternion or as roll, pitch and yaw"
+                        .to_owned(),
+                ))
+            }
+            (Some(q), None) => {
+                let norm = q.iter().map(|c| c * c).sum::<f64>().sqrt();
+                if !norm.is_normal() {
+                    return Err(invalid(format!("Invalid quaternion {q:?}")));
+                }
+                Transform {
+                    translation,
+                    rotation: q.map(|c| c / norm),
+                }
+            }
+            (None, Some([roll, pitch, yaw])) => Transform::from_rpy(translation, roll, pitch, yaw),
+            (None, None) => Transform {
+                translation,
+                ..Transform::IDENTITY
+            },
+        };
+        Ok(Self {
+            parent_frame: parent_frame
+                .ok_or_else(|| invalid("Missing argument '--frame-id'".to_owned()))?,
+            child_frame: child_frame
+                .ok_or_else(|| invalid("Missing argument '--child-frame-id'".to_owned()))?,
+            transform,
+        })
+    }
+}
+
+/// Publishes static transforms on `/tf_static`, like `tf2_ros::StaticTransformBroadcaster`.
+///
+/// The transforms are published with a transient local durability, so that they are delivered
+/// to subscriptions that are created later, e.g. to a [`TransformListener`], as long as the
+/// publisher exists. Each publication contains all transforms that have been sent through the
+/// publisher, since only the latest message is kept for late subscriptions.
+///
+/// The message type is `tf2_msgs::msg::TFMessage`, which is a type parameter since `rclrs` does
+/// not depend on `tf2_msgs`.
+///
+/// # Example
+/// ```ignore
+/// # use rclrs::*;
+/// let publisher = StaticTransformPublisher::<tf2_msgs::msg::TFMessage>::new(&mut node)?;
+/// publisher.send(&[StaticTransform {
+///     parent_frame: "base_link".into(),
+///     child_frame: "laser".into(),
+///     transform: Transform::from_rpy([0.1, 0.0, 0.2], 0.0, 0.0, 3.14),
+/// }])?;
+/// ```
+pub struct StaticTransformPublisher<M>
+where
+    M: TfMessage + Message,
+{
+    publisher: Publisher<M>,
+    clock: Clock,
+    // The messages of all transforms that have been sent, by child frame.
+    transforms: Mutex<BTreeMap<String, M::Transform>>,
+}
+
+impl<M> StaticTransformPublisher<M>
+where
+    M: TfMessage + Message,
+{
+    /// Creates a publisher on `/tf_static` on the node.
+    pub fn new(node: &mut Node) -> Result<Self, RclrsError> {
+        Ok(Self {
+            publisher: node.create_publisher("/tf_static", QOS_PROFILE_TF_STATIC_PUBLISHER)?,
+            clock: node.get_clock(),
+            transforms: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    /// Publishes the given transforms, together with the ones that have been sent before.
+    ///
+    /// A transform replaces the earlier one with the same child frame. The transforms are stamped
+    /// with the current time of the node's clock.
+    pub fn send(&self, transforms: &[StaticTransform]) -> Result<(), RclrsError> {
+        let (sec, nanosec) = self.clock.now()?.to_sec_nanosec();
+        let mut msg = M::default();
+        {
+            let all_transforms = &mut *self.transforms.lock();
+            for transform in transforms {
+                let mut transform_msg = M::Transform::default();
+                transform_msg.set_stamp(sec, nanosec);
+                transform_msg.set_frame_id(&transform.parent_frame);
+                transform_msg.set_child_frame_id(&transform.child_frame);
+                transform_msg.set_transform(
+                    transform.transform.translation,
+                    transform.transform.rotation,
+                );
+                all_transforms.insert(transform.child_frame.clone(), transform_msg);
+            }
+            msg.set_transforms(all_transforms.values().cloned().collect());
+        }
+        self.publisher.publish(msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ClockType;
+
+    const CACHE_DURATION: Duration = Duration::from_secs(10);
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert!(
+            actual
+                .iter()
+                .zip(expected)
+                .all(|(a, e)| (a - e).abs() < 1e-9),
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    fn at_sec(sec: i32) -> Time {
+        Time::from_sec_nanosec(sec, 0, ClockType::RosTime)
+    }
+
+    fn translation(x: f64, y: f64, z: f64) -> Transform {
+        Transform {
+            translation: [x, y, z],
+            ..Transform::IDENTITY
+        }
+    }
+
+    #[test]
+    fn test_compose_applies_the_other_transform_first() {
+        let turn = Transform::from_rpy([0.0; 3], 0.0, 0.0, std::f64::consts::FRAC_PI_2);
+        let shift = translation(1.0, 0.0, 0.0);
+        assert_close(&turn.compose(&shift).apply([0.0; 3]), &[0.0, 1.0, 0.0]);
+        assert_close(&shift.compose(&turn).apply([0.0; 3]), &[1.0, 0.0, 0.0]);
+        let point = [0.3, -2.0, 5.0];
+        assert_close(
+            &turn.compose(&shift).apply(point),
+            &turn.apply(shift.apply(point)),
+        );
+    }
+
+    #[test]
+    fn test_inverse_reverses_the_transform() {
+        let transform = Transform::from_rpy([1.0, -2.0, 0.5], 0.3, -1.2, 2.5);
+        for composed in [
+            transform.compose(&transform.inverse()),
+            transform.inverse().compose(&transform),
+        ] {
+            assert_close(&composed.translation, &[0.0; 3]);
+            assert_close(&composed.apply([1.0, 2.0, 3.0]), &[1.0, 2.0, 3.0]);
+        }
+        assert_eq!(Transform::IDENTITY.inverse(), Transform::IDENTITY);
+    }
+
+    #[test]
+    fn test_slerp() {
+        let identity = Transform::IDENTITY.rotation;
+        let half_turn = [0.0, 0.0, 1.0, 0.0];
+        assert_close(&slerp(identity, half_turn, 0.0), &identity);
+        assert_close(&slerp(identity, half_turn, 1.0), &half_turn);
+        let quarter_turn = Transform::from_rpy([0.0; 3], 0.0, 0.0, std::f64::consts::FRAC_PI_2);
+        assert_close(&slerp(identity, half_turn, 0.5), &quarter_turn.rotation);
+        // The negated quaternion is the same rotation, so the shorter way is taken.
+        let negated = quarter_turn.rotation.map(|c| -c);
+        let eighth_turn = Transform::from_rpy([0.0; 3], 0.0, 0.0, std::f64::consts::FRAC_PI_4);
+        assert_close(&slerp(identity, negated, 0.5), &eighth_turn.rotation);
+        // Almost equal rotations are interpolated linearly, but still normalized.
+        let nearly_identity = Transform::from_rpy([0.0; 3], 0.0, 0.0, 1e-6).rotation;
+        let q = slerp(identity, nearly_identity, 0.5);
+        assert!((q.iter().map(|c| c * c).sum::<f64>() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_frame_exists() {
+        let mut state = BufferState::default();
+        assert!(!state.frame_exists("map"));
+        state.insert("map", "odom", Transform::IDENTITY, None, CACHE_DURATION);
+        state.insert(
+            "odom",
+            "base_link",
+            Transform::IDENTITY,
+            Some((1, 0)),
+            CACHE_DURATION,
+        );
+        for frame in ["map", "odom", "base_link"] {
+            assert!(state.frame_exists(frame));
+        }
+        assert!(!state.frame_exists("camera"));
+    }
+
+    #[test]
+    fn test_lookup_through_common_parent() {
+        let mut state = BufferState::default();
+        state.insert(
+            "base_link",
+            "camera",
+            translation(0.0, 1.0, 0.0),
+            None,
+            CACHE_DURATION,
+        );
+        state.insert(
+            "base_link",
+            "lidar",
+            translation(2.0, 0.0, 0.0),
+            None,
+            CACHE_DURATION,
+        );
+        let camera_to_lidar = state.lookup("lidar", "camera", at_sec(1)).unwrap();
+        assert_close(&camera_to_lidar.apply([0.0; 3]), &[-2.0, 1.0, 0.0]);
+        let lidar_to_camera = state.lookup("camera", "lidar", at_sec(1)).unwrap();
+        assert_close(&lidar_to_camera.apply([0.0; 3]), &[2.0, -1.0, 0.0]);
+        assert_eq!(
+            state.lookup("/camera", "/lidar", at_sec(1)).unwrap(),
+            lidar_to_camera
+        );
+        assert_eq!(
+            state.lookup("camera", "camera", at_sec(1)).unwrap(),
+            Transform::IDENTITY
+        );
+    }
+
+    #[test]
+    fn test_lookup_interpolates_dynamic_transforms() {
+        let mut state = BufferState::default();
+        state.insert(
+            "map",
+            "odom",
+            translation(1.0, 0.0, 0.0),
+            None,
+            CACHE_DURATION,
+        );
+        for (sec, x) in [(1, 0.0), (3, 4.0)] {
+            let transform = translation(x, 0.0, 0.0);
+            state.insert(
+                "odom",
+                "base_link",
+                transform,
+                Some((sec, 0)),
+                CACHE_DURATION,
+            );
+        }
+        let transform = state.lookup("map", "base_link", at_sec(2)).unwrap();
+        assert_close(&transform.translation, &[3.0, 0.0, 0.0]);
+        // A time of zero is the latest time at which all links are available.
+        let transform = state.lookup("map", "base_link", at_sec(0)).unwrap();
+        assert_close(&transform.translation, &[5.0, 0.0, 0.0]);
+        assert!(state.lookup("map", "base_link", at_sec(4)).is_err());
+    }
+
+    #[test]
+    fn test_lookup_errors() {
+        let mut state = BufferState::default();
+        state.insert("map", "odom", Transform::IDENTITY, None, CACHE_DURATION);
+        state.insert("world", "robot", Transform::IDENTITY, None, CACHE_DURATION);
+        let error = state.lookup("map", "camera", at_sec(1)).unwrap_err();
+        assert_eq!(error.code, RclReturnCode::Error);
+        assert!(state.lookup("odom", "robot", at_sec(1)).is_err());
+        // A loop in the frame tree is an error instead of an endless lookup.
+        state.insert("odom", "map", Transform::IDENTITY, None, CACHE_DURATION);
+        assert!(state.lookup("map", "robot", at_sec(1)).is_err());
+    }
+}
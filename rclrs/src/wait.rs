@@ -15,31 +15,140 @@
 // DISTRIBUTION A. Approved for public release; distribution unlimited.
 // OPSEC #4584.
 
+use crate::allocator::copy_rcutils_allocator;
+#[cfg(feature = "std")]
+use crate::current::CurrentGuard;
 use crate::error::{to_rcl_result, RclReturnCode, RclrsError, ToResult};
 use crate::rcl_bindings::*;
-use crate::{Context, SubscriptionBase};
+use crate::tracetools;
+use crate::{
+    CallbackHooks, CallbackId, CallbackKind, ClientBase, Context, ContextHandle, GuardCondition,
+    Node, QoSEventBase, ServiceBase, SubscriptionBase, TimerBase,
+};
 
-use std::sync::Arc;
-use std::time::Duration;
-use std::vec::Vec;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::time::Duration;
 
-use parking_lot::Mutex;
+/// How many entities of each kind can be added to a [`WaitSet`].
+///
+/// New kinds of entities may be added in the future, so it is best to create this with the
+/// `..Default::default()` syntax.
+///
+/// # Example
+/// ```
+/// # use rclrs::{Context, RclrsError, WaitSet, WaitSetCapacities};
+/// let context = Context::new([])?;
+/// let capacities = WaitSetCapacities {
+///     subscriptions: 2,
+///     guard_conditions: 1,
+///     ..Default::default()
+/// };
+/// let wait_set = WaitSet::new(capacities, &context)?;
+/// # Ok::<(), RclrsError>(())
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct WaitSetCapacities {
+    /// The number of subscriptions.
+    pub subscriptions: usize,
+    /// The number of guard conditions.
+    pub guard_conditions: usize,
+    /// The number of timers.
+    pub timers: usize,
+    /// The number of clients.
+    pub clients: usize,
+    /// The number of services.
+    pub services: usize,
+    /// The number of QoS event handlers.
+    pub qos_events: usize,
+}
+
+impl WaitSetCapacities {
+    // The capacities for all entities of the node, without guard conditions.
+    pub(crate) fn for_node(node: &Node) -> Self {
+        Self {
+            subscriptions: node.subscriptions.len(),
+            guard_conditions: 0,
+            timers: node.timers.len(),
+            clients: node.clients.len(),
+            services: node.services.len(),
+            qos_events: node.qos_events.len(),
+        }
+    }
+}
 
 /// A struct for waiting on subscriptions and other waitable entities to become ready.
 pub struct WaitSet {
     handle: rcl_wait_set_t,
     // Used to ensure the context is alive while the wait set is alive.
-    _context_handle: Arc<Mutex<rcl_context_t>>,
+    _context_handle: Arc<ContextHandle>,
     // The subscriptions that are currently registered in the wait set.
     // This correspondence is an invariant that must be maintained by all functions,
     // even in the error case.
     subscriptions: Vec<Arc<dyn SubscriptionBase>>,
+    // The same for guard conditions.
+    guard_conditions: Vec<Arc<GuardCondition>>,
+    // The same for timers.
+    timers: Vec<Arc<dyn TimerBase>>,
+    // The same for clients.
+    clients: Vec<Arc<dyn ClientBase>>,
+    // The same for services.
+    services: Vec<Arc<dyn ServiceBase>>,
+    // The same for QoS events.
+    qos_events: Vec<Arc<dyn QoSEventBase>>,
+}
+
+/// A wait set together with the list for its ready entities, reused across calls to
+/// [`ReusableWaitSet::spin_once`].
+///
+/// Creating a wait set allocates in `rcl` and `rmw`, so a spin loop should not do it for every
+/// wakeup.
+pub(crate) struct ReusableWaitSet {
+    wait_set: WaitSet,
+    ready_entities: ReadyEntities,
+    // Added in every iteration, in addition to the entities of the node.
+    guard_conditions: Vec<Arc<GuardCondition>>,
 }
 
 /// A list of entities that are ready, returned by [`WaitSet::wait`].
 pub struct ReadyEntities {
     /// A list of subscriptions that have potentially received messages.
     pub subscriptions: Vec<Arc<dyn SubscriptionBase>>,
+    /// A list of guard conditions that have been triggered.
+    pub guard_conditions: Vec<Arc<GuardCondition>>,
+    /// A list of timers that are ready.
+    pub timers: Vec<Arc<dyn TimerBase>>,
+    /// A list of clients that have potentially received responses.
+    pub clients: Vec<Arc<dyn ClientBase>>,
+    /// A list of services that have potentially received requests.
+    pub services: Vec<Arc<dyn ServiceBase>>,
+    /// A list of QoS events that have potentially occurred.
+    pub qos_events: Vec<Arc<dyn QoSEventBase>>,
+}
+
+/// How the ready entities of a node are executed after a wakeup, see
+/// [`NodeOptions::dispatch_policy`][1].
+///
+/// With both policies, entities with a higher [priority][2] are executed first. Entities with the
+/// same priority are executed in the order subscriptions, timers, clients, services and QoS
+/// events. Clients and QoS events always have the default priority 0.
+///
+/// [1]: crate::NodeOptions::dispatch_policy
+/// [2]: crate::Subscription::set_priority
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DispatchPolicy {
+    /// Execute every ready entity once per wakeup.
+    ///
+    /// Each subscription takes at most one message per wakeup, so a subscription that receives
+    /// a flood of messages can not starve the others.
+    #[default]
+    RoundRobin,
+    /// Execute only the ready entities with the highest priority.
+    ///
+    /// The other entities stay ready, and are executed in a later wakeup once no entity with a
+    /// higher priority is ready. Their messages may be dropped meanwhile, depending on their QoS
+    /// history.
+    StrictPriority,
 }
 
 impl Drop for rcl_wait_set_t {
@@ -55,9 +164,9 @@ impl Drop for rcl_wait_set_t {
 impl WaitSet {
     /// Creates a new wait set.
     ///
-    /// The given number of subscriptions is a capacity, corresponding to how often
-    /// [`WaitSet::add_subscription`] may be called.
-    pub fn new(number_of_subscriptions: usize, context: &Context) -> Result<Self, RclrsError> {
+    /// The capacities correspond to how often the `add_*` functions, such as
+    /// [`WaitSet::add_subscription`], may be called for each kind of entity.
+    pub fn new(capacities: WaitSetCapacities, context: &Context) -> Result<Self, RclrsError> {
         let rcl_wait_set = unsafe {
             // SAFETY: Getting a zero-initialized value is always safe
             let mut rcl_wait_set = rcl_get_zero_initialized_wait_set();
@@ -65,14 +174,14 @@ impl WaitSet {
             // There are no other preconditions.
             rcl_wait_set_init(
                 &mut rcl_wait_set,
-                number_of_subscriptions,
-                0,
-                0,
-                0,
-                0,
-                0,
+                capacities.subscriptions,
+                capacities.guard_conditions,
+                capacities.timers,
+                capacities.clients,
+                capacities.services,
+                capacities.qos_events,
                 &mut *context.handle.lock(),
-                rcutils_get_default_allocator(),
+                copy_rcutils_allocator(&context.allocator),
             )
             .ok()?;
             rcl_wait_set
@@ -80,7 +189,12 @@ impl WaitSet {
         Ok(Self {
             handle: rcl_wait_set,
             _context_handle: context.handle.clone(),
-            subscriptions: Vec::new(),
+            subscriptions: Vec::with_capacity(capacities.subscriptions),
+            guard_conditions: Vec::with_capacity(capacities.guard_conditions),
+            timers: Vec::with_capacity(capacities.timers),
+            clients: Vec::with_capacity(capacities.clients),
+            services: Vec::with_capacity(capacities.services),
+            qos_events: Vec::with_capacity(capacities.qos_events),
         })
     }
 
@@ -90,6 +204,11 @@ impl WaitSet {
     /// [`WaitSet::new`].
     pub fn clear(&mut self) {
         self.subscriptions.clear();
+        self.guard_conditions.clear();
+        self.timers.clear();
+        self.clients.clear();
+        self.services.clear();
+        self.qos_events.clear();
         // This cannot fail – the rcl_wait_set_clear function only checks that the input handle is
         // valid, which it always is in our case. Hence, only debug_assert instead of returning
         // Result.
@@ -118,7 +237,7 @@ impl WaitSet {
             rcl_wait_set_add_subscription(
                 &mut self.handle,
                 &*subscription.handle().lock(),
-                std::ptr::null_mut(),
+                core::ptr::null_mut(),
             )
         }
         .ok()?;
@@ -126,6 +245,123 @@ impl WaitSet {
         Ok(())
     }
 
+    /// Adds a guard condition to the wait set.
+    ///
+    /// Unlike other entities, the same guard condition may be added to multiple wait sets.
+    ///
+    /// This will return an error if the number of guard conditions in the wait set is larger than
+    /// the capacity set in [`WaitSet::new`].
+    pub fn add_guard_condition(
+        &mut self,
+        guard_condition: Arc<GuardCondition>,
+    ) -> Result<(), RclrsError> {
+        unsafe {
+            // SAFETY: The guard condition pointer will remain valid for as long as the wait set
+            // exists, because it's stored in self.guard_conditions.
+            // Passing in a null pointer for the third argument is explicitly allowed.
+            rcl_wait_set_add_guard_condition(
+                &mut self.handle,
+                &*guard_condition.lock(),
+                core::ptr::null_mut(),
+            )
+        }
+        .ok()?;
+        self.guard_conditions.push(guard_condition);
+        Ok(())
+    }
+
+    /// Adds a timer to the wait set.
+    ///
+    /// This will return an error if the number of timers in the wait set is larger than the
+    /// capacity set in [`WaitSet::new`].
+    ///
+    /// The same timer must not be added to multiple wait sets, see
+    /// [`WaitSet::add_subscription`].
+    pub fn add_timer(&mut self, timer: Arc<dyn TimerBase>) -> Result<(), RclrsError> {
+        unsafe {
+            // SAFETY: The timer pointer will remain valid for as long as the wait set exists,
+            // because it's stored in self.timers.
+            // Passing in a null pointer for the third argument is explicitly allowed.
+            rcl_wait_set_add_timer(
+                &mut self.handle,
+                &*timer.handle().lock(),
+                core::ptr::null_mut(),
+            )
+        }
+        .ok()?;
+        self.timers.push(timer);
+        Ok(())
+    }
+
+    /// Adds a client to the wait set.
+    ///
+    /// This will return an error if the number of clients in the wait set is larger than the
+    /// capacity set in [`WaitSet::new`].
+    ///
+    /// The same client must not be added to multiple wait sets, see
+    /// [`WaitSet::add_subscription`].
+    pub fn add_client(&mut self, client: Arc<dyn ClientBase>) -> Result<(), RclrsError> {
+        unsafe {
+            // SAFETY: The client pointer will remain valid for as long as the wait set exists,
+            // because it's stored in self.clients.
+            // Passing in a null pointer for the third argument is explicitly allowed.
+            rcl_wait_set_add_client(
+                &mut self.handle,
+                &*client.handle().lock(),
+                core::ptr::null_mut(),
+            )
+        }
+        .ok()?;
+        self.clients.push(client);
+        Ok(())
+    }
+
+    /// Adds a service to the wait set.
+    ///
+    /// This will return an error if the number of services in the wait set is larger than the
+    /// capacity set in [`WaitSet::new`].
+    ///
+    /// The same service must not be added to multiple wait sets, see
+    /// [`WaitSet::add_subscription`].
+    pub fn add_service(&mut self, service: Arc<dyn ServiceBase>) -> Result<(), RclrsError> {
+        unsafe {
+            // SAFETY: The service pointer will remain valid for as long as the wait set exists,
+            // because it's stored in self.services.
+            // Passing in a null pointer for the third argument is explicitly allowed.
+            rcl_wait_set_add_service(
+                &mut self.handle,
+                &*service.handle().lock(),
+                core::ptr::null_mut(),
+            )
+        }
+        .ok()?;
+        self.services.push(service);
+        Ok(())
+    }
+
+    /// Adds a QoS event handler to the wait set.
+    ///
+    /// This will return an error if the number of QoS events in the wait set is larger than the
+    /// capacity set in [`WaitSet::new`].
+    ///
+    /// The same event must not be added to multiple wait sets, see
+    /// [`WaitSet::add_subscription`].
+    pub fn add_qos_event(&mut self, qos_event: Arc<dyn QoSEventBase>) -> Result<(), RclrsError> {
+        unsafe {
+            // SAFETY: The event pointer will remain valid for as long as the wait set exists,
+            // because it's stored in self.qos_events.
+            // Passing in a null pointer for the third argument is explicitly allowed.
+            rcl_wait_set_add_event(
+                &mut self.handle,
+                &*qos_event.handle().lock(),
+                core::ptr::null_mut(),
+            )
+        }
+        .ok()?;
+        self.qos_events.push(qos_event);
+        Ok(())
+    }
+
     /// Blocks until the wait set is ready, or until the timeout has been exceeded.
     ///
     /// If the timeout is `None` then this function will block indefinitely until
@@ -150,6 +386,21 @@ impl WaitSet {
     ///
     /// [1]: std::time::Duration::ZERO
     pub fn wait(&mut self, timeout: Option<Duration>) -> Result<ReadyEntities, RclrsError> {
+        let mut ready_entities = ReadyEntities::new();
+        self.wait_into(timeout, &mut ready_entities)?;
+        Ok(ready_entities)
+    }
+
+    /// Like [`WaitSet::wait`], but writes the ready entities into an existing list.
+    ///
+    /// The list is cleared first. This does not allocate as long as the list has enough capacity
+    /// for all entities in the wait set.
+    pub(crate) fn wait_into(
+        &mut self,
+        timeout: Option<Duration>,
+        ready_entities: &mut ReadyEntities,
+    ) -> Result<(), RclrsError> {
+        ready_entities.clear();
         let timeout_ns = match timeout.map(|d| d.as_nanos()) {
             None => -1,
             Some(ns) if ns <= i64::MAX as u128 => ns as i64,
@@ -160,15 +411,29 @@ impl WaitSet {
                 })
             }
         };
+        tracetools::executor_wait_for_work(timeout_ns);
         // SAFETY: The comments in rcl mention "This function cannot operate on the same wait set
         // in multiple threads, and the wait sets may not share content."
         // We cannot currently guarantee that the wait sets may not share content, but it is
         // mentioned in the doc comment for `add_subscription`.
         // Also, the handle is obviously valid.
         unsafe { rcl_wait(&mut self.handle, timeout_ns) }.ok()?;
-        let mut ready_entities = ReadyEntities {
-            subscriptions: Vec::new(),
-        };
+        tracetools::executor_get_next_ready();
+        self.collect_ready_into(ready_entities);
+        Ok(())
+    }
+
+    /// Returns the `rcl` handle, for waiting on it without borrowing the wait set.
+    ///
+    /// The entities of the wait set must not be changed while `rcl_wait()` runs on the handle.
+    #[cfg(feature = "std")]
+    pub(crate) fn rcl_handle(&mut self) -> *mut rcl_wait_set_t {
+        &mut self.handle
+    }
+
+    /// Writes the entities that are ready after `rcl_wait()` into the list, which is cleared first.
+    pub(crate) fn collect_ready_into(&self, ready_entities: &mut ReadyEntities) {
+        ready_entities.clear();
         for (i, subscription) in self.subscriptions.iter().enumerate() {
             // SAFETY: The `subscriptions` entry is an array of pointers, and this dereferencing is
             // equivalent to
@@ -178,6 +443,340 @@ impl WaitSet {
                 ready_entities.subscriptions.push(subscription.clone());
             }
         }
-        Ok(ready_entities)
+        for (i, guard_condition) in self.guard_conditions.iter().enumerate() {
+            // SAFETY: The `guard_conditions` entry is an array of pointers, like `subscriptions`
+            // above.
+            let wait_set_entry = unsafe { *self.handle.guard_conditions.add(i) };
+            if !wait_set_entry.is_null() {
+                ready_entities
+                    .guard_conditions
+                    .push(guard_condition.clone());
+            }
+        }
+        for (i, timer) in self.timers.iter().enumerate() {
+            // SAFETY: The `timers` entry is an array of pointers, like `subscriptions` above.
+            let wait_set_entry = unsafe { *self.handle.timers.add(i) };
+            if !wait_set_entry.is_null() {
+                ready_entities.timers.push(timer.clone());
+            }
+        }
+        for (i, client) in self.clients.iter().enumerate() {
+            // SAFETY: The `clients` entry is an array of pointers, like `subscriptions` above.
+            let wait_set_entry = unsafe { *self.handle.clients.add(i) };
+            if !wait_set_entry.is_null() {
+                ready_entities.clients.push(client.clone());
+            }
+        }
+        for (i, service) in self.services.iter().enumerate() {
+            // SAFETY: The `services` entry is an array of pointers, like `subscriptions` above.
+            let wait_set_entry = unsafe { *self.handle.services.add(i) };
+            if !wait_set_entry.is_null() {
+                ready_entities.services.push(service.clone());
+            }
+        }
+        for (i, qos_event) in self.qos_events.iter().enumerate() {
+            // SAFETY: The `events` entry is an array of pointers, like `subscriptions` above.
+            let wait_set_entry = unsafe { *self.handle.events.add(i) };
+            if !wait_set_entry.is_null() {
+                ready_entities.qos_events.push(qos_event.clone());
+            }
+        }
+    }
+}
+
+impl ReadyEntities {
+    /// Creates an empty list that does not allocate until entities are added.
+    pub(crate) fn new() -> Self {
+        Self {
+            subscriptions: Vec::new(),
+            guard_conditions: Vec::new(),
+            timers: Vec::new(),
+            clients: Vec::new(),
+            services: Vec::new(),
+            qos_events: Vec::new(),
+        }
+    }
+
+    // Executes the ready entities one after another, sorted by a key of their callback IDs and
+    // ignoring their priorities, e.g. for a deterministic executor. The sort is stable, so
+    // entities with the same key are executed in the order of `execute()`.
+    pub(crate) fn execute_sorted_by_key<K: Ord>(
+        &self,
+        hooks: Option<&CallbackHooks>,
+        key: impl Fn(&CallbackId) -> K,
+    ) -> Result<(), RclrsError> {
+        let mut ready: Vec<_> = self
+            .subscriptions
+            .iter()
+            .map(|entity| ReadyEntity::Subscription(&**entity))
+            .chain(
+                self.timers
+                    .iter()
+                    .map(|entity| ReadyEntity::Timer(&**entity)),
+            )
+            .chain(
+                self.clients
+                    .iter()
+                    .map(|entity| ReadyEntity::Client(&**entity)),
+            )
+            .chain(
+                self.services
+                    .iter()
+                    .map(|entity| ReadyEntity::Service(&**entity)),
+            )
+            .chain(
+                self.qos_events
+                    .iter()
+                    .map(|entity| ReadyEntity::QoSEvent(&**entity)),
+            )
+            .map(|entity| (entity.callback_id(), entity))
+            .collect();
+        ready.sort_by_cached_key(|(id, _)| key(id));
+        for (id, entity) in ready {
+            execute_entity(None, hooks, id, || entity.execute())?;
+        }
+        Ok(())
+    }
+
+    // Executes the ready entities in the order of their priorities. This does not allocate, since
+    // it is called for every wakeup.
+    //
+    // Without a node, e.g. in an Executor, the default dispatch policy is used and no statistics
+    // are recorded. The hooks are those of the node or the executor.
+    pub(crate) fn execute(
+        &self,
+        node: Option<&Node>,
+        hooks: Option<&CallbackHooks>,
+    ) -> Result<(), RclrsError> {
+        #[cfg(feature = "std")]
+        let _current = node.map(CurrentGuard::enter_node);
+        #[cfg(feature = "statistics_msgs")]
+        if let Some(statistics) = node.and_then(|node| node.statistics.as_ref()) {
+            statistics.record_wakeup(
+                self.subscriptions.len()
+                    + self.timers.len()
+                    + self.clients.len()
+                    + self.services.len()
+                    + self.qos_events.len(),
+            );
+        }
+        let priorities = self
+            .subscriptions
+            .iter()
+            .map(|subscription| subscription.handle().priority())
+            .chain(self.timers.iter().map(|timer| timer.handle().priority()))
+            .chain(
+                self.services
+                    .iter()
+                    .map(|service| service.handle().priority()),
+            )
+            .chain(
+                (!self.clients.is_empty()
+                    || !self.qos_events.is_empty()
+                    || node.is_some_and(|node| self.graph_changed(node)))
+                .then_some(0),
+            );
+        let mut next_priority = priorities.clone().max();
+        while let Some(priority) = next_priority {
+            self.execute_priority(node, hooks, priority)?;
+            if node.is_some_and(|node| node.dispatch_policy == DispatchPolicy::StrictPriority) {
+                break;
+            }
+            next_priority = priorities.clone().filter(|p| *p < priority).max();
+        }
+        Ok(())
+    }
+
+    fn execute_priority(
+        &self,
+        node: Option<&Node>,
+        hooks: Option<&CallbackHooks>,
+        priority: i32,
+    ) -> Result<(), RclrsError> {
+        for ready_subscription in &self.subscriptions {
+            if ready_subscription.handle().priority() == priority {
+                let id = CallbackId::new(CallbackKind::Subscription, &**ready_subscription);
+                execute_entity(node, hooks, id, || ready_subscription.execute())?;
+            }
+        }
+        for ready_timer in &self.timers {
+            if ready_timer.handle().priority() == priority {
+                let id = CallbackId::new(CallbackKind::Timer, &**ready_timer);
+                execute_entity(node, hooks, id, || ready_timer.execute())?;
+            }
+        }
+        if priority == 0 {
+            for ready_client in &self.clients {
+                let id = CallbackId::new(CallbackKind::Client, &**ready_client);
+                execute_entity(node, hooks, id, || ready_client.execute())?;
+            }
+        }
+        for ready_service in &self.services {
+            if ready_service.handle().priority() == priority {
+                let id = CallbackId::new(CallbackKind::Service, &**ready_service);
+                execute_entity(node, hooks, id, || ready_service.execute())?;
+            }
+        }
+        if priority == 0 {
+            for ready_qos_event in &self.qos_events {
+                let id = CallbackId::new(CallbackKind::QoSEvent, &**ready_qos_event);
+                execute_entity(node, hooks, id, || ready_qos_event.execute())?;
+            }
+            if let Some(node) = node.filter(|node| self.graph_changed(node)) {
+                for handler in node.graph_event_handlers.iter().filter_map(Weak::upgrade) {
+                    let id = CallbackId::new(CallbackKind::GraphEvent, &*handler);
+                    execute_entity(Some(node), hooks, id, || handler.handle_graph_event())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Returns whether the graph guard condition of the node has been triggered.
+    fn graph_changed(&self, node: &Node) -> bool {
+        self.guard_conditions
+            .iter()
+            .any(|guard_condition| Arc::ptr_eq(guard_condition, &node.graph_guard_condition))
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.subscriptions.clear();
+        self.guard_conditions.clear();
+        self.timers.clear();
+        self.clients.clear();
+        self.services.clear();
+        self.qos_events.clear();
+    }
+}
+
+// A ready entity of any kind, for executing entities of different kinds in a custom order.
+enum ReadyEntity<'a> {
+    Subscription(&'a dyn SubscriptionBase),
+    Timer(&'a dyn TimerBase),
+    Client(&'a dyn ClientBase),
+    Service(&'a dyn ServiceBase),
+    QoSEvent(&'a dyn QoSEventBase),
+}
+
+impl ReadyEntity<'_> {
+    fn callback_id(&self) -> CallbackId {
+        match *self {
+            Self::Subscription(entity) => CallbackId::new(CallbackKind::Subscription, entity),
+            Self::Timer(entity) => CallbackId::new(CallbackKind::Timer, entity),
+            Self::Client(entity) => CallbackId::new(CallbackKind::Client, entity),
+            Self::Service(entity) => CallbackId::new(CallbackKind::Service, entity),
+            Self::QoSEvent(entity) => CallbackId::new(CallbackKind::QoSEvent, entity),
+        }
+    }
+
+    fn execute(&self) -> Result<(), RclrsError> {
+        match self {
+            Self::Subscription(entity) => entity.execute(),
+            Self::Timer(entity) => entity.execute(),
+            Self::Client(entity) => entity.execute(),
+            Self::Service(entity) => entity.execute(),
+            Self::QoSEvent(entity) => entity.execute(),
+        }
+    }
+}
+
+// Executes an entity, surrounded by the callback hooks, and records how long its callback ran if
+// the node collects statistics.
+#[cfg_attr(
+    not(feature = "statistics_msgs"),
+    allow(unused_variables, clippy::only_used_in_recursion)
+)]
+fn execute_entity(
+    node: Option<&Node>,
+    hooks: Option<&CallbackHooks>,
+    id: CallbackId,
+    execute: impl FnOnce() -> Result<(), RclrsError>,
+) -> Result<(), RclrsError> {
+    if let Some(hooks) = hooks {
+        return hooks.run(id, || execute_entity(node, None, id, execute));
+    }
+    #[cfg(feature = "statistics_msgs")]
+    if let Some(statistics) = node.and_then(|node| node.statistics.as_ref()) {
+        let start = std::time::Instant::now();
+        let result = execute();
+        statistics.record_callback(start.elapsed());
+        return result;
+    }
+    execute()
+}
+
+impl ReusableWaitSet {
+    /// Creates a wait set for up to the given numbers of entities.
+    ///
+    /// The guard conditions are added to the wait set in every iteration, in addition to the
+    /// entities of the node. They do not have callbacks, but wake up the wait set when triggered.
+    /// The capacity for guard conditions is taken from their number.
+    pub(crate) fn new(
+        capacities: WaitSetCapacities,
+        guard_conditions: Vec<Arc<GuardCondition>>,
+        context: &Context,
+    ) -> Result<Self, RclrsError> {
+        let capacities = WaitSetCapacities {
+            guard_conditions: guard_conditions.len(),
+            ..capacities
+        };
+        Ok(Self {
+            wait_set: WaitSet::new(capacities, context)?,
+            ready_entities: ReadyEntities {
+                subscriptions: Vec::with_capacity(capacities.subscriptions),
+                guard_conditions: Vec::with_capacity(capacities.guard_conditions),
+                timers: Vec::with_capacity(capacities.timers),
+                clients: Vec::with_capacity(capacities.clients),
+                services: Vec::with_capacity(capacities.services),
+                qos_events: Vec::with_capacity(capacities.qos_events),
+            },
+            guard_conditions,
+        })
+    }
+
+    /// Equivalent to [`spin_once`][1], but reuses the wait set.
+    ///
+    /// The entities of the node that are no longer alive are skipped.
+    ///
+    /// [1]: crate::spin_once
+    pub(crate) fn spin_once(
+        &mut self,
+        node: &Node,
+        timeout: Option<Duration>,
+    ) -> Result<(), RclrsError> {
+        let result = self.wait_and_execute(node, timeout);
+        // Clear the storage also in the error case, so that the wait set does not keep dropped
+        // entities alive until the next call.
+        self.ready_entities.clear();
+        self.wait_set.clear();
+        result
+    }
+
+    fn wait_and_execute(
+        &mut self,
+        node: &Node,
+        timeout: Option<Duration>,
+    ) -> Result<(), RclrsError> {
+        for subscription in node.subscriptions.iter().filter_map(Weak::upgrade) {
+            self.wait_set.add_subscription(subscription)?;
+        }
+        for guard_condition in &self.guard_conditions {
+            self.wait_set.add_guard_condition(guard_condition.clone())?;
+        }
+        for timer in node.timers.iter().filter_map(Weak::upgrade) {
+            self.wait_set.add_timer(timer)?;
+        }
+        for client in node.clients.iter().filter_map(Weak::upgrade) {
+            self.wait_set.add_client(client)?;
+        }
+        for service in node.services.iter().filter_map(Weak::upgrade) {
+            self.wait_set.add_service(service)?;
+        }
+        for qos_event in node.qos_events.iter().filter_map(Weak::upgrade) {
+            self.wait_set.add_qos_event(qos_event)?;
+        }
+        self.wait_set.wait_into(timeout, &mut self.ready_entities)?;
+        self.ready_entities
+            .execute(Some(node), node.callback_hooks.as_ref())
     }
 }
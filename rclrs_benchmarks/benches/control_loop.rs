@@ -0,0 +1,125 @@
+//! Measures the latency distribution of a 1 kHz control subscriber.
+//!
+//! Unlike the criterion benchmarks, which report averages, this reports the tail of the
+//! distribution, since the worst case is what matters for a control loop.
+//!
+//! Each case is measured twice: with [`rclrs::spin_once`], which creates a new wait set for every
+//! wakeup, and in static memory mode, where it reuses a preallocated wait set like
+//! [`rclrs::spin`] does.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rclrs::{Context, StaticMemoryLimits, QOS_PROFILE_SENSOR_DATA};
+use rclrs_benchmarks::{create_benchmark_node, message_with_size, spin_until, topic_name};
+use std_msgs::msg::UInt8MultiArray;
+
+/// The period of the publisher.
+const PERIOD: Duration = Duration::from_millis(1);
+
+/// How many messages are measured.
+const NUM_MESSAGES: usize = 10_000;
+
+/// The numbers of idle subscriptions on the node being spun, in addition to the control one.
+const NUMBERS_OF_IDLE_SUBSCRIPTIONS: [usize; 3] = [0, 10, 100];
+
+fn main() {
+    let context = Context::new([]).unwrap();
+    println!("1 kHz control subscriber, latency from publishing to the callback:");
+    for count in NUMBERS_OF_IDLE_SUBSCRIPTIONS {
+        for static_memory in [false, true] {
+            let latencies = measure(&context, count, static_memory);
+            print_report(count, static_memory, latencies);
+        }
+    }
+}
+
+/// Publishes at 1 kHz from another thread, and returns the latencies of the received messages.
+///
+/// The publisher writes the time since `start` into the message, so that the callback can compute
+/// the latency with the same steady clock.
+fn measure(context: &Context, num_idle_subscriptions: usize, static_memory: bool) -> Vec<Duration> {
+    let mut node = create_benchmark_node(context, "control_loop").unwrap();
+    if static_memory {
+        node.enable_static_memory(StaticMemoryLimits {
+            max_subscriptions: num_idle_subscriptions + 1,
+            max_timers: 0,
+            max_clients: 0,
+            max_services: 0,
+            max_qos_events: 0,
+            max_pending_requests: 0,
+        })
+        .unwrap();
+    }
+    let _idle_subscriptions: Vec<_> = (0..num_idle_subscriptions)
+        .map(|i| {
+            node.create_subscription(
+                &topic_name("control_loop_idle", i),
+                QOS_PROFILE_SENSOR_DATA,
+                |_msg: UInt8MultiArray| {},
+            )
+            .unwrap()
+        })
+        .collect();
+    let topic = topic_name("control_loop", num_idle_subscriptions);
+    let start = Instant::now();
+    let latencies = Rc::new(RefCell::new(Vec::with_capacity(NUM_MESSAGES)));
+    let latencies_in_callback = Rc::clone(&latencies);
+    let _subscription = node
+        .create_subscription::<UInt8MultiArray, _>(
+            &topic,
+            QOS_PROFILE_SENSOR_DATA,
+            move |msg: UInt8MultiArray| {
+                let received = start.elapsed();
+                let sent = u64::from_le_bytes(msg.data[..8].try_into().unwrap());
+                latencies_in_callback
+                    .borrow_mut()
+                    .push(received.saturating_sub(Duration::from_nanos(sent)));
+            },
+        )
+        .unwrap();
+    let publisher = node
+        .create_publisher::<UInt8MultiArray>(&topic, QOS_PROFILE_SENSOR_DATA)
+        .unwrap();
+
+    let done = Arc::new(AtomicBool::new(false));
+    let done_in_thread = Arc::clone(&done);
+    let publisher_thread = std::thread::spawn(move || {
+        let mut message = message_with_size(8);
+        let mut next = Instant::now();
+        while !done_in_thread.load(Ordering::Relaxed) {
+            let sent = start.elapsed().as_nanos() as u64;
+            message.data.copy_from_slice(&sent.to_le_bytes());
+            publisher.publish(&message).unwrap();
+            next += PERIOD;
+            std::thread::sleep(next.saturating_duration_since(Instant::now()));
+        }
+    });
+
+    // The messages received before discovery has finished and the loop has settled are not
+    // representative.
+    spin_until(&node, || latencies.borrow().len() >= 100);
+    latencies.borrow_mut().clear();
+    spin_until(&node, || latencies.borrow().len() >= NUM_MESSAGES);
+    done.store(true, Ordering::Relaxed);
+    publisher_thread.join().unwrap();
+    latencies.take()
+}
+
+fn print_report(num_idle_subscriptions: usize, static_memory: bool, mut latencies: Vec<Duration>) {
+    latencies.sort();
+    let percentile = |p: f64| latencies[((latencies.len() - 1) as f64 * p) as usize];
+    let wait_set = if static_memory { "reused" } else { "new" };
+    println!(
+        "  {:>3} idle subscriptions, {:>6} wait set: median {:?}, p99 {:?}, p99.9 {:?}, max {:?}",
+        num_idle_subscriptions,
+        wait_set,
+        percentile(0.5),
+        percentile(0.99),
+        percentile(0.999),
+        latencies[latencies.len() - 1],
+    );
+}
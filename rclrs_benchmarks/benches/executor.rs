@@ -0,0 +1,116 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rclrs::{Context, Node, StaticMemoryLimits, Subscription, QOS_PROFILE_DEFAULT};
+use rclrs_benchmarks::{
+    create_benchmark_node, message_with_size, spin_until, spin_until_idle, topic_name,
+};
+use std_msgs::msg::UInt8MultiArray;
+
+/// The numbers of subscriptions on the node being spun.
+const NUMBERS_OF_SUBSCRIPTIONS: [usize; 4] = [1, 10, 100, 1000];
+
+/// Creates subscriptions that never receive messages.
+fn create_idle_subscriptions(
+    node: &mut Node,
+    benchmark: &str,
+    count: usize,
+) -> Vec<Arc<Subscription<UInt8MultiArray>>> {
+    (0..count)
+        .map(|i| {
+            node.create_subscription(
+                &topic_name(benchmark, i),
+                QOS_PROFILE_DEFAULT,
+                |_msg: UInt8MultiArray| {},
+            )
+            .unwrap()
+        })
+        .collect()
+}
+
+/// Measures the overhead of a non-blocking `spin_once()` when nothing is ready, with and without
+/// static memory mode.
+fn spin_once_idle(c: &mut Criterion) {
+    let context = Context::new([]).unwrap();
+    let mut group = c.benchmark_group("spin_once_idle");
+    for count in NUMBERS_OF_SUBSCRIPTIONS {
+        for static_memory in [false, true] {
+            let mut node = create_benchmark_node(&context, "spin_once_idle").unwrap();
+            if static_memory {
+                node.enable_static_memory(StaticMemoryLimits {
+                    max_subscriptions: count,
+                    max_timers: 0,
+                    max_clients: 0,
+                    max_services: 0,
+                    max_qos_events: 0,
+                    max_pending_requests: 0,
+                })
+                .unwrap();
+            }
+            let _subscriptions = create_idle_subscriptions(&mut node, "spin_once_idle", count);
+            let name = if static_memory {
+                "static_memory"
+            } else {
+                "dynamic_memory"
+            };
+            group.bench_with_input(BenchmarkId::new(name, count), &node, |b, node| {
+                // This returns a timeout error, which is expected.
+                b.iter(|| rclrs::spin_once(node, Some(Duration::ZERO)))
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Measures the time from publishing a small message until its callback has run, when the node
+/// has many other subscriptions that are not ready.
+fn wakeup_latency(c: &mut Criterion) {
+    let context = Context::new([]).unwrap();
+    let mut group = c.benchmark_group("wakeup_latency");
+    for count in NUMBERS_OF_SUBSCRIPTIONS {
+        let mut node = create_benchmark_node(&context, "wakeup_latency").unwrap();
+        let _subscriptions = create_idle_subscriptions(&mut node, "wakeup_latency_idle", count - 1);
+        let topic = topic_name("wakeup_latency", count);
+        let num_received = Rc::new(Cell::new(0usize));
+        let num_received_in_callback = Rc::clone(&num_received);
+        let _subscription = node
+            .create_subscription::<UInt8MultiArray, _>(
+                &topic,
+                QOS_PROFILE_DEFAULT,
+                move |_msg: UInt8MultiArray| {
+                    num_received_in_callback.set(num_received_in_callback.get() + 1)
+                },
+            )
+            .unwrap();
+        let publisher = node
+            .create_publisher::<UInt8MultiArray>(&topic, QOS_PROFILE_DEFAULT)
+            .unwrap();
+        let message = message_with_size(16);
+
+        // Messages published before discovery has finished are lost, so wait for one to arrive.
+        spin_until(&node, || {
+            publisher.publish(&message).unwrap();
+            num_received.get() > 0
+        });
+        spin_until_idle(&node);
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(count),
+            &message,
+            |b, message| {
+                b.iter(|| {
+                    let num_received_before = num_received.get();
+                    publisher.publish(message).unwrap();
+                    spin_until(&node, || num_received.get() > num_received_before);
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, spin_once_idle, wakeup_latency);
+criterion_main!(benches);
@@ -0,0 +1,74 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rclrs::{Context, QOS_PROFILE_DEFAULT};
+use rclrs_benchmarks::{
+    create_benchmark_node, message_with_size, spin_until, spin_until_idle, topic_name,
+    MESSAGE_SIZES,
+};
+use std_msgs::msg::UInt8MultiArray;
+
+/// Measures the time for publishing a message without any subscribers.
+fn publish(c: &mut Criterion) {
+    let context = Context::new([]).unwrap();
+    let mut node = create_benchmark_node(&context, "publish").unwrap();
+    let mut group = c.benchmark_group("publish");
+    for size in MESSAGE_SIZES {
+        let publisher = node
+            .create_publisher::<UInt8MultiArray>(&topic_name("publish", size), QOS_PROFILE_DEFAULT)
+            .unwrap();
+        let message = message_with_size(size);
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &message, |b, message| {
+            b.iter(|| publisher.publish(message).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// Measures the time from publishing a message until its subscription callback has run, within
+/// one process.
+fn publish_and_receive(c: &mut Criterion) {
+    let context = Context::new([]).unwrap();
+    let mut node = create_benchmark_node(&context, "publish_and_receive").unwrap();
+    let mut group = c.benchmark_group("publish_and_receive");
+    for size in MESSAGE_SIZES {
+        let topic = topic_name("publish_and_receive", size);
+        let num_received = Rc::new(Cell::new(0usize));
+        let num_received_in_callback = Rc::clone(&num_received);
+        let _subscription = node
+            .create_subscription::<UInt8MultiArray, _>(
+                &topic,
+                QOS_PROFILE_DEFAULT,
+                move |_msg: UInt8MultiArray| {
+                    num_received_in_callback.set(num_received_in_callback.get() + 1)
+                },
+            )
+            .unwrap();
+        let publisher = node
+            .create_publisher::<UInt8MultiArray>(&topic, QOS_PROFILE_DEFAULT)
+            .unwrap();
+        let message = message_with_size(size);
+
+        // Messages published before discovery has finished are lost, so wait for one to arrive.
+        spin_until(&node, || {
+            publisher.publish(&message).unwrap();
+            num_received.get() > 0
+        });
+        spin_until_idle(&node);
+
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &message, |b, message| {
+            b.iter(|| {
+                let num_received_before = num_received.get();
+                publisher.publish(message).unwrap();
+                spin_until(&node, || num_received.get() > num_received_before);
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, publish, publish_and_receive);
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use rclrs::{Context, QOS_PROFILE_SERVICES_DEFAULT};
+use rclrs_benchmarks::{create_benchmark_node, spin_until, topic_name};
+use std_srvs::srv::{SetBool, SetBool_Request, SetBool_Response};
+
+/// Measures the time from sending a request until the response callback has run, with the client
+/// and the service in the same node.
+fn round_trip(c: &mut Criterion) {
+    let context = Context::new([]).unwrap();
+    let mut node = create_benchmark_node(&context, "service_round_trip").unwrap();
+    let service_name = topic_name("service_round_trip", 0);
+    let _service = node
+        .create_service::<SetBool, _>(
+            &service_name,
+            QOS_PROFILE_SERVICES_DEFAULT,
+            |request: SetBool_Request| SetBool_Response {
+                success: request.data,
+                message: String::new(),
+            },
+        )
+        .unwrap();
+    let client = node
+        .create_client::<SetBool>(&service_name, QOS_PROFILE_SERVICES_DEFAULT)
+        .unwrap();
+    let num_responses = Rc::new(Cell::new(0usize));
+    let request = SetBool_Request { data: true };
+
+    // Requests sent before discovery has finished may be lost, so wait for the service first.
+    while !client.service_is_ready().unwrap() {
+        let _ = rclrs::spin_once(&node, Some(std::time::Duration::from_millis(10)));
+    }
+
+    c.bench_function("service_round_trip", |b| {
+        b.iter(|| {
+            let num_responses_before = num_responses.get();
+            let num_responses_in_callback = Rc::clone(&num_responses);
+            client
+                .async_send_request_with_callback(&request, move |_: SetBool_Response| {
+                    num_responses_in_callback.set(num_responses_in_callback.get() + 1)
+                })
+                .unwrap();
+            spin_until(&node, || num_responses.get() > num_responses_before);
+        })
+    });
+}
+
+criterion_group!(benches, round_trip);
+criterion_main!(benches);
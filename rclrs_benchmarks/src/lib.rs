@@ -0,0 +1,46 @@
+//! Helpers shared by the benchmarks of this package.
+//!
+//! The benchmarks themselves are in the `benches` directory.
+
+use rclrs::{Context, Node, RclrsError};
+use std::time::Duration;
+
+/// The payload sizes, in bytes, used by the benchmarks that depend on the message size.
+pub const MESSAGE_SIZES: [usize; 5] = [16, 1024, 64 * 1024, 1024 * 1024, 4 * 1024 * 1024];
+
+/// Creates a message with a payload of the given size.
+pub fn message_with_size(size: usize) -> std_msgs::msg::UInt8MultiArray {
+    std_msgs::msg::UInt8MultiArray {
+        data: vec![0; size],
+        ..Default::default()
+    }
+}
+
+/// Creates a node with a name that includes the given benchmark name.
+pub fn create_benchmark_node(context: &Context, benchmark: &str) -> Result<Node, RclrsError> {
+    context.create_node(&format!("rclrs_benchmark_{}", benchmark))
+}
+
+/// Returns a topic name that is unique to the given benchmark and parameter.
+///
+/// Using a different topic for each benchmark avoids that messages from a previous benchmark are
+/// received.
+pub fn topic_name(benchmark: &str, parameter: usize) -> String {
+    format!("rclrs_benchmark_{}_{}", benchmark, parameter)
+}
+
+/// Spins the node until the condition is true.
+///
+/// Errors from [`rclrs::spin_once`] are ignored, since timeouts and spurious wakeups are expected.
+pub fn spin_until(node: &Node, mut condition: impl FnMut() -> bool) {
+    while !condition() {
+        let _ = rclrs::spin_once(node, Some(Duration::from_millis(100)));
+    }
+}
+
+/// Spins the node until no more messages arrive within a short time.
+///
+/// This is used to drain messages left over from setting up a benchmark.
+pub fn spin_until_idle(node: &Node) {
+    while rclrs::spin_once(node, Some(Duration::from_millis(100))).is_ok() {}
+}
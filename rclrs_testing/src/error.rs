@@ -0,0 +1,45 @@
+use rclrs::RclrsError;
+use std::error::Error;
+use std::fmt::{self, Display};
+
+/// An error in an integration test.
+#[derive(Debug)]
+pub enum TestError {
+    /// An `rclrs` function failed.
+    Rcl(RclrsError),
+    /// Starting the node under test failed.
+    Spawn(std::io::Error),
+    /// Waiting for something took longer than the timeout.
+    Timeout {
+        /// What was being waited for.
+        waiting_for: String,
+    },
+}
+
+impl Display for TestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TestError::Rcl(e) => write!(f, "{}", e),
+            TestError::Spawn(e) => write!(f, "Could not start the node under test: {}", e),
+            TestError::Timeout { waiting_for } => {
+                write!(f, "Timed out waiting for {}", waiting_for)
+            }
+        }
+    }
+}
+
+impl Error for TestError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TestError::Rcl(e) => Some(e),
+            TestError::Spawn(e) => Some(e),
+            TestError::Timeout { .. } => None,
+        }
+    }
+}
+
+impl From<RclrsError> for TestError {
+    fn from(e: RclrsError) -> Self {
+        TestError::Rcl(e)
+    }
+}
@@ -0,0 +1,199 @@
+use crate::TestError;
+
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rclrs::testing::Inbox;
+use rclrs::{
+    Context, Node, Publisher, RclReturnCode, RclrsError, SubscriberErrorCode, Subscription,
+    QOS_PROFILE_DEFAULT,
+};
+use rosidl_runtime_rs::Message;
+
+/// How long to block at most in each iteration of a waiting loop.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The messages received on a topic, see [`TestHarness::collect`].
+pub struct Collector<T: Message> {
+    inbox: Inbox<T>,
+    subscription: Arc<Subscription<T>>,
+    topic: String,
+}
+
+impl<T: Message> Collector<T> {
+    /// Removes and returns the messages received so far.
+    pub fn take_all(&self) -> Vec<T> {
+        self.inbox.take_all()
+    }
+
+    /// Returns the subscription that receives the messages.
+    pub fn subscription(&self) -> &Subscription<T> {
+        &self.subscription
+    }
+}
+
+/// A node for talking to the node under test, and functions for waiting on it.
+pub struct TestHarness {
+    node: Node,
+    // Spinning a node without any subscriptions is an error, so it is avoided.
+    has_subscriptions: bool,
+    _context: Context,
+}
+
+impl TestHarness {
+    /// Creates a harness with a node of the given name in its own context.
+    pub fn new(node_name: &str) -> Result<Self, TestError> {
+        let context = Context::new([])?;
+        let node = context.create_node(node_name)?;
+        Ok(Self {
+            node,
+            has_subscriptions: false,
+            _context: context,
+        })
+    }
+
+    /// Returns the node of the harness.
+    pub fn node(&self) -> &Node {
+        &self.node
+    }
+
+    /// Creates a publisher with the default QoS profile.
+    pub fn create_publisher<T: Message>(&mut self, topic: &str) -> Result<Publisher<T>, TestError> {
+        Ok(self.node.create_publisher(topic, QOS_PROFILE_DEFAULT)?)
+    }
+
+    /// Subscribes to a topic with the default QoS profile, and collects all received messages.
+    ///
+    /// Messages are only received while the harness is waiting in one of its functions.
+    pub fn collect<T: Message>(&mut self, topic: &str) -> Result<Collector<T>, TestError> {
+        let inbox = Inbox::new();
+        let subscription =
+            self.node
+                .create_subscription(topic, QOS_PROFILE_DEFAULT, inbox.callback())?;
+        self.has_subscriptions = true;
+        Ok(Collector {
+            inbox,
+            subscription,
+            topic: topic.to_string(),
+        })
+    }
+
+    /// Receives messages until the condition is true, or fails after the timeout.
+    ///
+    /// The description of what is being waited for is used in the error message.
+    pub fn spin_until(
+        &self,
+        waiting_for: &str,
+        timeout: Duration,
+        mut condition: impl FnMut() -> Result<bool, TestError>,
+    ) -> Result<(), TestError> {
+        let deadline = Instant::now() + timeout;
+        while !condition()? {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(TestError::Timeout {
+                    waiting_for: waiting_for.to_string(),
+                });
+            }
+            let poll_timeout = POLL_INTERVAL.min(deadline - now);
+            if !self.has_subscriptions {
+                thread::sleep(poll_timeout);
+                continue;
+            }
+            match rclrs::spin_once(&self.node, Some(poll_timeout)) {
+                Ok(())
+                | Err(RclrsError {
+                    code: RclReturnCode::Timeout,
+                    ..
+                })
+                | Err(RclrsError {
+                    code:
+                        RclReturnCode::SubscriberError(SubscriberErrorCode::SubscriptionTakeFailed),
+                    ..
+                }) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Waits until the publisher is matched with at least the given number of subscriptions.
+    pub fn wait_for_subscriptions<T: Message>(
+        &self,
+        publisher: &Publisher<T>,
+        count: usize,
+        timeout: Duration,
+    ) -> Result<(), TestError> {
+        self.spin_until(
+            &format!("{} subscription(s) to be discovered", count),
+            timeout,
+            || Ok(publisher.get_subscription_count()? >= count),
+        )
+    }
+
+    /// Waits until the collector is matched with at least the given number of publishers.
+    pub fn wait_for_publishers<T: Message>(
+        &self,
+        collector: &Collector<T>,
+        count: usize,
+        timeout: Duration,
+    ) -> Result<(), TestError> {
+        self.spin_until(
+            &format!("{} publisher(s) to be discovered", count),
+            timeout,
+            || Ok(collector.subscription.get_publisher_count()? >= count),
+        )
+    }
+
+    /// Publishes a message once a subscription has been discovered.
+    ///
+    /// Publishing right after creating a publisher usually loses the message, since the node under
+    /// test has not been discovered yet.
+    pub fn inject<T: Message>(
+        &self,
+        publisher: &Publisher<T>,
+        message: &T,
+        timeout: Duration,
+    ) -> Result<(), TestError> {
+        self.wait_for_subscriptions(publisher, 1, timeout)?;
+        Ok(publisher.publish(message)?)
+    }
+
+    /// Waits until the collector has received at least the given number of messages, and returns
+    /// all of them.
+    pub fn wait_for_messages<T: Message>(
+        &self,
+        collector: &Collector<T>,
+        count: usize,
+        timeout: Duration,
+    ) -> Result<Vec<T>, TestError> {
+        let mut messages = Vec::new();
+        self.spin_until(
+            &format!("{} message(s) on {}", count, collector.topic),
+            timeout,
+            || {
+                messages.extend(collector.take_all());
+                Ok(messages.len() >= count)
+            },
+        )?;
+        Ok(messages)
+    }
+
+    /// Receives messages for the given duration, and fails if the collector received any.
+    pub fn assert_no_messages<T: Message>(
+        &self,
+        collector: &Collector<T>,
+        duration: Duration,
+    ) -> Result<(), TestError> {
+        // The timeout is the expected outcome here.
+        match self.spin_until("", duration, || Ok(!collector.inbox.is_empty())) {
+            Err(TestError::Timeout { .. }) => Ok(()),
+            Err(e) => Err(e),
+            Ok(()) => panic!(
+                "Expected no messages, but received {}",
+                collector.inbox.len()
+            ),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+#![warn(missing_docs)]
+//! Helpers for integration tests of Rust nodes.
+//!
+//! A typical test
+//! 1. starts the node under test in its own process with [`NodeUnderTest::spawn`],
+//! 2. creates a [`TestHarness`], which has its own node for talking to the node under test,
+//! 3. injects messages with [`TestHarness::inject`] and collects the outputs of the node under
+//!    test with [`TestHarness::collect`] and [`TestHarness::wait_for_messages`].
+//!
+//! All waiting functions take a timeout and return a [`TestError::Timeout`] when it is exceeded,
+//! so that a broken node makes the test fail instead of hang.
+//!
+//! # Example
+//! ```ignore
+//! use rclrs_testing::{NodeUnderTest, TestHarness};
+//! use std::time::Duration;
+//!
+//! let _node_under_test = NodeUnderTest::spawn("my_package", "my_relay", [])?;
+//! let mut harness = TestHarness::new("relay_test")?;
+//! let output = harness.collect::<std_msgs::msg::String>("output")?;
+//! let input = harness.create_publisher::<std_msgs::msg::String>("input")?;
+//! let message = std_msgs::msg::String { data: "hello".into() };
+//! harness.inject(&input, &message, Duration::from_secs(5))?;
+//! let received = harness.wait_for_messages(&output, 1, Duration::from_secs(5))?;
+//! assert_eq!(received, vec![message]);
+//! # Ok::<(), rclrs_testing::TestError>(())
+//! ```
+
+mod error;
+mod harness;
+mod process;
+
+pub use error::*;
+pub use harness::*;
+pub use process::*;
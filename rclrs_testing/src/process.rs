@@ -0,0 +1,43 @@
+use crate::TestError;
+
+use std::process::{Child, Command};
+
+/// A node that runs in a separate process, started with `ros2 run`.
+///
+/// The process is killed when this is dropped, so that a failing test does not leave it running.
+pub struct NodeUnderTest {
+    child: Child,
+}
+
+impl NodeUnderTest {
+    /// Starts an executable from a package, like `ros2 run <package> <executable> <args>`.
+    ///
+    /// ROS arguments, e.g. for remapping, can be passed after `--ros-args`.
+    pub fn spawn<'a>(
+        package: &str,
+        executable: &str,
+        args: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self, TestError> {
+        let child = Command::new("ros2")
+            .arg("run")
+            .arg(package)
+            .arg(executable)
+            .args(args)
+            .spawn()
+            .map_err(TestError::Spawn)?;
+        Ok(Self { child })
+    }
+
+    /// Returns true if the process has not exited yet.
+    pub fn is_running(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+}
+
+impl Drop for NodeUnderTest {
+    fn drop(&mut self) {
+        // The process may already have exited, which is fine.
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -7,16 +7,36 @@ edition = "2021"
 
 [dependencies]
 libc = "0.2"
-rosidl_runtime_rs = "*"
+rosidl_runtime_rs = { version = "*", default-features = false }
 serde = { version = "1", optional = true, features = ["derive"] }
+@[if package_name == 'geometry_msgs']@
+# Optional dependencies for converting geometry types to and from these math libraries
+glam = { version = "0.29", optional = true, default-features = false, features = ["libm"] }
+nalgebra = { version = "0.33", optional = true, default-features = false, features = ["libm"] }
+@[end if]@
 @[for dep in dependency_packages]@
-@(dep) = "*"
+@(dep) = { version = "*", default-features = false }
 @[end for]@
 
 [features]
+default = ["std"]
 @{
+std_features = ["rosidl_runtime_rs/std"]
+if package_name == 'geometry_msgs':
+	std_features += ["glam?/std", "nalgebra?/std"]
+for dep in dependency_packages:
+	std_features.append("{}/std".format(dep))
+
 serde_features = ["dep:serde", "rosidl_runtime_rs/serde"]
 for dep in dependency_packages:
 	serde_features.append("{}/serde".format(dep))
 }@
+std = @(std_features)
 serde = @(serde_features)
+# Provides MESSAGE_TYPE_SUPPORTS for rclrs::register_message_types(), which links the
+# introspection type support library of the package.
+type_registry = []
+@[if package_name == 'geometry_msgs']@
+glam = ["dep:glam"]
+nalgebra = ["dep:nalgebra"]
+@[end if]@
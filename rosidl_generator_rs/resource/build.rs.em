@@ -4,7 +4,11 @@ fn main() {
 	let lib_dir = Path::new("../../../lib")
 		.canonicalize()
 		.expect("Could not find '../../../lib'");
+	// On Windows, canonicalize() returns a verbatim path (starting with \\?\),
+	// which the MSVC linker does not understand.
+	let lib_dir = lib_dir.display().to_string();
+	let lib_dir = lib_dir.strip_prefix(r"\\?\").unwrap_or(&lib_dir);
 	// This allows building Rust packages that depend on message crates without
 	// sourcing the install directory first.
-	println!("cargo:rustc-link-search={}", lib_dir.display());
+	println!("cargo:rustc-link-search={}", lib_dir);
 }
@@ -1,5 +1,14 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 @[if len(msg_specs) > 0]@
 pub mod msg;
+
+/// The type supports of the messages of this package, for registering them with
+/// `rclrs::register_message_types()`.
+#[cfg(feature = "type_registry")]
+pub const MESSAGE_TYPE_SUPPORTS: &[rosidl_runtime_rs::MessageTypeSupport] = msg::rmw::MESSAGE_TYPE_SUPPORTS;
 @[end if]@
 
 @[if len(srv_specs) > 0]@
@@ -22,6 +22,12 @@ extern "C" {
     fn rosidl_typesupport_c__get_message_type_support_handle__@(package_name)__@(subfolder)__@(type_name)() -> libc::uintptr_t;
 }
 
+#[cfg(feature = "type_registry")]
+#[link(name = "@(package_name)__rosidl_typesupport_introspection_c")]
+extern "C" {
+    fn rosidl_typesupport_introspection_c__get_message_type_support_handle__@(package_name)__@(subfolder)__@(type_name)() -> libc::uintptr_t;
+}
+
 #[link(name = "@(package_name)__rosidl_generator_c")]
 extern "C" {
     fn @(package_name)__@(subfolder)__@(type_name)__init(msg: *mut @(type_name)) -> bool;
@@ -45,7 +51,7 @@ impl Default for @(type_name) {
   fn default() -> Self {
     unsafe {
 @#    // This is safe since a zeroed bit pattern always forms a valid message.
-      let mut msg = std::mem::zeroed();
+      let mut msg = core::mem::zeroed();
 @#    // This is safe since the precondititons for inti() are fulfilled by giving it a zeroed message.
       if !@(package_name)__@(subfolder)__@(type_name)__init(&mut msg as *mut _) {
         panic!("Call to @(package_name)__@(subfolder)__@(type_name)__init() failed");
@@ -73,23 +79,115 @@ impl rosidl_runtime_rs::SequenceAlloc for @(type_name) {
 
 impl rosidl_runtime_rs::Message for @(type_name) {
   type RmwMsg = Self;
-  fn into_rmw_message(msg_cow: std::borrow::Cow<'_, Self>) -> std::borrow::Cow<'_, Self::RmwMsg> { msg_cow }
+  fn into_rmw_message(msg_cow: alloc::borrow::Cow<'_, Self>) -> alloc::borrow::Cow<'_, Self::RmwMsg> { msg_cow }
   fn from_rmw_message(msg: Self::RmwMsg) -> Self { msg }
 }
 
 impl rosidl_runtime_rs::RmwMessage for @(type_name) where Self: Sized {
+  const TYPE_NAME: &'static str = "@(package_name)/@(subfolder)/@(type_name)";
+  const HAS_FIXED_SIZE: bool = @(get_has_fixed_size(msg_spec));
   fn get_type_support() -> libc::uintptr_t {
     unsafe { rosidl_typesupport_c__get_message_type_support_handle__@(package_name)__@(subfolder)__@(type_name)() }
   }
 }
 
+@{
+header_prefix = get_header_prefix(msg_spec)
+}@
+@[if header_prefix is not None]@
+impl rosidl_runtime_rs::Stamped for @(type_name) {
+  fn stamp(&self) -> (i32, u32) {
+    (self.@(header_prefix)stamp.sec, self.@(header_prefix)stamp.nanosec)
+  }
+  fn set_stamp(&mut self, sec: i32, nanosec: u32) {
+    self.@(header_prefix)stamp.sec = sec;
+    self.@(header_prefix)stamp.nanosec = nanosec;
+  }
+  fn set_frame_id(&mut self, frame_id: &str) {
+    self.@(header_prefix)frame_id = frame_id.into();
+  }
+}
+
+@[end if]@
+impl rosidl_runtime_rs::FieldAccess for @(type_name) {
+  fn get_field(&self, path: &str) -> Result<rosidl_runtime_rs::FieldValue, rosidl_runtime_rs::FieldAccessError> {
+    let (name, rest) = rosidl_runtime_rs::split_field_path(path)?;
+    match name {
+@[for member in msg_spec.structure.members]@
+      "@(member.name)" => rosidl_runtime_rs::FieldAccess::get_field(&self.@(get_rs_name(member.name)), rest),
+@[end for]@
+      _ => Err(rosidl_runtime_rs::FieldAccessError::NoSuchField(name.into())),
+    }
+  }
+  fn set_field(&mut self, path: &str, value: rosidl_runtime_rs::FieldValue) -> Result<(), rosidl_runtime_rs::FieldAccessError> {
+    let (name, rest) = rosidl_runtime_rs::split_field_path(path)?;
+    match name {
+@[for member in msg_spec.structure.members]@
+      "@(member.name)" => rosidl_runtime_rs::FieldAccess::set_field(&mut self.@(get_rs_name(member.name)), rest, value),
+@[end for]@
+      _ => Err(rosidl_runtime_rs::FieldAccessError::NoSuchField(name.into())),
+    }
+  }
+}
+
+@[if package_name == 'geometry_msgs' and type_name == 'TransformStamped']@
+impl rosidl_runtime_rs::TransformMessage for @(type_name) {
+  fn frame_ids(&self) -> (&[u8], &[u8]) {
+    (&self.header.frame_id, &self.child_frame_id)
+  }
+  fn translation(&self) -> [f64; 3] {
+    let translation = &self.transform.translation;
+    [translation.x, translation.y, translation.z]
+  }
+  fn rotation(&self) -> [f64; 4] {
+    let rotation = &self.transform.rotation;
+    [rotation.x, rotation.y, rotation.z, rotation.w]
+  }
+  fn set_child_frame_id(&mut self, child_frame_id: &str) {
+    self.child_frame_id = child_frame_id.into();
+  }
+  fn set_transform(&mut self, translation: [f64; 3], rotation: [f64; 4]) {
+    [self.transform.translation.x, self.transform.translation.y, self.transform.translation.z] = translation;
+    [self.transform.rotation.x, self.transform.rotation.y, self.transform.rotation.z, self.transform.rotation.w] = rotation;
+  }
+}
+
+@[end if]@
+@[if package_name == 'tf2_msgs' and type_name == 'TFMessage']@
+impl rosidl_runtime_rs::TfMessage for @(type_name) {
+  type Transform = geometry_msgs::msg::rmw::TransformStamped;
+  fn transforms(&self) -> &[Self::Transform] {
+    &self.transforms
+  }
+  fn set_transforms(&mut self, transforms: alloc::vec::Vec<Self::Transform>) {
+    self.transforms = transforms.into();
+  }
+}
+
+@[end if]@
 @[end for]
+// The type supports of all messages of the package, see rosidl_runtime_rs::MessageTypeSupport.
+#[cfg(feature = "type_registry")]
+pub(crate) const MESSAGE_TYPE_SUPPORTS: &[rosidl_runtime_rs::MessageTypeSupport] = &[
+@[for subfolder, msg_spec in msg_specs]@
+@{
+type_name = msg_spec.structure.namespaced_type.name
+}@
+  rosidl_runtime_rs::MessageTypeSupport {
+    type_name: "@(package_name)/@(subfolder)/@(type_name)",
+    type_support: <@(type_name) as rosidl_runtime_rs::RmwMessage>::get_type_support,
+    introspection_type_support: || unsafe { rosidl_typesupport_introspection_c__get_message_type_support_handle__@(package_name)__@(subfolder)__@(type_name)() },
+  },
+@[end for]@
+];
 }  // mod rmw
 
 @# #################################################
 @# ############ Idiomatic message types ############
 @# #################################################
 @# These types use standard Rust containers where possible.
+#[allow(unused_imports)]
+use alloc::string::ToString;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 @[for subfolder, msg_spec in msg_specs]@
@@ -108,16 +206,16 @@ pub struct @(type_name) {
 impl Default for @(type_name) {
   fn default() -> Self {
 @#  This has the benefit of automatically setting the right default values
-    <Self as rosidl_runtime_rs::Message>::from_rmw_message(crate::msg::rmw::@(type_name)::default())
+    <Self as rosidl_runtime_rs::Message>::from_rmw_message(crate::@(subfolder)::rmw::@(type_name)::default())
   }
 }
 
 impl rosidl_runtime_rs::Message for @(type_name) {
-  type RmwMsg = crate::msg::rmw::@(type_name);
+  type RmwMsg = crate::@(subfolder)::rmw::@(type_name);
 
-  fn into_rmw_message(msg_cow: std::borrow::Cow<'_, Self>) -> std::borrow::Cow<'_, Self::RmwMsg> {
+  fn into_rmw_message(msg_cow: alloc::borrow::Cow<'_, Self>) -> alloc::borrow::Cow<'_, Self::RmwMsg> {
     match msg_cow {
-      std::borrow::Cow::Owned(msg) => std::borrow::Cow::Owned(Self::RmwMsg {
+      alloc::borrow::Cow::Owned(msg) => alloc::borrow::Cow::Owned(Self::RmwMsg {
 @[for member in msg_spec.structure.members]@
 @#
 @#
@@ -128,7 +226,7 @@ impl rosidl_runtime_rs::Message for @(type_name) {
           .map(|elem| elem.as_str().into()),
 @[        elif isinstance(member.type.value_type, NamedType) or isinstance(member.type.value_type, NamespacedType)]@
         @(get_rs_name(member.name)): msg.@(get_rs_name(member.name))
-          .map(|elem| @(get_idiomatic_rs_type(member.type.value_type))::into_rmw_message(std::borrow::Cow::Owned(elem)).into_owned()),
+          .map(|elem| @(get_idiomatic_rs_type(member.type.value_type))::into_rmw_message(alloc::borrow::Cow::Owned(elem)).into_owned()),
 @[        elif isinstance(member.type.value_type, BasicType)]@
         @(get_rs_name(member.name)): msg.@(get_rs_name(member.name)),
 @[        else]@
@@ -151,7 +249,7 @@ impl rosidl_runtime_rs::Message for @(type_name) {
 @[        elif isinstance(member.type.value_type, NamedType) or isinstance(member.type.value_type, NamespacedType)]@
         @(get_rs_name(member.name)): msg.@(get_rs_name(member.name))
           .into_iter()
-          .map(|elem| @(get_idiomatic_rs_type(member.type.value_type))::into_rmw_message(std::borrow::Cow::Owned(elem)).into_owned())
+          .map(|elem| @(get_idiomatic_rs_type(member.type.value_type))::into_rmw_message(alloc::borrow::Cow::Owned(elem)).into_owned())
           .collect(),
 @[        else]@
         @(get_rs_name(member.name)): msg.@(get_rs_name(member.name)).into(),
@@ -160,7 +258,7 @@ impl rosidl_runtime_rs::Message for @(type_name) {
 @#
 @#    == NamedType + NamespacedType ==
 @[    elif isinstance(member.type, NamedType) or isinstance(member.type, NamespacedType)]@
-        @(get_rs_name(member.name)): @(get_idiomatic_rs_type(member.type))::into_rmw_message(std::borrow::Cow::Owned(msg.@(get_rs_name(member.name)))).into_owned(),
+        @(get_rs_name(member.name)): @(get_idiomatic_rs_type(member.type))::into_rmw_message(alloc::borrow::Cow::Owned(msg.@(get_rs_name(member.name)))).into_owned(),
 @#
 @#
 @#    == Bounded and basic types ==
@@ -169,7 +267,7 @@ impl rosidl_runtime_rs::Message for @(type_name) {
 @[    end if]@
 @[end for]@
       }),
-      std::borrow::Cow::Borrowed(msg) => std::borrow::Cow::Owned(Self::RmwMsg {
+      alloc::borrow::Cow::Borrowed(msg) => alloc::borrow::Cow::Owned(Self::RmwMsg {
 @[for member in msg_spec.structure.members]@
 @#
 @#
@@ -179,14 +277,14 @@ impl rosidl_runtime_rs::Message for @(type_name) {
         @(get_rs_name(member.name)): msg.@(get_rs_name(member.name))
           .iter()
           .map(|elem| elem.as_str().into())
-          .collect::<Vec<_>>()
+          .collect::<alloc::vec::Vec<_>>()
           .try_into()
           .unwrap(),
 @[        elif isinstance(member.type.value_type, NamedType) or isinstance(member.type.value_type, NamespacedType)]@
         @(get_rs_name(member.name)): msg.@(get_rs_name(member.name))
           .iter()
-          .map(|elem| @(get_idiomatic_rs_type(member.type.value_type))::into_rmw_message(std::borrow::Cow::Borrowed(elem)).into_owned())
-          .collect::<Vec<_>>()
+          .map(|elem| @(get_idiomatic_rs_type(member.type.value_type))::into_rmw_message(alloc::borrow::Cow::Borrowed(elem)).into_owned())
+          .collect::<alloc::vec::Vec<_>>()
           .try_into()
           .unwrap(),
 @[        elif isinstance(member.type.value_type, BasicType)]@
@@ -211,7 +309,7 @@ impl rosidl_runtime_rs::Message for @(type_name) {
 @[        elif isinstance(member.type.value_type, NamedType) or isinstance(member.type.value_type, NamespacedType)]@
         @(get_rs_name(member.name)): msg.@(get_rs_name(member.name))
           .iter()
-          .map(|elem| @(get_idiomatic_rs_type(member.type.value_type))::into_rmw_message(std::borrow::Cow::Borrowed(elem)).into_owned())
+          .map(|elem| @(get_idiomatic_rs_type(member.type.value_type))::into_rmw_message(alloc::borrow::Cow::Borrowed(elem)).into_owned())
           .collect(),
 @[        else]@
         @(get_rs_name(member.name)): msg.@(get_rs_name(member.name)).as_slice().into(),
@@ -220,7 +318,7 @@ impl rosidl_runtime_rs::Message for @(type_name) {
 @#
 @#    == NamedType + NamespacedType ==
 @[    elif isinstance(member.type, NamedType) or isinstance(member.type, NamespacedType)]@
-        @(get_rs_name(member.name)): @(get_idiomatic_rs_type(member.type))::into_rmw_message(std::borrow::Cow::Borrowed(&msg.@(get_rs_name(member.name)))).into_owned(),
+        @(get_rs_name(member.name)): @(get_idiomatic_rs_type(member.type))::into_rmw_message(alloc::borrow::Cow::Borrowed(&msg.@(get_rs_name(member.name)))).into_owned(),
 @#
 @#
 @#    == BasicType ==
@@ -284,6 +382,350 @@ impl rosidl_runtime_rs::Message for @(type_name) {
 @[end for]@
     }
   }
+
+@{
+borrowed_members = [member for member in msg_spec.structure.members if isinstance(member.type, UnboundedSequence) and isinstance(member.type.value_type, BasicType)]
+}@
+@[if borrowed_members]@
+@# Like the Cow::Borrowed case of into_rmw_message(), except that sequences of primitive types
+@# point into the vectors of this message instead of being copied, e.g. the data of an image.
+  fn with_rmw_message<R>(&self, f: impl FnOnce(&Self::RmwMsg) -> R) -> R {
+    // If f panics, the message is leaked, so that the borrowed sequences are never freed.
+    let mut msg = core::mem::ManuallyDrop::new(Self::RmwMsg {
+@[for member in msg_spec.structure.members]@
+@#
+@#
+@#    == Borrowed sequences ==
+@[    if member in borrowed_members]@
+      // SAFETY: The sequence is forgotten below, and the vector is borrowed until then.
+      @(get_rs_name(member.name)): unsafe { rosidl_runtime_rs::Sequence::from_borrowed_slice(&self.@(get_rs_name(member.name))) },
+@#
+@#
+@#    == Array ==
+@[    elif isinstance(member.type, Array)]@
+@[        if isinstance(member.type.value_type, UnboundedString) or isinstance(member.type.value_type, UnboundedWString)]@
+      @(get_rs_name(member.name)): self.@(get_rs_name(member.name))
+        .iter()
+        .map(|elem| elem.as_str().into())
+        .collect::<alloc::vec::Vec<_>>()
+        .try_into()
+        .unwrap(),
+@[        elif isinstance(member.type.value_type, NamedType) or isinstance(member.type.value_type, NamespacedType)]@
+      @(get_rs_name(member.name)): self.@(get_rs_name(member.name))
+        .iter()
+        .map(|elem| @(get_idiomatic_rs_type(member.type.value_type))::into_rmw_message(alloc::borrow::Cow::Borrowed(elem)).into_owned())
+        .collect::<alloc::vec::Vec<_>>()
+        .try_into()
+        .unwrap(),
+@[        elif isinstance(member.type.value_type, BasicType)]@
+      @(get_rs_name(member.name)): self.@(get_rs_name(member.name)),
+@[        else]@
+      @(get_rs_name(member.name)): self.@(get_rs_name(member.name)).clone(),
+@[        end if]@
+@#
+@#
+@#    == UnboundedString + UnboundedWString ==
+@[    elif isinstance(member.type, UnboundedString) or isinstance(member.type, UnboundedWString)]@
+      @(get_rs_name(member.name)): self.@(get_rs_name(member.name)).as_str().into(),
+@#
+@#
+@#    == UnboundedSequence ==
+@[    elif isinstance(member.type, UnboundedSequence)]@
+@[        if isinstance(member.type.value_type, UnboundedString) or isinstance(member.type.value_type, UnboundedWString)]@
+      @(get_rs_name(member.name)): self.@(get_rs_name(member.name))
+        .iter()
+        .map(|elem| elem.as_str().into())
+        .collect(),
+@[        elif isinstance(member.type.value_type, NamedType) or isinstance(member.type.value_type, NamespacedType)]@
+      @(get_rs_name(member.name)): self.@(get_rs_name(member.name))
+        .iter()
+        .map(|elem| @(get_idiomatic_rs_type(member.type.value_type))::into_rmw_message(alloc::borrow::Cow::Borrowed(elem)).into_owned())
+        .collect(),
+@[        else]@
+      @(get_rs_name(member.name)): self.@(get_rs_name(member.name)).as_slice().into(),
+@[        end if]@
+@#
+@#
+@#    == NamedType + NamespacedType ==
+@[    elif isinstance(member.type, NamedType) or isinstance(member.type, NamespacedType)]@
+      @(get_rs_name(member.name)): @(get_idiomatic_rs_type(member.type))::into_rmw_message(alloc::borrow::Cow::Borrowed(&self.@(get_rs_name(member.name)))).into_owned(),
+@#
+@#
+@#    == BasicType ==
+@[    elif isinstance(member.type, BasicType)]@
+      @(get_rs_name(member.name)): self.@(get_rs_name(member.name)),
+@#
+@#
+@#    == Bounded types ==
+@[    else]@
+      @(get_rs_name(member.name)): self.@(get_rs_name(member.name)).clone(),
+@[    end if]@
+@[end for]@
+    });
+    let result = f(&msg);
+@[for member in borrowed_members]@
+    core::mem::forget(core::mem::take(&mut msg.@(get_rs_name(member.name))));
+@[end for]@
+    // SAFETY: The message is not used anymore, and only owns the memory of its other fields.
+    unsafe { core::mem::ManuallyDrop::drop(&mut msg) };
+    result
+  }
+@[end if]@
 }
 
+@{
+header_prefix = get_header_prefix(msg_spec)
+}@
+@[if header_prefix is not None]@
+impl rosidl_runtime_rs::Stamped for @(type_name) {
+  fn stamp(&self) -> (i32, u32) {
+    (self.@(header_prefix)stamp.sec, self.@(header_prefix)stamp.nanosec)
+  }
+  fn set_stamp(&mut self, sec: i32, nanosec: u32) {
+    self.@(header_prefix)stamp.sec = sec;
+    self.@(header_prefix)stamp.nanosec = nanosec;
+  }
+  fn set_frame_id(&mut self, frame_id: &str) {
+    self.@(header_prefix)frame_id = frame_id.into();
+  }
+}
+
+@[end if]@
+impl rosidl_runtime_rs::FieldAccess for @(type_name) {
+  fn get_field(&self, path: &str) -> Result<rosidl_runtime_rs::FieldValue, rosidl_runtime_rs::FieldAccessError> {
+    let (name, rest) = rosidl_runtime_rs::split_field_path(path)?;
+    match name {
+@[for member in msg_spec.structure.members]@
+      "@(member.name)" => rosidl_runtime_rs::FieldAccess::get_field(&self.@(get_rs_name(member.name)), rest),
+@[end for]@
+      _ => Err(rosidl_runtime_rs::FieldAccessError::NoSuchField(name.into())),
+    }
+  }
+  fn set_field(&mut self, path: &str, value: rosidl_runtime_rs::FieldValue) -> Result<(), rosidl_runtime_rs::FieldAccessError> {
+    let (name, rest) = rosidl_runtime_rs::split_field_path(path)?;
+    match name {
+@[for member in msg_spec.structure.members]@
+      "@(member.name)" => rosidl_runtime_rs::FieldAccess::set_field(&mut self.@(get_rs_name(member.name)), rest, value),
+@[end for]@
+      _ => Err(rosidl_runtime_rs::FieldAccessError::NoSuchField(name.into())),
+    }
+  }
+}
+
+@[if package_name == 'geometry_msgs' and type_name == 'TransformStamped']@
+impl rosidl_runtime_rs::TransformMessage for @(type_name) {
+  fn frame_ids(&self) -> (&[u8], &[u8]) {
+    (self.header.frame_id.as_bytes(), self.child_frame_id.as_bytes())
+  }
+  fn translation(&self) -> [f64; 3] {
+    let translation = &self.transform.translation;
+    [translation.x, translation.y, translation.z]
+  }
+  fn rotation(&self) -> [f64; 4] {
+    let rotation = &self.transform.rotation;
+    [rotation.x, rotation.y, rotation.z, rotation.w]
+  }
+  fn set_child_frame_id(&mut self, child_frame_id: &str) {
+    self.child_frame_id = child_frame_id.into();
+  }
+  fn set_transform(&mut self, translation: [f64; 3], rotation: [f64; 4]) {
+    [self.transform.translation.x, self.transform.translation.y, self.transform.translation.z] = translation;
+    [self.transform.rotation.x, self.transform.rotation.y, self.transform.rotation.z, self.transform.rotation.w] = rotation;
+  }
+}
+
+@[end if]@
+@[if package_name == 'tf2_msgs' and type_name == 'TFMessage']@
+impl rosidl_runtime_rs::TfMessage for @(type_name) {
+  type Transform = geometry_msgs::msg::TransformStamped;
+  fn transforms(&self) -> &[Self::Transform] {
+    &self.transforms
+  }
+  fn set_transforms(&mut self, transforms: alloc::vec::Vec<Self::Transform>) {
+    self.transforms = transforms;
+  }
+}
+
+@[end if]@
 @[end for]
+@[if package_name == 'geometry_msgs']@
+@# #################################################
+@# ####### Conversions to nalgebra and glam #######
+@# #################################################
+@# The idiomatic and RMW-native geometry types have the same fields, so they get the same impls.
+@[for rs_module in ['', 'rmw::']]@
+
+#[cfg(feature = "nalgebra")]
+impl From<@(rs_module)Vector3> for nalgebra::Vector3<f64> {
+  fn from(v: @(rs_module)Vector3) -> Self {
+    nalgebra::Vector3::new(v.x, v.y, v.z)
+  }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<nalgebra::Vector3<f64>> for @(rs_module)Vector3 {
+  fn from(v: nalgebra::Vector3<f64>) -> Self {
+    Self { x: v.x, y: v.y, z: v.z }
+  }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<@(rs_module)Point> for nalgebra::Point3<f64> {
+  fn from(p: @(rs_module)Point) -> Self {
+    nalgebra::Point3::new(p.x, p.y, p.z)
+  }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<nalgebra::Point3<f64>> for @(rs_module)Point {
+  fn from(p: nalgebra::Point3<f64>) -> Self {
+    Self { x: p.x, y: p.y, z: p.z }
+  }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<@(rs_module)Quaternion> for nalgebra::Quaternion<f64> {
+  fn from(q: @(rs_module)Quaternion) -> Self {
+    nalgebra::Quaternion::new(q.w, q.x, q.y, q.z)
+  }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<nalgebra::Quaternion<f64>> for @(rs_module)Quaternion {
+  fn from(q: nalgebra::Quaternion<f64>) -> Self {
+    Self { x: q.i, y: q.j, z: q.k, w: q.w }
+  }
+}
+
+@# The quaternion of a message is not guaranteed to be normalized, so it is normalized here.
+#[cfg(feature = "nalgebra")]
+impl From<@(rs_module)Quaternion> for nalgebra::UnitQuaternion<f64> {
+  fn from(q: @(rs_module)Quaternion) -> Self {
+    nalgebra::UnitQuaternion::from_quaternion(q.into())
+  }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<nalgebra::UnitQuaternion<f64>> for @(rs_module)Quaternion {
+  fn from(q: nalgebra::UnitQuaternion<f64>) -> Self {
+    q.into_inner().into()
+  }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<@(rs_module)Pose> for nalgebra::Isometry3<f64> {
+  fn from(pose: @(rs_module)Pose) -> Self {
+    let p = pose.position;
+    nalgebra::Isometry3::from_parts(nalgebra::Translation3::new(p.x, p.y, p.z), pose.orientation.into())
+  }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<nalgebra::Isometry3<f64>> for @(rs_module)Pose {
+  fn from(isometry: nalgebra::Isometry3<f64>) -> Self {
+    Self {
+      position: nalgebra::Point3::from(isometry.translation.vector).into(),
+      orientation: isometry.rotation.into(),
+    }
+  }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<@(rs_module)Transform> for nalgebra::Isometry3<f64> {
+  fn from(transform: @(rs_module)Transform) -> Self {
+    let t = transform.translation;
+    nalgebra::Isometry3::from_parts(nalgebra::Translation3::new(t.x, t.y, t.z), transform.rotation.into())
+  }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<nalgebra::Isometry3<f64>> for @(rs_module)Transform {
+  fn from(isometry: nalgebra::Isometry3<f64>) -> Self {
+    Self {
+      translation: isometry.translation.vector.into(),
+      rotation: isometry.rotation.into(),
+    }
+  }
+}
+
+#[cfg(feature = "glam")]
+impl From<@(rs_module)Vector3> for glam::DVec3 {
+  fn from(v: @(rs_module)Vector3) -> Self {
+    glam::DVec3::new(v.x, v.y, v.z)
+  }
+}
+
+#[cfg(feature = "glam")]
+impl From<glam::DVec3> for @(rs_module)Vector3 {
+  fn from(v: glam::DVec3) -> Self {
+    Self { x: v.x, y: v.y, z: v.z }
+  }
+}
+
+#[cfg(feature = "glam")]
+impl From<@(rs_module)Point> for glam::DVec3 {
+  fn from(p: @(rs_module)Point) -> Self {
+    glam::DVec3::new(p.x, p.y, p.z)
+  }
+}
+
+#[cfg(feature = "glam")]
+impl From<glam::DVec3> for @(rs_module)Point {
+  fn from(p: glam::DVec3) -> Self {
+    Self { x: p.x, y: p.y, z: p.z }
+  }
+}
+
+#[cfg(feature = "glam")]
+impl From<@(rs_module)Quaternion> for glam::DQuat {
+  fn from(q: @(rs_module)Quaternion) -> Self {
+    glam::DQuat::from_xyzw(q.x, q.y, q.z, q.w)
+  }
+}
+
+#[cfg(feature = "glam")]
+impl From<glam::DQuat> for @(rs_module)Quaternion {
+  fn from(q: glam::DQuat) -> Self {
+    Self { x: q.x, y: q.y, z: q.z, w: q.w }
+  }
+}
+
+#[cfg(feature = "glam")]
+impl From<@(rs_module)Pose> for glam::DAffine3 {
+  fn from(pose: @(rs_module)Pose) -> Self {
+    glam::DAffine3::from_rotation_translation(pose.orientation.into(), pose.position.into())
+  }
+}
+
+@# An affine transform may contain a scale, which is dropped, since poses are rigid.
+#[cfg(feature = "glam")]
+impl From<glam::DAffine3> for @(rs_module)Pose {
+  fn from(affine: glam::DAffine3) -> Self {
+    let (_scale, rotation, translation) = affine.to_scale_rotation_translation();
+    Self {
+      position: translation.into(),
+      orientation: rotation.into(),
+    }
+  }
+}
+
+#[cfg(feature = "glam")]
+impl From<@(rs_module)Transform> for glam::DAffine3 {
+  fn from(transform: @(rs_module)Transform) -> Self {
+    glam::DAffine3::from_rotation_translation(transform.rotation.into(), transform.translation.into())
+  }
+}
+
+@# An affine transform may contain a scale, which is dropped, since transforms are rigid.
+#[cfg(feature = "glam")]
+impl From<glam::DAffine3> for @(rs_module)Transform {
+  fn from(affine: glam::DAffine3) -> Self {
+    let (_scale, rotation, translation) = affine.to_scale_rotation_translation();
+    Self {
+      translation: translation.into(),
+      rotation: rotation.into(),
+    }
+  }
+}
+@[end for]@
+@[end if]@
@@ -105,6 +105,8 @@ def generate_rs(generator_arguments_file, typesupport_impls):
         'get_rmw_rs_type': make_get_rmw_rs_type(args['package_name']),
         'get_rs_name': get_rs_name,
         'get_idiomatic_rs_type': make_get_idiomatic_rs_type(args['package_name']),
+        'get_header_prefix': make_get_header_prefix(args['package_name']),
+        'get_has_fixed_size': make_get_has_fixed_size(args['package_name']),
         'constant_value_to_rs': constant_value_to_rs,
         'value_to_rs': value_to_rs,
         'convert_camel_case_to_lower_case_underscore':
@@ -172,6 +174,46 @@ def generate_rs(generator_arguments_file, typesupport_impls):
 
     return 0
 
+def make_get_header_prefix(package_name):
+    def get_header_prefix(msg_spec):
+        """Return the path to the std_msgs/Header fields of a message, or None.
+
+        This is '' for the header itself, and 'header.' for messages with a header field.
+        """
+        type_ = msg_spec.structure.namespaced_type
+        if package_name == 'std_msgs' and type_.name == 'Header':
+            return ''
+        for member in msg_spec.structure.members:
+            if member.name == 'header' and isinstance(member.type, NamespacedType) \
+                    and member.type.namespaces == ['std_msgs', 'msg'] \
+                    and member.type.name == 'Header':
+                return 'header.'
+        return None
+    return get_header_prefix
+
+
+def make_get_has_fixed_size(package_name):
+    get_rmw_rs_type = make_get_rmw_rs_type(package_name)
+    def get_has_fixed_size(msg_spec):
+        """Return a Rust expression for whether a message has no strings or sequences.
+
+        Like rosidl_generator_traits::has_fixed_size in C++, nested messages are checked through
+        their own RmwMessage::HAS_FIXED_SIZE constant.
+        """
+        terms = []
+        for member in msg_spec.structure.members:
+            type_ = member.type.value_type if isinstance(member.type, Array) else member.type
+            if isinstance(type_, BasicType):
+                continue
+            elif isinstance(type_, NamespacedType):
+                terms.append('<{} as rosidl_runtime_rs::RmwMessage>::HAS_FIXED_SIZE'.format(
+                    get_rmw_rs_type(type_)))
+            else:
+                return 'false'
+        return ' && '.join(terms) or 'true'
+    return get_has_fixed_size
+
+
 def get_rs_name(name):
     keywords = [
         # strict keywords
@@ -291,9 +333,9 @@ def make_get_idiomatic_rs_type(package_name):
     get_rmw_rs_type = make_get_rmw_rs_type(package_name)
     def get_idiomatic_rs_type(type_):
         if isinstance(type_, UnboundedString) or isinstance(type_, UnboundedWString):
-            return 'std::string::String'
+            return 'alloc::string::String'
         elif isinstance(type_, UnboundedSequence):
-            return 'Vec::<{}>'.format(get_idiomatic_rs_type(type_.value_type))
+            return 'alloc::vec::Vec::<{}>'.format(get_idiomatic_rs_type(type_.value_type))
         elif isinstance(type_, NamespacedType):
             return '::'.join(type_.namespaced_name()).replace(package_name, 'crate')
         elif isinstance(type_, Array):
@@ -1,5 +1,4 @@
 use std::env;
-use std::path::Path;
 
 const AMENT_PREFIX_PATH: &str = "AMENT_PREFIX_PATH";
 
@@ -16,8 +15,9 @@ fn get_env_var_or_abort(env_var: &'static str) -> String {
 
 fn main() {
     let ament_prefix_path_list = get_env_var_or_abort(AMENT_PREFIX_PATH);
-    for ament_prefix_path in ament_prefix_path_list.split(':') {
-        let library_path = Path::new(ament_prefix_path).join("lib");
+    // The paths are separated by ':' on Unix and by ';' on Windows, where ':' is part of the paths.
+    for ament_prefix_path in env::split_paths(&ament_prefix_path_list) {
+        let library_path = ament_prefix_path.join("lib");
         println!("cargo:rustc-link-search=native={}", library_path.display());
     }
 }
@@ -0,0 +1,475 @@
+use alloc::string::{String as StdString, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+
+use crate::{BoundedSequence, BoundedString, BoundedWString, Sequence, SequenceAlloc};
+use crate::{String, WString};
+
+/// The value of a primitive or string field, read or written through [`FieldAccess`].
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum FieldValue {
+    /// A `bool` field.
+    Bool(bool),
+    /// An `int8` field.
+    I8(i8),
+    /// A `uint8`, `byte` or `char` field.
+    U8(u8),
+    /// An `int16` field.
+    I16(i16),
+    /// A `uint16` field.
+    U16(u16),
+    /// An `int32` field.
+    I32(i32),
+    /// A `uint32` field.
+    U32(u32),
+    /// An `int64` field.
+    I64(i64),
+    /// A `uint64` field.
+    U64(u64),
+    /// A `float32` field.
+    F32(f32),
+    /// A `float64` field.
+    F64(f64),
+    /// A `string` or `wstring` field, bounded or unbounded.
+    String(StdString),
+}
+
+impl FieldValue {
+    /// Returns the value as a floating-point number, e.g. for plotting it.
+    ///
+    /// Booleans are converted to 0 and 1. Returns `None` for strings.
+    pub fn as_f64(&self) -> Option<f64> {
+        Some(match *self {
+            FieldValue::Bool(b) => b as u8 as f64,
+            FieldValue::I8(v) => v as f64,
+            FieldValue::U8(v) => v as f64,
+            FieldValue::I16(v) => v as f64,
+            FieldValue::U16(v) => v as f64,
+            FieldValue::I32(v) => v as f64,
+            FieldValue::U32(v) => v as f64,
+            FieldValue::I64(v) => v as f64,
+            FieldValue::U64(v) => v as f64,
+            FieldValue::F32(v) => v as f64,
+            FieldValue::F64(v) => v,
+            FieldValue::String(_) => return None,
+        })
+    }
+
+    // Returns the value of an integer variant, widened to i128 so that all variants fit.
+    fn as_i128(&self) -> Option<i128> {
+        Some(match *self {
+            FieldValue::I8(v) => v as i128,
+            FieldValue::U8(v) => v as i128,
+            FieldValue::I16(v) => v as i128,
+            FieldValue::U16(v) => v as i128,
+            FieldValue::I32(v) => v as i128,
+            FieldValue::U32(v) => v as i128,
+            FieldValue::I64(v) => v as i128,
+            FieldValue::U64(v) => v as i128,
+            _ => return None,
+        })
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            FieldValue::Bool(_) => "bool",
+            FieldValue::I8(_) => "int8",
+            FieldValue::U8(_) => "uint8",
+            FieldValue::I16(_) => "int16",
+            FieldValue::U16(_) => "uint16",
+            FieldValue::I32(_) => "int32",
+            FieldValue::U32(_) => "uint32",
+            FieldValue::I64(_) => "int64",
+            FieldValue::U64(_) => "uint64",
+            FieldValue::F32(_) => "float32",
+            FieldValue::F64(_) => "float64",
+            FieldValue::String(_) => "string",
+        }
+    }
+}
+
+/// Error type for [`FieldAccess::get_field()`] and [`FieldAccess::set_field()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FieldAccessError {
+    /// The message has no member with this name.
+    NoSuchField(StdString),
+    /// The path is malformed, or does not end at a primitive or string field, e.g. because it
+    /// ends at a nested message or a sequence.
+    InvalidPath(StdString),
+    /// The index is out of bounds of the array or sequence, which has the given length.
+    IndexOutOfBounds {
+        /// The index in the path.
+        index: usize,
+        /// The length of the array or sequence.
+        len: usize,
+    },
+    /// The value cannot be stored in the field without loss, e.g. a `float64` in an `int32`
+    /// field, or a negative number in a `uint8` field.
+    TypeMismatch {
+        /// The ROS type of the field.
+        field_type: &'static str,
+        /// The ROS type of the value.
+        value_type: &'static str,
+    },
+    /// The string exceeds the upper bound of the field.
+    ExceedsBounds,
+}
+
+impl Display for FieldAccessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            FieldAccessError::NoSuchField(name) => write!(f, "No field named '{name}'"),
+            FieldAccessError::InvalidPath(path) => write!(f, "Invalid field path '{path}'"),
+            FieldAccessError::IndexOutOfBounds { index, len } => {
+                write!(f, "Index {index} is out of bounds for length {len}")
+            }
+            FieldAccessError::TypeMismatch {
+                field_type,
+                value_type,
+            } => write!(
+                f,
+                "Cannot store a {value_type} value in a {field_type} field"
+            ),
+            FieldAccessError::ExceedsBounds => write!(f, "The value exceeds the field's bound"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FieldAccessError {}
+
+/// Trait for reading and writing fields of a message by their path, e.g. `pose.position.x`.
+///
+/// This is implemented by the generated message types, both idiomatic and RMW-native, and by
+/// the types of their fields. It enables generic tools, such as plotters, parameter-driven
+/// mappings or test assertions, to work with strongly-typed messages without converting them to
+/// dynamic messages.
+///
+/// A path consists of member names separated by `.`, where array and sequence elements are
+/// selected with `[index]`, e.g. `poses[2].position.x`. The member names are the names from the
+/// message definition, which may differ from the Rust field names, e.g. `type` instead of
+/// `type_`. Paths must end at a primitive or string field.
+///
+/// Sequences are never resized, so only existing elements can be set.
+///
+/// # Example
+/// ```ignore
+/// let mut pose = geometry_msgs::msg::PoseStamped::default();
+/// pose.set_field("pose.position.x", FieldValue::F64(1.5))?;
+/// assert_eq!(pose.get_field("pose.position.x")?, FieldValue::F64(1.5));
+/// ```
+pub trait FieldAccess {
+    /// Returns the value of the field at the given path.
+    fn get_field(&self, path: &str) -> Result<FieldValue, FieldAccessError>;
+
+    /// Sets the field at the given path.
+    ///
+    /// Integers are converted to other integer types if the value is in range, and to
+    /// floating-point types. Any other conversion is a [`FieldAccessError::TypeMismatch`].
+    fn set_field(&mut self, path: &str, value: FieldValue) -> Result<(), FieldAccessError>;
+}
+
+/// Splits the first member name off a field path.
+///
+/// Returns the member name and the rest of the path, which is empty or starts with `[`, or is
+/// the path of a member of the nested message. This is used by the generated [`FieldAccess`]
+/// impls.
+pub fn split_field_path(path: &str) -> Result<(&str, &str), FieldAccessError> {
+    let end = path.find(['.', '[']).unwrap_or(path.len());
+    let (name, rest) = path.split_at(end);
+    if name.is_empty() {
+        return Err(FieldAccessError::InvalidPath(path.to_string()));
+    }
+    match rest.strip_prefix('.') {
+        Some("") => Err(FieldAccessError::InvalidPath(path.to_string())),
+        Some(member_path) => Ok((name, member_path)),
+        None => Ok((name, rest)),
+    }
+}
+
+// Splits the index off the path of an array or sequence element, e.g. `[2].x`.
+fn split_index(path: &str, len: usize) -> Result<(usize, &str), FieldAccessError> {
+    let invalid_path = || FieldAccessError::InvalidPath(path.to_string());
+    let (index, rest) = path
+        .strip_prefix('[')
+        .and_then(|path| path.split_once(']'))
+        .ok_or_else(invalid_path)?;
+    let index: usize = index.parse().map_err(|_| invalid_path())?;
+    if index >= len {
+        return Err(FieldAccessError::IndexOutOfBounds { index, len });
+    }
+    let rest = match rest.strip_prefix('.') {
+        Some("") => return Err(invalid_path()),
+        Some(member_path) => member_path,
+        None => rest,
+    };
+    Ok((index, rest))
+}
+
+fn leaf_path(path: &str) -> Result<(), FieldAccessError> {
+    if path.is_empty() {
+        Ok(())
+    } else {
+        Err(FieldAccessError::InvalidPath(path.to_string()))
+    }
+}
+
+fn type_mismatch(field_type: &'static str, value: &FieldValue) -> FieldAccessError {
+    FieldAccessError::TypeMismatch {
+        field_type,
+        value_type: value.type_name(),
+    }
+}
+
+impl FieldAccess for bool {
+    fn get_field(&self, path: &str) -> Result<FieldValue, FieldAccessError> {
+        leaf_path(path)?;
+        Ok(FieldValue::Bool(*self))
+    }
+
+    fn set_field(&mut self, path: &str, value: FieldValue) -> Result<(), FieldAccessError> {
+        leaf_path(path)?;
+        match value {
+            FieldValue::Bool(b) => *self = b,
+            _ => return Err(type_mismatch("bool", &value)),
+        }
+        Ok(())
+    }
+}
+
+macro_rules! impl_field_access_for_integer {
+    ($($type:ty => $variant:ident, $ros_type:literal);* $(;)?) => {
+        $(
+            impl FieldAccess for $type {
+                fn get_field(&self, path: &str) -> Result<FieldValue, FieldAccessError> {
+                    leaf_path(path)?;
+                    Ok(FieldValue::$variant(*self))
+                }
+
+                fn set_field(
+                    &mut self,
+                    path: &str,
+                    value: FieldValue,
+                ) -> Result<(), FieldAccessError> {
+                    leaf_path(path)?;
+                    *self = value
+                        .as_i128()
+                        .and_then(|v| v.try_into().ok())
+                        .ok_or_else(|| type_mismatch($ros_type, &value))?;
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_field_access_for_integer!(
+    i8 => I8, "int8";
+    u8 => U8, "uint8";
+    i16 => I16, "int16";
+    u16 => U16, "uint16";
+    i32 => I32, "int32";
+    u32 => U32, "uint32";
+    i64 => I64, "int64";
+    u64 => U64, "uint64";
+);
+
+macro_rules! impl_field_access_for_float {
+    ($($type:ty => $variant:ident, $ros_type:literal);* $(;)?) => {
+        $(
+            impl FieldAccess for $type {
+                fn get_field(&self, path: &str) -> Result<FieldValue, FieldAccessError> {
+                    leaf_path(path)?;
+                    Ok(FieldValue::$variant(*self))
+                }
+
+                fn set_field(
+                    &mut self,
+                    path: &str,
+                    value: FieldValue,
+                ) -> Result<(), FieldAccessError> {
+                    leaf_path(path)?;
+                    *self = match value {
+                        FieldValue::F32(v) => v as $type,
+                        FieldValue::F64(v) => v as $type,
+                        _ => value
+                            .as_i128()
+                            .ok_or_else(|| type_mismatch($ros_type, &value))?
+                            as $type,
+                    };
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_field_access_for_float!(
+    f32 => F32, "float32";
+    f64 => F64, "float64";
+);
+
+macro_rules! impl_field_access_for_string {
+    ($($type:ty),* $(,)?) => {
+        $(
+            impl FieldAccess for $type {
+                fn get_field(&self, path: &str) -> Result<FieldValue, FieldAccessError> {
+                    leaf_path(path)?;
+                    Ok(FieldValue::String(self.to_string()))
+                }
+
+                fn set_field(
+                    &mut self,
+                    path: &str,
+                    value: FieldValue,
+                ) -> Result<(), FieldAccessError> {
+                    leaf_path(path)?;
+                    match value {
+                        FieldValue::String(s) => *self = s.as_str().into(),
+                        _ => return Err(type_mismatch("string", &value)),
+                    }
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_field_access_for_string!(StdString, String, WString);
+
+macro_rules! impl_field_access_for_bounded_string {
+    ($($type:ident),* $(,)?) => {
+        $(
+            impl<const N: usize> FieldAccess for $type<N> {
+                fn get_field(&self, path: &str) -> Result<FieldValue, FieldAccessError> {
+                    leaf_path(path)?;
+                    Ok(FieldValue::String(self.to_string()))
+                }
+
+                fn set_field(
+                    &mut self,
+                    path: &str,
+                    value: FieldValue,
+                ) -> Result<(), FieldAccessError> {
+                    leaf_path(path)?;
+                    match value {
+                        FieldValue::String(s) => {
+                            *self = s
+                                .as_str()
+                                .try_into()
+                                .map_err(|_| FieldAccessError::ExceedsBounds)?
+                        }
+                        _ => return Err(type_mismatch("string", &value)),
+                    }
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_field_access_for_bounded_string!(BoundedString, BoundedWString);
+
+// Arrays and sequences of all kinds are accessed through their slices.
+fn get_element<T: FieldAccess>(elements: &[T], path: &str) -> Result<FieldValue, FieldAccessError> {
+    let (index, rest) = split_index(path, elements.len())?;
+    elements[index].get_field(rest)
+}
+
+fn set_element<T: FieldAccess>(
+    elements: &mut [T],
+    path: &str,
+    value: FieldValue,
+) -> Result<(), FieldAccessError> {
+    let (index, rest) = split_index(path, elements.len())?;
+    elements[index].set_field(rest, value)
+}
+
+impl<T: FieldAccess, const N: usize> FieldAccess for [T; N] {
+    fn get_field(&self, path: &str) -> Result<FieldValue, FieldAccessError> {
+        get_element(self, path)
+    }
+
+    fn set_field(&mut self, path: &str, value: FieldValue) -> Result<(), FieldAccessError> {
+        set_element(self, path, value)
+    }
+}
+
+impl<T: FieldAccess> FieldAccess for Vec<T> {
+    fn get_field(&self, path: &str) -> Result<FieldValue, FieldAccessError> {
+        get_element(self, path)
+    }
+
+    fn set_field(&mut self, path: &str, value: FieldValue) -> Result<(), FieldAccessError> {
+        set_element(self, path, value)
+    }
+}
+
+impl<T: FieldAccess + SequenceAlloc> FieldAccess for Sequence<T> {
+    fn get_field(&self, path: &str) -> Result<FieldValue, FieldAccessError> {
+        get_element(self, path)
+    }
+
+    fn set_field(&mut self, path: &str, value: FieldValue) -> Result<(), FieldAccessError> {
+        set_element(self, path, value)
+    }
+}
+
+impl<T: FieldAccess + SequenceAlloc, const N: usize> FieldAccess for BoundedSequence<T, N> {
+    fn get_field(&self, path: &str) -> Result<FieldValue, FieldAccessError> {
+        get_element(self, path)
+    }
+
+    fn set_field(&mut self, path: &str, value: FieldValue) -> Result<(), FieldAccessError> {
+        set_element(self, path, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_field_path() {
+        assert_eq!(split_field_path("x"), Ok(("x", "")));
+        assert_eq!(
+            split_field_path("pose.position.x"),
+            Ok(("pose", "position.x"))
+        );
+        assert_eq!(split_field_path("poses[2].x"), Ok(("poses", "[2].x")));
+        assert!(split_field_path("").is_err());
+        assert!(split_field_path(".x").is_err());
+        assert!(split_field_path("pose.").is_err());
+    }
+
+    #[test]
+    fn test_elements() {
+        let mut xs = [[1i32, 2], [3, 4]];
+        assert_eq!(xs.get_field("[1][0]"), Ok(FieldValue::I32(3)));
+        assert_eq!(
+            xs.get_field("[2][0]"),
+            Err(FieldAccessError::IndexOutOfBounds { index: 2, len: 2 })
+        );
+        assert!(xs.get_field("[1]").is_err());
+        assert!(xs.get_field("[x][0]").is_err());
+        xs.set_field("[0][1]", FieldValue::U8(7)).unwrap();
+        assert_eq!(xs, [[1, 7], [3, 4]]);
+    }
+
+    #[test]
+    fn test_conversions() {
+        let mut x = 0u8;
+        assert!(x.set_field("", FieldValue::I64(255)).is_ok());
+        assert_eq!(x, 255);
+        assert!(x.set_field("", FieldValue::I64(256)).is_err());
+        assert!(x.set_field("", FieldValue::I64(-1)).is_err());
+        assert!(x.set_field("", FieldValue::F64(1.0)).is_err());
+        let mut y = 0f32;
+        assert!(y.set_field("", FieldValue::I64(3)).is_ok());
+        assert_eq!(y, 3.0);
+        assert!(y.set_field("", FieldValue::Bool(true)).is_err());
+        let mut s = StdString::new();
+        assert!(s.set_field("", FieldValue::String("abc".into())).is_ok());
+        assert_eq!(s.get_field(""), Ok(FieldValue::String("abc".into())));
+    }
+}
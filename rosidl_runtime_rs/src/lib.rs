@@ -1,12 +1,24 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs)]
 //! Bindings to `rosidl_runtime_c` and related functionality for messages.
+//!
+//! The `std` feature is enabled by default. Disabling it makes this crate `no_std`, with only a
+//! dependency on `alloc`.
+
+extern crate alloc;
 
 #[macro_use]
 mod sequence;
 pub use sequence::{BoundedSequence, Sequence, SequenceExceedsBoundsError};
 
+mod field_access;
+pub use field_access::{split_field_path, FieldAccess, FieldAccessError, FieldValue};
+
 mod string;
 pub use string::{BoundedString, BoundedWString, String, StringExceedsBoundsError, WString};
 
 mod traits;
-pub use traits::{Message, RmwMessage, SequenceAlloc};
+pub use traits::{
+    Message, MessageTypeSupport, RmwMessage, SequenceAlloc, Service, Stamped, TfMessage,
+    TransformMessage,
+};
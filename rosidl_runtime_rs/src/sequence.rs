@@ -1,8 +1,9 @@
-use std::cmp::Ordering;
-use std::fmt::{self, Debug, Display};
-use std::hash::{Hash, Hasher};
-use std::iter::{Extend, FromIterator, FusedIterator};
-use std::ops::{Deref, DerefMut};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt::{self, Debug, Display};
+use core::hash::{Hash, Hasher};
+use core::iter::{Extend, FromIterator, FusedIterator};
+use core::ops::{Deref, DerefMut};
 
 #[cfg(feature = "serde")]
 mod serde;
@@ -107,7 +108,7 @@ impl<T: Debug + SequenceAlloc> Debug for Sequence<T> {
 impl<T: SequenceAlloc> Default for Sequence<T> {
     fn default() -> Self {
         Self {
-            data: std::ptr::null_mut(),
+            data: core::ptr::null_mut(),
             size: 0,
             capacity: 0,
         }
@@ -145,7 +146,7 @@ impl<T: SequenceAlloc> Extend<T> for Sequence<T> {
         let mut cur_idx = self.size;
         // Convenience closure for resizing self
         let resize = |seq: &mut Self, new_size: usize| {
-            let old_seq = std::mem::replace(seq, Sequence::new(new_size));
+            let old_seq = core::mem::replace(seq, Sequence::new(new_size));
             for (i, elem) in old_seq.into_iter().enumerate().take(new_size) {
                 seq[i] = elem;
             }
@@ -254,7 +255,7 @@ where
     pub fn as_slice(&self) -> &[T] {
         // SAFETY: self.data points to self.size consecutive, initialized elements and
         // isn't modified externally.
-        unsafe { std::slice::from_raw_parts(self.data, self.size) }
+        unsafe { core::slice::from_raw_parts(self.data, self.size) }
     }
 
     /// Extracts a mutable slice containing the entire sequence.
@@ -263,14 +264,31 @@ where
     pub fn as_mut_slice(&mut self) -> &mut [T] {
         // SAFETY: self.data points to self.size consecutive, initialized elements and
         // isn't modified externally.
-        unsafe { std::slice::from_raw_parts_mut(self.data, self.size) }
+        unsafe { core::slice::from_raw_parts_mut(self.data, self.size) }
+    }
+
+    /// Creates a sequence that points to the elements of a slice, without copying them.
+    ///
+    /// This allows passing the contents of a `Vec` to the RMW layer without copying them, see
+    /// [`Message::with_rmw_message()`](crate::Message::with_rmw_message).
+    ///
+    /// # Safety
+    /// The sequence does not own its elements, so it must not be dropped. Instead, it must be
+    /// passed to [`core::mem::forget()`] before the slice is modified or dropped, e.g. after
+    /// replacing it with an empty sequence. The sequence must not be modified.
+    pub unsafe fn from_borrowed_slice(slice: &[T]) -> Self {
+        Self {
+            data: slice.as_ptr() as *mut T,
+            size: slice.len(),
+            capacity: slice.len(),
+        }
     }
 }
 
 impl<T: Default + SequenceAlloc> Sequence<T> {
     /// Internal function for the sequence_copy impl. To be removed when rosidl#650 is backported and released.
     pub fn resize_to_at_least(&mut self, len: usize) {
-        let allocation_size = std::mem::size_of::<Self>() * len;
+        let allocation_size = core::mem::size_of::<Self>() * len;
         if self.capacity < len {
             // SAFETY: The memory in self.data is owned by C.
             let data = unsafe { libc::realloc(self.data as *mut _, allocation_size) } as *mut T;
@@ -303,7 +321,7 @@ impl<T: SequenceAlloc, const N: usize> Default for BoundedSequence<T, N> {
     fn default() -> Self {
         Self {
             inner: Sequence {
-                data: std::ptr::null_mut(),
+                data: core::ptr::null_mut(),
                 size: 0,
                 capacity: 0,
             },
@@ -386,10 +404,10 @@ impl<T: SequenceAlloc, const N: usize> IntoIterator for BoundedSequence<T, N> {
     type Item = T;
     type IntoIter = SequenceIterator<T>;
     fn into_iter(mut self) -> Self::IntoIter {
-        let seq = std::mem::replace(
+        let seq = core::mem::replace(
             &mut self.inner,
             Sequence {
-                data: std::ptr::null_mut(),
+                data: core::ptr::null_mut(),
                 size: 0,
                 capacity: 0,
             },
@@ -472,7 +490,7 @@ impl<T: SequenceAlloc> Iterator for SequenceIterator<T> {
             let ptr = self.seq.data.add(self.idx);
             let elem = ptr.read();
             // Need to make sure that dropping the sequence later will not fini() the elements
-            ptr.write(std::mem::zeroed::<T>());
+            ptr.write(core::mem::zeroed::<T>());
             elem
         };
         self.idx += 1;
@@ -505,6 +523,7 @@ impl Display for SequenceExceedsBoundsError {
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for SequenceExceedsBoundsError {}
 
 macro_rules! impl_sequence_alloc_for_primitive_type {
@@ -522,7 +541,7 @@ macro_rules! impl_sequence_alloc_for_primitive_type {
                     // This allocates space and sets seq.size and seq.capacity to size
                     let ret = $init_func(seq as *mut _, size);
                     // Zero memory, since it will be uninitialized if there is no default value
-                    std::ptr::write_bytes(seq.data, 0u8, size);
+                    core::ptr::write_bytes(seq.data, 0u8, size);
                     ret
                 }
             }
@@ -531,7 +550,7 @@ macro_rules! impl_sequence_alloc_for_primitive_type {
                 unsafe { $fini_func(seq as *mut _) }
             }
             fn sequence_copy(in_seq: &Sequence<Self>, out_seq: &mut Sequence<Self>) -> bool {
-                let allocation_size = std::mem::size_of::<Self>() * in_seq.size;
+                let allocation_size = core::mem::size_of::<Self>() * in_seq.size;
                 if out_seq.capacity < in_seq.size {
                     // SAFETY: The memory in out_seq.data is owned by C.
                     let data = unsafe { libc::realloc(out_seq.data as *mut _, allocation_size) };
@@ -1,3 +1,4 @@
+use alloc::vec::Vec;
 use serde::{de::Error, ser::SerializeSeq, Deserialize, Deserializer, Serialize, Serializer};
 
 use super::{BoundedSequence, Sequence};
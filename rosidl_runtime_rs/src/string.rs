@@ -1,8 +1,10 @@
-use std::cmp::Ordering;
-use std::ffi::CStr;
-use std::fmt::{self, Debug, Display};
-use std::hash::{Hash, Hasher};
-use std::ops::{Deref, DerefMut};
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::ffi::CStr;
+use core::fmt::{self, Debug, Display};
+use core::hash::{Hash, Hasher};
+use core::ops::{Deref, DerefMut};
 
 #[cfg(feature = "serde")]
 mod serde;
@@ -123,7 +125,7 @@ macro_rules! string_impl {
         impl Default for $string {
             fn default() -> Self {
                 let mut msg = Self {
-                    data: std::ptr::null_mut(),
+                    data: core::ptr::null_mut(),
                     size: 0,
                     capacity: 0,
                 };
@@ -159,7 +161,7 @@ macro_rules! string_impl {
             fn deref(&self) -> &Self::Target {
                 // SAFETY: self.data points to self.size consecutive, initialized elements and
                 // isn't modified externally.
-                unsafe { std::slice::from_raw_parts(self.data as *const $char_type, self.size) }
+                unsafe { core::slice::from_raw_parts(self.data as *const $char_type, self.size) }
             }
         }
 
@@ -167,13 +169,13 @@ macro_rules! string_impl {
             fn deref_mut(&mut self) -> &mut Self::Target {
                 // SAFETY: self.data points to self.size consecutive, initialized elements and
                 // isn't modified externally.
-                unsafe { std::slice::from_raw_parts_mut(self.data as *mut $char_type, self.size) }
+                unsafe { core::slice::from_raw_parts_mut(self.data as *mut $char_type, self.size) }
             }
         }
 
         impl Display for $string {
             fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-                let converted = std::string::String::$string_conversion_func(self.deref());
+                let converted = alloc::string::String::$string_conversion_func(self.deref());
                 Display::fmt(&converted, f)
             }
         }
@@ -257,7 +259,7 @@ string_impl!(
 impl From<&str> for String {
     fn from(s: &str) -> Self {
         let mut msg = Self {
-            data: std::ptr::null_mut(),
+            data: core::ptr::null_mut(),
             size: 0,
             capacity: 0,
         };
@@ -287,7 +289,7 @@ impl String {
 impl From<&str> for WString {
     fn from(s: &str) -> Self {
         let mut msg = Self {
-            data: std::ptr::null_mut(),
+            data: core::ptr::null_mut(),
             size: 0,
             capacity: 0,
         };
@@ -349,8 +351,8 @@ impl<const N: usize> SequenceAlloc for BoundedString<N> {
         // SAFETY: Transmute of a transparent type to the inner type is fine
         unsafe {
             <String as SequenceAlloc>::sequence_copy(
-                std::mem::transmute::<&Sequence<Self>, &Sequence<String>>(in_seq),
-                std::mem::transmute::<&mut Sequence<Self>, &mut Sequence<String>>(out_seq),
+                core::mem::transmute::<&Sequence<Self>, &Sequence<String>>(in_seq),
+                core::mem::transmute::<&mut Sequence<Self>, &mut Sequence<String>>(out_seq),
             )
         }
     }
@@ -415,8 +417,8 @@ impl<const N: usize> SequenceAlloc for BoundedWString<N> {
         // SAFETY: Transmute of a transparent type to the inner type is fine
         unsafe {
             <WString as SequenceAlloc>::sequence_copy(
-                std::mem::transmute::<&Sequence<Self>, &Sequence<WString>>(in_seq),
-                std::mem::transmute::<&mut Sequence<Self>, &mut Sequence<WString>>(out_seq),
+                core::mem::transmute::<&Sequence<Self>, &Sequence<WString>>(in_seq),
+                core::mem::transmute::<&mut Sequence<Self>, &mut Sequence<WString>>(out_seq),
             )
         }
     }
@@ -451,6 +453,7 @@ impl Display for StringExceedsBoundsError {
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for StringExceedsBoundsError {}
 
 #[cfg(test)]
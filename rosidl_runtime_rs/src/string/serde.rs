@@ -1,5 +1,5 @@
+use core::ops::Deref;
 use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
-use std::ops::Deref;
 
 use super::{BoundedString, BoundedWString, String, WString};
 
@@ -8,7 +8,7 @@ impl<'de> Deserialize<'de> for String {
     where
         D: Deserializer<'de>,
     {
-        std::string::String::deserialize(deserializer).map(|s| Self::from(s.as_str()))
+        alloc::string::String::deserialize(deserializer).map(|s| Self::from(s.as_str()))
     }
 }
 
@@ -18,7 +18,7 @@ impl Serialize for String {
         S: Serializer,
     {
         // Not particularly efficient
-        let s = std::string::String::from_utf8_lossy(self.deref());
+        let s = alloc::string::String::from_utf8_lossy(self.deref());
         serializer.serialize_str(&s)
     }
 }
@@ -28,7 +28,7 @@ impl<'de> Deserialize<'de> for WString {
     where
         D: Deserializer<'de>,
     {
-        std::string::String::deserialize(deserializer).map(|s| Self::from(s.as_str()))
+        alloc::string::String::deserialize(deserializer).map(|s| Self::from(s.as_str()))
     }
 }
 
@@ -38,7 +38,7 @@ impl Serialize for WString {
         S: Serializer,
     {
         // Not particularly efficient
-        let s = std::string::String::from_utf16_lossy(self.deref());
+        let s = alloc::string::String::from_utf16_lossy(self.deref());
         serializer.serialize_str(&s)
     }
 }
@@ -48,7 +48,7 @@ impl<'de, const N: usize> Deserialize<'de> for BoundedString<N> {
     where
         D: Deserializer<'de>,
     {
-        std::string::String::deserialize(deserializer)
+        alloc::string::String::deserialize(deserializer)
             .and_then(|s| Self::try_from(s.as_str()).map_err(D::Error::custom))
     }
 }
@@ -67,7 +67,7 @@ impl<'de, const N: usize> Deserialize<'de> for BoundedWString<N> {
     where
         D: Deserializer<'de>,
     {
-        std::string::String::deserialize(deserializer)
+        alloc::string::String::deserialize(deserializer)
             .and_then(|s| Self::try_from(s.as_str()).map_err(D::Error::custom))
     }
 }
@@ -15,8 +15,9 @@
 // DISTRIBUTION A. Approved for public release; distribution unlimited.
 // OPSEC #4584.
 //
-use std::borrow::Cow;
-use std::fmt::Debug;
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::fmt::Debug;
 
 /// Internal trait that connects a particular `Sequence<T>` instance to generated C functions
 /// that allocate and deallocate memory.
@@ -38,10 +39,39 @@ pub trait SequenceAlloc: Sized {
 ///
 /// User code never needs to call this trait's method, much less implement this trait.
 pub trait RmwMessage: Clone + Debug + Default {
+    /// The name of the message type, e.g. `std_msgs/msg/String`, as it appears in the ROS graph.
+    const TYPE_NAME: &'static str;
+
+    /// Whether the message has a fixed size, i.e. it contains no strings or sequences, also not
+    /// in nested messages, like `rosidl_generator_traits::has_fixed_size` in C++.
+    ///
+    /// Only such messages can be loaned from the middleware, since the contents of strings and
+    /// sequences, including bounded ones, are allocated outside of the message.
+    const HAS_FIXED_SIZE: bool = false;
+
     /// Get a pointer to the correct `rosidl_message_type_support_t` structure.
     fn get_type_support() -> libc::uintptr_t;
 }
 
+/// The type supports of a message type, for looking it up by name at runtime.
+///
+/// With their `type_registry` feature, the message crates generated by `rosidl_generator_rs`
+/// provide a `MESSAGE_TYPE_SUPPORTS` array with one entry for each of their message types. It can
+/// be registered with `rclrs::register_message_types()`, so that `rclrs` finds the types of
+/// generic publishers and subscriptions without loading the type support libraries at runtime.
+///
+/// User code never needs to create this struct.
+#[derive(Clone, Copy, Debug)]
+pub struct MessageTypeSupport {
+    /// The name of the message type, e.g. `std_msgs/msg/String`.
+    pub type_name: &'static str,
+    /// Gets a pointer to the `rosidl_message_type_support_t` structure of `rosidl_typesupport_c`.
+    pub type_support: fn() -> libc::uintptr_t,
+    /// Gets a pointer to the `rosidl_message_type_support_t` structure of
+    /// `rosidl_typesupport_introspection_c`.
+    pub introspection_type_support: fn() -> libc::uintptr_t,
+}
+
 /// Trait for types that can be used in a `rclrs::Subscription` and a `rclrs::Publisher`.
 ///
 /// `rosidl_generator_rs` generates two types of messages that implement this trait:
@@ -142,4 +172,84 @@ pub trait Message: Clone + Debug + Default + 'static {
 
     /// Converts the RMW-native message into an idiomatic message.
     fn from_rmw_message(msg: Self::RmwMsg) -> Self;
+
+    /// Calls `f` with the RMW-native version of the message.
+    ///
+    /// This is used for publishing, where the RMW-native message is only needed during the call.
+    /// By default, the message is converted with [`Message::into_rmw_message()`].
+    ///
+    /// The generated idiomatic messages avoid copying their sequences of primitive types, such as
+    /// the `data` of a `sensor_msgs/Image`, by pointing the RMW-native sequences into the vectors
+    /// of the message.
+    fn with_rmw_message<R>(&self, f: impl FnOnce(&Self::RmwMsg) -> R) -> R {
+        f(&Self::into_rmw_message(Cow::Borrowed(self)))
+    }
+}
+
+/// Trait for services.
+///
+/// User code never needs to call this trait's method, much less implement this trait.
+pub trait Service: 'static {
+    /// The request message associated with this service.
+    type Request: Message;
+
+    /// The response message associated with this service.
+    type Response: Message;
+
+    /// Get a pointer to the correct `rosidl_service_type_support_t` structure.
+    fn get_type_support() -> libc::uintptr_t;
+}
+
+/// Trait for messages with a `std_msgs/Header`, and for the header itself.
+///
+/// This is implemented by the generated message types, for any message with a field `header` of
+/// type `std_msgs/Header`. It allows stamping messages without knowing their type, e.g. with
+/// `rclrs::Node::stamp_now`.
+pub trait Stamped {
+    /// Returns the `sec` and `nanosec` fields of the header's stamp.
+    fn stamp(&self) -> (i32, u32);
+
+    /// Sets the `sec` and `nanosec` fields of the header's stamp.
+    fn set_stamp(&mut self, sec: i32, nanosec: u32);
+
+    /// Sets the `frame_id` field of the header.
+    fn set_frame_id(&mut self, frame_id: &str);
+}
+
+/// Trait for `geometry_msgs/TransformStamped` messages, for buffering transforms without
+/// depending on `geometry_msgs`.
+///
+/// This is implemented by the generated `geometry_msgs/TransformStamped` types. The stamp and the
+/// parent frame are in the header, which is accessed through the [`Stamped`] supertrait.
+pub trait TransformMessage: Stamped {
+    /// Returns the `header.frame_id` and `child_frame_id` fields, i.e. the parent and the child
+    /// frame of the transform.
+    fn frame_ids(&self) -> (&[u8], &[u8]);
+
+    /// Returns the `transform.translation` field as `[x, y, z]`.
+    fn translation(&self) -> [f64; 3];
+
+    /// Returns the `transform.rotation` field as a quaternion `[x, y, z, w]`.
+    fn rotation(&self) -> [f64; 4];
+
+    /// Sets the `child_frame_id` field.
+    fn set_child_frame_id(&mut self, child_frame_id: &str);
+
+    /// Sets the `transform.translation` field from `[x, y, z]` and the `transform.rotation` field
+    /// from a quaternion `[x, y, z, w]`.
+    fn set_transform(&mut self, translation: [f64; 3], rotation: [f64; 4]);
+}
+
+/// Trait for `tf2_msgs/TFMessage` messages, i.e. the messages on `/tf` and `/tf_static`.
+///
+/// This is implemented by the generated `tf2_msgs/TFMessage` types.
+pub trait TfMessage {
+    /// The `geometry_msgs/TransformStamped` type of the same kind, idiomatic or RMW-native.
+    type Transform: TransformMessage + Clone + Default;
+
+    /// Returns the `transforms` field.
+    fn transforms(&self) -> &[Self::Transform];
+
+    /// Sets the `transforms` field.
+    fn set_transforms(&mut self, transforms: Vec<Self::Transform>);
 }